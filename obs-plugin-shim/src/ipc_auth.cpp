@@ -0,0 +1,176 @@
+#include "ipc_auth.h"
+
+#ifdef _WIN32
+#ifndef WIN32_LEAN_AND_MEAN
+#define WIN32_LEAN_AND_MEAN
+#endif
+#ifndef NOMINMAX
+#define NOMINMAX
+#endif
+#include <windows.h>
+#include <bcrypt.h>
+#include <wincrypt.h>
+#endif
+
+#include <cstdint>
+#include <cstdlib>
+#include <fstream>
+#include <iterator>
+#include <sstream>
+#include <vector>
+
+namespace {
+constexpr const char* kEnvSecret = "AEGIS_IPC_SECRET";
+constexpr const char* kEnvVaultPath = "AEGIS_IPC_VAULT_PATH";
+constexpr const char* kEnvSecretKey = "AEGIS_IPC_SECRET_KEY";
+constexpr const char* kDefaultSecretKey = "ipc_shared_secret";
+
+std::string EnvOrEmpty(const char* name) {
+    const char* value = std::getenv(name);
+    return value ? std::string(value) : std::string();
+}
+
+std::string DefaultVaultPath() {
+    const std::string appdata = EnvOrEmpty("APPDATA");
+    return (appdata.empty() ? std::string(".") : appdata) + "\\Telemy\\vault.json";
+}
+
+// vault.json is `{"entries": {"<key>": "<base64>"}}`; keys and base64 values never need escaping,
+// so a quoted-key scan is enough and avoids pulling a JSON library into the shim.
+bool TryFindVaultEntry(const std::string& json, const std::string& key, std::string& out_value) {
+    const std::string needle = "\"" + key + "\"";
+    std::size_t pos = json.find(needle);
+    if (pos == std::string::npos) {
+        return false;
+    }
+    pos += needle.size();
+    while (pos < json.size() && (json[pos] == ' ' || json[pos] == '\t' || json[pos] == '\r' || json[pos] == '\n')) {
+        ++pos;
+    }
+    if (pos >= json.size() || json[pos] != ':') {
+        return false;
+    }
+    pos = json.find('"', pos);
+    if (pos == std::string::npos) {
+        return false;
+    }
+    const std::size_t end = json.find('"', pos + 1);
+    if (end == std::string::npos) {
+        return false;
+    }
+    out_value = json.substr(pos + 1, end - pos - 1);
+    return !out_value.empty();
+}
+
+#ifdef _WIN32
+bool DecodeAndUnprotect(const std::string& encoded, std::string& out_plain) {
+    DWORD len = 0;
+    if (!CryptStringToBinaryA(encoded.c_str(), static_cast<DWORD>(encoded.size()), CRYPT_STRING_BASE64,
+                              nullptr, &len, nullptr, nullptr)) {
+        return false;
+    }
+    std::vector<BYTE> sealed(len);
+    if (!CryptStringToBinaryA(encoded.c_str(), static_cast<DWORD>(encoded.size()), CRYPT_STRING_BASE64,
+                              sealed.data(), &len, nullptr, nullptr)) {
+        return false;
+    }
+
+    DATA_BLOB in_blob{len, sealed.data()};
+    DATA_BLOB out_blob{};
+    if (!CryptUnprotectData(&in_blob, nullptr, nullptr, nullptr, nullptr, CRYPTPROTECT_UI_FORBIDDEN, &out_blob)) {
+        return false;
+    }
+    out_plain.assign(reinterpret_cast<const char*>(out_blob.pbData), out_blob.cbData);
+    SecureZeroMemory(out_blob.pbData, out_blob.cbData);
+    LocalFree(out_blob.pbData);
+    return true;
+}
+#endif
+} // namespace
+
+namespace aegis {
+
+bool LoadIpcSharedSecret(std::string& out_secret, std::string& out_error) {
+    out_secret = EnvOrEmpty(kEnvSecret);
+    if (!out_secret.empty()) {
+        return true;
+    }
+
+    std::string path = EnvOrEmpty(kEnvVaultPath);
+    if (path.empty()) {
+        path = DefaultVaultPath();
+    }
+    std::string key = EnvOrEmpty(kEnvSecretKey);
+    if (key.empty()) {
+        key = kDefaultSecretKey;
+    }
+
+    std::ifstream in(path, std::ios::binary);
+    if (!in) {
+        out_error = "cannot read vault " + path;
+        return false;
+    }
+    const std::string json((std::istreambuf_iterator<char>(in)), std::istreambuf_iterator<char>());
+    if (json.find("\"encryption\"") != std::string::npos) {
+        out_error = "vault uses the encrypted backend; set AEGIS_IPC_SECRET";
+        return false;
+    }
+    std::string encoded;
+    if (!TryFindVaultEntry(json, key, encoded)) {
+        out_error = "vault has no entry " + key;
+        return false;
+    }
+#ifdef _WIN32
+    if (!DecodeAndUnprotect(encoded, out_secret)) {
+        std::ostringstream oss;
+        oss << "cannot unprotect vault entry " << key << " err=" << GetLastError();
+        out_error = oss.str();
+        return false;
+    }
+    return true;
+#else
+    out_error = "vault entries can only be unprotected on Windows; set AEGIS_IPC_SECRET";
+    return false;
+#endif
+}
+
+bool ComputeIpcAuthMac(const std::string& secret, const std::string& nonce, std::string& out_mac_hex) {
+#ifdef _WIN32
+    BCRYPT_ALG_HANDLE alg = nullptr;
+    if (!BCRYPT_SUCCESS(BCryptOpenAlgorithmProvider(&alg, BCRYPT_SHA256_ALGORITHM, nullptr,
+                                                    BCRYPT_ALG_HANDLE_HMAC_FLAG))) {
+        return false;
+    }
+    BCRYPT_HASH_HANDLE hash = nullptr;
+    UCHAR mac[32] = {};
+    bool ok = BCRYPT_SUCCESS(BCryptCreateHash(alg, &hash, nullptr, 0,
+                                              reinterpret_cast<PUCHAR>(const_cast<char*>(secret.data())),
+                                              static_cast<ULONG>(secret.size()), 0));
+    ok = ok && BCRYPT_SUCCESS(BCryptHashData(hash, reinterpret_cast<PUCHAR>(const_cast<char*>(nonce.data())),
+                                             static_cast<ULONG>(nonce.size()), 0));
+    ok = ok && BCRYPT_SUCCESS(BCryptFinishHash(hash, mac, sizeof(mac), 0));
+    if (hash) {
+        BCryptDestroyHash(hash);
+    }
+    BCryptCloseAlgorithmProvider(alg, 0);
+    if (!ok) {
+        return false;
+    }
+
+    static const char kHex[] = "0123456789abcdef";
+    out_mac_hex.clear();
+    out_mac_hex.reserve(sizeof(mac) * 2);
+    for (UCHAR b : mac) {
+        out_mac_hex.push_back(kHex[b >> 4]);
+        out_mac_hex.push_back(kHex[b & 0x0f]);
+    }
+    return true;
+#else
+    (void)secret;
+    (void)nonce;
+    out_mac_hex.clear();
+    return false;
+#endif
+}
+
+} // namespace aegis
@@ -0,0 +1,19 @@
+#pragma once
+
+#include <string>
+
+namespace aegis {
+
+// Shared-secret handling for the core's `auth_challenge` (docs/IPC_PROTOCOL_v1.md, "Authentication").
+//
+// The secret comes from AEGIS_IPC_SECRET when set (harness/dev), otherwise from the core vault
+// entry: %APPDATA%\Telemy\vault.json (override with AEGIS_IPC_VAULT_PATH), key
+// `ipc_shared_secret` (override with AEGIS_IPC_SECRET_KEY, e.g. for a profile-prefixed key).
+// Only the `os` vault backend can be read here; its entries are DPAPI-protected for the user
+// OBS runs as.
+bool LoadIpcSharedSecret(std::string& out_secret, std::string& out_error);
+
+// Lowercase hex HMAC-SHA256(secret, nonce), as expected in `auth_response.mac`.
+bool ComputeIpcAuthMac(const std::string& secret, const std::string& nonce, std::string& out_mac_hex);
+
+} // namespace aegis
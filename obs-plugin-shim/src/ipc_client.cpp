@@ -1,5 +1,7 @@
 #include "ipc_client.h"
 
+#include "ipc_auth.h"
+
 #ifdef _WIN32
 #ifndef WIN32_LEAN_AND_MEAN
 #define WIN32_LEAN_AND_MEAN
@@ -23,6 +25,9 @@ constexpr const char* kCmdPipe = R"(\\.\pipe\aegis_cmd_v1)";
 constexpr const char* kEvtPipe = R"(\\.\pipe\aegis_evt_v1)";
 constexpr int kReadPollMs = 250;
 constexpr int kHeartbeatMs = 1000;
+// A core without `hello_ack` (e.g. the harness mock core) gets request_status after this long.
+// A core that sent `auth_challenge` is always waited on.
+constexpr int kHelloAckGraceMs = 1000;
 constexpr std::uint32_t kMaxFrameSize = 64 * 1024;
 
 std::uint64_t NowUnixMs() {
@@ -418,6 +423,7 @@ struct ParsedEnvelopeMeta {
     std::string request_id;
     std::string scene_name;
     std::string reason;
+    std::string nonce;
 };
 
 std::string JsonEscape(const std::string& input) {
@@ -526,7 +532,7 @@ bool MpEnvelopeTypeAndSwitchSceneMeta(
         }
     }
 
-    if (out_meta.type != "switch_scene" || payload_bytes.empty()) {
+    if ((out_meta.type != "switch_scene" && out_meta.type != "auth_challenge") || payload_bytes.empty()) {
         return !out_meta.type.empty();
     }
 
@@ -548,6 +554,10 @@ bool MpEnvelopeTypeAndSwitchSceneMeta(
             std::string reason;
             if (MpReadString(pr, reason)) out_meta.reason = reason;
             else return true;
+        } else if (key == "nonce") {
+            std::string nonce;
+            if (MpReadString(pr, nonce)) out_meta.nonce = nonce;
+            else return true;
         } else {
             if (!MpSkip(pr)) return true;
         }
@@ -603,7 +613,7 @@ std::vector<std::uint8_t> BuildEnvelopeRequestStatus() {
     return out;
 }
 
-std::vector<std::uint8_t> BuildEnvelopePing() {
+std::vector<std::uint8_t> BuildEnvelopePing(std::int64_t rtt_ms) {
     std::vector<std::uint8_t> out;
     MpWriteMapHeader(out, 6);
     MpWriteString(out, "v");
@@ -617,9 +627,33 @@ std::vector<std::uint8_t> BuildEnvelopePing() {
     MpWriteString(out, "priority");
     MpWriteString(out, "normal");
     MpWriteString(out, "payload");
-    MpWriteMapHeader(out, 1);
+    MpWriteMapHeader(out, rtt_ms >= 0 ? 2 : 1);
     MpWriteString(out, "nonce");
     MpWriteString(out, NewId());
+    if (rtt_ms >= 0) {
+        MpWriteString(out, "rtt_ms");
+        MpWriteUInt(out, static_cast<std::uint64_t>(rtt_ms));
+    }
+    return out;
+}
+
+std::vector<std::uint8_t> BuildEnvelopeAuthResponse(const std::string& mac_hex) {
+    std::vector<std::uint8_t> out;
+    MpWriteMapHeader(out, 6);
+    MpWriteString(out, "v");
+    MpWriteUInt(out, 1);
+    MpWriteString(out, "id");
+    MpWriteString(out, NewId());
+    MpWriteString(out, "ts_unix_ms");
+    MpWriteUInt(out, NowUnixMs());
+    MpWriteString(out, "type");
+    MpWriteString(out, "auth_response");
+    MpWriteString(out, "priority");
+    MpWriteString(out, "high");
+    MpWriteString(out, "payload");
+    MpWriteMapHeader(out, 1);
+    MpWriteString(out, "mac");
+    MpWriteString(out, mac_hex);
     return out;
 }
 
@@ -869,6 +903,10 @@ void IpcClient::WorkerLoop() {
 void IpcClient::ConnectedSessionLoop() {
     handshake_sent_ = false;
     request_status_sent_ = false;
+    hello_acked_ = false;
+    auth_challenged_ = false;
+    awaiting_pong_ = false;
+    last_rtt_ms_ = -1;
 
     Log("ipc connected (session loop)");
 
@@ -886,8 +924,9 @@ void IpcClient::ConnectedSessionLoop() {
                 break;
             } else {
                 handshake_sent_ = true;
+                hello_sent_at_ = std::chrono::steady_clock::now();
             }
-        } else if (!request_status_sent_) {
+        } else if (!request_status_sent_ && HandshakeSettled()) {
             if (!SendRequestStatus()) {
                 Log("request_status send failed; ending session for reconnect");
                 break;
@@ -898,10 +937,14 @@ void IpcClient::ConnectedSessionLoop() {
             pending_request_status_.store(false);
         }
 
-        DrainPendingSetModeRequests();
-        DrainPendingSetSettingRequests();
-        DrainPendingSceneSwitchResults();
-        DrainPendingShutdownNotices();
+        // Until the core accepts the hello (and the auth_response, if it challenged us) it rejects
+        // anything but hello/auth_response/ping, so queued commands wait for request_status.
+        if (request_status_sent_) {
+            DrainPendingSetModeRequests();
+            DrainPendingSetSettingRequests();
+            DrainPendingSceneSwitchResults();
+            DrainPendingShutdownNotices();
+        }
 
         if (handshake_sent_ && request_status_sent_ && pending_request_status_.exchange(false)) {
             if (!SendRequestStatus()) {
@@ -1129,7 +1172,12 @@ bool IpcClient::SendRequestStatus() {
 }
 
 bool IpcClient::SendPing() {
-    return WriteFrame(BuildEnvelopePing());
+    if (!WriteFrame(BuildEnvelopePing(last_rtt_ms_))) {
+        return false;
+    }
+    last_ping_sent_at_ = std::chrono::steady_clock::now();
+    awaiting_pong_ = true;
+    return true;
 }
 
 bool IpcClient::SendSetModeRequest(const std::string& mode) {
@@ -1287,6 +1335,33 @@ void IpcClient::DrainPendingShutdownNotices() {
     }
 }
 
+bool IpcClient::HandshakeSettled() const {
+    if (hello_acked_) {
+        return true;
+    }
+    if (auth_challenged_) {
+        return false;
+    }
+    return std::chrono::duration_cast<std::chrono::milliseconds>(
+               std::chrono::steady_clock::now() - hello_sent_at_)
+               .count() >= kHelloAckGraceMs;
+}
+
+bool IpcClient::SendAuthResponse(const std::string& nonce) {
+    std::string secret;
+    std::string error;
+    if (!LoadIpcSharedSecret(secret, error)) {
+        Log("auth_challenge: " + error);
+        return false;
+    }
+    std::string mac;
+    if (!ComputeIpcAuthMac(secret, nonce, mac)) {
+        Log("auth_challenge: HMAC-SHA256 failed");
+        return false;
+    }
+    return WriteFrame(BuildEnvelopeAuthResponse(mac));
+}
+
 bool IpcClient::HandleIncomingFrame(const std::vector<std::uint8_t>& payload) {
     ParsedEnvelopeMeta meta;
     if (!MpEnvelopeTypeAndSwitchSceneMeta(payload, meta)) {
@@ -1309,6 +1384,27 @@ bool IpcClient::HandleIncomingFrame(const std::vector<std::uint8_t>& payload) {
         }
     }
 
+    if (meta.type == "hello_ack") {
+        hello_acked_ = true;
+    }
+
+    if (meta.type == "auth_challenge") {
+        auth_challenged_ = true;
+        if (meta.nonce.empty() || !SendAuthResponse(meta.nonce)) {
+            Log("failed to answer auth_challenge");
+            return false;
+        }
+        Log("sent auth_response");
+    }
+
+    if (meta.type == "pong" && awaiting_pong_) {
+        // One ping is in flight at a time, so the next pong answers it.
+        last_rtt_ms_ = std::chrono::duration_cast<std::chrono::milliseconds>(
+                           std::chrono::steady_clock::now() - last_ping_sent_at_)
+                           .count();
+        awaiting_pong_ = false;
+    }
+
     if (meta.type == "switch_scene") {
         if (callbacks_.on_switch_scene_request) {
             callbacks_.on_switch_scene_request(meta.request_id, meta.scene_name, meta.reason);
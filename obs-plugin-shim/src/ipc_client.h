@@ -1,6 +1,7 @@
 #pragma once
 
 #include <atomic>
+#include <chrono>
 #include <cstdint>
 #include <functional>
 #include <mutex>
@@ -72,6 +73,8 @@ private:
     bool WriteFrame(const std::vector<std::uint8_t>& payload);
 
     bool SendHello();
+    bool SendAuthResponse(const std::string& nonce);
+    bool HandshakeSettled() const;
     bool SendRequestStatus();
     bool SendPing();
     bool SendSetModeRequest(const std::string& mode);
@@ -105,6 +108,14 @@ private:
 
     bool handshake_sent_ = false;
     bool request_status_sent_ = false;
+    // Set by the core's hello_ack / auth_challenge; request_status waits on them.
+    bool hello_acked_ = false;
+    bool auth_challenged_ = false;
+    std::chrono::steady_clock::time_point hello_sent_at_{};
+    // Heartbeat RTT: measured from our ping to the core's pong, reported in the next ping.
+    std::chrono::steady_clock::time_point last_ping_sent_at_{};
+    bool awaiting_pong_ = false;
+    std::int64_t last_rtt_ms_ = -1;
 
     void* cmd_pipe_ = nullptr; // HANDLE
     void* evt_pipe_ = nullptr; // HANDLE
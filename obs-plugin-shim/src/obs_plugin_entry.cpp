@@ -59,6 +59,17 @@ std::mutex g_pending_set_mode_actions_mu;
 std::vector<PendingSetModeAction> g_pending_set_mode_actions;
 std::mutex g_pending_set_setting_actions_mu;
 std::vector<PendingSetSettingAction> g_pending_set_setting_actions;
+struct PendingRelayConfig {
+    std::string session_id;
+    bool active = false;
+    std::string srt_host;
+    int srt_port = 0;
+    std::string pair_token;
+};
+std::mutex g_pending_relay_config_mu;
+// Latest relay_config from core; only the newest one matters.
+bool g_has_pending_relay_config = false;
+PendingRelayConfig g_pending_relay_config;
 constexpr std::chrono::milliseconds kDockActionCompletionTimeoutMs(3000);
 constexpr std::chrono::milliseconds kDockActionDuplicateWindowMs(1500);
 std::uint64_t g_local_dock_action_seq = 0;
@@ -1449,7 +1460,42 @@ bool EmitDockSceneSnapshotPayload(const std::string& payload_json) {
     return delivered;
 }
 
+bool TryParseRelayConfig(const std::string& envelope_json, PendingRelayConfig* out) {
+    const QJsonDocument doc = QJsonDocument::fromJson(QByteArray::fromStdString(envelope_json));
+    if (!out || !doc.isObject()) {
+        return false;
+    }
+    const QJsonObject payload = doc.object().value(QStringLiteral("payload")).toObject();
+    *out = PendingRelayConfig{};
+    out->session_id = payload.value(QStringLiteral("session_id")).toString().toStdString();
+    out->active = payload.value(QStringLiteral("active")).toBool();
+    out->srt_host = payload.value(QStringLiteral("srt_host")).toString().toStdString();
+    out->srt_port = payload.value(QStringLiteral("srt_port")).toInt();
+    out->pair_token = payload.value(QStringLiteral("pair_token")).toString().toStdString();
+    if (out->active && (out->srt_host.empty() || out->srt_port <= 0 || out->pair_token.empty())) {
+        return false;
+    }
+    return true;
+}
+
+void EnqueueRelayConfig(const PendingRelayConfig& config) {
+    std::lock_guard<std::mutex> lock(g_pending_relay_config_mu);
+    g_pending_relay_config = config;
+    g_has_pending_relay_config = true;
+}
+
 void EmitDockIpcEnvelopeJson(const std::string& envelope_json) {
+    if (TryExtractEnvelopeTypeFromJson(envelope_json) == "relay_config") {
+        // Applied to the stream output on the OBS thread; the pair token is an
+        // ingest credential, so it is not forwarded to the dock page or logs.
+        PendingRelayConfig config;
+        if (TryParseRelayConfig(envelope_json, &config)) {
+            EnqueueRelayConfig(config);
+        } else {
+            blog(LOG_WARNING, "[aegis-obs-shim] relay_config ignored (missing endpoint fields)");
+        }
+        return;
+    }
     const std::string themed_envelope_json = MaybeAugmentStatusSnapshotEnvelopeWithObsTheme(envelope_json);
     const std::string envelope_type = TryExtractEnvelopeTypeFromJson(themed_envelope_json);
     CacheDockIpcEnvelopeForReplay(themed_envelope_json);
@@ -1709,6 +1755,64 @@ void DrainSwitchSceneRequestsOnObsThread() {
     }
 }
 
+// Points the stream output at the relay's SRT ingest, switching to a custom
+// service if needed. Takes effect the next time streaming starts.
+void ApplyRelayConfigOnObsThread(const PendingRelayConfig& config) {
+    if (!config.active) {
+        blog(
+            LOG_INFO,
+            "[aegis-obs-shim] relay_config withdrawn: session_id=%s (stream settings left as-is)",
+            config.session_id.c_str());
+        return;
+    }
+
+    std::ostringstream server;
+    server << "srt://" << config.srt_host << ":" << config.srt_port
+           << "?streamid=" << config.pair_token;
+    obs_data_t* settings = obs_data_create();
+    obs_data_set_string(settings, "server", server.str().c_str());
+    obs_data_set_string(settings, "key", "");
+
+    obs_service_t* current = obs_frontend_get_streaming_service();
+    const char* current_type = current ? obs_service_get_type(current) : nullptr;
+    if (current && current_type && std::string(current_type) == "rtmp_custom") {
+        obs_service_update(current, settings);
+    } else {
+        obs_service_t* custom =
+            obs_service_create("rtmp_custom", "aegis_relay_service", settings, nullptr);
+        if (!custom) {
+            obs_data_release(settings);
+            blog(LOG_WARNING, "[aegis-obs-shim] relay_config: failed to create custom stream service");
+            return;
+        }
+        obs_frontend_set_streaming_service(custom);
+        obs_service_release(custom);
+    }
+    obs_data_release(settings);
+    obs_frontend_save_streaming_service();
+
+    blog(
+        LOG_INFO,
+        "[aegis-obs-shim] relay_config applied: session_id=%s host=%s port=%d streaming=%s",
+        config.session_id.c_str(),
+        config.srt_host.c_str(),
+        config.srt_port,
+        obs_frontend_streaming_active() ? "true (applies on restart)" : "false");
+}
+
+void DrainRelayConfigOnObsThread() {
+    PendingRelayConfig config;
+    {
+        std::lock_guard<std::mutex> lock(g_pending_relay_config_mu);
+        if (!g_has_pending_relay_config) {
+            return;
+        }
+        config = g_pending_relay_config;
+        g_has_pending_relay_config = false;
+    }
+    ApplyRelayConfigOnObsThread(config);
+}
+
 void SwitchScenePumpTick(void*, float seconds) {
     if (seconds > 0.0f) {
         g_switch_pump_accum_seconds += seconds;
@@ -1724,6 +1828,7 @@ void SwitchScenePumpTick(void*, float seconds) {
     }
     g_switch_pump_accum_seconds = 0.0f;
     DrainSwitchSceneRequestsOnObsThread();
+    DrainRelayConfigOnObsThread();
 }
 
 } // namespace
@@ -2068,6 +2173,10 @@ void obs_module_unload(void) {
         std::lock_guard<std::mutex> lock(g_pending_set_setting_actions_mu);
         g_pending_set_setting_actions.clear();
     }
+    {
+        std::lock_guard<std::mutex> lock(g_pending_relay_config_mu);
+        g_has_pending_relay_config = false;
+    }
     {
         std::lock_guard<std::mutex> lock(g_recent_dock_actions_mu);
         g_recent_dock_actions.clear();
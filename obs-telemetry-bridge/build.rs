@@ -4,6 +4,10 @@ fn main() {
         let mut res = winres::WindowsResource::new();
         res.set_icon("assets\\telemy.ico");
         res.set_icon_with_id("assets\\telemy.ico", "tray_default");
+        res.set_icon_with_id("assets\\tray_healthy.ico", "tray_healthy");
+        res.set_icon_with_id("assets\\tray_degraded.ico", "tray_degraded");
+        res.set_icon_with_id("assets\\tray_critical.ico", "tray_critical");
+        res.set_icon_with_id("assets\\tray_disconnected.ico", "tray_disconnected");
         let _ = res.compile();
     }
 }
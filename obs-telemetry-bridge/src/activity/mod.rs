@@ -0,0 +1,221 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+/// Entries kept in memory for `/api/v1/audit`.
+const CAPACITY: usize = 500;
+/// `activity.jsonl` moves to `activity.jsonl.1` at this size.
+const FILE_MAX_BYTES: u64 = 4 * 1024 * 1024;
+/// Enough of the file's end to refill [`CAPACITY`] entries on open.
+const TAIL_BYTES: u64 = 512 * 1024;
+
+static LOG: OnceLock<ActivityLog> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    SwitchScene,
+    RelayStart,
+    RelayStop,
+    BitrateChange,
+    ObsControl,
+    ToggleSource,
+    RunCommand,
+    Alert,
+}
+
+impl ActionKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::SwitchScene => "switch_scene",
+            Self::RelayStart => "relay_start",
+            Self::RelayStop => "relay_stop",
+            Self::BitrateChange => "bitrate_change",
+            Self::ObsControl => "obs_control",
+            Self::ToggleSource => "toggle_source",
+            Self::RunCommand => "run_command",
+            Self::Alert => "alert",
+        }
+    }
+}
+
+/// One action the bridge took or was asked to take, with its outcome.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Activity {
+    pub id: u64,
+    pub at_unix_ms: u64,
+    pub action: ActionKind,
+    /// Who acted: `failover`, `bitrate_fallback`, `auto`, `grace`,
+    /// `rule:<name>`, `script`, `tray`, `api`, `ipc`, `notify` or `shutdown`.
+    pub source: String,
+    /// Why, where the caller knows, e.g. the bitrate behind a failover.
+    pub reason: Option<String>,
+    pub ok: bool,
+    /// What happened, or the error.
+    pub detail: String,
+}
+
+/// Every automated and user-triggered action, kept in memory and appended as
+/// JSONL to `activity.jsonl` once [`open`] has run. The file is rotated at
+/// [`FILE_MAX_BYTES`] and only its tail is read back.
+pub struct ActivityLog {
+    entries: Mutex<VecDeque<Activity>>,
+    path: Mutex<Option<PathBuf>>,
+    next_id: AtomicU64,
+}
+
+/// The process-wide activity log.
+pub fn log() -> &'static ActivityLog {
+    LOG.get_or_init(ActivityLog::in_memory)
+}
+
+/// Loads earlier entries from `path` and persists new ones there.
+pub fn open(path: PathBuf) {
+    log().restore(path);
+}
+
+impl ActivityLog {
+    fn in_memory() -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::new()),
+            path: Mutex::new(None),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    fn restore(&self, path: PathBuf) {
+        if let Ok(lines) = crate::recorder::tail_lines(&path, TAIL_BYTES) {
+            let mut entries = self.entries.lock().unwrap();
+            let recorded = std::mem::take(&mut *entries);
+            for line in lines {
+                if let Ok(entry) = serde_json::from_str::<Activity>(&line) {
+                    self.next_id.fetch_max(entry.id + 1, Ordering::Relaxed);
+                    keep(&mut entries, entry);
+                }
+            }
+            // Anything recorded before the file was opened comes after it.
+            for mut entry in recorded {
+                entry.id = self.next_id.fetch_add(1, Ordering::Relaxed);
+                keep(&mut entries, entry);
+            }
+        }
+        *self.path.lock().unwrap() = Some(path);
+    }
+
+    pub fn record(
+        &self,
+        action: ActionKind,
+        source: &str,
+        reason: Option<String>,
+        outcome: Result<String, String>,
+    ) {
+        let entry = Activity {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            at_unix_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            action,
+            source: source.to_string(),
+            reason,
+            ok: outcome.is_ok(),
+            detail: outcome.unwrap_or_else(|err| err),
+        };
+
+        // Held across the write so concurrent records do not race the rotation.
+        if let Some(file) = &*self.path.lock().unwrap() {
+            if let Err(err) = append_line(file, &entry) {
+                tracing::warn!("activity log write failed: {}", err);
+            }
+        }
+        keep(&mut self.entries.lock().unwrap(), entry);
+    }
+
+    /// Entries after `since_unix_ms`, most recent first, optionally only the
+    /// named actions and sources.
+    pub fn query(
+        &self,
+        since_unix_ms: u64,
+        actions: &[String],
+        source: Option<&str>,
+        limit: usize,
+    ) -> Vec<Activity> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|e| e.at_unix_ms > since_unix_ms)
+            .filter(|e| actions.is_empty() || actions.iter().any(|a| a == e.action.as_str()))
+            .filter(|e| source.is_none_or(|s| e.source == s))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+fn keep(entries: &mut VecDeque<Activity>, entry: Activity) {
+    if entries.len() == CAPACITY {
+        entries.pop_front();
+    }
+    entries.push_back(entry);
+}
+
+fn append_line(path: &Path, entry: &Activity) -> std::io::Result<()> {
+    let line = serde_json::to_string(entry)?;
+    crate::recorder::append_rotating(path, &line, FILE_MAX_BYTES)
+}
+
+pub fn default_path() -> PathBuf {
+    let base = std::env::var("APPDATA").unwrap_or_else(|_| ".".to_string());
+    Path::new(&base).join("Telemy").join("activity.jsonl")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn persists_and_filters_actions() {
+        let path = std::env::temp_dir()
+            .join(format!("telemy-activity-{}", uuid::Uuid::new_v4()))
+            .join("activity.jsonl");
+        let log = ActivityLog::in_memory();
+        log.record(ActionKind::Alert, "notify", None, Ok("early".to_string()));
+        log.restore(path.clone());
+        log.record(
+            ActionKind::SwitchScene,
+            "failover",
+            Some("900 kbps".to_string()),
+            Ok("switched to 'Audio only'".to_string()),
+        );
+        log.record(
+            ActionKind::RelayStart,
+            "tray",
+            None,
+            Err("aegis is not configured".to_string()),
+        );
+
+        let reopened = ActivityLog::in_memory();
+        reopened.restore(path.clone());
+        let all = reopened.query(0, &[], None, 10);
+        assert_eq!(all.len(), 2, "only entries after open are persisted");
+        assert_eq!(all[0].action, ActionKind::RelayStart);
+        assert!(!all[0].ok);
+        assert_eq!(all[1].reason.as_deref(), Some("900 kbps"));
+        assert_eq!(all[1].id, 2);
+
+        let scenes = log.query(0, &["switch_scene".to_string()], None, 10);
+        assert_eq!(scenes.len(), 1);
+        assert!(log.query(0, &[], Some("tray"), 10)[0]
+            .detail
+            .contains("aegis"));
+        assert_eq!(log.query(0, &[], None, 1).len(), 1);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
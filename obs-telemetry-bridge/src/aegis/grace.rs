@@ -0,0 +1,194 @@
+use super::{RelaySession, RelayTimers};
+use std::time::{Duration, Instant};
+
+/// What a [`GraceCountdown::tick`] means for the caller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraceTick {
+    /// No session in grace.
+    Idle,
+    /// The session just entered grace.
+    Entered { remaining: Duration },
+    /// A periodic reminder is due.
+    Reminder { remaining: Duration },
+    /// The grace window has run out without the session recovering.
+    Expired,
+    /// Counting down; nothing to announce.
+    Counting,
+}
+
+/// Counts down a session's grace window locally between control-plane
+/// refreshes. Each tick rewrites `timers.grace_remaining_seconds` in the shared
+/// snapshot so the dashboard and IPC snapshots show time left without polling;
+/// a value that differs from the last one written came from the control plane
+/// and resets the deadline.
+#[derive(Debug, Default)]
+pub struct GraceCountdown {
+    session_id: Option<String>,
+    deadline: Option<Instant>,
+    written_secs: Option<u64>,
+    last_notice_at: Option<Instant>,
+    expired: bool,
+}
+
+impl GraceCountdown {
+    pub fn tick(
+        &mut self,
+        session: Option<&mut RelaySession>,
+        now: Instant,
+        remind_every: Duration,
+    ) -> GraceTick {
+        let Some(session) = session.filter(|s| s.status == "grace") else {
+            *self = Self::default();
+            return GraceTick::Idle;
+        };
+        let timers = session.timers.get_or_insert_with(RelayTimers::default);
+        let reported = timers
+            .grace_remaining_seconds
+            .or(timers.grace_window_seconds)
+            .unwrap_or(0);
+
+        let entered = self.session_id.as_deref() != Some(session.session_id.as_str());
+        if entered {
+            *self = Self {
+                session_id: Some(session.session_id.clone()),
+                ..Self::default()
+            };
+        }
+        if entered || self.written_secs != Some(reported) {
+            self.deadline = Some(now + Duration::from_secs(reported));
+        }
+        let remaining = self.deadline.map_or(Duration::ZERO, |deadline| {
+            deadline.saturating_duration_since(now)
+        });
+        let remaining_secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+        timers.grace_remaining_seconds = Some(remaining_secs);
+        self.written_secs = Some(remaining_secs);
+
+        if entered {
+            self.last_notice_at = Some(now);
+            return GraceTick::Entered { remaining };
+        }
+        if remaining.is_zero() {
+            if self.expired {
+                return GraceTick::Counting;
+            }
+            self.expired = true;
+            return GraceTick::Expired;
+        }
+        if self
+            .last_notice_at
+            .is_none_or(|at| now.duration_since(at) >= remind_every)
+        {
+            self.last_notice_at = Some(now);
+            return GraceTick::Reminder { remaining };
+        }
+        GraceTick::Counting
+    }
+}
+
+/// `9m 30s`-style time left, for notices.
+pub fn format_remaining(remaining: Duration) -> String {
+    let secs = remaining.as_secs();
+    match (secs / 60, secs % 60) {
+        (0, s) => format!("{s}s"),
+        (m, 0) => format!("{m}m"),
+        (m, s) => format!("{m}m {s}s"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grace_session(id: &str, remaining: u64) -> RelaySession {
+        RelaySession {
+            session_id: id.to_string(),
+            status: "grace".to_string(),
+            timers: Some(RelayTimers {
+                grace_remaining_seconds: Some(remaining),
+                ..RelayTimers::default()
+            }),
+            ..RelaySession::default()
+        }
+    }
+
+    fn remaining_of(session: &RelaySession) -> Option<u64> {
+        session.timers.as_ref()?.grace_remaining_seconds
+    }
+
+    #[test]
+    fn counts_down_reminds_and_expires_once() {
+        let remind = Duration::from_secs(60);
+        let start = Instant::now();
+        let mut countdown = GraceCountdown::default();
+        let mut session = grace_session("ses_1", 120);
+
+        assert_eq!(
+            countdown.tick(Some(&mut session), start, remind),
+            GraceTick::Entered {
+                remaining: Duration::from_secs(120)
+            }
+        );
+        assert_eq!(
+            countdown.tick(Some(&mut session), start + Duration::from_secs(30), remind),
+            GraceTick::Counting
+        );
+        assert_eq!(remaining_of(&session), Some(90));
+        assert_eq!(
+            countdown.tick(Some(&mut session), start + Duration::from_secs(60), remind),
+            GraceTick::Reminder {
+                remaining: Duration::from_secs(60)
+            }
+        );
+        let end = start + Duration::from_secs(120);
+        assert_eq!(
+            countdown.tick(Some(&mut session), end, remind),
+            GraceTick::Expired
+        );
+        assert_eq!(
+            countdown.tick(Some(&mut session), end, remind),
+            GraceTick::Counting
+        );
+        assert_eq!(remaining_of(&session), Some(0));
+    }
+
+    #[test]
+    fn control_plane_values_reset_the_deadline() {
+        let remind = Duration::from_secs(600);
+        let start = Instant::now();
+        let mut countdown = GraceCountdown::default();
+        countdown.tick(Some(&mut grace_session("ses_1", 120)), start, remind);
+
+        // A refresh later reports more time than the local countdown had left.
+        let mut refreshed = grace_session("ses_1", 300);
+        let later = start + Duration::from_secs(10);
+        assert_eq!(
+            countdown.tick(Some(&mut refreshed), later, remind),
+            GraceTick::Counting
+        );
+        assert_eq!(
+            countdown.tick(
+                Some(&mut refreshed),
+                later + Duration::from_secs(100),
+                remind
+            ),
+            GraceTick::Counting
+        );
+        assert_eq!(remaining_of(&refreshed), Some(200));
+
+        let mut active = grace_session("ses_1", 0);
+        active.status = "active".to_string();
+        assert_eq!(
+            countdown.tick(Some(&mut active), later, remind),
+            GraceTick::Idle
+        );
+        assert_eq!(countdown.tick(None, later, remind), GraceTick::Idle);
+    }
+
+    #[test]
+    fn formats_remaining_time() {
+        assert_eq!(format_remaining(Duration::from_secs(45)), "45s");
+        assert_eq!(format_remaining(Duration::from_secs(600)), "10m");
+        assert_eq!(format_remaining(Duration::from_secs(570)), "9m 30s");
+    }
+}
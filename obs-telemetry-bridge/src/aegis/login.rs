@@ -0,0 +1,231 @@
+use super::{ControlPlaneError, DEFAULT_TIMEOUT_SECS};
+use crate::security::SecretString;
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+const CLIENT_ID: &str = "telemy";
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
+/// RFC 8628 default when the server does not say how often to poll.
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 5;
+/// Added to the poll interval on every `slow_down`, per RFC 8628 §3.5.
+const SLOW_DOWN_STEP: Duration = Duration::from_secs(5);
+
+/// What the user needs to approve this device.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceAuthorization {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    #[serde(default)]
+    pub verification_uri_complete: Option<String>,
+    pub expires_in: u64,
+    #[serde(default = "default_interval")]
+    pub interval: u64,
+}
+
+fn default_interval() -> u64 {
+    DEFAULT_POLL_INTERVAL_SECS
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeviceTokens {
+    pub access_token: SecretString,
+    #[serde(default)]
+    pub refresh_token: Option<SecretString>,
+}
+
+#[derive(Debug)]
+pub enum DevicePoll {
+    Pending,
+    SlowDown,
+    Granted(DeviceTokens),
+}
+
+#[derive(Debug, Deserialize)]
+struct OAuthError {
+    error: String,
+}
+
+/// OAuth 2.0 device authorization grant (RFC 8628) against the control plane,
+/// so a user can sign in from a browser instead of pasting a JWT into the vault.
+#[derive(Debug, Clone)]
+pub struct DeviceLogin {
+    http: reqwest::Client,
+    base_url: Url,
+}
+
+impl DeviceLogin {
+    pub fn new(base_url: &str) -> Result<Self, ControlPlaneError> {
+        let mut base_url =
+            Url::parse(base_url.trim()).map_err(|err| ControlPlaneError::Url(err.to_string()))?;
+        if !base_url.path().ends_with('/') {
+            let path = format!("{}/", base_url.path());
+            base_url.set_path(&path);
+        }
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_secs(DEFAULT_TIMEOUT_SECS))
+            .build()
+            .map_err(ControlPlaneError::Http)?;
+        Ok(Self { http, base_url })
+    }
+
+    fn url(&self, path: &str) -> Result<Url, ControlPlaneError> {
+        self.base_url
+            .join(&format!("api/v1/auth/device/{path}"))
+            .map_err(|err| ControlPlaneError::Url(err.to_string()))
+    }
+
+    pub async fn start(&self) -> Result<DeviceAuthorization, ControlPlaneError> {
+        let resp = self
+            .http
+            .post(self.url("code")?)
+            .form(&[("client_id", CLIENT_ID)])
+            .send()
+            .await
+            .map_err(ControlPlaneError::Http)?;
+        let status = resp.status();
+        let body = resp.text().await.map_err(ControlPlaneError::Http)?;
+        if !status.is_success() {
+            return Err(ControlPlaneError::Api { status, body });
+        }
+        serde_json::from_str(&body).map_err(ControlPlaneError::Json)
+    }
+
+    pub async fn poll(&self, device_code: &str) -> Result<DevicePoll, ControlPlaneError> {
+        let resp = self
+            .http
+            .post(self.url("token")?)
+            .form(&[
+                ("grant_type", DEVICE_CODE_GRANT),
+                ("device_code", device_code),
+                ("client_id", CLIENT_ID),
+            ])
+            .send()
+            .await
+            .map_err(ControlPlaneError::Http)?;
+        let status = resp.status();
+        let body = resp.text().await.map_err(ControlPlaneError::Http)?;
+        parse_token_response(status, &body)
+    }
+
+    /// Polls until the user approves or the code expires.
+    pub async fn wait_for_tokens(
+        &self,
+        auth: &DeviceAuthorization,
+    ) -> Result<DeviceTokens, ControlPlaneError> {
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(auth.expires_in);
+        let mut interval = Duration::from_secs(auth.interval.max(1));
+        loop {
+            tokio::time::sleep(interval).await;
+            if tokio::time::Instant::now() >= deadline {
+                return Err(ControlPlaneError::Config(
+                    "device code expired before it was approved",
+                ));
+            }
+            match self.poll(&auth.device_code).await? {
+                DevicePoll::Pending => {}
+                DevicePoll::SlowDown => interval += SLOW_DOWN_STEP,
+                DevicePoll::Granted(tokens) => return Ok(tokens),
+            }
+        }
+    }
+}
+
+fn parse_token_response(status: StatusCode, body: &str) -> Result<DevicePoll, ControlPlaneError> {
+    if status.is_success() {
+        let tokens: DeviceTokens = serde_json::from_str(body).map_err(ControlPlaneError::Json)?;
+        return Ok(DevicePoll::Granted(tokens));
+    }
+    let error = serde_json::from_str::<OAuthError>(body).map(|e| e.error);
+    match error.as_deref() {
+        Ok("authorization_pending") => Ok(DevicePoll::Pending),
+        Ok("slow_down") => Ok(DevicePoll::SlowDown),
+        Ok("access_denied") => Err(ControlPlaneError::Config("device login was denied")),
+        Ok("expired_token") => Err(ControlPlaneError::Config(
+            "device code expired before it was approved",
+        )),
+        _ => Err(ControlPlaneError::Api {
+            status,
+            body: body.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn maps_oauth_device_errors() {
+        let bad = StatusCode::BAD_REQUEST;
+        assert!(matches!(
+            parse_token_response(bad, r#"{"error":"authorization_pending"}"#),
+            Ok(DevicePoll::Pending)
+        ));
+        assert!(matches!(
+            parse_token_response(bad, r#"{"error":"slow_down"}"#),
+            Ok(DevicePoll::SlowDown)
+        ));
+        let denied = parse_token_response(bad, r#"{"error":"access_denied"}"#).unwrap_err();
+        assert!(denied.to_string().contains("denied"));
+        assert!(matches!(
+            parse_token_response(StatusCode::INTERNAL_SERVER_ERROR, "oops"),
+            Err(ControlPlaneError::Api { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn device_flow_polls_until_approved() {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let counter = polls.clone();
+        let app = axum::Router::new()
+            .route(
+                "/api/v1/auth/device/code",
+                axum::routing::post(|| async {
+                    axum::Json(serde_json::json!({
+                        "device_code": "dev-1",
+                        "user_code": "ABCD-EFGH",
+                        "verification_uri": "https://aegis.example.test/device",
+                        "expires_in": 600,
+                        "interval": 1
+                    }))
+                }),
+            )
+            .route(
+                "/api/v1/auth/device/token",
+                axum::routing::post(move || {
+                    let n = counter.fetch_add(1, Ordering::SeqCst);
+                    async move {
+                        if n == 0 {
+                            (
+                                axum::http::StatusCode::BAD_REQUEST,
+                                r#"{"error":"authorization_pending"}"#,
+                            )
+                        } else {
+                            (
+                                axum::http::StatusCode::OK,
+                                r#"{"access_token":"jwt-new","refresh_token":"rt-1"}"#,
+                            )
+                        }
+                    }
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let login = DeviceLogin::new(&format!("http://{addr}")).unwrap();
+        let auth = login.start().await.unwrap();
+        assert_eq!(auth.user_code, "ABCD-EFGH");
+        let tokens = login.wait_for_tokens(&auth).await.unwrap();
+        assert_eq!(tokens.access_token.expose(), "jwt-new");
+        assert_eq!(
+            tokens.refresh_token.as_ref().map(SecretString::expose),
+            Some("rt-1")
+        );
+        assert_eq!(polls.load(Ordering::SeqCst), 2);
+    }
+}
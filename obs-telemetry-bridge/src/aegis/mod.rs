@@ -1,9 +1,30 @@
+use crate::security::SecretString;
 use reqwest::{
     header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE},
     Client, Method, Request, StatusCode, Url,
 };
 use serde::{Deserialize, Serialize};
-use std::{fmt, net::IpAddr, str::FromStr, time::Duration};
+use std::{
+    fmt,
+    net::IpAddr,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+mod grace;
+mod login;
+mod persist;
+mod poller;
+mod region;
+mod retry;
+
+pub use grace::{format_remaining, GraceCountdown, GraceTick};
+pub use login::{DeviceLogin, DeviceTokens};
+pub use persist::{default_session_path, SessionFile};
+pub use poller::{SessionFreshness, SessionPoller};
+pub use region::resolve_region_preference;
+pub use retry::{CircuitBreaker, ControlPlaneHealth, RetryPolicy};
 
 const DEFAULT_TIMEOUT_SECS: u64 = 15;
 pub const DEFAULT_CLIENT_PLATFORM: &str = "windows";
@@ -13,29 +34,35 @@ pub const DEFAULT_CLIENT_VERSION: &str = env!("CARGO_PKG_VERSION");
 pub struct ControlPlaneClient {
     http: Client,
     base_url: Url,
-    access_jwt: String,
+    access_jwt: SecretString,
     client_version: String,
     client_platform: String,
+    retry: RetryPolicy,
+    breaker: Arc<CircuitBreaker>,
 }
 
 #[derive(Clone, Debug)]
 pub struct ControlPlaneClientBuilder {
     base_url: String,
-    access_jwt: String,
+    access_jwt: SecretString,
     client_version: String,
     client_platform: String,
     timeout: Duration,
+    retry: RetryPolicy,
+    breaker: Arc<CircuitBreaker>,
 }
 
 #[allow(dead_code)] // retained for future client/plugin overrides and test tuning
 impl ControlPlaneClientBuilder {
-    pub fn new(base_url: impl Into<String>, access_jwt: impl Into<String>) -> Self {
+    pub fn new(base_url: impl Into<String>, access_jwt: impl Into<SecretString>) -> Self {
         Self {
             base_url: base_url.into(),
             access_jwt: access_jwt.into(),
             client_version: DEFAULT_CLIENT_VERSION.to_string(),
             client_platform: DEFAULT_CLIENT_PLATFORM.to_string(),
             timeout: Duration::from_secs(DEFAULT_TIMEOUT_SECS),
+            retry: RetryPolicy::default(),
+            breaker: CircuitBreaker::shared(),
         }
     }
 
@@ -54,6 +81,17 @@ impl ControlPlaneClientBuilder {
         self
     }
 
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Defaults to the process-wide [`CircuitBreaker::shared`].
+    pub fn circuit_breaker(mut self, breaker: Arc<CircuitBreaker>) -> Self {
+        self.breaker = breaker;
+        self
+    }
+
     pub fn build(self) -> Result<ControlPlaneClient, ControlPlaneError> {
         ControlPlaneClient::from_parts(
             self.base_url,
@@ -61,6 +99,8 @@ impl ControlPlaneClientBuilder {
             self.client_version,
             self.client_platform,
             self.timeout,
+            self.retry,
+            self.breaker,
         )
     }
 }
@@ -68,26 +108,28 @@ impl ControlPlaneClientBuilder {
 impl ControlPlaneClient {
     pub fn builder(
         base_url: impl Into<String>,
-        access_jwt: impl Into<String>,
+        access_jwt: impl Into<SecretString>,
     ) -> ControlPlaneClientBuilder {
         ControlPlaneClientBuilder::new(base_url, access_jwt)
     }
 
     pub fn new(
         base_url: impl Into<String>,
-        access_jwt: impl Into<String>,
+        access_jwt: impl Into<SecretString>,
     ) -> Result<Self, ControlPlaneError> {
         Self::builder(base_url, access_jwt).build()
     }
 
     fn from_parts(
         base_url: String,
-        access_jwt: String,
+        access_jwt: SecretString,
         client_version: String,
         client_platform: String,
         timeout: Duration,
+        retry: RetryPolicy,
+        breaker: Arc<CircuitBreaker>,
     ) -> Result<Self, ControlPlaneError> {
-        if access_jwt.trim().is_empty() {
+        if access_jwt.expose().trim().is_empty() {
             return Err(ControlPlaneError::Config(
                 "control-plane access JWT must not be empty",
             ));
@@ -120,14 +162,57 @@ impl ControlPlaneClient {
             access_jwt,
             client_version,
             client_platform,
+            retry,
+            breaker,
         })
     }
 
+    /// Sends `req`, retrying network errors, 5xx and 429 per the retry policy.
+    /// Every relay call is safe to repeat: `relay/start` carries an
+    /// Idempotency-Key and stopping a session twice is harmless. The final
+    /// outcome of each call feeds the circuit breaker.
+    async fn execute(&self, req: Request) -> Result<(StatusCode, String), ControlPlaneError> {
+        if let Err(retry_in) = self.breaker.check(Instant::now()) {
+            return Err(ControlPlaneError::CircuitOpen { retry_in });
+        }
+        let mut attempt = 1;
+        loop {
+            let this_attempt = req
+                .try_clone()
+                .ok_or(ControlPlaneError::Config("request body cannot be retried"))?;
+            let (failure, retry_after) = match self.http.execute(this_attempt).await {
+                Ok(resp) if !retry::retryable_status(resp.status()) => {
+                    let status = resp.status();
+                    let body = resp.text().await.map_err(ControlPlaneError::Http)?;
+                    self.breaker.record_success();
+                    return Ok((status, body));
+                }
+                Ok(resp) => {
+                    let status = resp.status();
+                    let retry_after = retry::retry_after(resp.headers());
+                    let body = resp.text().await.unwrap_or_default();
+                    (ControlPlaneError::Api { status, body }, retry_after)
+                }
+                Err(err) => (ControlPlaneError::Http(err), None),
+            };
+            let Some(delay) = self.retry.delay(attempt, retry_after) else {
+                self.breaker.record_failure(failure.to_string(), Instant::now());
+                return Err(failure);
+            };
+            tracing::debug!(
+                attempt,
+                error = %failure,
+                delay_ms = delay.as_millis() as u64,
+                "aegis request failed; retrying"
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
     pub async fn relay_active(&self) -> Result<Option<RelaySession>, ControlPlaneError> {
-        let req = self.build_request(Method::GET, "relay/active")?;
-        let resp = self.http.execute(req).await.map_err(ControlPlaneError::Http)?;
-        let status = resp.status();
-        let body = resp.text().await.map_err(ControlPlaneError::Http)?;
+        let req = self.build_relay_active_request()?;
+        let (status, body) = self.execute(req).await?;
         parse_relay_active_response(status, &body)
     }
 
@@ -137,9 +222,7 @@ impl ControlPlaneClient {
         request: &RelayStartRequest,
     ) -> Result<RelaySession, ControlPlaneError> {
         let req = self.build_relay_start_request(idempotency_key, request)?;
-        let resp = self.http.execute(req).await.map_err(ControlPlaneError::Http)?;
-        let status = resp.status();
-        let body = resp.text().await.map_err(ControlPlaneError::Http)?;
+        let (status, body) = self.execute(req).await?;
         parse_relay_start_response(status, &body)
     }
 
@@ -148,12 +231,20 @@ impl ControlPlaneClient {
         request: &RelayStopRequest,
     ) -> Result<RelayStopResponse, ControlPlaneError> {
         let req = self.build_relay_stop_request(request)?;
-        let resp = self.http.execute(req).await.map_err(ControlPlaneError::Http)?;
-        let status = resp.status();
-        let body = resp.text().await.map_err(ControlPlaneError::Http)?;
+        let (status, body) = self.execute(req).await?;
         parse_relay_stop_response(status, &body)
     }
 
+    pub async fn regions(&self) -> Result<Vec<RelayRegion>, ControlPlaneError> {
+        let req = self.build_relay_manifest_request()?;
+        let (status, body) = self.execute(req).await?;
+        parse_relay_manifest_response(status, &body)
+    }
+
+    pub fn build_relay_manifest_request(&self) -> Result<Request, ControlPlaneError> {
+        self.build_request(Method::GET, "relay/manifest")
+    }
+
     pub fn build_relay_active_request(&self) -> Result<Request, ControlPlaneError> {
         self.build_request(Method::GET, "relay/active")
     }
@@ -208,7 +299,7 @@ impl ControlPlaneClient {
         let mut headers = HeaderMap::new();
         headers.insert(
             AUTHORIZATION,
-            HeaderValue::from_str(&format!("Bearer {}", self.access_jwt))
+            HeaderValue::from_str(&format!("Bearer {}", self.access_jwt.expose()))
                 .map_err(ControlPlaneError::InvalidHeaderValue)?,
         );
         headers.insert(
@@ -233,6 +324,8 @@ pub enum ControlPlaneError {
     Json(serde_json::Error),
     InvalidHeaderValue(reqwest::header::InvalidHeaderValue),
     Api { status: StatusCode, body: String },
+    /// Not sent: recent calls kept failing.
+    CircuitOpen { retry_in: Duration },
 }
 
 impl fmt::Display for ControlPlaneError {
@@ -244,6 +337,11 @@ impl fmt::Display for ControlPlaneError {
             Self::Json(err) => write!(f, "json error: {err}"),
             Self::InvalidHeaderValue(err) => write!(f, "invalid header value: {err}"),
             Self::Api { status, body } => write!(f, "api error {}: {}", status.as_u16(), body),
+            Self::CircuitOpen { retry_in } => write!(
+                f,
+                "control plane degraded; not retrying for {}s",
+                retry_in.as_secs().max(1)
+            ),
         }
     }
 }
@@ -287,6 +385,10 @@ pub struct RelaySessionEnvelope {
     pub session: RelaySession,
 }
 
+/// The last known relay session, shared by the server, IPC and background
+/// tasks. Read on every status request, so it sits behind an async `RwLock`.
+pub type RelaySessionHandle = Arc<tokio::sync::RwLock<Option<RelaySession>>>;
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct RelaySession {
     pub session_id: String,
@@ -364,6 +466,39 @@ pub struct RelayUsage {
     pub duration_seconds: Option<u64>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RelayRegion {
+    pub region: String,
+    #[serde(default)]
+    pub ami_id: Option<String>,
+    #[serde(default)]
+    pub default_instance_type: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    /// `host:port` to measure latency against, if the manifest provides one.
+    #[serde(default)]
+    pub probe_host: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RelayManifestResponse {
+    regions: Vec<RelayRegion>,
+}
+
+pub fn parse_relay_manifest_response(
+    status: StatusCode,
+    body: &str,
+) -> Result<Vec<RelayRegion>, ControlPlaneError> {
+    if !status.is_success() {
+        return Err(ControlPlaneError::Api {
+            status,
+            body: body.to_string(),
+        });
+    }
+    let manifest: RelayManifestResponse = serde_json::from_str(body).map_err(ControlPlaneError::Json)?;
+    Ok(manifest.regions)
+}
+
 pub fn parse_relay_active_response(
     status: StatusCode,
     body: &str,
@@ -409,6 +544,11 @@ pub fn parse_relay_stop_response(
     serde_json::from_str(body).map_err(ControlPlaneError::Json)
 }
 
+/// Health of the process-wide circuit breaker shared by every client.
+pub fn control_plane_health() -> ControlPlaneHealth {
+    CircuitBreaker::shared().health(Instant::now())
+}
+
 fn normalize_ip_string(raw: &str) -> String {
     let trimmed = raw.trim();
     if let Some((candidate_ip, suffix)) = trimmed.rsplit_once('/') {
@@ -487,6 +627,27 @@ mod tests {
         assert_eq!(json["client_context"]["mode"], "studio");
     }
 
+    #[test]
+    fn parse_manifest_lists_regions() {
+        let regions = parse_relay_manifest_response(
+            StatusCode::OK,
+            r#"{
+                "regions": [
+                    { "region": "us-east-1", "ami_id": "ami-1", "default_instance_type": "t4g.small", "updated_at": "2026-02-21T18:00:00Z" },
+                    { "region": "eu-west-1", "probe_host": "ingest.eu-west-1.example.test:9000" }
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(regions.len(), 2);
+        assert_eq!(regions[0].ami_id.as_deref(), Some("ami-1"));
+        assert_eq!(
+            regions[1].probe_host.as_deref(),
+            Some("ingest.eu-west-1.example.test:9000")
+        );
+        assert!(parse_relay_manifest_response(StatusCode::SERVICE_UNAVAILABLE, "{}").is_err());
+    }
+
     #[test]
     fn parse_active_returns_none_on_204() {
         let out = parse_relay_active_response(StatusCode::NO_CONTENT, "").unwrap();
@@ -542,6 +703,79 @@ mod tests {
         assert_eq!(normalize_ip_string("2001:db8::1/128"), "2001:db8::1");
     }
 
+    /// Serves `statuses` in order (repeating the last) and counts requests.
+    async fn flaky_server(
+        statuses: Vec<StatusCode>,
+    ) -> (String, Arc<std::sync::atomic::AtomicUsize>) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        let app = axum::Router::new().route(
+            "/api/v1/relay/active",
+            axum::routing::get(move || {
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                let status = statuses[n.min(statuses.len() - 1)];
+                // reqwest and axum depend on different `http` major versions.
+                let status = axum::http::StatusCode::from_u16(status.as_u16()).unwrap();
+                async move { (status, [("retry-after", "0")]) }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (format!("http://{addr}"), hits)
+    }
+
+    fn test_client(
+        base_url: &str,
+        max_attempts: u32,
+        breaker: Arc<CircuitBreaker>,
+    ) -> ControlPlaneClient {
+        ControlPlaneClient::builder(base_url, "jwt-123")
+            .retry_policy(RetryPolicy {
+                max_attempts,
+                initial_backoff: Duration::from_millis(1),
+                max_backoff: Duration::from_millis(10),
+            })
+            .circuit_breaker(breaker)
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn relay_active_retries_server_errors() {
+        let (url, hits) = flaky_server(vec![
+            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::SERVICE_UNAVAILABLE,
+            StatusCode::NO_CONTENT,
+        ])
+        .await;
+        let breaker = Arc::new(CircuitBreaker::default());
+        let client = test_client(&url, 3, breaker.clone());
+        assert!(client.relay_active().await.unwrap().is_none());
+        assert_eq!(hits.load(std::sync::atomic::Ordering::SeqCst), 3);
+        assert_eq!(breaker.health(Instant::now()).state, "ok");
+    }
+
+    #[tokio::test]
+    async fn open_circuit_short_circuits_calls() {
+        let (url, hits) = flaky_server(vec![StatusCode::INTERNAL_SERVER_ERROR]).await;
+        let breaker = Arc::new(CircuitBreaker::default());
+        let client = test_client(&url, 1, breaker.clone());
+        for _ in 0..5 {
+            assert!(matches!(
+                client.relay_active().await,
+                Err(ControlPlaneError::Api { .. })
+            ));
+        }
+        assert!(matches!(
+            client.relay_active().await,
+            Err(ControlPlaneError::CircuitOpen { .. })
+        ));
+        assert_eq!(hits.load(std::sync::atomic::Ordering::SeqCst), 5);
+        assert_eq!(breaker.health(Instant::now()).state, "open");
+    }
+
     #[test]
     fn start_request_rejects_empty_idempotency_key() {
         let err = client()
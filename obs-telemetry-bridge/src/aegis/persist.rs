@@ -0,0 +1,122 @@
+use super::RelaySession;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Statuses that may still be billing; anything else is not worth remembering.
+fn is_live(status: &str) -> bool {
+    matches!(status, "provisioning" | "active" | "grace")
+}
+
+/// The part of a session worth keeping across restarts: enough to show,
+/// monitor and stop it. Credentials stay out of plain files and come back
+/// with the next successful poll; timers and usage go stale immediately.
+fn persisted_form(session: &RelaySession) -> RelaySession {
+    RelaySession {
+        credentials: None,
+        timers: None,
+        usage: None,
+        ..session.clone()
+    }
+}
+
+/// Last known live relay session, kept on disk so a core restarted after a
+/// crash still knows about a billed relay when the control plane is
+/// unreachable at startup.
+#[derive(Debug, Clone)]
+pub struct SessionFile {
+    path: PathBuf,
+}
+
+impl SessionFile {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn load(&self) -> Option<RelaySession> {
+        let raw = fs::read(&self.path).ok()?;
+        match serde_json::from_slice::<RelaySession>(&raw) {
+            Ok(session) if is_live(&session.status) => Some(session),
+            Ok(_) => None,
+            Err(err) => {
+                tracing::warn!(path = %self.path.display(), error = %err, "ignoring unreadable relay session file");
+                None
+            }
+        }
+    }
+
+    /// Writes `session` if it is live and removes the file otherwise. Returns
+    /// whether the file changed, so callers can skip redundant writes.
+    pub fn save(&self, session: Option<&RelaySession>) -> std::io::Result<bool> {
+        let wanted = session
+            .filter(|s| is_live(&s.status))
+            .map(persisted_form)
+            .map(|s| serde_json::to_vec_pretty(&s))
+            .transpose()?;
+        let current = match fs::read(&self.path) {
+            Ok(raw) => Some(raw),
+            Err(err) if err.kind() == ErrorKind::NotFound => None,
+            Err(err) => return Err(err),
+        };
+        if current == wanted {
+            return Ok(false);
+        }
+        match wanted {
+            Some(raw) => {
+                if let Some(parent) = self.path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(&self.path, raw)?;
+            }
+            None => fs::remove_file(&self.path)?,
+        }
+        Ok(true)
+    }
+}
+
+pub fn default_session_path() -> PathBuf {
+    let base = std::env::var("APPDATA").unwrap_or_else(|_| ".".to_string());
+    Path::new(&base).join("Telemy").join("relay-session.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aegis::{RelayCredentials, RelayTimers};
+
+    #[test]
+    fn keeps_live_sessions_without_credentials() {
+        let dir = std::env::temp_dir().join(format!("telemy-session-{}", uuid::Uuid::new_v4()));
+        let file = SessionFile::new(dir.join("relay-session.json"));
+        assert!(file.load().is_none());
+
+        let mut session = RelaySession {
+            session_id: "ses_1".to_string(),
+            status: "active".to_string(),
+            region: Some("us-east-1".to_string()),
+            credentials: Some(RelayCredentials {
+                pair_token: Some("secret".to_string()),
+                relay_ws_token: None,
+            }),
+            timers: Some(RelayTimers::default()),
+            ..RelaySession::default()
+        };
+        assert!(file.save(Some(&session)).unwrap());
+        let restored = file.load().unwrap();
+        assert_eq!(restored.session_id, "ses_1");
+        assert_eq!(restored.region.as_deref(), Some("us-east-1"));
+        assert!(restored.credentials.is_none());
+
+        session.timers = None;
+        assert!(
+            !file.save(Some(&session)).unwrap(),
+            "unchanged fields are not rewritten"
+        );
+
+        session.status = "stopped".to_string();
+        assert!(file.save(Some(&session)).unwrap());
+        assert!(!file.path.exists());
+        assert!(!file.save(None).unwrap());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,150 @@
+use serde::Serialize;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{watch, Notify};
+
+/// A snapshot older than this many poll intervals is reported as stale.
+const STALE_AFTER_INTERVALS: u32 = 3;
+
+/// How current the shared relay session snapshot is.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Default)]
+pub struct SessionFreshness {
+    pub last_refreshed_at_unix_ms: Option<u64>,
+    pub stale: bool,
+}
+
+#[derive(Debug)]
+struct PollState {
+    interval: Duration,
+    last_refreshed: Option<(Instant, u64)>,
+}
+
+/// Bookkeeping for the background session poll: when the snapshot was last
+/// refreshed, and a way for request handlers to ask for an early refresh
+/// instead of calling the control plane themselves.
+#[derive(Debug)]
+pub struct SessionPoller {
+    state: Mutex<PollState>,
+    wake: Notify,
+    /// Bumped after every poll; carries that poll's outcome.
+    completed: watch::Sender<(u64, Result<(), String>)>,
+}
+
+impl Default for SessionPoller {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(PollState {
+                interval: Duration::from_secs(30),
+                last_refreshed: None,
+            }),
+            wake: Notify::new(),
+            completed: watch::channel((0, Ok(()))).0,
+        }
+    }
+}
+
+impl SessionPoller {
+    /// The poller for the process; one Aegis account is polled per process.
+    pub fn shared() -> Arc<Self> {
+        static SHARED: OnceLock<Arc<SessionPoller>> = OnceLock::new();
+        SHARED.get_or_init(Arc::default).clone()
+    }
+
+    pub fn set_interval(&self, interval: Duration) {
+        self.state.lock().unwrap().interval = interval;
+    }
+
+    /// Records the outcome of a refresh and wakes anyone waiting in [`Self::refresh_now`].
+    pub fn record(&self, result: Result<(), String>, now: Instant) {
+        if result.is_ok() {
+            let unix_ms = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64;
+            self.state.lock().unwrap().last_refreshed = Some((now, unix_ms));
+        }
+        self.completed.send_modify(|(generation, outcome)| {
+            *generation += 1;
+            *outcome = result;
+        });
+    }
+
+    pub fn freshness(&self, now: Instant) -> SessionFreshness {
+        let state = self.state.lock().unwrap();
+        match state.last_refreshed {
+            Some((at, unix_ms)) => SessionFreshness {
+                last_refreshed_at_unix_ms: Some(unix_ms),
+                stale: now.duration_since(at) > state.interval * STALE_AFTER_INTERVALS,
+            },
+            None => SessionFreshness::default(),
+        }
+    }
+
+    /// Asks the poller to refresh now and waits up to `timeout` for the result.
+    /// `None` means no poll finished in time.
+    pub async fn refresh_now(&self, timeout: Duration) -> Option<Result<(), String>> {
+        let mut completed = self.completed.subscribe();
+        let generation = completed.borrow_and_update().0;
+        self.wake.notify_one();
+        let done = completed.wait_for(|(g, _)| *g > generation);
+        let outcome = match tokio::time::timeout(timeout, done).await {
+            Ok(Ok(outcome)) => Some(outcome.1.clone()),
+            _ => None,
+        };
+        outcome
+    }
+
+    /// Sleeps until the next poll is due: the interval with ±20% jitter, so
+    /// several cores started together do not poll in step, or earlier if a
+    /// refresh was requested.
+    pub async fn wait_next(&self) {
+        let interval = self.state.lock().unwrap().interval;
+        let jitter = rand::random::<f64>() * 0.4 - 0.2;
+        let delay = interval.mul_f64(1.0 + jitter);
+        tokio::select! {
+            _ = tokio::time::sleep(delay) => {}
+            _ = self.wake.notified() => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snapshot_goes_stale_after_missed_polls() {
+        let poller = SessionPoller::default();
+        poller.set_interval(Duration::from_secs(10));
+        let now = Instant::now();
+        assert_eq!(poller.freshness(now), SessionFreshness::default());
+
+        poller.record(Ok(()), now);
+        let fresh = poller.freshness(now + Duration::from_secs(30));
+        assert!(fresh.last_refreshed_at_unix_ms.is_some());
+        assert!(!fresh.stale);
+
+        poller.record(Err("503".to_string()), now + Duration::from_secs(30));
+        assert!(poller.freshness(now + Duration::from_secs(31)).stale);
+    }
+
+    #[tokio::test]
+    async fn refresh_now_waits_for_the_next_poll() {
+        let poller = Arc::new(SessionPoller::default());
+        let background = poller.clone();
+        let task = tokio::spawn(async move {
+            background.wake.notified().await;
+            background.record(Err("api error 503".to_string()), Instant::now());
+        });
+        tokio::task::yield_now().await;
+        let outcome = poller.refresh_now(Duration::from_secs(1)).await;
+        assert_eq!(outcome, Some(Err("api error 503".to_string())));
+        task.await.unwrap();
+
+        let nobody_polling = SessionPoller::default();
+        assert_eq!(
+            nobody_polling.refresh_now(Duration::from_millis(20)).await,
+            None
+        );
+    }
+}
@@ -0,0 +1,151 @@
+use super::{ControlPlaneClient, RelayRegion};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+
+/// Connects attempted per region; the fastest one counts, so a single slow
+/// handshake does not push a region down the list.
+const PROBE_ATTEMPTS: u32 = 3;
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegionProbe {
+    pub region: String,
+    /// `None` when the region could not be reached.
+    pub rtt: Option<Duration>,
+}
+
+/// Where to measure a region's latency from. Relays only get an address once
+/// provisioned, so unless the manifest names an ingest host the region's EC2
+/// endpoint stands in for it: relays run in that region, so the path is close.
+fn probe_target(region: &RelayRegion) -> String {
+    match region.probe_host.as_deref().map(str::trim) {
+        Some(host) if !host.is_empty() => host.to_string(),
+        _ => format!("ec2.{}.amazonaws.com:443", region.region),
+    }
+}
+
+/// TCP connect time to `target`, best of [`PROBE_ATTEMPTS`].
+async fn probe(target: &str) -> Option<Duration> {
+    let mut best: Option<Duration> = None;
+    for _ in 0..PROBE_ATTEMPTS {
+        let started = Instant::now();
+        if let Ok(Ok(_)) = tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect(target)).await {
+            let rtt = started.elapsed();
+            best = Some(best.map_or(rtt, |b| b.min(rtt)));
+        }
+    }
+    best
+}
+
+/// Probes every region concurrently.
+pub async fn probe_regions(regions: &[RelayRegion]) -> Vec<RegionProbe> {
+    let mut tasks = tokio::task::JoinSet::new();
+    for region in regions {
+        let name = region.region.clone();
+        let target = probe_target(region);
+        tasks.spawn(async move {
+            RegionProbe {
+                region: name,
+                rtt: probe(&target).await,
+            }
+        });
+    }
+    let mut probes = Vec::with_capacity(regions.len());
+    while let Some(result) = tasks.join_next().await {
+        if let Ok(probe) = result {
+            probes.push(probe);
+        }
+    }
+    probes.sort_by(|a, b| a.region.cmp(&b.region));
+    probes
+}
+
+/// The reachable region with the lowest RTT.
+pub fn fastest(probes: &[RegionProbe]) -> Option<&str> {
+    probes
+        .iter()
+        .filter_map(|p| p.rtt.map(|rtt| (rtt, p.region.as_str())))
+        .min()
+        .map(|(_, region)| region)
+}
+
+/// Turns `"auto"` into the lowest-latency region from the manifest. Any other
+/// preference is passed through. If no region can be measured, `"auto"` is
+/// sent as before and the control plane picks.
+pub async fn resolve_region_preference(
+    client: &ControlPlaneClient,
+    preference: Option<String>,
+) -> Option<String> {
+    if !preference
+        .as_deref()
+        .is_some_and(|p| p.eq_ignore_ascii_case("auto"))
+    {
+        return preference;
+    }
+    let regions = match client.regions().await {
+        Ok(regions) => regions,
+        Err(err) => {
+            tracing::warn!(error = %err, "aegis region list failed; leaving region selection to the control plane");
+            return preference;
+        }
+    };
+    let probes = probe_regions(&regions).await;
+    match fastest(&probes) {
+        Some(region) => {
+            tracing::info!(region, probes = ?probes, "aegis selected lowest-latency region");
+            Some(region.to_string())
+        }
+        None => {
+            tracing::warn!(
+                "no aegis region reachable; leaving region selection to the control plane"
+            );
+            preference
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(name: &str, probe_host: Option<String>) -> RelayRegion {
+        RelayRegion {
+            region: name.to_string(),
+            probe_host,
+            ..RelayRegion::default()
+        }
+    }
+
+    fn probe_of(region: &str, rtt_ms: Option<u64>) -> RegionProbe {
+        RegionProbe {
+            region: region.to_string(),
+            rtt: rtt_ms.map(Duration::from_millis),
+        }
+    }
+
+    #[test]
+    fn fastest_skips_unreachable_regions() {
+        let probes = [
+            probe_of("us-east-1", Some(80)),
+            probe_of("eu-west-1", None),
+            probe_of("us-west-2", Some(20)),
+        ];
+        assert_eq!(fastest(&probes), Some("us-west-2"));
+        assert_eq!(fastest(&[probe_of("eu-west-1", None)]), None);
+    }
+
+    #[tokio::test]
+    async fn probes_the_manifest_ingest_host() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { while listener.accept().await.is_ok() {} });
+
+        assert_eq!(
+            probe_target(&region("eu-west-1", None)),
+            "ec2.eu-west-1.amazonaws.com:443"
+        );
+        let probes = probe_regions(&[region("local", Some(addr.to_string()))]).await;
+        assert_eq!(probes.len(), 1);
+        assert!(probes[0].rtt.is_some());
+    }
+}
@@ -0,0 +1,204 @@
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::StatusCode;
+use serde::Serialize;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Consecutive failed calls that open the breaker.
+const FAILURE_THRESHOLD: u32 = 5;
+/// How long an open breaker rejects calls before letting them through again.
+const OPEN_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// How often a control-plane call is attempted and how long to wait between attempts.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The wait before retrying after failed attempt number `attempt` (from 1), or
+    /// `None` to give up. A `Retry-After` longer than `max_backoff` is honoured by
+    /// not retrying at all.
+    pub(super) fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(1 << (attempt - 1).min(16))
+            .min(self.max_backoff);
+        match retry_after {
+            Some(wait) if wait > self.max_backoff => None,
+            Some(wait) => Some(wait.max(backoff)),
+            None => Some(backoff),
+        }
+    }
+}
+
+/// Responses worth another attempt: the control plane is overloaded or broken,
+/// not rejecting the request itself.
+pub(super) fn retryable_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// `Retry-After` in delta-seconds form; HTTP dates are ignored.
+pub(super) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let secs: u64 = headers
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()?;
+    Some(Duration::from_secs(secs))
+}
+
+#[derive(Debug, Default)]
+struct BreakerState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    last_error: Option<String>,
+}
+
+/// Stops calling the control plane after repeated failures so a dead endpoint
+/// costs one fast error instead of a full retry cycle per request. After the
+/// cooldown calls go through again, and the first failure re-opens it.
+#[derive(Debug, Default)]
+pub struct CircuitBreaker {
+    state: Mutex<BreakerState>,
+}
+
+/// Control-plane reachability as seen by this process, for `/api/v1/aegis/status`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ControlPlaneHealth {
+    /// `ok`, `degraded` (recent failures) or `open` (calls are short-circuited).
+    pub state: &'static str,
+    pub consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_in_secs: Option<u64>,
+}
+
+impl CircuitBreaker {
+    /// The breaker every client in the process reports to. Clients are built per
+    /// request, so the failure history has to live outside them.
+    pub fn shared() -> Arc<Self> {
+        static SHARED: OnceLock<Arc<CircuitBreaker>> = OnceLock::new();
+        SHARED.get_or_init(Arc::default).clone()
+    }
+
+    /// `Err(remaining)` while the breaker is open.
+    pub fn check(&self, now: Instant) -> Result<(), Duration> {
+        match self.state.lock().unwrap().open_until {
+            Some(until) if now < until => Err(until - now),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().unwrap();
+        if state.consecutive_failures >= FAILURE_THRESHOLD {
+            tracing::info!("aegis control plane reachable again; circuit closed");
+        }
+        *state = BreakerState::default();
+    }
+
+    pub fn record_failure(&self, error: String, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        state.consecutive_failures += 1;
+        state.last_error = Some(error);
+        if state.consecutive_failures >= FAILURE_THRESHOLD {
+            if state.open_until.is_none_or(|until| now >= until) {
+                tracing::warn!(
+                    failures = state.consecutive_failures,
+                    cooldown_secs = OPEN_COOLDOWN.as_secs(),
+                    "aegis control plane degraded; circuit open"
+                );
+            }
+            state.open_until = Some(now + OPEN_COOLDOWN);
+        }
+    }
+
+    pub fn health(&self, now: Instant) -> ControlPlaneHealth {
+        let state = self.state.lock().unwrap();
+        let retry_in = state
+            .open_until
+            .filter(|until| now < *until)
+            .map(|until| until - now);
+        ControlPlaneHealth {
+            state: match (retry_in, state.consecutive_failures) {
+                (Some(_), _) => "open",
+                (None, 0) => "ok",
+                (None, _) => "degraded",
+            },
+            consecutive_failures: state.consecutive_failures,
+            last_error: state.last_error.clone(),
+            retry_in_secs: retry_in.map(|d| d.as_secs().max(1)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap_and_honours_retry_after() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(3),
+        };
+        assert_eq!(policy.delay(1, None), Some(Duration::from_secs(1)));
+        assert_eq!(policy.delay(2, None), Some(Duration::from_secs(2)));
+        assert_eq!(policy.delay(3, None), Some(Duration::from_secs(3)));
+        assert_eq!(policy.delay(5, None), None);
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(2))),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(policy.delay(1, Some(Duration::from_secs(60))), None);
+    }
+
+    #[test]
+    fn breaker_opens_after_repeated_failures_and_closes_on_success() {
+        let breaker = CircuitBreaker::default();
+        let now = Instant::now();
+        for _ in 0..FAILURE_THRESHOLD - 1 {
+            breaker.record_failure("503".to_string(), now);
+        }
+        assert!(breaker.check(now).is_ok());
+        assert_eq!(breaker.health(now).state, "degraded");
+
+        breaker.record_failure("503".to_string(), now);
+        assert!(breaker.check(now).is_err());
+        assert_eq!(breaker.health(now).state, "open");
+
+        let later = now + OPEN_COOLDOWN;
+        assert!(
+            breaker.check(later).is_ok(),
+            "lets a call through after the cooldown"
+        );
+        breaker.record_failure("503".to_string(), later);
+        assert!(
+            breaker.check(later).is_err(),
+            "one more failure re-opens it"
+        );
+
+        breaker.record_success();
+        assert_eq!(breaker.health(later).state, "ok");
+    }
+}
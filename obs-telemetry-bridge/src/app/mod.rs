@@ -1,247 +1,778 @@
+use crate::activity::ActionKind;
 use crate::aegis::{
-    ControlPlaneClient, RelaySession, RelayStartClientContext, RelayStartRequest, RelayStopRequest,
+    format_remaining, resolve_region_preference, ControlPlaneClient, DeviceLogin, DeviceTokens,
+    GraceCountdown, GraceTick, RelaySession, RelaySessionHandle, RelayStartClientContext,
+    RelayStartRequest, RelayStopRequest, SessionFile, SessionPoller,
 };
-use crate::config::Config;
-use crate::exporters::GrafanaExporter;
-use crate::metrics::MetricsHub;
-use crate::model::TelemetryFrame;
-use crate::security::Vault;
+use crate::cli::{
+    AegisCommand, AutostartCommand, Cli, Command, ConfigCommand, DiagnosticsCommand, RelayCommand,
+    VaultCommand,
+};
+use crate::config::{
+    Config, ConfigHandle, GraceAction, MetricsConfig, NetworkConfig, VaultBackend,
+};
+use crate::diagnostics;
+use crate::instance::{Instance, InstanceInfo};
+use crate::ipc::{CoreIpcCommand, SnapshotMode, UserNoticeLevel};
+use crate::metrics::{MetricsHub, ObsLink, ObsLinkSettings};
+use crate::model::{ObsConnectionState, TelemetryFrame};
+use crate::security::{SecretString, Vault, VaultHandle};
+use clap::Parser;
 use rand::{distributions::Alphanumeric, Rng};
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
-use tokio::sync::watch;
+use tokio::sync::{watch, RwLock};
 use tokio::time::Duration;
 
+/// Vault key for the Aegis access token when `aegis.access_jwt_key` is unset.
+const DEFAULT_AEGIS_JWT_KEY: &str = "aegis_access_jwt";
+/// Longest the exit sequence may take before the process leaves anyway; under
+/// `telemy stop`'s wait.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(8);
+/// How long the plugin gets to receive `shutdown_request` before the pipe goes.
+const IPC_FAREWELL_TIMEOUT: Duration = Duration::from_secs(1);
+const OTLP_FLUSH_TIMEOUT: Duration = Duration::from_secs(3);
+
 pub async fn run() -> Result<(), Box<dyn std::error::Error>> {
-    let config = Config::load()?;
+    let cli = Cli::parse();
+    if let Some(path) = cli.global.config.clone() {
+        crate::config::set_path_override(path);
+    }
+    let profile = match cli.global.profile.clone() {
+        Some(profile) => crate::config::set_profile(profile),
+        None => Ok(()),
+    };
+    let command = cli.command.unwrap_or(Command::Serve { paused: false });
+    // Logging follows the config, so it starts once the file to load is known.
+    let logging = Config::load().map(|c| c.logging).unwrap_or_default();
+    crate::logging::init(&logging, matches!(command, Command::Serve { .. }));
+    profile?;
+    let json = cli.global.json;
 
-    if let Some(ref command) = std::env::args().nth(1) {
-        if command == "vault-set" {
-            return handle_vault_set(&config);
-        }
-        if command == "vault-get" {
-            return handle_vault_get(&config);
-        }
-        if command == "vault-list" {
-            return handle_vault_list(&config);
-        }
-        if command == "config-init" {
-            return handle_config_init();
-        }
-        if command == "autostart-enable" {
-            return handle_autostart(true, &config);
-        }
-        if command == "autostart-disable" {
-            return handle_autostart(false, &config);
-        }
-        if command == "aegis-relay-active" {
-            return handle_aegis_relay_active(&config).await;
-        }
-        if command == "aegis-relay-start" {
-            return handle_aegis_relay_start(&config).await;
-        }
-        if command == "aegis-relay-stop" {
-            return handle_aegis_relay_stop(&config).await;
+    match command {
+        Command::Serve { paused } => serve(paused).await,
+        Command::Collect {
+            obs_timeout,
+            sample_ms,
+        } => handle_collect(obs_timeout, sample_ms, json).await,
+        Command::Status => handle_status(json).await,
+        Command::Open => handle_open(json),
+        Command::Stop => handle_stop(json).await,
+        Command::Vault { command } => handle_vault(command, json),
+        Command::Aegis {
+            command: AegisCommand::Relay { command },
+        } => handle_aegis_relay(command, json).await,
+        Command::Aegis {
+            command: AegisCommand::Login { base_url },
+        } => handle_aegis_login(base_url, json).await,
+        Command::Config { command } => handle_config(command, json),
+        Command::Diagnostics { command } => handle_diagnostics(command, json).await,
+        Command::Autostart { command } => {
+            handle_autostart(matches!(command, AutostartCommand::Enable), json)
         }
     }
+}
 
-    let vault = Arc::new(Mutex::new(Vault::new(config.vault.path.as_deref())?));
+fn obs_link_settings(config: &Config, vault: &Vault) -> ObsLinkSettings {
+    ObsLinkSettings {
+        host: config.obs.host.clone(),
+        port: config.obs.port,
+        password: config
+            .obs
+            .password_key
+            .as_deref()
+            .and_then(|key| vault.retrieve(key).ok())
+            .map(|p| p.expose().trim().into()),
+        auto_detect: config.obs.auto_detect_process,
+        process_name: config.obs.process_name.clone(),
+    }
+}
 
-    let obs_password = {
-        let v = vault.lock().unwrap();
-        match config.obs.password_key.as_deref() {
-            Some(key) => v.retrieve(key).ok().map(|p| p.trim().to_string()),
-            None => None,
-        }
+async fn serve(paused: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    let vault = Vault::open(&config.vault)?;
+    let _instance = match crate::instance::acquire(config.server.port)? {
+        Instance::Primary(lock) => lock,
+        Instance::Running(info) => return hand_off(&config, &vault, &info, paused).await,
     };
+    if paused {
+        crate::pause::set_paused(true, "cli");
+    }
+
+    let vault: VaultHandle = Arc::new(RwLock::new(vault));
+    if cfg!(not(windows)) && config.vault.backend == VaultBackend::Os {
+        tracing::warn!("vault secrets are stored unencrypted; set vault.backend = \"encrypted\"");
+    }
+
+    // Started first so startup logs reach Loki too.
+    if config.loki.enabled {
+        let loki_auth_value = {
+            let v = vault.read().await;
+            match config.loki.auth_value_key.as_deref() {
+                Some(key) => v.retrieve(key).ok(),
+                None => None,
+            }
+        };
+        crate::exporters::loki::spawn(&config.loki, loki_auth_value);
+    }
 
     let grafana_auth_value = {
-        let v = vault.lock().unwrap();
+        let v = vault.read().await;
         match config.grafana.auth_value_key.as_deref() {
             Some(key) => v.retrieve(key).ok(),
             None => None,
         }
     };
 
+    let webhook_auth_values: Vec<_> = {
+        let v = vault.read().await;
+        config
+            .webhooks
+            .iter()
+            .map(|hook| {
+                hook.auth_value_key
+                    .as_deref()
+                    .and_then(|key| v.retrieve(key).ok())
+            })
+            .collect()
+    };
+
+    let peer_tokens: Vec<_> = {
+        let v = vault.read().await;
+        config
+            .peers
+            .iter()
+            .map(|peer| {
+                peer.token_key
+                    .as_deref()
+                    .and_then(|key| v.retrieve(key).ok())
+            })
+            .collect()
+    };
+
     let grafana_configured =
         config.grafana.enabled && config.grafana.endpoint.is_some() && grafana_auth_value.is_some();
 
-    let aegis_session_snapshot = Arc::new(Mutex::new(None::<RelaySession>));
-    run_aegis_startup_probe(&config, vault.clone(), aegis_session_snapshot.clone()).await;
+    let aegis_session_snapshot: RelaySessionHandle = Arc::default();
+    let session_file = SessionFile::new(crate::aegis::default_session_path());
+    run_aegis_startup_probe(
+        &config,
+        vault.clone(),
+        aegis_session_snapshot.clone(),
+        &session_file,
+    )
+    .await;
+    let config_handle = ConfigHandle::new(config.clone());
+    config_handle.spawn_watcher();
+    spawn_aegis_session_poller(
+        config_handle.clone(),
+        vault.clone(),
+        aegis_session_snapshot.clone(),
+    );
+    spawn_aegis_session_persistence(session_file.clone(), aegis_session_snapshot.clone());
+
+    // Shared by metrics collection and the REST/IPC output controls.
+    let obs = ObsLink::spawn(obs_link_settings(&config, &*vault.read().await));
 
     let (tx, rx) = watch::channel(TelemetryFrame::default());
     let ipc_debug_status = crate::ipc::new_debug_status();
+    let ipc_overrides = Arc::new(crate::ipc::OverrideStore::load(
+        crate::ipc::default_overrides_path(),
+    ));
+    crate::activity::open(crate::activity::default_path());
+    // Shared with the REST server so IPC handshake failures show up in the same audit log.
+    let audit = Arc::new(crate::server::AuditLog::open(
+        crate::server::default_audit_path(),
+    ));
+    let ipc_auth = if config.ipc.auth_required {
+        let secret = crate::ipc::load_or_create_ipc_secret(
+            &mut *vault.write().await,
+            &config.ipc.secret_key,
+        )?;
+        Some(Arc::new(crate::ipc::IpcAuth::new(
+            secret.expose(),
+            audit.clone(),
+        )))
+    } else {
+        None
+    };
+    let ipc_tcp = match &config.ipc.tcp_listen {
+        Some(listen) => {
+            let token = crate::ipc::load_or_create_ipc_secret(
+                &mut *vault.write().await,
+                &config.ipc.tcp_token_key,
+            )?;
+            Some(crate::ipc::TcpTransport {
+                addr: listen.parse()?,
+                auth: Arc::new(crate::ipc::IpcAuth::new(token.expose(), audit.clone())),
+            })
+        }
+        None => None,
+    };
     let ipc_cmd_tx = crate::ipc::spawn_server(
         rx.clone(),
         aegis_session_snapshot.clone(),
         ipc_debug_status.clone(),
+        obs.clone(),
+        ipc_overrides.clone(),
+        ipc_auth,
+        ipc_tcp,
     );
-    let obs_host = config.obs.host.clone();
-    let obs_port = config.obs.port;
-    let latency_target = config.network.latency_target.clone();
-    let obs_auto_detect = config.obs.auto_detect_process;
-    let obs_process_name = config.obs.process_name.clone();
+    let relay_telemetry = crate::relay::spawn_relay_telemetry(aegis_session_snapshot.clone());
+    if config.aegis.enabled {
+        spawn_aegis_grace_watch(
+            config.clone(),
+            vault.clone(),
+            aegis_session_snapshot.clone(),
+            rx.clone(),
+            ipc_cmd_tx.clone(),
+            ipc_overrides.clone(),
+        );
+    }
+    if config.aegis.auto_relay {
+        spawn_aegis_auto_relay(
+            config.clone(),
+            vault.clone(),
+            aegis_session_snapshot.clone(),
+            rx.clone(),
+            ipc_overrides.clone(),
+        );
+    }
+    let network_config = config.network.clone();
+
+    let sinks =
+        crate::exporters::pipeline_from_config(&config, grafana_auth_value, webhook_auth_values);
+    tracing::info!(sinks = ?sinks.names(), "telemetry export pipeline");
+    let has_sinks = !sinks.names().is_empty();
+    sinks.spawn(rx.clone());
 
+    let metrics = MetricsLoop {
+        obs: obs.clone(),
+        network: network_config,
+        config: config.metrics.clone(),
+        ipc_status: ipc_debug_status.clone(),
+        relay_telemetry,
+        has_sinks,
+        tx,
+    };
+    let metrics_alerts = config_handle.clone();
     let metrics_task = tokio::spawn(async move {
-        let mut hub = MetricsHub::new(
-            obs_host,
-            obs_port,
-            obs_password,
-            latency_target,
-            obs_auto_detect,
-            obs_process_name,
-        );
-        let mut ticker = tokio::time::interval(std::time::Duration::from_millis(500));
-        loop {
-            ticker.tick().await;
-            if let Ok(frame) = hub.collect().await {
-                let _ = tx.send(frame);
-            }
-        }
+        // A panic in collection restarts the loop instead of leaving every
+        // consumer on the last frame.
+        crate::metrics::supervise(metrics_alerts, || metrics.run()).await;
     });
+    crate::metrics::spawn_watchdog(config_handle.clone(), rx.clone());
 
-    if config.grafana.enabled {
-        if let Some(endpoint) = config.grafana.endpoint.clone() {
-            let export_rx = rx.clone();
-            let interval_ms = config.grafana.push_interval_ms;
-            let grafana_auth_header = config.grafana.auth_header.clone();
-            tokio::spawn(async move {
-                let mut backoff_ms = 1000u64;
-                loop {
-                    let exporter = GrafanaExporter::new(
-                        &endpoint,
-                        &grafana_auth_header,
-                        grafana_auth_value.clone(),
-                        interval_ms,
-                    );
-
-                    match exporter {
-                        Ok(exporter) => {
-                            let mut ticker =
-                                tokio::time::interval(Duration::from_millis(interval_ms));
-                            loop {
-                                ticker.tick().await;
-                                let frame = export_rx.borrow().clone();
-                                exporter.record(&frame);
-                            }
-                        }
-                        Err(err) => {
-                            eprintln!("grafana exporter init failed: {err}");
-                            tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
-                            backoff_ms = (backoff_ms * 2).min(30_000);
-                        }
-                    }
-                }
-            });
+    if config.reports.enabled {
+        crate::reports::spawn_report_writer(&config.reports, rx.clone());
+    }
+
+    crate::events::spawn(config_handle.clone(), rx.clone());
+    crate::notify::spawn(config_handle.clone(), rx.clone());
+    crate::diskguard::spawn(config_handle.clone(), obs.clone(), rx.clone());
+    crate::bitrate::spawn(
+        config_handle.clone(),
+        obs.clone(),
+        ipc_overrides.clone(),
+        rx.clone(),
+    );
+    crate::failover::spawn(
+        config_handle.clone(),
+        obs.clone(),
+        ipc_overrides.clone(),
+        rx.clone(),
+    );
+    let ingest = crate::ingest::spawn(config_handle.clone(), aegis_session_snapshot.clone());
+    let peers = crate::peers::spawn(&config.peers, peer_tokens);
+    let rules = crate::rules::spawn(
+        crate::rules::RuleContext {
+            obs: obs.clone(),
+            config: config.clone(),
+            vault: vault.clone(),
+            aegis_session_snapshot: aegis_session_snapshot.clone(),
+            overrides: ipc_overrides.clone(),
+        },
+        rx.clone(),
+        crate::rules::default_audit_path(),
+    );
+    crate::scripting::spawn(
+        crate::scripting::ScriptContext {
+            obs: obs.clone(),
+            config: config.clone(),
+            vault: vault.clone(),
+            aegis_session_snapshot: aegis_session_snapshot.clone(),
+            ipc_cmd_tx: ipc_cmd_tx.clone(),
+            overrides: ipc_overrides.clone(),
+        },
+        rx.clone(),
+    );
+
+    let ship_obs_log = config.loki.enabled && config.loki.ship_obs_log;
+    let obs_log = if config.obs.tail_log || ship_obs_log {
+        crate::obslog::spawn(crate::obslog::log_dir(&config.obs))
+    } else {
+        Arc::new(crate::obslog::ObsLog::default())
+    };
+    if ship_obs_log {
+        crate::exporters::loki::ship_obs_log(obs_log.subscribe());
+    }
+
+    crate::crash::spawn(config_handle.clone(), obs_log.clone(), rx.clone());
+
+    if config.twitch.enabled {
+        match vault.read().await.retrieve(&config.twitch.oauth_token_key) {
+            Ok(token) => crate::twitch::spawn(
+                config.twitch.clone(),
+                config.tray.brb_scene.clone(),
+                token,
+                rx.clone(),
+            ),
+            Err(err) => tracing::warn!(
+                key = %config.twitch.oauth_token_key,
+                error = %err,
+                "twitch chat disabled: no OAuth token in the vault"
+            ),
         }
     }
 
     if config.startup.enable_autostart {
-        if let Err(err) = crate::startup::set_autostart(&config.startup.app_name, true) {
+        if let Err(err) = crate::startup::set_autostart(&config.startup, true) {
             eprintln!("autostart setup failed: {err}");
         }
     }
 
-    let addr: SocketAddr = format!("127.0.0.1:{}", config.server.port).parse()?;
+    let addr = SocketAddr::new(config.server.bind.parse()?, config.server.port);
+    let _discovery = crate::discovery::announce(&config.discovery, addr);
 
     // Get or generate server token, storing in vault for persistence
     let token = if let Some(token) = config.server.token {
         token
     } else {
         // Try to retrieve existing token from vault
-        let vault_lock = vault.lock().unwrap();
+        let vault_lock = vault.read().await;
         match vault_lock.retrieve("server_token") {
             Ok(existing_token) => existing_token,
             Err(_) => {
                 // Generate new token and store in vault
                 drop(vault_lock); // Drop lock before re-acquiring
                 let new_token = generate_token(32);
-                let mut vault_lock = vault.lock().unwrap();
+                let mut vault_lock = vault.write().await;
                 if let Err(e) = vault_lock.store("server_token", &new_token) {
                     tracing::warn!("Failed to store server token in vault: {}", e);
                 }
-                new_token
+                new_token.into()
             }
         }
     };
 
     let dashboard_url = format!(
         "http://127.0.0.1:{}/obs?token={}",
-        config.server.port, token
+        config.server.port,
+        token.expose()
     );
     let settings_url = format!(
         "http://127.0.0.1:{}/settings?token={}",
-        config.server.port, token
+        config.server.port,
+        token.expose()
     );
 
     println!("OBS dashboard: {}", dashboard_url);
 
     let shutdown_flag = Arc::new(AtomicBool::new(false));
     let (shutdown_tx, mut shutdown_rx) = watch::channel(false);
-    let shutdown_rx_server = shutdown_rx.clone();
 
     if config.tray.enable {
-        let url = dashboard_url.clone();
-        let settings = settings_url.clone();
-        let flag = shutdown_flag.clone();
-        let tx = shutdown_tx.clone();
+        let tray = crate::tray::TrayContext {
+            dashboard_url: dashboard_url.clone(),
+            settings_url: settings_url.clone(),
+            shutdown_flag: shutdown_flag.clone(),
+            shutdown_tx: shutdown_tx.clone(),
+            telemetry: rx.clone(),
+            config: config_handle.clone(),
+            obs: obs.clone(),
+            vault: vault.clone(),
+            aegis_session_snapshot: aegis_session_snapshot.clone(),
+            ipc_overrides: ipc_overrides.clone(),
+            runtime: tokio::runtime::Handle::current(),
+        };
         std::thread::spawn(move || {
-            if let Err(err) = crate::tray::start_tray(url, settings, flag, tx) {
+            if let Err(err) = crate::tray::start_tray(tray) {
                 eprintln!("tray failed: {err}");
             }
         });
     }
 
-    tokio::select! {
+    let shutdown = Shutdown {
+        config: config_handle.clone(),
+        vault: vault.clone(),
+        aegis_session_snapshot: aegis_session_snapshot.clone(),
+        session_file,
+        ipc_cmd_tx: ipc_cmd_tx.clone(),
+        ipc_debug_status: ipc_debug_status.clone(),
+    };
+
+    let result = tokio::select! {
         res = crate::server::start(
             addr,
             token,
             rx,
-            shutdown_rx_server,
-            config.theme.clone(),
+            shutdown_tx.clone(),
             vault.clone(),
             grafana_configured,
             aegis_session_snapshot.clone(),
             ipc_cmd_tx,
             ipc_debug_status,
+            ipc_overrides,
+            obs,
+            obs_log,
+            ingest,
+            peers,
+            rules,
+            audit,
+            config.server.rate_limit.clone(),
+            config_handle,
         ) => res,
         _ = tokio::signal::ctrl_c() => {
             eprintln!("shutdown: ctrl-c");
-            metrics_task.abort();
             let _ = shutdown_tx.send(true);
             Ok(())
         }
         _ = shutdown_rx.changed() => {
-            eprintln!("shutdown: tray");
-            metrics_task.abort();
+            eprintln!("shutdown: requested");
             Ok(())
         }
+    };
+    metrics_task.abort();
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, shutdown.run())
+        .await
+        .is_err()
+    {
+        tracing::warn!(
+            timeout_secs = SHUTDOWN_TIMEOUT.as_secs(),
+            "shutdown sequence timed out"
+        );
     }
+    result
+}
+
+/// What the exit sequence needs, cloned out before the server takes its
+/// handles.
+struct Shutdown {
+    config: ConfigHandle,
+    vault: VaultHandle,
+    aegis_session_snapshot: RelaySessionHandle,
+    session_file: SessionFile,
+    ipc_cmd_tx: crate::ipc::CoreIpcCommandSender,
+    ipc_debug_status: crate::ipc::IpcDebugStatusHandle,
 }
 
+impl Shutdown {
+    /// Stops the relay when `aegis.stop_relay_on_exit` is on, sends the plugin
+    /// its `shutdown_request`, flushes the OTLP exporter and writes the final
+    /// relay session. Overrides and the activity log are saved as they change.
+    async fn run(self) {
+        let config = self.config.get();
+        if config.aegis.enabled && config.aegis.stop_relay_on_exit {
+            self.stop_relay(&config).await;
+        }
+        self.close_ipc().await;
+        crate::exporters::shutdown_otlp(OTLP_FLUSH_TIMEOUT).await;
+        let session = self.aegis_session_snapshot.read().await.clone();
+        if let Err(err) = self.session_file.save(session.as_ref()) {
+            tracing::warn!(error = %err, "failed to persist aegis relay session");
+        }
+        tracing::info!("shutdown complete");
+    }
+
+    async fn stop_relay(&self, config: &Config) {
+        let session_id = match self.aegis_session_snapshot.read().await.as_ref() {
+            Some(s) if matches!(s.status.as_str(), "provisioning" | "active" | "grace") => {
+                s.session_id.clone()
+            }
+            _ => return,
+        };
+        match relay_stop(
+            config,
+            &self.vault,
+            session_id.clone(),
+            "shutdown",
+            "shutdown",
+        )
+        .await
+        {
+            Ok(()) => {
+                tracing::info!(session_id = %session_id, "aegis relay stopped on exit");
+                *self.aegis_session_snapshot.write().await = None;
+            }
+            Err(err) => {
+                tracing::warn!(error = %err, session_id = %session_id, "aegis relay stop on exit failed")
+            }
+        }
+    }
+
+    /// Waits for the session to close after the farewell, so the plugin hears
+    /// why instead of seeing a broken pipe.
+    async fn close_ipc(&self) {
+        let connected = || self.ipc_debug_status.lock().unwrap().session_connected;
+        if !connected() {
+            return;
+        }
+        let _ = self.ipc_cmd_tx.send(CoreIpcCommand::Shutdown {
+            reason: "manual_exit".to_string(),
+        });
+        let deadline = tokio::time::Instant::now() + IPC_FAREWELL_TIMEOUT;
+        while connected() && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+}
+
+/// The collection loop's inputs, kept outside it so a restart after a panic
+/// starts over with a fresh hub and trend but the same channel.
+struct MetricsLoop {
+    obs: ObsLink,
+    network: NetworkConfig,
+    config: MetricsConfig,
+    ipc_status: crate::ipc::IpcDebugStatusHandle,
+    relay_telemetry: crate::relay::RelayTelemetryHandle,
+    has_sinks: bool,
+    tx: watch::Sender<TelemetryFrame>,
+}
+
+impl MetricsLoop {
+    async fn run(&self) {
+        let mut hub = MetricsHub::new(self.obs.clone(), &self.network);
+        let base_interval = Duration::from_millis(self.config.interval_ms);
+        let mut ticker = tokio::time::interval(base_interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        let mut last_collect: Option<std::time::Instant> = None;
+        let mut trend = crate::metrics::TrendAnalyzer::default();
+        loop {
+            ticker.tick().await;
+            if crate::pause::is_paused() {
+                continue;
+            }
+            // Ticks stay at the base rate so a newly attached consumer is picked up
+            // quickly; idle ticks are skipped until the longer interval has passed.
+            let has_consumers = self.has_sinks
+                || crate::selfstats::stats().ws_clients() > 0
+                || self.ipc_status.lock().unwrap().session_connected;
+            let due =
+                crate::metrics::collection_interval(&self.config, &self.tx.borrow(), has_consumers);
+            if last_collect.is_some_and(|at| at.elapsed() < due.saturating_sub(base_interval / 2)) {
+                continue;
+            }
+            let started = std::time::Instant::now();
+            last_collect = Some(started);
+            let collected = hub.collect().await;
+            crate::selfstats::stats().record_collect(started.elapsed());
+            if let Ok(mut frame) = collected {
+                frame.relay = self.relay_telemetry.lock().unwrap().clone();
+                frame.health_trend = trend.observe(&frame);
+                let _ = self.tx.send(frame);
+            }
+        }
+    }
+}
+
+/// A second `serve` points at the running instance instead of failing to bind
+/// its port, passing `--paused` along.
+async fn hand_off(
+    config: &Config,
+    vault: &Vault,
+    info: &InstanceInfo,
+    paused: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let token = local_api_token(config, Some(vault)).ok_or(format!(
+        "Telemy is already running (pid {}) but no server token is available",
+        info.pid
+    ))?;
+    if paused {
+        crate::instance::call(
+            info,
+            &token,
+            reqwest::Method::PUT,
+            "/api/v1/pause",
+            Some(serde_json::json!({ "paused": true })),
+        )
+        .await?;
+    }
+    let url = dashboard_url(info, &token);
+    println!("Telemy is already running (pid {}).", info.pid);
+    println!("OBS dashboard: {}", url);
+    #[cfg(windows)]
+    let _ = open_in_browser(&url);
+    Ok(())
+}
+
+fn dashboard_url(info: &InstanceInfo, token: &SecretString) -> String {
+    format!(
+        "http://127.0.0.1:{}/obs?token={}",
+        info.port,
+        token.expose()
+    )
+}
+
+fn open_in_browser(url: &str) -> std::io::Result<()> {
+    #[cfg(windows)]
+    let mut command = {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", "", url]);
+        command
+    };
+    #[cfg(not(windows))]
+    let mut command = {
+        let mut command = std::process::Command::new("xdg-open");
+        command.arg(url);
+        command
+    };
+    command.spawn().map(|_| ())
+}
+
+/// The running instance and a token for its local API, for the commands that
+/// only make sense against it.
+fn running_instance() -> Result<(InstanceInfo, SecretString), Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    let info = crate::instance::running().ok_or("Telemy is not running")?;
+    let vault = Vault::open(&config.vault).ok();
+    let token = local_api_token(&config, vault.as_ref())
+        .ok_or("no server token available for the running instance")?;
+    Ok((info, token))
+}
+
+async fn handle_status(json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let (info, token) = running_instance()?;
+    let status =
+        crate::instance::call(&info, &token, reqwest::Method::GET, "/api/v1/status", None).await?;
+    print_output(json, &status, || describe_status(&status, info.port))
+}
+
+fn describe_status(status: &serde_json::Value, port: u16) -> String {
+    let obs = &status["obs"];
+    let mut obs_line = obs["state"].as_str().unwrap_or("unknown").replace('_', " ");
+    for flag in ["streaming", "recording"] {
+        if obs[flag].as_bool().unwrap_or(false) {
+            obs_line.push_str(&format!(", {flag}"));
+        }
+    }
+    if let (Some(kbps), Some(drop)) = (obs["bitrate_kbps"].as_u64(), obs["drop_pct"].as_f64()) {
+        obs_line.push_str(&format!(", {kbps} kbps, {:.2}% dropped", drop * 100.0));
+    }
+    let aegis = match status["aegis"].as_object() {
+        Some(session) => format!(
+            "relay {} {}{}",
+            session["session_id"].as_str().unwrap_or_default(),
+            session["status"].as_str().unwrap_or_default(),
+            session["region"]
+                .as_str()
+                .map(|r| format!(" ({r})"))
+                .unwrap_or_default()
+        ),
+        None => "no relay session".to_string(),
+    };
+    format!(
+        "Telemy {} running (pid {}, port {}, up {}s){}\nHealth: {:.1}%\nOBS: {}\nAegis: {}",
+        status["version"].as_str().unwrap_or_default(),
+        status["pid"],
+        port,
+        status["uptime_secs"],
+        if status["paused"].as_bool().unwrap_or(false) {
+            " [paused]"
+        } else {
+            ""
+        },
+        status["health"].as_f64().unwrap_or(0.0) * 100.0,
+        obs_line,
+        aegis
+    )
+}
+
+fn handle_open(json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let (info, token) = running_instance()?;
+    let url = dashboard_url(&info, &token);
+    let opened = open_in_browser(&url);
+    print_output(
+        json,
+        &serde_json::json!({ "url": url, "opened": opened.is_ok() }),
+        || match &opened {
+            Ok(()) => format!("Opened {url}"),
+            Err(err) => format!("Could not start a browser ({err}); open {url}"),
+        },
+    )
+}
+
+/// How long `telemy stop` waits for the instance to release its lock.
+const STOP_WAIT: Duration = Duration::from_secs(10);
+
+async fn handle_stop(json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let (info, token) = running_instance()?;
+    crate::instance::call(
+        &info,
+        &token,
+        reqwest::Method::POST,
+        "/api/v1/shutdown",
+        None,
+    )
+    .await?;
+    let deadline = std::time::Instant::now() + STOP_WAIT;
+    while crate::instance::running().is_some() {
+        if std::time::Instant::now() >= deadline {
+            return Err(format!(
+                "Telemy (pid {}) is still running after {}s",
+                info.pid,
+                STOP_WAIT.as_secs()
+            )
+            .into());
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    print_output(
+        json,
+        &serde_json::json!({ "stopped": true, "pid": info.pid }),
+        || format!("Telemy (pid {}) stopped.", info.pid),
+    )
+}
+
+/// The token for this machine's own dashboard API: the configured one, else the
+/// one `serve` generated into the vault.
+fn local_api_token(config: &Config, vault: Option<&Vault>) -> Option<SecretString> {
+    config
+        .server
+        .token
+        .clone()
+        .or_else(|| vault.and_then(|v| v.retrieve("server_token").ok()))
+}
+
+/// Seeds the session snapshot from the control plane, falling back to the
+/// session persisted by the last run so a relay that outlived a crash is not
+/// forgotten while the control plane is unreachable.
 async fn run_aegis_startup_probe(
     config: &Config,
-    vault: Arc<Mutex<Vault>>,
-    snapshot: Arc<Mutex<Option<RelaySession>>>,
+    vault: VaultHandle,
+    snapshot: RelaySessionHandle,
+    session_file: &SessionFile,
 ) {
     if !config.aegis.enabled {
         return;
     }
-
     let client = {
-        let guard = vault.lock().unwrap();
-        match build_aegis_client(config, &guard) {
-            Ok(client) => client,
-            Err(err) => {
-                tracing::warn!(error = %err, "aegis startup probe disabled: invalid config or credentials");
-                return;
-            }
+        let guard = vault.read().await;
+        build_aegis_client(config, &guard)
+    };
+    let probed = match client {
+        Ok(client) => client.relay_active().await.map_err(|err| {
+            tracing::warn!(error = %err, "aegis startup probe failed");
+        }),
+        Err(err) => {
+            tracing::warn!(error = %err, "aegis startup probe disabled: invalid config or credentials");
+            Err(())
         }
     };
 
-    match client.relay_active().await {
+    match probed {
         Ok(Some(session)) => {
             tracing::info!(
                 session_id = %session.session_id,
@@ -249,114 +780,955 @@ async fn run_aegis_startup_probe(
                 region = ?session.region,
                 "aegis startup probe: active/provisioning session found"
             );
-            *snapshot.lock().unwrap() = Some(session);
+            *snapshot.write().await = Some(session);
+            SessionPoller::shared().record(Ok(()), std::time::Instant::now());
         }
         Ok(None) => {
             tracing::info!("aegis startup probe: no active relay session");
+            SessionPoller::shared().record(Ok(()), std::time::Instant::now());
         }
-        Err(err) => {
-            tracing::warn!(error = %err, "aegis startup probe failed");
+        Err(()) => {
+            if let Some(session) = session_file.load() {
+                tracing::warn!(
+                    session_id = %session.session_id,
+                    status = %session.status,
+                    region = ?session.region,
+                    "aegis startup probe: resuming persisted relay session until the control plane answers"
+                );
+                *snapshot.write().await = Some(session);
+            }
         }
     }
 }
 
-fn handle_vault_set(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
-    let mut args = std::env::args().skip(2);
-    let key = args.next().ok_or("missing key")?;
-    let value = args.next().ok_or("missing value")?;
+/// Mirrors the live session snapshot to `session_file` whenever it changes.
+fn spawn_aegis_session_persistence(session_file: SessionFile, snapshot: RelaySessionHandle) {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(Duration::from_secs(2));
+        loop {
+            tick.tick().await;
+            let session = snapshot.read().await.clone();
+            if let Err(err) = session_file.save(session.as_ref()) {
+                tracing::warn!(error = %err, "failed to persist aegis relay session");
+            }
+        }
+    });
+}
 
-    let mut vault = Vault::new(config.vault.path.as_deref())?;
-    vault.store(&key, &value)?;
+/// Keeps the session snapshot current by polling `relay/active` every
+/// `aegis.poll_interval_secs`, or sooner when a handler asks through
+/// [`SessionPoller::refresh_now`]. Config is read from the shared handle each
+/// round, so enabling Aegis or rotating its credentials takes effect without a
+/// restart.
+fn spawn_aegis_session_poller(
+    config: ConfigHandle,
+    vault: VaultHandle,
+    snapshot: RelaySessionHandle,
+) {
+    let poller = SessionPoller::shared();
+    poller.set_interval(Duration::from_secs(
+        config.read(|c| c.aegis.poll_interval_secs),
+    ));
+    tokio::spawn(async move {
+        loop {
+            poller.wait_next().await;
+            let config = config.get();
+            poller.set_interval(Duration::from_secs(config.aegis.poll_interval_secs));
+            if !config.aegis.enabled {
+                continue;
+            }
+            let result = poll_aegis_session(&config, &vault, &snapshot).await;
+            if let Err(err) = &result {
+                tracing::debug!(error = %err, "aegis session poll failed");
+            }
+            poller.record(result, std::time::Instant::now());
+        }
+    });
+}
 
-    println!("Stored vault key: {}", key);
+async fn poll_aegis_session(
+    config: &Config,
+    vault: &VaultHandle,
+    snapshot: &RelaySessionHandle,
+) -> Result<(), String> {
+    let client = {
+        let guard = vault.read().await;
+        build_aegis_client(config, &guard).map_err(|err| err.to_string())?
+    };
+    let session = client.relay_active().await.map_err(|err| err.to_string())?;
+    let mut current = snapshot.write().await;
+    let key = |s: &RelaySession| (s.session_id.clone(), s.status.clone());
+    if current.as_ref().map(key) != session.as_ref().map(key) {
+        tracing::info!(
+            session_id = ?session.as_ref().map(|s| &s.session_id),
+            status = ?session.as_ref().map(|s| &s.status),
+            "aegis relay session changed"
+        );
+    }
+    *current = session;
     Ok(())
 }
 
-fn handle_vault_get(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
-    let mut args = std::env::args().skip(2);
-    let key = args.next().ok_or("missing key")?;
+/// Starts the relay when OBS goes live and stops it once streaming has been
+/// idle for `aegis.auto_relay_stop_grace_secs`, unless the manual override is
+/// on.
+fn spawn_aegis_auto_relay(
+    config: Config,
+    vault: VaultHandle,
+    snapshot: RelaySessionHandle,
+    rx: watch::Receiver<TelemetryFrame>,
+    ipc_overrides: crate::ipc::OverrideStoreHandle,
+) {
+    tokio::spawn(async move {
+        let stop_grace = Duration::from_secs(config.aegis.auto_relay_stop_grace_secs);
+        let mut ticker = tokio::time::interval(Duration::from_secs(1));
+        let mut backoff = Duration::from_secs(2);
+        let mut next_attempt_at = tokio::time::Instant::now();
+        // Set once we have seen OBS streaming, so a relay started by hand while
+        // idle is never stopped by automation.
+        let mut armed = false;
+        let mut idle_since: Option<tokio::time::Instant> = None;
 
-    let vault = Vault::new(config.vault.path.as_deref())?;
-    let value = vault.retrieve(&key)?;
+        loop {
+            ticker.tick().await;
+            let now = tokio::time::Instant::now();
 
-    println!("{}", value);
-    Ok(())
+            // Manual override: whatever the relay is doing stays as is, and
+            // the stop grace starts over once automation resumes.
+            if ipc_overrides.manual_override() {
+                idle_since = None;
+                continue;
+            }
+            let streaming = rx.borrow().obs.streaming;
+            let irl_selected = ipc_overrides.is_irl();
+            let want_relay = streaming && (irl_selected || !config.aegis.auto_relay_irl_only);
+            let session_live = snapshot.read().await.as_ref().is_some_and(|s| {
+                matches!(s.status.as_str(), "provisioning" | "active" | "grace")
+            });
+
+            if want_relay {
+                armed = true;
+                idle_since = None;
+                if session_live || now < next_attempt_at {
+                    continue;
+                }
+                let obs_connected = rx.borrow().obs.connected;
+                match relay_start(
+                    &config,
+                    &vault,
+                    "auto",
+                    ipc_overrides.get().mode,
+                    obs_connected,
+                )
+                .await
+                {
+                    Ok(session) => {
+                        tracing::info!(
+                            session_id = %session.session_id,
+                            status = %session.status,
+                            "aegis auto relay started"
+                        );
+                        *snapshot.write().await = Some(session);
+                        backoff = Duration::from_secs(2);
+                    }
+                    Err(err) => {
+                        tracing::warn!(error = %err, retry_in_secs = backoff.as_secs(), "aegis auto relay start failed");
+                        next_attempt_at = now + backoff;
+                        backoff = (backoff * 2).min(Duration::from_secs(60));
+                    }
+                }
+                continue;
+            }
+
+            if !armed || streaming {
+                idle_since = None;
+                continue;
+            }
+            if !session_live {
+                armed = false;
+                idle_since = None;
+                continue;
+            }
+            let since = *idle_since.get_or_insert(now);
+            if now.duration_since(since) < stop_grace || now < next_attempt_at {
+                continue;
+            }
+
+            let session_id = snapshot
+                .read()
+                .await
+                .as_ref()
+                .map(|s| s.session_id.clone())
+                .unwrap_or_default();
+            match relay_stop(
+                &config,
+                &vault,
+                session_id.clone(),
+                "auto",
+                "user_requested",
+            )
+            .await
+            {
+                Ok(()) => {
+                    tracing::info!(session_id = %session_id, "aegis auto relay stopped after stream end");
+                    *snapshot.write().await = None;
+                    armed = false;
+                    idle_since = None;
+                    backoff = Duration::from_secs(2);
+                }
+                Err(err) => {
+                    tracing::warn!(error = %err, retry_in_secs = backoff.as_secs(), "aegis auto relay stop failed");
+                    next_attempt_at = now + backoff;
+                    backoff = (backoff * 2).min(Duration::from_secs(60));
+                }
+            }
+        }
+    });
 }
 
-fn handle_vault_list(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
-    let vault = Vault::new(config.vault.path.as_deref())?;
-    for key in vault.list_keys() {
-        println!("{}", key);
-    }
-    Ok(())
+/// Counts down the grace window of the current session, reminds the user over
+/// IPC, and applies `aegis.grace_action`.
+fn spawn_aegis_grace_watch(
+    config: Config,
+    vault: VaultHandle,
+    snapshot: RelaySessionHandle,
+    rx: watch::Receiver<TelemetryFrame>,
+    ipc_cmd_tx: crate::ipc::CoreIpcCommandSender,
+    ipc_overrides: crate::ipc::OverrideStoreHandle,
+) {
+    tokio::spawn(async move {
+        let remind_every = Duration::from_secs(config.aegis.grace_notice_interval_secs);
+        let mut ticker = tokio::time::interval(Duration::from_secs(1));
+        let mut countdown = GraceCountdown::default();
+        loop {
+            ticker.tick().await;
+            let (tick, session_id) = {
+                let mut guard = snapshot.write().await;
+                let tick = countdown.tick(guard.as_mut(), std::time::Instant::now(), remind_every);
+                let session_id = guard.as_ref().map(|s| s.session_id.clone());
+                (tick, session_id.unwrap_or_default())
+            };
+            let notice = |level, message: String| {
+                let _ = ipc_cmd_tx.send(CoreIpcCommand::UserNotice { level, message });
+            };
+            match tick {
+                GraceTick::Idle | GraceTick::Counting => {}
+                GraceTick::Entered { remaining } => {
+                    tracing::info!(session_id = %session_id, remaining_secs = remaining.as_secs(), action = ?config.aegis.grace_action, "aegis session entered grace");
+                    if config.aegis.grace_action == GraceAction::Stop {
+                        match relay_stop(
+                            &config,
+                            &vault,
+                            session_id.clone(),
+                            "grace",
+                            "user_requested",
+                        )
+                        .await
+                        {
+                            Ok(()) => {
+                                *snapshot.write().await = None;
+                                notice(
+                                    UserNoticeLevel::Info,
+                                    "Relay entered its grace window and was released".to_string(),
+                                );
+                            }
+                            Err(err) => {
+                                tracing::warn!(error = %err, session_id = %session_id, "aegis grace stop failed");
+                                notice(
+                                    UserNoticeLevel::Warn,
+                                    format!("Relay in grace; stopping it failed: {err}"),
+                                );
+                            }
+                        }
+                        continue;
+                    }
+                    notice(UserNoticeLevel::Warn, grace_reminder(remaining));
+                }
+                GraceTick::Reminder { remaining } => {
+                    notice(UserNoticeLevel::Warn, grace_reminder(remaining))
+                }
+                GraceTick::Expired => {
+                    let streaming = rx.borrow().obs.streaming;
+                    if config.aegis.grace_action != GraceAction::Extend || !streaming {
+                        notice(UserNoticeLevel::Warn, "Relay grace window ended".into());
+                        continue;
+                    }
+                    // The control plane has no extend call, so a fresh session
+                    // takes over once the old one is reclaimed.
+                    let obs_connected = rx.borrow().obs.connected;
+                    match relay_start(
+                        &config,
+                        &vault,
+                        "grace",
+                        ipc_overrides.get().mode,
+                        obs_connected,
+                    )
+                    .await
+                    {
+                        Ok(session) => {
+                            tracing::info!(old_session_id = %session_id, session_id = %session.session_id, "aegis grace window ended; started a new session");
+                            *snapshot.write().await = Some(session);
+                            notice(
+                                UserNoticeLevel::Info,
+                                "Relay grace window ended; started a new relay session".to_string(),
+                            );
+                        }
+                        Err(err) => {
+                            tracing::warn!(error = %err, "aegis grace extend failed");
+                            notice(
+                                UserNoticeLevel::Error,
+                                format!("Relay grace window ended; starting a new session failed: {err}"),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    });
 }
 
-fn handle_config_init() -> Result<(), Box<dyn std::error::Error>> {
-    let path = Config::default_path();
-    Config::write_default(&path)?;
-    println!("Wrote default config to {}", path.display());
-    Ok(())
+fn grace_reminder(remaining: Duration) -> String {
+    format!(
+        "Relay in grace window: {} left",
+        format_remaining(remaining)
+    )
 }
 
-fn handle_autostart(enable: bool, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
-    crate::startup::set_autostart(&config.startup.app_name, enable)?;
-    println!(
-        "autostart {} for {}",
-        if enable { "enabled" } else { "disabled" },
-        config.startup.app_name
+/// Starts a relay session on behalf of automation; `requested_by` is reported
+/// to Aegis so it can tell auto relay and rule-triggered starts apart, and is
+/// the source in the activity log. `mode` is the dock's selected mode, if any,
+/// and `obs_connected` whether the caller sees OBS connected.
+pub(crate) async fn relay_start(
+    config: &Config,
+    vault: &VaultHandle,
+    requested_by: &str,
+    mode: Option<SnapshotMode>,
+    obs_connected: bool,
+) -> Result<RelaySession, String> {
+    let result = request_relay_start(config, vault, requested_by, mode, obs_connected).await;
+    crate::activity::log().record(
+        ActionKind::RelayStart,
+        requested_by,
+        None,
+        result
+            .as_ref()
+            .map(|s| format!("relay session {} {}", s.session_id, s.status))
+            .map_err(Clone::clone),
     );
-    Ok(())
+    result
 }
 
-async fn handle_aegis_relay_active(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
-    let vault = Vault::new(config.vault.path.as_deref())?;
-    let client = build_aegis_client(config, &vault)?;
-    let session = client.relay_active().await?;
-    println!("{}", serde_json::to_string_pretty(&session)?);
-    Ok(())
+async fn request_relay_start(
+    config: &Config,
+    vault: &VaultHandle,
+    requested_by: &str,
+    mode: Option<SnapshotMode>,
+    obs_connected: bool,
+) -> Result<RelaySession, String> {
+    let client = {
+        let guard = vault.read().await;
+        build_aegis_client(config, &guard).map_err(|err| err.to_string())?
+    };
+    let request = RelayStartRequest {
+        region_preference: resolve_region_preference(&client, Some("auto".to_string())).await,
+        client_context: Some(RelayStartClientContext {
+            obs_connected: Some(obs_connected),
+            mode: mode.map(|m| m.as_str().to_string()),
+            requested_by: Some(requested_by.to_string()),
+        }),
+    };
+    client
+        .relay_start(&generate_idempotency_key(), &request)
+        .await
+        .map_err(|err| err.to_string())
 }
 
-async fn handle_aegis_relay_start(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
-    let mut args = std::env::args().skip(2);
-    let region_preference = args.next();
+/// Stops `session_id`, logging the attempt under `requested_by`. `reason` is
+/// one of the control plane's stop reasons (`user_requested`, `shutdown`, ...).
+pub(crate) async fn relay_stop(
+    config: &Config,
+    vault: &VaultHandle,
+    session_id: String,
+    requested_by: &str,
+    reason: &str,
+) -> Result<(), String> {
+    let detail = format!("relay session {session_id} stopped");
+    let result = request_relay_stop(config, vault, session_id, reason).await;
+    crate::activity::log().record(
+        ActionKind::RelayStop,
+        requested_by,
+        None,
+        result.clone().map(|()| detail),
+    );
+    result
+}
 
-    let vault = Vault::new(config.vault.path.as_deref())?;
-    let client = build_aegis_client(config, &vault)?;
+async fn request_relay_stop(
+    config: &Config,
+    vault: &VaultHandle,
+    session_id: String,
+    reason: &str,
+) -> Result<(), String> {
+    let client = {
+        let guard = vault.read().await;
+        build_aegis_client(config, &guard).map_err(|err| err.to_string())?
+    };
+    client
+        .relay_stop(&RelayStopRequest {
+            session_id,
+            reason: reason.to_string(),
+        })
+        .await
+        .map(|_| ())
+        .map_err(|err| err.to_string())
+}
 
-    let request = RelayStartRequest {
-        region_preference,
-        client_context: Some(RelayStartClientContext {
-            obs_connected: None,
-            mode: Some("studio".to_string()),
-            requested_by: Some("cli".to_string()),
+fn handle_vault(command: VaultCommand, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    let mut vault = Vault::open(&config.vault)?;
+
+    match command {
+        VaultCommand::Set { key, value } => {
+            vault.store(&key, &value)?;
+            print_output(json, &serde_json::json!({ "key": key, "stored": true }), || {
+                format!("Stored vault key: {}", key)
+            })
+        }
+        VaultCommand::Get { key } => {
+            let value = vault.retrieve(&key)?;
+            print_output(json, &serde_json::json!({ "key": key, "value": value.expose() }), || {
+                value.expose().to_string()
+            })
+        }
+        VaultCommand::List => {
+            let keys = vault.list_keys();
+            print_output(json, &keys, || keys.join("\n"))
+        }
+        VaultCommand::Delete { key } => {
+            if !vault.delete(&key)? {
+                return Err(format!("vault key not found: {}", key).into());
+            }
+            print_output(json, &serde_json::json!({ "key": key, "deleted": true }), || {
+                format!("Deleted vault key: {}", key)
+            })
+        }
+        VaultCommand::Rotate => {
+            let (rotated, failed) = vault.rotate()?;
+            print_output(
+                json,
+                &serde_json::json!({ "rotated": rotated, "failed": failed }),
+                || {
+                    let mut out = format!("Re-encrypted {rotated} vault entries");
+                    if !failed.is_empty() {
+                        out.push_str(&format!(
+                            "\nCould not decrypt (set these again): {}",
+                            failed.join(", ")
+                        ));
+                    }
+                    out
+                },
+            )
+        }
+        VaultCommand::Export { path, force } => {
+            if path.exists() && !force {
+                return Err(format!(
+                    "{} already exists; pass --force to replace it",
+                    path.display()
+                )
+                .into());
+            }
+            let password = backup_password(true)?;
+            std::fs::write(&path, vault.export_backup(&password)?)?;
+            let keys = vault.list_keys();
+            print_output(
+                json,
+                &serde_json::json!({ "path": path, "keys": keys }),
+                || format!("Exported {} vault keys to {}", keys.len(), path.display()),
+            )
+        }
+        VaultCommand::Import { path, overwrite } => {
+            let raw = std::fs::read_to_string(&path)?;
+            let password = backup_password(false)?;
+            let (imported, skipped) = vault.import_backup(&raw, &password, overwrite)?;
+            print_output(
+                json,
+                &serde_json::json!({ "imported": imported, "skipped": skipped }),
+                || {
+                    let mut out = format!("Imported {} vault keys", imported.len());
+                    if !skipped.is_empty() {
+                        out.push_str(&format!(
+                            "\nKept existing (use --overwrite to replace): {}",
+                            skipped.join(", ")
+                        ));
+                    }
+                    out
+                },
+            )
+        }
+    }
+}
+
+/// `TELEMY_VAULT_PASSWORD` for scripted use, otherwise an interactive prompt.
+fn backup_password(confirm: bool) -> Result<String, Box<dyn std::error::Error>> {
+    if let Ok(password) = std::env::var("TELEMY_VAULT_PASSWORD") {
+        return Ok(password);
+    }
+    let password = rpassword::prompt_password("Backup password: ")?;
+    if confirm && rpassword::prompt_password("Repeat password: ")? != password {
+        return Err("passwords do not match".into());
+    }
+    Ok(password)
+}
+
+fn handle_config(command: ConfigCommand, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        ConfigCommand::Init => {
+            let path = Config::default_path();
+            Config::write_default(&path)?;
+            print_output(json, &serde_json::json!({ "path": path }), || {
+                format!("Wrote default config to {}", path.display())
+            })
+        }
+        ConfigCommand::Path => {
+            let path = Config::active_path();
+            let profile = crate::config::active_profile();
+            print_output(
+                json,
+                &serde_json::json!({ "path": path, "exists": path.exists(), "profile": profile }),
+                || path.display().to_string(),
+            )
+        }
+        ConfigCommand::Show => {
+            let config = diagnostics::redact(&Config::load()?);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&config)?);
+            } else {
+                print!("{}", toml::to_string_pretty(&config)?);
+            }
+            Ok(())
+        }
+        ConfigCommand::Get { key } => {
+            let value = Config::load()?
+                .get_key(&key)?
+                .ok_or_else(|| format!("config key not set: {key}"))?;
+            print_output(
+                json,
+                &serde_json::json!({ "key": key, "value": value }),
+                || display_toml_value(&value),
+            )
+        }
+        ConfigCommand::Set { key, value } => {
+            let path = Config::active_path();
+            let value = Config::set_key_in_file(&path, &key, &value)?;
+            print_output(
+                json,
+                &serde_json::json!({ "key": key, "value": value, "path": path }),
+                || format!("Set {key} = {value} in {}", path.display()),
+            )
+        }
+        ConfigCommand::Validate => {
+            let path = Config::active_path();
+            if !path.exists() {
+                return print_output(
+                    json,
+                    &serde_json::json!({ "path": path, "exists": false, "valid": true }),
+                    || {
+                        format!(
+                            "No config file at {}; defaults are in effect",
+                            path.display()
+                        )
+                    },
+                );
+            }
+            Config::validate_file(&path)?;
+            print_output(
+                json,
+                &serde_json::json!({ "path": path, "exists": true, "valid": true }),
+                || format!("{} is valid", path.display()),
+            )
+        }
+        ConfigCommand::Diff => {
+            let changes = diagnostics::redact(&Config::load()?).diff_from_defaults()?;
+            print_output(json, &changes, || {
+                if changes.is_empty() {
+                    return "No settings differ from the defaults".to_string();
+                }
+                changes
+                    .iter()
+                    .map(|change| {
+                        let show = |v: &Option<toml::Value>| {
+                            v.as_ref().map_or("(unset)".to_string(), display_toml_value)
+                        };
+                        format!(
+                            "{}: {} -> {}",
+                            change.key,
+                            show(&change.default),
+                            show(&change.current)
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+        }
+        ConfigCommand::Templates { force } => {
+            let dir = crate::server::templates_dir(&Config::load()?.dashboard);
+            let written = crate::server::export_templates(&dir, force)?;
+            print_output(
+                json,
+                &serde_json::json!({ "dir": dir, "written": written }),
+                || {
+                    if written.is_empty() {
+                        format!(
+                            "Templates already exist in {}; use --force to replace them",
+                            dir.display()
+                        )
+                    } else {
+                        format!("Wrote {} templates to {}", written.len(), dir.display())
+                    }
+                },
+            )
+        }
+    }
+}
+
+/// Tables print as TOML; scalars print bare so they can be used in scripts.
+fn display_toml_value(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        toml::Value::Table(table) => toml::to_string_pretty(table)
+            .unwrap_or_default()
+            .trim_end()
+            .to_string(),
+        other => other.to_string(),
+    }
+}
+
+async fn handle_diagnostics(
+    command: DiagnosticsCommand,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        DiagnosticsCommand::Export { out, frames } => {
+            let config = Config::load()?;
+            let vault = Vault::open(&config.vault).ok();
+            let token = local_api_token(&config, vault.as_ref());
+            let (selfstats, selfstats_error) = match token {
+                Some(token) => {
+                    match diagnostics::fetch_selfstats(config.server.port, token.expose()).await {
+                        Ok(stats) => (Some(stats), None),
+                        Err(err) => (None, Some(err)),
+                    }
+                }
+                None => (None, Some("no server token configured".to_string())),
+            };
+            let bundle = diagnostics::Bundle {
+                config: diagnostics::redacted_config(&config)?,
+                selfstats,
+                selfstats_error,
+                frames: crate::recorder::recent_frames(
+                    &crate::recorder::recorder_dir(&config.recorder),
+                    frames,
+                )
+                .unwrap_or_default(),
+                logs: diagnostics::recent_logs(&crate::obslog::log_dir(&config.obs), "txt")
+                    .unwrap_or_default()
+                    .into_iter()
+                    .chain(
+                        diagnostics::recent_logs(&crate::logging::log_dir(&config.logging), "log")
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|(name, tail)| (format!("telemy/{name}"), tail)),
+                    )
+                    .collect(),
+                vault_keys: vault.map(|v| v.list_keys()).unwrap_or_default(),
+            };
+            let path = out.unwrap_or_else(|| {
+                let stamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                PathBuf::from(format!("telemy-diagnostics-{stamp}.zip"))
+            });
+            let manifest = bundle.write(&path)?;
+            print_output(
+                json,
+                &serde_json::json!({ "path": path, "manifest": manifest }),
+                || {
+                    let mut lines = vec![format!("Wrote {}", path.display())];
+                    lines.push(format!(
+                        "  {} frames, {} OBS logs",
+                        manifest.frames,
+                        manifest.logs.len()
+                    ));
+                    if let Some(err) = &manifest.selfstats_error {
+                        lines.push(format!("  selfstats skipped: {err}"));
+                    }
+                    lines.join("\n")
+                },
+            )
+        }
+        DiagnosticsCommand::Import { bundle, force } => {
+            let (manifest, config) = diagnostics::read_bundle(&bundle)?;
+            let path = Config::active_path();
+            if path.exists() && !force {
+                return Err(format!(
+                    "config file exists at {}; pass --force to replace it",
+                    path.display()
+                )
+                .into());
+            }
+            config.save()?;
+            print_output(
+                json,
+                &serde_json::json!({ "path": path, "vault_keys": manifest.vault_keys }),
+                || {
+                    let mut lines = vec![format!(
+                        "Restored config from {} to {}",
+                        bundle.display(),
+                        path.display()
+                    )];
+                    if !manifest.vault_keys.is_empty() {
+                        lines.push(format!(
+                            "Secrets are not in bundles; re-enter with `telemy vault set`: {}",
+                            manifest.vault_keys.join(", ")
+                        ));
+                    }
+                    lines.join("\n")
+                },
+            )
+        }
+    }
+}
+
+fn handle_autostart(enable: bool, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    crate::startup::set_autostart(&config.startup, enable)?;
+    print_output(
+        json,
+        &serde_json::json!({
+            "app_name": config.startup.app_name,
+            "enabled": enable,
+            "method": config.startup.method,
         }),
+        || {
+            format!(
+                "autostart {} for {} ({:?})",
+                if enable { "enabled" } else { "disabled" },
+                config.startup.app_name,
+                config.startup.method
+            )
+        },
+    )
+}
+
+async fn handle_aegis_relay(
+    command: RelayCommand,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    let vault = Vault::open(&config.vault)?;
+
+    // Through the running core, so its dashboard and session state stay in step.
+    let forward = match &command {
+        RelayCommand::Start { region } => Some((
+            "/api/v1/aegis/start",
+            serde_json::json!({ "region": region, "requested_by": "cli" }),
+        )),
+        RelayCommand::Stop { session_id, reason } => Some((
+            "/api/v1/aegis/stop",
+            serde_json::json!({ "session_id": session_id, "reason": reason }),
+        )),
+        RelayCommand::Status => None,
     };
-    let idempotency_key = generate_idempotency_key();
-    let session = client.relay_start(&idempotency_key, &request).await?;
+    if let (Some((path, body)), Some(info)) = (forward, crate::instance::running()) {
+        let token = local_api_token(&config, Some(&vault))
+            .ok_or("no server token available for the running instance")?;
+        let response =
+            crate::instance::call(&info, &token, reqwest::Method::POST, path, Some(body)).await?;
+        let session: Option<RelaySession> =
+            serde_json::from_value(response["session"].clone()).unwrap_or(None);
+        return print_output(json, &response, || match &session {
+            Some(session) => describe_session(session),
+            None => response["message"].as_str().unwrap_or_default().to_string(),
+        });
+    }
 
-    tracing::info!(idempotency_key = %idempotency_key, session_id = %session.session_id, status = %session.status, "aegis relay start completed");
-    println!("{}", serde_json::to_string_pretty(&session)?);
-    Ok(())
+    let client = build_aegis_client(&config, &vault)?;
+    match command {
+        RelayCommand::Status => {
+            let session = client.relay_active().await?;
+            print_output(json, &session, || match &session {
+                Some(s) => describe_session(s),
+                None => "no active relay session".to_string(),
+            })
+        }
+        RelayCommand::Start { region } => {
+            let request = RelayStartRequest {
+                region_preference: resolve_region_preference(&client, region).await,
+                client_context: Some(RelayStartClientContext {
+                    obs_connected: None,
+                    mode: Some("studio".to_string()),
+                    requested_by: Some("cli".to_string()),
+                }),
+            };
+            let idempotency_key = generate_idempotency_key();
+            let session = client.relay_start(&idempotency_key, &request).await?;
+
+            tracing::info!(idempotency_key = %idempotency_key, session_id = %session.session_id, status = %session.status, "aegis relay start completed");
+            print_output(json, &session, || describe_session(&session))
+        }
+        RelayCommand::Stop { session_id, reason } => {
+            let session_id = match session_id {
+                Some(id) => id,
+                None => client
+                    .relay_active()
+                    .await?
+                    .map(|s| s.session_id)
+                    .ok_or("no active relay session to stop")?,
+            };
+            let response = client
+                .relay_stop(&RelayStopRequest { session_id, reason })
+                .await?;
+            print_output(json, &response, || {
+                format!("{} {}", response.session_id, response.status)
+            })
+        }
+    }
 }
 
-async fn handle_aegis_relay_stop(config: &Config) -> Result<(), Box<dyn std::error::Error>> {
-    let mut args = std::env::args().skip(2);
-    let session_id = args
-        .next()
-        .ok_or("missing session_id (usage: aegis-relay-stop <session_id> [reason])")?;
-    let reason = args.next().unwrap_or_else(|| "user_requested".to_string());
+fn describe_session(session: &RelaySession) -> String {
+    let mut out = format!("{} {}", session.session_id, session.status);
+    if let Some(region) = &session.region {
+        out.push_str(&format!(" region={}", region));
+    }
+    if let Some(ip) = session.relay.as_ref().and_then(|r| r.public_ip.as_deref()) {
+        out.push_str(&format!(" ip={}", ip));
+    }
+    out
+}
 
-    let vault = Vault::new(config.vault.path.as_deref())?;
-    let client = build_aegis_client(config, &vault)?;
-    let response = client
-        .relay_stop(&RelayStopRequest { session_id, reason })
-        .await?;
+async fn handle_aegis_login(
+    base_url: Option<String>,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = Config::load()?;
+    let base_url = base_url
+        .or_else(|| config.aegis.base_url.clone())
+        .filter(|url| !url.trim().is_empty())
+        .ok_or("missing aegis.base_url in config (or pass --base-url)")?;
+    let login = DeviceLogin::new(&base_url)?;
+    let auth = login.start().await?;
+    // On stderr so `--json` output stays a single document on stdout.
+    eprintln!(
+        "To sign in, open {} and enter code {}",
+        auth.verification_uri_complete
+            .as_deref()
+            .unwrap_or(&auth.verification_uri),
+        auth.user_code
+    );
+    eprintln!("Waiting for approval...");
+    let tokens = login.wait_for_tokens(&auth).await?;
+
+    let mut vault = Vault::open(&config.vault)?;
+    let key = store_aegis_tokens(&mut config, &mut vault, &base_url, &tokens)?;
+    print_output(
+        json,
+        &serde_json::json!({ "signed_in": true, "access_jwt_key": key }),
+        || format!("Signed in to Aegis; access token stored in vault key {key}"),
+    )
+}
+
+/// Stores device-login tokens in the vault, under `aegis.access_jwt_key` (the
+/// refresh token next to it with a `_refresh` suffix), and fills in the Aegis
+/// config keys that are still unset so the next start uses them.
+pub(crate) fn store_aegis_tokens(
+    config: &mut Config,
+    vault: &mut Vault,
+    base_url: &str,
+    tokens: &DeviceTokens,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let configured_key = config
+        .aegis
+        .access_jwt_key
+        .clone()
+        .filter(|key| !key.trim().is_empty());
+    let key = configured_key
+        .clone()
+        .unwrap_or_else(|| DEFAULT_AEGIS_JWT_KEY.to_string());
+    vault.store(&key, tokens.access_token.expose())?;
+    if let Some(refresh_token) = &tokens.refresh_token {
+        vault.store(&format!("{key}_refresh"), refresh_token.expose())?;
+    }
+    let missing_base_url = config
+        .aegis
+        .base_url
+        .as_deref()
+        .is_none_or(|url| url.trim().is_empty());
+    if configured_key.is_none() || missing_base_url {
+        config.aegis.access_jwt_key = Some(key.clone());
+        if missing_base_url {
+            config.aegis.base_url = Some(base_url.trim().to_string());
+        }
+        config.save()?;
+    }
+    Ok(key)
+}
+
+/// One frame from a throwaway [`MetricsHub`]; the first sample only primes the
+/// CPU and network counters.
+async fn handle_collect(
+    obs_timeout: u64,
+    sample_ms: u64,
+    json: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load()?;
+    let vault = Vault::open(&config.vault)?;
+    let obs = ObsLink::spawn(obs_link_settings(&config, &vault));
+    let deadline = std::time::Instant::now() + Duration::from_secs(obs_timeout);
+    loop {
+        let (state, _) = obs.status();
+        if matches!(
+            state,
+            ObsConnectionState::Connected
+                | ObsConnectionState::AuthFailed
+                | ObsConnectionState::NotRunning
+        ) || std::time::Instant::now() >= deadline
+        {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let (state, error) = obs.status();
+    if state != ObsConnectionState::Connected {
+        tracing::warn!(?state, error = ?error, "collecting without OBS");
+    }
+
+    let mut hub = MetricsHub::new(obs, &config.network);
+    hub.collect().await?;
+    tokio::time::sleep(Duration::from_millis(sample_ms)).await;
+    let frame = hub.collect().await?;
+    if json {
+        println!("{}", serde_json::to_string(&frame)?);
+    } else {
+        println!("{}", serde_json::to_string_pretty(&frame)?);
+    }
+    Ok(())
+}
 
-    println!("{}", serde_json::to_string_pretty(&response)?);
+fn print_output<T: serde::Serialize>(
+    json: bool,
+    value: &T,
+    human: impl FnOnce() -> String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if json {
+        println!("{}", serde_json::to_string(value)?);
+    } else {
+        println!("{}", human());
+    }
     Ok(())
 }
 
-fn build_aegis_client(
+pub(crate) fn build_aegis_client(
     config: &Config,
     vault: &Vault,
 ) -> Result<ControlPlaneClient, Box<dyn std::error::Error>> {
@@ -380,7 +1752,10 @@ fn build_aegis_client(
     }
     let access_jwt = vault.retrieve(jwt_key)?;
 
-    Ok(ControlPlaneClient::new(base_url, access_jwt.trim())?)
+    Ok(ControlPlaneClient::new(
+        base_url,
+        access_jwt.expose().trim(),
+    )?)
 }
 
 fn generate_token(len: usize) -> String {
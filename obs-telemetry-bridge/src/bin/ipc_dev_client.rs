@@ -1,3 +1,6 @@
+// The named pipes are Windows-only; elsewhere only `--tcp` can connect.
+#![cfg_attr(not(windows), allow(dead_code, unused_imports))]
+
 use serde::{Deserialize, Serialize};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
@@ -45,6 +48,16 @@ struct PingPayload {
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct RequestStatusPayload {}
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AuthChallengePayload {
+    nonce: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AuthResponsePayload {
+    mac: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct SceneSwitchResultPayload {
     request_id: String,
@@ -113,15 +126,42 @@ where
     writer.flush().await
 }
 
-#[cfg(windows)]
+/// `hex(HMAC-SHA256(secret, nonce))`, the answer to `auth_challenge`.
+fn sign_nonce(secret: &str, nonce: &str) -> String {
+    let key = ring::hmac::Key::new(ring::hmac::HMAC_SHA256, secret.as_bytes());
+    ring::hmac::sign(&key, nonce.as_bytes())
+        .as_ref()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn arg_value(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip_while(|a| a != name);
+    args.next()?;
+    args.next()
+}
+
 async fn run() -> Result<(), Box<dyn std::error::Error>> {
-    use tokio::net::windows::named_pipe::ClientOptions;
+    // The TCP transport always challenges; over the pipes only with
+    // `ipc.auth_required`, where the shared secret is passed the same way.
+    let secret = arg_value("--token");
+    if let Some(addr) = arg_value("--tcp") {
+        println!("ipc-dev-client: connecting to {addr}");
+        let stream = tokio::net::TcpStream::connect(&addr).await?;
+        println!("ipc-dev-client: connected to tcp={addr}");
+        let (evt_read, cmd_write) = stream.into_split();
+        return session(evt_read, cmd_write, secret).await;
+    }
+    run_pipes(secret).await
+}
 
-    let auto_ack = !std::env::args().any(|a| a == "--no-auto-ack");
-    let request_status = !std::env::args().any(|a| a == "--no-request-status");
+#[cfg(windows)]
+async fn run_pipes(secret: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    use tokio::net::windows::named_pipe::ClientOptions;
 
     println!("ipc-dev-client: connecting");
-    let mut cmd_pipe;
+    let cmd_pipe;
     let evt_pipe;
 
     loop {
@@ -144,6 +184,43 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
         "ipc-dev-client: connected to cmd={} evt={}",
         CMD_PIPE_NAME, EVT_PIPE_NAME
     );
+    let (evt_read, _evt_write) = tokio::io::split(evt_pipe);
+    session(evt_read, cmd_pipe, secret).await
+}
+
+#[cfg(not(windows))]
+async fn run_pipes(_secret: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    eprintln!("ipc-dev-client: named pipes are Windows-only; use --tcp <addr> --token <token>");
+    Ok(())
+}
+
+fn value_envelope<T: Serialize>(
+    message_type: &str,
+    priority: Priority,
+    payload: T,
+) -> Result<Envelope<serde_json::Value>, serde_json::Error> {
+    let msg = make_envelope(message_type, priority, payload);
+    Ok(Envelope {
+        v: msg.v,
+        id: msg.id,
+        ts_unix_ms: msg.ts_unix_ms,
+        message_type: msg.message_type,
+        priority: msg.priority,
+        payload: serde_json::to_value(msg.payload)?,
+    })
+}
+
+async fn session<R, W>(
+    mut evt_read: R,
+    mut cmd_write: W,
+    secret: Option<String>,
+) -> Result<(), Box<dyn std::error::Error>>
+where
+    R: AsyncRead + Unpin,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    let auto_ack = !std::env::args().any(|a| a == "--no-auto-ack");
+    let request_status = !std::env::args().any(|a| a == "--no-request-status");
 
     let hello = make_envelope(
         "hello",
@@ -159,7 +236,6 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
             ],
         },
     );
-    let (mut evt_read, _evt_write) = tokio::io::split(evt_pipe);
     let (out_tx, mut out_rx) = mpsc::unbounded_channel::<Envelope<serde_json::Value>>();
 
     tokio::spawn(async move {
@@ -167,7 +243,7 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
             let Some(msg) = out_rx.recv().await else {
                 break;
             };
-            if write_frame(&mut cmd_pipe, &msg).await.is_err() {
+            if write_frame(&mut cmd_write, &msg).await.is_err() {
                 break;
             }
         }
@@ -206,20 +282,6 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
     out_tx.send(hello_value)?;
     println!("-> hello");
 
-    if request_status {
-        let req = make_envelope("request_status", Priority::High, RequestStatusPayload {});
-        let req_value = Envelope {
-            v: req.v,
-            id: req.id,
-            ts_unix_ms: req.ts_unix_ms,
-            message_type: req.message_type,
-            priority: req.priority,
-            payload: serde_json::to_value(req.payload)?,
-        };
-        out_tx.send(req_value)?;
-        println!("-> request_status");
-    }
-
     loop {
         let msg = read_frame(&mut evt_read).await?;
         println!(
@@ -228,6 +290,31 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
             serde_json::to_string(&msg.payload)?
         );
 
+        if msg.message_type == "auth_challenge" {
+            let Some(secret) = secret.as_deref() else {
+                eprintln!("ipc-dev-client: the core wants a token; pass --token <token>");
+                return Ok(());
+            };
+            let challenge: AuthChallengePayload = serde_json::from_value(msg.payload.clone())?;
+            let mac = sign_nonce(secret, &challenge.nonce);
+            out_tx.send(value_envelope(
+                "auth_response",
+                Priority::High,
+                AuthResponsePayload { mac },
+            )?)?;
+            println!("-> auth_response");
+        }
+
+        // Anything but ping before `hello_ack` fails an authenticated session.
+        if msg.message_type == "hello_ack" && request_status {
+            out_tx.send(value_envelope(
+                "request_status",
+                Priority::High,
+                RequestStatusPayload {},
+            )?)?;
+            println!("-> request_status");
+        }
+
         if msg.message_type == "switch_scene" {
             let payload: SwitchScenePayload = serde_json::from_value(msg.payload.clone())?;
             if auto_ack {
@@ -256,12 +343,6 @@ async fn run() -> Result<(), Box<dyn std::error::Error>> {
     }
 }
 
-#[cfg(not(windows))]
-async fn run() -> Result<(), Box<dyn std::error::Error>> {
-    eprintln!("ipc-dev-client is Windows-only (named pipes)");
-    Ok(())
-}
-
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     run().await
@@ -0,0 +1,387 @@
+// The named-pipe client is Windows-only; the scenarios are unused elsewhere.
+#![cfg_attr(not(windows), allow(dead_code, unused_imports))]
+
+//! Sends hostile input to a running core's IPC pipes and checks that it
+//! answers with the right `protocol_error`, resets the session after a flood
+//! and recycles the pipes for the next client.
+//!
+//! Usage: `ipc_fuzz_client [--scenario <name>] [--secret <ipc shared secret>]`
+
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use uuid::Uuid;
+
+const IPC_PROTOCOL_VERSION: u8 = 1;
+const MAX_FRAME_SIZE: usize = 64 * 1024;
+const CMD_PIPE_NAME: &str = r"\\.\pipe\aegis_cmd_v1";
+const EVT_PIPE_NAME: &str = r"\\.\pipe\aegis_evt_v1";
+/// More protocol errors than the core tolerates in its 10s window.
+const FLOOD_FRAMES: usize = 8;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const EXPECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Priority {
+    Critical,
+    High,
+    Normal,
+    Low,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Envelope<T> {
+    v: u8,
+    id: String,
+    ts_unix_ms: u64,
+    #[serde(rename = "type")]
+    message_type: String,
+    priority: Priority,
+    payload: T,
+}
+
+fn now_unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+fn make_envelope(
+    message_type: &str,
+    priority: Priority,
+    payload: serde_json::Value,
+) -> Envelope<serde_json::Value> {
+    Envelope {
+        v: IPC_PROTOCOL_VERSION,
+        id: Uuid::new_v4().to_string(),
+        ts_unix_ms: now_unix_ms(),
+        message_type: message_type.to_string(),
+        priority,
+        payload,
+    }
+}
+
+fn hello() -> Envelope<serde_json::Value> {
+    make_envelope(
+        "hello",
+        Priority::High,
+        serde_json::json!({
+            "plugin_version": "ipc-fuzz-client",
+            "protocol_version": IPC_PROTOCOL_VERSION,
+            "obs_pid": std::process::id(),
+            "capabilities": ["dock"],
+        }),
+    )
+}
+
+fn ping() -> Envelope<serde_json::Value> {
+    make_envelope(
+        "ping",
+        Priority::Normal,
+        serde_json::json!({ "nonce": Uuid::new_v4().to_string() }),
+    )
+}
+
+/// A length prefix followed by `body`, whatever it contains.
+fn raw_frame(body: &[u8]) -> Vec<u8> {
+    let mut frame = (body.len() as u32).to_le_bytes().to_vec();
+    frame.extend_from_slice(body);
+    frame
+}
+
+fn encode(message: &Envelope<serde_json::Value>) -> Vec<u8> {
+    raw_frame(&rmp_serde::to_vec_named(message).expect("envelope encodes"))
+}
+
+/// Bytes that are not a MessagePack map (0xc1 is never valid).
+const GARBAGE: &[u8] = &[0xc1, 0xc1, 0xde, 0xad, 0xbe, 0xef];
+
+async fn read_frame<R>(reader: &mut R) -> std::io::Result<Envelope<serde_json::Value>>
+where
+    R: AsyncRead + Unpin,
+{
+    let len = reader.read_u32_le().await? as usize;
+    if len > MAX_FRAME_SIZE {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("frame too large: {len}"),
+        ));
+    }
+    let mut buf = vec![0u8; len];
+    reader.read_exact(&mut buf).await?;
+    rmp_serde::from_slice(&buf)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err.to_string()))
+}
+
+/// One connection to the core's command and event pipes.
+struct Link {
+    cmd: Option<Box<dyn AsyncWrite + Unpin + Send>>,
+    evt: Box<dyn AsyncRead + Unpin + Send>,
+    secret: Option<String>,
+}
+
+enum Event {
+    Frame(Envelope<serde_json::Value>),
+    Closed,
+    TimedOut,
+}
+
+impl Link {
+    async fn send_raw(&mut self, bytes: &[u8]) -> Result<(), String> {
+        let cmd = self.cmd.as_mut().ok_or("command pipe already closed")?;
+        cmd.write_all(bytes)
+            .await
+            .map_err(|err| format!("write failed: {err}"))?;
+        cmd.flush()
+            .await
+            .map_err(|err| format!("flush failed: {err}"))
+    }
+
+    async fn send(&mut self, message: &Envelope<serde_json::Value>) -> Result<(), String> {
+        self.send_raw(&encode(message)).await
+    }
+
+    /// Closes the command pipe, leaving the event pipe open to watch the core react.
+    fn close_cmd(&mut self) {
+        self.cmd = None;
+    }
+
+    async fn next_event(&mut self, deadline: tokio::time::Instant) -> Event {
+        match tokio::time::timeout_at(deadline, read_frame(&mut self.evt)).await {
+            Err(_) => Event::TimedOut,
+            Ok(Ok(frame)) => Event::Frame(frame),
+            Ok(Err(_)) => Event::Closed,
+        }
+    }
+
+    /// Reads events until one of `message_type` arrives, skipping status snapshots and the like.
+    async fn expect(&mut self, message_type: &str) -> Result<Envelope<serde_json::Value>, String> {
+        let deadline = tokio::time::Instant::now() + EXPECT_TIMEOUT;
+        loop {
+            match self.next_event(deadline).await {
+                Event::Frame(frame) if frame.message_type == message_type => return Ok(frame),
+                Event::Frame(_) => {}
+                Event::Closed => {
+                    return Err(format!("pipe closed while waiting for {message_type}"))
+                }
+                Event::TimedOut => {
+                    return Err(format!("no {message_type} within {EXPECT_TIMEOUT:?}"))
+                }
+            }
+        }
+    }
+
+    async fn expect_protocol_error(&mut self, code: &str) -> Result<(), String> {
+        let frame = self.expect("protocol_error").await?;
+        match frame.payload.get("code").and_then(|c| c.as_str()) {
+            Some(got) if got == code => Ok(()),
+            got => Err(format!("expected protocol_error {code}, got {got:?}")),
+        }
+    }
+
+    /// Waits for the core to end the session and close the event pipe.
+    async fn expect_closed(&mut self) -> Result<(), String> {
+        let deadline = tokio::time::Instant::now() + EXPECT_TIMEOUT;
+        loop {
+            match self.next_event(deadline).await {
+                Event::Frame(_) => {}
+                Event::Closed => return Ok(()),
+                Event::TimedOut => {
+                    return Err(format!("session still open after {EXPECT_TIMEOUT:?}"))
+                }
+            }
+        }
+    }
+
+    /// Sends `hello` and waits for `hello_ack`, answering an auth challenge if one comes.
+    async fn handshake(&mut self) -> Result<(), String> {
+        self.send(&hello()).await?;
+        let deadline = tokio::time::Instant::now() + EXPECT_TIMEOUT;
+        loop {
+            match self.next_event(deadline).await {
+                Event::Frame(frame) if frame.message_type == "hello_ack" => return Ok(()),
+                Event::Frame(frame) if frame.message_type == "auth_challenge" => {
+                    let secret = self
+                        .secret
+                        .clone()
+                        .ok_or("core requires IPC auth; pass --secret")?;
+                    let nonce = frame.payload["nonce"].as_str().unwrap_or_default();
+                    let key = hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes());
+                    let mac: String = hmac::sign(&key, nonce.as_bytes())
+                        .as_ref()
+                        .iter()
+                        .map(|b| format!("{b:02x}"))
+                        .collect();
+                    let response = make_envelope(
+                        "auth_response",
+                        Priority::High,
+                        serde_json::json!({ "mac": mac }),
+                    );
+                    self.send(&response).await?;
+                }
+                Event::Frame(_) => {}
+                Event::Closed => return Err("pipe closed during handshake".to_string()),
+                Event::TimedOut => return Err("no hello_ack".to_string()),
+            }
+        }
+    }
+}
+
+const SCENARIOS: &[&str] = &[
+    "malformed_envelope",
+    "invalid_payload",
+    "unknown_type",
+    "oversized_frame",
+    "wrong_version",
+    "truncated_frame",
+    "protocol_error_flood",
+    "recovery",
+];
+
+/// Runs one scenario on a fresh link. Each expects a specific reaction from the core.
+async fn run_scenario(name: &str, link: &mut Link) -> Result<(), String> {
+    match name {
+        // A bad frame is reported and the session carries on.
+        "malformed_envelope" => {
+            link.handshake().await?;
+            link.send_raw(&raw_frame(GARBAGE)).await?;
+            link.expect_protocol_error("decode_failed").await?;
+            link.send(&ping()).await?;
+            link.expect("pong").await.map(|_| ())
+        }
+        "invalid_payload" => {
+            link.handshake().await?;
+            let bad_ping =
+                make_envelope("ping", Priority::Normal, serde_json::json!({ "nonce": 42 }));
+            link.send(&bad_ping).await?;
+            link.expect_protocol_error("invalid_payload").await
+        }
+        "unknown_type" => {
+            link.handshake().await?;
+            let unknown = make_envelope("fuzz_unknown", Priority::Low, serde_json::json!({}));
+            link.send(&unknown).await?;
+            link.expect_protocol_error("unknown_type").await
+        }
+        // Only the length prefix is sent; the core must reject it without waiting for the body.
+        "oversized_frame" => {
+            link.handshake().await?;
+            link.send_raw(&((MAX_FRAME_SIZE as u32) + 1).to_le_bytes())
+                .await?;
+            link.expect_protocol_error("frame_too_large").await
+        }
+        "wrong_version" => {
+            let mut message = hello();
+            message.v = IPC_PROTOCOL_VERSION + 1;
+            link.send(&message).await?;
+            link.expect("user_notice").await?;
+            link.expect_closed().await
+        }
+        // The plugin dies mid-frame.
+        "truncated_frame" => {
+            link.handshake().await?;
+            let mut partial = 256u32.to_le_bytes().to_vec();
+            partial.extend_from_slice(&[0x80; 16]);
+            link.send_raw(&partial).await?;
+            link.close_cmd();
+            link.expect_closed().await
+        }
+        // Past the threshold the core resets the session instead of reporting each error.
+        "protocol_error_flood" => {
+            link.handshake().await?;
+            for _ in 0..FLOOD_FRAMES {
+                link.send_raw(&raw_frame(GARBAGE)).await?;
+            }
+            link.expect_closed().await
+        }
+        // The recycled pipes serve a well-behaved client after all of the above.
+        "recovery" => {
+            link.handshake().await?;
+            link.send(&ping()).await?;
+            link.expect("pong").await.map(|_| ())
+        }
+        other => Err(format!("unknown scenario '{other}'")),
+    }
+}
+
+#[cfg(windows)]
+async fn connect(secret: Option<String>) -> Result<Link, String> {
+    use tokio::net::windows::named_pipe::ClientOptions;
+
+    let deadline = tokio::time::Instant::now() + CONNECT_TIMEOUT;
+    loop {
+        if let (Ok(cmd), Ok(evt)) = (
+            ClientOptions::new().open(CMD_PIPE_NAME),
+            ClientOptions::new().open(EVT_PIPE_NAME),
+        ) {
+            return Ok(Link {
+                cmd: Some(Box::new(cmd)),
+                evt: Box::new(evt),
+                secret,
+            });
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(format!("pipes not available within {CONNECT_TIMEOUT:?}"));
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+}
+
+#[cfg(windows)]
+async fn run() -> Result<bool, Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    let arg = |flag: &str| {
+        args.iter()
+            .position(|a| a == flag)
+            .and_then(|i| args.get(i + 1).cloned())
+    };
+    let secret = arg("--secret");
+    let selected: Vec<&str> = match arg("--scenario") {
+        Some(name) => vec![SCENARIOS
+            .iter()
+            .copied()
+            .find(|s| *s == name)
+            .ok_or(format!("unknown scenario '{name}'"))?],
+        None => SCENARIOS.to_vec(),
+    };
+
+    let mut failed = 0;
+    for name in &selected {
+        // Connecting again after each scenario is itself the pipe-recycling check.
+        let result = match connect(secret.clone()).await {
+            Ok(mut link) => run_scenario(name, &mut link).await,
+            Err(err) => Err(err),
+        };
+        match result {
+            Ok(()) => println!("PASS {name}"),
+            Err(err) => {
+                failed += 1;
+                println!("FAIL {name}: {err}");
+            }
+        }
+        // Let the core notice the disconnect and create the next pipe instances.
+        tokio::time::sleep(Duration::from_millis(300)).await;
+    }
+    println!(
+        "ipc-fuzz-client: {} passed, {} failed",
+        selected.len() - failed,
+        failed
+    );
+    Ok(failed == 0)
+}
+
+#[cfg(not(windows))]
+async fn run() -> Result<bool, Box<dyn std::error::Error>> {
+    eprintln!("ipc-fuzz-client is Windows-only (named pipes)");
+    Ok(true)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    if !run().await? {
+        std::process::exit(1);
+    }
+    Ok(())
+}
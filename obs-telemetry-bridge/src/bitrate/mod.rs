@@ -0,0 +1,280 @@
+use crate::activity::ActionKind;
+use crate::config::{ConfigHandle, LowQualityFallbackConfig};
+use crate::ipc::OverrideStoreHandle;
+use crate::metrics::ObsLink;
+use crate::model::{TelemetryFrame, TrendState};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    StepDown,
+    StepUp,
+    /// Put back the bitrate from before the first step down.
+    Restore,
+}
+
+impl Action {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::StepDown => "step_down",
+            Self::StepUp => "step_up",
+            Self::Restore => "restore",
+        }
+    }
+}
+
+/// Decides when the streaming bitrate moves. A step needs the stream to stay
+/// degraded (or healthy) for the configured time, so one bad sample never
+/// touches the profile. A stream gets at most one step: the live encoder never
+/// sees it, so its health cannot answer a step and further ones would only
+/// ratchet the next start's bitrate down to `min_kbps`.
+#[derive(Debug, Default)]
+struct Fallback {
+    degraded: bool,
+    /// When the stream entered its current state.
+    since: Option<Instant>,
+    /// This stream already had its step.
+    stepped: bool,
+}
+
+impl Fallback {
+    fn observe(
+        &mut self,
+        frame: &TelemetryFrame,
+        config: &LowQualityFallbackConfig,
+        enabled: bool,
+        lowered: bool,
+        now: Instant,
+    ) -> Option<Action> {
+        if !enabled {
+            self.since = None;
+            return lowered.then_some(Action::Restore);
+        }
+        // A lowered bitrate is kept for the next start, which is when it applies.
+        if !frame.obs.streaming {
+            self.since = None;
+            self.stepped = false;
+            return None;
+        }
+        if self.stepped {
+            return None;
+        }
+        let degraded =
+            frame.health_trend.state == TrendState::Degrading || frame.health < config.min_health;
+        let since = match self.since {
+            Some(since) if degraded == self.degraded => since,
+            _ => {
+                self.degraded = degraded;
+                self.since = Some(now);
+                return None;
+            }
+        };
+        let held = now.duration_since(since);
+        let step = if degraded {
+            held >= Duration::from_secs(config.degrade_secs)
+        } else {
+            lowered && held >= Duration::from_secs(config.recover_secs)
+        };
+        if !step {
+            return None;
+        }
+        self.stepped = true;
+        Some(if degraded {
+            Action::StepDown
+        } else {
+            Action::StepUp
+        })
+    }
+}
+
+/// The bitrate `action` moves `current` to, never below `min_kbps` on the way
+/// down nor above `original` on the way up.
+fn target(action: Action, original: u32, current: u32, config: &LowQualityFallbackConfig) -> u32 {
+    match action {
+        Action::StepDown => current
+            .saturating_sub(config.step_kbps)
+            .max(config.min_kbps)
+            .min(current),
+        Action::StepUp => current.saturating_add(config.step_kbps).min(original),
+        Action::Restore => original,
+    }
+}
+
+/// Lowers the profile's streaming bitrate while the stream degrades and
+/// raises it back as it recovers, reading `[low_quality_fallback]` and the
+/// dock's override on every frame. OBS only reads that bitrate when a stream
+/// starts, so a step applies from the next start, never to the live encoder.
+/// Every step is alerted. The bitrate from before the first step is restored
+/// when the fallback is turned off. Nothing changes while the manual override
+/// is on.
+pub fn spawn(
+    config: ConfigHandle,
+    obs: ObsLink,
+    overrides: OverrideStoreHandle,
+    mut rx: watch::Receiver<TelemetryFrame>,
+) {
+    tokio::spawn(async move {
+        let mut fallback = Fallback::default();
+        // `(original, current)` while the bitrate is lowered.
+        let mut lowered: Option<(u32, u32)> = None;
+        let mut unsupported = false;
+        while rx.changed().await.is_ok() {
+            let frame = rx.borrow_and_update().clone();
+            // The operator has the stream; hold the bitrate where it is.
+            if overrides.manual_override() {
+                continue;
+            }
+            let (settings, notifications) =
+                config.read(|c| (c.low_quality_fallback.clone(), c.notifications.clone()));
+            let enabled = overrides
+                .get()
+                .low_quality_fallback
+                .unwrap_or(settings.enabled);
+            let Some(action) = fallback.observe(
+                &frame,
+                &settings,
+                enabled,
+                lowered.is_some(),
+                Instant::now(),
+            ) else {
+                continue;
+            };
+            let (original, current) = match lowered {
+                Some(lowered) => lowered,
+                None => match obs.stream_bitrate().await {
+                    Ok(kbps) => {
+                        unsupported = false;
+                        (kbps, kbps)
+                    }
+                    Err(err) => {
+                        if !std::mem::replace(&mut unsupported, true) {
+                            tracing::warn!(error = %err, "cannot lower the stream bitrate");
+                        }
+                        continue;
+                    }
+                },
+            };
+
+            let to = target(action, original, current, &settings);
+            if to == current {
+                lowered = lowered.filter(|_| to != original);
+                continue;
+            }
+            let result = obs.set_stream_bitrate(to).await;
+            crate::activity::log().record(
+                ActionKind::BitrateChange,
+                "bitrate_fallback",
+                Some(action.as_str().to_string()),
+                result
+                    .clone()
+                    .map(|()| format!("{current} -> {to} kbps from the next stream start")),
+            );
+            match result {
+                Ok(()) => {
+                    tracing::info!(
+                        from = current,
+                        to,
+                        action = action.as_str(),
+                        "stream bitrate for the next start changed"
+                    );
+                    let message = match action {
+                        Action::StepDown => Some("Stream degraded: the next stream starts at"),
+                        Action::StepUp => Some("Stream recovered: the next stream starts at"),
+                        Action::Restore => None,
+                    };
+                    if let Some(message) = message {
+                        let message = format!("{message} {to} kbps (was {current})");
+                        crate::notify::alert(&notifications, &message);
+                    }
+                    lowered = (to != original).then_some((original, to));
+                }
+                Err(err) if action == Action::Restore => {
+                    let message =
+                        format!("Restoring the stream bitrate to {original} kbps failed: {err}");
+                    crate::notify::alert(&notifications, &message);
+                    lowered = None;
+                }
+                Err(err) => {
+                    tracing::warn!(error = %err, to, "changing the stream bitrate failed");
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_down_while_degraded_and_back_up_after_recovery() {
+        let config = LowQualityFallbackConfig::default();
+        let mut fallback = Fallback::default();
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut frame = TelemetryFrame::default();
+        frame.obs.streaming = true;
+        frame.health = 0.5;
+        assert_eq!(fallback.observe(&frame, &config, false, false, at(0)), None);
+        assert_eq!(fallback.observe(&frame, &config, true, false, at(0)), None);
+        assert_eq!(fallback.observe(&frame, &config, true, false, at(4)), None);
+        assert_eq!(
+            fallback.observe(&frame, &config, true, false, at(5)),
+            Some(Action::StepDown)
+        );
+        assert_eq!(
+            fallback.observe(&frame, &config, true, true, at(600)),
+            None,
+            "one step per stream"
+        );
+        frame.health = 1.0;
+        assert_eq!(fallback.observe(&frame, &config, true, true, at(700)), None);
+
+        frame.obs.streaming = false;
+        assert_eq!(fallback.observe(&frame, &config, true, true, at(701)), None);
+        frame.obs.streaming = true;
+        assert_eq!(fallback.observe(&frame, &config, true, true, at(702)), None);
+        assert_eq!(fallback.observe(&frame, &config, true, true, at(731)), None);
+        assert_eq!(
+            fallback.observe(&frame, &config, true, true, at(732)),
+            Some(Action::StepUp)
+        );
+        assert_eq!(fallback.observe(&frame, &config, true, true, at(800)), None);
+    }
+
+    #[test]
+    fn a_lowered_bitrate_outlives_the_stream_until_the_fallback_is_off() {
+        let config = LowQualityFallbackConfig::default();
+        let mut fallback = Fallback::default();
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut frame = TelemetryFrame::default();
+        frame.obs.streaming = true;
+        frame.health = 0.5;
+        fallback.observe(&frame, &config, true, false, at(0));
+        assert_eq!(
+            fallback.observe(&frame, &config, true, false, at(5)),
+            Some(Action::StepDown)
+        );
+
+        // Stopping is what lets the next start pick the lowered bitrate up.
+        frame.obs.streaming = false;
+        assert_eq!(fallback.observe(&frame, &config, true, true, at(6)), None);
+        assert_eq!(fallback.observe(&frame, &config, true, true, at(600)), None);
+        assert_eq!(
+            fallback.observe(&frame, &config, false, true, at(601)),
+            Some(Action::Restore)
+        );
+    }
+
+    #[test]
+    fn steps_stay_between_the_floor_and_the_original() {
+        let config = LowQualityFallbackConfig::default();
+        assert_eq!(target(Action::StepDown, 6000, 6000, &config), 5000);
+        assert_eq!(target(Action::StepDown, 6000, 2000, &config), 1500);
+        assert_eq!(target(Action::StepDown, 1000, 1000, &config), 1000);
+        assert_eq!(target(Action::StepUp, 6000, 5500, &config), 6000);
+        assert_eq!(target(Action::Restore, 6000, 1500, &config), 6000);
+    }
+}
@@ -0,0 +1,261 @@
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Debug, Parser)]
+#[command(name = "telemy", version, about = "OBS telemetry bridge and Aegis relay client")]
+pub struct Cli {
+    #[command(flatten)]
+    pub global: GlobalArgs,
+
+    /// Defaults to `serve` when omitted.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Args)]
+pub struct GlobalArgs {
+    /// Path to config.toml (overrides ./config.toml and %APPDATA%/Telemy/config.toml).
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Named profile: uses config.<NAME>.toml and `<NAME>.`-prefixed vault keys
+    /// (defaults to TELEMY_PROFILE).
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Print machine-readable JSON instead of human-readable text.
+    #[arg(long, global = true)]
+    pub json: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run the telemetry bridge, dashboard server and IPC server.
+    Serve {
+        /// Start with telemetry collection paused; resume from the tray or
+        /// `PUT /api/v1/pause`.
+        #[arg(long)]
+        paused: bool,
+    },
+    /// Collect one telemetry frame without starting the server and print it
+    /// as JSON (compact with `--json`).
+    Collect {
+        /// How long to wait for the OBS connection before collecting without it.
+        #[arg(long, default_value_t = 5, value_name = "SECS")]
+        obs_timeout: u64,
+        /// Gap between the warm-up sample and the printed one, so CPU and
+        /// network rates cover a real interval.
+        #[arg(long, default_value_t = 1000, value_name = "MS")]
+        sample_ms: u64,
+    },
+    /// Show health, OBS and Aegis state of the running instance.
+    Status,
+    /// Open the running instance's dashboard in the browser.
+    Open,
+    /// Shut the running instance down gracefully.
+    Stop,
+    /// Manage secrets in the local vault.
+    Vault {
+        #[command(subcommand)]
+        command: VaultCommand,
+    },
+    /// Interact with the Aegis control plane.
+    Aegis {
+        #[command(subcommand)]
+        command: AegisCommand,
+    },
+    /// Inspect or initialize configuration.
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Build or restore support bundles for bug reports.
+    Diagnostics {
+        #[command(subcommand)]
+        command: DiagnosticsCommand,
+    },
+    /// Enable or disable launching at login.
+    Autostart {
+        #[command(subcommand)]
+        command: AutostartCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum VaultCommand {
+    /// Store a secret.
+    Set { key: String, value: String },
+    /// Print a secret.
+    Get { key: String },
+    /// List stored keys.
+    List,
+    /// Remove a secret.
+    Delete { key: String },
+    /// Re-encrypt every entry for the current Windows user, e.g. after a
+    /// profile migration.
+    Rotate,
+    /// Write this profile's secrets to a password-protected backup file
+    /// (password from TELEMY_VAULT_PASSWORD or a prompt).
+    Export {
+        path: PathBuf,
+        /// Replace an existing file.
+        #[arg(long)]
+        force: bool,
+    },
+    /// Restore secrets from a backup made by `vault export`.
+    Import {
+        path: PathBuf,
+        /// Replace keys that already exist in the vault.
+        #[arg(long)]
+        overwrite: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AegisCommand {
+    /// Manage the relay session.
+    Relay {
+        #[command(subcommand)]
+        command: RelayCommand,
+    },
+    /// Sign in from a browser and store the resulting tokens in the vault.
+    Login {
+        /// Control-plane URL; saved as `aegis.base_url` when that is unset.
+        #[arg(long)]
+        base_url: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RelayCommand {
+    /// Request a relay session.
+    Start {
+        /// Preferred region (defaults to control-plane selection; `auto` picks
+        /// the lowest-latency region from this machine).
+        #[arg(long)]
+        region: Option<String>,
+    },
+    /// Stop a relay session (defaults to the active one).
+    Stop {
+        session_id: Option<String>,
+        #[arg(long, default_value = "user_requested")]
+        reason: String,
+    },
+    /// Show the active relay session.
+    Status,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    /// Write a default config file.
+    Init,
+    /// Print the config file path in use.
+    Path,
+    /// Print the effective config (file plus environment overrides), redacted
+    /// like the support bundle's.
+    Show,
+    /// Print one effective setting, e.g. `obs.port`.
+    Get { key: String },
+    /// Change one setting in the config file, e.g. `obs.port 4456`.
+    Set {
+        key: String,
+        /// TOML value (`4456`, `true`, `["a"]`); anything else is taken as a string.
+        value: String,
+    },
+    /// Check the config file for syntax, type and range errors.
+    Validate,
+    /// Show settings that differ from the defaults, redacted like `config show`.
+    Diff,
+    /// Write the built-in page templates into the templates directory, as a
+    /// starting point for custom ones.
+    Templates {
+        /// Replace templates that already exist there.
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DiagnosticsCommand {
+    /// Write a zip with the redacted config, recent OBS logs, selfstats and
+    /// recorded frames.
+    Export {
+        /// Output file (defaults to telemy-diagnostics-<time>.zip here).
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+        /// How many recorded telemetry frames to include.
+        #[arg(long, default_value_t = 200)]
+        frames: usize,
+    },
+    /// Restore the config from a bundle; secrets must be re-entered with `vault set`.
+    Import {
+        bundle: PathBuf,
+        /// Replace an existing config file.
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AutostartCommand {
+    Enable,
+    Disable,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn cli_definition_is_consistent() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn parses_nested_relay_stop_with_global_flags() {
+        let cli = Cli::try_parse_from([
+            "telemy",
+            "aegis",
+            "relay",
+            "stop",
+            "ses_1",
+            "--json",
+            "--config",
+            "custom.toml",
+        ])
+        .unwrap();
+        assert!(cli.global.json);
+        assert_eq!(cli.global.config, Some(PathBuf::from("custom.toml")));
+        match cli.command {
+            Some(Command::Aegis {
+                command:
+                    AegisCommand::Relay {
+                        command: RelayCommand::Stop { session_id, reason },
+                    },
+            }) => {
+                assert_eq!(session_id.as_deref(), Some("ses_1"));
+                assert_eq!(reason, "user_requested");
+            }
+            other => panic!("unexpected command: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn no_subcommand_means_serve() {
+        let cli = Cli::try_parse_from(["telemy"]).unwrap();
+        assert!(cli.command.is_none());
+    }
+
+    #[test]
+    fn collect_defaults_wait_for_obs_and_sample_for_a_second() {
+        let cli = Cli::try_parse_from(["telemy", "collect", "--obs-timeout", "2"]).unwrap();
+        match cli.command {
+            Some(Command::Collect {
+                obs_timeout,
+                sample_ms,
+            }) => assert_eq!((obs_timeout, sample_ms), (2, 1000)),
+            other => panic!("unexpected command: {other:?}"),
+        }
+    }
+}
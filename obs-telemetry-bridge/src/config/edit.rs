@@ -0,0 +1,229 @@
+use super::Config;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use toml::{Table, Value};
+use toml_edit::{DocumentMut, Item};
+
+/// One setting that differs from its default, for `telemy config diff`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConfigChange {
+    pub key: String,
+    /// `None` when the setting has no default (an unset optional value).
+    pub default: Option<Value>,
+    /// `None` when the setting is back to unset.
+    pub current: Option<Value>,
+}
+
+fn to_table(config: &Config) -> Result<Table, Box<dyn std::error::Error>> {
+    Ok(Table::try_from(config)?)
+}
+
+fn lookup<'a>(table: &'a Table, key: &str) -> Option<&'a Value> {
+    let mut parts = key.split('.');
+    let mut value = table.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_table()?.get(part)?;
+    }
+    Some(value)
+}
+
+/// Parses a CLI value the way it would be written in TOML (`4456`, `true`,
+/// `["a", "b"]`), falling back to a plain string so `obs.host 10.0.0.2` needs
+/// no quoting.
+fn parse_value(raw: &str) -> toml_edit::Value {
+    format!("v = {raw}")
+        .parse::<DocumentMut>()
+        .ok()
+        .and_then(|doc| doc.get("v")?.as_value().cloned())
+        .unwrap_or_else(|| raw.into())
+}
+
+fn flatten(prefix: &str, table: &Table, out: &mut BTreeMap<String, Value>) {
+    for (name, value) in table {
+        let key = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{prefix}.{name}")
+        };
+        match value {
+            Value::Table(inner) => flatten(&key, inner, out),
+            other => {
+                out.insert(key, other.clone());
+            }
+        }
+    }
+}
+
+impl Config {
+    /// The effective value at a dotted key such as `obs.port`.
+    pub fn get_key(&self, key: &str) -> Result<Option<Value>, Box<dyn std::error::Error>> {
+        Ok(lookup(&to_table(self)?, key).cloned())
+    }
+
+    /// Settings that differ from [`Config::default`], sorted by key.
+    pub fn diff_from_defaults(&self) -> Result<Vec<ConfigChange>, Box<dyn std::error::Error>> {
+        let (mut defaults, mut current) = (BTreeMap::new(), BTreeMap::new());
+        flatten("", &to_table(&Config::default())?, &mut defaults);
+        flatten("", &to_table(self)?, &mut current);
+        let mut keys: Vec<&String> = defaults.keys().chain(current.keys()).collect();
+        keys.sort();
+        keys.dedup();
+        Ok(keys
+            .into_iter()
+            .filter(|key| defaults.get(*key) != current.get(*key))
+            .map(|key| ConfigChange {
+                key: key.clone(),
+                default: defaults.get(key).cloned(),
+                current: current.get(key).cloned(),
+            })
+            .collect())
+    }
+
+    /// Sets one key in the config file at `path`, keeping everything else in
+    /// the file as it was. The key must name a real setting and the result must
+    /// validate, or nothing is written.
+    pub fn set_key_in_file(
+        path: &Path,
+        key: &str,
+        raw: &str,
+    ) -> Result<Value, Box<dyn std::error::Error>> {
+        let mut file = match fs::read_to_string(path) {
+            Ok(raw) => raw
+                .parse::<DocumentMut>()
+                .map_err(|err| format!("{}: {err}", path.display()))?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => DocumentMut::new(),
+            Err(err) => return Err(err.into()),
+        };
+        let mut value = parse_value(raw);
+
+        let (parents, leaf) = match key.rsplit_once('.') {
+            Some((parents, leaf)) => (Some(parents), leaf),
+            None => (None, key),
+        };
+        let mut table = file.as_table_mut() as &mut dyn toml_edit::TableLike;
+        for part in parents.into_iter().flat_map(|p| p.split('.')) {
+            table = table
+                .entry(part)
+                .or_insert_with(toml_edit::table)
+                .as_table_like_mut()
+                .ok_or_else(|| format!("{key}: `{part}` is not a section"))?;
+        }
+        match table.get_mut(leaf).and_then(Item::as_value_mut) {
+            // Keeps the comment after the old value.
+            Some(existing) => {
+                *value.decor_mut() = existing.decor().clone();
+                *existing = value.clone();
+            }
+            None => {
+                table.insert(leaf, Item::Value(value.clone()));
+            }
+        }
+
+        // Unknown keys are dropped by deserialization, so a key that does not
+        // survive the round trip is not a setting.
+        let edited = file.to_string();
+        let config: Config = toml::from_str(&edited).map_err(|err| format!("{key}: {err}"))?;
+        let value: Value = toml::from_str::<Table>(&format!("v = {value}"))?
+            .remove("v")
+            .ok_or("unparseable value")?;
+        if config.get_key(key)?.as_ref() != Some(&value) {
+            return Err(format!("unknown config key: {key}").into());
+        }
+        config.validate()?;
+
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        fs::write(path, edited)?;
+        Ok(value)
+    }
+
+    /// Parses and validates the file at `path` without environment overrides.
+    /// Syntax and type errors carry the line and column from the TOML parser.
+    pub fn validate_file(path: &Path) -> Result<Config, String> {
+        let raw = fs::read_to_string(path).map_err(|err| format!("{}: {err}", path.display()))?;
+        let config: Config =
+            toml::from_str(&raw).map_err(|err| format!("{}: {err}", path.display()))?;
+        config
+            .validate()
+            .map_err(|err| format!("{}: {err}", path.display()))?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_config() -> std::path::PathBuf {
+        std::env::temp_dir()
+            .join(format!("telemy-config-edit-{}", uuid::Uuid::new_v4()))
+            .join("config.toml")
+    }
+
+    #[test]
+    fn set_key_updates_one_setting_and_rejects_unknown_keys() {
+        let path = temp_config();
+        Config::set_key_in_file(&path, "obs.port", "4456").unwrap();
+        Config::set_key_in_file(&path, "obs.host", "10.0.0.2").unwrap();
+        let config = Config::validate_file(&path).unwrap();
+        assert_eq!(config.obs.port, 4456);
+        assert_eq!(config.obs.host, "10.0.0.2");
+        assert_eq!(
+            config.get_key("obs.port").unwrap(),
+            Some(Value::Integer(4456))
+        );
+
+        let unknown = Config::set_key_in_file(&path, "obs.prot", "4457").unwrap_err();
+        assert!(unknown.to_string().contains("unknown config key"));
+        assert!(Config::set_key_in_file(&path, "obs.port", "0").is_err());
+        assert!(Config::set_key_in_file(&path, "obs.port", "\"high\"").is_err());
+        assert_eq!(Config::validate_file(&path).unwrap().obs.port, 4456);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn set_key_keeps_comments_and_order() {
+        let path = temp_config();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let original = "# Telemy config\n\n[server]\nport = 7070\n\n[obs]\n# where OBS runs\nport = 4455 # default\nhost = \"127.0.0.1\"\n";
+        fs::write(&path, original).unwrap();
+
+        Config::set_key_in_file(&path, "obs.port", "4456").unwrap();
+        Config::set_key_in_file(&path, "obs.process_name", "obs").unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            original.replace("4455", "4456") + "process_name = \"obs\"\n"
+        );
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn validate_file_reports_the_error_location() {
+        let path = temp_config();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "[obs]\nport = \"high\"\n").unwrap();
+        let err = Config::validate_file(&path).unwrap_err();
+        assert!(err.contains("line 2"), "{err}");
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn diff_lists_only_changed_settings() {
+        let mut config = Config::default();
+        assert!(config.diff_from_defaults().unwrap().is_empty());
+        config.obs.port = 4456;
+        config.aegis.base_url = Some("https://aegis.example.com".to_string());
+        let keys: Vec<String> = config
+            .diff_from_defaults()
+            .unwrap()
+            .into_iter()
+            .map(|change| change.key)
+            .collect();
+        assert_eq!(keys, ["aegis.base_url", "obs.port"]);
+    }
+}
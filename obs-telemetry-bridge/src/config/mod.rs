@@ -1,12 +1,63 @@
+use crate::security::SecretString;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::env;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+mod edit;
+mod shared;
+
+pub use shared::ConfigHandle;
 
 const CONFIG_FILE: &str = "config.toml";
 const ENV_PREFIX: &str = "TELEMY_";
 
+static CONFIG_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+static PROFILE_OVERRIDE: OnceLock<String> = OnceLock::new();
+
+/// Pins the config file used by every subsequent load/save (the CLI `--config` flag).
+pub fn set_path_override(path: PathBuf) {
+    let _ = CONFIG_PATH_OVERRIDE.set(path);
+}
+
+/// Selects a named profile for every subsequent load/save (the CLI `--profile`
+/// flag): `config.<name>.toml` is used instead of `config.toml`, and vault keys
+/// get a `<name>.` prefix.
+pub fn set_profile(name: String) -> Result<(), String> {
+    if !valid_profile_name(&name) {
+        return Err(format!(
+            "invalid profile name {name:?} (use letters, digits, '-' or '_')"
+        ));
+    }
+    let _ = PROFILE_OVERRIDE.set(name);
+    Ok(())
+}
+
+/// The profile set by `--profile`, else `TELEMY_PROFILE`.
+pub fn active_profile() -> Option<String> {
+    PROFILE_OVERRIDE
+        .get()
+        .cloned()
+        .or_else(|| env::var(format!("{}PROFILE", ENV_PREFIX)).ok())
+        .filter(|name| valid_profile_name(name))
+}
+
+fn valid_profile_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn profile_file_name(profile: Option<&str>) -> String {
+    match profile {
+        Some(name) => format!("config.{name}.toml"),
+        None => CONFIG_FILE.to_string(),
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
 #[serde(default)]
 pub struct Config {
@@ -14,12 +65,29 @@ pub struct Config {
     pub server: ServerConfig,
     pub vault: VaultConfig,
     pub grafana: GrafanaConfig,
+    pub loki: LokiConfig,
     pub aegis: AegisConfig,
     pub network: NetworkConfig,
+    pub metrics: MetricsConfig,
     pub startup: StartupConfig,
     pub tray: TrayConfig,
+    pub notifications: NotificationsConfig,
+    pub disk_guard: DiskGuardConfig,
+    pub low_quality_fallback: LowQualityFallbackConfig,
+    pub failover: FailoverConfig,
+    pub twitch: TwitchConfig,
+    pub logging: LoggingConfig,
     pub theme: ThemeConfig,
+    pub dashboard: DashboardConfig,
+    pub recorder: RecorderConfig,
+    pub reports: ReportsConfig,
+    pub scripting: ScriptingConfig,
+    pub ipc: IpcConfig,
     pub output_names: HashMap<String, String>,
+    pub rules: Vec<RuleConfig>,
+    pub webhooks: Vec<WebhookConfig>,
+    pub peers: Vec<PeerConfig>,
+    pub discovery: DiscoveryConfig,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -30,6 +98,15 @@ pub struct ObsConfig {
     pub password_key: Option<String>,
     pub auto_detect_process: bool,
     pub process_name: String,
+    /// Follow the newest OBS log for encoder, bandwidth and connection trouble.
+    pub tail_log: bool,
+    /// Defaults to `%APPDATA%/obs-studio/logs`.
+    pub log_dir: Option<String>,
+    /// Start OBS again when it exits mid-stream.
+    pub restart_on_crash: bool,
+    /// Defaults to the installer's `obs64.exe` (`obs` elsewhere).
+    pub restart_path: Option<String>,
+    pub restart_args: Vec<String>,
 }
 
 impl Default for ObsConfig {
@@ -40,6 +117,15 @@ impl Default for ObsConfig {
             password_key: None,
             auto_detect_process: true,
             process_name: "obs64.exe".to_string(),
+            tail_log: true,
+            log_dir: None,
+            restart_on_crash: false,
+            restart_path: None,
+            // OBS 30+ otherwise stops at a "run in Safe Mode?" prompt after a crash.
+            restart_args: vec![
+                "--startstreaming".to_string(),
+                "--disable-shutdown-check".to_string(),
+            ],
         }
     }
 }
@@ -47,15 +133,86 @@ impl Default for ObsConfig {
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(default)]
 pub struct ServerConfig {
+    /// Listen address; `0.0.0.0` makes the dashboard reachable from the LAN.
+    pub bind: String,
     pub port: u16,
-    pub token: Option<String>,
+    pub token: Option<SecretString>,
+    pub rate_limit: RateLimitConfig,
+    pub access: AccessConfig,
+    pub cors: CorsConfig,
+    /// Language of the served pages; one of `server::LANGUAGES`.
+    pub language: String,
 }
 
 impl Default for ServerConfig {
     fn default() -> Self {
         Self {
+            bind: "127.0.0.1".to_string(),
             port: 7070,
             token: None,
+            rate_limit: RateLimitConfig::default(),
+            access: AccessConfig::default(),
+            cors: CorsConfig::default(),
+            language: "en".to_string(),
+        }
+    }
+}
+
+/// Which clients may reach the server at all, for dashboards exposed through a
+/// tunnel or reverse proxy.
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct AccessConfig {
+    /// Addresses or CIDR blocks allowed to connect; empty allows everyone.
+    pub allow: Vec<String>,
+    /// Proxies whose `X-Forwarded-For` names the real client.
+    pub trusted_proxies: Vec<String>,
+}
+
+/// Cross-origin access for web overlays and dashboards hosted elsewhere. Those
+/// callers send the bearer token in a header, so no cookies are allowed.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct CorsConfig {
+    /// Origins such as `https://overlay.example.com`, or `["*"]`; empty disables CORS.
+    pub allowed_origins: Vec<String>,
+    /// Request headers cross-origin callers may send.
+    pub allowed_headers: Vec<String>,
+    /// How long browsers may cache a preflight.
+    pub max_age_secs: u64,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            allowed_headers: vec!["authorization".to_string(), "content-type".to_string()],
+            max_age_secs: 600,
+        }
+    }
+}
+
+/// Per-IP request limits on auth-sensitive routes, plus a temporary lockout from
+/// those routes once a client racks up `max_failures` 401s within
+/// `failure_window_secs`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub requests_per_minute: u32,
+    pub max_failures: u32,
+    pub failure_window_secs: u64,
+    pub lockout_secs: u64,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            requests_per_minute: 30,
+            max_failures: 5,
+            failure_window_secs: 300,
+            lockout_secs: 300,
         }
     }
 }
@@ -68,6 +225,16 @@ pub struct GrafanaConfig {
     pub auth_header: String,
     pub auth_value_key: Option<String>,
     pub push_interval_ms: u64,
+    /// Export every metric as a histogram, as releases before gauges/counters did.
+    pub legacy_histograms: bool,
+    /// Pushes kept in memory while the endpoint is unreachable and replayed once it
+    /// recovers; 0 drops failed pushes.
+    pub buffer_max_batches: usize,
+    /// OTLP `service.instance.id`; defaults to the host name.
+    pub instance_id: Option<String>,
+    pub streamer_name: Option<String>,
+    /// Extra OTLP resource attributes; these override the built-in ones.
+    pub resource_attributes: HashMap<String, String>,
 }
 
 impl Default for GrafanaConfig {
@@ -78,6 +245,11 @@ impl Default for GrafanaConfig {
             auth_header: "Authorization".to_string(),
             auth_value_key: None,
             push_interval_ms: 5000,
+            legacy_histograms: false,
+            buffer_max_batches: 720,
+            instance_id: None,
+            streamer_name: None,
+            resource_attributes: HashMap::new(),
         }
     }
 }
@@ -88,6 +260,31 @@ pub struct AegisConfig {
     pub enabled: bool,
     pub base_url: Option<String>,
     pub access_jwt_key: Option<String>,
+    /// Start the relay when OBS starts streaming and stop it after streaming ends.
+    pub auto_relay: bool,
+    /// Only auto-start while the dock has selected IRL mode.
+    pub auto_relay_irl_only: bool,
+    pub auto_relay_stop_grace_secs: u64,
+    /// What to do when a relay session enters its grace window.
+    pub grace_action: GraceAction,
+    /// How often to remind the user while a session is in grace.
+    pub grace_notice_interval_secs: u64,
+    /// How often the background poller refreshes the relay session.
+    pub poll_interval_secs: u64,
+    /// Stop an active relay session when the bridge exits.
+    pub stop_relay_on_exit: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraceAction {
+    /// Only show countdown notices.
+    #[default]
+    Notify,
+    /// Stop the session right away instead of holding the relay for the window.
+    Stop,
+    /// Start a new session when the window runs out while OBS is still streaming.
+    Extend,
 }
 
 impl Default for AegisConfig {
@@ -96,6 +293,13 @@ impl Default for AegisConfig {
             enabled: false,
             base_url: None,
             access_jwt_key: None,
+            auto_relay: false,
+            auto_relay_irl_only: true,
+            auto_relay_stop_grace_secs: 120,
+            grace_action: GraceAction::Notify,
+            grace_notice_interval_secs: 60,
+            poll_interval_secs: 30,
+            stop_relay_on_exit: false,
         }
     }
 }
@@ -104,12 +308,83 @@ impl Default for AegisConfig {
 #[serde(default)]
 pub struct NetworkConfig {
     pub latency_target: String,
+    /// Adapters whose traffic makes up the upload/download totals; empty
+    /// measures the one carrying the default route.
+    pub interfaces: Vec<String>,
+    /// Links of a bonded setup, each reported on its own and combined.
+    pub uplinks: Vec<UplinkConfig>,
+    pub ingest_check: IngestCheckConfig,
 }
 
 impl Default for NetworkConfig {
     fn default() -> Self {
         Self {
             latency_target: "1.1.1.1:443".to_string(),
+            interfaces: Vec::new(),
+            uplinks: Vec::new(),
+            ingest_check: IngestCheckConfig::default(),
+        }
+    }
+}
+
+/// Reachability probe of the streaming ingest, also run on demand from
+/// `/api/v1/network/ingest-check`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct IngestCheckConfig {
+    /// `rtmp://`, `rtmps://` or `srt://` URL, or a bare `host:port` checked
+    /// over TCP. Unset probes the active Aegis relay, else Twitch's ingest.
+    pub target: Option<String>,
+    /// 0 only probes on request.
+    pub interval_secs: u64,
+    pub timeout_ms: u64,
+    /// Alert when the ingest stops answering, and again once it is back.
+    pub alert: bool,
+}
+
+impl Default for IngestCheckConfig {
+    fn default() -> Self {
+        Self {
+            target: None,
+            interval_secs: 300,
+            timeout_ms: 3000,
+            alert: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct UplinkConfig {
+    pub name: String,
+    /// Adapter the link's throughput is read from.
+    pub interface: Option<String>,
+    /// Local address probes are sent from; without it probes go through
+    /// `interface`, which only works on Linux.
+    pub ip: Option<String>,
+    /// Defaults to `network.latency_target`.
+    pub probe_target: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MetricsConfig {
+    pub interval_ms: u64,
+    /// Collect every `idle_interval_ms` instead while OBS is not live and either OBS
+    /// is closed or nothing (WebSocket, IPC, exporter) is consuming frames.
+    pub adaptive: bool,
+    pub idle_interval_ms: u64,
+    /// A frame older than this is marked stale: collection has stopped.
+    pub stale_after_secs: u64,
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            interval_ms: 500,
+            adaptive: true,
+            idle_interval_ms: 5000,
+            stale_after_secs: 15,
         }
     }
 }
@@ -119,6 +394,10 @@ impl Default for NetworkConfig {
 pub struct StartupConfig {
     pub enable_autostart: bool,
     pub app_name: String,
+    pub method: AutostartMethod,
+    /// Task Scheduler only: run the logon task elevated, for setups where OBS
+    /// runs as administrator. Creating the task needs an elevated prompt.
+    pub highest_privileges: bool,
 }
 
 impl Default for StartupConfig {
@@ -126,6 +405,8 @@ impl Default for StartupConfig {
         Self {
             enable_autostart: false,
             app_name: "Telemy".to_string(),
+            method: AutostartMethod::default(),
+            highest_privileges: false,
         }
     }
 }
@@ -134,11 +415,214 @@ impl Default for StartupConfig {
 #[serde(default)]
 pub struct TrayConfig {
     pub enable: bool,
+    /// Scene the tray's quick switch goes to.
+    pub brb_scene: String,
 }
 
 impl Default for TrayConfig {
     fn default() -> Self {
-        Self { enable: true }
+        Self {
+            enable: true,
+            brb_scene: "BRB".to_string(),
+        }
+    }
+}
+
+/// Desktop notifications: rule and script alerts, plus the built-in events
+/// below, which the tray's "Notify on" menu toggles.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    pub enable: bool,
+    pub on_disconnect: bool,
+    pub on_high_drops: bool,
+    pub on_low_disk: bool,
+    /// Warn when `health_trend` turns `degrading` while live.
+    pub on_degrading: bool,
+    /// Dropped-frame fraction of the main stream that counts as high.
+    pub drop_pct_threshold: f32,
+    pub low_disk_mb: f64,
+    /// Minimum gap between two notifications of the same kind.
+    pub cooldown_secs: u64,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            enable: true,
+            on_disconnect: true,
+            on_high_drops: true,
+            on_low_disk: true,
+            on_degrading: true,
+            drop_pct_threshold: 0.02,
+            low_disk_mb: 5120.0,
+            cooldown_secs: 300,
+        }
+    }
+}
+
+/// What to do about a recording running out of space, going by OBS's
+/// `available_disk_space_mb`; warnings are `notifications.low_disk_mb`. 0 turns
+/// an action off.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DiskGuardConfig {
+    /// Restart the recording in `fallback_path` below this many MB.
+    pub switch_path_below_mb: f64,
+    pub fallback_path: Option<String>,
+    /// Pause the recording below this many MB.
+    pub pause_recording_below_mb: f64,
+}
+
+impl Default for DiskGuardConfig {
+    fn default() -> Self {
+        Self {
+            switch_path_below_mb: 0.0,
+            fallback_path: None,
+            pause_recording_below_mb: 0.0,
+        }
+    }
+}
+
+/// Steps the profile's streaming bitrate down while the stream degrades and
+/// back up once it recovers, for the next stream start, at most once per
+/// stream. The dock's `low_quality_fallback` toggle overrides `enabled`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LowQualityFallbackConfig {
+    pub enabled: bool,
+    /// Each step lowers or raises the bitrate by this much.
+    pub step_kbps: u32,
+    pub min_kbps: u32,
+    /// Health below this counts as degraded, as does a `degrading` trend.
+    pub min_health: f32,
+    /// How long the stream must stay degraded before it steps down.
+    pub degrade_secs: u64,
+    /// How long it must stay healthy before it steps back up.
+    pub recover_secs: u64,
+}
+
+impl Default for LowQualityFallbackConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            step_kbps: 1000,
+            min_kbps: 1500,
+            min_health: 0.9,
+            degrade_secs: 5,
+            recover_secs: 30,
+        }
+    }
+}
+
+/// Scene failover on the incoming bitrate, the way IRL bonding setups do it:
+/// `low_scene` while the bitrate sags, `tray.brb_scene` once it is all but
+/// gone, and back to `live_scene` after it recovers. Each tier has its own
+/// enter and exit threshold so a bitrate hovering around one does not flap.
+/// The dock's `auto_scene_switch` toggle overrides `enabled`.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FailoverConfig {
+    pub enabled: bool,
+    pub live_scene: String,
+    /// Low-quality or audio-only scene; `None` fails straight over to BRB.
+    pub low_scene: Option<String>,
+    pub low_enter_kbps: u32,
+    pub low_exit_kbps: u32,
+    pub brb_enter_kbps: u32,
+    pub brb_exit_kbps: u32,
+    /// How long the bitrate must stay past a threshold before switching.
+    pub hold_secs: u64,
+}
+
+impl Default for FailoverConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            live_scene: "Live".to_string(),
+            low_scene: None,
+            low_enter_kbps: 1500,
+            low_exit_kbps: 2500,
+            brb_enter_kbps: 300,
+            brb_exit_kbps: 800,
+            hold_secs: 3,
+        }
+    }
+}
+
+/// Twitch chat bot: answers `!bitrate` and `!health` and announces switches
+/// to and from `tray.brb_scene` while live.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TwitchConfig {
+    pub enabled: bool,
+    /// Channel to join, without the `#`.
+    pub channel: String,
+    /// Login of the account the bot chats as.
+    pub username: String,
+    /// Vault key holding the bot account's chat OAuth token.
+    pub oauth_token_key: String,
+    /// Twitch IRC over WebSocket.
+    pub server: String,
+    pub commands: bool,
+    /// Minimum gap between two answers to the same command.
+    pub command_cooldown_secs: u64,
+    pub announce_brb: bool,
+    pub brb_message: String,
+    pub recovered_message: String,
+}
+
+impl Default for TwitchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            channel: String::new(),
+            username: String::new(),
+            oauth_token_key: "twitch_oauth".to_string(),
+            server: "wss://irc-ws.chat.twitch.tv:443".to_string(),
+            commands: true,
+            command_cooldown_secs: 10,
+            announce_brb: true,
+            brb_message: "Connection trouble, switching to BRB. Hang tight!".to_string(),
+            recovered_message: "We're back!".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    /// One JSON object per event, on stderr and in the files, for Loki/ELK.
+    Json,
+}
+
+/// Bridge logs go to stderr and, for `serve`, to daily files under the data dir.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Default level; `RUST_LOG`, when set, replaces this and `modules`.
+    pub level: String,
+    /// Levels per tracing target, e.g. `obws = "warn"`.
+    pub modules: BTreeMap<String, String>,
+    pub format: LogFormat,
+    pub file: bool,
+    pub dir: Option<String>,
+    /// Daily files kept; older ones are deleted on rotation.
+    pub max_files: usize,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            modules: BTreeMap::new(),
+            format: LogFormat::default(),
+            file: true,
+            dir: None,
+            max_files: 7,
+        }
     }
 }
 
@@ -148,6 +632,7 @@ pub struct ThemeConfig {
     pub font_family: String,
     pub bg: String,
     pub panel: String,
+    pub text: String,
     pub muted: String,
     pub good: String,
     pub warn: String,
@@ -161,6 +646,7 @@ impl Default for ThemeConfig {
             font_family: "Arial, sans-serif".to_string(),
             bg: "#0b0e12".to_string(),
             panel: "#111723".to_string(),
+            text: "#e6f0ff".to_string(),
             muted: "#8da3c1".to_string(),
             good: "#33d17a".to_string(),
             warn: "#f6d32d".to_string(),
@@ -170,104 +656,614 @@ impl Default for ThemeConfig {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, Serialize, Default)]
-#[serde(default)]
-pub struct VaultConfig {
-    pub path: Option<String>,
+impl ThemeConfig {
+    /// Palettes offered on the settings page. `dark` is the default theme.
+    pub fn presets() -> [(&'static str, ThemeConfig); 3] {
+        let palette = |colors: [&str; 8]| {
+            let [bg, panel, text, muted, good, warn, bad, line] = colors.map(str::to_string);
+            ThemeConfig {
+                font_family: "Arial, sans-serif".to_string(),
+                bg,
+                panel,
+                text,
+                muted,
+                good,
+                warn,
+                bad,
+                line,
+            }
+        };
+        [
+            ("dark", ThemeConfig::default()),
+            (
+                "light",
+                palette([
+                    "#f4f6fa", "#ffffff", "#1b2230", "#5b6b82", "#1a7f37", "#9a6700", "#cf222e",
+                    "#d0d7e2",
+                ]),
+            ),
+            (
+                "high_contrast",
+                palette([
+                    "#000000", "#000000", "#ffffff", "#ffff00", "#00ff00", "#ffff00", "#ff4040",
+                    "#ffffff",
+                ]),
+            ),
+        ]
+    }
+
+    /// `(name, value)` for every field, in the order the settings page shows them.
+    pub fn fields(&self) -> [(&'static str, &str); 9] {
+        [
+            ("font_family", &self.font_family),
+            ("bg", &self.bg),
+            ("panel", &self.panel),
+            ("text", &self.text),
+            ("muted", &self.muted),
+            ("good", &self.good),
+            ("warn", &self.warn),
+            ("bad", &self.bad),
+            ("line", &self.line),
+        ]
+    }
 }
 
-impl Config {
-    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
-        // Start with default config
-        let mut config = Self::default();
-        let config_path = active_config_path();
+/// A top-level card on the `/obs` dashboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DashboardPanel {
+    /// Connection, system and encoder summary, with the expandable diagnostics.
+    Summary,
+    /// Aegis relay start/stop and the IPC scene switch.
+    Controls,
+    /// OBS plugin link stats.
+    Link,
+    Outputs,
+    Audio,
+    Logs,
+    /// Other instances followed in hub mode (`[[peers]]`).
+    Peers,
+    /// Recent actions from `/api/v1/audit`.
+    Activity,
+}
 
-        // Load from file if it exists
-        if let Ok(raw) = fs::read_to_string(&config_path) {
-            if let Ok(file_config) = toml::from_str::<Config>(&raw) {
-                config = file_config;
-            }
+impl DashboardPanel {
+    pub const ALL: [DashboardPanel; 8] = [
+        DashboardPanel::Summary,
+        DashboardPanel::Controls,
+        DashboardPanel::Link,
+        DashboardPanel::Outputs,
+        DashboardPanel::Audio,
+        DashboardPanel::Logs,
+        DashboardPanel::Peers,
+        DashboardPanel::Activity,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DashboardPanel::Summary => "summary",
+            DashboardPanel::Controls => "controls",
+            DashboardPanel::Link => "link",
+            DashboardPanel::Outputs => "outputs",
+            DashboardPanel::Audio => "audio",
+            DashboardPanel::Logs => "logs",
+            DashboardPanel::Peers => "peers",
+            DashboardPanel::Activity => "activity",
         }
+    }
+}
 
-        // Override with environment variables
-        config.apply_env_overrides()?;
+impl std::str::FromStr for DashboardPanel {
+    type Err = String;
 
-        config.validate()?;
-        Ok(config)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|panel| panel.as_str() == s)
+            .ok_or_else(|| format!("unknown dashboard panel \"{s}\""))
     }
+}
 
-    fn apply_env_overrides(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        // OBS settings
-        if let Ok(val) = env::var(format!("{}OBS_HOST", ENV_PREFIX)) {
-            self.obs.host = val;
-        }
-        if let Ok(val) = env::var(format!("{}OBS_PORT", ENV_PREFIX)) {
-            if let Ok(port) = val.parse() {
-                self.obs.port = port;
-            }
-        }
-        if let Ok(val) = env::var(format!("{}OBS_PASSWORD_KEY", ENV_PREFIX)) {
-            self.obs.password_key = Some(val);
-        }
-        if let Ok(val) = env::var(format!("{}OBS_AUTO_DETECT", ENV_PREFIX)) {
-            self.obs.auto_detect_process = val.parse().unwrap_or(true);
-        }
-
-        // Server settings
-        if let Ok(val) = env::var(format!("{}SERVER_PORT", ENV_PREFIX)) {
-            if let Ok(port) = val.parse() {
-                self.server.port = port;
-            }
-        }
-        if let Ok(val) = env::var(format!("{}SERVER_TOKEN", ENV_PREFIX)) {
-            self.server.token = Some(val);
-        }
+/// Layout of the `/obs` dashboard, read on every page load.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DashboardConfig {
+    /// Panels in display order; panels left out are hidden.
+    pub panels: Vec<DashboardPanel>,
+    /// Show "Expanded Diagnostics" inside the summary panel.
+    pub diagnostics: bool,
+    /// Initial state of the "Hide inactive outputs" toggle.
+    pub hide_inactive_outputs: bool,
+    /// Page templates (`dashboard.html`, `dock.html`, `settings.html`) that
+    /// replace the built-in ones; defaults to `templates` under the data dir.
+    pub templates_dir: Option<String>,
+}
 
-        // Vault settings
-        if let Ok(val) = env::var(format!("{}VAULT_PATH", ENV_PREFIX)) {
-            self.vault.path = Some(val);
+impl Default for DashboardConfig {
+    fn default() -> Self {
+        Self {
+            panels: DashboardPanel::ALL.to_vec(),
+            diagnostics: true,
+            hide_inactive_outputs: false,
+            templates_dir: None,
         }
+    }
+}
 
-        // Grafana settings
-        if let Ok(val) = env::var(format!("{}GRAFANA_ENABLED", ENV_PREFIX)) {
-            self.grafana.enabled = val.parse().unwrap_or(false);
-        }
-        if let Ok(val) = env::var(format!("{}GRAFANA_ENDPOINT", ENV_PREFIX)) {
-            self.grafana.endpoint = Some(val);
-        }
-        if let Ok(val) = env::var(format!("{}GRAFANA_AUTH_VALUE_KEY", ENV_PREFIX)) {
-            self.grafana.auth_value_key = Some(val);
-        }
-        if let Ok(val) = env::var(format!("{}GRAFANA_PUSH_INTERVAL_MS", ENV_PREFIX)) {
-            if let Ok(interval) = val.parse() {
-                self.grafana.push_interval_ms = interval;
-            }
-        }
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RecorderConfig {
+    pub enabled: bool,
+    pub dir: Option<String>,
+    pub max_file_mb: u64,
+    pub max_files: usize,
+    /// Recordings older than this are deleted; 0 keeps them until `max_files` is hit.
+    pub retention_days: u64,
+    /// Record at most one frame per interval; 0 records every frame.
+    pub interval_ms: u64,
+    /// Rows per `/api/v1/history/export.csv` download.
+    pub export_max_rows: usize,
+}
 
-        // Aegis control-plane settings
-        if let Ok(val) = env::var(format!("{}AEGIS_ENABLED", ENV_PREFIX)) {
-            self.aegis.enabled = val.parse().unwrap_or(false);
-        }
-        if let Ok(val) = env::var(format!("{}AEGIS_BASE_URL", ENV_PREFIX)) {
-            self.aegis.base_url = Some(val);
-        }
-        if let Ok(val) = env::var(format!("{}AEGIS_ACCESS_JWT_KEY", ENV_PREFIX)) {
-            self.aegis.access_jwt_key = Some(val);
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: None,
+            max_file_mb: 50,
+            max_files: 20,
+            retention_days: 14,
+            interval_ms: 0,
+            export_max_rows: 100_000,
         }
+    }
+}
 
-        // Network settings
-        if let Ok(val) = env::var(format!("{}LATENCY_TARGET", ENV_PREFIX)) {
-            self.network.latency_target = val;
-        }
+/// Ships tracing events (and optionally the OBS log) to a Loki push endpoint,
+/// e.g. Grafana Cloud Logs. Auth mirrors `[grafana]`: the header value lives in the vault.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct LokiConfig {
+    pub enabled: bool,
+    pub endpoint: Option<String>,
+    pub auth_header: String,
+    pub auth_value_key: Option<String>,
+    pub push_interval_ms: u64,
+    pub batch_max: usize,
+    pub labels: HashMap<String, String>,
+    /// Also ship the OBS log lines `[obs]` follows (see `obs.log_dir`).
+    pub ship_obs_log: bool,
+}
 
-        // Startup settings
-        if let Ok(val) = env::var(format!("{}AUTOSTART", ENV_PREFIX)) {
-            self.startup.enable_autostart = val.parse().unwrap_or(false);
+impl Default for LokiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: None,
+            auth_header: "Authorization".to_string(),
+            auth_value_key: None,
+            push_interval_ms: 5000,
+            batch_max: 500,
+            labels: HashMap::new(),
+            ship_obs_log: false,
         }
+    }
+}
 
-        // Tray settings
-        if let Ok(val) = env::var(format!("{}TRAY_ENABLE", ENV_PREFIX)) {
-            self.tray.enable = val.parse().unwrap_or(true);
+/// `[[webhooks]]`: POSTs a templated payload for services without a native
+/// exporter. See `exporters::webhook::Template` for the syntax.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    pub name: String,
+    pub enabled: bool,
+    pub url: String,
+    /// Send on every Nth frame (`metrics.interval_ms` apart); 0 only sends on events.
+    pub every_n_frames: u64,
+    /// `scripting::FRAME_EVENTS` that trigger a send, e.g. `stream_started`.
+    pub on_events: Vec<String>,
+    /// Payload template; defaults to the event and the whole frame as JSON.
+    pub template: Option<String>,
+    pub content_type: String,
+    pub headers: HashMap<String, String>,
+    pub auth_header: String,
+    /// Vault key holding the `auth_header` value.
+    pub auth_value_key: Option<String>,
+    pub timeout_ms: u64,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            name: "webhook".to_string(),
+            enabled: true,
+            url: String::new(),
+            every_n_frames: 0,
+            on_events: Vec::new(),
+            template: None,
+            content_type: "application/json".to_string(),
+            headers: HashMap::new(),
+            auth_header: "Authorization".to_string(),
+            auth_value_key: None,
+            timeout_ms: 5000,
+        }
+    }
+}
+
+/// `[[peers]]`: another Telemy instance whose frames this one follows and
+/// aggregates under `/api/v1/peers` (hub mode).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct PeerConfig {
+    pub name: String,
+    pub enabled: bool,
+    /// The peer's base URL, e.g. `http://10.0.0.12:7070`.
+    pub url: String,
+    /// Vault key holding an API token with `telemetry_read` on the peer.
+    pub token_key: Option<String>,
+}
+
+impl Default for PeerConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            enabled: true,
+            url: String::new(),
+            token_key: None,
+        }
+    }
+}
+
+/// Announces the dashboard on the LAN over mDNS as `_telemy._tcp`.
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct DiscoveryConfig {
+    pub enabled: bool,
+    /// Advertised instance name; defaults to the host name.
+    pub instance_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ReportsConfig {
+    pub enabled: bool,
+    pub dir: Option<String>,
+    pub max_reports: usize,
+}
+
+impl Default for ReportsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: None,
+            max_reports: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ScriptingConfig {
+    pub enabled: bool,
+    /// Rhai script paths, loaded in order at startup.
+    pub scripts: Vec<String>,
+    /// Per-call operation budget, so a runaway loop cannot wedge the script thread.
+    pub max_operations: u64,
+}
+
+impl Default for ScriptingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            scripts: Vec::new(),
+            max_operations: 1_000_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct IpcConfig {
+    /// Require the plugin to answer an HMAC challenge after `hello`.
+    pub auth_required: bool,
+    /// Vault key of the shared secret; generated on first start if missing.
+    pub secret_key: String,
+    /// Loopback address for the TCP debug transport, e.g. `127.0.0.1:7290`.
+    /// Off unless set.
+    pub tcp_listen: Option<String>,
+    /// Vault key of the token TCP clients must prove they hold; generated on
+    /// first start if missing.
+    pub tcp_token_key: String,
+}
+
+impl Default for IpcConfig {
+    fn default() -> Self {
+        Self {
+            auth_required: false,
+            secret_key: "ipc_shared_secret".to_string(),
+            tcp_listen: None,
+            tcp_token_key: "ipc_tcp_token".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RuleConfig {
+    pub name: String,
+    pub enabled: bool,
+    /// Every condition must hold, each for at least its own `for_secs`.
+    pub when: Vec<RuleCondition>,
+    /// Run in order each time the rule fires.
+    pub actions: Vec<RuleAction>,
+    /// Minimum time between two firings.
+    pub cooldown_secs: u64,
+}
+
+impl Default for RuleConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            enabled: true,
+            when: Vec::new(),
+            actions: Vec::new(),
+            cooldown_secs: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RuleCondition {
+    /// One of `rules::METRICS`, e.g. `stream.bitrate_kbps`.
+    pub metric: String,
+    pub op: CompareOp,
+    pub value: f64,
+    #[serde(default)]
+    pub for_secs: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum CompareOp {
+    #[serde(rename = "<")]
+    Lt,
+    #[serde(rename = "<=")]
+    Le,
+    #[serde(rename = ">")]
+    Gt,
+    #[serde(rename = ">=")]
+    Ge,
+    #[serde(rename = "==")]
+    Eq,
+    #[serde(rename = "!=")]
+    Ne,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RuleAction {
+    SwitchScene {
+        scene: String,
+    },
+    /// Starts an Aegis relay session unless one is already live.
+    StartRelay,
+    Alert {
+        message: String,
+    },
+    /// Runs a program directly (no shell) without waiting for it to exit.
+    RunCommand {
+        command: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    /// Shows (`enabled = true`) or hides a source; omit `enabled` to flip it.
+    ToggleSource {
+        scene: String,
+        source: String,
+        #[serde(default)]
+        enabled: Option<bool>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AutostartMethod {
+    /// The HKCU `Run` key; always starts unelevated.
+    #[default]
+    Registry,
+    /// A Task Scheduler logon task, which can run with highest privileges.
+    TaskScheduler,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VaultBackend {
+    /// DPAPI on Windows; elsewhere values are only base64-encoded.
+    #[default]
+    Os,
+    /// ChaCha20-Poly1305 under a key from `TELEMY_VAULT_PASSPHRASE` or a key file.
+    Encrypted,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(default)]
+pub struct VaultConfig {
+    pub path: Option<String>,
+    /// Prepended to every vault key; defaults to `<profile>.` when a profile is active.
+    pub key_prefix: Option<String>,
+    pub backend: VaultBackend,
+    /// Machine key for the `encrypted` backend when no passphrase is set;
+    /// defaults to `vault.key` next to the vault file.
+    pub key_file: Option<String>,
+}
+
+impl VaultConfig {
+    pub fn effective_key_prefix(&self) -> Option<String> {
+        self.key_prefix
+            .clone()
+            .filter(|prefix| !prefix.is_empty())
+            .or_else(|| active_profile().map(|name| format!("{name}.")))
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Self, Box<dyn std::error::Error>> {
+        Self::load_from(&active_config_path())
+    }
+
+    fn load_from(config_path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        // Start with default config
+        let mut config = Self::default();
+
+        // Load from file if it exists
+        if let Ok(raw) = fs::read_to_string(config_path) {
+            if let Ok(file_config) = toml::from_str::<Config>(&raw) {
+                config = file_config;
+            }
+        }
+
+        // Override with environment variables
+        config.apply_env_overrides()?;
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn apply_env_overrides(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        // OBS settings
+        if let Ok(val) = env::var(format!("{}OBS_HOST", ENV_PREFIX)) {
+            self.obs.host = val;
+        }
+        if let Ok(val) = env::var(format!("{}OBS_PORT", ENV_PREFIX)) {
+            if let Ok(port) = val.parse() {
+                self.obs.port = port;
+            }
+        }
+        if let Ok(val) = env::var(format!("{}OBS_PASSWORD_KEY", ENV_PREFIX)) {
+            self.obs.password_key = Some(val);
+        }
+        if let Ok(val) = env::var(format!("{}OBS_AUTO_DETECT", ENV_PREFIX)) {
+            self.obs.auto_detect_process = val.parse().unwrap_or(true);
+        }
+
+        // Server settings
+        if let Ok(val) = env::var(format!("{}SERVER_PORT", ENV_PREFIX)) {
+            if let Ok(port) = val.parse() {
+                self.server.port = port;
+            }
+        }
+        if let Ok(val) = env::var(format!("{}SERVER_TOKEN", ENV_PREFIX)) {
+            self.server.token = Some(val.into());
+        }
+        if let Ok(val) = env::var(format!("{}SERVER_RATE_LIMIT_ENABLED", ENV_PREFIX)) {
+            self.server.rate_limit.enabled = val.parse().unwrap_or(true);
+        }
+
+        // Vault settings
+        if let Ok(val) = env::var(format!("{}VAULT_PATH", ENV_PREFIX)) {
+            self.vault.path = Some(val);
+        }
+
+        // Grafana settings
+        if let Ok(val) = env::var(format!("{}GRAFANA_ENABLED", ENV_PREFIX)) {
+            self.grafana.enabled = val.parse().unwrap_or(false);
+        }
+        if let Ok(val) = env::var(format!("{}GRAFANA_ENDPOINT", ENV_PREFIX)) {
+            self.grafana.endpoint = Some(val);
+        }
+        if let Ok(val) = env::var(format!("{}GRAFANA_AUTH_VALUE_KEY", ENV_PREFIX)) {
+            self.grafana.auth_value_key = Some(val);
+        }
+        if let Ok(val) = env::var(format!("{}GRAFANA_PUSH_INTERVAL_MS", ENV_PREFIX)) {
+            if let Ok(interval) = val.parse() {
+                self.grafana.push_interval_ms = interval;
+            }
+        }
+        if let Ok(val) = env::var(format!("{}GRAFANA_INSTANCE_ID", ENV_PREFIX)) {
+            self.grafana.instance_id = Some(val);
+        }
+        if let Ok(val) = env::var(format!("{}GRAFANA_STREAMER_NAME", ENV_PREFIX)) {
+            self.grafana.streamer_name = Some(val);
+        }
+
+        // Aegis control-plane settings
+        if let Ok(val) = env::var(format!("{}AEGIS_ENABLED", ENV_PREFIX)) {
+            self.aegis.enabled = val.parse().unwrap_or(false);
+        }
+        if let Ok(val) = env::var(format!("{}AEGIS_BASE_URL", ENV_PREFIX)) {
+            self.aegis.base_url = Some(val);
+        }
+        if let Ok(val) = env::var(format!("{}AEGIS_ACCESS_JWT_KEY", ENV_PREFIX)) {
+            self.aegis.access_jwt_key = Some(val);
+        }
+        if let Ok(val) = env::var(format!("{}AEGIS_AUTO_RELAY", ENV_PREFIX)) {
+            self.aegis.auto_relay = val.parse().unwrap_or(false);
+        }
+        if let Ok(val) = env::var(format!("{}AEGIS_AUTO_RELAY_STOP_GRACE_SECS", ENV_PREFIX)) {
+            if let Ok(secs) = val.parse() {
+                self.aegis.auto_relay_stop_grace_secs = secs;
+            }
+        }
+
+        // Recorder settings
+        if let Ok(val) = env::var(format!("{}RECORDER_ENABLED", ENV_PREFIX)) {
+            self.recorder.enabled = val.parse().unwrap_or(false);
+        }
+        if let Ok(val) = env::var(format!("{}RECORDER_DIR", ENV_PREFIX)) {
+            self.recorder.dir = Some(val);
+        }
+
+        // Report settings
+        if let Ok(val) = env::var(format!("{}REPORTS_ENABLED", ENV_PREFIX)) {
+            self.reports.enabled = val.parse().unwrap_or(true);
+        }
+        if let Ok(val) = env::var(format!("{}REPORTS_DIR", ENV_PREFIX)) {
+            self.reports.dir = Some(val);
+        }
+
+        // Loki settings
+        if let Ok(val) = env::var(format!("{}LOKI_ENABLED", ENV_PREFIX)) {
+            self.loki.enabled = val.parse().unwrap_or(false);
+        }
+        if let Ok(val) = env::var(format!("{}LOKI_ENDPOINT", ENV_PREFIX)) {
+            self.loki.endpoint = Some(val);
+        }
+        if let Ok(val) = env::var(format!("{}LOKI_AUTH_VALUE_KEY", ENV_PREFIX)) {
+            self.loki.auth_value_key = Some(val);
+        }
+        if let Ok(val) = env::var(format!("{}LOKI_SHIP_OBS_LOG", ENV_PREFIX)) {
+            self.loki.ship_obs_log = val.parse().unwrap_or(false);
+        }
+
+        // Network settings
+        if let Ok(val) = env::var(format!("{}LATENCY_TARGET", ENV_PREFIX)) {
+            self.network.latency_target = val;
+        }
+
+        // Startup settings
+        if let Ok(val) = env::var(format!("{}AUTOSTART", ENV_PREFIX)) {
+            self.startup.enable_autostart = val.parse().unwrap_or(false);
+        }
+
+        // Tray settings
+        if let Ok(val) = env::var(format!("{}TRAY_ENABLE", ENV_PREFIX)) {
+            self.tray.enable = val.parse().unwrap_or(true);
+        }
+        if let Ok(val) = env::var(format!("{}NOTIFICATIONS_ENABLE", ENV_PREFIX)) {
+            self.notifications.enable = val.parse().unwrap_or(true);
+        }
+
+        // Logging settings
+        if let Ok(val) = env::var(format!("{}LOG_LEVEL", ENV_PREFIX)) {
+            self.logging.level = val;
+        }
+        if let Ok(val) = env::var(format!("{}LOG_FORMAT", ENV_PREFIX)) {
+            self.logging.format = match val.as_str() {
+                "json" => LogFormat::Json,
+                _ => LogFormat::Text,
+            };
+        }
+        if let Ok(val) = env::var(format!("{}LOG_DIR", ENV_PREFIX)) {
+            self.logging.dir = Some(val);
         }
 
         Ok(())
@@ -277,9 +1273,28 @@ impl Config {
         if self.obs.port == 0 {
             return Err("obs.port must be non-zero".into());
         }
+        if self
+            .obs
+            .restart_path
+            .as_deref()
+            .is_some_and(|p| p.trim().is_empty())
+        {
+            return Err("obs.restart_path must not be empty".into());
+        }
         if self.server.port == 0 {
             return Err("server.port must be non-zero".into());
         }
+        if self.server.bind.parse::<std::net::IpAddr>().is_err() {
+            return Err("server.bind must be an IP address".into());
+        }
+        if self
+            .discovery
+            .instance_name
+            .as_deref()
+            .is_some_and(|n| n.trim().is_empty())
+        {
+            return Err("discovery.instance_name must not be empty".into());
+        }
         if self.grafana.enabled {
             if self.grafana.endpoint.as_deref().unwrap_or("").is_empty() {
                 return Err("grafana.endpoint is required when grafana.enabled = true".into());
@@ -293,6 +1308,75 @@ impl Config {
                 return Err("grafana.push_interval_ms must be >= 500".into());
             }
         }
+        if self.server.rate_limit.enabled {
+            if self.server.rate_limit.requests_per_minute == 0 {
+                return Err("server.rate_limit.requests_per_minute must be > 0".into());
+            }
+            if self.server.rate_limit.max_failures == 0 {
+                return Err("server.rate_limit.max_failures must be > 0".into());
+            }
+        }
+        for entry in self
+            .server
+            .access
+            .allow
+            .iter()
+            .chain(&self.server.access.trusted_proxies)
+        {
+            entry
+                .parse::<crate::server::IpNet>()
+                .map_err(|err| format!("server.access: {err}"))?;
+        }
+        let cors = &self.server.cors;
+        if cors.allowed_origins.len() > 1 && cors.allowed_origins.iter().any(|o| o == "*") {
+            return Err(
+                "server.cors.allowed_origins: \"*\" cannot be combined with other origins".into(),
+            );
+        }
+        for origin in cors.allowed_origins.iter().filter(|o| *o != "*") {
+            if !(origin.starts_with("http://") || origin.starts_with("https://"))
+                || origin.trim_end_matches('/') != origin
+                || axum::http::HeaderValue::from_str(origin).is_err()
+            {
+                return Err(format!(
+                    "server.cors.allowed_origins: \"{origin}\" is not an origin like https://host[:port]"
+                )
+                .into());
+            }
+        }
+        for name in &cors.allowed_headers {
+            axum::http::HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| format!("server.cors.allowed_headers: invalid header \"{name}\""))?;
+        }
+        if !crate::server::LANGUAGES.contains(&self.server.language.as_str()) {
+            return Err(format!(
+                "server.language must be one of {}",
+                crate::server::LANGUAGES.join(", ")
+            )
+            .into());
+        }
+        // Pasted into page <style> blocks, so nothing that could end the rule or the tag.
+        for (name, value) in self.theme.fields() {
+            if value.trim().is_empty() || value.contains([';', '{', '}', '<', '>', '\\']) {
+                return Err(format!("theme.{name} is not a valid CSS value: {value:?}").into());
+            }
+        }
+        for (i, panel) in self.dashboard.panels.iter().enumerate() {
+            if self.dashboard.panels[..i].contains(panel) {
+                return Err(format!("dashboard.panels lists \"{}\" twice", panel.as_str()).into());
+            }
+        }
+        if self.recorder.export_max_rows == 0 {
+            return Err("recorder.export_max_rows must be > 0".into());
+        }
+        if self.loki.enabled {
+            if self.loki.endpoint.as_deref().unwrap_or("").trim().is_empty() {
+                return Err("loki.endpoint is required when loki.enabled = true".into());
+            }
+            if self.loki.push_interval_ms < 500 {
+                return Err("loki.push_interval_ms must be >= 500".into());
+            }
+        }
         if self.aegis.enabled {
             if self.aegis.base_url.as_deref().unwrap_or("").trim().is_empty() {
                 return Err("aegis.base_url is required when aegis.enabled = true".into());
@@ -301,9 +1385,218 @@ impl Config {
                 return Err("aegis.access_jwt_key is required when aegis.enabled = true".into());
             }
         }
+        if self.aegis.auto_relay && !self.aegis.enabled {
+            return Err("aegis.auto_relay requires aegis.enabled = true".into());
+        }
+        if self.aegis.grace_notice_interval_secs == 0 {
+            return Err("aegis.grace_notice_interval_secs must be > 0".into());
+        }
+        if self.aegis.poll_interval_secs < 5 {
+            return Err("aegis.poll_interval_secs must be >= 5".into());
+        }
+        if self.recorder.enabled {
+            if self.recorder.max_file_mb == 0 {
+                return Err("recorder.max_file_mb must be > 0".into());
+            }
+            if self.recorder.max_files == 0 {
+                return Err("recorder.max_files must be > 0".into());
+            }
+        }
+        let threshold = self.notifications.drop_pct_threshold;
+        if !(threshold > 0.0 && threshold <= 1.0) {
+            return Err("notifications.drop_pct_threshold must be in (0, 1]".into());
+        }
+        if self.notifications.low_disk_mb < 0.0 {
+            return Err("notifications.low_disk_mb must be >= 0".into());
+        }
+        let guard = &self.disk_guard;
+        if guard.switch_path_below_mb < 0.0 || guard.pause_recording_below_mb < 0.0 {
+            return Err("disk_guard thresholds must be >= 0".into());
+        }
+        let fallback = guard.fallback_path.as_deref().unwrap_or("");
+        if guard.switch_path_below_mb > 0.0 && fallback.trim().is_empty() {
+            return Err("disk_guard.fallback_path is required to switch paths".into());
+        }
+        let fallback = &self.low_quality_fallback;
+        if fallback.step_kbps == 0 || fallback.min_kbps == 0 {
+            return Err("low_quality_fallback.step_kbps and min_kbps must be > 0".into());
+        }
+        if !(0.0..=1.0).contains(&fallback.min_health) {
+            return Err("low_quality_fallback.min_health must be in [0, 1]".into());
+        }
+        let failover = &self.failover;
+        if failover.live_scene.trim().is_empty() {
+            return Err("failover.live_scene must not be empty".into());
+        }
+        if failover.brb_exit_kbps <= failover.brb_enter_kbps {
+            return Err("failover.brb_exit_kbps must be above brb_enter_kbps".into());
+        }
+        if let Some(low_scene) = &failover.low_scene {
+            if low_scene.trim().is_empty() {
+                return Err("failover.low_scene must not be empty".into());
+            }
+            if failover.low_exit_kbps <= failover.low_enter_kbps {
+                return Err("failover.low_exit_kbps must be above low_enter_kbps".into());
+            }
+            if failover.low_enter_kbps <= failover.brb_enter_kbps {
+                return Err("failover.low_enter_kbps must be above brb_enter_kbps".into());
+            }
+        }
+        tracing_subscriber::EnvFilter::builder()
+            .parse(crate::logging::directives(&self.logging))
+            .map_err(|err| format!("logging.level/modules: {err}"))?;
+        if self.logging.file && self.logging.max_files == 0 {
+            return Err("logging.max_files must be > 0".into());
+        }
+        if self.startup.highest_privileges && self.startup.method != AutostartMethod::TaskScheduler
+        {
+            return Err(
+                "startup.highest_privileges requires startup.method = \"task_scheduler\"".into(),
+            );
+        }
         if self.network.latency_target.trim().is_empty() {
             return Err("network.latency_target must be set".into());
         }
+        if let Some(target) = &self.network.ingest_check.target {
+            crate::ingest::Target::parse(target)
+                .map_err(|err| format!("network.ingest_check.target: {err}"))?;
+        }
+        if self.network.ingest_check.timeout_ms == 0 {
+            return Err("network.ingest_check.timeout_ms must be non-zero".into());
+        }
+        let uplinks = &self.network.uplinks;
+        for (i, uplink) in uplinks.iter().enumerate() {
+            if uplink.name.trim().is_empty() {
+                return Err(format!("network.uplinks[{i}].name must be set").into());
+            }
+            if uplinks[..i].iter().any(|u| u.name == uplink.name) {
+                return Err(format!("duplicate network.uplinks name: {}", uplink.name).into());
+            }
+            if uplink.interface.is_none() && uplink.ip.is_none() {
+                return Err(format!("network.uplinks[{i}] needs an interface or ip").into());
+            }
+            if let Some(ip) = &uplink.ip {
+                if ip.parse::<std::net::IpAddr>().is_err() {
+                    return Err(format!("network.uplinks[{i}].ip is not an IP address").into());
+                }
+            }
+        }
+        if self.metrics.interval_ms < 100 {
+            return Err("metrics.interval_ms must be >= 100".into());
+        }
+        if self.metrics.adaptive && self.metrics.idle_interval_ms < self.metrics.interval_ms {
+            return Err("metrics.idle_interval_ms must be >= metrics.interval_ms".into());
+        }
+        let slowest_ms = if self.metrics.adaptive {
+            self.metrics.idle_interval_ms
+        } else {
+            self.metrics.interval_ms
+        };
+        if self.metrics.stale_after_secs.saturating_mul(1000) <= slowest_ms {
+            return Err(
+                "metrics.stale_after_secs must be longer than the slowest collection interval"
+                    .into(),
+            );
+        }
+        if self.scripting.enabled {
+            if self.scripting.scripts.is_empty() {
+                return Err("scripting.scripts is required when scripting.enabled = true".into());
+            }
+            if self.scripting.max_operations == 0 {
+                return Err("scripting.max_operations must be > 0".into());
+            }
+        }
+        if self.twitch.enabled {
+            let twitch = &self.twitch;
+            if twitch.channel.trim().is_empty() || twitch.username.trim().is_empty() {
+                return Err("twitch.channel and twitch.username are required".into());
+            }
+            if twitch.oauth_token_key.trim().is_empty() {
+                return Err("twitch.oauth_token_key is required when twitch.enabled = true".into());
+            }
+            if !twitch.server.starts_with("wss://") && !twitch.server.starts_with("ws://") {
+                return Err("twitch.server must be a ws:// or wss:// URL".into());
+            }
+        }
+        if self.ipc.auth_required && self.ipc.secret_key.trim().is_empty() {
+            return Err("ipc.secret_key is required when ipc.auth_required = true".into());
+        }
+        if let Some(listen) = &self.ipc.tcp_listen {
+            match listen.parse::<std::net::SocketAddr>() {
+                Ok(addr) if addr.ip().is_loopback() => {}
+                _ => return Err("ipc.tcp_listen must be a loopback ip:port".into()),
+            }
+            if self.ipc.tcp_token_key.trim().is_empty() {
+                return Err("ipc.tcp_token_key is required when ipc.tcp_listen is set".into());
+            }
+        }
+        for rule in &self.rules {
+            if rule.name.trim().is_empty() {
+                return Err("rules.name must be set".into());
+            }
+            if rule.when.is_empty() || rule.actions.is_empty() {
+                return Err(format!(
+                    "rule '{}' needs at least one condition and action",
+                    rule.name
+                )
+                .into());
+            }
+            if let Some(cond) = rule
+                .when
+                .iter()
+                .find(|c| !crate::rules::is_known_metric(&c.metric))
+            {
+                return Err(
+                    format!("rule '{}': unknown metric '{}'", rule.name, cond.metric).into(),
+                );
+            }
+            let needs_aegis = rule
+                .actions
+                .iter()
+                .any(|a| matches!(a, RuleAction::StartRelay));
+            if needs_aegis && !self.aegis.enabled {
+                return Err(format!(
+                    "rule '{}': start_relay requires aegis.enabled = true",
+                    rule.name
+                )
+                .into());
+            }
+        }
+        for hook in self.webhooks.iter().filter(|h| h.enabled) {
+            let name = &hook.name;
+            if !hook.url.starts_with("http://") && !hook.url.starts_with("https://") {
+                return Err(format!("webhook '{name}': url must be an http(s) URL").into());
+            }
+            if hook.every_n_frames == 0 && hook.on_events.is_empty() {
+                return Err(format!("webhook '{name}' needs every_n_frames or on_events").into());
+            }
+            if let Some(event) = hook
+                .on_events
+                .iter()
+                .find(|e| !crate::scripting::FRAME_EVENTS.contains(&e.as_str()))
+            {
+                return Err(format!("webhook '{name}': unknown event '{event}'").into());
+            }
+            if let Some(template) = &hook.template {
+                crate::exporters::webhook::Template::parse(template)
+                    .map_err(|e| format!("webhook '{name}': {e}"))?;
+            }
+            if hook.timeout_ms == 0 {
+                return Err(format!("webhook '{name}': timeout_ms must be > 0").into());
+            }
+        }
+        for (i, peer) in self.peers.iter().enumerate() {
+            let name = &peer.name;
+            if name.trim().is_empty() {
+                return Err("every [[peers]] entry needs a name".into());
+            }
+            if self.peers[..i].iter().any(|p| &p.name == name) {
+                return Err(format!("peer '{name}' is listed twice").into());
+            }
+            if !peer.url.starts_with("http://") && !peer.url.starts_with("https://") {
+                return Err(format!("peer '{name}': url must be an http(s) URL").into());
+            }
+        }
         Ok(())
     }
 
@@ -322,7 +1615,10 @@ impl Config {
     }
 
     pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let path = active_config_path();
+        self.save_to(&active_config_path())
+    }
+
+    fn save_to(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
         self.validate()?;
         let data = toml::to_string_pretty(self)?;
         if let Some(parent) = path.parent() {
@@ -335,7 +1631,14 @@ impl Config {
     }
 
     pub fn default_path() -> PathBuf {
-        managed_config_path()
+        CONFIG_PATH_OVERRIDE
+            .get()
+            .cloned()
+            .unwrap_or_else(managed_config_path)
+    }
+
+    pub fn active_path() -> PathBuf {
+        active_config_path()
     }
 }
 
@@ -344,11 +1647,16 @@ fn managed_config_path() -> PathBuf {
         return PathBuf::from(path);
     }
     let appdata = env::var("APPDATA").unwrap_or_else(|_| ".".to_string());
-    Path::new(&appdata).join("Telemy").join(CONFIG_FILE)
+    Path::new(&appdata)
+        .join("Telemy")
+        .join(profile_file_name(active_profile().as_deref()))
 }
 
 fn active_config_path() -> PathBuf {
-    let local = PathBuf::from(CONFIG_FILE);
+    if let Some(path) = CONFIG_PATH_OVERRIDE.get() {
+        return path.clone();
+    }
+    let local = PathBuf::from(profile_file_name(active_profile().as_deref()));
     if local.exists() {
         local
     } else {
@@ -378,6 +1686,26 @@ mod tests {
         assert!(cfg.validate().is_err());
     }
 
+    #[test]
+    fn validate_checks_bind_and_discovery() {
+        let mut cfg = Config::default();
+        cfg.server.bind = "localhost".to_string();
+        assert!(cfg.validate().is_err());
+        cfg.server.bind = "0.0.0.0".to_string();
+        assert!(cfg.validate().is_ok());
+        cfg.discovery.instance_name = Some("".to_string());
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_empty_obs_restart_path() {
+        let mut cfg = Config::default();
+        cfg.obs.restart_path = Some(" ".to_string());
+        assert!(cfg.validate().is_err());
+        cfg.obs.restart_path = Some("/usr/bin/obs".to_string());
+        assert!(cfg.validate().is_ok());
+    }
+
     #[test]
     fn validate_requires_grafana_fields_when_enabled() {
         let mut cfg = Config::default();
@@ -412,6 +1740,338 @@ mod tests {
         cfg.aegis.access_jwt_key = Some("aegis_cp_access_jwt".to_string());
         assert!(cfg.validate().is_ok());
     }
-}
 
+    #[test]
+    fn validate_requires_aegis_enabled_for_auto_relay() {
+        let mut cfg = Config::default();
+        cfg.aegis.auto_relay = true;
+        assert!(cfg.validate().is_err());
+
+        cfg.aegis.enabled = true;
+        cfg.aegis.base_url = Some("https://api.example.test".to_string());
+        cfg.aegis.access_jwt_key = Some("aegis_cp_access_jwt".to_string());
+        assert!(cfg.validate().is_ok());
+    }
+
+    #[test]
+    fn grace_action_parses_and_notice_interval_must_be_positive() {
+        let cfg: Config = toml::from_str("[aegis]\ngrace_action = \"extend\"").unwrap();
+        assert_eq!(cfg.aegis.grace_action, GraceAction::Extend);
 
+        let mut cfg = Config::default();
+        cfg.aegis.grace_notice_interval_secs = 0;
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_aggressive_aegis_poll_interval() {
+        let mut cfg = Config::default();
+        cfg.aegis.poll_interval_secs = 1;
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn validate_checks_rule_metrics_and_actions() {
+        let mut cfg = Config::default();
+        cfg.rules.push(RuleConfig {
+            name: "cpu".to_string(),
+            when: vec![RuleCondition {
+                metric: "system.cpu".to_string(),
+                op: CompareOp::Gt,
+                value: 90.0,
+                for_secs: 30,
+            }],
+            actions: vec![RuleAction::StartRelay],
+            ..RuleConfig::default()
+        });
+        assert!(cfg.validate().is_err());
+
+        cfg.rules[0].when[0].metric = "system.cpu_percent".to_string();
+        assert!(cfg.validate().is_err(), "start_relay needs aegis");
+
+        cfg.rules[0].actions = vec![RuleAction::Alert {
+            message: "CPU is pegged".to_string(),
+        }];
+        assert!(cfg.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_requires_ipc_secret_key_when_auth_required() {
+        let mut cfg = Config::default();
+        cfg.ipc.auth_required = true;
+        assert!(cfg.validate().is_ok());
+
+        cfg.ipc.secret_key = " ".to_string();
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn validate_keeps_ipc_tcp_on_loopback() {
+        let mut cfg = Config::default();
+        cfg.ipc.tcp_listen = Some("127.0.0.1:7290".to_string());
+        assert!(cfg.validate().is_ok());
+        cfg.ipc.tcp_listen = Some("[::1]:7290".to_string());
+        assert!(cfg.validate().is_ok());
+
+        cfg.ipc.tcp_listen = Some("0.0.0.0:7290".to_string());
+        assert!(cfg.validate().is_err());
+        cfg.ipc.tcp_listen = Some("localhost:7290".to_string());
+        assert!(cfg.validate().is_err());
+
+        cfg.ipc.tcp_listen = Some("127.0.0.1:7290".to_string());
+        cfg.ipc.tcp_token_key = String::new();
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn validate_checks_disk_guard() {
+        let mut cfg = Config::default();
+        cfg.disk_guard.switch_path_below_mb = 5120.0;
+        assert!(cfg.validate().is_err(), "no fallback path");
+        cfg.disk_guard.fallback_path = Some("D:/Recordings".to_string());
+        assert!(cfg.validate().is_ok());
+        cfg.disk_guard.pause_recording_below_mb = -1.0;
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn validate_checks_low_quality_fallback() {
+        let mut cfg = Config::default();
+        cfg.low_quality_fallback.step_kbps = 0;
+        assert!(cfg.validate().is_err());
+        cfg.low_quality_fallback.step_kbps = 500;
+        cfg.low_quality_fallback.min_health = 1.5;
+        assert!(cfg.validate().is_err());
+        cfg.low_quality_fallback.min_health = 0.8;
+        assert!(cfg.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_checks_failover_thresholds() {
+        let mut cfg = Config::default();
+        cfg.failover.brb_exit_kbps = cfg.failover.brb_enter_kbps;
+        assert!(cfg.validate().is_err());
+        cfg.failover.brb_exit_kbps = 800;
+        cfg.failover.low_scene = Some("Audio only".to_string());
+        assert!(cfg.validate().is_ok());
+        cfg.failover.low_enter_kbps = 200;
+        assert!(cfg.validate().is_err(), "low tier below BRB");
+    }
+
+    #[test]
+    fn validate_checks_stale_after() {
+        let mut cfg = Config::default();
+        cfg.metrics.stale_after_secs = 5;
+        assert!(cfg.validate().is_err(), "idle frames are 5s apart");
+        cfg.metrics.adaptive = false;
+        assert!(cfg.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_checks_uplinks() {
+        let mut cfg = Config::default();
+        let lte = |name: &str, ip: &str| UplinkConfig {
+            name: name.to_string(),
+            ip: Some(ip.to_string()),
+            ..Default::default()
+        };
+        cfg.network.uplinks = vec![lte("lte1", "192.168.8.100"), lte("lte2", "192.168.9.100")];
+        assert!(cfg.validate().is_ok());
+        cfg.network.uplinks[1].name = "lte1".to_string();
+        assert!(cfg.validate().is_err(), "names must be unique");
+        cfg.network.uplinks[1] = lte("lte2", "modem");
+        assert!(cfg.validate().is_err());
+        cfg.network.uplinks[1].ip = None;
+        assert!(cfg.validate().is_err(), "interface or ip required");
+    }
+
+    #[test]
+    fn validate_checks_ingest_target() {
+        let mut cfg = Config::default();
+        cfg.network.ingest_check.target = Some("srt://relay.example.com:9000".to_string());
+        assert!(cfg.validate().is_ok());
+        cfg.network.ingest_check.target = Some("srt://relay.example.com".to_string());
+        assert!(cfg.validate().is_err(), "srt needs a port");
+        cfg.network.ingest_check.target = Some("udp://relay.example.com:9000".to_string());
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn validate_checks_twitch() {
+        let mut cfg = Config::default();
+        cfg.twitch.enabled = true;
+        assert!(cfg.validate().is_err(), "channel and username are required");
+        cfg.twitch.channel = "streamer".to_string();
+        cfg.twitch.username = "telemy_bot".to_string();
+        assert!(cfg.validate().is_ok());
+        cfg.twitch.server = "irc.chat.twitch.tv:6697".to_string();
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn validate_checks_webhooks() {
+        let mut cfg = Config::default();
+        cfg.webhooks.push(WebhookConfig {
+            url: "ftp://example.com".to_string(),
+            every_n_frames: 10,
+            ..Default::default()
+        });
+        assert!(cfg.validate().is_err(), "url must be http(s)");
+
+        cfg.webhooks[0].url = "https://example.com/hook".to_string();
+        assert!(cfg.validate().is_ok());
+        cfg.webhooks[0].on_events = vec!["stream_begun".to_string()];
+        assert!(cfg.validate().is_err(), "unknown event");
+        cfg.webhooks[0].on_events = vec!["stream_started".to_string()];
+        cfg.webhooks[0].template = Some("{{#if event}}live".to_string());
+        assert!(cfg.validate().is_err(), "unclosed #if");
+        cfg.webhooks[0].template = Some(r#"{"content":"{{event.name}}"}"#.to_string());
+        cfg.webhooks[0].every_n_frames = 0;
+        assert!(cfg.validate().is_ok());
+        cfg.webhooks[0].on_events.clear();
+        assert!(cfg.validate().is_err(), "nothing triggers it");
+    }
+
+    #[test]
+    fn validate_checks_peers() {
+        let mut cfg = Config::default();
+        cfg.peers.push(PeerConfig {
+            name: "rig-a".to_string(),
+            url: "10.0.0.12:7070".to_string(),
+            ..Default::default()
+        });
+        assert!(cfg.validate().is_err(), "url must be http(s)");
+        cfg.peers[0].url = "http://10.0.0.12:7070".to_string();
+        assert!(cfg.validate().is_ok());
+        cfg.peers.push(cfg.peers[0].clone());
+        assert!(cfg.validate().is_err(), "duplicate name");
+        cfg.peers[1].name = " ".to_string();
+        assert!(cfg.validate().is_err(), "blank name");
+    }
+
+    #[test]
+    fn validate_rejects_malformed_access_entries() {
+        let mut cfg = Config::default();
+        cfg.server.access.allow = vec!["127.0.0.1".to_string(), "10.0.0.0/8".to_string()];
+        assert!(cfg.validate().is_ok());
+        cfg.server.access.trusted_proxies = vec!["10.0.0.0/40".to_string()];
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn validate_checks_cors_origins() {
+        let mut cfg = Config::default();
+        cfg.server.cors.allowed_origins = vec!["https://overlay.example.com".to_string()];
+        assert!(cfg.validate().is_ok());
+        cfg.server.cors.allowed_origins = vec!["*".to_string()];
+        assert!(cfg.validate().is_ok());
+        cfg.server.cors.allowed_origins.push("https://a.io".into());
+        assert!(cfg.validate().is_err());
+        cfg.server.cors.allowed_origins = vec!["https://overlay.example.com/".to_string()];
+        assert!(cfg.validate().is_err());
+        cfg.server.cors.allowed_origins = vec!["overlay.example.com".to_string()];
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn validate_checks_the_page_language() {
+        let mut cfg = Config::default();
+        cfg.server.language = "pt".to_string();
+        assert!(cfg.validate().is_ok());
+        cfg.server.language = "fr".to_string();
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn validate_rejects_theme_values_that_escape_the_style_block() {
+        for (_, preset) in ThemeConfig::presets() {
+            let cfg = Config {
+                theme: preset,
+                ..Config::default()
+            };
+            assert!(cfg.validate().is_ok());
+        }
+        let mut cfg = Config::default();
+        cfg.theme.bg = "red; } </style><script>".to_string();
+        assert!(cfg.validate().is_err());
+        cfg.theme.bg = " ".to_string();
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn dashboard_panels_parse_and_reject_duplicates() {
+        let cfg: Config =
+            toml::from_str("[dashboard]\npanels = [\"outputs\", \"summary\"]").unwrap();
+        assert_eq!(
+            cfg.dashboard.panels,
+            [DashboardPanel::Outputs, DashboardPanel::Summary]
+        );
+        assert!(cfg.validate().is_ok());
+        assert!(toml::from_str::<Config>("[dashboard]\npanels = [\"graph\"]").is_err());
+
+        let mut cfg = Config::default();
+        cfg.dashboard.panels.push(DashboardPanel::Logs);
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn validate_requires_task_scheduler_for_highest_privileges() {
+        let mut cfg = Config::default();
+        cfg.startup.highest_privileges = true;
+        assert!(cfg.validate().is_err());
+        cfg.startup.method = AutostartMethod::TaskScheduler;
+        assert!(cfg.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_out_of_range_notification_thresholds() {
+        let mut cfg = Config::default();
+        cfg.notifications.drop_pct_threshold = 0.0;
+        assert!(cfg.validate().is_err());
+        cfg.notifications.drop_pct_threshold = 0.5;
+        cfg.notifications.low_disk_mb = -1.0;
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn log_format_parses() {
+        assert_eq!(Config::default().logging.format, LogFormat::Text);
+        let cfg: Config = toml::from_str("[logging]\nformat = \"json\"").unwrap();
+        assert_eq!(cfg.logging.format, LogFormat::Json);
+    }
+
+    #[test]
+    fn validate_rejects_unparseable_log_levels() {
+        let mut cfg = Config::default();
+        cfg.logging
+            .modules
+            .insert("obws".to_string(), "loud".to_string());
+        assert!(cfg.validate().is_err());
+        cfg.logging
+            .modules
+            .insert("obws".to_string(), "warn".to_string());
+        assert!(cfg.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_requires_scripts_when_scripting_enabled() {
+        let mut cfg = Config::default();
+        cfg.scripting.enabled = true;
+        assert!(cfg.validate().is_err());
+
+        cfg.scripting.scripts = vec!["auto.rhai".to_string()];
+        assert!(cfg.validate().is_ok());
+
+        cfg.scripting.max_operations = 0;
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn profiles_select_their_own_config_file() {
+        assert_eq!(profile_file_name(None), "config.toml");
+        assert_eq!(profile_file_name(Some("irl")), "config.irl.toml");
+        assert!(valid_profile_name("home-studio_2"));
+        assert!(!valid_profile_name(""));
+        assert!(!valid_profile_name("../irl"));
+    }
+}
@@ -0,0 +1,132 @@
+use super::{active_config_path, Config};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+
+/// How often the watcher checks the config file for outside edits.
+const WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug)]
+struct Cached {
+    config: Config,
+    modified: Option<SystemTime>,
+}
+
+/// The config the running core works from, loaded once and kept current by
+/// [`ConfigHandle::save`] and a watcher on the file, so request handlers do
+/// not read and parse it from disk on every call.
+#[derive(Debug, Clone)]
+pub struct ConfigHandle {
+    path: PathBuf,
+    inner: Arc<RwLock<Cached>>,
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+impl ConfigHandle {
+    /// Wraps an already loaded config for the active config file.
+    pub fn new(config: Config) -> Self {
+        Self::with_path(active_config_path(), config)
+    }
+
+    fn with_path(path: PathBuf, config: Config) -> Self {
+        let modified = modified(&path);
+        Self {
+            path,
+            inner: Arc::new(RwLock::new(Cached { config, modified })),
+        }
+    }
+
+    /// A copy of the current config.
+    pub fn get(&self) -> Config {
+        self.inner.read().unwrap().config.clone()
+    }
+
+    /// Reads part of the config without cloning all of it.
+    pub fn read<T>(&self, f: impl FnOnce(&Config) -> T) -> T {
+        f(&self.inner.read().unwrap().config)
+    }
+
+    /// Validates and writes `config`, then makes it current.
+    pub fn save(&self, config: Config) -> Result<(), Box<dyn std::error::Error>> {
+        config.save_to(&self.path)?;
+        *self.inner.write().unwrap() = Cached {
+            config,
+            modified: modified(&self.path),
+        };
+        Ok(())
+    }
+
+    /// Reloads the file if it changed since it was last read or written.
+    /// Returns whether the cached config was replaced; an edit that fails to
+    /// validate leaves the previous config in place.
+    pub fn reload_if_changed(&self) -> Result<bool, Box<dyn std::error::Error>> {
+        let current = modified(&self.path);
+        if self.inner.read().unwrap().modified == current {
+            return Ok(false);
+        }
+        let result = Config::load_from(&self.path);
+        let mut cached = self.inner.write().unwrap();
+        // Recorded even on failure so a broken edit is reported once, not every tick.
+        cached.modified = current;
+        cached.config = result?;
+        Ok(true)
+    }
+
+    /// Picks up edits made outside the core (by hand or by the CLI).
+    pub fn spawn_watcher(&self) {
+        let handle = self.clone();
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(WATCH_INTERVAL);
+            loop {
+                tick.tick().await;
+                match handle.reload_if_changed() {
+                    Ok(true) => tracing::info!(path = %handle.path.display(), "config reloaded"),
+                    Ok(false) => {}
+                    Err(err) => {
+                        tracing::warn!(error = %err, "config file changed but failed to load; keeping previous config")
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_up_saves_and_outside_edits() {
+        let dir = std::env::temp_dir().join(format!("telemy-config-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("config.toml");
+        let handle = ConfigHandle::with_path(path.clone(), Config::default());
+        assert!(!handle.reload_if_changed().unwrap());
+
+        let mut config = handle.get();
+        config.obs.port = 4460;
+        handle.save(config).unwrap();
+        assert_eq!(handle.read(|c| c.obs.port), 4460);
+        assert!(
+            !handle.reload_if_changed().unwrap(),
+            "own saves are not reloaded"
+        );
+
+        // An outside edit; the mtime is pushed forward so it differs on coarse clocks.
+        let raw = fs::read_to_string(&path).unwrap();
+        fs::write(&path, raw.replace("port = 4460", "port = 4461")).unwrap();
+        let bumped = SystemTime::now() + Duration::from_secs(5);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(bumped)
+            .unwrap();
+        assert!(handle.reload_if_changed().unwrap());
+        assert_eq!(handle.read(|c| c.obs.port), 4461);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
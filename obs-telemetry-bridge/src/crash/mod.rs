@@ -0,0 +1,157 @@
+use crate::config::{ConfigHandle, ObsConfig};
+use crate::model::TelemetryFrame;
+use crate::obslog::{LogEvent, LogEventKind, ObsLog};
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use sysinfo::System;
+use tokio::sync::watch;
+
+/// How long OBS gets to exit after its WebSocket drops; the crash handler can
+/// keep the process around for a moment while it writes the crash log.
+const EXIT_GRACE: Duration = Duration::from_secs(15);
+const EXIT_POLL: Duration = Duration::from_secs(1);
+/// At most one relaunch per window, so an OBS that dies on start is not
+/// restarted in a loop.
+const RELAUNCH_COOLDOWN: Duration = Duration::from_secs(300);
+
+/// Notices the OBS connection dropping while the last connected frame was
+/// streaming. Whether that was a crash depends on the process being gone.
+#[derive(Debug, Default)]
+struct CrashWatcher {
+    streaming: bool,
+}
+
+impl CrashWatcher {
+    fn observe(&mut self, frame: &TelemetryFrame) -> bool {
+        if frame.obs.connected {
+            self.streaming = frame.obs.streaming;
+            return false;
+        }
+        std::mem::take(&mut self.streaming)
+    }
+}
+
+/// `obs.restart_path`, else where the OBS installer puts it.
+fn restart_path(config: &ObsConfig) -> PathBuf {
+    match &config.restart_path {
+        Some(path) => PathBuf::from(path),
+        None if cfg!(windows) => PathBuf::from(r"C:\Program Files\obs-studio\bin\64bit\obs64.exe"),
+        None => PathBuf::from("obs"),
+    }
+}
+
+/// Starts OBS again. OBS looks for its data relative to the working directory,
+/// so it is started from the executable's folder.
+fn relaunch(config: &ObsConfig) -> Result<(), String> {
+    let path = restart_path(config);
+    let mut command = Command::new(&path);
+    command.args(&config.restart_args);
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        command.current_dir(dir);
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|err| format!("failed to start {}: {err}", path.display()))?;
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+/// Waits up to [`EXIT_GRACE`] for the OBS process to go away.
+async fn process_exited(process_name: String) -> bool {
+    let started = Instant::now();
+    let mut sys = System::new();
+    loop {
+        let (running, returned) = tokio::task::spawn_blocking({
+            let name = process_name.clone();
+            move || (crate::metrics::obs_process_running(&mut sys, &name), sys)
+        })
+        .await
+        .unwrap_or_else(|_| (true, System::new()));
+        sys = returned;
+        if !running {
+            return true;
+        }
+        if started.elapsed() >= EXIT_GRACE {
+            return false;
+        }
+        tokio::time::sleep(EXIT_POLL).await;
+    }
+}
+
+/// Watches for OBS going away mid-stream: records a `crash` event next to the
+/// OBS log events, raises an alert and, with `obs.restart_on_crash`, starts
+/// OBS again with `obs.restart_args`.
+pub fn spawn(config: ConfigHandle, obs_log: Arc<ObsLog>, mut rx: watch::Receiver<TelemetryFrame>) {
+    tokio::spawn(async move {
+        let mut watcher = CrashWatcher::default();
+        let mut last_relaunch: Option<Instant> = None;
+        while rx.changed().await.is_ok() {
+            let frame = rx.borrow_and_update().clone();
+            if !watcher.observe(&frame) {
+                continue;
+            }
+            let (obs, notifications) = config.read(|c| (c.obs.clone(), c.notifications.clone()));
+            if !process_exited(obs.process_name.clone()).await {
+                tracing::info!("OBS stopped answering mid-stream but is still running");
+                continue;
+            }
+
+            let mut message = "OBS exited while streaming".to_string();
+            if obs.restart_on_crash {
+                if last_relaunch.is_some_and(|at| at.elapsed() < RELAUNCH_COOLDOWN) {
+                    message.push_str("; not relaunching, it was restarted less than 5 minutes ago");
+                } else {
+                    last_relaunch = Some(Instant::now());
+                    match relaunch(&obs) {
+                        Ok(()) => {
+                            tracing::info!(args = ?obs.restart_args, "relaunched OBS after a crash");
+                            message.push_str("; relaunched it");
+                        }
+                        Err(err) => {
+                            tracing::warn!(error = %err, "OBS relaunch failed");
+                            message.push_str(&format!("; relaunch failed: {err}"));
+                        }
+                    }
+                }
+            }
+            crate::notify::alert(&notifications, &message);
+            obs_log.record(LogEvent {
+                kind: LogEventKind::Crash,
+                line: message,
+                at_unix_ms: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as u64,
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_a_drop_while_streaming_is_a_candidate() {
+        let mut watcher = CrashWatcher::default();
+        let mut frame = TelemetryFrame::default();
+        assert!(!watcher.observe(&frame), "never connected");
+
+        frame.obs.connected = true;
+        assert!(!watcher.observe(&frame));
+        frame.obs.connected = false;
+        assert!(!watcher.observe(&frame), "idle when the link dropped");
+
+        frame.obs.connected = true;
+        frame.obs.streaming = true;
+        assert!(!watcher.observe(&frame));
+        frame.obs.connected = false;
+        frame.obs.streaming = false;
+        assert!(watcher.observe(&frame));
+        assert!(!watcher.observe(&frame), "reported once");
+    }
+}
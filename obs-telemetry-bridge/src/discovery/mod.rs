@@ -0,0 +1,123 @@
+use crate::config::DiscoveryConfig;
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+
+pub const SERVICE_TYPE: &str = "_telemy._tcp.local.";
+/// DNS labels, and so mDNS instance names, are at most 63 bytes.
+const MAX_NAME_BYTES: usize = 63;
+
+/// Keeps the service registered; dropping it sends the goodbye packets.
+pub struct Announcement {
+    daemon: ServiceDaemon,
+    fullname: String,
+}
+
+impl Drop for Announcement {
+    fn drop(&mut self) {
+        let _ = self.daemon.unregister(&self.fullname);
+        let _ = self.daemon.shutdown();
+    }
+}
+
+fn instance_name(config: &DiscoveryConfig) -> String {
+    let name = config
+        .instance_name
+        .clone()
+        .or_else(sysinfo::System::host_name)
+        .unwrap_or_else(|| "telemy".to_string());
+    let name = name.trim();
+    let mut end = name.len().min(MAX_NAME_BYTES);
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    name[..end].to_string()
+}
+
+/// TXT records telling clients where to find the pages and API.
+fn properties() -> HashMap<String, String> {
+    [
+        ("version", env!("CARGO_PKG_VERSION").to_string()),
+        ("schema", crate::model::SCHEMA_VERSION.to_string()),
+        ("dashboard", "/obs".to_string()),
+        ("api", "/api/v1".to_string()),
+        ("ws", "/api/v1/ws".to_string()),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value))
+    .collect()
+}
+
+/// Advertises the server at `addr` as `_telemy._tcp` on every LAN interface.
+/// Returns `None` when discovery is off, the server only listens on loopback,
+/// or the mDNS responder cannot start.
+pub fn announce(config: &DiscoveryConfig, addr: SocketAddr) -> Option<Announcement> {
+    if !config.enabled {
+        return None;
+    }
+    if addr.ip().is_loopback() {
+        tracing::warn!("discovery needs server.bind off loopback, e.g. 0.0.0.0; not announcing");
+        return None;
+    }
+
+    let name = instance_name(config);
+    let announced = ServiceDaemon::new().and_then(|daemon| {
+        let host = format!("{}.local.", name.replace([' ', '.'], "-"));
+        let ips: Vec<IpAddr> = if addr.ip().is_unspecified() {
+            Vec::new()
+        } else {
+            vec![addr.ip()]
+        };
+        let info = ServiceInfo::new(
+            SERVICE_TYPE,
+            &name,
+            &host,
+            &ips[..],
+            addr.port(),
+            properties(),
+        )?;
+        let info = if addr.ip().is_unspecified() {
+            info.enable_addr_auto()
+        } else {
+            info
+        };
+        let fullname = info.get_fullname().to_string();
+        daemon.register(info)?;
+        Ok(Announcement { daemon, fullname })
+    });
+    match announced {
+        Ok(announcement) => {
+            tracing::info!(name = %name, port = addr.port(), "announced on the LAN via mDNS");
+            Some(announcement)
+        }
+        Err(err) => {
+            tracing::warn!(error = %err, "mDNS announcement failed");
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instance_names_fit_a_dns_label() {
+        let config = DiscoveryConfig {
+            enabled: true,
+            instance_name: Some(format!(" {} ", "ü".repeat(40))),
+        };
+        let name = instance_name(&config);
+        assert!(name.len() <= MAX_NAME_BYTES);
+        assert_eq!(name, "ü".repeat(31));
+        assert!(!instance_name(&DiscoveryConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn advertises_paths_and_version() {
+        let txt = properties();
+        assert_eq!(txt["dashboard"], "/obs");
+        assert_eq!(txt["ws"], "/api/v1/ws");
+        assert_eq!(txt["version"], env!("CARGO_PKG_VERSION"));
+    }
+}
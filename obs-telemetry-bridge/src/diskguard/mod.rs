@@ -0,0 +1,110 @@
+use crate::config::{ConfigHandle, DiskGuardConfig};
+use crate::metrics::ObsLink;
+use crate::model::TelemetryFrame;
+use tokio::sync::watch;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Action {
+    SwitchPath(String),
+    PauseRecording,
+}
+
+/// Decides when a recording low on space gets moved or paused. Each action
+/// runs once per recording; the path switch once per session, since a second
+/// switch would only restart the recording in the same place.
+#[derive(Debug, Default)]
+struct DiskGuard {
+    switched: bool,
+    paused: bool,
+}
+
+impl DiskGuard {
+    fn observe(&mut self, frame: &TelemetryFrame, config: &DiskGuardConfig) -> Option<Action> {
+        let obs = &frame.obs;
+        if !obs.connected || !obs.recording {
+            self.paused = false;
+            return None;
+        }
+        // OBS reports 0 before its first stats poll.
+        let free = obs.available_disk_space_mb;
+        let below = |threshold: f64| threshold > 0.0 && free > 0.0 && free < threshold;
+        if !self.switched && below(config.switch_path_below_mb) {
+            if let Some(path) = config.fallback_path.clone() {
+                self.switched = true;
+                return Some(Action::SwitchPath(path));
+            }
+        }
+        if !self.paused && below(config.pause_recording_below_mb) {
+            self.paused = true;
+            return Some(Action::PauseRecording);
+        }
+        None
+    }
+}
+
+/// Watches the recording drive's free space, reading the thresholds from the
+/// live config, and alerts on every action taken.
+pub fn spawn(config: ConfigHandle, obs: ObsLink, mut rx: watch::Receiver<TelemetryFrame>) {
+    tokio::spawn(async move {
+        let mut guard = DiskGuard::default();
+        while rx.changed().await.is_ok() {
+            let frame = rx.borrow_and_update().clone();
+            let (settings, notifications) =
+                config.read(|c| (c.disk_guard.clone(), c.notifications.clone()));
+            let Some(action) = guard.observe(&frame, &settings) else {
+                continue;
+            };
+            let outcome = match &action {
+                Action::SwitchPath(path) => match obs.move_recording(path).await {
+                    Ok(()) => format!("recording moved to {path}"),
+                    Err(err) => format!("moving the recording to {path} failed: {err}"),
+                },
+                Action::PauseRecording => match obs.pause_recording().await {
+                    Ok(()) => "recording paused".to_string(),
+                    Err(err) => format!("pausing the recording failed: {err}"),
+                },
+            };
+            let free_gb = frame.obs.available_disk_space_mb / 1024.0;
+            let message = format!("Disk almost full ({free_gb:.1} GB left): {outcome}");
+            crate::notify::alert(&notifications, &message);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn acts_once_per_recording() {
+        let config = DiskGuardConfig {
+            switch_path_below_mb: 5000.0,
+            fallback_path: Some("D:/Recordings".to_string()),
+            pause_recording_below_mb: 1000.0,
+        };
+        let mut guard = DiskGuard::default();
+        let mut frame = TelemetryFrame::default();
+        frame.obs.connected = true;
+        frame.obs.available_disk_space_mb = 4000.0;
+        assert_eq!(guard.observe(&frame, &config), None, "not recording");
+
+        frame.obs.recording = true;
+        assert_eq!(
+            guard.observe(&frame, &config),
+            Some(Action::SwitchPath("D:/Recordings".to_string()))
+        );
+        assert_eq!(guard.observe(&frame, &config), None);
+
+        frame.obs.available_disk_space_mb = 800.0;
+        assert_eq!(guard.observe(&frame, &config), Some(Action::PauseRecording));
+        assert_eq!(guard.observe(&frame, &config), None);
+
+        frame.obs.recording = false;
+        guard.observe(&frame, &config);
+        frame.obs.recording = true;
+        frame.obs.available_disk_space_mb = 3000.0;
+        assert_eq!(guard.observe(&frame, &config), None, "switched already");
+        frame.obs.available_disk_space_mb = 0.0;
+        assert_eq!(guard.observe(&frame, &config), None, "no stats yet");
+    }
+}
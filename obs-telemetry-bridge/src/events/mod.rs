@@ -0,0 +1,343 @@
+use crate::config::{ConfigHandle, NotificationsConfig, RecorderConfig};
+use crate::model::TelemetryFrame;
+use crate::recorder::{recent_lines, recorder_dir, Recorder, EVENTS_PREFIX};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::{broadcast, watch};
+
+/// Events kept in memory for `/api/v1/events`.
+const CAPACITY: usize = 500;
+const BROADCAST_CAPACITY: usize = 64;
+
+static BUS: OnceLock<EventBus> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelayState {
+    /// No relay session.
+    None,
+    Disconnected,
+    Connected,
+    /// Connected and receiving the stream.
+    Ingesting,
+}
+
+impl RelayState {
+    fn of(frame: &TelemetryFrame) -> Self {
+        match &frame.relay {
+            None => Self::None,
+            Some(relay) if !relay.connected => Self::Disconnected,
+            Some(relay) if relay.ingest_active => Self::Ingesting,
+            Some(_) => Self::Connected,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EventKind {
+    ObsConnected,
+    ObsDisconnected,
+    StreamStarted,
+    StreamStopped,
+    /// The main output's drops reached `notifications.drop_pct_threshold`.
+    DropsSpike {
+        output: String,
+        drop_pct: f32,
+    },
+    RelayStateChange {
+        from: RelayState,
+        to: RelayState,
+    },
+    SceneSwitched {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    /// Anything passed to `crate::notify::alert` or shown as a notification.
+    AlertFired {
+        message: String,
+    },
+}
+
+/// A state transition, numbered in the order it happened.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelemetryEvent {
+    pub id: u64,
+    pub at_unix_ms: u64,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+impl TelemetryEvent {
+    /// The `kind` tag, e.g. `stream_started`.
+    pub fn name(&self) -> String {
+        serde_json::to_value(&self.kind)
+            .ok()
+            .and_then(|v| v["kind"].as_str().map(str::to_string))
+            .unwrap_or_default()
+    }
+}
+
+/// Fans events out to subscribers and keeps the latest [`CAPACITY`].
+pub struct EventBus {
+    tx: broadcast::Sender<TelemetryEvent>,
+    recent: Mutex<VecDeque<TelemetryEvent>>,
+    next_id: AtomicU64,
+}
+
+/// The process-wide event bus.
+pub fn bus() -> &'static EventBus {
+    BUS.get_or_init(|| EventBus {
+        tx: broadcast::channel(BROADCAST_CAPACITY).0,
+        recent: Mutex::new(VecDeque::new()),
+        next_id: AtomicU64::new(1),
+    })
+}
+
+impl EventBus {
+    pub fn emit(&self, kind: EventKind) {
+        let event = TelemetryEvent {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            at_unix_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            kind,
+        };
+        self.keep(event.clone());
+        let _ = self.tx.send(event);
+    }
+
+    fn keep(&self, event: TelemetryEvent) {
+        let mut recent = self.recent.lock().unwrap();
+        if recent.len() == CAPACITY {
+            recent.pop_front();
+        }
+        recent.push_back(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TelemetryEvent> {
+        self.tx.subscribe()
+    }
+
+    /// Events after `since_unix_ms`, oldest first, optionally only the named
+    /// kinds.
+    pub fn since(&self, since_unix_ms: u64, kinds: &[String]) -> Vec<TelemetryEvent> {
+        self.recent
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|event| event.at_unix_ms > since_unix_ms)
+            .filter(|event| kinds.is_empty() || kinds.contains(&event.name()))
+            .cloned()
+            .collect()
+    }
+
+    /// Loads events persisted by an earlier run, continuing their numbering.
+    fn restore(&self, lines: &[String]) {
+        for line in lines {
+            if let Ok(event) = serde_json::from_str::<TelemetryEvent>(line) {
+                self.next_id.fetch_max(event.id + 1, Ordering::Relaxed);
+                self.keep(event);
+            }
+        }
+    }
+}
+
+/// Turns consecutive frames into events. Drop spikes are edge-triggered, one
+/// per episode.
+#[derive(Debug, Default)]
+struct Detector {
+    prev: TelemetryFrame,
+    spiking: bool,
+}
+
+impl Detector {
+    fn observe(&mut self, next: &TelemetryFrame, config: &NotificationsConfig) -> Vec<EventKind> {
+        let prev = std::mem::replace(&mut self.prev, next.clone());
+        let mut events = Vec::new();
+        if prev.obs.connected != next.obs.connected {
+            events.push(if next.obs.connected {
+                EventKind::ObsConnected
+            } else {
+                EventKind::ObsDisconnected
+            });
+        }
+        if prev.obs.streaming != next.obs.streaming {
+            events.push(if next.obs.streaming {
+                EventKind::StreamStarted
+            } else {
+                EventKind::StreamStopped
+            });
+        }
+
+        let spike = crate::rules::main_output(next)
+            .filter(|o| next.obs.streaming && o.drop_pct >= config.drop_pct_threshold);
+        if let Some(output) = spike.filter(|_| !self.spiking) {
+            events.push(EventKind::DropsSpike {
+                output: output.name.clone(),
+                drop_pct: output.drop_pct,
+            });
+        }
+        self.spiking = spike.is_some();
+
+        let (from, to) = (RelayState::of(&prev), RelayState::of(next));
+        if from != to {
+            events.push(EventKind::RelayStateChange { from, to });
+        }
+        if prev.obs.current_scene.is_some()
+            && next.obs.current_scene.is_some()
+            && prev.obs.current_scene != next.obs.current_scene
+        {
+            events.push(EventKind::SceneSwitched {
+                from: prev.obs.current_scene,
+                to: next.obs.current_scene.clone(),
+            });
+        }
+        events
+    }
+}
+
+/// Appends every event to `events-*.jsonl` next to the frame recordings, on
+/// its own thread since the recorder writes synchronously.
+fn spawn_writer(config: &RecorderConfig) {
+    let dir = recorder_dir(config);
+    let recorder = Recorder::new(
+        &dir,
+        config.max_file_mb.saturating_mul(1024 * 1024),
+        config.max_files,
+        config.retention_days,
+    )
+    .map(|recorder| recorder.with_prefix(EVENTS_PREFIX));
+    let mut recorder = match recorder {
+        Ok(recorder) => recorder,
+        Err(err) => {
+            tracing::warn!(dir = %dir.display(), error = %err, "event recorder disabled");
+            return;
+        }
+    };
+    if let Ok(lines) = recent_lines(&dir, EVENTS_PREFIX, CAPACITY) {
+        bus().restore(&lines);
+    }
+    let mut events = bus().subscribe();
+    let spawned = std::thread::Builder::new()
+        .name("telemy-events".to_string())
+        .spawn(move || loop {
+            match events.blocking_recv() {
+                Ok(event) => {
+                    if let Err(err) = recorder.append(&event) {
+                        tracing::warn!(error = %err, "event recording failed");
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!(missed, "event recorder fell behind");
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            }
+        });
+    if let Err(err) = spawned {
+        tracing::warn!(error = %err, "event recorder unavailable");
+    }
+}
+
+/// Derives events from the frame stream, reading the drop threshold from the
+/// live config, and persists them alongside recordings when `[recorder]` is on.
+pub fn spawn(config: ConfigHandle, mut rx: watch::Receiver<TelemetryFrame>) {
+    let recorder = config.read(|c| c.recorder.clone());
+    if recorder.enabled {
+        spawn_writer(&recorder);
+    }
+    tokio::spawn(async move {
+        let mut detector = Detector::default();
+        while rx.changed().await.is_ok() {
+            let frame = rx.borrow_and_update().clone();
+            let notifications = config.read(|c| c.notifications.clone());
+            for kind in detector.observe(&frame, &notifications) {
+                bus().emit(kind);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{RelayFrame, StreamOutput};
+
+    #[test]
+    fn detects_transitions() {
+        let config = NotificationsConfig::default();
+        let mut detector = Detector::default();
+        let mut frame = TelemetryFrame::default();
+        frame.obs.connected = true;
+        frame.obs.current_scene = Some("Live".to_string());
+        assert_eq!(
+            detector.observe(&frame, &config),
+            vec![EventKind::ObsConnected]
+        );
+
+        frame.obs.streaming = true;
+        frame.obs.current_scene = Some("BRB".to_string());
+        frame.relay = Some(RelayFrame {
+            connected: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            detector.observe(&frame, &config),
+            vec![
+                EventKind::StreamStarted,
+                EventKind::RelayStateChange {
+                    from: RelayState::None,
+                    to: RelayState::Connected,
+                },
+                EventKind::SceneSwitched {
+                    from: Some("Live".to_string()),
+                    to: Some("BRB".to_string()),
+                },
+            ]
+        );
+
+        frame.streams.push(StreamOutput {
+            name: "adv_stream".to_string(),
+            drop_pct: 0.05,
+            ..Default::default()
+        });
+        let spike = EventKind::DropsSpike {
+            output: "adv_stream".to_string(),
+            drop_pct: 0.05,
+        };
+        assert_eq!(detector.observe(&frame, &config), vec![spike]);
+        assert!(detector.observe(&frame, &config).is_empty(), "same episode");
+    }
+
+    #[test]
+    fn events_round_trip_and_filter() {
+        let bus = EventBus {
+            tx: broadcast::channel(1).0,
+            recent: Mutex::new(VecDeque::new()),
+            next_id: AtomicU64::new(1),
+        };
+        let scene = TelemetryEvent {
+            id: 7,
+            at_unix_ms: 1_000,
+            kind: EventKind::SceneSwitched {
+                from: None,
+                to: Some("BRB".to_string()),
+            },
+        };
+        let json = serde_json::to_string(&scene).unwrap();
+        assert!(json.contains(r#""kind":"scene_switched""#));
+        bus.restore(&[json, "not json".to_string()]);
+        bus.emit(EventKind::StreamStarted);
+
+        let all = bus.since(0, &[]);
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[1].id, 8, "numbering continues after restored events");
+        let scenes = bus.since(0, &["scene_switched".to_string()]);
+        assert_eq!(scenes.len(), 1);
+        assert_eq!(scenes[0].kind, scene.kind);
+    }
+}
@@ -0,0 +1,257 @@
+use crate::config::LokiConfig;
+use crate::security::SecretString;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::sync::OnceLock;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{broadcast, mpsc};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+const CHANNEL_CAPACITY: usize = 10_000;
+// The shipper's own failures must not be shipped, or a dead endpoint feeds itself.
+const SELF_TARGET: &str = module_path!();
+
+static SENDER: OnceLock<mpsc::Sender<LogEntry>> = OnceLock::new();
+
+#[derive(Debug, Clone)]
+struct LogEntry {
+    ts_ns: u128,
+    source: &'static str,
+    level: Option<&'static str>,
+    line: String,
+}
+
+/// Forwards tracing events to the Loki shipper once `spawn` has installed it;
+/// until then (or when `[loki]` is disabled) events are dropped here.
+pub struct LokiLayer;
+
+impl<S: Subscriber> Layer<S> for LokiLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let Some(sender) = SENDER.get() else {
+            return;
+        };
+        let meta = event.metadata();
+        if meta.target().starts_with(SELF_TARGET) {
+            return;
+        }
+        let mut visitor = LineVisitor::default();
+        event.record(&mut visitor);
+        let mut line = visitor.message;
+        line.push_str(&visitor.fields);
+        let _ = sender.try_send(LogEntry {
+            ts_ns: now_ns(),
+            source: "telemy",
+            level: Some(level_label(meta.level())),
+            line,
+        });
+    }
+}
+
+#[derive(Default)]
+struct LineVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for LineVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{value:?}");
+        } else {
+            let _ = write!(self.fields, " {}={value:?}", field.name());
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={value}", field.name());
+        }
+    }
+}
+
+fn level_label(level: &Level) -> &'static str {
+    match *level {
+        Level::ERROR => "error",
+        Level::WARN => "warn",
+        Level::INFO => "info",
+        Level::DEBUG => "debug",
+        Level::TRACE => "trace",
+    }
+}
+
+fn now_ns() -> u128 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+}
+
+/// Starts the batching shipper.
+pub fn spawn(config: &LokiConfig, auth_value: Option<SecretString>) {
+    let Some(endpoint) = config.endpoint.clone() else {
+        return;
+    };
+    let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+    if SENDER.set(tx.clone()).is_err() {
+        return;
+    }
+
+    let shipper = Shipper {
+        endpoint,
+        auth_header: config.auth_header.clone(),
+        auth_value,
+        labels: config.labels.clone(),
+        batch_max: config.batch_max.max(1),
+        interval: Duration::from_millis(config.push_interval_ms),
+    };
+    tokio::spawn(shipper.run(rx));
+}
+
+/// Ships the OBS log lines `crate::obslog` follows, once `spawn` has
+/// installed the shipper.
+pub fn ship_obs_log(mut lines: broadcast::Receiver<String>) {
+    let Some(tx) = SENDER.get().cloned() else {
+        return;
+    };
+    tokio::spawn(async move {
+        loop {
+            let line = match lines.recv().await {
+                Ok(line) => line,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => return,
+            };
+            let _ = tx.try_send(LogEntry {
+                ts_ns: now_ns(),
+                source: "obs",
+                level: None,
+                line,
+            });
+        }
+    });
+}
+
+struct Shipper {
+    endpoint: String,
+    auth_header: String,
+    auth_value: Option<SecretString>,
+    labels: HashMap<String, String>,
+    batch_max: usize,
+    interval: Duration,
+}
+
+impl Shipper {
+    async fn run(self, mut rx: mpsc::Receiver<LogEntry>) {
+        let client = reqwest::Client::new();
+        let mut ticker = tokio::time::interval(self.interval);
+        let mut batch = Vec::new();
+        loop {
+            tokio::select! {
+                entry = rx.recv() => match entry {
+                    Some(entry) => {
+                        batch.push(entry);
+                        if batch.len() < self.batch_max {
+                            continue;
+                        }
+                    }
+                    None => return,
+                },
+                _ = ticker.tick() => {}
+            }
+            if batch.is_empty() {
+                continue;
+            }
+            let body = build_push_body(&batch, &self.labels);
+            batch.clear();
+
+            let mut req = client
+                .post(&self.endpoint)
+                .header("Content-Type", "application/json")
+                .body(body.to_string());
+            if let Some(value) = &self.auth_value {
+                req = req.header(self.auth_header.as_str(), value.expose());
+            }
+            let ok = match req.send().await {
+                Ok(resp) if resp.status().is_success() => true,
+                Ok(resp) => {
+                    tracing::warn!("loki push rejected: {}", resp.status());
+                    false
+                }
+                Err(err) => {
+                    tracing::warn!("loki push failed: {}", err);
+                    false
+                }
+            };
+            crate::selfstats::stats().record_export("loki", ok);
+        }
+    }
+}
+
+/// Groups entries into one Loki stream per (source, level) label set.
+fn build_push_body(entries: &[LogEntry], labels: &HashMap<String, String>) -> serde_json::Value {
+    let mut streams: BTreeMap<(&str, Option<&str>), Vec<[String; 2]>> = BTreeMap::new();
+    for entry in entries {
+        streams
+            .entry((entry.source, entry.level))
+            .or_default()
+            .push([entry.ts_ns.to_string(), entry.line.clone()]);
+    }
+    let streams: Vec<_> = streams
+        .into_iter()
+        .map(|((source, level), values)| {
+            let mut stream: serde_json::Map<String, serde_json::Value> = labels
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone().into()))
+                .collect();
+            stream.insert("app".to_string(), "telemy".into());
+            stream.insert("source".to_string(), source.into());
+            if let Some(level) = level {
+                stream.insert("level".to_string(), level.into());
+            }
+            serde_json::json!({ "stream": stream, "values": values })
+        })
+        .collect();
+    serde_json::json!({ "streams": streams })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_body_groups_by_source_and_level() {
+        let entry = |source, level: Option<&'static str>, line: &str| LogEntry {
+            ts_ns: 1_700_000_000_000_000_000,
+            source,
+            level,
+            line: line.to_string(),
+        };
+        let entries = vec![
+            entry("telemy", Some("info"), "started"),
+            entry("telemy", Some("warn"), "obs connect failed"),
+            entry("telemy", Some("info"), "connected"),
+            entry("obs", None, "10:00:00.000: ==== Streaming Start ===="),
+        ];
+        let labels = HashMap::from([("host".to_string(), "studio-pc".to_string())]);
+        let body = build_push_body(&entries, &labels);
+        let streams = body["streams"].as_array().unwrap();
+        assert_eq!(streams.len(), 3);
+
+        let info = streams
+            .iter()
+            .find(|s| s["stream"]["level"] == "info")
+            .unwrap();
+        assert_eq!(info["stream"]["host"], "studio-pc");
+        assert_eq!(info["values"].as_array().unwrap().len(), 2);
+        assert_eq!(info["values"][0][0], "1700000000000000000");
+
+        let obs = streams
+            .iter()
+            .find(|s| s["stream"]["source"] == "obs")
+            .unwrap();
+        assert!(obs["stream"].get("level").is_none());
+    }
+}
@@ -1,18 +1,63 @@
-use crate::model::TelemetryFrame;
-use opentelemetry::{global, metrics::Histogram, metrics::MeterProvider as _, KeyValue};
+use crate::config::{Config, GrafanaConfig};
+use crate::model::{
+    DiskFrame, GpuFrame, InterfaceFrame, RelayFrame, RelayLinkFrame, StreamOutput, TelemetryFrame,
+    UplinkFrame,
+};
+use crate::security::SecretString;
+use opentelemetry::metrics::{
+    Histogram, Meter, MeterProvider as _, ObservableCounter, ObservableGauge,
+};
+use opentelemetry::{global, KeyValue};
 use opentelemetry_otlp::WithExportConfig;
 use opentelemetry_sdk::metrics::reader::{DefaultAggregationSelector, DefaultTemporalitySelector};
 use opentelemetry_sdk::metrics::{MeterProvider, PeriodicReader};
-use std::{collections::HashMap, time::Duration};
+use opentelemetry_sdk::Resource;
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+pub mod loki;
+mod pipeline;
+pub mod prometheus;
+mod retry;
+pub mod webhook;
+
+pub use pipeline::{Pipeline, SinkError, TelemetrySink};
 
 type AnyError = Box<dyn std::error::Error + Send + Sync + 'static>;
 
+type LatestFrame = Arc<Mutex<Option<TelemetryFrame>>>;
+type OutputNames = Arc<HashMap<String, String>>;
+
+/// The OTLP meter provider, kept so [`shutdown_otlp`] can push the last export.
+static PROVIDER: Mutex<Option<MeterProvider>> = Mutex::new(None);
+
+/// Pushes telemetry over OTLP. By default instantaneous values are observable gauges
+/// and OBS frame totals are monotonic counters, both read from the latest recorded
+/// frame at each export; `legacy_histograms` keeps the original histogram-only shape
+/// for dashboards built against it.
 pub struct GrafanaExporter {
+    interval: Duration,
+    latest: LatestFrame,
+    output_names: OutputNames,
+    histograms: Option<HistogramInstruments>,
+    _gauges: Vec<ObservableGauge<f64>>,
+    _counters: Vec<ObservableCounter<u64>>,
+}
+
+struct HistogramInstruments {
     health: Histogram<f64>,
     cpu: Histogram<f64>,
     mem: Histogram<f64>,
     gpu: Histogram<f64>,
     gpu_temp: Histogram<f64>,
+    encoder: Histogram<f64>,
+    gpu_device_util: Histogram<f64>,
+    gpu_device_vram: Histogram<f64>,
+    gpu_device_temp: Histogram<f64>,
+    gpu_device_encoder: Histogram<f64>,
     upload: Histogram<f64>,
     download: Histogram<f64>,
     latency: Histogram<f64>,
@@ -29,15 +74,20 @@ pub struct GrafanaExporter {
 }
 
 impl GrafanaExporter {
+    /// `output_names` are the dashboard display names, attached to per-output series
+    /// as `output_name` next to the raw OBS `output` id.
     pub fn new(
-        endpoint: &str,
-        auth_header: &str,
-        auth_value: Option<String>,
-        interval_ms: u64,
+        config: &GrafanaConfig,
+        auth_value: Option<SecretString>,
+        output_names: HashMap<String, String>,
     ) -> Result<Self, AnyError> {
+        let endpoint = config
+            .endpoint
+            .as_deref()
+            .ok_or("missing grafana.endpoint in config")?;
         let mut headers = HashMap::new();
         if let Some(value) = auth_value {
-            headers.insert(auth_header.to_string(), value);
+            headers.insert(config.auth_header.clone(), value.expose().to_string());
         }
 
         let exporter = opentelemetry_otlp::new_exporter()
@@ -48,20 +98,470 @@ impl GrafanaExporter {
                 Box::new(DefaultAggregationSelector::new()),
                 Box::new(DefaultTemporalitySelector::new()),
             )?;
+        let exporter = retry::BufferingExporter::new(exporter, config.buffer_max_batches);
 
         let reader = PeriodicReader::builder(exporter, opentelemetry_sdk::runtime::Tokio)
-            .with_interval(Duration::from_millis(interval_ms))
+            .with_interval(Duration::from_millis(config.push_interval_ms))
             .build();
 
-        let provider = MeterProvider::builder().with_reader(reader).build();
+        let provider = MeterProvider::builder()
+            .with_reader(reader)
+            .with_resource(build_resource(config))
+            .build();
         let meter = provider.meter("telemy");
+        *PROVIDER.lock().unwrap() = Some(provider.clone());
         global::set_meter_provider(provider);
 
+        let interval = Duration::from_millis(config.push_interval_ms);
+        let latest: LatestFrame = Arc::new(Mutex::new(None));
+        let output_names: OutputNames = Arc::new(output_names);
+        if config.legacy_histograms {
+            return Ok(Self {
+                interval,
+                latest,
+                output_names,
+                histograms: Some(HistogramInstruments::new(&meter)),
+                _gauges: Vec::new(),
+                _counters: Vec::new(),
+            });
+        }
+
+        Ok(Self {
+            interval,
+            _gauges: register_gauges(&meter, &latest, &output_names),
+            _counters: register_counters(&meter, &latest),
+            latest,
+            output_names,
+            histograms: None,
+        })
+    }
+}
+
+impl TelemetrySink for GrafanaExporter {
+    fn interval(&self) -> Option<Duration> {
+        Some(self.interval)
+    }
+
+    fn record(&mut self, frame: &TelemetryFrame) -> Result<(), AnyError> {
+        match &self.histograms {
+            Some(histograms) => histograms.record(frame, &self.output_names),
+            None => *self.latest.lock().unwrap() = Some(frame.clone()),
+        }
+        Ok(())
+    }
+}
+
+/// Exports what the OTLP reader has collected since its last push, including
+/// batches buffered while the endpoint was down, and stops the exporter. Does
+/// nothing when OTLP export is off.
+pub async fn shutdown_otlp(timeout: Duration) {
+    let Some(provider) = PROVIDER.lock().unwrap().take() else {
+        return;
+    };
+    // The SDK blocks on its reader task, so keep it off the async workers.
+    let shutdown = tokio::task::spawn_blocking(move || provider.shutdown());
+    match tokio::time::timeout(timeout, shutdown).await {
+        Ok(Ok(Ok(()))) => tracing::info!("otlp exporter flushed"),
+        Ok(Ok(Err(err))) => tracing::warn!(error = %err, "otlp exporter shutdown failed"),
+        Ok(Err(err)) => tracing::warn!(error = %err, "otlp exporter shutdown panicked"),
+        Err(_) => tracing::warn!(
+            timeout_ms = timeout.as_millis() as u64,
+            "otlp exporter shutdown timed out"
+        ),
+    }
+}
+
+/// Every sink enabled in config; each runs independently once spawned.
+/// `webhook_auth_values` line up with `config.webhooks`.
+pub fn pipeline_from_config(
+    config: &Config,
+    grafana_auth_value: Option<SecretString>,
+    webhook_auth_values: Vec<Option<SecretString>>,
+) -> Pipeline {
+    let mut pipeline = Pipeline::new();
+    if config.grafana.enabled && config.grafana.endpoint.is_some() {
+        let grafana = config.grafana.clone();
+        let output_names = config.output_names.clone();
+        pipeline.add("grafana_otlp", move || {
+            GrafanaExporter::new(&grafana, grafana_auth_value.clone(), output_names.clone())
+        });
+    }
+    if config.recorder.enabled {
+        let recorder = config.recorder.clone();
+        pipeline.add("recorder", move || {
+            crate::recorder::Recorder::from_config(&recorder).map_err(AnyError::from)
+        });
+    }
+    for (hook, auth_value) in config.webhooks.iter().zip(webhook_auth_values) {
+        if !hook.enabled {
+            continue;
+        }
+        let hook = hook.clone();
+        pipeline.add("webhook", move || {
+            webhook::WebhookSink::new(&hook, auth_value.clone())
+        });
+    }
+    pipeline
+}
+
+type Reading = (f64, Vec<KeyValue>);
+type GaugeReader = fn(&TelemetryFrame, &HashMap<String, String>) -> Vec<Reading>;
+type CounterReader = fn(&TelemetryFrame) -> u64;
+
+const GAUGES: &[(&str, GaugeReader)] = &[
+    ("telemy.health", |f, _| single(f.health as f64)),
+    ("telemy.system.cpu_percent", |f, _| {
+        single(f.system.cpu_percent as f64)
+    }),
+    ("telemy.system.mem_percent", |f, _| {
+        single(f.system.mem_percent as f64)
+    }),
+    ("telemy.system.gpu_percent", |f, _| {
+        optional(f.system.gpu_percent)
+    }),
+    ("telemy.system.gpu_temp_c", |f, _| {
+        optional(f.system.gpu_temp_c)
+    }),
+    ("telemy.system.encoder_percent", |f, _| {
+        optional(f.system.encoder_percent)
+    }),
+    ("telemy.gpu.utilization_percent", |f, _| {
+        per_gpu(f, |g| g.utilization_percent.map(f64::from))
+    }),
+    ("telemy.gpu.vram_used_mb", |f, _| {
+        per_gpu(f, |g| g.vram_used_mb.map(|v| v as f64))
+    }),
+    ("telemy.gpu.temp_c", |f, _| {
+        per_gpu(f, |g| g.temp_c.map(f64::from))
+    }),
+    ("telemy.gpu.encoder_percent", |f, _| {
+        per_gpu(f, |g| g.encoder_percent.map(f64::from))
+    }),
+    ("telemy.network.upload_mbps", |f, _| {
+        single(f.network.upload_mbps as f64)
+    }),
+    ("telemy.network.download_mbps", |f, _| {
+        single(f.network.download_mbps as f64)
+    }),
+    ("telemy.network.latency_ms", |f, _| {
+        single(f.network.latency_ms as f64)
+    }),
+    ("telemy.network.interface.upload_mbps", |f, _| {
+        per_interface(f, |i| i.upload_mbps as f64)
+    }),
+    ("telemy.network.interface.download_mbps", |f, _| {
+        per_interface(f, |i| i.download_mbps as f64)
+    }),
+    ("telemy.uplink.up", |f, _| {
+        per_uplink(f, |u| Some(u.up as u8 as f64))
+    }),
+    ("telemy.uplink.upload_mbps", |f, _| {
+        per_uplink(f, |u| u.upload_mbps.map(f64::from))
+    }),
+    ("telemy.uplink.download_mbps", |f, _| {
+        per_uplink(f, |u| u.download_mbps.map(f64::from))
+    }),
+    ("telemy.uplink.latency_ms", |f, _| {
+        per_uplink(f, |u| u.latency_ms.map(f64::from))
+    }),
+    ("telemy.uplink.loss_pct", |f, _| {
+        per_uplink(f, |u| u.loss_pct.map(f64::from))
+    }),
+    ("telemy.output.bitrate_kbps", |f, names| {
+        per_output(f, names, |o| o.bitrate_kbps as f64)
+    }),
+    ("telemy.output.drop_pct", |f, names| {
+        per_output(f, names, |o| o.drop_pct as f64)
+    }),
+    ("telemy.output.fps", |f, names| {
+        per_output(f, names, |o| o.fps as f64)
+    }),
+    ("telemy.output.encoding_lag_ms", |f, names| {
+        per_output(f, names, |o| o.encoding_lag_ms as f64)
+    }),
+    ("telemy.stream.avg_bitrate_1m_kbps", |f, _| {
+        optional(f.stream_stats.avg_bitrate_1m_kbps)
+    }),
+    ("telemy.stream.avg_bitrate_5m_kbps", |f, _| {
+        optional(f.stream_stats.avg_bitrate_5m_kbps)
+    }),
+    ("telemy.stream.bitrate_stability", |f, _| {
+        optional(f.stream_stats.bitrate_stability)
+    }),
+    ("telemy.stream.drops_per_min", |f, _| {
+        optional(f.stream_stats.drops_per_min)
+    }),
+    ("telemy.stream.frame_time_p95_ms", |f, _| {
+        optional(f.stream_stats.frame_time_p95_ms)
+    }),
+    ("telemy.obs.active_fps", |f, _| {
+        single(f.obs.active_fps as f64)
+    }),
+    ("telemy.obs.disk_space_mb", |f, _| {
+        single(f.obs.available_disk_space_mb)
+    }),
+    ("telemy.disk.available_mb", |f, _| {
+        per_disk(f, |d| d.available_mb)
+    }),
+    ("telemy.disk.used_percent", |f, _| {
+        per_disk(f, |d| d.used_percent as f64)
+    }),
+    // Aegis relay, only while its telemetry is attached to the frame.
+    ("telemy.relay.connected", |f, _| {
+        relay(f, |r| if r.connected { 1.0 } else { 0.0 })
+    }),
+    ("telemy.relay.bitrate_kbps", |f, _| {
+        relay(f, |r| r.bitrate_kbps as f64)
+    }),
+    ("telemy.relay.rtt_ms", |f, _| relay(f, |r| r.rtt_ms as f64)),
+    ("telemy.relay.packet_loss_pct", |f, _| {
+        relay(f, |r| r.packet_loss_pct as f64)
+    }),
+    ("telemy.relay.reconnects", |f, _| {
+        relay(f, |r| r.reconnects as f64)
+    }),
+    ("telemy.relay.link.bitrate_kbps", |f, _| {
+        per_link(f, |l| l.bitrate_kbps as f64)
+    }),
+    ("telemy.relay.link.rtt_ms", |f, _| {
+        per_link(f, |l| l.rtt_ms as f64)
+    }),
+    ("telemy.relay.link.packet_loss_pct", |f, _| {
+        per_link(f, |l| l.packet_loss_pct as f64)
+    }),
+    ("telemy.relay.link.jitter_ms", |f, _| {
+        per_link(f, |l| l.jitter_ms as f64)
+    }),
+];
+
+// OBS reports these as running totals, which is exactly what an observable
+// (cumulative) counter expects.
+const COUNTERS: &[(&str, CounterReader)] = &[
+    ("telemy.obs.render_missed_frames", |f| {
+        f.obs.render_missed_frames as u64
+    }),
+    ("telemy.obs.render_total_frames", |f| {
+        f.obs.render_total_frames as u64
+    }),
+    ("telemy.obs.output_skipped_frames", |f| {
+        f.obs.output_skipped_frames as u64
+    }),
+    ("telemy.obs.output_total_frames", |f| {
+        f.obs.output_total_frames as u64
+    }),
+];
+
+/// Identifies this Telemy instance so several can push into one Grafana stack;
+/// Grafana Cloud maps `service.name`/`service.instance.id` to `job`/`instance`.
+fn build_resource(config: &GrafanaConfig) -> Resource {
+    let host_name = sysinfo::System::host_name().unwrap_or_else(|| "unknown".to_string());
+    let instance_id = config
+        .instance_id
+        .clone()
+        .unwrap_or_else(|| host_name.clone());
+    let mut attributes = vec![
+        KeyValue::new("service.name", "telemy"),
+        KeyValue::new("service.version", env!("CARGO_PKG_VERSION")),
+        KeyValue::new("service.instance.id", instance_id),
+        KeyValue::new("host.name", host_name),
+    ];
+    if let Some(streamer) = &config.streamer_name {
+        attributes.push(KeyValue::new("telemy.streamer", streamer.clone()));
+    }
+    let defaults = Resource::new(attributes);
+    let extra = Resource::new(
+        config
+            .resource_attributes
+            .iter()
+            .map(|(k, v)| KeyValue::new(k.clone(), v.clone())),
+    );
+    // Later resources win on conflicting keys, so user-supplied attributes override.
+    defaults.merge(&extra)
+}
+
+fn register_gauges(
+    meter: &Meter,
+    latest: &LatestFrame,
+    output_names: &OutputNames,
+) -> Vec<ObservableGauge<f64>> {
+    GAUGES
+        .iter()
+        .map(|&(name, read)| {
+            let latest = latest.clone();
+            let output_names = output_names.clone();
+            meter
+                .f64_observable_gauge(name)
+                .with_callback(move |observer| {
+                    if let Some(frame) = latest.lock().unwrap().as_ref() {
+                        for (value, labels) in read(frame, &output_names) {
+                            observer.observe(value, &labels);
+                        }
+                    }
+                })
+                .init()
+        })
+        .collect()
+}
+
+fn register_counters(meter: &Meter, latest: &LatestFrame) -> Vec<ObservableCounter<u64>> {
+    COUNTERS
+        .iter()
+        .map(|&(name, read)| {
+            let latest = latest.clone();
+            meter
+                .u64_observable_counter(name)
+                .with_callback(move |observer| {
+                    if let Some(frame) = latest.lock().unwrap().as_ref() {
+                        observer.observe(read(frame), &[]);
+                    }
+                })
+                .init()
+        })
+        .collect()
+}
+
+fn single(value: f64) -> Vec<Reading> {
+    vec![(value, Vec::new())]
+}
+
+fn optional(value: Option<f32>) -> Vec<Reading> {
+    value.map(|v| (v as f64, Vec::new())).into_iter().collect()
+}
+
+fn per_gpu(frame: &TelemetryFrame, read: impl Fn(&GpuFrame) -> Option<f64>) -> Vec<Reading> {
+    frame
+        .system
+        .gpus
+        .iter()
+        .filter_map(|gpu| Some((read(gpu)?, gpu_labels(gpu).to_vec())))
+        .collect()
+}
+
+fn per_interface(frame: &TelemetryFrame, read: impl Fn(&InterfaceFrame) -> f64) -> Vec<Reading> {
+    frame
+        .network
+        .interfaces
+        .iter()
+        .map(|iface| {
+            let labels = vec![
+                KeyValue::new("interface", iface.name.clone()),
+                KeyValue::new("measured", iface.measured),
+            ];
+            (read(iface), labels)
+        })
+        .collect()
+}
+
+fn per_uplink(frame: &TelemetryFrame, read: impl Fn(&UplinkFrame) -> Option<f64>) -> Vec<Reading> {
+    frame
+        .network
+        .uplinks
+        .iter()
+        .filter_map(|uplink| {
+            Some((
+                read(uplink)?,
+                vec![KeyValue::new("uplink", uplink.name.clone())],
+            ))
+        })
+        .collect()
+}
+
+fn per_disk(frame: &TelemetryFrame, read: impl Fn(&DiskFrame) -> f64) -> Vec<Reading> {
+    frame
+        .system
+        .disks
+        .iter()
+        .map(|disk| {
+            let labels = vec![
+                KeyValue::new("mount", disk.mount_point.clone()),
+                KeyValue::new("obs_uses", disk.obs_uses.join(",")),
+            ];
+            (read(disk), labels)
+        })
+        .collect()
+}
+
+fn relay(frame: &TelemetryFrame, read: impl Fn(&RelayFrame) -> f64) -> Vec<Reading> {
+    frame.relay.iter().map(|r| (read(r), Vec::new())).collect()
+}
+
+fn per_link(frame: &TelemetryFrame, read: impl Fn(&RelayLinkFrame) -> f64) -> Vec<Reading> {
+    frame
+        .relay
+        .iter()
+        .flat_map(|r| &r.links)
+        .map(|link| {
+            let labels = vec![
+                KeyValue::new("link", link.link_id.clone()),
+                KeyValue::new("link_name", link.label.clone()),
+            ];
+            (read(link), labels)
+        })
+        .collect()
+}
+
+fn per_output(
+    frame: &TelemetryFrame,
+    names: &HashMap<String, String>,
+    read: impl Fn(&StreamOutput) -> f64,
+) -> Vec<Reading> {
+    frame
+        .streams
+        .iter()
+        .map(|out| (read(out), output_labels(out, names).to_vec()))
+        .collect()
+}
+
+/// Every metric name the gauge and counter exporters emit, in OTLP form.
+#[cfg(test)]
+pub fn metric_names() -> impl Iterator<Item = &'static str> {
+    GAUGES
+        .iter()
+        .map(|&(name, _)| name)
+        .chain(COUNTERS.iter().map(|&(name, _)| name))
+}
+
+fn output_labels(out: &StreamOutput, names: &HashMap<String, String>) -> [KeyValue; 2] {
+    let display = names
+        .get(&out.name)
+        .cloned()
+        .unwrap_or_else(|| default_output_name(&out.name).to_string());
+    [
+        KeyValue::new("output", out.name.clone()),
+        KeyValue::new("output_name", display),
+    ]
+}
+
+// Mirrors the dashboard's defaults for OBS's built-in output ids.
+fn default_output_name(id: &str) -> &str {
+    match id {
+        "adv_stream" | "simple_stream" => "Main Stream",
+        "adv_file_output" | "simple_file_output" => "Recording",
+        "virtualcam_output" => "Virtual Camera",
+        _ => id,
+    }
+}
+
+fn gpu_labels(gpu: &GpuFrame) -> [KeyValue; 3] {
+    [
+        KeyValue::new("gpu", gpu.index.to_string()),
+        KeyValue::new("vendor", gpu.vendor.clone()),
+        KeyValue::new("name", gpu.name.clone()),
+    ]
+}
+
+impl HistogramInstruments {
+    fn new(meter: &Meter) -> Self {
         let health = meter.f64_histogram("telemy.health").init();
         let cpu = meter.f64_histogram("telemy.system.cpu_percent").init();
         let mem = meter.f64_histogram("telemy.system.mem_percent").init();
         let gpu = meter.f64_histogram("telemy.system.gpu_percent").init();
         let gpu_temp = meter.f64_histogram("telemy.system.gpu_temp_c").init();
+        let encoder = meter.f64_histogram("telemy.system.encoder_percent").init();
+        let gpu_device_util = meter.f64_histogram("telemy.gpu.utilization_percent").init();
+        let gpu_device_vram = meter.f64_histogram("telemy.gpu.vram_used_mb").init();
+        let gpu_device_temp = meter.f64_histogram("telemy.gpu.temp_c").init();
+        let gpu_device_encoder = meter.f64_histogram("telemy.gpu.encoder_percent").init();
         let upload = meter.f64_histogram("telemy.network.upload_mbps").init();
         let download = meter.f64_histogram("telemy.network.download_mbps").init();
         let latency = meter.f64_histogram("telemy.network.latency_ms").init();
@@ -80,12 +580,17 @@ impl GrafanaExporter {
         let active_fps = meter.f64_histogram("telemy.obs.active_fps").init();
         let disk_space = meter.f64_histogram("telemy.obs.disk_space_mb").init();
 
-        Ok(Self {
+        Self {
             health,
             cpu,
             mem,
             gpu,
             gpu_temp,
+            encoder,
+            gpu_device_util,
+            gpu_device_vram,
+            gpu_device_temp,
+            gpu_device_encoder,
             upload,
             download,
             latency,
@@ -99,10 +604,10 @@ impl GrafanaExporter {
             output_total,
             active_fps,
             disk_space,
-        })
+        }
     }
 
-    pub fn record(&self, frame: &TelemetryFrame) {
+    fn record(&self, frame: &TelemetryFrame, output_names: &HashMap<String, String>) {
         self.health.record(frame.health as f64, &[]);
         self.cpu.record(frame.system.cpu_percent as f64, &[]);
         self.mem.record(frame.system.mem_percent as f64, &[]);
@@ -110,6 +615,9 @@ impl GrafanaExporter {
             .record(frame.system.gpu_percent.unwrap_or(0.0) as f64, &[]);
         self.gpu_temp
             .record(frame.system.gpu_temp_c.unwrap_or(0.0) as f64, &[]);
+        if let Some(encoder) = frame.system.encoder_percent {
+            self.encoder.record(encoder as f64, &[]);
+        }
         self.upload.record(frame.network.upload_mbps as f64, &[]);
         self.download
             .record(frame.network.download_mbps as f64, &[]);
@@ -128,8 +636,24 @@ impl GrafanaExporter {
         self.disk_space
             .record(frame.obs.available_disk_space_mb, &[]);
 
+        for gpu in &frame.system.gpus {
+            let labels = gpu_labels(gpu);
+            if let Some(util) = gpu.utilization_percent {
+                self.gpu_device_util.record(util as f64, &labels);
+            }
+            if let Some(used) = gpu.vram_used_mb {
+                self.gpu_device_vram.record(used as f64, &labels);
+            }
+            if let Some(temp) = gpu.temp_c {
+                self.gpu_device_temp.record(temp as f64, &labels);
+            }
+            if let Some(encoder) = gpu.encoder_percent {
+                self.gpu_device_encoder.record(encoder as f64, &labels);
+            }
+        }
+
         for out in &frame.streams {
-            let labels = [KeyValue::new("output", out.name.clone())];
+            let labels = output_labels(out, output_names);
             self.out_bitrate.record(out.bitrate_kbps as f64, &labels);
             self.out_drop.record(out.drop_pct as f64, &labels);
             self.out_fps.record(out.fps as f64, &labels);
@@ -137,3 +661,83 @@ impl GrafanaExporter {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attribute(resource: &Resource, key: &'static str) -> Option<String> {
+        resource
+            .get(opentelemetry::Key::from_static_str(key))
+            .map(|v| v.to_string())
+    }
+
+    #[test]
+    fn resource_identifies_instance_and_allows_overrides() {
+        let config = GrafanaConfig {
+            instance_id: Some("studio-pc".to_string()),
+            streamer_name: Some("irl_andy".to_string()),
+            resource_attributes: HashMap::from([
+                ("deployment.environment".to_string(), "irl".to_string()),
+                ("service.name".to_string(), "telemy-backpack".to_string()),
+            ]),
+            ..GrafanaConfig::default()
+        };
+        let resource = build_resource(&config);
+        assert_eq!(
+            attribute(&resource, "service.instance.id").as_deref(),
+            Some("studio-pc")
+        );
+        assert_eq!(
+            attribute(&resource, "telemy.streamer").as_deref(),
+            Some("irl_andy")
+        );
+        assert_eq!(
+            attribute(&resource, "service.name").as_deref(),
+            Some("telemy-backpack")
+        );
+        assert_eq!(
+            attribute(&resource, "deployment.environment").as_deref(),
+            Some("irl")
+        );
+        assert!(attribute(&resource, "host.name").is_some());
+    }
+
+    #[test]
+    fn output_labels_prefer_configured_names() {
+        let out = |name: &str| StreamOutput {
+            name: name.to_string(),
+            ..StreamOutput::default()
+        };
+        let names = HashMap::from([("adv_stream".to_string(), "Twitch".to_string())]);
+        let label = |out: &StreamOutput| output_labels(out, &names)[1].value.to_string();
+        assert_eq!(label(&out("adv_stream")), "Twitch");
+        assert_eq!(label(&out("adv_file_output")), "Recording");
+        assert_eq!(label(&out("obs_multi_rtmp_1")), "obs_multi_rtmp_1");
+    }
+
+    #[test]
+    fn relay_gauges_follow_the_relay_frame() {
+        let read = |frame: &TelemetryFrame, metric: &str| {
+            let (_, reader) = GAUGES.iter().find(|(name, _)| *name == metric).unwrap();
+            reader(frame, &HashMap::new())
+        };
+        let mut frame = TelemetryFrame::default();
+        assert!(read(&frame, "telemy.relay.connected").is_empty());
+
+        frame.relay = Some(RelayFrame {
+            connected: true,
+            links: vec![RelayLinkFrame {
+                link_id: "wlan0".to_string(),
+                label: "Phone".to_string(),
+                jitter_ms: 4.0,
+                ..RelayLinkFrame::default()
+            }],
+            ..RelayFrame::default()
+        });
+        assert_eq!(read(&frame, "telemy.relay.connected")[0].0, 1.0);
+        let jitter = read(&frame, "telemy.relay.link.jitter_ms");
+        assert_eq!(jitter[0].0, 4.0);
+        assert_eq!(jitter[0].1[1].value.to_string(), "Phone");
+    }
+}
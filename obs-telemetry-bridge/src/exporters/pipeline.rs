@@ -0,0 +1,193 @@
+use super::AnyError;
+use crate::model::TelemetryFrame;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::MissedTickBehavior;
+
+pub type SinkError = AnyError;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// Consecutive `record` errors after which a sink is torn down and rebuilt.
+const MAX_CONSECUTIVE_FAILURES: u32 = 5;
+
+/// A destination for telemetry frames (OTLP push, file recorder, ...).
+pub trait TelemetrySink: Send {
+    /// How often the sink is handed the latest frame; `None` delivers every new frame.
+    fn interval(&self) -> Option<Duration> {
+        None
+    }
+
+    fn record(&mut self, frame: &TelemetryFrame) -> Result<(), SinkError>;
+}
+
+type SinkFactory = Box<dyn FnMut() -> Result<Box<dyn TelemetrySink>, SinkError> + Send>;
+
+/// Fans the frame channel out to every configured sink. Each sink runs on its own
+/// task, so a slow or failing sink never delays the others; failed sinks are
+/// rebuilt from their factory with exponential backoff.
+#[derive(Default)]
+pub struct Pipeline {
+    sinks: Vec<(&'static str, SinkFactory)>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add<S, F>(&mut self, name: &'static str, mut factory: F)
+    where
+        S: TelemetrySink + 'static,
+        F: FnMut() -> Result<S, SinkError> + Send + 'static,
+    {
+        self.sinks.push((
+            name,
+            Box::new(move || factory().map(|s| Box::new(s) as Box<dyn TelemetrySink>)),
+        ));
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.sinks.iter().map(|(name, _)| *name).collect()
+    }
+
+    pub fn spawn(self, rx: watch::Receiver<TelemetryFrame>) {
+        for (name, factory) in self.sinks {
+            tokio::spawn(run_sink(name, factory, rx.clone()));
+        }
+    }
+}
+
+async fn run_sink(
+    name: &'static str,
+    mut factory: SinkFactory,
+    mut rx: watch::Receiver<TelemetryFrame>,
+) {
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        let mut sink = match factory() {
+            Ok(sink) => sink,
+            Err(err) => {
+                tracing::warn!(
+                    sink = name,
+                    error = %err,
+                    retry_in_ms = backoff.as_millis() as u64,
+                    "telemetry sink init failed"
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+                continue;
+            }
+        };
+        tracing::info!(sink = name, "telemetry sink started");
+
+        let mut ticker = sink.interval().map(|period| {
+            let mut ticker = tokio::time::interval(period);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            ticker
+        });
+        let mut failures = 0u32;
+        while failures < MAX_CONSECUTIVE_FAILURES {
+            match &mut ticker {
+                Some(ticker) => {
+                    ticker.tick().await;
+                    if rx.has_changed().is_err() {
+                        return;
+                    }
+                    // Interval sinks would otherwise keep re-sending the last frame.
+                    if crate::pause::is_paused() {
+                        continue;
+                    }
+                }
+                None => {
+                    if rx.changed().await.is_err() {
+                        return;
+                    }
+                }
+            }
+            let frame = rx.borrow_and_update().clone();
+            match sink.record(&frame) {
+                Ok(()) => {
+                    if failures > 0 {
+                        tracing::info!(sink = name, "telemetry sink recovered");
+                    }
+                    failures = 0;
+                    backoff = INITIAL_BACKOFF;
+                }
+                Err(err) => {
+                    failures += 1;
+                    tracing::warn!(
+                        sink = name,
+                        error = %err,
+                        failures,
+                        "telemetry sink write failed"
+                    );
+                }
+            }
+        }
+
+        tracing::warn!(
+            sink = name,
+            retry_in_ms = backoff.as_millis() as u64,
+            "telemetry sink restarting after repeated failures"
+        );
+        drop(sink);
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    struct FlakySink {
+        seen: Arc<Mutex<Vec<u64>>>,
+        fail: bool,
+    }
+
+    impl TelemetrySink for FlakySink {
+        fn record(&mut self, frame: &TelemetryFrame) -> Result<(), SinkError> {
+            if self.fail {
+                return Err("sink offline".into());
+            }
+            self.seen.lock().unwrap().push(frame.timestamp_unix);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn failing_sink_does_not_block_others() {
+        let healthy = Arc::new(Mutex::new(Vec::new()));
+        let broken = Arc::new(Mutex::new(Vec::new()));
+        let mut pipeline = Pipeline::new();
+        for (name, seen, fail) in [
+            ("healthy", healthy.clone(), false),
+            ("broken", broken.clone(), true),
+        ] {
+            pipeline.add(name, move || {
+                Ok(FlakySink {
+                    seen: seen.clone(),
+                    fail,
+                })
+            });
+        }
+        assert_eq!(pipeline.names(), vec!["healthy", "broken"]);
+
+        let (tx, rx) = watch::channel(TelemetryFrame::default());
+        pipeline.spawn(rx);
+        for ts in 1..=3 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            tx.send(TelemetryFrame {
+                timestamp_unix: ts,
+                ..Default::default()
+            })
+            .unwrap();
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+
+        assert_eq!(*healthy.lock().unwrap(), vec![1, 2, 3]);
+        assert!(broken.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,215 @@
+use super::{COUNTERS, GAUGES};
+use crate::model::TelemetryFrame;
+use crate::selfstats::SelfStatsSnapshot;
+use opentelemetry::KeyValue;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Renders the latest frame (same series as the OTLP gauges and counters) plus the
+/// bridge's self-telemetry in the Prometheus text exposition format.
+pub fn render(
+    frame: &TelemetryFrame,
+    output_names: &HashMap<String, String>,
+    stats: &SelfStatsSnapshot,
+) -> String {
+    let mut out = String::new();
+    for &(name, read) in GAUGES {
+        let name = metric_name(name);
+        let _ = writeln!(out, "# TYPE {name} gauge");
+        for (value, labels) in read(frame, output_names) {
+            sample(&mut out, &name, &labels, value);
+        }
+    }
+    for &(name, read) in COUNTERS {
+        let name = format!("{}_total", metric_name(name));
+        let _ = writeln!(out, "# TYPE {name} counter");
+        sample(&mut out, &name, &[], read(frame) as f64);
+    }
+    render_self(&mut out, stats);
+    out
+}
+
+fn render_self(out: &mut String, stats: &SelfStatsSnapshot) {
+    let mut series = vec![
+        (
+            "telemy_self_uptime_seconds",
+            "gauge",
+            stats.uptime_secs as f64,
+        ),
+        (
+            "telemy_self_collect_last_seconds",
+            "gauge",
+            stats.collect.last_ms / 1000.0,
+        ),
+        (
+            "telemy_self_collect_max_seconds",
+            "gauge",
+            stats.collect.max_ms / 1000.0,
+        ),
+        (
+            "telemy_self_obs_request_failures_total",
+            "counter",
+            stats.obs_request_failures as f64,
+        ),
+        (
+            "telemy_self_obs_connect_failures_total",
+            "counter",
+            stats.obs_connect_failures as f64,
+        ),
+        ("telemy_self_ws_clients", "gauge", stats.ws_clients as f64),
+        (
+            "telemy_self_ipc_session_connected",
+            "gauge",
+            u8::from(stats.ipc_session_connected) as f64,
+        ),
+        (
+            "telemy_self_otlp_buffered_batches",
+            "gauge",
+            stats.otlp_buffered_batches as f64,
+        ),
+        (
+            "telemy_self_metrics_restarts_total",
+            "counter",
+            stats.metrics_restarts as f64,
+        ),
+        // Per-session IPC counts reset on reconnect, so they are gauges.
+        (
+            "telemy_self_ipc_session_frames_sent",
+            "gauge",
+            stats.ipc_link.frames_sent as f64,
+        ),
+        (
+            "telemy_self_ipc_session_frames_received",
+            "gauge",
+            stats.ipc_link.frames_received as f64,
+        ),
+        (
+            "telemy_self_ipc_session_protocol_errors",
+            "gauge",
+            stats.ipc_link.protocol_errors as f64,
+        ),
+        (
+            "telemy_self_ipc_sessions_total",
+            "counter",
+            stats.ipc_link.session_count as f64,
+        ),
+    ];
+    if let Some(rtt_ms) = stats.ipc_link.rtt_ms {
+        series.push((
+            "telemy_self_ipc_rtt_seconds",
+            "gauge",
+            rtt_ms as f64 / 1000.0,
+        ));
+    }
+    if let Some(rss) = stats.process_rss_bytes {
+        series.push((
+            "telemy_self_process_resident_memory_bytes",
+            "gauge",
+            rss as f64,
+        ));
+    }
+    for (name, kind, value) in series {
+        let _ = writeln!(out, "# TYPE {name} {kind}");
+        sample(out, name, &[], value);
+    }
+
+    let name = "telemy_self_collect_duration_seconds";
+    let _ = writeln!(out, "# TYPE {name} summary");
+    sample(out, &format!("{name}_sum"), &[], stats.collect.total_secs);
+    sample(
+        out,
+        &format!("{name}_count"),
+        &[],
+        stats.collect.count as f64,
+    );
+
+    for (outcome, pick) in [("successes", true), ("failures", false)] {
+        let name = format!("telemy_self_export_{outcome}_total");
+        let _ = writeln!(out, "# TYPE {name} counter");
+        for (exporter, counts) in &stats.exports {
+            let value = if pick {
+                counts.successes
+            } else {
+                counts.failures
+            };
+            sample(
+                out,
+                &name,
+                &[KeyValue::new("exporter", *exporter)],
+                value as f64,
+            );
+        }
+    }
+}
+
+fn metric_name(otlp_name: &str) -> String {
+    otlp_name.replace('.', "_")
+}
+
+fn sample(out: &mut String, name: &str, labels: &[KeyValue], value: f64) {
+    out.push_str(name);
+    if !labels.is_empty() {
+        out.push('{');
+        for (idx, label) in labels.iter().enumerate() {
+            if idx > 0 {
+                out.push(',');
+            }
+            let value = label.value.as_str();
+            let _ = write!(
+                out,
+                "{}=\"{}\"",
+                metric_name(label.key.as_str()),
+                escape_label(&value)
+            );
+        }
+        out.push('}');
+    }
+    let _ = writeln!(out, " {value}");
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::StreamOutput;
+
+    #[test]
+    fn renders_frame_and_self_metrics() {
+        let frame = TelemetryFrame {
+            health: 0.75,
+            streams: vec![StreamOutput {
+                name: "adv_stream".to_string(),
+                bitrate_kbps: 6000,
+                ..StreamOutput::default()
+            }],
+            ..TelemetryFrame::default()
+        };
+        let names = HashMap::from([("adv_stream".to_string(), "Say \"hi\"".to_string())]);
+        let link = crate::ipc::IpcLinkStats {
+            rtt_ms: Some(12),
+            session_count: 2,
+            ..Default::default()
+        };
+        let mut stats = crate::selfstats::stats().snapshot(true, link);
+        stats.exports.insert("loki", Default::default());
+
+        let text = render(&frame, &names, &stats);
+        assert!(text.contains("# TYPE telemy_health gauge\ntelemy_health 0.75\n"));
+        assert!(text.contains(
+            "telemy_output_bitrate_kbps{output=\"adv_stream\",output_name=\"Say \\\"hi\\\"\"} 6000\n"
+        ));
+        assert!(text.contains("# TYPE telemy_obs_render_missed_frames_total counter\n"));
+        assert!(text.contains("telemy_self_ipc_session_connected 1\n"));
+        assert!(text.contains("telemy_self_ipc_rtt_seconds 0.012\n"));
+        assert!(text.contains("telemy_self_ipc_sessions_total 2\n"));
+        assert!(text.contains("telemy_self_export_failures_total{exporter=\"loki\"} 0\n"));
+    }
+}
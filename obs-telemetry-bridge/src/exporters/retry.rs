@@ -0,0 +1,256 @@
+use async_trait::async_trait;
+use opentelemetry::metrics::Result as MetricsResult;
+use opentelemetry_sdk::metrics::data::{ResourceMetrics, Temporality};
+use opentelemetry_sdk::metrics::exporter::PushMetricsExporter;
+use opentelemetry_sdk::metrics::reader::{AggregationSelector, TemporalitySelector};
+use opentelemetry_sdk::metrics::{Aggregation, InstrumentKind};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+struct RetryState {
+    backoff: Option<Duration>,
+    next_attempt: Option<Instant>,
+    dropped: u64,
+}
+
+/// Wraps an OTLP exporter so batches that fail to send are kept (up to
+/// `max_batches`, oldest dropped first) and replayed in order once the endpoint
+/// answers again. While the endpoint is down, sends are retried with exponential
+/// backoff rather than on every push interval.
+///
+/// Only transport failures (unreachable endpoint, timeouts) are buffered; the OTLP
+/// HTTP client treats any response, including 5xx, as delivered.
+pub struct BufferingExporter<E> {
+    inner: E,
+    max_batches: usize,
+    // An async mutex so a flush cancelled by the reader's export timeout leaves
+    // the unsent batches queued.
+    queue: tokio::sync::Mutex<VecDeque<ResourceMetrics>>,
+    retry: Mutex<RetryState>,
+}
+
+impl<E: PushMetricsExporter> BufferingExporter<E> {
+    pub fn new(inner: E, max_batches: usize) -> Self {
+        Self {
+            inner,
+            max_batches,
+            queue: tokio::sync::Mutex::new(VecDeque::new()),
+            retry: Mutex::new(RetryState::default()),
+        }
+    }
+
+    async fn export_at(&self, metrics: &mut ResourceMetrics, now: Instant) -> MetricsResult<()> {
+        if self.max_batches == 0 {
+            return self.inner.export(metrics).await;
+        }
+
+        // The reader reuses `metrics` for its next collection, so move the data out.
+        let batch = ResourceMetrics {
+            resource: metrics.resource.clone(),
+            scope_metrics: std::mem::take(&mut metrics.scope_metrics),
+        };
+        let mut queue = self.queue.lock().await;
+        queue.push_back(batch);
+        if queue.len() > self.max_batches {
+            queue.pop_front();
+            self.retry.lock().unwrap().dropped += 1;
+        }
+
+        let deferred = self
+            .retry
+            .lock()
+            .unwrap()
+            .next_attempt
+            .is_some_and(|at| now < at);
+        if deferred {
+            return Ok(());
+        }
+        self.flush_queue(&mut queue, now).await;
+        Ok(())
+    }
+
+    async fn flush_queue(&self, queue: &mut VecDeque<ResourceMetrics>, now: Instant) {
+        let backlog = queue.len();
+        while let Some(batch) = queue.front_mut() {
+            let sent = self.inner.export(batch).await;
+            crate::selfstats::stats().record_export("grafana_otlp", sent.is_ok());
+            if let Err(err) = sent {
+                let mut retry = self.retry.lock().unwrap();
+                let backoff = retry
+                    .backoff
+                    .map_or(INITIAL_BACKOFF, |b| (b * 2).min(MAX_BACKOFF));
+                if retry.backoff.is_none() {
+                    tracing::warn!(
+                        error = %err,
+                        "grafana export failed; buffering metrics until the endpoint recovers"
+                    );
+                }
+                retry.backoff = Some(backoff);
+                retry.next_attempt = Some(now + backoff);
+                crate::selfstats::stats().set_otlp_buffered(queue.len());
+                return;
+            }
+            queue.pop_front();
+        }
+        crate::selfstats::stats().set_otlp_buffered(0);
+
+        let mut retry = self.retry.lock().unwrap();
+        if retry.backoff.is_some() {
+            tracing::info!(
+                replayed = backlog,
+                dropped = retry.dropped,
+                "grafana export recovered"
+            );
+        }
+        *retry = RetryState::default();
+    }
+
+    #[cfg(test)]
+    async fn buffered(&self) -> usize {
+        self.queue.lock().await.len()
+    }
+}
+
+impl<E: PushMetricsExporter> AggregationSelector for BufferingExporter<E> {
+    fn aggregation(&self, kind: InstrumentKind) -> Aggregation {
+        self.inner.aggregation(kind)
+    }
+}
+
+impl<E: PushMetricsExporter> TemporalitySelector for BufferingExporter<E> {
+    fn temporality(&self, kind: InstrumentKind) -> Temporality {
+        self.inner.temporality(kind)
+    }
+}
+
+#[async_trait]
+impl<E: PushMetricsExporter> PushMetricsExporter for BufferingExporter<E> {
+    async fn export(&self, metrics: &mut ResourceMetrics) -> MetricsResult<()> {
+        self.export_at(metrics, Instant::now()).await
+    }
+
+    async fn force_flush(&self) -> MetricsResult<()> {
+        let mut queue = self.queue.lock().await;
+        self.flush_queue(&mut queue, Instant::now()).await;
+        self.inner.force_flush().await
+    }
+
+    fn shutdown(&self) -> MetricsResult<()> {
+        self.inner.shutdown()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::metrics::MetricsError;
+    use opentelemetry::{Key, KeyValue};
+    use opentelemetry_sdk::metrics::data::ScopeMetrics;
+    use opentelemetry_sdk::metrics::reader::{
+        DefaultAggregationSelector, DefaultTemporalitySelector,
+    };
+    use opentelemetry_sdk::Resource;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[derive(Default)]
+    struct FakeEndpoint {
+        down: AtomicBool,
+        received: Mutex<Vec<i64>>,
+    }
+
+    struct FakeExporter(Arc<FakeEndpoint>);
+
+    impl AggregationSelector for FakeExporter {
+        fn aggregation(&self, kind: InstrumentKind) -> Aggregation {
+            DefaultAggregationSelector::new().aggregation(kind)
+        }
+    }
+
+    impl TemporalitySelector for FakeExporter {
+        fn temporality(&self, kind: InstrumentKind) -> Temporality {
+            DefaultTemporalitySelector::new().temporality(kind)
+        }
+    }
+
+    #[async_trait]
+    impl PushMetricsExporter for FakeExporter {
+        async fn export(&self, metrics: &mut ResourceMetrics) -> MetricsResult<()> {
+            if self.0.down.load(Ordering::SeqCst) {
+                return Err(MetricsError::Other("connection refused".into()));
+            }
+            let seq = metrics.resource.get(Key::from_static_str("seq")).unwrap();
+            self.0
+                .received
+                .lock()
+                .unwrap()
+                .push(seq.to_string().parse().unwrap());
+            Ok(())
+        }
+
+        async fn force_flush(&self) -> MetricsResult<()> {
+            Ok(())
+        }
+
+        fn shutdown(&self) -> MetricsResult<()> {
+            Ok(())
+        }
+    }
+
+    fn batch(seq: i64) -> ResourceMetrics {
+        ResourceMetrics {
+            resource: Resource::new([KeyValue::new("seq", seq)]),
+            scope_metrics: vec![ScopeMetrics::default()],
+        }
+    }
+
+    #[tokio::test]
+    async fn replays_buffered_batches_in_order_after_outage() {
+        let endpoint = Arc::new(FakeEndpoint::default());
+        let exporter = BufferingExporter::new(FakeExporter(endpoint.clone()), 3);
+        let start = Instant::now();
+
+        endpoint.down.store(true, Ordering::SeqCst);
+        for seq in 1..=4 {
+            let at = start + Duration::from_secs(seq as u64 * 5);
+            exporter.export_at(&mut batch(seq), at).await.unwrap();
+        }
+        // Bounded at three batches: the oldest was dropped.
+        assert_eq!(exporter.buffered().await, 3);
+
+        endpoint.down.store(false, Ordering::SeqCst);
+        let recovered = start + Duration::from_secs(25 + MAX_BACKOFF.as_secs());
+        exporter.export_at(&mut batch(5), recovered).await.unwrap();
+        assert_eq!(*endpoint.received.lock().unwrap(), vec![3, 4, 5]);
+        assert_eq!(exporter.buffered().await, 0);
+    }
+
+    #[tokio::test]
+    async fn backs_off_between_attempts_while_endpoint_is_down() {
+        let endpoint = Arc::new(FakeEndpoint::default());
+        let exporter = BufferingExporter::new(FakeExporter(endpoint.clone()), 10);
+        let start = Instant::now();
+
+        endpoint.down.store(true, Ordering::SeqCst);
+        exporter.export_at(&mut batch(1), start).await.unwrap();
+        endpoint.down.store(false, Ordering::SeqCst);
+
+        // Still inside the first backoff window: queued, not sent.
+        exporter
+            .export_at(&mut batch(2), start + Duration::from_secs(1))
+            .await
+            .unwrap();
+        assert!(endpoint.received.lock().unwrap().is_empty());
+
+        exporter
+            .export_at(&mut batch(3), start + INITIAL_BACKOFF)
+            .await
+            .unwrap();
+        assert_eq!(*endpoint.received.lock().unwrap(), vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,374 @@
+use super::{AnyError, SinkError, TelemetrySink};
+use crate::config::WebhookConfig;
+use crate::model::TelemetryFrame;
+use crate::security::SecretString;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
+use serde_json::Value;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Payloads waiting for delivery; more are dropped while the endpoint is slow.
+const QUEUE_CAPACITY: usize = 64;
+
+/// Sent when `template` is not set: the triggering event (null for periodic
+/// pushes) and the whole frame.
+pub const DEFAULT_TEMPLATE: &str = r#"{"event":{{json event}},"frame":{{json frame}}}"#;
+
+/// A payload template rendered against `{ "event": ..., "frame": ... }`.
+///
+/// - `{{frame.obs.current_scene}}` inserts a value; strings are JSON-escaped
+///   without quotes so they can sit inside a JSON string. Numeric segments
+///   index arrays (`frame.streams.0.bitrate_kbps`), and missing values are empty.
+/// - `{{json frame.system}}` inserts the value as JSON (`null` when missing).
+/// - `{{#if event}}...{{else}}...{{/if}}` keeps the first branch when the value
+///   is set and not `false`, `0`, `""` or `[]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template(Vec<Node>);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Value {
+        path: Vec<String>,
+        json: bool,
+    },
+    If {
+        path: Vec<String>,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+enum Token {
+    Text(String),
+    Tag(String),
+}
+
+impl Template {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut tokens = tokenize(source)?.into_iter();
+        match parse_nodes(&mut tokens)? {
+            (nodes, None) => Ok(Self(nodes)),
+            (_, Some(tag)) => Err(format!("{{{{{tag}}}}} without a matching {{{{#if}}}}")),
+        }
+    }
+
+    pub fn render(&self, context: &Value) -> String {
+        let mut out = String::new();
+        render_nodes(&self.0, context, &mut out);
+        out
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            tokens.push(Token::Text(rest[..start].to_string()));
+        }
+        let len = rest[start..]
+            .find("}}")
+            .ok_or_else(|| format!("unclosed {{{{ at \"{}\"", &rest[start..]))?;
+        tokens.push(Token::Tag(rest[start + 2..start + len].trim().to_string()));
+        rest = &rest[start + len + 2..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest.to_string()));
+    }
+    Ok(tokens)
+}
+
+/// Nodes up to the end or the next `else`/`/if`, which is returned so the
+/// enclosing `#if` can tell its branches apart.
+fn parse_nodes(
+    tokens: &mut impl Iterator<Item = Token>,
+) -> Result<(Vec<Node>, Option<String>), String> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next() {
+        let tag = match token {
+            Token::Text(text) => {
+                nodes.push(Node::Text(text));
+                continue;
+            }
+            Token::Tag(tag) => tag,
+        };
+        if tag == "else" || tag == "/if" {
+            return Ok((nodes, Some(tag)));
+        }
+        if let Some(path) = tag.strip_prefix("#if ") {
+            let path = parse_path(path)?;
+            let (then, end) = parse_nodes(tokens)?;
+            let otherwise = match end.as_deref() {
+                Some("/if") => Vec::new(),
+                Some("else") => match parse_nodes(tokens)? {
+                    (otherwise, Some(end)) if end == "/if" => otherwise,
+                    _ => return Err("{{else}} without a closing {{/if}}".to_string()),
+                },
+                _ => return Err("{{#if}} without a closing {{/if}}".to_string()),
+            };
+            nodes.push(Node::If {
+                path,
+                then,
+                otherwise,
+            });
+        } else if let Some(path) = tag.strip_prefix("json ") {
+            nodes.push(Node::Value {
+                path: parse_path(path)?,
+                json: true,
+            });
+        } else {
+            nodes.push(Node::Value {
+                path: parse_path(&tag)?,
+                json: false,
+            });
+        }
+    }
+    Ok((nodes, None))
+}
+
+fn parse_path(path: &str) -> Result<Vec<String>, String> {
+    let segments: Vec<String> = path.trim().split('.').map(str::to_string).collect();
+    let valid = segments.iter().all(|segment| {
+        !segment.is_empty()
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+    });
+    if !valid {
+        return Err(format!("invalid template path \"{}\"", path.trim()));
+    }
+    Ok(segments)
+}
+
+fn lookup<'a>(context: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(context, |value, segment| match value {
+        Value::Object(map) => map.get(segment),
+        Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+        _ => None,
+    })
+}
+
+fn truthy(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) | Some(Value::Bool(false)) => false,
+        Some(Value::Number(n)) => n.as_f64() != Some(0.0),
+        Some(Value::String(s)) => !s.is_empty(),
+        Some(Value::Array(items)) => !items.is_empty(),
+        Some(_) => true,
+    }
+}
+
+fn render_nodes(nodes: &[Node], context: &Value, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Value { path, json: true } => match lookup(context, path) {
+                Some(value) => out.push_str(&value.to_string()),
+                None => out.push_str("null"),
+            },
+            Node::Value { path, json: false } => match lookup(context, path) {
+                Some(Value::String(s)) => {
+                    let quoted = Value::String(s.clone()).to_string();
+                    out.push_str(&quoted[1..quoted.len() - 1]);
+                }
+                Some(Value::Null) | None => {}
+                Some(value) => out.push_str(&value.to_string()),
+            },
+            Node::If {
+                path,
+                then,
+                otherwise,
+            } => {
+                let branch = if truthy(lookup(context, path)) {
+                    then
+                } else {
+                    otherwise
+                };
+                render_nodes(branch, context, out);
+            }
+        }
+    }
+}
+
+/// POSTs a rendered template every `every_n_frames` frames and on the
+/// `on_events` transitions (the scripting events: `stream_started`,
+/// `scene_changed`, ...). Requests go out from a background task so a slow
+/// endpoint never holds up the frame; failures are logged, not retried.
+pub struct WebhookSink {
+    name: String,
+    template: Template,
+    every_n_frames: u64,
+    on_events: Vec<String>,
+    frames: u64,
+    prev: Option<TelemetryFrame>,
+    tx: mpsc::Sender<String>,
+}
+
+impl WebhookSink {
+    pub fn new(config: &WebhookConfig, auth_value: Option<SecretString>) -> Result<Self, AnyError> {
+        let template = Template::parse(config.template.as_deref().unwrap_or(DEFAULT_TEMPLATE))?;
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_str(&config.content_type)?);
+        for (name, value) in &config.headers {
+            headers.insert(HeaderName::from_bytes(name.as_bytes())?, value.parse()?);
+        }
+        if let Some(value) = auth_value {
+            let mut value = HeaderValue::from_str(value.expose())?;
+            value.set_sensitive(true);
+            headers.insert(
+                HeaderName::from_bytes(config.auth_header.as_bytes())?,
+                value,
+            );
+        }
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .default_headers(headers)
+            .build()?;
+
+        let (tx, rx) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(deliver(config.name.clone(), client, config.url.clone(), rx));
+        Ok(Self {
+            name: config.name.clone(),
+            template,
+            every_n_frames: config.every_n_frames,
+            on_events: config.on_events.clone(),
+            frames: 0,
+            prev: None,
+            tx,
+        })
+    }
+
+    /// One payload per subscribed transition since the previous frame, then
+    /// the periodic one when this frame is due.
+    fn payloads(&mut self, frame: &TelemetryFrame) -> Vec<String> {
+        let frame_json = serde_json::to_value(frame).unwrap_or(Value::Null);
+        let mut events = Vec::new();
+        if let Some(prev) = &self.prev {
+            for (name, data) in crate::scripting::frame_events(prev, frame) {
+                if !self.on_events.iter().any(|e| e == name) {
+                    continue;
+                }
+                let mut event = rhai::serde::from_dynamic::<Value>(&data.into())
+                    .unwrap_or_else(|_| Value::Object(Default::default()));
+                event["name"] = name.into();
+                events.push(event);
+            }
+        }
+        self.prev = Some(frame.clone());
+        self.frames += 1;
+        if self.every_n_frames > 0 && self.frames.is_multiple_of(self.every_n_frames) {
+            events.push(Value::Null);
+        }
+
+        events
+            .into_iter()
+            .map(|event| {
+                let context = serde_json::json!({ "event": event, "frame": frame_json });
+                self.template.render(&context)
+            })
+            .collect()
+    }
+}
+
+impl TelemetrySink for WebhookSink {
+    fn record(&mut self, frame: &TelemetryFrame) -> Result<(), SinkError> {
+        for payload in self.payloads(frame) {
+            match self.tx.try_send(payload) {
+                Ok(()) => {}
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    tracing::warn!(webhook = %self.name, "webhook queue full; dropping payload");
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => {
+                    return Err("webhook delivery task stopped".into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+async fn deliver(
+    name: String,
+    client: reqwest::Client,
+    url: String,
+    mut rx: mpsc::Receiver<String>,
+) {
+    while let Some(body) = rx.recv().await {
+        let ok = match client.post(&url).body(body).send().await {
+            Ok(resp) if resp.status().is_success() => true,
+            Ok(resp) => {
+                tracing::warn!(webhook = %name, status = %resp.status(), "webhook rejected");
+                false
+            }
+            Err(err) => {
+                tracing::warn!(webhook = %name, error = %err, "webhook push failed");
+                false
+            }
+        };
+        crate::selfstats::stats().record_export("webhook", ok);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn templates_fill_values_conditions_and_json() {
+        let template = Template::parse(
+            r#"{"text":"{{#if event}}{{event.name}}: {{event.to}}{{else}}tick{{/if}} at {{frame.health}}","sys":{{json frame.system}},"gone":"{{frame.nope}}"}"#,
+        )
+        .unwrap();
+        let context = serde_json::json!({
+            "event": { "name": "scene_changed", "to": "BRB \"2\"" },
+            "frame": { "health": 0.9, "system": { "cpu_percent": 12 } },
+        });
+        assert_eq!(
+            template.render(&context),
+            r#"{"text":"scene_changed: BRB \"2\" at 0.9","sys":{"cpu_percent":12},"gone":""}"#
+        );
+        let periodic = serde_json::json!({ "event": null, "frame": { "health": 1 } });
+        assert!(template
+            .render(&periodic)
+            .starts_with(r#"{"text":"tick at 1""#));
+
+        let list = Template::parse("{{frame.streams.1.name}}").unwrap();
+        let streams =
+            serde_json::json!({ "frame": { "streams": [{ "name": "a" }, { "name": "b" }] } });
+        assert_eq!(list.render(&streams), "b");
+    }
+
+    #[test]
+    fn template_errors_are_reported() {
+        assert!(Template::parse(DEFAULT_TEMPLATE).is_ok());
+        for bad in [
+            "{{#if event}}x",
+            "x{{/if}}",
+            "{{frame.",
+            "{{frame..obs}}",
+            "{{#if a}}{{else}}",
+        ] {
+            assert!(Template::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[tokio::test]
+    async fn payloads_follow_the_triggers() {
+        let config = WebhookConfig {
+            url: "http://127.0.0.1:9/hook".to_string(),
+            every_n_frames: 2,
+            on_events: vec!["stream_started".to_string()],
+            template: Some("{{#if event}}{{event.name}}{{else}}tick{{/if}}".to_string()),
+            ..Default::default()
+        };
+        let mut sink = WebhookSink::new(&config, None).unwrap();
+        let mut frame = TelemetryFrame::default();
+        assert!(sink.payloads(&frame).is_empty());
+        frame.obs.connected = true;
+        frame.obs.streaming = true;
+        assert_eq!(sink.payloads(&frame), ["stream_started", "tick"]);
+        assert!(sink.payloads(&frame).is_empty());
+        assert_eq!(sink.payloads(&frame), ["tick"]);
+    }
+}
@@ -0,0 +1,238 @@
+use crate::activity::ActionKind;
+use crate::config::{ConfigHandle, FailoverConfig};
+use crate::ipc::OverrideStoreHandle;
+use crate::metrics::ObsLink;
+use crate::model::TelemetryFrame;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tier {
+    Live,
+    Low,
+    Brb,
+}
+
+impl Tier {
+    /// The tier whose scene is on program, `None` on any other scene.
+    fn of(scene: &str, config: &FailoverConfig, brb_scene: &str) -> Option<Self> {
+        if scene == config.live_scene {
+            Some(Self::Live)
+        } else if config.low_scene.as_deref() == Some(scene) {
+            Some(Self::Low)
+        } else if scene == brb_scene {
+            Some(Self::Brb)
+        } else {
+            None
+        }
+    }
+
+    fn scene<'a>(self, config: &'a FailoverConfig, brb_scene: &'a str) -> &'a str {
+        match self {
+            Self::Live => &config.live_scene,
+            Self::Low => config.low_scene.as_deref().unwrap_or(brb_scene),
+            Self::Brb => brb_scene,
+        }
+    }
+
+    /// Where `kbps` belongs coming from this tier: entering a worse tier takes
+    /// its enter threshold, leaving one its exit threshold.
+    fn wanted(self, kbps: u32, config: &FailoverConfig) -> Self {
+        let low = config.low_scene.is_some();
+        match self {
+            Self::Live if kbps < config.brb_enter_kbps => Self::Brb,
+            Self::Live if low && kbps < config.low_enter_kbps => Self::Low,
+            Self::Live => Self::Live,
+            Self::Low if kbps < config.brb_enter_kbps => Self::Brb,
+            Self::Low if kbps >= config.low_exit_kbps => Self::Live,
+            Self::Low => Self::Low,
+            Self::Brb if kbps < config.brb_exit_kbps => Self::Brb,
+            Self::Brb if low && kbps < config.low_exit_kbps => Self::Low,
+            Self::Brb => Self::Live,
+        }
+    }
+}
+
+/// The bitrate failover watches: the relay's incoming stream while one is
+/// attached, else OBS's own main output.
+fn bitrate_kbps(frame: &TelemetryFrame) -> Option<u32> {
+    match &frame.relay {
+        Some(relay) if relay.connected => Some(relay.bitrate_kbps),
+        _ => crate::rules::main_output(frame).map(|o| o.bitrate_kbps),
+    }
+}
+
+/// Decides scene switches while live. The current tier is read from the
+/// program scene, so a manual switch between the failover scenes is followed
+/// and any other scene (intro, ending) is left alone.
+#[derive(Debug, Default)]
+struct Failover {
+    /// The tier the bitrate points at and since when.
+    pending: Option<(Tier, Instant)>,
+}
+
+impl Failover {
+    fn observe(
+        &mut self,
+        frame: &TelemetryFrame,
+        config: &FailoverConfig,
+        brb_scene: &str,
+        now: Instant,
+    ) -> Option<Tier> {
+        let current = frame
+            .obs
+            .current_scene
+            .as_deref()
+            .filter(|_| frame.obs.streaming)
+            .and_then(|scene| Tier::of(scene, config, brb_scene));
+        let (Some(current), Some(kbps)) = (current, bitrate_kbps(frame)) else {
+            self.pending = None;
+            return None;
+        };
+        let wanted = current.wanted(kbps, config);
+        if wanted == current {
+            self.pending = None;
+            return None;
+        }
+        let since = match self.pending {
+            Some((tier, since)) if tier == wanted => since,
+            _ => {
+                self.pending = Some((wanted, now));
+                now
+            }
+        };
+        if now.duration_since(since) < Duration::from_secs(config.hold_secs) {
+            return None;
+        }
+        self.pending = None;
+        Some(wanted)
+    }
+}
+
+/// Switches between `[failover]`'s scenes on the stream's bitrate, reading
+/// the live config and the dock's `auto_scene_switch` override every frame,
+/// and alerts on each switch. The manual override pauses it.
+pub fn spawn(
+    config: ConfigHandle,
+    obs: ObsLink,
+    overrides: OverrideStoreHandle,
+    mut rx: watch::Receiver<TelemetryFrame>,
+) {
+    tokio::spawn(async move {
+        let mut failover = Failover::default();
+        while rx.changed().await.is_ok() {
+            let frame = rx.borrow_and_update().clone();
+            let (settings, brb_scene, notifications) = config.read(|c| {
+                (
+                    c.failover.clone(),
+                    c.tray.brb_scene.clone(),
+                    c.notifications.clone(),
+                )
+            });
+            let enabled = overrides
+                .get()
+                .auto_scene_switch
+                .unwrap_or(settings.enabled);
+            if !enabled || overrides.manual_override() {
+                failover = Failover::default();
+                continue;
+            }
+            let Some(tier) = failover.observe(&frame, &settings, &brb_scene, Instant::now()) else {
+                continue;
+            };
+            let scene = tier.scene(&settings, &brb_scene);
+            let kbps = bitrate_kbps(&frame).unwrap_or_default();
+            let result = obs.switch_scene(scene).await;
+            crate::activity::log().record(
+                ActionKind::SwitchScene,
+                "failover",
+                Some(format!("{kbps} kbps")),
+                result.clone().map(|()| format!("switched to '{scene}'")),
+            );
+            let message = match result {
+                Ok(()) => {
+                    tracing::info!(scene, kbps, ?tier, "failover switched scenes");
+                    format!("Failover: {kbps} kbps, switched to {scene}")
+                }
+                Err(err) => format!("Failover to {scene} failed: {err}"),
+            };
+            crate::notify::alert(&notifications, &message);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::RelayFrame;
+
+    fn live(scene: &str, kbps: u32) -> TelemetryFrame {
+        let mut frame = TelemetryFrame::default();
+        frame.obs.streaming = true;
+        frame.obs.current_scene = Some(scene.to_string());
+        frame.relay = Some(RelayFrame {
+            connected: true,
+            bitrate_kbps: kbps,
+            ..Default::default()
+        });
+        frame
+    }
+
+    #[test]
+    fn tiers_have_their_own_enter_and_exit_thresholds() {
+        let config = FailoverConfig {
+            low_scene: Some("Audio only".to_string()),
+            ..Default::default()
+        };
+        let tier = |from: Tier, kbps| from.wanted(kbps, &config);
+        assert_eq!(tier(Tier::Live, 2000), Tier::Live);
+        assert_eq!(tier(Tier::Live, 1000), Tier::Low);
+        assert_eq!(tier(Tier::Live, 100), Tier::Brb);
+        assert_eq!(tier(Tier::Low, 2000), Tier::Low, "below low_exit_kbps");
+        assert_eq!(tier(Tier::Low, 2500), Tier::Live);
+        assert_eq!(tier(Tier::Brb, 500), Tier::Brb, "below brb_exit_kbps");
+        assert_eq!(tier(Tier::Brb, 1000), Tier::Low);
+        assert_eq!(tier(Tier::Brb, 3000), Tier::Live);
+
+        let direct = FailoverConfig::default();
+        assert_eq!(Tier::Live.wanted(1000, &direct), Tier::Live);
+        assert_eq!(Tier::Brb.wanted(1000, &direct), Tier::Live);
+    }
+
+    #[test]
+    fn switches_after_the_hold_and_only_from_failover_scenes() {
+        let config = FailoverConfig {
+            low_scene: Some("Audio only".to_string()),
+            ..Default::default()
+        };
+        let mut failover = Failover::default();
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        let sagging = live("Live", 1000);
+        assert_eq!(failover.observe(&sagging, &config, "BRB", at(0)), None);
+        assert_eq!(failover.observe(&sagging, &config, "BRB", at(2)), None);
+        assert_eq!(
+            failover.observe(&sagging, &config, "BRB", at(3)),
+            Some(Tier::Low)
+        );
+
+        let dead = live("Audio only", 0);
+        assert_eq!(failover.observe(&dead, &config, "BRB", at(4)), None);
+        assert_eq!(
+            failover.observe(&dead, &config, "BRB", at(7)),
+            Some(Tier::Brb)
+        );
+
+        let back = live("BRB", 4000);
+        assert_eq!(failover.observe(&back, &config, "BRB", at(8)), None);
+        assert_eq!(
+            failover.observe(&back, &config, "BRB", at(11)),
+            Some(Tier::Live)
+        );
+
+        let intro = live("Starting Soon", 0);
+        assert_eq!(failover.observe(&intro, &config, "BRB", at(12)), None);
+        assert_eq!(failover.observe(&intro, &config, "BRB", at(20)), None);
+    }
+}
@@ -0,0 +1,383 @@
+use crate::aegis::RelaySessionHandle;
+use crate::config::ConfigHandle;
+use rand::Rng;
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+use tokio::time::timeout;
+use utoipa::ToSchema;
+
+/// Probed when no target is set and no relay session is active.
+const TWITCH_INGEST: &str = "rtmp://live.twitch.tv/app";
+/// How often a disabled schedule looks at the config again.
+const IDLE_RECHECK: Duration = Duration::from_secs(30);
+/// RTMP handshake version byte, sent in C0 and expected back in S0.
+const RTMP_VERSION: u8 = 3;
+const RTMP_C1_LEN: usize = 1536;
+const SRT_HANDSHAKE_LEN: usize = 64;
+const SRT_INDUCTION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Protocol {
+    Rtmp,
+    /// RTMP over TLS; only the TCP connect is checked.
+    Rtmps,
+    Srt,
+    /// A bare `host:port`, checked with a TCP connect.
+    Tcp,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub protocol: Protocol,
+    pub host: String,
+    pub port: u16,
+}
+
+impl Target {
+    /// Reads an ingest URL as OBS takes it (`rtmp://host/app`,
+    /// `srt://host:port?streamid=...`) or a bare `host:port`.
+    pub fn parse(target: &str) -> Result<Self, String> {
+        let target = target.trim();
+        let (protocol, rest) = match target.split_once("://") {
+            Some((scheme, rest)) => {
+                let protocol = match scheme.to_ascii_lowercase().as_str() {
+                    "rtmp" => Protocol::Rtmp,
+                    "rtmps" => Protocol::Rtmps,
+                    "srt" => Protocol::Srt,
+                    other => return Err(format!("unsupported scheme {other}")),
+                };
+                (protocol, rest)
+            }
+            None => (Protocol::Tcp, target),
+        };
+        let authority = rest.split(['/', '?']).next().unwrap_or_default();
+        let (host, port) = match authority.strip_prefix('[') {
+            Some(v6) => {
+                let (host, rest) = v6
+                    .split_once(']')
+                    .ok_or_else(|| format!("unclosed [ in {target}"))?;
+                (host, rest.strip_prefix(':'))
+            }
+            None => match authority.split_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            },
+        };
+        let port = port
+            .map(|port| port.parse::<u16>())
+            .transpose()
+            .map_err(|_| format!("invalid port in {target}"))?;
+        if host.is_empty() {
+            return Err(format!("no host in {target}"));
+        }
+        let port = match (port, protocol) {
+            (Some(port), _) => port,
+            (None, Protocol::Rtmp) => 1935,
+            (None, Protocol::Rtmps) => 443,
+            (None, Protocol::Srt | Protocol::Tcp) => {
+                return Err(format!("{target} needs a port"));
+            }
+        };
+        Ok(Self {
+            protocol,
+            host: host.to_string(),
+            port,
+        })
+    }
+}
+
+/// Outcome of one probe. `rtt_ms` is the TCP connect time for RTMP and the
+/// induction round trip for SRT.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct IngestCheck {
+    pub target: String,
+    pub protocol: Protocol,
+    pub reachable: bool,
+    pub rtt_ms: Option<f64>,
+    pub error: Option<String>,
+    pub checked_at_unix_ms: u64,
+}
+
+/// Tracks the scheduled probe's reachability so an outage alerts once, and
+/// its end once.
+#[derive(Debug, Default)]
+struct Outage {
+    down: bool,
+}
+
+impl Outage {
+    fn observe(&mut self, reachable: bool) -> Option<bool> {
+        if self.down == reachable {
+            self.down = !reachable;
+            return Some(reachable);
+        }
+        None
+    }
+}
+
+/// Probes the configured ingest, on a schedule and on request.
+pub struct IngestProbe {
+    config: ConfigHandle,
+    session: RelaySessionHandle,
+}
+
+impl IngestProbe {
+    /// `network.ingest_check.target`, else the active Aegis relay's SRT
+    /// listener, else Twitch's ingest.
+    pub async fn default_target(&self) -> String {
+        if let Some(target) = self.config.read(|c| c.network.ingest_check.target.clone()) {
+            return target;
+        }
+        let session = self.session.read().await;
+        let relay = session.as_ref().and_then(|s| s.relay.as_ref());
+        match relay.and_then(|r| Some((r.public_ip.as_deref()?, r.srt_port?))) {
+            Some((ip, port)) => format!("srt://{ip}:{port}"),
+            None => TWITCH_INGEST.to_string(),
+        }
+    }
+
+    /// Probes `target`, or the default target when `None`. Only an
+    /// unparseable target is an error; an unreachable one is a result.
+    pub async fn check(&self, target: Option<&str>) -> Result<IngestCheck, String> {
+        let target = match target {
+            Some(target) => target.to_string(),
+            None => self.default_target().await,
+        };
+        let parsed = Target::parse(&target)?;
+        let wait = Duration::from_millis(self.config.read(|c| c.network.ingest_check.timeout_ms));
+        let result = timeout(wait, probe(&parsed))
+            .await
+            .unwrap_or_else(|_| Err(format!("no answer within {} ms", wait.as_millis())));
+        Ok(IngestCheck {
+            target,
+            protocol: parsed.protocol,
+            reachable: result.is_ok(),
+            rtt_ms: result.as_ref().ok().map(|rtt| rtt.as_secs_f64() * 1000.0),
+            error: result.err(),
+            checked_at_unix_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+        })
+    }
+}
+
+async fn resolve(target: &Target) -> Result<SocketAddr, String> {
+    tokio::net::lookup_host((target.host.as_str(), target.port))
+        .await
+        .map_err(|err| format!("cannot resolve {}: {err}", target.host))?
+        .next()
+        .ok_or_else(|| format!("{} has no address", target.host))
+}
+
+async fn probe(target: &Target) -> Result<Duration, String> {
+    let addr = resolve(target).await?;
+    match target.protocol {
+        Protocol::Srt => probe_srt(addr).await,
+        Protocol::Rtmp => probe_tcp(addr, true).await,
+        Protocol::Rtmps | Protocol::Tcp => probe_tcp(addr, false).await,
+    }
+}
+
+/// Connects and, for plain RTMP, sends C0/C1 and waits for the server's S0.
+async fn probe_tcp(addr: SocketAddr, rtmp_handshake: bool) -> Result<Duration, String> {
+    let started = Instant::now();
+    let mut stream = TcpStream::connect(addr)
+        .await
+        .map_err(|err| format!("connect to {addr} failed: {err}"))?;
+    let rtt = started.elapsed();
+    if rtmp_handshake {
+        let mut hello = vec![0u8; 1 + RTMP_C1_LEN];
+        hello[0] = RTMP_VERSION;
+        rand::thread_rng().fill(&mut hello[9..]);
+        stream
+            .write_all(&hello)
+            .await
+            .map_err(|err| format!("RTMP handshake failed: {err}"))?;
+        let version = stream
+            .read_u8()
+            .await
+            .map_err(|err| format!("RTMP handshake failed: {err}"))?;
+        if version != RTMP_VERSION {
+            return Err(format!("unexpected RTMP version {version}"));
+        }
+    }
+    let _ = stream.shutdown().await;
+    Ok(rtt)
+}
+
+/// A caller's SRT induction request (HSv4 framing, as every caller sends it).
+fn srt_induction(socket_id: u32, initial_seq: u32) -> [u8; SRT_HANDSHAKE_LEN] {
+    let words: [u32; 12] = [
+        0x8000_0000, // control packet, type 0: handshake
+        0,
+        0, // timestamp
+        0, // destination socket: unknown yet
+        4, // version
+        2, // encryption 0, extension field 2
+        initial_seq,
+        1500, // MTU
+        8192, // flow window
+        SRT_INDUCTION,
+        socket_id,
+        0, // SYN cookie
+    ];
+    let mut packet = [0u8; SRT_HANDSHAKE_LEN];
+    for (chunk, word) in packet.chunks_mut(4).zip(words) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    packet
+}
+
+/// Whether `reply` is the listener's induction response to `socket_id`.
+fn is_induction_reply(reply: &[u8], socket_id: u32) -> bool {
+    let word = |i: usize| u32::from_be_bytes(reply[i * 4..i * 4 + 4].try_into().unwrap());
+    reply.len() >= SRT_HANDSHAKE_LEN
+        && word(0) == 0x8000_0000
+        && word(3) == socket_id
+        && word(9) == SRT_INDUCTION
+}
+
+/// Sends an SRT induction request and waits for the listener's response. No
+/// connection is set up, so the relay never sees a caller.
+async fn probe_srt(addr: SocketAddr) -> Result<Duration, String> {
+    let bind: SocketAddr = if addr.is_ipv4() {
+        "0.0.0.0:0".parse().unwrap()
+    } else {
+        "[::]:0".parse().unwrap()
+    };
+    let socket = UdpSocket::bind(bind)
+        .await
+        .map_err(|err| format!("UDP socket failed: {err}"))?;
+    socket
+        .connect(addr)
+        .await
+        .map_err(|err| format!("UDP connect to {addr} failed: {err}"))?;
+    let (socket_id, initial_seq) = {
+        let mut rng = rand::thread_rng();
+        (rng.gen_range(1..0x4000_0000), rng.gen_range(0..0x7fff_ffff))
+    };
+    let started = Instant::now();
+    socket
+        .send(&srt_induction(socket_id, initial_seq))
+        .await
+        .map_err(|err| format!("SRT handshake to {addr} failed: {err}"))?;
+    let mut reply = [0u8; 1500];
+    loop {
+        let len = socket
+            .recv(&mut reply)
+            .await
+            .map_err(|err| format!("SRT handshake to {addr} failed: {err}"))?;
+        if is_induction_reply(&reply[..len], socket_id) {
+            return Ok(started.elapsed());
+        }
+    }
+}
+
+/// Probes the default target every `network.ingest_check.interval_secs`,
+/// reading the schedule from the live config, and alerts when the ingest
+/// stops answering and once it is back.
+pub fn spawn(config: ConfigHandle, session: RelaySessionHandle) -> Arc<IngestProbe> {
+    let probe = Arc::new(IngestProbe { config, session });
+    let scheduled = probe.clone();
+    tokio::spawn(async move {
+        let mut outage = Outage::default();
+        loop {
+            let (settings, notifications) = scheduled
+                .config
+                .read(|c| (c.network.ingest_check.clone(), c.notifications.clone()));
+            if settings.interval_secs == 0 {
+                tokio::time::sleep(IDLE_RECHECK).await;
+                continue;
+            }
+            match scheduled.check(None).await {
+                Ok(check) => {
+                    tracing::debug!(
+                        ingest = %check.target,
+                        reachable = check.reachable,
+                        rtt_ms = ?check.rtt_ms,
+                        "ingest check"
+                    );
+                    match outage.observe(check.reachable) {
+                        Some(false) if settings.alert => crate::notify::alert(
+                            &notifications,
+                            &format!(
+                                "Ingest {} unreachable: {}",
+                                check.target,
+                                check.error.as_deref().unwrap_or("no answer")
+                            ),
+                        ),
+                        Some(true) if settings.alert => crate::notify::alert(
+                            &notifications,
+                            &format!("Ingest {} reachable again", check.target),
+                        ),
+                        _ => {}
+                    }
+                }
+                Err(err) => tracing::warn!(error = %err, "ingest check skipped"),
+            }
+            tokio::time::sleep(Duration::from_secs(settings.interval_secs)).await;
+        }
+    });
+    probe
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ingest_urls() {
+        let twitch = Target::parse("rtmp://live.twitch.tv/app").unwrap();
+        assert_eq!(twitch.protocol, Protocol::Rtmp);
+        assert_eq!(
+            (twitch.host.as_str(), twitch.port),
+            ("live.twitch.tv", 1935)
+        );
+
+        let relay = Target::parse("srt://203.0.113.7:9000?streamid=live").unwrap();
+        assert_eq!(relay.protocol, Protocol::Srt);
+        assert_eq!((relay.host.as_str(), relay.port), ("203.0.113.7", 9000));
+
+        let v6 = Target::parse("srt://[2001:db8::1]:9000").unwrap();
+        assert_eq!((v6.host.as_str(), v6.port), ("2001:db8::1", 9000));
+
+        assert_eq!(
+            Target::parse("rtmps://ingest.example.com/live")
+                .unwrap()
+                .port,
+            443
+        );
+        assert_eq!(
+            Target::parse("ingest.example.com:1935").unwrap().protocol,
+            Protocol::Tcp
+        );
+        assert!(Target::parse("srt://relay.example.com").is_err());
+        assert!(Target::parse("rtmp://:1935/app").is_err());
+        assert!(Target::parse("http://example.com").is_err());
+    }
+
+    #[test]
+    fn induction_reply_must_answer_our_socket() {
+        let mut reply = srt_induction(0, 0);
+        reply[12..16].copy_from_slice(&42u32.to_be_bytes());
+        assert!(is_induction_reply(&reply, 42));
+        assert!(!is_induction_reply(&reply, 7));
+        assert!(!is_induction_reply(&reply[..16], 42));
+    }
+
+    #[test]
+    fn outage_alerts_on_transitions_only() {
+        let mut outage = Outage::default();
+        assert_eq!(outage.observe(true), None);
+        assert_eq!(outage.observe(false), Some(false));
+        assert_eq!(outage.observe(false), None);
+        assert_eq!(outage.observe(true), Some(true));
+        assert_eq!(outage.observe(true), None);
+    }
+}
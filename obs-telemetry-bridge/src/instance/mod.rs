@@ -0,0 +1,178 @@
+use crate::security::SecretString;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, TryLockError};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const LOCAL_API_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What the running instance publishes next to its lock, so a second launch can
+/// find its server.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InstanceInfo {
+    pub pid: u32,
+    pub port: u16,
+    pub started_unix: u64,
+}
+
+/// Held by `serve` for the life of the process; the OS drops the lock when the
+/// process exits, however it exits, so a crash never leaves a stale lock.
+#[derive(Debug)]
+pub struct InstanceLock {
+    _file: File,
+}
+
+pub enum Instance {
+    Primary(InstanceLock),
+    Running(InstanceInfo),
+}
+
+/// One lock per profile, since profiles run side by side on their own ports.
+fn file_stem() -> String {
+    match crate::config::active_profile() {
+        Some(profile) => format!("instance.{profile}"),
+        None => "instance".to_string(),
+    }
+}
+
+fn default_dir() -> PathBuf {
+    let base = std::env::var("APPDATA").unwrap_or_else(|_| ".".to_string());
+    Path::new(&base).join("Telemy")
+}
+
+fn paths(dir: &Path) -> (PathBuf, PathBuf) {
+    let stem = file_stem();
+    (
+        dir.join(format!("{stem}.lock")),
+        dir.join(format!("{stem}.json")),
+    )
+}
+
+/// Becomes the primary instance, or reports the one already running. The info
+/// lives in a separate file because Windows locks also block reads.
+pub fn acquire(port: u16) -> std::io::Result<Instance> {
+    acquire_in(&default_dir(), port)
+}
+
+/// The lock file, locked, or `None` while another process holds it.
+fn try_lock(path: &Path) -> std::io::Result<Option<File>> {
+    let file = File::options()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(path)?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(file)),
+        Err(TryLockError::WouldBlock) => Ok(None),
+        Err(TryLockError::Error(err)) => Err(err),
+    }
+}
+
+fn acquire_in(dir: &Path, port: u16) -> std::io::Result<Instance> {
+    fs::create_dir_all(dir)?;
+    let (lock_path, info_path) = paths(dir);
+    let Some(file) = try_lock(&lock_path)? else {
+        return read_info(&info_path).map(Instance::Running);
+    };
+    let info = InstanceInfo {
+        pid: std::process::id(),
+        port,
+        started_unix: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    };
+    fs::write(&info_path, serde_json::to_vec_pretty(&info)?)?;
+    Ok(Instance::Primary(InstanceLock { _file: file }))
+}
+
+fn read_info(path: &Path) -> std::io::Result<InstanceInfo> {
+    let raw = fs::read(path)?;
+    serde_json::from_slice(&raw).map_err(std::io::Error::other)
+}
+
+/// The running instance, if any, for CLI commands that should go through it.
+pub fn running() -> Option<InstanceInfo> {
+    running_in(&default_dir())
+}
+
+fn running_in(dir: &Path) -> Option<InstanceInfo> {
+    let (lock_path, info_path) = paths(dir);
+    if !lock_path.exists() {
+        return None;
+    }
+    // A lock we can take is released again on drop; nothing else is running.
+    match try_lock(&lock_path) {
+        Ok(None) => read_info(&info_path).ok(),
+        _ => None,
+    }
+}
+
+/// Calls the running instance's local API and returns the JSON body, or the
+/// body's `error`/`message` on a failure status.
+pub async fn call(
+    info: &InstanceInfo,
+    token: &SecretString,
+    method: reqwest::Method,
+    path: &str,
+    body: Option<serde_json::Value>,
+) -> Result<serde_json::Value, String> {
+    let client = reqwest::Client::builder()
+        .timeout(LOCAL_API_TIMEOUT)
+        .build()
+        .map_err(|err| err.to_string())?;
+    let mut req = client
+        .request(method, format!("http://127.0.0.1:{}{path}", info.port))
+        .bearer_auth(token.expose());
+    if let Some(body) = body {
+        req = req
+            .header("Content-Type", "application/json")
+            .body(body.to_string());
+    }
+    let resp = req.send().await.map_err(|err| {
+        format!(
+            "running instance (pid {}) not reachable on port {}: {err}",
+            info.pid, info.port
+        )
+    })?;
+    let status = resp.status();
+    let text = resp.text().await.map_err(|err| err.to_string())?;
+    let value = serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text));
+    if status.is_success() {
+        return Ok(value);
+    }
+    let detail = ["error", "message"]
+        .iter()
+        .find_map(|key| value.get(key).and_then(|v| v.as_str()))
+        .map(str::to_string)
+        .unwrap_or_else(|| value.to_string());
+    Err(format!("running instance answered {status}: {detail}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_acquire_sees_the_first_instance() {
+        let dir = std::env::temp_dir().join(format!("telemy-instance-{}", uuid::Uuid::new_v4()));
+        let Instance::Primary(lock) = acquire_in(&dir, 7070).unwrap() else {
+            panic!("first acquire is primary");
+        };
+        match acquire_in(&dir, 7071).unwrap() {
+            Instance::Running(info) => {
+                assert_eq!(info.port, 7070);
+                assert_eq!(info.pid, std::process::id());
+            }
+            Instance::Primary(_) => panic!("lock was not held"),
+        }
+        assert_eq!(running_in(&dir).map(|info| info.port), Some(7070));
+        drop(lock);
+        assert_eq!(running_in(&dir), None);
+        assert!(matches!(
+            acquire_in(&dir, 7072).unwrap(),
+            Instance::Primary(_)
+        ));
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,95 @@
+use crate::security::{SecretString, Vault};
+use crate::server::AuditLog;
+use rand::RngCore;
+use ring::hmac;
+use std::sync::Arc;
+
+pub type IpcAuthHandle = Arc<IpcAuth>;
+
+/// The shared secret the plugin must prove it holds before a session is
+/// accepted: after `hello` the core sends a random nonce and expects
+/// `hex(HMAC-SHA256(secret, nonce))` back. Failures go to the security audit log.
+pub struct IpcAuth {
+    key: hmac::Key,
+    audit: Arc<AuditLog>,
+}
+
+impl IpcAuth {
+    pub fn new(secret: &str, audit: Arc<AuditLog>) -> Self {
+        Self {
+            key: hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes()),
+            audit,
+        }
+    }
+
+    pub fn challenge(&self) -> String {
+        let mut nonce = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        hex(&nonce)
+    }
+
+    /// Constant-time check of the plugin's answer to `nonce`.
+    pub fn verify(&self, nonce: &str, mac_hex: &str) -> bool {
+        match unhex(mac_hex) {
+            Some(mac) => hmac::verify(&self.key, nonce.as_bytes(), &mac).is_ok(),
+            None => false,
+        }
+    }
+
+    pub fn record_failure(&self, reason: &str) {
+        self.audit.record("ipc", &format!("ipc:{reason}"));
+    }
+
+    #[cfg(test)]
+    pub fn sign(&self, nonce: &str) -> String {
+        hex(hmac::sign(&self.key, nonce.as_bytes()).as_ref())
+    }
+}
+
+/// Reads the shared secret from the vault, generating and storing one on first use.
+pub fn load_or_create_secret(
+    vault: &mut Vault,
+    key: &str,
+) -> Result<SecretString, Box<dyn std::error::Error>> {
+    if let Ok(secret) = vault.retrieve(key) {
+        return Ok(secret);
+    }
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let secret = hex(&bytes);
+    vault.store(key, &secret)?;
+    tracing::info!(vault_key = key, "generated ipc shared secret");
+    Ok(secret.into())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verifies_only_the_matching_mac() {
+        let auth = IpcAuth::new("secret", Arc::new(AuditLog::in_memory()));
+        let nonce = auth.challenge();
+        assert_eq!(nonce.len(), 64);
+        assert!(auth.verify(&nonce, &auth.sign(&nonce)));
+        assert!(!auth.verify(&nonce, &auth.sign("other nonce")));
+        assert!(!auth.verify(&nonce, "zz"));
+
+        let other = IpcAuth::new("wrong secret", Arc::new(AuditLog::in_memory()));
+        assert!(!auth.verify(&nonce, &other.sign(&nonce)));
+    }
+}
@@ -0,0 +1,212 @@
+use super::{make_envelope, Envelope, Priority, MAX_FRAME_SIZE};
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::time::{Duration, Instant};
+
+/// Raw bytes per chunk; base64 plus the envelope keeps each frame well under
+/// [`MAX_FRAME_SIZE`].
+const CHUNK_DATA_SIZE: usize = 32 * 1024;
+/// Largest message either side will split or reassemble.
+pub const MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+const MAX_CHUNKS: u32 = MAX_MESSAGE_SIZE.div_ceil(CHUNK_DATA_SIZE) as u32;
+/// A message whose chunks stop arriving is abandoned after this.
+const REASSEMBLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One part of a message too large for a single frame. `data` is a base64
+/// slice of the original envelope's MessagePack encoding.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(super) struct ChunkPayload {
+    message_id: String,
+    seq: u32,
+    total: u32,
+    data: String,
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Splits an encoded envelope into length-prefixed `chunk` frames, written back to back.
+pub(super) fn split(encoded: &[u8], message_id: &str, priority: &Priority) -> io::Result<Vec<u8>> {
+    if encoded.len() > MAX_MESSAGE_SIZE {
+        return Err(invalid(format!(
+            "encoded message too large: {}",
+            encoded.len()
+        )));
+    }
+    let total = encoded.len().div_ceil(CHUNK_DATA_SIZE) as u32;
+    let mut frames = Vec::with_capacity(encoded.len() * 4 / 3 + total as usize * 128);
+    for (seq, data) in encoded.chunks(CHUNK_DATA_SIZE).enumerate() {
+        let chunk = make_envelope(
+            "chunk",
+            priority.clone(),
+            ChunkPayload {
+                message_id: message_id.to_string(),
+                seq: seq as u32,
+                total,
+                data: general_purpose::STANDARD.encode(data),
+            },
+        );
+        let frame = rmp_serde::to_vec_named(&chunk)
+            .map_err(|err| invalid(format!("encode failed: {err}")))?;
+        debug_assert!(frame.len() <= MAX_FRAME_SIZE);
+        frames.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        frames.extend_from_slice(&frame);
+    }
+    Ok(frames)
+}
+
+struct Partial {
+    message_id: String,
+    total: u32,
+    data: Vec<u8>,
+    next_seq: u32,
+    started_at: Instant,
+}
+
+/// Reassembles `chunk` frames from one stream. Chunks of a message arrive in
+/// order and are not interleaved with another chunked message, so only one
+/// partial message is held at a time.
+#[derive(Default)]
+pub(super) struct Reassembler {
+    partial: Option<Partial>,
+}
+
+impl Reassembler {
+    /// Passes ordinary frames through. Returns `None` while a chunked message
+    /// is incomplete and the decoded envelope once its last chunk arrives.
+    pub fn accept(
+        &mut self,
+        frame: Envelope<serde_json::Value>,
+        now: Instant,
+    ) -> io::Result<Option<Envelope<serde_json::Value>>> {
+        if self
+            .partial
+            .as_ref()
+            .is_some_and(|p| now.duration_since(p.started_at) >= REASSEMBLY_TIMEOUT)
+        {
+            let stale = self.partial.take().unwrap();
+            tracing::warn!(message_id = %stale.message_id, "ipc chunked message timed out");
+        }
+        if frame.message_type != "chunk" {
+            return Ok(Some(frame));
+        }
+
+        let chunk: ChunkPayload = serde_json::from_value(frame.payload)
+            .map_err(|err| invalid(format!("chunk decode failed: {err}")))?;
+        if chunk.total == 0 || chunk.total > MAX_CHUNKS || chunk.seq >= chunk.total {
+            self.partial = None;
+            return Err(invalid(format!(
+                "chunk {}/{} out of range",
+                chunk.seq, chunk.total
+            )));
+        }
+        if chunk.seq == 0 {
+            self.partial = Some(Partial {
+                message_id: chunk.message_id.clone(),
+                total: chunk.total,
+                data: Vec::new(),
+                next_seq: 0,
+                started_at: now,
+            });
+        }
+        let Some(partial) = self.partial.as_mut().filter(|p| {
+            p.message_id == chunk.message_id && p.total == chunk.total && p.next_seq == chunk.seq
+        }) else {
+            self.partial = None;
+            return Err(invalid(format!(
+                "unexpected chunk {}/{} for message {}",
+                chunk.seq, chunk.total, chunk.message_id
+            )));
+        };
+
+        let data = general_purpose::STANDARD
+            .decode(&chunk.data)
+            .map_err(|err| invalid(format!("chunk data decode failed: {err}")))?;
+        if partial.data.len() + data.len() > MAX_MESSAGE_SIZE {
+            self.partial = None;
+            return Err(invalid("chunked message too large".to_string()));
+        }
+        partial.data.extend_from_slice(&data);
+        partial.next_seq += 1;
+        if partial.next_seq < partial.total {
+            return Ok(None);
+        }
+
+        let complete = self.partial.take().unwrap();
+        rmp_serde::from_slice(&complete.data)
+            .map(Some)
+            .map_err(|err| invalid(format!("decode failed: {err}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(bytes: &[u8]) -> Vec<Envelope<serde_json::Value>> {
+        let mut out = Vec::new();
+        let mut rest = bytes;
+        while !rest.is_empty() {
+            let len = u32::from_le_bytes(rest[..4].try_into().unwrap()) as usize;
+            assert!(len <= MAX_FRAME_SIZE);
+            out.push(rmp_serde::from_slice(&rest[4..4 + len]).unwrap());
+            rest = &rest[4 + len..];
+        }
+        out
+    }
+
+    fn large_message() -> (Envelope<serde_json::Value>, Vec<u8>) {
+        let message = make_envelope(
+            "scene_list",
+            Priority::Low,
+            serde_json::json!({ "thumbnail": "x".repeat(200 * 1024) }),
+        );
+        let encoded = rmp_serde::to_vec_named(&message).unwrap();
+        (message, encoded)
+    }
+
+    #[test]
+    fn splits_and_reassembles_a_large_message() {
+        let (message, encoded) = large_message();
+        let chunks = frames(&split(&encoded, &message.id, &message.priority).unwrap());
+        assert_eq!(chunks.len(), encoded.len().div_ceil(CHUNK_DATA_SIZE));
+
+        let mut reassembler = Reassembler::default();
+        let now = Instant::now();
+        let (last, rest) = chunks.split_last().unwrap();
+        for chunk in rest {
+            assert!(reassembler.accept(chunk.clone(), now).unwrap().is_none());
+        }
+        let whole = reassembler.accept(last.clone(), now).unwrap().unwrap();
+        assert_eq!(whole.id, message.id);
+        assert_eq!(whole.payload, message.payload);
+
+        let ping = make_envelope("ping", Priority::Normal, serde_json::json!({}));
+        assert!(reassembler.accept(ping, now).unwrap().is_some());
+    }
+
+    #[test]
+    fn rejects_out_of_order_oversized_and_stale_chunks() {
+        let (message, encoded) = large_message();
+        let chunks = frames(&split(&encoded, &message.id, &message.priority).unwrap());
+        let now = Instant::now();
+
+        let mut reassembler = Reassembler::default();
+        reassembler.accept(chunks[0].clone(), now).unwrap();
+        assert!(reassembler.accept(chunks[2].clone(), now).is_err());
+
+        let mut reassembler = Reassembler::default();
+        reassembler.accept(chunks[0].clone(), now).unwrap();
+        let late = now + REASSEMBLY_TIMEOUT;
+        assert!(reassembler.accept(chunks[1].clone(), late).is_err());
+
+        let mut bogus = chunks[0].clone();
+        bogus.payload["total"] = serde_json::json!(MAX_CHUNKS + 1);
+        assert!(Reassembler::default().accept(bogus, now).is_err());
+
+        let too_big = vec![0u8; MAX_MESSAGE_SIZE + 1];
+        assert!(split(&too_big, "id", &Priority::Low).is_err());
+    }
+}
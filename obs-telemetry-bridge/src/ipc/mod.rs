@@ -1,17 +1,50 @@
-use crate::aegis::RelaySession;
+use crate::activity::ActionKind;
+use crate::aegis::{RelaySession, RelaySessionHandle, SessionPoller};
+use crate::metrics::{ObsControlAction, ObsLink};
 use crate::model::TelemetryFrame;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
 use std::io;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
 use tokio::sync::{broadcast, watch};
 use uuid::Uuid;
 
+mod auth;
+mod chunk;
+mod outbox;
+mod overrides;
+mod queue;
+mod tcp;
+
+pub use auth::{load_or_create_secret as load_or_create_ipc_secret, IpcAuth, IpcAuthHandle};
+use outbox::Outbox;
+pub use overrides::{
+    default_path as default_overrides_path, OverrideStore, OverrideStoreHandle, SessionOverrides,
+};
+use queue::{CommandQueue, CommandQueueHandle};
+pub use tcp::TcpTransport;
+
+/// Envelope framing version (`v`). Message-level protocol revisions are
+/// negotiated in `hello`/`hello_ack` and do not change it.
 const IPC_PROTOCOL_VERSION: u8 = 1;
+/// Protocol revisions this core can speak.
+const IPC_PROTOCOL_MIN: u8 = 1;
+const IPC_PROTOCOL_MAX: u8 = 2;
+/// Optional message families, with the first revision that can negotiate them.
+/// v1 plugins predate feature flags and get every v1 feature implicitly.
+const IPC_FEATURES: &[(&str, u8)] = &[
+    ("obs_control", 1),
+    ("configure_stream", 1),
+    ("relay_config", 1),
+    ("chunked_frames", 2),
+];
 const MAX_FRAME_SIZE: usize = 64 * 1024;
+// Only the Windows named-pipe transport opens these.
+#[cfg_attr(not(windows), allow(dead_code))]
 pub const CMD_PIPE_NAME: &str = r"\\.\pipe\aegis_cmd_v1";
+#[cfg_attr(not(windows), allow(dead_code))]
 pub const EVT_PIPE_NAME: &str = r"\\.\pipe\aegis_evt_v1";
 pub type IpcDebugStatusHandle = Arc<Mutex<IpcDebugStatus>>;
 
@@ -25,11 +58,32 @@ const STATUS_PUSH_INTERVAL: Duration = Duration::from_millis(1000);
 #[cfg(test)]
 const STATUS_PUSH_INTERVAL: Duration = Duration::from_millis(100);
 
+/// Bounds on the push interval a plugin can ask for.
+const MIN_STATUS_PUSH_INTERVAL: Duration = Duration::from_millis(250);
+const MAX_STATUS_PUSH_INTERVAL: Duration = Duration::from_secs(10);
+/// Top-level `status_snapshot` fields a plugin can subscribe to.
+const SNAPSHOT_FIELDS: &[&str] = &[
+    "mode",
+    "state_mode",
+    "health",
+    "bitrate_kbps",
+    "rtt_ms",
+    "override_enabled",
+    "relay",
+    "settings",
+];
+
 #[cfg(not(test))]
 const HEARTBEAT_TIMEOUT: Duration = Duration::from_millis(3500);
 #[cfg(test)]
 const HEARTBEAT_TIMEOUT: Duration = Duration::from_millis(350);
 
+/// How long the plugin has to answer `auth_challenge`.
+#[cfg(not(test))]
+const AUTH_TIMEOUT: Duration = Duration::from_secs(5);
+#[cfg(test)]
+const AUTH_TIMEOUT: Duration = Duration::from_millis(300);
+
 const PROTOCOL_ERROR_WINDOW: Duration = Duration::from_secs(10);
 const PROTOCOL_ERROR_RESET_THRESHOLD: usize = 5;
 
@@ -59,29 +113,92 @@ pub struct IpcDebugStatus {
     pub last_switch_request: Option<IpcSwitchRequestDebug>,
     pub last_switch_result: Option<IpcSwitchResultDebug>,
     pub last_notice: Option<String>,
+    pub mode_override: Option<String>,
+    /// Core commands waiting for a plugin session.
+    pub queued_command_count: u32,
+    /// Normal/Low frames the current session shed under backpressure.
+    pub dropped_frame_count: u64,
+    pub link: IpcLinkStats,
+    pub protocol_version: Option<u8>,
+    pub features: Vec<String>,
     pub updated_ts_unix_ms: Option<u64>,
 }
 
+/// Health of the plugin link. Frame and error counts cover the current session;
+/// `session_count` counts every session since the core started, so anything
+/// above 1 is a reconnect.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
+pub struct IpcLinkStats {
+    /// Last heartbeat round trip, as measured by the plugin and reported in its next `ping`.
+    pub rtt_ms: Option<u32>,
+    pub rtt_max_ms: Option<u32>,
+    pub frames_sent: u64,
+    pub frames_received: u64,
+    pub protocol_errors: u64,
+    pub session_count: u64,
+}
+
 pub fn new_debug_status() -> IpcDebugStatusHandle {
     Arc::new(Mutex::new(IpcDebugStatus::default()))
 }
 
 pub fn spawn_server(
     rx: watch::Receiver<TelemetryFrame>,
-    aegis_session_snapshot: Arc<Mutex<Option<RelaySession>>>,
+    aegis_session_snapshot: RelaySessionHandle,
     debug_status: IpcDebugStatusHandle,
+    obs: ObsLink,
+    overrides: OverrideStoreHandle,
+    auth: Option<IpcAuthHandle>,
+    tcp: Option<TcpTransport>,
 ) -> CoreIpcCommandSender {
-    let (core_cmd_tx, _core_cmd_rx) = broadcast::channel(64);
+    let (core_cmd_tx, core_cmd_rx) = broadcast::channel(64);
+    let queue: CommandQueueHandle = Arc::new(CommandQueue::default());
+    queue::spawn_forwarder(core_cmd_rx, queue.clone(), debug_status.clone());
+    if let Some(mode) = overrides.get().mode {
+        update_debug_status(&debug_status, |s| {
+            s.mode_override = Some(mode.as_str().to_string())
+        });
+    }
+    if let Some(transport) = tcp {
+        let (commands, rx, aegis_session_snapshot, obs, overrides) = (
+            core_cmd_tx.subscribe(),
+            rx.clone(),
+            aegis_session_snapshot.clone(),
+            obs.clone(),
+            overrides.clone(),
+        );
+        tokio::spawn(async move {
+            let result = async {
+                let listener = tcp::bind(transport.addr).await?;
+                tcp::serve(
+                    listener,
+                    transport.auth,
+                    commands,
+                    rx,
+                    aegis_session_snapshot,
+                    obs,
+                    overrides,
+                )
+                .await
+            }
+            .await;
+            if let Err(err) = result {
+                tracing::warn!(error = %err, "ipc tcp transport stopped");
+            }
+        });
+    }
     #[cfg(windows)]
     {
-        let server_cmd_tx = core_cmd_tx.clone();
         let debug_status_clone = debug_status.clone();
         let _ipc_task = tokio::spawn(async move {
             if let Err(err) = run_named_pipe_server(
                 rx,
                 aegis_session_snapshot,
-                server_cmd_tx,
+                queue,
                 debug_status_clone,
+                obs,
+                overrides,
+                auth,
             )
             .await
             {
@@ -92,7 +209,7 @@ pub fn spawn_server(
 
     #[cfg(not(windows))]
     {
-        let _ = (rx, aegis_session_snapshot);
+        let _ = (rx, aegis_session_snapshot, obs, overrides, queue, auth);
         if let Ok(mut s) = debug_status.lock() {
             s.session_connected = false;
             s.updated_ts_unix_ms = Some(now_unix_ms());
@@ -130,21 +247,144 @@ struct HelloPayload {
     obs_pid: u32,
     #[serde(default)]
     capabilities: Vec<String>,
+    /// v2: the supported range; both default to `protocol_version`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    min_protocol_version: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_protocol_version: Option<u8>,
+    /// v2: optional features the plugin wants enabled.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    features: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct PingPayload {
     nonce: String,
+    /// Round trip of the plugin's previous ping/pong.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    rtt_ms: Option<u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct RequestStatusPayload {}
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AuthChallengePayload {
+    /// Hex-encoded random bytes, fresh for every `hello`.
+    nonce: String,
+    algorithm: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AuthResponsePayload {
+    /// `hex(HMAC-SHA256(shared_secret, nonce))`.
+    mac: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct HelloAckPayload {
     core_version: String,
+    /// The negotiated revision for the rest of the session.
     protocol_version: u8,
+    min_protocol_version: u8,
+    max_protocol_version: u8,
     capabilities: Vec<String>,
+    /// Features enabled for this session.
+    features: Vec<String>,
+}
+
+/// The outcome of a successful `hello`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Negotiated {
+    version: u8,
+    features: Vec<String>,
+}
+
+impl Negotiated {
+    fn has(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+}
+
+/// How often this session gets a `status_snapshot` and which fields it carries.
+struct StreamConfig {
+    push_interval: Duration,
+    /// `None` sends every field.
+    fields: Option<Vec<String>>,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self {
+            push_interval: STATUS_PUSH_INTERVAL,
+            fields: None,
+        }
+    }
+}
+
+impl StreamConfig {
+    fn apply(&mut self, req: ConfigureStreamRequestPayload) -> ConfigureStreamResultPayload {
+        if let Some(ms) = req.push_interval_ms {
+            self.push_interval =
+                Duration::from_millis(ms).clamp(MIN_STATUS_PUSH_INTERVAL, MAX_STATUS_PUSH_INTERVAL);
+        }
+        if let Some(requested) = req.fields {
+            let known: Vec<String> = SNAPSHOT_FIELDS
+                .iter()
+                .filter(|field| requested.iter().any(|r| r == *field))
+                .map(|field| field.to_string())
+                .collect();
+            self.fields = (!known.is_empty()).then_some(known);
+        }
+        ConfigureStreamResultPayload {
+            push_interval_ms: self.push_interval.as_millis() as u64,
+            fields: self
+                .fields
+                .clone()
+                .unwrap_or_else(|| SNAPSHOT_FIELDS.iter().map(|f| f.to_string()).collect()),
+        }
+    }
+
+    fn snapshot(
+        &self,
+        payload: StatusSnapshotPayload,
+        priority: Priority,
+    ) -> io::Result<Envelope<serde_json::Value>> {
+        let mut value = serde_json::to_value(payload)?;
+        if let (Some(fields), Some(map)) = (&self.fields, value.as_object_mut()) {
+            map.retain(|key, _| fields.contains(key));
+        }
+        Ok(make_envelope("status_snapshot", priority, value))
+    }
+}
+
+/// A `hello` that is waiting on the plugin's `auth_response`.
+struct PendingAuth {
+    nonce: String,
+    session: Negotiated,
+    issued_at: Instant,
+}
+
+/// Picks the highest revision both sides support, then the features that
+/// revision allows and the plugin asked for.
+fn negotiate(hello: &HelloPayload) -> Result<Negotiated, String> {
+    let plugin_min = hello.min_protocol_version.unwrap_or(hello.protocol_version);
+    let plugin_max = hello.max_protocol_version.unwrap_or(hello.protocol_version);
+    let version = plugin_max.min(IPC_PROTOCOL_MAX);
+    if plugin_min > plugin_max || version < plugin_min.max(IPC_PROTOCOL_MIN) {
+        return Err(format!(
+            "Protocol mismatch (plugin {}-{}, core {}-{})",
+            plugin_min, plugin_max, IPC_PROTOCOL_MIN, IPC_PROTOCOL_MAX
+        ));
+    }
+    let features = IPC_FEATURES
+        .iter()
+        .filter(|(name, since)| {
+            version >= *since && (version == 1 || hello.features.iter().any(|f| f == name))
+        })
+        .map(|(name, _)| name.to_string())
+        .collect();
+    Ok(Negotiated { version, features })
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -164,6 +404,11 @@ struct ObsShutdownNoticePayload {
     reason: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ShutdownRequestPayload {
+    reason: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct SwitchScenePayload {
     request_id: String,
@@ -183,13 +428,52 @@ struct SetSettingRequestPayload {
     value: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ObsControlRequestPayload {
+    action: ObsControlAction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ObsControlResultPayload {
+    action: ObsControlAction,
+    ok: bool,
+    error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+struct ConfigureStreamRequestPayload {
+    /// Omitted leaves the current interval.
+    #[serde(default)]
+    push_interval_ms: Option<u64>,
+    /// Omitted leaves the current selection; an empty list restores every field.
+    #[serde(default)]
+    fields: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ConfigureStreamResultPayload {
+    /// The interval in effect, after clamping.
+    push_interval_ms: u64,
+    /// The fields that will be sent; names the core does not know are dropped.
+    fields: Vec<String>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
-enum SnapshotMode {
+pub enum SnapshotMode {
     Studio,
     Irl,
 }
 
+impl SnapshotMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SnapshotMode::Studio => "studio",
+            SnapshotMode::Irl => "irl",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 enum SnapshotHealth {
@@ -223,6 +507,58 @@ struct RelaySnapshot {
     status: RelayStatus,
     region: Option<String>,
     grace_remaining_seconds: u64,
+    /// When the core last heard from Aegis about this session.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_refreshed_at_unix_ms: Option<u64>,
+    #[serde(default)]
+    stale: bool,
+}
+
+/// Where the plugin should point its SRT output while a relay is up. Sent
+/// with `active: false` and no endpoint once the relay session ends.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct RelayConfigPayload {
+    session_id: String,
+    active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    region: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    srt_host: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    srt_port: Option<u16>,
+    /// Ingest credential for the relay, used as the SRT stream id.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pair_token: Option<String>,
+}
+
+impl RelayConfigPayload {
+    /// The endpoint of a relay that is taking (or waiting for) the stream, once
+    /// Aegis has reported its address and pair token.
+    fn from_session(session: &RelaySession) -> Option<Self> {
+        if !matches!(session.status.as_str(), "active" | "grace") {
+            return None;
+        }
+        let relay = session.relay.as_ref()?;
+        Some(Self {
+            session_id: session.session_id.clone(),
+            active: true,
+            region: session.region.clone(),
+            srt_host: Some(relay.public_ip.clone()?),
+            srt_port: Some(relay.srt_port?),
+            pair_token: Some(session.credentials.as_ref()?.pair_token.clone()?),
+        })
+    }
+
+    fn withdrawn(session_id: String) -> Self {
+        Self {
+            session_id,
+            active: false,
+            region: None,
+            srt_host: None,
+            srt_port: None,
+            pair_token: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -230,6 +566,10 @@ struct StatusSnapshotPayload {
     mode: SnapshotMode,
     state_mode: StateModeV1,
     health: SnapshotHealth,
+    /// The core's telemetry stopped advancing; `health` reads `offline` and
+    /// the other fields are frozen.
+    #[serde(default)]
+    stale: bool,
     bitrate_kbps: u32,
     rtt_ms: u32,
     override_enabled: bool,
@@ -254,7 +594,7 @@ struct StatusSnapshotSettingsPayload {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
-enum UserNoticeLevel {
+pub enum UserNoticeLevel {
     Info,
     Warn,
     Error,
@@ -274,6 +614,8 @@ enum ProtocolErrorCode {
     UnknownType,
     Timeout,
     InvalidPayload,
+    UnsupportedVersion,
+    Unauthorized,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -291,6 +633,14 @@ pub enum CoreIpcCommand {
         reason: String,
         deadline_ms: u64,
     },
+    /// Shown to the user by the plugin as a `user_notice`.
+    UserNotice {
+        level: UserNoticeLevel,
+        message: String,
+    },
+    /// Sent to the plugin as a `shutdown_request` while the core exits; the
+    /// session closes right after.
+    Shutdown { reason: String },
 }
 
 #[derive(Debug, Clone)]
@@ -367,6 +717,7 @@ impl ProtocolErrorTracker {
     }
 }
 
+#[cfg(test)]
 fn build_status_snapshot(
     frame: &TelemetryFrame,
     relay_session: Option<&RelaySession>,
@@ -374,81 +725,6 @@ fn build_status_snapshot(
     build_status_snapshot_with_overrides(frame, relay_session, &SessionOverrides::default())
 }
 
-#[derive(Debug, Clone, Default)]
-struct SessionOverrides {
-    mode: Option<SnapshotMode>,
-    auto_scene_switch: Option<bool>,
-    low_quality_fallback: Option<bool>,
-    manual_override: Option<bool>,
-    chat_bot: Option<bool>,
-    alerts: Option<bool>,
-}
-
-impl SessionOverrides {
-    fn set_mode_if_changed(&mut self, mode: SnapshotMode) -> bool {
-        if self.mode.as_ref() == Some(&mode) {
-            return false;
-        }
-        self.mode = Some(mode);
-        true
-    }
-
-    fn apply_setting_if_changed(&mut self, key: &str, value: bool) -> Result<bool, ()> {
-        let changed = match key {
-            "auto_scene_switch" => {
-                if self.auto_scene_switch == Some(value) {
-                    false
-                } else {
-                    self.auto_scene_switch = Some(value);
-                    true
-                }
-            }
-            "low_quality_fallback" => {
-                if self.low_quality_fallback == Some(value) {
-                    false
-                } else {
-                    self.low_quality_fallback = Some(value);
-                    true
-                }
-            }
-            "manual_override" => {
-                if self.manual_override == Some(value) {
-                    false
-                } else {
-                    self.manual_override = Some(value);
-                    true
-                }
-            }
-            "chat_bot" => {
-                if self.chat_bot == Some(value) {
-                    false
-                } else {
-                    self.chat_bot = Some(value);
-                    true
-                }
-            }
-            "alerts" => {
-                if self.alerts == Some(value) {
-                    false
-                } else {
-                    self.alerts = Some(value);
-                    true
-                }
-            }
-            _ => return Err(()),
-        };
-        Ok(changed)
-    }
-
-    fn has_any_settings(&self) -> bool {
-        self.auto_scene_switch.is_some()
-            || self.low_quality_fallback.is_some()
-            || self.manual_override.is_some()
-            || self.chat_bot.is_some()
-            || self.alerts.is_some()
-    }
-}
-
 fn build_status_snapshot_with_overrides(
     frame: &TelemetryFrame,
     relay_session: Option<&RelaySession>,
@@ -464,7 +740,8 @@ fn build_status_snapshot_with_overrides(
     if let Some(mode_override) = &overrides.mode {
         mode = mode_override.clone();
     }
-    let health = if !frame.obs.connected {
+    let stale = crate::metrics::frames_stale();
+    let health = if stale || !frame.obs.connected {
         SnapshotHealth::Offline
     } else if frame.health < 0.5 {
         SnapshotHealth::Degraded
@@ -478,6 +755,7 @@ fn build_status_snapshot_with_overrides(
         .map(|s| s.bitrate_kbps)
         .fold(0u32, |acc, v| acc.saturating_add(v));
 
+    let freshness = SessionPoller::shared().freshness(Instant::now());
     let relay = match relay_session {
         Some(session) => {
             let status = match session.status.as_str() {
@@ -494,12 +772,16 @@ fn build_status_snapshot_with_overrides(
                     .as_ref()
                     .and_then(|t| t.grace_remaining_seconds)
                     .unwrap_or(0),
+                last_refreshed_at_unix_ms: freshness.last_refreshed_at_unix_ms,
+                stale: freshness.stale,
             }
         }
         None => RelaySnapshot {
             status: RelayStatus::Inactive,
             region: None,
             grace_remaining_seconds: 0,
+            last_refreshed_at_unix_ms: freshness.last_refreshed_at_unix_ms,
+            stale: freshness.stale,
         },
     };
 
@@ -507,6 +789,7 @@ fn build_status_snapshot_with_overrides(
         mode,
         state_mode,
         health,
+        stale,
         bitrate_kbps,
         rtt_ms: frame.network.latency_ms.max(0.0).round() as u32,
         override_enabled: overrides.manual_override.unwrap_or(false),
@@ -534,37 +817,137 @@ fn derive_state_mode(frame: &TelemetryFrame, relay_session: Option<&RelaySession
     }
 }
 
+/// Runs one plugin session. Frames go through an [`Outbox`] drained by a
+/// separate writer, so a plugin that reads slowly delays only the frames it
+/// can afford to lose and never stalls command handling.
+#[allow(clippy::too_many_arguments)]
 async fn handle_session_io<R, W>(
     cmd_reader: &mut R,
     evt_writer: &mut W,
     rx: watch::Receiver<TelemetryFrame>,
-    aegis_session_snapshot: Arc<Mutex<Option<RelaySession>>>,
-    mut core_cmd_rx: broadcast::Receiver<CoreIpcCommand>,
+    aegis_session_snapshot: RelaySessionHandle,
+    queue: CommandQueueHandle,
     debug_status: IpcDebugStatusHandle,
+    obs: ObsLink,
+    overrides: OverrideStoreHandle,
+    auth: Option<IpcAuthHandle>,
 ) -> io::Result<()>
 where
     R: AsyncRead + Unpin,
     W: AsyncWrite + Unpin,
+{
+    update_debug_status(&debug_status, |s| {
+        s.link = IpcLinkStats {
+            session_count: s.link.session_count + 1,
+            ..IpcLinkStats::default()
+        };
+    });
+    let outbox = Outbox::default();
+    let session = async {
+        let result = run_session(
+            cmd_reader,
+            &outbox,
+            rx,
+            aegis_session_snapshot,
+            queue,
+            debug_status.clone(),
+            obs,
+            overrides,
+            auth,
+        )
+        .await;
+        outbox.close();
+        result
+    };
+    let writer = outbox.run(evt_writer);
+    tokio::pin!(session, writer);
+    let result = tokio::select! {
+        result = &mut session => {
+            // Flush what the session queued before it ended, e.g. a final protocol_error.
+            let flushed = writer.await;
+            result.and(flushed)
+        }
+        result = &mut writer => match result {
+            Ok(()) => session.await,
+            Err(err) => Err(err),
+        },
+    };
+    sync_outbox_stats(&debug_status, &outbox);
+    result
+}
+
+fn sync_outbox_stats(debug_status: &IpcDebugStatusHandle, outbox: &Outbox) {
+    let stats = outbox.stats();
+    update_debug_status(debug_status, |s| {
+        s.dropped_frame_count = stats.dropped;
+        s.link.frames_sent = stats.sent;
+        s.link.protocol_errors = stats.protocol_errors;
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn run_session<R>(
+    cmd_reader: &mut R,
+    outbox: &Outbox,
+    rx: watch::Receiver<TelemetryFrame>,
+    aegis_session_snapshot: RelaySessionHandle,
+    queue: CommandQueueHandle,
+    debug_status: IpcDebugStatusHandle,
+    obs: ObsLink,
+    overrides: OverrideStoreHandle,
+    auth: Option<IpcAuthHandle>,
+) -> io::Result<()>
+where
+    R: AsyncRead + Unpin,
 {
     let mut protocol_errors = ProtocolErrorTracker::new();
     let mut pending_switches: HashMap<String, PendingSwitchScene> = HashMap::new();
-    let mut session_overrides = SessionOverrides::default();
     let mut handshake_complete = false;
+    let mut negotiated: Option<Negotiated> = None;
+    let mut reassembler = chunk::Reassembler::default();
+    // With auth required, only the handshake is accepted until the plugin has
+    // answered the challenge.
+    let mut authenticated = auth.is_none();
+    let mut pending_auth: Option<PendingAuth> = None;
     let mut last_ping_at = Instant::now();
+    let mut stream = StreamConfig::default();
+    let mut relay_config: Option<RelayConfigPayload> = None;
     let mut last_status_push_at = Instant::now();
     loop {
-        while let Ok(cmd) = core_cmd_rx.try_recv() {
-            if !handshake_complete {
-                tracing::debug!("dropping core ipc command before handshake");
+        sync_outbox_stats(&debug_status, outbox);
+        // Commands stay queued until the handshake, then go out oldest first.
+        let (ready, expired) = if handshake_complete {
+            queue.drain(Instant::now())
+        } else {
+            (Vec::new(), Vec::new())
+        };
+        if !ready.is_empty() || !expired.is_empty() {
+            update_debug_status(&debug_status, |s| {
+                s.queued_command_count = queue.len() as u32;
+            });
+        }
+        for stale in expired {
+            tracing::warn!(request_id = %stale.request_id, "ipc command expired in queue");
+            if !matches!(stale.command, CoreIpcCommand::SwitchScene { .. }) {
                 continue;
             }
-            match cmd {
+            update_debug_status(&debug_status, |s| {
+                s.last_switch_result = Some(IpcSwitchResultDebug {
+                    request_id: stale.request_id.clone(),
+                    status: "expired".to_string(),
+                    error: None,
+                    ts_unix_ms: now_unix_ms(),
+                });
+            });
+        }
+        for queued in ready {
+            match queued.command {
                 CoreIpcCommand::SwitchScene {
                     scene_name,
                     reason,
                     deadline_ms,
                 } => {
-                    let request_id = Uuid::new_v4().to_string();
+                    let request_id = queued.request_id;
                     let request_ts = now_unix_ms();
                     let evt = make_envelope(
                         "switch_scene",
@@ -576,7 +959,7 @@ where
                             deadline_ms,
                         },
                     );
-                    write_frame(evt_writer, &evt).await?;
+                    outbox.push(&evt)?;
                     pending_switches.insert(
                         request_id,
                         PendingSwitchScene {
@@ -596,6 +979,33 @@ where
                         });
                     });
                 }
+                CoreIpcCommand::UserNotice { level, message } => {
+                    let notice = make_envelope(
+                        "user_notice",
+                        Priority::High,
+                        UserNoticePayload {
+                            level,
+                            message: message.clone(),
+                        },
+                    );
+                    outbox.push(&notice)?;
+                    update_debug_status(&debug_status, |s| s.last_notice = Some(message));
+                }
+                CoreIpcCommand::Shutdown { reason } => {
+                    let farewell = make_envelope(
+                        "shutdown_request",
+                        Priority::High,
+                        ShutdownRequestPayload {
+                            reason: reason.clone(),
+                        },
+                    );
+                    outbox.push(&farewell)?;
+                    tracing::info!(reason = %reason, "ipc session closed for core shutdown");
+                    update_debug_status(&debug_status, |s| {
+                        s.last_notice = Some(format!("core shutdown: {reason}"));
+                    });
+                    return Ok(());
+                }
             }
         }
 
@@ -623,7 +1033,7 @@ where
                             ),
                         },
                     );
-                    let _ = write_frame(evt_writer, &notice).await;
+                    let _ = outbox.push(&notice);
                     update_debug_status(&debug_status, |s| {
                         s.pending_switch_count = pending_switches.len() as u32;
                         s.last_switch_result = Some(IpcSwitchResultDebug {
@@ -641,22 +1051,62 @@ where
             }
         }
 
-        if handshake_complete && last_status_push_at.elapsed() >= STATUS_PUSH_INTERVAL {
+        if handshake_complete && last_status_push_at.elapsed() >= stream.push_interval {
             let frame = rx.borrow().clone();
-            let relay = aegis_session_snapshot.lock().unwrap().clone();
-            let payload = build_status_snapshot_with_overrides(&frame, relay.as_ref(), &session_overrides);
-            let snapshot = make_envelope("status_snapshot", Priority::Normal, payload);
-            write_frame(evt_writer, &snapshot).await?;
+            let relay = aegis_session_snapshot.read().await.clone();
+            let payload =
+                build_status_snapshot_with_overrides(&frame, relay.as_ref(), &overrides.get());
+            outbox.push(&stream.snapshot(payload, Priority::Normal)?)?;
             last_status_push_at = Instant::now();
         }
 
+        if handshake_complete && negotiated.as_ref().is_some_and(|n| n.has("relay_config")) {
+            let current = aegis_session_snapshot
+                .read()
+                .await
+                .as_ref()
+                .and_then(RelayConfigPayload::from_session);
+            if current != relay_config {
+                let previous = relay_config.take();
+                let payload = current
+                    .clone()
+                    .or_else(|| previous.map(|p| RelayConfigPayload::withdrawn(p.session_id)));
+                if let Some(payload) = payload {
+                    tracing::info!(
+                        session_id = %payload.session_id,
+                        active = payload.active,
+                        "ipc relay_config sent"
+                    );
+                    outbox.push(&make_envelope("relay_config", Priority::High, payload))?;
+                }
+                relay_config = current;
+            }
+        }
+
+        if let Some(auth) = auth.as_deref() {
+            if pending_auth
+                .as_ref()
+                .is_some_and(|p| p.issued_at.elapsed() >= AUTH_TIMEOUT)
+            {
+                reject_unauthenticated(
+                    outbox,
+                    auth,
+                    &debug_status,
+                    "timeout",
+                    "auth_response not received in time",
+                    None,
+                );
+                return Ok(());
+            }
+        }
+
         if handshake_complete && last_ping_at.elapsed() >= HEARTBEAT_TIMEOUT {
             let protocol_error = make_protocol_error(
                 ProtocolErrorCode::Timeout,
                 "Heartbeat timeout (missing ping)",
                 None,
             );
-            let _ = write_frame(evt_writer, &protocol_error).await;
+            let _ = outbox.push(&protocol_error);
             tracing::warn!("ipc session closed after heartbeat timeout");
             update_debug_status(&debug_status, |s| {
                 s.last_notice = Some("Heartbeat timeout (missing ping)".to_string());
@@ -668,7 +1118,10 @@ where
             match tokio::time::timeout(READ_POLL_TIMEOUT, read_frame(cmd_reader)).await {
                 Err(_) => continue,
                 Ok(read_res) => match read_res {
-                    Ok(frame) => frame,
+                    Ok(frame) => {
+                        update_debug_status(&debug_status, |s| s.link.frames_received += 1);
+                        frame
+                    }
                     Err(err) if err.kind() == io::ErrorKind::InvalidData => {
                         let msg = err.to_string();
                         let code = if msg.contains("frame too large") {
@@ -677,7 +1130,7 @@ where
                             ProtocolErrorCode::DecodeFailed
                         };
                         let protocol_error = make_protocol_error(code, msg, None);
-                        let _ = write_frame(evt_writer, &protocol_error).await;
+                        let _ = outbox.push(&protocol_error);
                         update_debug_status(&debug_status, |s| {
                             s.last_notice = Some("IPC decode/frame protocol error".to_string());
                         });
@@ -690,6 +1143,27 @@ where
                     Err(err) => return Err(err),
                 },
             };
+        let incoming = if negotiated.as_ref().is_some_and(|n| n.has("chunked_frames")) {
+            match reassembler.accept(incoming, Instant::now()) {
+                Ok(Some(message)) => message,
+                Ok(None) => continue,
+                Err(err) => {
+                    let protocol_error = make_protocol_error(
+                        ProtocolErrorCode::InvalidPayload,
+                        err.to_string(),
+                        None,
+                    );
+                    outbox.push(&protocol_error)?;
+                    if protocol_errors.record_and_should_reset() {
+                        tracing::warn!("ipc session reset after repeated protocol errors");
+                        return Ok(());
+                    }
+                    continue;
+                }
+            }
+        } else {
+            incoming
+        };
         if incoming.v != IPC_PROTOCOL_VERSION {
             let notice = make_envelope(
                 "user_notice",
@@ -702,19 +1176,34 @@ where
                     ),
                 },
             );
-            write_frame(evt_writer, &notice).await?;
+            outbox.push(&notice)?;
             update_debug_status(&debug_status, |s| {
                 s.last_notice = Some("IPC envelope version mismatch".to_string());
             });
             return Ok(());
         }
 
+        if let Some(auth) = auth.as_deref().filter(|_| !authenticated) {
+            let message_type = incoming.message_type.as_str();
+            if !matches!(message_type, "hello" | "auth_response" | "ping") {
+                reject_unauthenticated(
+                    outbox,
+                    auth,
+                    &debug_status,
+                    message_type,
+                    &format!("{message_type} sent before authentication"),
+                    Some(incoming.id.clone()),
+                );
+                return Ok(());
+            }
+        }
+
         match incoming.message_type.as_str() {
             "hello" => {
                 let hello: HelloPayload = match decode_payload(&incoming) {
                     Ok(v) => v,
                     Err(err) => {
-                        emit_protocol_error_for_payload(evt_writer, &incoming, err).await?;
+                        emit_protocol_error_for_payload(outbox, &incoming, err)?;
                         if protocol_errors.record_and_should_reset() {
                             tracing::warn!("ipc session reset after repeated protocol errors");
                             return Ok(());
@@ -722,48 +1211,106 @@ where
                         continue;
                     }
                 };
-                if hello.protocol_version != IPC_PROTOCOL_VERSION {
-                    let notice = make_envelope(
-                        "user_notice",
+                let session = match negotiate(&hello) {
+                    Ok(session) => session,
+                    Err(message) => {
+                        // Say why before closing, so the dock can show it instead of
+                        // a bare disconnect.
+                        let protocol_error = make_protocol_error(
+                            ProtocolErrorCode::UnsupportedVersion,
+                            message.clone(),
+                            Some(incoming.id.clone()),
+                        );
+                        outbox.push(&protocol_error)?;
+                        let notice = make_envelope(
+                            "user_notice",
+                            Priority::High,
+                            UserNoticePayload {
+                                level: UserNoticeLevel::Error,
+                                message,
+                            },
+                        );
+                        outbox.push(&notice)?;
+                        update_debug_status(&debug_status, |s| {
+                            s.last_notice = Some("IPC protocol mismatch".to_string());
+                        });
+                        return Ok(());
+                    }
+                };
+                tracing::info!(
+                    protocol_version = session.version,
+                    features = ?session.features,
+                    "ipc protocol negotiated"
+                );
+                update_debug_status(&debug_status, |s| {
+                    s.protocol_version = Some(session.version);
+                    s.features = session.features.clone();
+                });
+                if session.has("chunked_frames") {
+                    outbox.enable_chunking();
+                }
+
+                if let Some(auth) = auth.as_deref().filter(|_| !authenticated) {
+                    let nonce = auth.challenge();
+                    let challenge = make_envelope(
+                        "auth_challenge",
                         Priority::High,
-                        UserNoticePayload {
-                            level: UserNoticeLevel::Error,
-                            message: format!(
-                                "Protocol mismatch (plugin {}, core {})",
-                                hello.protocol_version, IPC_PROTOCOL_VERSION
-                            ),
+                        AuthChallengePayload {
+                            nonce: nonce.clone(),
+                            algorithm: "hmac-sha256".to_string(),
                         },
                     );
-                    write_frame(evt_writer, &notice).await?;
-                    update_debug_status(&debug_status, |s| {
-                        s.last_notice = Some("IPC protocol mismatch".to_string());
+                    outbox.push(&challenge)?;
+                    pending_auth = Some(PendingAuth {
+                        nonce,
+                        session,
+                        issued_at: Instant::now(),
                     });
-                    return Ok(());
+                    continue;
                 }
 
-                let ack = make_envelope(
-                    "hello_ack",
-                    Priority::High,
-                    HelloAckPayload {
-                        core_version: env!("CARGO_PKG_VERSION").to_string(),
-                        protocol_version: IPC_PROTOCOL_VERSION,
-                        capabilities: vec![
-                            "state_machine".to_string(),
-                            "aegis".to_string(),
-                            "ipc_stub".to_string(),
-                        ],
-                    },
-                );
-                write_frame(evt_writer, &ack).await?;
+                outbox.push_first(&make_hello_ack(&session))?;
+                negotiated = Some(session);
+                handshake_complete = true;
+                last_ping_at = Instant::now();
+                last_status_push_at = Instant::now() - stream.push_interval;
+            }
+            "auth_response" => {
+                let (Some(auth), Some(pending)) = (auth.as_deref(), pending_auth.take()) else {
+                    let protocol_error = make_protocol_error(
+                        ProtocolErrorCode::InvalidPayload,
+                        "auth_response without a pending auth_challenge",
+                        Some(incoming.id.clone()),
+                    );
+                    outbox.push(&protocol_error)?;
+                    continue;
+                };
+                let verified = decode_payload::<AuthResponsePayload>(&incoming)
+                    .is_ok_and(|response| auth.verify(&pending.nonce, &response.mac));
+                if !verified {
+                    reject_unauthenticated(
+                        outbox,
+                        auth,
+                        &debug_status,
+                        "auth_response",
+                        "auth_response does not match the challenge",
+                        Some(incoming.id.clone()),
+                    );
+                    return Ok(());
+                }
+                tracing::info!("ipc plugin authenticated");
+                authenticated = true;
+                outbox.push_first(&make_hello_ack(&pending.session))?;
+                negotiated = Some(pending.session);
                 handshake_complete = true;
                 last_ping_at = Instant::now();
-                last_status_push_at = Instant::now() - STATUS_PUSH_INTERVAL;
+                last_status_push_at = Instant::now() - stream.push_interval;
             }
             "ping" => {
                 let ping: PingPayload = match decode_payload(&incoming) {
                     Ok(v) => v,
                     Err(err) => {
-                        emit_protocol_error_for_payload(evt_writer, &incoming, err).await?;
+                        emit_protocol_error_for_payload(outbox, &incoming, err)?;
                         if protocol_errors.record_and_should_reset() {
                             tracing::warn!("ipc session reset after repeated protocol errors");
                             return Ok(());
@@ -771,16 +1318,22 @@ where
                         continue;
                     }
                 };
+                if let Some(rtt_ms) = ping.rtt_ms {
+                    update_debug_status(&debug_status, |s| {
+                        s.link.rtt_ms = Some(rtt_ms);
+                        s.link.rtt_max_ms = s.link.rtt_max_ms.max(Some(rtt_ms));
+                    });
+                }
                 let pong =
                     make_envelope("pong", Priority::Normal, PongPayload { nonce: ping.nonce });
-                write_frame(evt_writer, &pong).await?;
+                outbox.push(&pong)?;
                 last_ping_at = Instant::now();
             }
             "request_status" => {
                 let _: RequestStatusPayload = match decode_payload(&incoming) {
                     Ok(v) => v,
                     Err(err) => {
-                        emit_protocol_error_for_payload(evt_writer, &incoming, err).await?;
+                        emit_protocol_error_for_payload(outbox, &incoming, err)?;
                         if protocol_errors.record_and_should_reset() {
                             tracing::warn!("ipc session reset after repeated protocol errors");
                             return Ok(());
@@ -789,18 +1342,17 @@ where
                     }
                 };
                 let frame = rx.borrow().clone();
-                let relay = aegis_session_snapshot.lock().unwrap().clone();
+                let relay = aegis_session_snapshot.read().await.clone();
                 let payload =
-                    build_status_snapshot_with_overrides(&frame, relay.as_ref(), &session_overrides);
-                let snapshot = make_envelope("status_snapshot", Priority::High, payload);
-                write_frame(evt_writer, &snapshot).await?;
+                    build_status_snapshot_with_overrides(&frame, relay.as_ref(), &overrides.get());
+                outbox.push(&stream.snapshot(payload, Priority::High)?)?;
                 last_status_push_at = Instant::now();
             }
             "set_mode_request" => {
                 let req: SetModeRequestPayload = match decode_payload(&incoming) {
                     Ok(v) => v,
                     Err(err) => {
-                        emit_protocol_error_for_payload(evt_writer, &incoming, err).await?;
+                        emit_protocol_error_for_payload(outbox, &incoming, err)?;
                         if protocol_errors.record_and_should_reset() {
                             tracing::warn!("ipc session reset after repeated protocol errors");
                             return Ok(());
@@ -817,14 +1369,17 @@ where
                             format!("Invalid mode for set_mode_request: {}", req.mode),
                             Some(incoming.id.clone()),
                         );
-                        write_frame(evt_writer, &protocol_error).await?;
+                        outbox.push(&protocol_error)?;
                         continue;
                     }
                 };
-                if !session_overrides.set_mode_if_changed(normalized) {
+                if !overrides.update(|o| o.set_mode_if_changed(normalized)) {
                     tracing::debug!(mode = %req.mode, "ipc set_mode_request no-op (unchanged override)");
                     continue;
                 }
+                update_debug_status(&debug_status, |s| {
+                    s.mode_override = Some(req.mode.clone());
+                });
                 let notice = make_envelope(
                     "user_notice",
                     Priority::Normal,
@@ -833,20 +1388,19 @@ where
                         message: format!("Dock mode override set to {}", req.mode),
                     },
                 );
-                let _ = write_frame(evt_writer, &notice).await;
+                let _ = outbox.push(&notice);
                 let frame = rx.borrow().clone();
-                let relay = aegis_session_snapshot.lock().unwrap().clone();
+                let relay = aegis_session_snapshot.read().await.clone();
                 let payload =
-                    build_status_snapshot_with_overrides(&frame, relay.as_ref(), &session_overrides);
-                let snapshot = make_envelope("status_snapshot", Priority::High, payload);
-                write_frame(evt_writer, &snapshot).await?;
+                    build_status_snapshot_with_overrides(&frame, relay.as_ref(), &overrides.get());
+                outbox.push(&stream.snapshot(payload, Priority::High)?)?;
                 last_status_push_at = Instant::now();
             }
             "set_setting_request" => {
                 let req: SetSettingRequestPayload = match decode_payload(&incoming) {
                     Ok(v) => v,
                     Err(err) => {
-                        emit_protocol_error_for_payload(evt_writer, &incoming, err).await?;
+                        emit_protocol_error_for_payload(outbox, &incoming, err)?;
                         if protocol_errors.record_and_should_reset() {
                             tracing::warn!("ipc session reset after repeated protocol errors");
                             return Ok(());
@@ -854,7 +1408,8 @@ where
                         continue;
                     }
                 };
-                let changed = match session_overrides.apply_setting_if_changed(&req.key, req.value) {
+                let applied = overrides.update(|o| o.apply_setting_if_changed(&req.key, req.value));
+                let changed = match applied {
                     Ok(changed) => changed,
                     Err(()) => {
                         let protocol_error = make_protocol_error(
@@ -862,7 +1417,7 @@ where
                             format!("Unsupported setting key for set_setting_request: {}", req.key),
                             Some(incoming.id.clone()),
                         );
-                        write_frame(evt_writer, &protocol_error).await?;
+                        outbox.push(&protocol_error)?;
                         continue;
                     }
                 };
@@ -882,20 +1437,31 @@ where
                         message: format!("Dock setting '{}' set to {}", req.key, req.value),
                     },
                 );
-                let _ = write_frame(evt_writer, &notice).await;
+                let _ = outbox.push(&notice);
                 let frame = rx.borrow().clone();
-                let relay = aegis_session_snapshot.lock().unwrap().clone();
+                let relay = aegis_session_snapshot.read().await.clone();
                 let payload =
-                    build_status_snapshot_with_overrides(&frame, relay.as_ref(), &session_overrides);
-                let snapshot = make_envelope("status_snapshot", Priority::High, payload);
-                write_frame(evt_writer, &snapshot).await?;
+                    build_status_snapshot_with_overrides(&frame, relay.as_ref(), &overrides.get());
+                outbox.push(&stream.snapshot(payload, Priority::High)?)?;
                 last_status_push_at = Instant::now();
             }
-            "scene_switch_result" => {
-                let result: SceneSwitchResultPayload = match decode_payload(&incoming) {
+            "configure_stream_request"
+                if !negotiated
+                    .as_ref()
+                    .is_some_and(|n| n.has("configure_stream")) =>
+            {
+                let protocol_error = make_protocol_error(
+                    ProtocolErrorCode::UnknownType,
+                    "configure_stream_request requires the configure_stream feature",
+                    Some(incoming.id.clone()),
+                );
+                outbox.push(&protocol_error)?;
+            }
+            "configure_stream_request" => {
+                let req: ConfigureStreamRequestPayload = match decode_payload(&incoming) {
                     Ok(v) => v,
                     Err(err) => {
-                        emit_protocol_error_for_payload(evt_writer, &incoming, err).await?;
+                        emit_protocol_error_for_payload(outbox, &incoming, err)?;
                         if protocol_errors.record_and_should_reset() {
                             tracing::warn!("ipc session reset after repeated protocol errors");
                             return Ok(());
@@ -903,23 +1469,87 @@ where
                         continue;
                     }
                 };
+                let applied = stream.apply(req);
                 tracing::info!(
-                    request_id = %result.request_id,
-                    ok = result.ok,
-                    error = ?result.error,
-                    "ipc scene_switch_result received"
+                    push_interval_ms = applied.push_interval_ms,
+                    fields = ?applied.fields,
+                    "ipc status stream configured"
                 );
-                if pending_switches.remove(&result.request_id).is_none() {
-                    tracing::warn!(
-                        request_id = %result.request_id,
-                        "ipc scene_switch_result received for unknown request"
-                    );
-                    update_debug_status(&debug_status, |s| {
-                        s.last_switch_result = Some(IpcSwitchResultDebug {
-                            request_id: result.request_id.clone(),
-                            status: "unknown_request".to_string(),
-                            error: result.error.clone(),
-                            ts_unix_ms: now_unix_ms(),
+                let result = make_envelope("configure_stream_result", Priority::High, applied);
+                outbox.push(&result)?;
+                // The next loop sends a snapshot in the new shape right away.
+                last_status_push_at = Instant::now() - stream.push_interval;
+            }
+            "obs_control_request" if !negotiated.as_ref().is_some_and(|n| n.has("obs_control")) => {
+                let protocol_error = make_protocol_error(
+                    ProtocolErrorCode::UnknownType,
+                    "obs_control_request requires the obs_control feature",
+                    Some(incoming.id.clone()),
+                );
+                outbox.push(&protocol_error)?;
+            }
+            "obs_control_request" => {
+                let req: ObsControlRequestPayload = match decode_payload(&incoming) {
+                    Ok(v) => v,
+                    Err(err) => {
+                        emit_protocol_error_for_payload(outbox, &incoming, err)?;
+                        if protocol_errors.record_and_should_reset() {
+                            tracing::warn!("ipc session reset after repeated protocol errors");
+                            return Ok(());
+                        }
+                        continue;
+                    }
+                };
+                let result = obs.control(req.action).await;
+                crate::activity::log().record(
+                    ActionKind::ObsControl,
+                    "ipc",
+                    None,
+                    result.clone().map(|()| req.action.as_str().to_string()),
+                );
+                if let Err(err) = &result {
+                    tracing::warn!(action = ?req.action, error = %err, "ipc obs_control_request failed");
+                }
+                let evt = make_envelope(
+                    "obs_control_result",
+                    Priority::High,
+                    ObsControlResultPayload {
+                        action: req.action,
+                        ok: result.is_ok(),
+                        error: result.err(),
+                    },
+                );
+                outbox.push(&evt)?;
+            }
+            "scene_switch_result" => {
+                let result: SceneSwitchResultPayload = match decode_payload(&incoming) {
+                    Ok(v) => v,
+                    Err(err) => {
+                        emit_protocol_error_for_payload(outbox, &incoming, err)?;
+                        if protocol_errors.record_and_should_reset() {
+                            tracing::warn!("ipc session reset after repeated protocol errors");
+                            return Ok(());
+                        }
+                        continue;
+                    }
+                };
+                tracing::info!(
+                    request_id = %result.request_id,
+                    ok = result.ok,
+                    error = ?result.error,
+                    "ipc scene_switch_result received"
+                );
+                if pending_switches.remove(&result.request_id).is_none() {
+                    tracing::warn!(
+                        request_id = %result.request_id,
+                        "ipc scene_switch_result received for unknown request"
+                    );
+                    update_debug_status(&debug_status, |s| {
+                        s.last_switch_result = Some(IpcSwitchResultDebug {
+                            request_id: result.request_id.clone(),
+                            status: "unknown_request".to_string(),
+                            error: result.error.clone(),
+                            ts_unix_ms: now_unix_ms(),
                         });
                         s.last_notice = Some("scene_switch_result for unknown request".to_string());
                     });
@@ -939,7 +1569,7 @@ where
                 let notice: ObsShutdownNoticePayload = match decode_payload(&incoming) {
                     Ok(v) => v,
                     Err(err) => {
-                        emit_protocol_error_for_payload(evt_writer, &incoming, err).await?;
+                        emit_protocol_error_for_payload(outbox, &incoming, err)?;
                         if protocol_errors.record_and_should_reset() {
                             tracing::warn!("ipc session reset after repeated protocol errors");
                             return Ok(());
@@ -959,7 +1589,7 @@ where
                     format!("Unsupported IPC command in core stub: {other}"),
                     Some(incoming.id.clone()),
                 );
-                write_frame(evt_writer, &protocol_error).await?;
+                outbox.push(&protocol_error)?;
                 update_debug_status(&debug_status, |s| {
                     s.last_notice = Some(format!("Unsupported IPC command: {other}"));
                 });
@@ -972,20 +1602,57 @@ where
     }
 }
 
-async fn emit_protocol_error_for_payload<W>(
-    evt_writer: &mut W,
+fn make_hello_ack(session: &Negotiated) -> Envelope<HelloAckPayload> {
+    make_envelope(
+        "hello_ack",
+        Priority::High,
+        HelloAckPayload {
+            core_version: env!("CARGO_PKG_VERSION").to_string(),
+            protocol_version: session.version,
+            min_protocol_version: IPC_PROTOCOL_MIN,
+            max_protocol_version: IPC_PROTOCOL_MAX,
+            capabilities: vec![
+                "state_machine".to_string(),
+                "aegis".to_string(),
+                "ipc_stub".to_string(),
+                "obs_control".to_string(),
+            ],
+            features: session.features.clone(),
+        },
+    )
+}
+
+/// Tells an unauthenticated plugin why it is being dropped and audits the attempt.
+/// The caller ends the session.
+fn reject_unauthenticated(
+    outbox: &Outbox,
+    auth: &IpcAuth,
+    debug_status: &IpcDebugStatusHandle,
+    reason: &str,
+    message: &str,
+    related_message_id: Option<String>,
+) {
+    auth.record_failure(reason);
+    tracing::warn!(reason, "ipc session rejected: {}", message);
+    let protocol_error =
+        make_protocol_error(ProtocolErrorCode::Unauthorized, message, related_message_id);
+    let _ = outbox.push(&protocol_error);
+    update_debug_status(debug_status, |s| {
+        s.last_notice = Some(format!("IPC authentication failed: {message}"));
+    });
+}
+
+fn emit_protocol_error_for_payload(
+    outbox: &Outbox,
     incoming: &Envelope<serde_json::Value>,
     err: io::Error,
-) -> io::Result<()>
-where
-    W: AsyncWrite + Unpin,
-{
+) -> io::Result<()> {
     let protocol_error = make_protocol_error(
         ProtocolErrorCode::InvalidPayload,
         err.to_string(),
         Some(incoming.id.clone()),
     );
-    write_frame(evt_writer, &protocol_error).await
+    outbox.push(&protocol_error)
 }
 
 fn decode_payload<T: for<'de> Deserialize<'de>>(
@@ -1016,22 +1683,35 @@ where
         .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("decode failed: {err}")))
 }
 
-async fn write_frame<W, T>(writer: &mut W, message: &Envelope<T>) -> io::Result<()>
-where
-    W: AsyncWrite + Unpin,
-    T: Serialize,
-{
+/// Encodes a length-prefixed frame. With `chunking`, a message too large for
+/// one frame is split into `chunk` frames instead of failing.
+fn encode_frame<T: Serialize>(message: &Envelope<T>, chunking: bool) -> io::Result<Vec<u8>> {
     let payload = rmp_serde::to_vec_named(message).map_err(|err| {
         io::Error::new(io::ErrorKind::InvalidData, format!("encode failed: {err}"))
     })?;
+    if payload.len() > MAX_FRAME_SIZE && chunking {
+        return chunk::split(&payload, &message.id, &message.priority);
+    }
     if payload.len() > MAX_FRAME_SIZE {
         return Err(io::Error::new(
             io::ErrorKind::InvalidData,
             format!("encoded frame too large: {}", payload.len()),
         ));
     }
-    writer.write_u32_le(payload.len() as u32).await?;
-    writer.write_all(&payload).await?;
+    let mut frame = Vec::with_capacity(4 + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&payload);
+    Ok(frame)
+}
+
+#[cfg(test)]
+async fn write_frame<W, T>(writer: &mut W, message: &Envelope<T>) -> io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+    T: Serialize,
+{
+    use tokio::io::AsyncWriteExt;
+    writer.write_all(&encode_frame(message, true)?).await?;
     writer.flush().await
 }
 
@@ -1047,9 +1727,12 @@ mod windows_impl {
 
     pub async fn run_named_pipe_server(
         rx: watch::Receiver<TelemetryFrame>,
-        aegis_session_snapshot: Arc<Mutex<Option<RelaySession>>>,
-        core_cmd_tx: broadcast::Sender<CoreIpcCommand>,
+        aegis_session_snapshot: RelaySessionHandle,
+        queue: CommandQueueHandle,
         debug_status: IpcDebugStatusHandle,
+        obs: ObsLink,
+        overrides: OverrideStoreHandle,
+        auth: Option<IpcAuthHandle>,
     ) -> io::Result<()> {
         tracing::info!(
             cmd_pipe = CMD_PIPE_NAME,
@@ -1090,9 +1773,9 @@ mod windows_impl {
 
             let (mut cmd_pipe, mut evt_pipe) =
                 tokio::try_join!(connect_pipe(cmd_pipe), connect_pipe(evt_pipe))?;
-            let session_cmd_rx = core_cmd_tx.subscribe();
             update_debug_status(&debug_status, |s| {
                 s.session_connected = true;
+                s.dropped_frame_count = 0;
             });
 
             tracing::info!("ipc client connected");
@@ -1101,13 +1784,18 @@ mod windows_impl {
                 &mut evt_pipe,
                 rx.clone(),
                 aegis_session_snapshot.clone(),
-                session_cmd_rx,
+                queue.clone(),
                 debug_status.clone(),
+                obs.clone(),
+                overrides.clone(),
+                auth.clone(),
             )
             .await;
             update_debug_status(&debug_status, |s| {
                 s.session_connected = false;
                 s.pending_switch_count = 0;
+                s.protocol_version = None;
+                s.features.clear();
             });
             match session_result {
                 Ok(()) => tracing::info!("ipc client disconnected"),
@@ -1152,6 +1840,7 @@ use windows_impl::run_named_pipe_server;
 mod tests {
     use super::*;
     use crate::aegis::{RelaySession, RelayTimers};
+    use crate::server::AuditLog;
     use tokio::io::{split, DuplexStream};
 
     #[test]
@@ -1239,6 +1928,9 @@ mod tests {
                 protocol_version: IPC_PROTOCOL_VERSION,
                 obs_pid: 1234,
                 capabilities: vec!["dock".to_string()],
+                min_protocol_version: None,
+                max_protocol_version: None,
+                features: Vec::new(),
             },
         )
     }
@@ -1249,6 +1941,7 @@ mod tests {
             Priority::Normal,
             PingPayload {
                 nonce: nonce.to_string(),
+                rtt_ms: None,
             },
         )
     }
@@ -1304,27 +1997,277 @@ mod tests {
         tokio::task::JoinHandle<io::Result<()>>,
         watch::Sender<TelemetryFrame>,
         broadcast::Sender<CoreIpcCommand>,
+    ) {
+        spawn_test_session_with_overrides(Arc::new(OverrideStore::in_memory())).await
+    }
+
+    async fn spawn_test_session_with_overrides(
+        overrides: OverrideStoreHandle,
+    ) -> (
+        DuplexStream,
+        tokio::task::JoinHandle<io::Result<()>>,
+        watch::Sender<TelemetryFrame>,
+        broadcast::Sender<CoreIpcCommand>,
+    ) {
+        spawn_test_session_with(overrides, None, new_debug_status(), Arc::default()).await
+    }
+
+    async fn spawn_test_session_with(
+        overrides: OverrideStoreHandle,
+        auth: Option<IpcAuthHandle>,
+        debug_status: IpcDebugStatusHandle,
+        relay: RelaySessionHandle,
+    ) -> (
+        DuplexStream,
+        tokio::task::JoinHandle<io::Result<()>>,
+        watch::Sender<TelemetryFrame>,
+        broadcast::Sender<CoreIpcCommand>,
     ) {
         let (server_side, client_side) = tokio::io::duplex(64 * 1024);
         let (mut server_reader, mut server_writer) = split(server_side);
         let (tx, rx) = watch::channel(TelemetryFrame::default());
         let (cmd_tx, cmd_rx) = broadcast::channel(64);
-        let snapshot = Arc::new(Mutex::new(None::<RelaySession>));
-        let debug_status = new_debug_status();
+        let queue: CommandQueueHandle = Arc::new(CommandQueue::default());
+        queue::spawn_forwarder(cmd_rx, queue.clone(), debug_status.clone());
         let task = tokio::spawn(async move {
             handle_session_io(
                 &mut server_reader,
                 &mut server_writer,
                 rx,
-                snapshot,
-                cmd_rx,
+                relay,
+                queue,
                 debug_status,
+                ObsLink::detached(),
+                overrides,
+                auth,
             )
             .await
         });
         (client_side, task, tx, cmd_tx)
     }
 
+    async fn spawn_authenticated_test_session() -> (
+        DuplexStream,
+        tokio::task::JoinHandle<io::Result<()>>,
+        Arc<IpcAuth>,
+        Arc<AuditLog>,
+    ) {
+        let audit = Arc::new(AuditLog::in_memory());
+        let auth = Arc::new(IpcAuth::new("shared-secret", audit.clone()));
+        let (client, task, _tx, _cmd_tx) = spawn_test_session_with(
+            Arc::new(OverrideStore::in_memory()),
+            Some(auth.clone()),
+            new_debug_status(),
+            Arc::default(),
+        )
+        .await;
+        (client, task, auth, audit)
+    }
+
+    async fn read_auth_challenge(client: &mut DuplexStream) -> AuthChallengePayload {
+        write_frame(client, &hello_envelope()).await.unwrap();
+        let challenge = read_event(client).await;
+        assert_eq!(challenge.message_type, "auth_challenge");
+        serde_json::from_value(challenge.payload).unwrap()
+    }
+
+    fn auth_response_envelope(mac: String) -> Envelope<AuthResponsePayload> {
+        make_envelope("auth_response", Priority::High, AuthResponsePayload { mac })
+    }
+
+    fn assert_unauthorized(msg: Envelope<serde_json::Value>) {
+        assert_eq!(msg.message_type, "protocol_error");
+        let payload: ProtocolErrorPayload = serde_json::from_value(msg.payload).unwrap();
+        assert!(matches!(payload.code, ProtocolErrorCode::Unauthorized));
+    }
+
+    #[tokio::test]
+    async fn session_tracks_heartbeat_rtt_and_frame_counts() {
+        let debug_status = new_debug_status();
+        let (mut client, task, _tx, _cmd_tx) = spawn_test_session_with(
+            Arc::new(OverrideStore::in_memory()),
+            None,
+            debug_status.clone(),
+            Arc::default(),
+        )
+        .await;
+        write_frame(&mut client, &hello_envelope()).await.unwrap();
+        assert_eq!(read_event(&mut client).await.message_type, "hello_ack");
+
+        let mut ping = ping_envelope("rtt");
+        ping.payload.rtt_ms = Some(7);
+        write_frame(&mut client, &ping).await.unwrap();
+        drain_until_message_type(&mut client, "pong", Duration::from_secs(1)).await;
+        let unknown = make_envelope("bad_cmd", Priority::Normal, serde_json::json!({}));
+        write_frame(&mut client, &unknown).await.unwrap();
+        drain_until_message_type(&mut client, "protocol_error", Duration::from_secs(1)).await;
+        tokio::time::sleep(READ_POLL_TIMEOUT * 2).await;
+
+        let link = debug_status.lock().unwrap().link;
+        assert_eq!(link.rtt_ms, Some(7));
+        assert_eq!(link.rtt_max_ms, Some(7));
+        assert_eq!(link.frames_received, 3);
+        assert_eq!(link.protocol_errors, 1);
+        assert!(link.frames_sent >= 3, "hello_ack, pong and protocol_error");
+        assert_eq!(link.session_count, 1);
+
+        drop(client);
+        let _ = task.await;
+    }
+
+    #[tokio::test]
+    async fn plugin_that_answers_the_auth_challenge_gets_hello_ack() {
+        let (mut client, task, auth, audit) = spawn_authenticated_test_session().await;
+        let challenge = read_auth_challenge(&mut client).await;
+        assert_eq!(challenge.algorithm, "hmac-sha256");
+
+        let response = auth_response_envelope(auth.sign(&challenge.nonce));
+        write_frame(&mut client, &response).await.unwrap();
+        let ack = read_event(&mut client).await;
+        assert_eq!(ack.message_type, "hello_ack");
+        assert!(audit.recent(10).is_empty());
+
+        drop(client);
+        let _ = task.await;
+    }
+
+    #[tokio::test]
+    async fn wrong_auth_response_is_audited_and_closes_the_session() {
+        let (mut client, task, _auth, audit) = spawn_authenticated_test_session().await;
+        read_auth_challenge(&mut client).await;
+
+        let response = auth_response_envelope("00".repeat(32));
+        write_frame(&mut client, &response).await.unwrap();
+        assert_unauthorized(read_event(&mut client).await);
+        assert!(task.await.unwrap().is_ok());
+
+        let failures = audit.recent(10);
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].ip, "ipc");
+        assert_eq!(failures[0].path, "ipc:auth_response");
+    }
+
+    #[tokio::test]
+    async fn commands_before_authentication_are_rejected() {
+        let (mut client, task, _auth, audit) = spawn_authenticated_test_session().await;
+        write_frame(&mut client, &request_status_envelope())
+            .await
+            .unwrap();
+        assert_unauthorized(read_event(&mut client).await);
+        assert!(task.await.unwrap().is_ok());
+        assert_eq!(audit.recent(10)[0].path, "ipc:request_status");
+    }
+
+    #[tokio::test]
+    async fn tcp_transport_requires_the_token_and_serves_the_same_protocol() {
+        assert!(tcp::bind("0.0.0.0:0".parse().unwrap()).await.is_err());
+
+        let listener = tcp::bind("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let auth = Arc::new(IpcAuth::new("tcp-token", Arc::new(AuditLog::in_memory())));
+        let (cmd_tx, _) = broadcast::channel(64);
+        let (_tx, rx) = watch::channel(TelemetryFrame::default());
+        let server = tokio::spawn(tcp::serve(
+            listener,
+            auth.clone(),
+            cmd_tx.subscribe(),
+            rx,
+            Arc::default(),
+            ObsLink::detached(),
+            Arc::new(OverrideStore::in_memory()),
+        ));
+
+        let _client = connect_tcp_client(addr, &auth).await;
+
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn a_tcp_client_leaves_the_pipe_session_and_its_queue_alone() {
+        let debug_status = new_debug_status();
+        let (mut pipe, pipe_task, _tx, cmd_tx) = spawn_test_session_with(
+            Arc::new(OverrideStore::in_memory()),
+            None,
+            debug_status.clone(),
+            Arc::default(),
+        )
+        .await;
+        // Set by the pipe server around the session, as run_named_pipe_server does.
+        update_debug_status(&debug_status, |s| s.session_connected = true);
+        write_frame(&mut pipe, &hello_envelope()).await.unwrap();
+        assert_eq!(read_event(&mut pipe).await.message_type, "hello_ack");
+
+        let listener = tcp::bind("127.0.0.1:0".parse().unwrap()).await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let auth = Arc::new(IpcAuth::new("tcp-token", Arc::new(AuditLog::in_memory())));
+        let (_frame_tx, rx) = watch::channel(TelemetryFrame::default());
+        let server = tokio::spawn(tcp::serve(
+            listener,
+            auth.clone(),
+            cmd_tx.subscribe(),
+            rx,
+            Arc::default(),
+            ObsLink::detached(),
+            Arc::new(OverrideStore::in_memory()),
+        ));
+        let mut tcp_client = connect_tcp_client(addr, &auth).await;
+
+        cmd_tx
+            .send(CoreIpcCommand::SwitchScene {
+                scene_name: "BRB".to_string(),
+                reason: "test".to_string(),
+                deadline_ms: 500,
+            })
+            .unwrap();
+        let to_pipe =
+            drain_until_message_type(&mut pipe, "switch_scene", Duration::from_secs(1)).await;
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(1);
+        loop {
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "tcp client got no switch_scene"
+            );
+            let msg = tokio::time::timeout(Duration::from_millis(250), read_frame(&mut tcp_client))
+                .await
+                .unwrap()
+                .unwrap();
+            if msg.message_type == "switch_scene" {
+                assert_eq!(msg.payload["scene_name"], to_pipe.payload["scene_name"]);
+                break;
+            }
+        }
+
+        drop(tcp_client);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        {
+            let status = debug_status.lock().unwrap();
+            assert!(status.session_connected, "pipe session still reported");
+            assert_eq!(status.protocol_version, Some(IPC_PROTOCOL_VERSION));
+        }
+
+        server.abort();
+        drop(pipe);
+        let _ = pipe_task.await;
+    }
+
+    async fn connect_tcp_client(
+        addr: std::net::SocketAddr,
+        auth: &IpcAuth,
+    ) -> tokio::net::TcpStream {
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        write_frame(&mut client, &hello_envelope()).await.unwrap();
+        let challenge = read_frame(&mut client).await.unwrap();
+        assert_eq!(challenge.message_type, "auth_challenge");
+        let challenge: AuthChallengePayload = serde_json::from_value(challenge.payload).unwrap();
+        let response = auth_response_envelope(auth.sign(&challenge.nonce));
+        write_frame(&mut client, &response).await.unwrap();
+        assert_eq!(
+            read_frame(&mut client).await.unwrap().message_type,
+            "hello_ack"
+        );
+        client
+    }
+
     #[tokio::test]
     async fn session_sends_hello_ack_and_periodic_status_snapshot() {
         let (mut client, task, _tx, _cmd_tx) = spawn_test_session().await;
@@ -1401,6 +2344,60 @@ mod tests {
         let _ = task.await;
     }
 
+    #[tokio::test]
+    async fn configure_stream_request_filters_snapshot_fields() {
+        let (mut client, task, _tx, _cmd_tx) = spawn_test_session().await;
+        write_frame(&mut client, &hello_envelope()).await.unwrap();
+        let ack = read_event(&mut client).await;
+        assert_eq!(ack.message_type, "hello_ack");
+
+        let request = make_envelope(
+            "configure_stream_request",
+            Priority::Normal,
+            ConfigureStreamRequestPayload {
+                push_interval_ms: Some(1),
+                fields: Some(vec![
+                    "bitrate_kbps".to_string(),
+                    "health".to_string(),
+                    "thumbnail".to_string(),
+                ]),
+            },
+        );
+        write_frame(&mut client, &request).await.unwrap();
+        let result = drain_until_message_type(
+            &mut client,
+            "configure_stream_result",
+            Duration::from_secs(1),
+        )
+        .await;
+        let result: ConfigureStreamResultPayload = serde_json::from_value(result.payload).unwrap();
+        assert_eq!(
+            result.push_interval_ms,
+            MIN_STATUS_PUSH_INTERVAL.as_millis() as u64
+        );
+        assert_eq!(result.fields, ["health", "bitrate_kbps"]);
+
+        // A snapshot queued before the request may still be in flight.
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(1);
+        loop {
+            assert!(
+                tokio::time::Instant::now() < deadline,
+                "no filtered snapshot"
+            );
+            let msg =
+                drain_until_message_type(&mut client, "status_snapshot", Duration::from_secs(1))
+                    .await;
+            let keys: Vec<&String> = msg.payload.as_object().unwrap().keys().collect();
+            if keys.len() == 2 {
+                assert!(keys.iter().all(|k| *k == "health" || *k == "bitrate_kbps"));
+                break;
+            }
+        }
+
+        drop(client);
+        let _ = task.await;
+    }
+
     #[tokio::test]
     async fn session_returns_status_snapshot_on_request_status() {
         let (mut client, task, tx, _cmd_tx) = spawn_test_session().await;
@@ -1573,6 +2570,52 @@ mod tests {
         assert!(session_result.is_ok());
     }
 
+    #[tokio::test]
+    async fn core_user_notice_command_reaches_the_plugin() {
+        let (mut client, _task, _tx, cmd_tx) = spawn_test_session().await;
+
+        write_frame(&mut client, &hello_envelope()).await.unwrap();
+        let ack = read_event(&mut client).await;
+        assert_eq!(ack.message_type, "hello_ack");
+
+        cmd_tx
+            .send(CoreIpcCommand::UserNotice {
+                level: UserNoticeLevel::Warn,
+                message: "Relay in grace window: 5m left".to_string(),
+            })
+            .unwrap();
+        let msg =
+            drain_until_message_type(&mut client, "user_notice", Duration::from_secs(1)).await;
+        let payload: UserNoticePayload = serde_json::from_value(msg.payload).unwrap();
+        assert!(matches!(payload.level, UserNoticeLevel::Warn));
+        assert_eq!(payload.message, "Relay in grace window: 5m left");
+    }
+
+    #[tokio::test]
+    async fn core_shutdown_sends_shutdown_request_and_closes_the_session() {
+        let (mut client, task, _tx, cmd_tx) = spawn_test_session().await;
+
+        write_frame(&mut client, &hello_envelope()).await.unwrap();
+        let ack = read_event(&mut client).await;
+        assert_eq!(ack.message_type, "hello_ack");
+
+        cmd_tx
+            .send(CoreIpcCommand::Shutdown {
+                reason: "manual_exit".to_string(),
+            })
+            .unwrap();
+        let msg =
+            drain_until_message_type(&mut client, "shutdown_request", Duration::from_secs(1)).await;
+        let payload: ShutdownRequestPayload = serde_json::from_value(msg.payload).unwrap();
+        assert_eq!(payload.reason, "manual_exit");
+
+        let session_result = tokio::time::timeout(Duration::from_secs(1), task)
+            .await
+            .expect("session task should finish after shutdown")
+            .unwrap();
+        assert!(session_result.is_ok());
+    }
+
     #[tokio::test]
     async fn core_switch_scene_command_emits_event_and_ack_clears_timeout() {
         let (mut client, task, _tx, cmd_tx) = spawn_test_session().await;
@@ -1647,6 +2690,31 @@ mod tests {
         let _ = task.await;
     }
 
+    #[tokio::test]
+    async fn switch_scene_sent_before_plugin_connects_is_delivered_after_hello() {
+        let (mut client, task, _tx, cmd_tx) = spawn_test_session().await;
+        cmd_tx
+            .send(CoreIpcCommand::SwitchScene {
+                scene_name: "Starting".to_string(),
+                reason: "script".to_string(),
+                deadline_ms: 500,
+            })
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        write_frame(&mut client, &hello_envelope()).await.unwrap();
+        let ack = read_event(&mut client).await;
+        assert_eq!(ack.message_type, "hello_ack");
+        let msg =
+            drain_until_message_type(&mut client, "switch_scene", Duration::from_secs(1)).await;
+        let payload: SwitchScenePayload = serde_json::from_value(msg.payload).unwrap();
+        assert_eq!(payload.scene_name, "Starting");
+        assert_eq!(payload.deadline_ms, 500);
+
+        drop(client);
+        let _ = task.await;
+    }
+
     #[tokio::test]
     async fn core_switch_scene_command_timeout_emits_user_notice() {
         let (mut client, task, _tx, cmd_tx) = spawn_test_session().await;
@@ -1671,21 +2739,18 @@ mod tests {
             let _ = write_frame(&mut client, &ping_envelope("keepalive")).await;
             let msg =
                 tokio::time::timeout(Duration::from_millis(150), read_event(&mut client)).await;
-            match msg {
-                Ok(msg) => {
-                    if msg.message_type == "switch_scene" {
-                        saw_switch_scene = true;
-                    }
-                    if msg.message_type == "user_notice" {
-                        let payload: UserNoticePayload =
-                            serde_json::from_value(msg.payload).unwrap();
-                        if payload.message.contains("timed out") {
-                            saw_timeout_notice = true;
-                            break;
-                        }
+            if let Ok(msg) = msg {
+                if msg.message_type == "switch_scene" {
+                    saw_switch_scene = true;
+                }
+                if msg.message_type == "user_notice" {
+                    let payload: UserNoticePayload =
+                        serde_json::from_value(msg.payload).unwrap();
+                    if payload.message.contains("timed out") {
+                        saw_timeout_notice = true;
+                        break;
                     }
                 }
-                Err(_) => {}
             }
         }
 
@@ -1696,6 +2761,31 @@ mod tests {
         let _ = task.await;
     }
 
+    #[tokio::test]
+    async fn mode_override_survives_plugin_reconnect() {
+        let overrides = Arc::new(OverrideStore::in_memory());
+        let (mut client, task, _tx, _cmd_tx) =
+            spawn_test_session_with_overrides(overrides.clone()).await;
+        write_frame(&mut client, &hello_envelope()).await.unwrap();
+        let _ = drain_until_message_type(&mut client, "hello_ack", Duration::from_secs(1)).await;
+        write_frame(&mut client, &set_mode_request_envelope("irl"))
+            .await
+            .unwrap();
+        let _ = drain_until_message_type(&mut client, "user_notice", Duration::from_secs(1)).await;
+        drop(client);
+        let _ = task.await;
+
+        let (mut client, task, _tx, _cmd_tx) = spawn_test_session_with_overrides(overrides).await;
+        write_frame(&mut client, &hello_envelope()).await.unwrap();
+        let snapshot =
+            drain_until_message_type(&mut client, "status_snapshot", Duration::from_secs(1)).await;
+        let snap: StatusSnapshotPayload = serde_json::from_value(snapshot.payload).unwrap();
+        assert!(matches!(snap.mode, SnapshotMode::Irl));
+
+        drop(client);
+        let _ = task.await;
+    }
+
     #[tokio::test]
     async fn repeated_identical_set_mode_request_is_noop() {
         let (mut client, task, _tx, _cmd_tx) = spawn_test_session().await;
@@ -1725,22 +2815,19 @@ mod tests {
         let mut saw_redundant_snapshot = false;
         while tokio::time::Instant::now() < until {
             let _ = write_frame(&mut client, &ping_envelope("keepalive-noop-mode")).await;
-            match tokio::time::timeout(Duration::from_millis(80), read_event(&mut client)).await {
-                Ok(msg) => {
-                    if msg.message_type == "user_notice" {
-                        let payload: UserNoticePayload = serde_json::from_value(msg.payload).unwrap();
-                        if payload.message.contains("Dock mode override set to irl") {
-                            saw_redundant_notice = true;
-                            break;
-                        }
-                    } else if msg.message_type == "status_snapshot"
-                        && matches!(msg.priority, Priority::High)
-                    {
-                        saw_redundant_snapshot = true;
+            if let Ok(msg) = tokio::time::timeout(Duration::from_millis(80), read_event(&mut client)).await {
+                if msg.message_type == "user_notice" {
+                    let payload: UserNoticePayload = serde_json::from_value(msg.payload).unwrap();
+                    if payload.message.contains("Dock mode override set to irl") {
+                        saw_redundant_notice = true;
                         break;
                     }
+                } else if msg.message_type == "status_snapshot"
+                    && matches!(msg.priority, Priority::High)
+                {
+                    saw_redundant_snapshot = true;
+                    break;
                 }
-                Err(_) => {}
             }
         }
         assert!(!saw_redundant_notice, "unexpected duplicate user_notice for no-op set_mode_request");
@@ -1786,22 +2873,19 @@ mod tests {
         let mut saw_redundant_snapshot = false;
         while tokio::time::Instant::now() < until {
             let _ = write_frame(&mut client, &ping_envelope("keepalive-noop-setting")).await;
-            match tokio::time::timeout(Duration::from_millis(80), read_event(&mut client)).await {
-                Ok(msg) => {
-                    if msg.message_type == "user_notice" {
-                        let payload: UserNoticePayload = serde_json::from_value(msg.payload).unwrap();
-                        if payload.message.contains("auto_scene_switch") {
-                            saw_redundant_notice = true;
-                            break;
-                        }
-                    } else if msg.message_type == "status_snapshot"
-                        && matches!(msg.priority, Priority::High)
-                    {
-                        saw_redundant_snapshot = true;
+            if let Ok(msg) = tokio::time::timeout(Duration::from_millis(80), read_event(&mut client)).await {
+                if msg.message_type == "user_notice" {
+                    let payload: UserNoticePayload = serde_json::from_value(msg.payload).unwrap();
+                    if payload.message.contains("auto_scene_switch") {
+                        saw_redundant_notice = true;
                         break;
                     }
+                } else if msg.message_type == "status_snapshot"
+                    && matches!(msg.priority, Priority::High)
+                {
+                    saw_redundant_snapshot = true;
+                    break;
                 }
-                Err(_) => {}
             }
         }
         assert!(
@@ -1816,4 +2900,190 @@ mod tests {
         drop(client);
         let _ = task.await;
     }
+
+    #[tokio::test]
+    async fn obs_control_request_reports_failure_while_obs_is_disconnected() {
+        let (mut client, task, _tx, _cmd_tx) = spawn_test_session().await;
+
+        write_frame(&mut client, &hello_envelope()).await.unwrap();
+        let ack = read_event(&mut client).await;
+        assert_eq!(ack.message_type, "hello_ack");
+
+        let request = make_envelope(
+            "obs_control_request",
+            Priority::High,
+            serde_json::json!({ "action": "replay_buffer_save" }),
+        );
+        write_frame(&mut client, &request).await.unwrap();
+        let result =
+            drain_until_message_type(&mut client, "obs_control_result", Duration::from_secs(1))
+                .await;
+        let payload: ObsControlResultPayload = serde_json::from_value(result.payload).unwrap();
+        assert_eq!(payload.action, ObsControlAction::ReplayBufferSave);
+        assert!(!payload.ok);
+        assert_eq!(payload.error.as_deref(), Some("OBS is not connected"));
+
+        drop(client);
+        let _ = task.await;
+    }
+
+    fn hello_v2(min: u8, max: u8, features: &[&str]) -> HelloPayload {
+        HelloPayload {
+            plugin_version: "0.1.0".to_string(),
+            protocol_version: max,
+            obs_pid: 1234,
+            capabilities: vec!["dock".to_string()],
+            min_protocol_version: Some(min),
+            max_protocol_version: Some(max),
+            features: features.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn negotiation_picks_highest_common_version_and_gates_features() {
+        let legacy = hello_envelope().payload;
+        let v1 = negotiate(&legacy).unwrap();
+        assert_eq!(v1.version, 1);
+        assert!(v1.has("obs_control"), "v1 plugins keep every v1 feature");
+
+        let v2 = negotiate(&hello_v2(1, 9, &[])).unwrap();
+        assert_eq!(v2.version, IPC_PROTOCOL_MAX);
+        assert!(!v2.has("obs_control"), "v2 features must be requested");
+        let v2 = negotiate(&hello_v2(2, 2, &["obs_control", "unknown"])).unwrap();
+        assert_eq!(v2.features, ["obs_control"]);
+
+        assert!(negotiate(&hello_v2(IPC_PROTOCOL_MAX + 1, IPC_PROTOCOL_MAX + 2, &[])).is_err());
+        assert!(negotiate(&hello_v2(2, 1, &[])).is_err());
+    }
+
+    #[tokio::test]
+    async fn incompatible_plugin_gets_reason_before_close() {
+        let (mut client, task, _tx, _cmd_tx) = spawn_test_session().await;
+        let hello = make_envelope("hello", Priority::High, hello_v2(7, 8, &[]));
+        write_frame(&mut client, &hello).await.unwrap();
+
+        let err = read_event(&mut client).await;
+        assert_eq!(err.message_type, "protocol_error");
+        let payload: ProtocolErrorPayload = serde_json::from_value(err.payload).unwrap();
+        assert!(matches!(
+            payload.code,
+            ProtocolErrorCode::UnsupportedVersion
+        ));
+        assert_eq!(
+            payload.related_message_id.as_deref(),
+            Some(hello.id.as_str())
+        );
+        assert_eq!(read_event(&mut client).await.message_type, "user_notice");
+        assert!(task.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn v2_session_rejects_features_it_did_not_negotiate() {
+        let (mut client, task, _tx, _cmd_tx) = spawn_test_session().await;
+        let hello = make_envelope("hello", Priority::High, hello_v2(1, 2, &[]));
+        write_frame(&mut client, &hello).await.unwrap();
+        let ack = read_event(&mut client).await;
+        let ack: HelloAckPayload = serde_json::from_value(ack.payload).unwrap();
+        assert_eq!(ack.protocol_version, 2);
+        assert!(ack.features.is_empty());
+
+        let request = make_envelope(
+            "obs_control_request",
+            Priority::High,
+            ObsControlRequestPayload {
+                action: ObsControlAction::VirtualcamStart,
+            },
+        );
+        write_frame(&mut client, &request).await.unwrap();
+        let err =
+            drain_until_message_type(&mut client, "protocol_error", Duration::from_secs(1)).await;
+        let payload: ProtocolErrorPayload = serde_json::from_value(err.payload).unwrap();
+        assert!(matches!(payload.code, ProtocolErrorCode::UnknownType));
+
+        drop(client);
+        let _ = task.await;
+    }
+
+    #[tokio::test]
+    async fn chunked_session_reassembles_large_messages_both_ways() {
+        let (mut client, task, _tx, _cmd_tx) = spawn_test_session().await;
+        let hello = make_envelope("hello", Priority::High, hello_v2(2, 2, &["chunked_frames"]));
+        write_frame(&mut client, &hello).await.unwrap();
+        let ack = read_event(&mut client).await;
+        let ack: HelloAckPayload = serde_json::from_value(ack.payload).unwrap();
+        assert_eq!(ack.features, ["chunked_frames"]);
+
+        let nonce = "n".repeat(3 * MAX_FRAME_SIZE);
+        let ping = make_envelope(
+            "ping",
+            Priority::Normal,
+            PingPayload {
+                nonce: nonce.clone(),
+                rtt_ms: None,
+            },
+        );
+        write_frame(&mut client, &ping).await.unwrap();
+
+        let mut reassembler = chunk::Reassembler::default();
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(2);
+        let pong = loop {
+            assert!(tokio::time::Instant::now() < deadline, "no pong");
+            let frame = read_event(&mut client).await;
+            match reassembler.accept(frame, Instant::now()).unwrap() {
+                Some(msg) if msg.message_type == "pong" => break msg,
+                _ => {}
+            }
+        };
+        let pong: PongPayload = serde_json::from_value(pong.payload).unwrap();
+        assert_eq!(pong.nonce, nonce);
+
+        drop(client);
+        let _ = task.await;
+    }
+
+    #[tokio::test]
+    async fn relay_config_follows_the_active_relay_session() {
+        let relay: RelaySessionHandle = Arc::default();
+        let (mut client, task, _tx, _cmd_tx) = spawn_test_session_with(
+            Arc::new(OverrideStore::in_memory()),
+            None,
+            new_debug_status(),
+            relay.clone(),
+        )
+        .await;
+        write_frame(&mut client, &hello_envelope()).await.unwrap();
+        assert_eq!(read_event(&mut client).await.message_type, "hello_ack");
+
+        *relay.write().await = Some(RelaySession {
+            session_id: "sess-1".to_string(),
+            status: "active".to_string(),
+            region: Some("us-east-1".to_string()),
+            relay: Some(crate::aegis::RelayEndpoint {
+                public_ip: Some("203.0.113.7".to_string()),
+                srt_port: Some(9000),
+                ..Default::default()
+            }),
+            credentials: Some(crate::aegis::RelayCredentials {
+                pair_token: Some("A1B2C3D4".to_string()),
+                relay_ws_token: None,
+            }),
+            ..Default::default()
+        });
+        let msg =
+            drain_until_message_type(&mut client, "relay_config", Duration::from_secs(1)).await;
+        let config: RelayConfigPayload = serde_json::from_value(msg.payload).unwrap();
+        assert!(config.active);
+        assert_eq!(config.srt_host.as_deref(), Some("203.0.113.7"));
+        assert_eq!(config.srt_port, Some(9000));
+        assert_eq!(config.pair_token.as_deref(), Some("A1B2C3D4"));
+
+        *relay.write().await = None;
+        let msg =
+            drain_until_message_type(&mut client, "relay_config", Duration::from_secs(1)).await;
+        let config: RelayConfigPayload = serde_json::from_value(msg.payload).unwrap();
+        assert_eq!(config, RelayConfigPayload::withdrawn("sess-1".to_string()));
+
+        drop(client);
+        let _ = task.await;
+    }
 }
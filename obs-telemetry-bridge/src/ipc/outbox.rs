@@ -0,0 +1,218 @@
+use super::{encode_frame, Envelope, Priority};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::Notify;
+
+/// Normal and Low frames queued beyond this are shed, oldest Low first.
+const DROPPABLE_CAPACITY: usize = 64;
+
+struct Queued {
+    message_type: String,
+    bytes: Vec<u8>,
+}
+
+#[derive(Default)]
+struct State {
+    /// One FIFO per priority, Critical first.
+    queues: [VecDeque<Queued>; 4],
+    closed: bool,
+    stats: OutboxStats,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OutboxStats {
+    /// Frames handed to the writer.
+    pub sent: u64,
+    pub dropped: u64,
+    pub protocol_errors: u64,
+}
+
+/// Outgoing frames for one session, written highest priority first by
+/// [`Outbox::run`]. While the plugin is slow to read, a newer Normal/Low
+/// `status_snapshot` replaces any still waiting, and Normal/Low frames are shed
+/// past a bound; Critical and High frames (scene switches, replies) are never
+/// dropped.
+#[derive(Default)]
+pub struct Outbox {
+    state: Mutex<State>,
+    ready: Notify,
+    /// Set once the plugin negotiates `chunked_frames`.
+    chunking: AtomicBool,
+}
+
+fn rank(priority: &Priority) -> usize {
+    match priority {
+        Priority::Critical => 0,
+        Priority::High => 1,
+        Priority::Normal => 2,
+        Priority::Low => 3,
+    }
+}
+
+impl Outbox {
+    pub fn push<T: Serialize>(&self, message: &Envelope<T>) -> io::Result<()> {
+        let bytes = encode_frame(message, self.chunking.load(Ordering::Relaxed))?;
+        let rank = rank(&message.priority);
+        let mut state = self.state.lock().unwrap();
+        if rank >= 2 {
+            if message.message_type == "status_snapshot" {
+                for queue in &mut state.queues[2..] {
+                    queue.retain(|q| q.message_type != "status_snapshot");
+                }
+            }
+            if state.queues[2].len() + state.queues[3].len() >= DROPPABLE_CAPACITY {
+                let shed = if state.queues[3].is_empty() { 2 } else { 3 };
+                if let Some(dropped) = state.queues[shed].pop_front() {
+                    state.stats.dropped += 1;
+                    tracing::debug!(message_type = %dropped.message_type, "ipc outbox full; dropping frame");
+                }
+            }
+        }
+        if message.message_type == "protocol_error" {
+            state.stats.protocol_errors += 1;
+        }
+        state.queues[rank].push_back(Queued {
+            message_type: message.message_type.clone(),
+            bytes,
+        });
+        drop(state);
+        self.ready.notify_one();
+        Ok(())
+    }
+
+    /// Queues a frame ahead of everything already waiting. Used for `hello_ack`,
+    /// which must reach the plugin before any frame it unblocks.
+    pub fn push_first<T: Serialize>(&self, message: &Envelope<T>) -> io::Result<()> {
+        let queued = Queued {
+            message_type: message.message_type.clone(),
+            bytes: encode_frame(message, self.chunking.load(Ordering::Relaxed))?,
+        };
+        self.state.lock().unwrap().queues[0].push_front(queued);
+        self.ready.notify_one();
+        Ok(())
+    }
+
+    /// Splits frames over the size limit from now on instead of rejecting them.
+    pub fn enable_chunking(&self) {
+        self.chunking.store(true, Ordering::Relaxed);
+    }
+
+    /// No more frames will be pushed; [`Outbox::run`] returns once the rest is written.
+    pub fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.ready.notify_one();
+    }
+
+    pub fn stats(&self) -> OutboxStats {
+        self.state.lock().unwrap().stats
+    }
+
+    fn next(&self) -> Option<Option<Vec<u8>>> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(queued) = state.queues.iter_mut().find_map(|q| q.pop_front()) {
+            state.stats.sent += 1;
+            return Some(Some(queued.bytes));
+        }
+        (!state.closed).then_some(None)
+    }
+
+    pub async fn run<W>(&self, writer: &mut W) -> io::Result<()>
+    where
+        W: AsyncWrite + Unpin,
+    {
+        loop {
+            match self.next() {
+                Some(Some(bytes)) => {
+                    writer.write_all(&bytes).await?;
+                    writer.flush().await?;
+                }
+                Some(None) => self.ready.notified().await,
+                None => return Ok(()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{make_envelope, PongPayload};
+    use super::*;
+
+    fn frame(message_type: &str, priority: Priority) -> Envelope<PongPayload> {
+        make_envelope(
+            message_type,
+            priority,
+            PongPayload {
+                nonce: message_type.to_string(),
+            },
+        )
+    }
+
+    fn drain(outbox: &Outbox) -> Vec<String> {
+        let mut types = Vec::new();
+        while let Some(Some(bytes)) = outbox.next() {
+            let env: Envelope<PongPayload> = rmp_serde::from_slice(&bytes[4..]).unwrap();
+            types.push(env.message_type);
+        }
+        types
+    }
+
+    #[test]
+    fn writes_by_priority_and_coalesces_stale_snapshots() {
+        let outbox = Outbox::default();
+        outbox
+            .push(&frame("status_snapshot", Priority::Normal))
+            .unwrap();
+        outbox
+            .push(&frame("user_notice", Priority::Normal))
+            .unwrap();
+        outbox
+            .push(&frame("status_snapshot", Priority::High))
+            .unwrap();
+        outbox
+            .push(&frame("switch_scene", Priority::Critical))
+            .unwrap();
+        outbox
+            .push(&frame("status_snapshot", Priority::Normal))
+            .unwrap();
+        outbox
+            .push_first(&frame("hello_ack", Priority::High))
+            .unwrap();
+
+        assert_eq!(
+            drain(&outbox),
+            [
+                "hello_ack",
+                "switch_scene",
+                "status_snapshot",
+                "user_notice",
+                "status_snapshot"
+            ]
+        );
+        assert_eq!(outbox.stats().dropped, 0);
+    }
+
+    #[test]
+    fn sheds_low_priority_frames_but_never_critical_ones() {
+        let outbox = Outbox::default();
+        for _ in 0..DROPPABLE_CAPACITY {
+            outbox.push(&frame("user_notice", Priority::Low)).unwrap();
+        }
+        for _ in 0..10 {
+            outbox
+                .push(&frame("switch_scene", Priority::Critical))
+                .unwrap();
+            outbox
+                .push(&frame("user_notice", Priority::Normal))
+                .unwrap();
+        }
+        assert_eq!(outbox.stats().dropped, 10);
+        let types = drain(&outbox);
+        assert_eq!(types.iter().filter(|t| *t == "switch_scene").count(), 10);
+        assert_eq!(types.len(), 10 + DROPPABLE_CAPACITY);
+    }
+}
@@ -0,0 +1,213 @@
+use super::SnapshotMode;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use utoipa::ToSchema;
+
+pub type OverrideStoreHandle = Arc<OverrideStore>;
+
+/// Dock mode and settings toggles. `None` means the dock never set it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(default)]
+pub struct SessionOverrides {
+    #[schema(value_type = Option<String>, example = "irl")]
+    pub mode: Option<SnapshotMode>,
+    pub auto_scene_switch: Option<bool>,
+    pub low_quality_fallback: Option<bool>,
+    pub manual_override: Option<bool>,
+    pub chat_bot: Option<bool>,
+    pub alerts: Option<bool>,
+}
+
+impl SessionOverrides {
+    pub fn set_mode_if_changed(&mut self, mode: SnapshotMode) -> bool {
+        if self.mode.as_ref() == Some(&mode) {
+            return false;
+        }
+        self.mode = Some(mode);
+        true
+    }
+
+    pub fn apply_setting_if_changed(&mut self, key: &str, value: bool) -> Result<bool, ()> {
+        let changed = match key {
+            "auto_scene_switch" => {
+                if self.auto_scene_switch == Some(value) {
+                    false
+                } else {
+                    self.auto_scene_switch = Some(value);
+                    true
+                }
+            }
+            "low_quality_fallback" => {
+                if self.low_quality_fallback == Some(value) {
+                    false
+                } else {
+                    self.low_quality_fallback = Some(value);
+                    true
+                }
+            }
+            "manual_override" => {
+                if self.manual_override == Some(value) {
+                    false
+                } else {
+                    self.manual_override = Some(value);
+                    true
+                }
+            }
+            "chat_bot" => {
+                if self.chat_bot == Some(value) {
+                    false
+                } else {
+                    self.chat_bot = Some(value);
+                    true
+                }
+            }
+            "alerts" => {
+                if self.alerts == Some(value) {
+                    false
+                } else {
+                    self.alerts = Some(value);
+                    true
+                }
+            }
+            _ => return Err(()),
+        };
+        Ok(changed)
+    }
+
+    /// Copies every field set in `patch`, leaving the rest untouched.
+    pub fn merge(&mut self, patch: SessionOverrides) {
+        let SessionOverrides {
+            mode,
+            auto_scene_switch,
+            low_quality_fallback,
+            manual_override,
+            chat_bot,
+            alerts,
+        } = patch;
+        self.mode = mode.or(self.mode.take());
+        self.auto_scene_switch = auto_scene_switch.or(self.auto_scene_switch);
+        self.low_quality_fallback = low_quality_fallback.or(self.low_quality_fallback);
+        self.manual_override = manual_override.or(self.manual_override);
+        self.chat_bot = chat_bot.or(self.chat_bot);
+        self.alerts = alerts.or(self.alerts);
+    }
+
+    pub fn has_any_settings(&self) -> bool {
+        self.auto_scene_switch.is_some()
+            || self.low_quality_fallback.is_some()
+            || self.manual_override.is_some()
+            || self.chat_bot.is_some()
+            || self.alerts.is_some()
+    }
+}
+
+/// The overrides shared by every IPC session and `/api/v1/settings/runtime`,
+/// written to `runtime-overrides.json` on each change so dock toggles survive
+/// plugin reconnects and core restarts.
+pub struct OverrideStore {
+    current: Mutex<SessionOverrides>,
+    path: Option<PathBuf>,
+}
+
+impl OverrideStore {
+    pub fn load(path: PathBuf) -> Self {
+        let current = match fs::read_to_string(&path) {
+            Ok(raw) => serde_json::from_str(&raw).unwrap_or_else(|err| {
+                tracing::warn!("ignoring unreadable runtime overrides: {}", err);
+                SessionOverrides::default()
+            }),
+            Err(_) => SessionOverrides::default(),
+        };
+        Self {
+            current: Mutex::new(current),
+            path: Some(path),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        Self {
+            current: Mutex::new(SessionOverrides::default()),
+            path: None,
+        }
+    }
+
+    pub fn get(&self) -> SessionOverrides {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// Applies `change` and persists the result if it altered anything.
+    pub fn update<T>(&self, change: impl FnOnce(&mut SessionOverrides) -> T) -> T {
+        let mut current = self.current.lock().unwrap();
+        let before = current.clone();
+        let result = change(&mut current);
+        if *current != before {
+            if let Some(path) = &self.path {
+                if let Err(err) = save(path, &current) {
+                    tracing::warn!("runtime overrides write failed: {}", err);
+                }
+            }
+        }
+        result
+    }
+
+    pub fn is_irl(&self) -> bool {
+        self.current.lock().unwrap().mode == Some(SnapshotMode::Irl)
+    }
+
+    /// While on, rules, failover, auto relay and the bitrate fallback leave
+    /// OBS and the relay alone.
+    pub fn manual_override(&self) -> bool {
+        self.current.lock().unwrap().manual_override == Some(true)
+    }
+}
+
+fn save(path: &Path, overrides: &SessionOverrides) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_vec_pretty(overrides)?)
+}
+
+pub fn default_path() -> PathBuf {
+    let base = std::env::var("APPDATA").unwrap_or_else(|_| ".".to_string());
+    Path::new(&base)
+        .join("Telemy")
+        .join("runtime-overrides.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overrides_persist_across_reloads() {
+        let path = std::env::temp_dir()
+            .join(format!("telemy-overrides-{}", uuid::Uuid::new_v4()))
+            .join("runtime-overrides.json");
+        let store = OverrideStore::load(path.clone());
+        assert!(store.update(|o| o.set_mode_if_changed(SnapshotMode::Irl)));
+        assert_eq!(
+            store.update(|o| o.apply_setting_if_changed("alerts", false)),
+            Ok(true)
+        );
+        assert!(!store.manual_override());
+        assert_eq!(
+            store.update(|o| o.apply_setting_if_changed("manual_override", true)),
+            Ok(true)
+        );
+        assert_eq!(
+            store.update(|o| o.apply_setting_if_changed("bogus", true)),
+            Err(())
+        );
+
+        let reloaded = OverrideStore::load(path.clone());
+        assert!(reloaded.is_irl());
+        assert!(reloaded.manual_override());
+        assert_eq!(reloaded.get().alerts, Some(false));
+        assert_eq!(reloaded.get().chat_bot, None);
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+}
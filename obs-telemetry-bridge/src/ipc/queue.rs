@@ -0,0 +1,135 @@
+use super::{
+    now_unix_ms, update_debug_status, CoreIpcCommand, IpcDebugStatusHandle, IpcSwitchResultDebug,
+};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+const CAPACITY: usize = 32;
+/// A scene switch older than this is no longer what the caller wants.
+const MAX_AGE: Duration = Duration::from_secs(30);
+
+pub type CommandQueueHandle = Arc<CommandQueue>;
+
+#[derive(Debug, Clone)]
+pub struct QueuedCommand {
+    pub request_id: String,
+    pub command: CoreIpcCommand,
+    pub queued_at: Instant,
+}
+
+/// Core commands waiting for a plugin session. Commands outlive the session
+/// that was connected when they were sent, so nothing is lost while the plugin
+/// reconnects; each is delivered once, with its deadline starting at delivery.
+/// Mode and setting overrides need no queueing: they live in the
+/// [`OverrideStore`](super::OverrideStore) and every snapshot carries them.
+#[derive(Default)]
+pub struct CommandQueue {
+    pending: Mutex<VecDeque<QueuedCommand>>,
+}
+
+impl CommandQueue {
+    /// Queues a command and returns its request id. A full queue drops its oldest entry.
+    pub fn push(&self, command: CoreIpcCommand, now: Instant) -> String {
+        let request_id = Uuid::new_v4().to_string();
+        let mut pending = self.pending.lock().unwrap();
+        if pending.len() == CAPACITY {
+            if let Some(dropped) = pending.pop_front() {
+                tracing::warn!(request_id = %dropped.request_id, "ipc command queue full; dropping oldest");
+            }
+        }
+        pending.push_back(QueuedCommand {
+            request_id: request_id.clone(),
+            command,
+            queued_at: now,
+        });
+        request_id
+    }
+
+    /// Empties the queue, splitting it into commands to deliver and ones that expired.
+    pub fn drain(&self, now: Instant) -> (Vec<QueuedCommand>, Vec<QueuedCommand>) {
+        self.pending
+            .lock()
+            .unwrap()
+            .drain(..)
+            .partition(|queued| now.duration_since(queued.queued_at) < MAX_AGE)
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+}
+
+/// Moves commands from the sender side into the queue, so a send succeeds
+/// whether or not a plugin is connected.
+pub fn spawn_forwarder(
+    mut rx: broadcast::Receiver<CoreIpcCommand>,
+    queue: CommandQueueHandle,
+    debug_status: IpcDebugStatusHandle,
+) {
+    tokio::spawn(async move {
+        loop {
+            let command = match rx.recv().await {
+                Ok(command) => command,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!(skipped, "ipc command forwarder lagged");
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            };
+            let is_switch = matches!(command, CoreIpcCommand::SwitchScene { .. });
+            let request_id = queue.push(command, Instant::now());
+            let queued = queue.len() as u32;
+            update_debug_status(&debug_status, |s| {
+                s.queued_command_count = queued;
+                if is_switch && !s.session_connected {
+                    s.last_switch_result = Some(IpcSwitchResultDebug {
+                        request_id,
+                        status: "queued".to_string(),
+                        error: None,
+                        ts_unix_ms: now_unix_ms(),
+                    });
+                }
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn switch(scene: &str) -> CoreIpcCommand {
+        CoreIpcCommand::SwitchScene {
+            scene_name: scene.to_string(),
+            reason: "test".to_string(),
+            deadline_ms: 500,
+        }
+    }
+
+    #[test]
+    fn queue_is_bounded_and_expires_stale_commands() {
+        let queue = CommandQueue::default();
+        let start = Instant::now();
+        queue.push(switch("old"), start);
+        for i in 0..CAPACITY {
+            queue.push(switch(&format!("scene {i}")), start + MAX_AGE);
+        }
+        assert_eq!(queue.len(), CAPACITY);
+
+        let (ready, expired) = queue.drain(start + MAX_AGE + Duration::from_secs(1));
+        assert_eq!(ready.len(), CAPACITY);
+        assert!(
+            expired.is_empty(),
+            "the oldest command was dropped when full"
+        );
+        assert_eq!(queue.len(), 0);
+
+        queue.push(switch("late"), start);
+        let (ready, expired) = queue.drain(start + MAX_AGE);
+        assert!(ready.is_empty());
+        assert_eq!(expired.len(), 1);
+    }
+}
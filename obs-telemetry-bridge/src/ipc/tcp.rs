@@ -0,0 +1,90 @@
+use super::queue::{self, CommandQueue, CommandQueueHandle};
+use super::{
+    handle_session_io, new_debug_status, update_debug_status, CoreIpcCommand, IpcAuthHandle,
+    OverrideStoreHandle,
+};
+use crate::aegis::RelaySessionHandle;
+use crate::metrics::ObsLink;
+use crate::model::TelemetryFrame;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::{broadcast, watch};
+
+/// Debug transport for `ipc.tcp_listen`: the same length-prefixed envelopes as
+/// the named pipes, with commands and events sharing one loopback connection.
+/// Every session must pass the HMAC challenge keyed with the TCP token.
+#[derive(Clone)]
+pub struct TcpTransport {
+    pub addr: SocketAddr,
+    pub auth: IpcAuthHandle,
+}
+
+/// Binds `ipc.tcp_listen`. Anything but loopback is refused: the transport is
+/// for a dev client on this machine or WSL, not the network.
+pub(super) async fn bind(addr: SocketAddr) -> io::Result<TcpListener> {
+    if !addr.ip().is_loopback() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "ipc.tcp_listen must be a loopback address",
+        ));
+    }
+    let listener = TcpListener::bind(addr).await?;
+    tracing::warn!(addr = %addr, "ipc debug tcp transport listening");
+    Ok(listener)
+}
+
+/// Serves one TCP client at a time, like the pipe server. The transport
+/// keeps its own command queue and debug status, fed from its own subscription
+/// to the core commands: a dev client gets every command the plugin gets, but
+/// never drains the plugin's queue or reports its sessions as the plugin's.
+pub(super) async fn serve(
+    listener: TcpListener,
+    auth: IpcAuthHandle,
+    commands: broadcast::Receiver<CoreIpcCommand>,
+    rx: watch::Receiver<TelemetryFrame>,
+    aegis_session_snapshot: RelaySessionHandle,
+    obs: ObsLink,
+    overrides: OverrideStoreHandle,
+) -> io::Result<()> {
+    let queue: CommandQueueHandle = Arc::new(CommandQueue::default());
+    let debug_status = new_debug_status();
+    queue::spawn_forwarder(commands, queue.clone(), debug_status.clone());
+    loop {
+        let (stream, peer) = listener.accept().await?;
+        let _ = stream.set_nodelay(true);
+        let (mut reader, mut writer) = stream.into_split();
+        update_debug_status(&debug_status, |s| {
+            s.session_connected = true;
+            s.dropped_frame_count = 0;
+        });
+
+        tracing::info!(peer = %peer, "ipc tcp client connected");
+        let session_result = handle_session_io(
+            &mut reader,
+            &mut writer,
+            rx.clone(),
+            aegis_session_snapshot.clone(),
+            queue.clone(),
+            debug_status.clone(),
+            obs.clone(),
+            overrides.clone(),
+            Some(auth.clone()),
+        )
+        .await;
+        update_debug_status(&debug_status, |s| {
+            s.session_connected = false;
+            s.pending_switch_count = 0;
+            s.protocol_version = None;
+            s.features.clear();
+        });
+        match session_result {
+            Ok(()) => tracing::info!(peer = %peer, "ipc tcp client disconnected"),
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                tracing::info!(peer = %peer, "ipc tcp client disconnected")
+            }
+            Err(err) => tracing::warn!(peer = %peer, error = %err, "ipc tcp session error"),
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use crate::config::{LogFormat, LoggingConfig};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+
+const FILE_PREFIX: &str = "telemy";
+const FILE_SUFFIX: &str = "log";
+/// `tail` never reads further back than this into the newest file.
+const MAX_TAIL_BYTES: u64 = 1024 * 1024;
+
+/// `logging.dir`, else `logs` under the data dir. Profiles get their own
+/// subdirectory, since rotation prunes every file that shares its prefix.
+pub fn log_dir(config: &LoggingConfig) -> PathBuf {
+    if let Some(dir) = &config.dir {
+        return PathBuf::from(dir);
+    }
+    let base = std::env::var("APPDATA").unwrap_or_else(|_| ".".to_string());
+    let dir = Path::new(&base).join("Telemy").join("logs");
+    match crate::config::active_profile() {
+        Some(profile) => dir.join(profile),
+        None => dir,
+    }
+}
+
+/// `logging.level` followed by the per-module overrides, as an `EnvFilter` string.
+pub fn directives(config: &LoggingConfig) -> String {
+    let mut parts = vec![config.level.clone()];
+    parts.extend(
+        config
+            .modules
+            .iter()
+            .map(|(target, level)| format!("{target}={level}")),
+    );
+    parts.join(",")
+}
+
+/// Installs the global subscriber: stderr, the rolling file when `to_file` and
+/// `logging.file` are set, both in `logging.format`, and the Loki layer. `RUST_LOG` overrides the
+/// configured levels. Called once, before anything logs.
+pub fn init(config: &LoggingConfig, to_file: bool) {
+    let filter = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(directives(config)))
+        .unwrap_or_else(|_| EnvFilter::new("info"));
+    let dir = log_dir(config);
+    let (appender, file_error) = if to_file && config.file {
+        match appender(&dir, config.max_files) {
+            Ok(appender) => (Some(appender), None),
+            Err(err) => (None, Some(err)),
+        }
+    } else {
+        (None, None)
+    };
+    let json = config.format == LogFormat::Json;
+    let mut layers = vec![if json {
+        fmt::layer().json().boxed()
+    } else {
+        fmt::layer().with_target(false).boxed()
+    }];
+    if let Some(writer) = appender {
+        let layer = fmt::layer().with_ansi(false).with_writer(writer);
+        layers.push(if json {
+            layer.json().boxed()
+        } else {
+            layer.boxed()
+        });
+    }
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(layers)
+        .with(crate::exporters::loki::LokiLayer)
+        .init();
+    if let Some(err) = file_error {
+        tracing::warn!(dir = %dir.display(), error = %err, "file logging disabled");
+    }
+}
+
+/// Writes synchronously, so the last lines before a fatal exit are not lost.
+fn appender(dir: &Path, max_files: usize) -> Result<RollingFileAppender, String> {
+    fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(FILE_PREFIX)
+        .filename_suffix(FILE_SUFFIX)
+        .max_log_files(max_files)
+        .build(dir)
+        .map_err(|err| err.to_string())
+}
+
+/// Log files in `dir`, newest first.
+fn log_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files: Vec<(SystemTime, PathBuf)> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            let name = e.file_name();
+            let name = name.to_string_lossy();
+            name.starts_with(FILE_PREFIX) && name.ends_with(FILE_SUFFIX)
+        })
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .collect();
+    files.sort_by(|a, b| b.cmp(a));
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+/// The file name and last `lines` lines of the newest log in `dir`, or `None`
+/// before anything was written.
+pub fn tail(dir: &Path, lines: usize) -> std::io::Result<Option<(String, Vec<String>)>> {
+    let Some(path) = log_files(dir)?.into_iter().next() else {
+        return Ok(None);
+    };
+    let mut file = File::open(&path)?;
+    let len = file.metadata()?.len();
+    let start = len.saturating_sub(MAX_TAIL_BYTES);
+    file.seek(SeekFrom::Start(start))?;
+    let mut raw = Vec::new();
+    file.read_to_end(&mut raw)?;
+    let text = String::from_utf8_lossy(&raw);
+    let mut all: Vec<&str> = text.lines().collect();
+    if start > 0 && !all.is_empty() {
+        // The first line was cut mid-way by the seek.
+        all.remove(0);
+    }
+    let tail = all[all.len().saturating_sub(lines)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    Ok(Some((name.into_owned(), tail)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directives_append_module_levels() {
+        let mut config = LoggingConfig::default();
+        assert_eq!(directives(&config), "info");
+        config.level = "warn".to_string();
+        config
+            .modules
+            .insert("obws".to_string(), "error".to_string());
+        config.modules.insert(
+            "obs_telemetry_bridge::exporters".to_string(),
+            "debug".to_string(),
+        );
+        assert_eq!(
+            directives(&config),
+            "warn,obs_telemetry_bridge::exporters=debug,obws=error"
+        );
+    }
+
+    #[test]
+    fn tail_reads_the_newest_file() {
+        let dir = std::env::temp_dir().join(format!("telemy-logs-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        assert!(tail(&dir, 10).unwrap().is_none());
+
+        fs::write(dir.join("telemy.2026-10-13.log"), "old\n").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        fs::write(dir.join("telemy.2026-10-14.log"), "a\nb\nc\n").unwrap();
+        fs::write(dir.join("other.txt"), "ignored\n").unwrap();
+
+        let (name, lines) = tail(&dir, 2).unwrap().unwrap();
+        assert_eq!(name, "telemy.2026-10-14.log");
+        assert_eq!(lines, ["b", "c"]);
+        let _ = fs::remove_dir_all(dir);
+    }
+}
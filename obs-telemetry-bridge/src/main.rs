@@ -1,27 +1,42 @@
+mod activity;
 mod aegis;
 mod app;
+mod bitrate;
+mod cli;
 mod config;
+mod crash;
+mod diagnostics;
+mod discovery;
+mod diskguard;
+mod events;
 mod exporters;
+mod failover;
+mod ingest;
+mod instance;
 mod ipc;
+mod logging;
 mod metrics;
 mod model;
+mod notify;
+mod obslog;
+mod pause;
+mod peers;
+mod recorder;
+mod relay;
+mod reports;
+mod rules;
+mod scripting;
 mod security;
+mod selfstats;
 mod server;
 mod startup;
 mod tray;
+mod twitch;
 
 #[tokio::main]
 async fn main() {
-    init_logging();
     if let Err(err) = app::run().await {
         tracing::error!(error = %err, "fatal");
         std::process::exit(1);
     }
 }
-
-fn init_logging() {
-    use tracing_subscriber::{fmt, EnvFilter};
-    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
-    fmt().with_env_filter(filter).with_target(false).init();
-}
-
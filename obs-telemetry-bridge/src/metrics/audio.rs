@@ -0,0 +1,167 @@
+use crate::model::AudioInputFrame;
+use futures_util::StreamExt;
+use obws::events::{Event, InputVolumeMeter};
+use obws::requests::inputs::InputId;
+use obws::Client as ObsClient;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+/// OBS stops listing an input in `InputVolumeMeters` once it is deactivated.
+const METER_STALE: Duration = Duration::from_secs(2);
+/// Peaks at or above full scale (0 dBFS) are reported as clipping.
+const CLIP_MUL: f32 = 1.0;
+const SILENCE_DB: f32 = -100.0;
+
+struct InputAudio {
+    muted: bool,
+    volume_db: f32,
+    level_mul: f32,
+    peak_mul: f32,
+    seen: Instant,
+}
+
+/// Latest per-input audio state, fed by the OBS event stream and drained into
+/// each frame by the metrics loop.
+#[derive(Default)]
+pub struct AudioMeters {
+    inputs: Mutex<BTreeMap<String, InputAudio>>,
+}
+
+impl AudioMeters {
+    /// Applies one `InputVolumeMeters` event and returns the inputs seen for the first time.
+    fn apply_meters(&self, meters: &[InputVolumeMeter], now: Instant) -> Vec<String> {
+        let mut inputs = self.inputs.lock().unwrap();
+        let mut added = Vec::new();
+        for meter in meters {
+            // Each channel reports [magnitude, peak, input peak] as a multiplier.
+            let level = meter.levels.iter().map(|c| c[0]).fold(0.0, f32::max);
+            let peak = meter.levels.iter().map(|c| c[1]).fold(0.0, f32::max);
+            let input = inputs.entry(meter.name.clone()).or_insert_with(|| {
+                added.push(meter.name.clone());
+                InputAudio {
+                    muted: false,
+                    volume_db: 0.0,
+                    level_mul: 0.0,
+                    peak_mul: 0.0,
+                    seen: now,
+                }
+            });
+            input.level_mul = level;
+            input.peak_mul = input.peak_mul.max(peak);
+            input.seen = now;
+        }
+        added
+    }
+
+    fn update(&self, name: &str, apply: impl FnOnce(&mut InputAudio)) {
+        if let Some(input) = self.inputs.lock().unwrap().get_mut(name) {
+            apply(input);
+        }
+    }
+
+    /// Returns the active inputs and resets their held peaks.
+    pub fn take(&self, now: Instant) -> Vec<AudioInputFrame> {
+        let mut inputs = self.inputs.lock().unwrap();
+        inputs.retain(|_, input| now.duration_since(input.seen) < METER_STALE);
+        inputs
+            .iter_mut()
+            .map(|(name, input)| {
+                let peak = std::mem::take(&mut input.peak_mul);
+                AudioInputFrame {
+                    name: name.clone(),
+                    muted: input.muted,
+                    volume_db: input.volume_db,
+                    level_db: mul_to_db(input.level_mul),
+                    peak_db: mul_to_db(peak),
+                    clipping: peak >= CLIP_MUL,
+                }
+            })
+            .collect()
+    }
+
+    fn clear(&self) {
+        self.inputs.lock().unwrap().clear();
+    }
+}
+
+/// Follows one OBS connection's event stream until it closes. Holds the client
+/// weakly so a connection dropped by the link is not kept alive here.
+pub async fn watch(client: Weak<ObsClient>, meters: Arc<AudioMeters>) {
+    let Some(events) = client.upgrade().and_then(|c| c.events().ok()) else {
+        return;
+    };
+    futures_util::pin_mut!(events);
+    meters.clear();
+
+    while let Some(event) = events.next().await {
+        match event {
+            Event::InputVolumeMeters { inputs } => {
+                for name in meters.apply_meters(&inputs, Instant::now()) {
+                    let Some(client) = client.upgrade() else {
+                        return;
+                    };
+                    let muted = client.inputs().muted(InputId::Name(&name)).await;
+                    let volume = client.inputs().volume(InputId::Name(&name)).await;
+                    meters.update(&name, |input| {
+                        input.muted = muted.unwrap_or(false);
+                        input.volume_db = volume.map_or(0.0, |v| v.db);
+                    });
+                }
+            }
+            Event::InputMuteStateChanged { id, muted } => {
+                meters.update(&id.name, |input| input.muted = muted);
+            }
+            Event::InputVolumeChanged { id, db, .. } => {
+                meters.update(&id.name, |input| input.volume_db = db as f32);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn mul_to_db(mul: f32) -> f32 {
+    if mul <= 0.0 {
+        SILENCE_DB
+    } else {
+        (20.0 * mul.log10()).max(SILENCE_DB)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn meter(name: &str, magnitude: f32, peak: f32) -> InputVolumeMeter {
+        InputVolumeMeter {
+            name: name.to_string(),
+            levels: vec![[magnitude, peak, peak], [magnitude / 2.0, peak / 2.0, 0.0]],
+        }
+    }
+
+    #[test]
+    fn holds_peaks_between_frames_and_flags_clipping() {
+        let meters = AudioMeters::default();
+        let start = Instant::now();
+        let added =
+            meters.apply_meters(&[meter("Mic", 0.1, 1.0), meter("Desktop", 0.0, 0.0)], start);
+        assert_eq!(added, ["Mic", "Desktop"]);
+        meters.update("Mic", |input| input.muted = true);
+        assert!(meters
+            .apply_meters(&[meter("Mic", 0.1, 0.5)], start)
+            .is_empty());
+
+        let frame = meters.take(start);
+        let mic = frame.iter().find(|i| i.name == "Mic").unwrap();
+        assert!(mic.muted && mic.clipping);
+        assert_eq!(mic.peak_db, 0.0);
+        assert_eq!(mic.level_db, -20.0);
+        let desktop = frame.iter().find(|i| i.name == "Desktop").unwrap();
+        assert_eq!(desktop.level_db, SILENCE_DB);
+
+        meters.apply_meters(&[meter("Mic", 0.1, 0.5)], start + Duration::from_secs(1));
+        let frame = meters.take(start + METER_STALE);
+        assert_eq!(frame.len(), 1);
+        assert!(!frame[0].clipping);
+    }
+}
@@ -0,0 +1,85 @@
+use crate::model::DiskFrame;
+use std::path::Path;
+use sysinfo::Disks;
+
+const BYTES_PER_MB: f64 = 1024.0 * 1024.0;
+
+/// Every mounted drive, tagged with what OBS writes to it: `uses` pairs a label
+/// such as `recording` with the folder OBS uses for it.
+pub fn collect_disks(disks: &Disks, uses: &[(&str, &str)]) -> Vec<DiskFrame> {
+    let mut frames: Vec<DiskFrame> = Vec::new();
+    for disk in disks.list() {
+        let mount_point = disk.mount_point().to_string_lossy().into_owned();
+        let total = disk.total_space();
+        if total == 0 || frames.iter().any(|d| d.mount_point == mount_point) {
+            continue;
+        }
+        let available = disk.available_space();
+        frames.push(DiskFrame {
+            mount_point,
+            name: disk.name().to_string_lossy().into_owned(),
+            total_mb: total as f64 / BYTES_PER_MB,
+            available_mb: available as f64 / BYTES_PER_MB,
+            used_percent: (1.0 - available as f64 / total as f64) as f32 * 100.0,
+            obs_uses: Vec::new(),
+        });
+    }
+    let mounts: Vec<String> = frames.iter().map(|d| d.mount_point.clone()).collect();
+    for (label, dir) in uses {
+        if let Some(i) = mount_for(Path::new(dir), &mounts) {
+            let uses = &mut frames[i].obs_uses;
+            if !uses.iter().any(|u| u == label) {
+                uses.push(label.to_string());
+            }
+        }
+    }
+    frames
+}
+
+/// The index of the mount `path` lives on: the longest mount point that is a
+/// prefix of it, ignoring case on Windows.
+fn mount_for(path: &Path, mounts: &[String]) -> Option<usize> {
+    let path = normalize(&path.to_string_lossy());
+    mounts
+        .iter()
+        .enumerate()
+        .filter(|(_, mount)| {
+            let mount = normalize(mount);
+            path.strip_prefix(mount.as_str()).is_some_and(|rest| {
+                mount.ends_with('/') || rest.is_empty() || rest.starts_with('/')
+            })
+        })
+        .max_by_key(|(_, mount)| mount.len())
+        .map(|(i, _)| i)
+}
+
+fn normalize(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    if cfg!(windows) {
+        path.to_lowercase()
+    } else {
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paths_map_to_the_deepest_mount() {
+        let mounts = [
+            "/".to_string(),
+            "/mnt/rec".to_string(),
+            "/mnt/recordings2".to_string(),
+        ];
+        assert_eq!(mount_for(Path::new("/home/me/Videos"), &mounts), Some(0));
+        assert_eq!(mount_for(Path::new("/mnt/rec/obs"), &mounts), Some(1));
+        assert_eq!(mount_for(Path::new("/mnt/rec"), &mounts), Some(1));
+        assert_eq!(mount_for(Path::new("/mnt/recordings2/x"), &mounts), Some(2));
+        assert_eq!(
+            mount_for(Path::new("D:/Videos"), &["C:\\".to_string()]),
+            None
+        );
+    }
+}
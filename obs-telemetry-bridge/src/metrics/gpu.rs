@@ -0,0 +1,242 @@
+use crate::model::GpuFrame;
+use nvml_wrapper::enum_wrappers::device::TemperatureSensor;
+use nvml_wrapper::Nvml;
+
+const VENDOR_NVIDIA: u32 = 0x10de;
+const VENDOR_AMD: u32 = 0x1002;
+const VENDOR_INTEL: u32 = 0x8086;
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// Enumerates every GPU: NVIDIA through NVML, everything else through the platform
+/// backend (sysfs on Linux, DXGI on Windows).
+pub fn collect_gpus(nvml: Option<&Nvml>) -> Vec<GpuFrame> {
+    let mut gpus = nvml.map(collect_nvidia).unwrap_or_default();
+    let skip_nvidia = !gpus.is_empty();
+    let mut others = collect_platform_gpus(skip_nvidia);
+    let offset = gpus.len() as u32;
+    for (i, gpu) in others.iter_mut().enumerate() {
+        gpu.index = offset + i as u32;
+    }
+    gpus.extend(others);
+    gpus
+}
+
+fn collect_nvidia(nvml: &Nvml) -> Vec<GpuFrame> {
+    let count = nvml.device_count().unwrap_or(0);
+    (0..count)
+        .filter_map(|i| {
+            let device = nvml.device_by_index(i).ok()?;
+            let memory = device.memory_info().ok();
+            Some(GpuFrame {
+                index: i,
+                vendor: "nvidia".to_string(),
+                name: device.name().unwrap_or_else(|_| format!("NVIDIA GPU {i}")),
+                utilization_percent: device.utilization_rates().ok().map(|u| u.gpu as f32),
+                vram_used_mb: memory.as_ref().map(|m| m.used / BYTES_PER_MB),
+                vram_total_mb: memory.as_ref().map(|m| m.total / BYTES_PER_MB),
+                temp_c: device
+                    .temperature(TemperatureSensor::Gpu)
+                    .ok()
+                    .map(|t| t as f32),
+                encoder_percent: device
+                    .encoder_utilization()
+                    .ok()
+                    .map(|u| u.utilization as f32),
+            })
+        })
+        .collect()
+}
+
+#[cfg(not(windows))]
+fn collect_platform_gpus(skip_nvidia: bool) -> Vec<GpuFrame> {
+    sysfs::collect(std::path::Path::new("/sys/class/drm"), skip_nvidia)
+}
+
+#[cfg(windows)]
+fn collect_platform_gpus(skip_nvidia: bool) -> Vec<GpuFrame> {
+    dxgi::collect(skip_nvidia)
+}
+
+#[cfg(not(windows))]
+mod sysfs {
+    use super::{BYTES_PER_MB, VENDOR_AMD, VENDOR_INTEL, VENDOR_NVIDIA};
+    use crate::model::GpuFrame;
+    use std::fs;
+    use std::path::Path;
+
+    pub(super) fn collect(drm_root: &Path, skip_nvidia: bool) -> Vec<GpuFrame> {
+        let Ok(entries) = fs::read_dir(drm_root) else {
+            return Vec::new();
+        };
+        let mut cards: Vec<_> = entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(|n| n.strip_prefix("card"))
+                    .is_some_and(|rest| {
+                        !rest.is_empty() && rest.chars().all(|c| c.is_ascii_digit())
+                    })
+            })
+            .collect();
+        cards.sort();
+
+        cards
+            .iter()
+            .filter_map(|card| {
+                let device = card.join("device");
+                let vendor = read_hex(&device.join("vendor"))?;
+                let vendor_name = match vendor {
+                    VENDOR_AMD => "amd",
+                    VENDOR_INTEL => "intel",
+                    VENDOR_NVIDIA if !skip_nvidia => "nvidia",
+                    _ => return None,
+                };
+                let name = read_trimmed(&device.join("product_name"))
+                    .unwrap_or_else(|| format!("{} GPU", vendor_name.to_uppercase()));
+                Some(GpuFrame {
+                    index: 0,
+                    vendor: vendor_name.to_string(),
+                    name,
+                    utilization_percent: read_u64(&device.join("gpu_busy_percent"))
+                        .map(|v| v as f32),
+                    vram_used_mb: read_u64(&device.join("mem_info_vram_used"))
+                        .map(|b| b / BYTES_PER_MB),
+                    vram_total_mb: read_u64(&device.join("mem_info_vram_total"))
+                        .map(|b| b / BYTES_PER_MB),
+                    temp_c: read_hwmon_temp(&device),
+                    encoder_percent: None,
+                })
+            })
+            .collect()
+    }
+
+    fn read_trimmed(path: &Path) -> Option<String> {
+        let value = fs::read_to_string(path).ok()?.trim().to_string();
+        (!value.is_empty()).then_some(value)
+    }
+
+    fn read_u64(path: &Path) -> Option<u64> {
+        read_trimmed(path)?.parse().ok()
+    }
+
+    fn read_hex(path: &Path) -> Option<u32> {
+        let raw = read_trimmed(path)?;
+        u32::from_str_radix(raw.trim_start_matches("0x"), 16).ok()
+    }
+
+    fn read_hwmon_temp(device: &Path) -> Option<f32> {
+        let entries = fs::read_dir(device.join("hwmon")).ok()?;
+        entries
+            .filter_map(|e| e.ok())
+            .find_map(|e| read_u64(&e.path().join("temp1_input")))
+            .map(|milli_c| milli_c as f32 / 1000.0)
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn reads_amd_and_intel_cards_and_skips_connectors() {
+            let root = std::env::temp_dir().join(format!("telemy-drm-{}", uuid::Uuid::new_v4()));
+            let amd = root.join("card0/device");
+            fs::create_dir_all(amd.join("hwmon/hwmon3")).unwrap();
+            fs::write(amd.join("vendor"), "0x1002\n").unwrap();
+            fs::write(amd.join("gpu_busy_percent"), "37\n").unwrap();
+            fs::write(
+                amd.join("mem_info_vram_used"),
+                (512 * BYTES_PER_MB).to_string(),
+            )
+            .unwrap();
+            fs::write(
+                amd.join("mem_info_vram_total"),
+                (8192 * BYTES_PER_MB).to_string(),
+            )
+            .unwrap();
+            fs::write(amd.join("hwmon/hwmon3/temp1_input"), "61000\n").unwrap();
+
+            let intel = root.join("card1/device");
+            fs::create_dir_all(&intel).unwrap();
+            fs::write(intel.join("vendor"), "0x8086\n").unwrap();
+            fs::create_dir_all(root.join("card0-DP-1")).unwrap();
+
+            let gpus = collect(&root, true);
+            assert_eq!(gpus.len(), 2);
+            assert_eq!(gpus[0].vendor, "amd");
+            assert_eq!(gpus[0].utilization_percent, Some(37.0));
+            assert_eq!(gpus[0].vram_used_mb, Some(512));
+            assert_eq!(gpus[0].vram_total_mb, Some(8192));
+            assert_eq!(gpus[0].temp_c, Some(61.0));
+            assert_eq!(gpus[1].vendor, "intel");
+            assert_eq!(gpus[1].utilization_percent, None);
+            let _ = fs::remove_dir_all(&root);
+        }
+    }
+}
+
+#[cfg(windows)]
+mod dxgi {
+    use super::{BYTES_PER_MB, VENDOR_AMD, VENDOR_INTEL, VENDOR_NVIDIA};
+    use crate::model::GpuFrame;
+    use windows::core::ComInterface;
+    use windows::Win32::Graphics::Dxgi::{
+        CreateDXGIFactory1, IDXGIAdapter3, IDXGIFactory1, DXGI_ADAPTER_DESC1,
+        DXGI_ADAPTER_FLAG_SOFTWARE, DXGI_MEMORY_SEGMENT_GROUP_LOCAL, DXGI_QUERY_VIDEO_MEMORY_INFO,
+    };
+
+    /// DXGI exposes adapter names and VRAM usage for every vendor; utilization and
+    /// temperature need vendor SDKs (ADL/IGCL) and are left empty here.
+    pub(super) fn collect(skip_nvidia: bool) -> Vec<GpuFrame> {
+        let factory: IDXGIFactory1 = match unsafe { CreateDXGIFactory1() } {
+            Ok(factory) => factory,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut gpus = Vec::new();
+        let mut i = 0;
+        while let Ok(adapter) = unsafe { factory.EnumAdapters1(i) } {
+            i += 1;
+            let mut desc = DXGI_ADAPTER_DESC1::default();
+            if unsafe { adapter.GetDesc1(&mut desc) }.is_err() {
+                continue;
+            }
+            if desc.Flags & DXGI_ADAPTER_FLAG_SOFTWARE.0 as u32 != 0 {
+                continue;
+            }
+            let vendor = match desc.VendorId {
+                VENDOR_AMD => "amd",
+                VENDOR_INTEL => "intel",
+                VENDOR_NVIDIA if !skip_nvidia => "nvidia",
+                _ => continue,
+            };
+            let name_len = desc
+                .Description
+                .iter()
+                .position(|&c| c == 0)
+                .unwrap_or(desc.Description.len());
+            let name = String::from_utf16_lossy(&desc.Description[..name_len]);
+
+            let vram_used_mb = adapter.cast::<IDXGIAdapter3>().ok().and_then(|a3| {
+                let mut info = DXGI_QUERY_VIDEO_MEMORY_INFO::default();
+                unsafe { a3.QueryVideoMemoryInfo(0, DXGI_MEMORY_SEGMENT_GROUP_LOCAL, &mut info) }
+                    .ok()
+                    .map(|_| info.CurrentUsage / BYTES_PER_MB)
+            });
+
+            gpus.push(GpuFrame {
+                index: 0,
+                vendor: vendor.to_string(),
+                name,
+                utilization_percent: None,
+                vram_used_mb,
+                vram_total_mb: Some(desc.DedicatedVideoMemory as u64 / BYTES_PER_MB),
+                temp_c: None,
+                encoder_percent: None,
+            });
+        }
+        gpus
+    }
+}
@@ -1,74 +1,90 @@
-use crate::model::{NetworkFrame, ObsFrame, StreamOutput, SystemFrame, TelemetryFrame};
+use crate::config::{MetricsConfig, NetworkConfig};
+use crate::model::{
+    AudioFrame, DiskFrame, GpuFrame, InterfaceFrame, NetworkFrame, ObsFrame, SceneEntry,
+    SceneInventory, SceneSource, StreamOutput, SystemFrame, TelemetryFrame, SCHEMA_VERSION,
+};
 use nvml_wrapper::Nvml;
+use obws::responses::scene_items::SourceType;
 use obws::Client as ObsClient;
+use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
-use sysinfo::{Networks, System};
+use sysinfo::{Disks, Networks, System};
 use tokio::io::AsyncWriteExt;
 use tokio::net::TcpStream;
 use tokio::time::timeout;
 
+mod audio;
+mod disks;
+mod gpu;
+mod obs_link;
+mod rolling;
+mod route;
+mod scene_history;
+mod scene_list;
+mod supervisor;
+mod trend;
+mod uplinks;
+mod watchdog;
+
+pub use obs_link::{obs_process_running, ObsControlAction, ObsLink, ObsLinkSettings};
+pub use supervisor::supervise;
+pub use trend::TrendAnalyzer;
+pub use watchdog::{frames_stale, spawn_watchdog};
+
 pub struct MetricsHub {
-    obs_host: String,
-    obs_port: u16,
-    obs_password: Option<String>,
-    obs_client: Option<ObsClient>,
+    obs: ObsLink,
     sys: System,
     networks: Networks,
     last_net_at: Option<Instant>,
-    last_rx_bytes: u64,
-    last_tx_bytes: u64,
+    interfaces: Vec<String>,
+    /// Only followed while `interfaces` is empty.
+    default_route: Option<route::DefaultRoute>,
+    uplinks: uplinks::Uplinks,
     nvml: Option<Nvml>,
     latency_target: String,
-    obs_auto_detect: bool,
-    obs_process_name: String,
-    last_process_check: Instant,
-    obs_process_running: bool,
+    last_replay_path: Option<String>,
+    disks: Disks,
+    /// OBS's recording folder and when it was read; drives are relisted then too.
+    record_dir: Option<(Instant, String)>,
+    rolling: rolling::Rolling,
 }
 
+/// How often the recording folder and the list of drives are re-read.
+const DISK_LIST_REFRESH: Duration = Duration::from_secs(30);
+
 impl MetricsHub {
-    pub fn new(
-        obs_host: String,
-        obs_port: u16,
-        obs_password: Option<String>,
-        latency_target: String,
-        obs_auto_detect: bool,
-        obs_process_name: String,
-    ) -> Self {
+    pub fn new(obs: ObsLink, network: &NetworkConfig) -> Self {
         Self {
-            obs_host,
-            obs_port,
-            obs_password,
-            obs_client: None,
+            obs,
             sys: System::new(),
             networks: Networks::new_with_refreshed_list(),
             last_net_at: None,
-            last_rx_bytes: 0,
-            last_tx_bytes: 0,
+            interfaces: network.interfaces.clone(),
+            default_route: network
+                .interfaces
+                .is_empty()
+                .then(route::DefaultRoute::spawn),
+            uplinks: uplinks::Uplinks::spawn(&network.uplinks, &network.latency_target),
             nvml: Nvml::init().ok(),
-            latency_target,
-            obs_auto_detect,
-            obs_process_name,
-            last_process_check: Instant::now() - Duration::from_secs(5),
-            obs_process_running: true,
+            latency_target: network.latency_target.clone(),
+            last_replay_path: None,
+            disks: Disks::new_with_refreshed_list(),
+            record_dir: None,
+            rolling: rolling::Rolling::default(),
         }
     }
 
     pub async fn collect(&mut self) -> Result<TelemetryFrame, Box<dyn std::error::Error>> {
         let ts = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
 
-        if self.obs_auto_detect {
-            self.refresh_obs_process();
-        }
-
-        if self.obs_client.is_none() && self.obs_process_running {
-            self.try_connect_obs().await;
-        }
-
+        let mut obs_client = self.obs.client();
         let mut outputs = Vec::new();
+        // Bytes sent per output, for the rolling bitrate.
+        let mut output_bytes = HashMap::new();
         let mut obs = ObsFrame::default();
 
-        if let Some(client) = &self.obs_client {
+        if let Some(client) = &obs_client {
             match client.outputs().list().await {
                 Ok(list) => {
                     for output in list {
@@ -86,6 +102,7 @@ impl MetricsHub {
                             let duration_secs =
                                 status.duration.whole_milliseconds() as f32 / 1000.0;
                             let bytes = status.bytes as f32;
+                            output_bytes.insert(stream.name.clone(), status.bytes);
 
                             if total_frames > 0.0 {
                                 stream.drop_pct = skipped_frames / total_frames;
@@ -105,12 +122,14 @@ impl MetricsHub {
                         outputs.push(stream);
                     }
                 }
-                Err(_) => {
-                    self.obs_client = None;
+                Err(err) => {
+                    crate::selfstats::stats().obs_request_failed();
+                    self.obs.report_failure(err);
+                    obs_client = None;
                 }
             }
 
-            if let Some(client) = &self.obs_client {
+            if let Some(client) = &obs_client {
                 match client.streaming().status().await {
                     Ok(status) => {
                         obs.connected = true;
@@ -132,14 +151,16 @@ impl MetricsHub {
                             }
                         }
                     }
-                    Err(_) => {
-                        self.obs_client = None;
+                    Err(err) => {
+                        crate::selfstats::stats().obs_request_failed();
+                        self.obs.report_failure(err);
+                        obs_client = None;
                     }
                 }
             }
 
             // Collect OBS general stats (encoding lag, render/output frames, disk space)
-            if let Some(client) = &self.obs_client {
+            if let Some(client) = &obs_client {
                 if let Ok(stats) = client.general().stats().await {
                     for o in outputs.iter_mut() {
                         o.encoding_lag_ms = stats.average_frame_render_time as f32;
@@ -154,92 +175,98 @@ impl MetricsHub {
             }
 
             // Collect recording status
-            if let Some(client) = &self.obs_client {
+            if let Some(client) = &obs_client {
                 if let Ok(rec) = client.recording().status().await {
                     obs.recording = rec.active;
                 }
             }
 
             // Detect OBS studio mode
-            if let Some(client) = &self.obs_client {
+            if let Some(client) = &obs_client {
                 obs.studio_mode = client.ui().studio_mode_enabled().await.unwrap_or(false);
             }
+
+            // Replay buffer and virtual camera; both error when the output is unavailable
+            if let Some(client) = &obs_client {
+                obs.replay_buffer_active = client.replay_buffer().status().await.unwrap_or(false);
+                if obs.replay_buffer_active {
+                    if let Ok(path) = client.replay_buffer().last_replay().await {
+                        self.last_replay_path = Some(path);
+                    }
+                }
+                obs.virtualcam_active = client.virtual_cam().status().await.unwrap_or(false);
+            }
+
+            if let Some(client) = &obs_client {
+                let stale = self
+                    .record_dir
+                    .as_ref()
+                    .is_none_or(|(at, _)| at.elapsed() >= DISK_LIST_REFRESH);
+                if stale {
+                    if let Ok(dir) = client.config().record_directory().await {
+                        self.record_dir = Some((Instant::now(), dir));
+                        self.disks.refresh_list();
+                    }
+                }
+            }
         }
+        obs.last_replay_path = self.last_replay_path.clone();
 
+        (obs.connection_state, obs.last_error) = self.obs.status();
+        obs.current_scene = self.obs.scene_history().current();
         let health = compute_health(&outputs);
 
         let (cpu_percent, mem_percent) = self.collect_system();
-        let (gpu_percent, gpu_temp_c) = self.collect_gpu();
-        let (upload_mbps, download_mbps) = self.collect_network();
+        let (gpus, gpu_percent, gpu_temp_c) = self.collect_gpu();
+        let (upload_mbps, download_mbps, interfaces) = self.collect_network();
         let latency_ms = self.collect_latency().await;
+        let uplinks = self.uplinks.frames(&interfaces);
+        let disks = self.collect_disks();
 
-        Ok(TelemetryFrame {
+        let mut frame = TelemetryFrame {
+            schema_version: SCHEMA_VERSION,
             timestamp_unix: ts,
             health,
+            health_trend: Default::default(),
             obs,
             system: SystemFrame {
                 cpu_percent,
                 mem_percent,
                 gpu_percent,
                 gpu_temp_c,
+                encoder_percent: gpus
+                    .iter()
+                    .filter_map(|g| g.encoder_percent)
+                    .reduce(f32::max),
+                gpus,
+                disks,
             },
             network: NetworkFrame {
                 upload_mbps,
                 download_mbps,
                 latency_ms,
+                interfaces,
+                bond: uplinks::bond(&uplinks),
+                uplinks,
             },
             streams: outputs,
-        })
-    }
-
-    async fn try_connect_obs(&mut self) {
-        let password = self.obs_password.as_deref();
-
-        // Log connection attempt details (without exposing the actual password)
-        let password_status = if password.is_some() {
-            "with password"
-        } else {
-            "without password"
+            stream_stats: Default::default(),
+            relay: None,
+            audio: AudioFrame {
+                inputs: self.obs.audio().take(Instant::now()),
+            },
         };
-        tracing::debug!(
-            "Attempting to connect to OBS at {}:{} {}",
-            self.obs_host,
-            self.obs_port,
-            password_status
-        );
-
-        match ObsClient::connect(&self.obs_host, self.obs_port, password).await {
-            Ok(client) => {
-                tracing::info!(
-                    "Successfully connected to OBS at {}:{}",
-                    self.obs_host,
-                    self.obs_port
-                );
-                self.obs_client = Some(client);
-            }
-            Err(e) => {
-                // Provide more specific error messages for common failures
-                let error_msg = e.to_string();
-                if error_msg.contains("handshake") {
-                    tracing::warn!(
-                        "Failed to connect to OBS at {}:{}: Authentication handshake failed. \
-                        This usually means the password is incorrect or OBS WebSocket server requires authentication. \
-                        Error: {}",
-                        self.obs_host, self.obs_port, e
-                    );
-                } else {
-                    tracing::warn!(
-                        "Failed to connect to OBS at {}:{}: {}",
-                        self.obs_host,
-                        self.obs_port,
-                        e
-                    );
-                }
-
-                // Add a small delay before next connection attempt to avoid hammering
-                tokio::time::sleep(Duration::from_secs(2)).await;
-            }
-        }
+        let sample = crate::rules::main_output(&frame)
+            .filter(|_| frame.obs.streaming)
+            .and_then(|main| {
+                Some(rolling::StreamSample {
+                    bytes: *output_bytes.get(&main.name)?,
+                    dropped: frame.obs.total_dropped_frames,
+                    frame_time_ms: main.encoding_lag_ms,
+                })
+            });
+        frame.stream_stats = self.rolling.observe(Instant::now(), sample);
+        Ok(frame)
     }
 
     fn collect_system(&mut self) -> (f32, f32) {
@@ -259,55 +286,79 @@ impl MetricsHub {
         (cpu, mem_percent)
     }
 
-    fn collect_network(&mut self) -> (f32, f32) {
+    /// Per-adapter rates since the previous collect, plus the totals of the
+    /// measured adapters.
+    fn collect_network(&mut self) -> (f32, f32, Vec<InterfaceFrame>) {
         self.networks.refresh();
-
-        let mut rx_bytes = 0u64;
-        let mut tx_bytes = 0u64;
-        for (_name, data) in &self.networks {
-            rx_bytes = rx_bytes.saturating_add(data.received());
-            tx_bytes = tx_bytes.saturating_add(data.transmitted());
-        }
-
         let now = Instant::now();
-        let mut upload_mbps = 0.0;
-        let mut download_mbps = 0.0;
-
-        if let Some(prev) = self.last_net_at {
-            let dt = now.duration_since(prev).as_secs_f32();
+        let dt = self
+            .last_net_at
+            .replace(now)
+            .map_or(0.0, |prev| now.duration_since(prev).as_secs_f32());
+        let mbps = |bytes: u64| {
             if dt > 0.0 {
-                let delta_tx = tx_bytes.saturating_sub(self.last_tx_bytes);
-                let delta_rx = rx_bytes.saturating_sub(self.last_rx_bytes);
-                upload_mbps = (delta_tx as f32 * 8.0) / dt / 1_000_000.0;
-                download_mbps = (delta_rx as f32 * 8.0) / dt / 1_000_000.0;
+                (bytes as f32 * 8.0) / dt / 1_000_000.0
+            } else {
+                0.0
             }
-        }
-
-        self.last_net_at = Some(now);
-        self.last_rx_bytes = rx_bytes;
-        self.last_tx_bytes = tx_bytes;
-
-        (upload_mbps, download_mbps)
-    }
-
-    fn collect_gpu(&mut self) -> (Option<f32>, Option<f32>) {
-        let nvml = match &self.nvml {
-            Some(nvml) => nvml,
-            None => return (None, None),
         };
 
-        let device = match nvml.device_by_index(0) {
-            Ok(device) => device,
-            Err(_) => return (None, None),
-        };
+        let route = self.default_route.as_ref().and_then(|r| r.interface());
+        let mut interfaces: Vec<InterfaceFrame> = self
+            .networks
+            .iter()
+            .filter(|(_, data)| data.total_received() > 0 || data.total_transmitted() > 0)
+            .map(|(name, data)| InterfaceFrame {
+                name: name.clone(),
+                upload_mbps: mbps(data.transmitted()),
+                download_mbps: mbps(data.received()),
+                measured: false,
+            })
+            .collect();
+        interfaces.sort_by(|a, b| a.name.cmp(&b.name));
+        mark_measured(&mut interfaces, &self.interfaces, route.as_deref());
+
+        let measured = interfaces.iter().filter(|i| i.measured);
+        let (upload, download) = measured.fold((0.0, 0.0), |(up, down), i| {
+            (up + i.upload_mbps, down + i.download_mbps)
+        });
+        (upload, download, interfaces)
+    }
 
-        let util = device.utilization_rates().ok().map(|u| u.gpu as f32);
-        let temp = device
-            .temperature(nvml_wrapper::enum_wrappers::device::TemperatureSensor::Gpu)
-            .ok()
-            .map(|t| t as f32);
+    /// Drives with the OBS recording folder and the folder of the last saved
+    /// replay marked; the replay buffer saves into the recording path unless
+    /// the profile says otherwise.
+    fn collect_disks(&mut self) -> Vec<DiskFrame> {
+        self.disks.refresh();
+        let replay_dir = self
+            .last_replay_path
+            .as_deref()
+            .and_then(|p| std::path::Path::new(p).parent())
+            .map(|p| p.to_string_lossy().into_owned());
+        let mut uses = Vec::new();
+        if let Some((_, dir)) = &self.record_dir {
+            uses.push(("recording", dir.as_str()));
+            if replay_dir.is_none() {
+                uses.push(("replay", dir.as_str()));
+            }
+        }
+        if let Some(dir) = &replay_dir {
+            uses.push(("replay", dir.as_str()));
+        }
+        disks::collect_disks(&self.disks, &uses)
+    }
 
-        (util, temp)
+    /// Returns all GPUs plus the primary GPU's utilization/temperature, which the
+    /// single-GPU fields (`gpu_percent`, `gpu_temp_c`) keep reporting.
+    fn collect_gpu(&mut self) -> (Vec<GpuFrame>, Option<f32>, Option<f32>) {
+        let gpus = gpu::collect_gpus(self.nvml.as_ref());
+        let primary = gpus
+            .iter()
+            .find(|g| g.utilization_percent.is_some())
+            .or(gpus.first());
+        let util = primary.and_then(|g| g.utilization_percent);
+        let temp = primary.and_then(|g| g.temp_c);
+        (gpus, util, temp)
     }
 
     async fn collect_latency(&self) -> f32 {
@@ -326,19 +377,86 @@ impl MetricsHub {
             _ => 0.0,
         }
     }
+}
+
+/// Reads the scene list (program/preview scene plus each scene's items) from a connected OBS.
+pub async fn collect_scene_inventory(client: &ObsClient) -> Result<SceneInventory, obws::error::Error> {
+    let list = client.scenes().list().await?;
+
+    let mut scenes = Vec::with_capacity(list.scenes.len());
+    for scene in list.scenes {
+        let items = client.scene_items().list(scene.id.name.as_str().into()).await?;
+        let sources = items
+            .into_iter()
+            .map(|item| SceneSource {
+                item_id: item.id,
+                name: item.source_name,
+                source_type: source_type_name(item.source_type).to_string(),
+                input_kind: item.input_kind,
+                is_group: item.is_group.unwrap_or(false),
+            })
+            .collect();
+        scenes.push(SceneEntry {
+            name: scene.id.name,
+            index: scene.index,
+            sources,
+        });
+    }
+    // OBS reports the bottom-most scene as index 0; list them top-down like the OBS UI.
+    scenes.sort_by_key(|s| std::cmp::Reverse(s.index));
+
+    Ok(SceneInventory {
+        current_program_scene: list.current_program_scene.map(|s| s.name),
+        current_preview_scene: list.current_preview_scene.map(|s| s.name),
+        scenes,
+    })
+}
+
+fn source_type_name(source_type: SourceType) -> &'static str {
+    match source_type {
+        SourceType::Input => "input",
+        SourceType::Filter => "filter",
+        SourceType::Transition => "transition",
+        SourceType::Scene => "scene",
+        _ => "unknown",
+    }
+}
+
+/// How long the collection loop should wait after producing `last`.
+pub fn collection_interval(
+    config: &MetricsConfig,
+    last: &TelemetryFrame,
+    has_consumers: bool,
+) -> Duration {
+    let idle = !last.obs.streaming && (!last.obs.connected || !has_consumers);
+    if config.adaptive && idle {
+        Duration::from_millis(config.idle_interval_ms)
+    } else {
+        Duration::from_millis(config.interval_ms)
+    }
+}
 
-    fn refresh_obs_process(&mut self) {
-        if self.last_process_check.elapsed() < Duration::from_secs(2) {
+/// Marks the `pinned` adapters, else the default-route one, else every
+/// adapter except loopback, so the totals never silently read zero because a
+/// route could not be detected.
+fn mark_measured(interfaces: &mut [InterfaceFrame], pinned: &[String], route: Option<&str>) {
+    if !pinned.is_empty() {
+        for iface in interfaces.iter_mut() {
+            iface.measured = pinned.iter().any(|p| p.eq_ignore_ascii_case(&iface.name));
+        }
+        return;
+    }
+    if let Some(route) = route {
+        for iface in interfaces.iter_mut() {
+            iface.measured = iface.name == route;
+        }
+        if interfaces.iter().any(|i| i.measured) {
             return;
         }
-        self.last_process_check = Instant::now();
-        self.sys.refresh_processes();
-        let target = self.obs_process_name.to_lowercase();
-        self.obs_process_running = self
-            .sys
-            .processes()
-            .values()
-            .any(|p| p.name().to_lowercase() == target);
+    }
+    for iface in interfaces.iter_mut() {
+        let lower = iface.name.to_ascii_lowercase();
+        iface.measured = !(lower == "lo" || lower == "lo0" || lower.starts_with("loopback"));
     }
 }
 
@@ -350,3 +468,59 @@ fn compute_health(outputs: &[StreamOutput]) -> f32 {
     let health = 1.0 - avg_drop;
     health.clamp(0.0, 1.0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_pinned_then_default_route_then_all_but_loopback() {
+        let names = ["eth0", "lo", "tailscale0", "wlan0"];
+        let mut interfaces: Vec<InterfaceFrame> = names
+            .iter()
+            .map(|name| InterfaceFrame {
+                name: name.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        let measured = |interfaces: &[InterfaceFrame]| -> Vec<String> {
+            interfaces
+                .iter()
+                .filter(|i| i.measured)
+                .map(|i| i.name.clone())
+                .collect()
+        };
+
+        mark_measured(&mut interfaces, &["WLAN0".to_string()], Some("eth0"));
+        assert_eq!(measured(&interfaces), ["wlan0"]);
+        mark_measured(&mut interfaces, &[], Some("eth0"));
+        assert_eq!(measured(&interfaces), ["eth0"]);
+        mark_measured(&mut interfaces, &[], Some("ppp0"));
+        assert_eq!(measured(&interfaces), ["eth0", "tailscale0", "wlan0"]);
+    }
+
+    #[test]
+    fn adaptive_interval_slows_only_when_idle() {
+        let config = MetricsConfig::default();
+        let mut frame = TelemetryFrame::default();
+        let normal = Duration::from_millis(config.interval_ms);
+        let idle = Duration::from_millis(config.idle_interval_ms);
+
+        // OBS closed: slow even with a dashboard attached.
+        assert_eq!(collection_interval(&config, &frame, true), idle);
+
+        frame.obs.connected = true;
+        assert_eq!(collection_interval(&config, &frame, true), normal);
+        assert_eq!(collection_interval(&config, &frame, false), idle);
+
+        frame.obs.streaming = true;
+        assert_eq!(collection_interval(&config, &frame, false), normal);
+
+        let fixed = MetricsConfig {
+            adaptive: false,
+            ..MetricsConfig::default()
+        };
+        frame = TelemetryFrame::default();
+        assert_eq!(collection_interval(&fixed, &frame, false), normal);
+    }
+}
@@ -0,0 +1,398 @@
+use super::audio::{self, AudioMeters};
+use super::scene_history::{self, SceneHistory};
+use super::scene_list::{self, SceneList};
+use crate::model::ObsConnectionState;
+use crate::security::SecretString;
+use obws::client::{ConnectConfig, DEFAULT_BROADCAST_CAPACITY};
+use obws::requests::profiles::SetParameter;
+use obws::requests::scene_items::{Id as SceneItemId, SetEnabled};
+use obws::requests::EventSubscription;
+use obws::Client as ObsClient;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use sysinfo::System;
+use tokio::sync::Notify;
+use utoipa::ToSchema;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+const PROCESS_POLL: Duration = Duration::from_secs(2);
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(3);
+const RESTART_ATTEMPTS: u32 = 10;
+
+pub struct ObsLinkSettings {
+    pub host: String,
+    pub port: u16,
+    pub password: Option<SecretString>,
+    pub auto_detect: bool,
+    pub process_name: String,
+}
+
+#[derive(Default)]
+struct LinkState {
+    client: Option<Arc<ObsClient>>,
+    state: ObsConnectionState,
+    last_error: Option<String>,
+}
+
+/// Output controls exposed over REST and IPC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ObsControlAction {
+    ReplayBufferStart,
+    ReplayBufferStop,
+    ReplayBufferSave,
+    VirtualcamStart,
+    VirtualcamStop,
+}
+
+impl ObsControlAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::ReplayBufferStart => "replay_buffer_start",
+            Self::ReplayBufferStop => "replay_buffer_stop",
+            Self::ReplayBufferSave => "replay_buffer_save",
+            Self::VirtualcamStart => "virtualcam_start",
+            Self::VirtualcamStop => "virtualcam_stop",
+        }
+    }
+}
+
+/// The OBS WebSocket connection, owned by a dedicated task that reconnects with
+/// exponential backoff so a slow or failing connect never stalls collection.
+/// Clones share the same connection.
+#[derive(Clone)]
+pub struct ObsLink {
+    shared: Arc<Mutex<LinkState>>,
+    lost: Arc<Notify>,
+    audio: Arc<AudioMeters>,
+    scenes: Arc<SceneHistory>,
+    scene_list: Arc<SceneList>,
+}
+
+impl ObsLink {
+    pub fn spawn(settings: ObsLinkSettings) -> Self {
+        let link = Self {
+            shared: Arc::new(Mutex::new(LinkState::default())),
+            lost: Arc::new(Notify::new()),
+            audio: Arc::new(AudioMeters::default()),
+            scenes: Arc::new(SceneHistory::default()),
+            scene_list: Arc::new(SceneList::default()),
+        };
+        tokio::spawn(run_connector(
+            settings,
+            link.shared.clone(),
+            link.lost.clone(),
+            link.audio.clone(),
+            link.scenes.clone(),
+            link.scene_list.clone(),
+        ));
+        link
+    }
+
+    /// A link that never connects, for exercising callers without OBS.
+    #[cfg(test)]
+    pub fn detached() -> Self {
+        Self {
+            shared: Arc::new(Mutex::new(LinkState::default())),
+            lost: Arc::new(Notify::new()),
+            audio: Arc::new(AudioMeters::default()),
+            scenes: Arc::new(SceneHistory::default()),
+            scene_list: Arc::new(SceneList::default()),
+        }
+    }
+
+    pub fn client(&self) -> Option<Arc<ObsClient>> {
+        self.shared.lock().unwrap().client.clone()
+    }
+
+    pub fn status(&self) -> (ObsConnectionState, Option<String>) {
+        let shared = self.shared.lock().unwrap();
+        (shared.state, shared.last_error.clone())
+    }
+
+    pub fn audio(&self) -> &AudioMeters {
+        &self.audio
+    }
+
+    pub fn scene_history(&self) -> &SceneHistory {
+        &self.scenes
+    }
+
+    pub fn scene_list(&self) -> &SceneList {
+        &self.scene_list
+    }
+
+    pub async fn control(&self, action: ObsControlAction) -> Result<(), String> {
+        let client = self.connected()?;
+        timed(async {
+            match action {
+                ObsControlAction::ReplayBufferStart => client.replay_buffer().start().await,
+                ObsControlAction::ReplayBufferStop => client.replay_buffer().stop().await,
+                ObsControlAction::ReplayBufferSave => client.replay_buffer().save().await,
+                ObsControlAction::VirtualcamStart => client.virtual_cam().start().await,
+                ObsControlAction::VirtualcamStop => client.virtual_cam().stop().await,
+            }
+        })
+        .await
+    }
+
+    pub async fn pause_recording(&self) -> Result<(), String> {
+        let client = self.connected()?;
+        timed(client.recording().pause()).await
+    }
+
+    /// Points recordings at `directory`; a running recording is stopped and a
+    /// new one started there.
+    pub async fn move_recording(&self, directory: &str) -> Result<(), String> {
+        let client = self.connected()?;
+        let active = timed(client.recording().status()).await?.active;
+        if active {
+            timed(client.recording().stop()).await?;
+        }
+        timed(client.config().set_record_directory(directory)).await?;
+        if !active {
+            return Ok(());
+        }
+        // StopRecord answers before the output has fully shut down.
+        let mut attempts = 0;
+        loop {
+            match timed(client.recording().start()).await {
+                Ok(()) => return Ok(()),
+                Err(_) if attempts < RESTART_ATTEMPTS => {
+                    attempts += 1;
+                    tokio::time::sleep(Duration::from_millis(500)).await;
+                }
+                Err(err) => return Err(format!("recording stopped but did not restart: {err}")),
+            }
+        }
+    }
+
+    pub async fn switch_scene(&self, scene: &str) -> Result<(), String> {
+        let client = self.connected()?;
+        timed(client.scenes().set_current_program_scene(scene)).await
+    }
+
+    /// The streaming video bitrate of the active profile. Only simple output
+    /// mode keeps it in the profile; advanced mode stores it in the encoder
+    /// settings, which OBS WebSocket does not expose.
+    pub async fn stream_bitrate(&self) -> Result<u32, String> {
+        let client = self.connected()?;
+        let mode = timed(client.profiles().parameter("Output", "Mode")).await?;
+        let mode = mode.value.or(mode.default_value).unwrap_or_default();
+        if !mode.eq_ignore_ascii_case("simple") {
+            return Err(format!(
+                "output mode is {mode:?}; bitrate control needs simple output mode"
+            ));
+        }
+        let bitrate = timed(client.profiles().parameter("SimpleOutput", "VBitrate")).await?;
+        bitrate
+            .value
+            .or(bitrate.default_value)
+            .and_then(|v| v.trim().parse().ok())
+            .ok_or_else(|| "OBS did not report a streaming bitrate".to_string())
+    }
+
+    /// Writes the profile's simple output bitrate. OBS reads it when a stream
+    /// starts, so an encoder that is already streaming keeps its bitrate and
+    /// the new one applies from the next start.
+    pub async fn set_stream_bitrate(&self, kbps: u32) -> Result<(), String> {
+        let client = self.connected()?;
+        let value = kbps.to_string();
+        timed(client.profiles().set_parameter(SetParameter {
+            category: "SimpleOutput",
+            name: "VBitrate",
+            value: Some(&value),
+        }))
+        .await
+    }
+
+    /// Shows or hides `source` in `scene`; `None` flips its current state.
+    /// Returns the new state.
+    pub async fn set_source_enabled(
+        &self,
+        scene: &str,
+        source: &str,
+        enabled: Option<bool>,
+    ) -> Result<bool, String> {
+        let client = self.connected()?;
+        timed(async {
+            let item_id = client
+                .scene_items()
+                .id(SceneItemId {
+                    scene: scene.into(),
+                    source,
+                    search_offset: None,
+                })
+                .await?;
+            let enabled = match enabled {
+                Some(enabled) => enabled,
+                None => !client.scene_items().enabled(scene.into(), item_id).await?,
+            };
+            client
+                .scene_items()
+                .set_enabled(SetEnabled {
+                    scene: scene.into(),
+                    item_id,
+                    enabled,
+                })
+                .await
+                .map(|()| enabled)
+        })
+        .await
+    }
+
+    fn connected(&self) -> Result<Arc<ObsClient>, String> {
+        self.client()
+            .ok_or_else(|| "OBS is not connected".to_string())
+    }
+
+    /// A request on the current client failed: drop it and let the connector retry.
+    pub fn report_failure(&self, err: impl Display) {
+        let mut shared = self.shared.lock().unwrap();
+        if shared.client.take().is_some() {
+            shared.state = ObsConnectionState::Disconnected;
+            shared.last_error = Some(err.to_string());
+            tracing::warn!("lost connection to OBS: {}", err);
+            self.lost.notify_one();
+        }
+    }
+}
+
+async fn run_connector(
+    settings: ObsLinkSettings,
+    shared: Arc<Mutex<LinkState>>,
+    lost: Arc<Notify>,
+    audio: Arc<AudioMeters>,
+    scenes: Arc<SceneHistory>,
+    scene_list: Arc<SceneList>,
+) {
+    let mut sys = System::new();
+    let mut backoff = INITIAL_BACKOFF;
+    loop {
+        if shared.lock().unwrap().client.is_some() {
+            lost.notified().await;
+            continue;
+        }
+
+        if settings.auto_detect && !obs_process_running(&mut sys, &settings.process_name) {
+            {
+                let mut shared = shared.lock().unwrap();
+                shared.state = ObsConnectionState::NotRunning;
+                shared.last_error = None;
+            }
+            backoff = INITIAL_BACKOFF;
+            tokio::time::sleep(PROCESS_POLL).await;
+            continue;
+        }
+
+        shared.lock().unwrap().state = ObsConnectionState::Connecting;
+        tracing::debug!(
+            "Attempting to connect to OBS at {}:{} {}",
+            settings.host,
+            settings.port,
+            if settings.password.is_some() {
+                "with password"
+            } else {
+                "without password"
+            }
+        );
+
+        // Volume meters are a high-volume event and have to be requested explicitly.
+        let connect = ObsClient::connect_with_config(ConnectConfig {
+            host: settings.host.as_str(),
+            port: settings.port,
+            password: settings.password.as_ref().map(SecretString::expose),
+            event_subscriptions: Some(
+                EventSubscription::ALL | EventSubscription::INPUT_VOLUME_METERS,
+            ),
+            broadcast_capacity: DEFAULT_BROADCAST_CAPACITY,
+            connect_timeout: CONNECT_TIMEOUT,
+            dangerous: None,
+        });
+        let result = match tokio::time::timeout(CONNECT_TIMEOUT, connect).await {
+            Ok(result) => result.map_err(|e| e.to_string()),
+            Err(_) => Err(format!(
+                "connect timed out after {}s",
+                CONNECT_TIMEOUT.as_secs()
+            )),
+        };
+
+        match result {
+            Ok(client) => {
+                tracing::info!(
+                    "Successfully connected to OBS at {}:{}",
+                    settings.host,
+                    settings.port
+                );
+                let client = Arc::new(client);
+                tokio::spawn(audio::watch(Arc::downgrade(&client), audio.clone()));
+                tokio::spawn(scene_history::watch(
+                    Arc::downgrade(&client),
+                    scenes.clone(),
+                ));
+                tokio::spawn(scene_list::watch(
+                    Arc::downgrade(&client),
+                    scene_list.clone(),
+                ));
+                let mut shared = shared.lock().unwrap();
+                shared.client = Some(client);
+                shared.state = ObsConnectionState::Connected;
+                shared.last_error = None;
+                backoff = INITIAL_BACKOFF;
+            }
+            Err(error_msg) => {
+                crate::selfstats::stats().obs_connect_failed();
+                let auth_failed = error_msg.contains("handshake");
+                if auth_failed {
+                    tracing::warn!(
+                        "Failed to connect to OBS at {}:{}: Authentication handshake failed. \
+                        This usually means the password is incorrect or OBS WebSocket server requires authentication. \
+                        Error: {}",
+                        settings.host, settings.port, error_msg
+                    );
+                } else {
+                    tracing::warn!(
+                        "Failed to connect to OBS at {}:{}: {} (retrying in {}s)",
+                        settings.host,
+                        settings.port,
+                        error_msg,
+                        backoff.as_secs()
+                    );
+                }
+                {
+                    let mut shared = shared.lock().unwrap();
+                    shared.state = if auth_failed {
+                        ObsConnectionState::AuthFailed
+                    } else {
+                        ObsConnectionState::Disconnected
+                    };
+                    shared.last_error = Some(error_msg);
+                }
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        }
+    }
+}
+
+/// Bounds a control request so a wedged OBS cannot hang the caller.
+async fn timed<T>(
+    request: impl std::future::Future<Output = Result<T, obws::error::Error>>,
+) -> Result<T, String> {
+    match tokio::time::timeout(CONTROL_TIMEOUT, request).await {
+        Ok(result) => result.map_err(|e| e.to_string()),
+        Err(_) => Err("OBS did not respond in time".to_string()),
+    }
+}
+
+pub fn obs_process_running(sys: &mut System, process_name: &str) -> bool {
+    sys.refresh_processes();
+    let target = process_name.to_lowercase();
+    sys.processes()
+        .values()
+        .any(|p| p.name().to_lowercase() == target)
+}
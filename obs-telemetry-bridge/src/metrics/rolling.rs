@@ -0,0 +1,180 @@
+use crate::model::StreamStats;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+const SHORT_WINDOW: Duration = Duration::from_secs(60);
+const LONG_WINDOW: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    at: Instant,
+    bytes: u64,
+    dropped: u64,
+    frame_time_ms: f32,
+}
+
+/// What one collection saw of the main stream output.
+#[derive(Debug, Clone, Copy)]
+pub struct StreamSample {
+    /// Bytes sent since the output started.
+    pub bytes: u64,
+    /// Frames skipped since the stream started.
+    pub dropped: u64,
+    pub frame_time_ms: f32,
+}
+
+/// The last five minutes of the main stream output. OBS only reports totals
+/// since the output started, so rates come from the difference between
+/// samples; a fresh stream (counters going backwards) starts over.
+#[derive(Debug, Default)]
+pub struct Rolling {
+    samples: VecDeque<Sample>,
+}
+
+impl Rolling {
+    /// Records `sample` (`None` while not streaming) and returns the stats.
+    pub fn observe(&mut self, at: Instant, sample: Option<StreamSample>) -> StreamStats {
+        let Some(sample) = sample else {
+            self.samples.clear();
+            return StreamStats::default();
+        };
+        let restarted = self
+            .samples
+            .back()
+            .is_some_and(|last| sample.bytes < last.bytes || sample.dropped < last.dropped);
+        if restarted {
+            self.samples.clear();
+        }
+        self.samples.push_back(Sample {
+            at,
+            bytes: sample.bytes,
+            dropped: sample.dropped,
+            frame_time_ms: sample.frame_time_ms,
+        });
+        while self
+            .samples
+            .front()
+            .is_some_and(|s| at.duration_since(s.at) > LONG_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+        self.stats(at)
+    }
+
+    /// Samples from the last `window`, oldest first.
+    fn window(&self, at: Instant, window: Duration) -> Vec<Sample> {
+        self.samples
+            .iter()
+            .filter(|s| at.duration_since(s.at) <= window)
+            .copied()
+            .collect()
+    }
+
+    fn stats(&self, at: Instant) -> StreamStats {
+        let short = self.window(at, SHORT_WINDOW);
+        let rates: Vec<f32> = short
+            .windows(2)
+            .filter_map(|w| kbps(&w[0], &w[1]))
+            .collect();
+        let (variance, stability) = match rates.len() {
+            0 | 1 => (None, None),
+            n => {
+                let mean = rates.iter().sum::<f32>() / n as f32;
+                let variance = rates.iter().map(|r| (r - mean).powi(2)).sum::<f32>() / n as f32;
+                let stability = if mean > 0.0 {
+                    (1.0 - variance.sqrt() / mean).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                (Some(variance), Some(stability))
+            }
+        };
+        let drops_per_min = match (short.first(), short.last()) {
+            (Some(first), Some(last)) if last.at > first.at => {
+                let minutes = last.at.duration_since(first.at).as_secs_f32() / 60.0;
+                Some((last.dropped - first.dropped) as f32 / minutes)
+            }
+            _ => None,
+        };
+
+        StreamStats {
+            avg_bitrate_1m_kbps: span_kbps(&short),
+            avg_bitrate_5m_kbps: span_kbps(&self.window(at, LONG_WINDOW)),
+            bitrate_variance: variance,
+            bitrate_stability: stability,
+            drops_per_min,
+            frame_time_p95_ms: p95(short.iter().map(|s| s.frame_time_ms).collect()),
+        }
+    }
+}
+
+fn kbps(from: &Sample, to: &Sample) -> Option<f32> {
+    let secs = to.at.duration_since(from.at).as_secs_f32();
+    (secs > 0.0).then(|| (to.bytes - from.bytes) as f32 * 8.0 / secs / 1000.0)
+}
+
+fn span_kbps(samples: &[Sample]) -> Option<f32> {
+    kbps(samples.first()?, samples.last()?)
+}
+
+/// Nearest-rank 95th percentile.
+fn p95(mut values: Vec<f32>) -> Option<f32> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f32::total_cmp);
+    let rank = (values.len() as f32 * 0.95).ceil() as usize;
+    Some(values[rank.max(1) - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(bytes: u64, dropped: u64, frame_time_ms: f32) -> Option<StreamSample> {
+        Some(StreamSample {
+            bytes,
+            dropped,
+            frame_time_ms,
+        })
+    }
+
+    #[test]
+    fn steady_stream_is_stable() {
+        let start = Instant::now();
+        let mut rolling = Rolling::default();
+        let mut stats = StreamStats::default();
+        // 6000 kbps = 750_000 bytes/s, one sample per second for two minutes.
+        for i in 0..=120u64 {
+            let at = start + Duration::from_secs(i);
+            stats = rolling.observe(at, sample(i * 750_000, i / 10, 4.0 + (i % 20) as f32));
+        }
+        assert_eq!(stats.avg_bitrate_1m_kbps, Some(6000.0));
+        assert_eq!(stats.avg_bitrate_5m_kbps, Some(6000.0));
+        assert_eq!(stats.bitrate_variance, Some(0.0));
+        assert_eq!(stats.bitrate_stability, Some(1.0));
+        assert_eq!(stats.drops_per_min, Some(6.0));
+        assert_eq!(stats.frame_time_p95_ms, Some(22.0));
+    }
+
+    #[test]
+    fn uneven_stream_loses_stability_and_restarts_reset() {
+        let start = Instant::now();
+        let mut rolling = Rolling::default();
+        let mut bytes = 0;
+        let mut stats = StreamStats::default();
+        for i in 0..10u64 {
+            bytes += if i % 2 == 0 { 1_000_000 } else { 250_000 };
+            stats = rolling.observe(start + Duration::from_secs(i), sample(bytes, 0, 4.0));
+        }
+        assert!(stats.bitrate_variance.unwrap() > 0.0);
+        assert!(stats.bitrate_stability.unwrap() < 0.7);
+
+        let stats = rolling.observe(start + Duration::from_secs(10), sample(1_000, 0, 4.0));
+        assert_eq!(stats.avg_bitrate_1m_kbps, None, "new stream starts over");
+        assert_eq!(
+            rolling.observe(start + Duration::from_secs(11), None),
+            StreamStats::default()
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const REFRESH: Duration = Duration::from_secs(60);
+
+/// The interface carrying the default route, re-read in the background since
+/// on Windows that takes a PowerShell call.
+#[derive(Clone, Default)]
+pub struct DefaultRoute {
+    interface: Arc<Mutex<Option<String>>>,
+}
+
+impl DefaultRoute {
+    pub fn spawn() -> Self {
+        let route = Self::default();
+        let shared = route.interface.clone();
+        let spawned = std::thread::Builder::new()
+            .name("telemy-route".to_string())
+            .spawn(move || loop {
+                let detected = detect();
+                let mut current = shared.lock().unwrap();
+                if *current != detected {
+                    tracing::info!(interface = ?detected, "default route interface");
+                    *current = detected;
+                }
+                drop(current);
+                std::thread::sleep(REFRESH);
+            });
+        if let Err(err) = spawned {
+            tracing::warn!(error = %err, "default route detection unavailable");
+        }
+        route
+    }
+
+    pub fn interface(&self) -> Option<String> {
+        self.interface.lock().unwrap().clone()
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn detect() -> Option<String> {
+    parse_proc_route(&std::fs::read_to_string("/proc/net/route").ok()?)
+}
+
+#[cfg(windows)]
+fn detect() -> Option<String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    const SCRIPT: &str = "Get-NetRoute -DestinationPrefix 0.0.0.0/0 | \
+        Sort-Object { $_.RouteMetric + $_.InterfaceMetric } | \
+        Select-Object -First 1 -ExpandProperty InterfaceAlias";
+    let output = std::process::Command::new("powershell")
+        .args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+        .creation_flags(CREATE_NO_WINDOW)
+        .output()
+        .ok()?;
+    let alias = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!alias.is_empty()).then_some(alias)
+}
+
+#[cfg(target_os = "macos")]
+fn detect() -> Option<String> {
+    let output = std::process::Command::new("route")
+        .args(["-n", "get", "default"])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("interface:"))
+        .map(|name| name.trim().to_string())
+}
+
+#[cfg(not(any(windows, target_os = "linux", target_os = "macos")))]
+fn detect() -> Option<String> {
+    None
+}
+
+/// The lowest-metric `0.0.0.0/0` entry of `/proc/net/route`.
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn parse_proc_route(table: &str) -> Option<String> {
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            let (iface, dest, metric) = (cols.first()?, cols.get(1)?, cols.get(6)?);
+            (*dest == "00000000").then(|| (metric.parse::<u32>().unwrap_or(u32::MAX), *iface))
+        })
+        .min()
+        .map(|(_, iface)| iface.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_default_route_from_proc() {
+        let table =
+            "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n\
+            wg0\t0000000A\t00000000\t0001\t0\t0\t0\t000000FF\t0\t0\t0\n\
+            wlan0\t00000000\t0101A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0\n\
+            eth0\t00000000\t0100A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0\n";
+        assert_eq!(parse_proc_route(table).as_deref(), Some("eth0"));
+        assert_eq!(parse_proc_route("Iface\tDestination\n"), None);
+    }
+}
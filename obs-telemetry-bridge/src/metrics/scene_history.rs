@@ -0,0 +1,97 @@
+use futures_util::StreamExt;
+use obws::events::Event;
+use obws::Client as ObsClient;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const CAPACITY: usize = 500;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SceneChange {
+    pub scene: String,
+    pub at_unix_ms: u64,
+}
+
+/// Program scene changes, newest last, kept across OBS reconnects.
+#[derive(Default)]
+pub struct SceneHistory {
+    changes: Mutex<VecDeque<SceneChange>>,
+}
+
+impl SceneHistory {
+    fn record(&self, scene: String, at_unix_ms: u64) {
+        let mut changes = self.changes.lock().unwrap();
+        if changes.back().is_some_and(|last| last.scene == scene) {
+            return;
+        }
+        if changes.len() == CAPACITY {
+            changes.pop_front();
+        }
+        changes.push_back(SceneChange { scene, at_unix_ms });
+    }
+
+    pub fn current(&self) -> Option<String> {
+        self.changes
+            .lock()
+            .unwrap()
+            .back()
+            .map(|change| change.scene.clone())
+    }
+
+    pub fn snapshot(&self) -> Vec<SceneChange> {
+        self.changes.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+/// Seeds the history with the scene active at connect time, then follows
+/// `CurrentProgramSceneChanged` until the connection closes.
+pub async fn watch(client: Weak<ObsClient>, history: Arc<SceneHistory>) {
+    let Some(events) = client.upgrade().and_then(|c| c.events().ok()) else {
+        return;
+    };
+    futures_util::pin_mut!(events);
+    if let Some(client) = client.upgrade() {
+        if let Ok(current) = client.scenes().current_program_scene().await {
+            history.record(current.id.name, now_unix_ms());
+        }
+    }
+
+    while let Some(event) = events.next().await {
+        if let Event::CurrentProgramSceneChanged { id } = event {
+            history.record(id.name, now_unix_ms());
+        }
+    }
+}
+
+fn now_unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_only_actual_changes_and_stays_bounded() {
+        let history = SceneHistory::default();
+        history.record("Intro".to_string(), 1);
+        history.record("Intro".to_string(), 2);
+        history.record("Game".to_string(), 3);
+        assert_eq!(history.current().as_deref(), Some("Game"));
+        let changes = history.snapshot();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].at_unix_ms, 1);
+
+        for i in 0..CAPACITY as u64 {
+            history.record(format!("Scene {i}"), 10 + i);
+        }
+        let changes = history.snapshot();
+        assert_eq!(changes.len(), CAPACITY);
+        assert_eq!(changes[0].scene, "Scene 0");
+    }
+}
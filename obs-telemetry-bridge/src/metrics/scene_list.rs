@@ -0,0 +1,91 @@
+use futures_util::StreamExt;
+use obws::events::Event;
+use obws::Client as ObsClient;
+use std::sync::{Arc, Mutex, Weak};
+
+/// OBS scene names, so a `switch_scene` for a scene that does not exist is
+/// refused up front instead of timing out in the plugin. Empty while OBS is
+/// not connected, when nothing is refused.
+#[derive(Default)]
+pub struct SceneList {
+    names: Mutex<Option<Vec<String>>>,
+}
+
+impl SceneList {
+    fn replace(&self, names: Option<Vec<String>>) {
+        *self.names.lock().unwrap() = names;
+    }
+
+    /// True only when the list is known and `scene` is not on it.
+    pub fn is_unknown(&self, scene: &str) -> bool {
+        self.names
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|names| !names.iter().any(|name| name == scene))
+    }
+}
+
+/// Loads the list at connect time and again whenever OBS reports scenes
+/// added, removed, renamed or reordered, until the connection closes.
+pub async fn watch(client: Weak<ObsClient>, list: Arc<SceneList>) {
+    let Some(events) = client.upgrade().and_then(|c| c.events().ok()) else {
+        return;
+    };
+    futures_util::pin_mut!(events);
+    refresh(&client, &list).await;
+
+    while let Some(event) = events.next().await {
+        if matches!(
+            event,
+            Event::SceneListChanged { .. }
+                | Event::SceneCreated { .. }
+                | Event::SceneRemoved { .. }
+                | Event::SceneNameChanged { .. }
+        ) {
+            refresh(&client, &list).await;
+        }
+    }
+    list.replace(None);
+}
+
+async fn refresh(client: &Weak<ObsClient>, list: &SceneList) {
+    let Some(client) = client.upgrade() else {
+        return;
+    };
+    match client.scenes().list().await {
+        Ok(scenes) => list.replace(Some(
+            scenes
+                .scenes
+                .into_iter()
+                .map(|scene| scene.id.name)
+                .collect(),
+        )),
+        Err(err) => {
+            tracing::debug!(error = %err, "OBS scene list refresh failed");
+            list.replace(None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_a_known_list_refuses_scenes() {
+        let list = SceneList::default();
+        assert!(!list.is_unknown("Game"), "OBS not connected yet");
+
+        list.replace(Some(vec!["Game".to_string(), "BRB".to_string()]));
+        assert!(!list.is_unknown("Game"));
+        assert!(
+            list.is_unknown("game"),
+            "OBS scene names are case-sensitive"
+        );
+        assert!(list.is_unknown("Intro"));
+
+        list.replace(None);
+        assert!(!list.is_unknown("Intro"));
+    }
+}
@@ -0,0 +1,141 @@
+use crate::config::ConfigHandle;
+use futures_util::FutureExt;
+use std::any::Any;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::time::{Duration, Instant};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// A run at least this long was healthy, so the next restart starts from the
+/// initial backoff again.
+const STABLE_RUN: Duration = Duration::from_secs(60);
+/// Restarts within `ALERT_WINDOW` that raise an alert.
+const ALERT_RESTARTS: usize = 3;
+const ALERT_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// Backoff and alerting for a task that keeps dying.
+#[derive(Debug, Default)]
+struct Restarts {
+    backoff: Option<Duration>,
+    recent: VecDeque<Instant>,
+}
+
+impl Restarts {
+    /// Records a restart after a run of `ran_for`. Returns how long to wait
+    /// before the next run and whether this restart makes `ALERT_RESTARTS`
+    /// within the window, which happens once per burst.
+    fn record(&mut self, now: Instant, ran_for: Duration) -> (Duration, bool) {
+        if ran_for >= STABLE_RUN {
+            self.backoff = None;
+        }
+        let backoff = self
+            .backoff
+            .map_or(INITIAL_BACKOFF, |b| (b * 2).min(MAX_BACKOFF));
+        self.backoff = Some(backoff);
+        self.recent
+            .retain(|at| now.duration_since(*at) < ALERT_WINDOW);
+        self.recent.push_back(now);
+        (backoff, self.recent.len() == ALERT_RESTARTS)
+    }
+}
+
+/// Runs the future `run` builds, and builds a new one with backoff whenever
+/// it panics or returns. Each restart is counted in selfstats; repeated
+/// restarts are alerted.
+pub async fn supervise<F, Fut>(config: ConfigHandle, mut run: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mut restarts = Restarts::default();
+    loop {
+        let started = Instant::now();
+        let reason = match AssertUnwindSafe(run()).catch_unwind().await {
+            Ok(()) => "the task returned".to_string(),
+            Err(panic) => panic_message(panic.as_ref()),
+        };
+        crate::selfstats::stats().metrics_task_restarted();
+        let (backoff, alert) = restarts.record(Instant::now(), started.elapsed());
+        tracing::error!(
+            reason = %reason,
+            retry_in_ms = backoff.as_millis() as u64,
+            "metrics task died; restarting"
+        );
+        if alert {
+            let notifications = config.read(|c| c.notifications.clone());
+            let message = format!(
+                "Telemetry collection restarted {ALERT_RESTARTS} times in {} minutes: {reason}",
+                ALERT_WINDOW.as_secs() / 60
+            );
+            crate::notify::alert(&notifications, &message);
+        }
+        tokio::time::sleep(backoff).await;
+    }
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panic".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_resets_after_a_stable_run_and_alerts_once_per_burst() {
+        let mut restarts = Restarts::default();
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let quick = Duration::from_secs(1);
+
+        assert_eq!(restarts.record(at(0), quick), (INITIAL_BACKOFF, false));
+        assert_eq!(
+            restarts.record(at(2), quick),
+            (Duration::from_secs(2), false)
+        );
+        assert_eq!(
+            restarts.record(at(5), quick),
+            (Duration::from_secs(4), true)
+        );
+        assert_eq!(
+            restarts.record(at(10), quick),
+            (Duration::from_secs(8), false)
+        );
+
+        let (backoff, alert) = restarts.record(at(200), STABLE_RUN);
+        assert_eq!(backoff, INITIAL_BACKOFF);
+        assert!(!alert, "the burst was already alerted");
+
+        let later = at(2000);
+        assert!(!restarts.record(later, quick).1);
+        for _ in 0..10 {
+            restarts.record(later, quick);
+        }
+        assert_eq!(restarts.backoff, Some(MAX_BACKOFF));
+    }
+
+    #[tokio::test]
+    async fn panics_are_caught_and_the_task_is_rebuilt() {
+        let handle = ConfigHandle::new(crate::config::Config::default());
+        let runs = std::sync::atomic::AtomicU32::new(0);
+        let before = crate::selfstats::stats().metrics_restarts();
+        let supervised = supervise(handle, || {
+            let run = runs.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async move {
+                if run == 0 {
+                    panic!("collector exploded");
+                }
+                std::future::pending::<()>().await
+            }
+        });
+        let _ = tokio::time::timeout(Duration::from_millis(1500), supervised).await;
+        assert_eq!(runs.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert!(crate::selfstats::stats().metrics_restarts() > before);
+    }
+}
@@ -0,0 +1,186 @@
+use crate::model::{HealthTrend, TelemetryFrame, TrendState};
+
+/// Smoothing of the fast and slow averages; the fast one follows about the
+/// last 3 samples, the slow one the last 20.
+const FAST: f32 = 0.3;
+const SLOW: f32 = 0.05;
+/// Samples before the averages are trusted.
+const WARMUP: u32 = 10;
+/// Fast drop fraction this far above the slow one reads as a trend.
+const DROP_MARGIN: f32 = 0.005;
+/// RTT moves count once they exceed this, or a fifth of the usual RTT.
+const RTT_MARGIN_MS: f32 = 15.0;
+
+#[derive(Debug, Clone, Copy)]
+struct Ewma {
+    fast: f32,
+    slow: f32,
+}
+
+impl Ewma {
+    fn update(this: &mut Option<Self>, value: f32) {
+        match this {
+            Some(ewma) => {
+                ewma.fast += FAST * (value - ewma.fast);
+                ewma.slow += SLOW * (value - ewma.slow);
+            }
+            None => {
+                *this = Some(Self {
+                    fast: value,
+                    slow: value,
+                })
+            }
+        }
+    }
+
+    fn slope(this: &Option<Self>) -> f32 {
+        this.map_or(0.0, |ewma| ewma.fast - ewma.slow)
+    }
+}
+
+/// Compares a fast and a slow moving average of the main output's drop rate
+/// and of the RTT (the relay's while attached, else the latency probe), so a
+/// stream that is getting worse shows up as `degrading` before it crosses a
+/// hard threshold. Resets whenever the stream stops.
+#[derive(Debug, Default)]
+pub struct TrendAnalyzer {
+    drops: Option<Ewma>,
+    rtt: Option<Ewma>,
+    samples: u32,
+    /// `(total, dropped)` frames at the previous sample.
+    last_counts: Option<(u64, u64)>,
+}
+
+impl TrendAnalyzer {
+    pub fn observe(&mut self, frame: &TelemetryFrame) -> HealthTrend {
+        if !frame.obs.streaming {
+            *self = Self::default();
+            return HealthTrend::default();
+        }
+
+        // OBS counts frames since the stream started; the drop rate of this
+        // interval reacts far sooner than the running `drop_pct`.
+        let counts = (frame.obs.total_frames, frame.obs.total_dropped_frames);
+        if let Some((total, dropped)) = self.last_counts.replace(counts) {
+            if counts.0 > total && counts.1 >= dropped {
+                let rate = (counts.1 - dropped) as f32 / (counts.0 - total) as f32;
+                Ewma::update(&mut self.drops, rate);
+            }
+        }
+        let rtt = match &frame.relay {
+            Some(relay) if relay.connected => relay.rtt_ms,
+            _ => frame.network.latency_ms,
+        };
+        if rtt > 0.0 {
+            Ewma::update(&mut self.rtt, rtt);
+        }
+        self.samples = self.samples.saturating_add(1);
+
+        let drop_slope = Ewma::slope(&self.drops);
+        let rtt_slope = Ewma::slope(&self.rtt);
+        let rtt_margin = self
+            .rtt
+            .map_or(RTT_MARGIN_MS, |ewma| RTT_MARGIN_MS.max(ewma.slow * 0.2));
+        let state = if self.samples < WARMUP {
+            TrendState::Stable
+        } else if drop_slope > DROP_MARGIN || rtt_slope > rtt_margin {
+            TrendState::Degrading
+        } else if drop_slope < -DROP_MARGIN || rtt_slope < -rtt_margin {
+            TrendState::Improving
+        } else {
+            TrendState::Stable
+        };
+        HealthTrend {
+            state,
+            drop_ratio_slope: drop_slope,
+            rtt_slope_ms: rtt_slope,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn streaming(latency_ms: f32) -> TelemetryFrame {
+        let mut frame = TelemetryFrame::default();
+        frame.obs.streaming = true;
+        frame.network.latency_ms = latency_ms;
+        frame
+    }
+
+    /// Advances the frame by one second at 60 fps with `dropped` of them lost.
+    fn tick(frame: &mut TelemetryFrame, dropped: u64) {
+        frame.obs.total_frames += 60;
+        frame.obs.total_dropped_frames += dropped;
+    }
+
+    #[test]
+    fn rising_drops_degrade_before_the_threshold() {
+        let mut analyzer = TrendAnalyzer::default();
+        let mut frame = streaming(40.0);
+        for _ in 0..20 {
+            tick(&mut frame, 0);
+            assert_eq!(analyzer.observe(&frame).state, TrendState::Stable);
+        }
+
+        // One frame in 60 is 1.7%, under the default 2% notification threshold.
+        tick(&mut frame, 1);
+        analyzer.observe(&frame);
+        tick(&mut frame, 1);
+        assert_eq!(analyzer.observe(&frame).state, TrendState::Degrading);
+
+        tick(&mut frame, 0);
+        analyzer.observe(&frame);
+        tick(&mut frame, 0);
+        assert_eq!(
+            analyzer.observe(&frame).state,
+            TrendState::Stable,
+            "back within the margin"
+        );
+
+        frame.obs.streaming = false;
+        assert_eq!(analyzer.observe(&frame), HealthTrend::default());
+    }
+
+    #[test]
+    fn rtt_climbs_degrade_and_recoveries_improve() {
+        let mut analyzer = TrendAnalyzer::default();
+        let mut frame = streaming(40.0);
+        for _ in 0..5 {
+            tick(&mut frame, 0);
+            analyzer.observe(&frame);
+        }
+        frame.network.latency_ms = 140.0;
+        tick(&mut frame, 0);
+        assert_eq!(
+            analyzer.observe(&frame).state,
+            TrendState::Stable,
+            "still warming up"
+        );
+
+        let mut analyzer = TrendAnalyzer::default();
+        frame.network.latency_ms = 40.0;
+        for _ in 0..20 {
+            tick(&mut frame, 0);
+            analyzer.observe(&frame);
+        }
+        frame.network.latency_ms = 140.0;
+        tick(&mut frame, 0);
+        let trend = analyzer.observe(&frame);
+        assert_eq!(trend.state, TrendState::Degrading);
+        assert!(trend.rtt_slope_ms > RTT_MARGIN_MS);
+
+        for _ in 0..20 {
+            tick(&mut frame, 0);
+            analyzer.observe(&frame);
+        }
+        frame.network.latency_ms = 40.0;
+        let mut state = TrendState::Stable;
+        for _ in 0..3 {
+            tick(&mut frame, 0);
+            state = analyzer.observe(&frame).state;
+        }
+        assert_eq!(state, TrendState::Improving);
+    }
+}
@@ -0,0 +1,264 @@
+use crate::config::UplinkConfig;
+use crate::model::{BondFrame, InterfaceFrame, UplinkFrame};
+use std::collections::VecDeque;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::TcpSocket;
+use tokio::task::JoinHandle;
+
+const PROBE_INTERVAL: Duration = Duration::from_secs(1);
+/// Longer than the main latency probe: a loaded LTE link easily takes 300 ms.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(1);
+/// Probes loss is computed over, about half a minute.
+const WINDOW: usize = 30;
+/// Recent probes latency is averaged over.
+const LATENCY_SAMPLES: usize = 5;
+
+/// Outcomes of the latest probes, newest last; `None` is a lost probe.
+#[derive(Debug, Default)]
+struct ProbeWindow {
+    results: VecDeque<Option<f32>>,
+}
+
+impl ProbeWindow {
+    fn push(&mut self, result: Option<f32>) {
+        if self.results.len() == WINDOW {
+            self.results.pop_front();
+        }
+        self.results.push_back(result);
+    }
+
+    fn latency_ms(&self) -> Option<f32> {
+        let recent: Vec<f32> = self
+            .results
+            .iter()
+            .rev()
+            .take(LATENCY_SAMPLES)
+            .flatten()
+            .copied()
+            .collect();
+        (!recent.is_empty()).then(|| recent.iter().sum::<f32>() / recent.len() as f32)
+    }
+
+    fn loss_pct(&self) -> Option<f32> {
+        if self.results.is_empty() {
+            return None;
+        }
+        let lost = self.results.iter().filter(|r| r.is_none()).count();
+        Some(lost as f32 / self.results.len() as f32 * 100.0)
+    }
+
+    fn up(&self) -> bool {
+        self.results.back().is_some_and(Option::is_some)
+    }
+}
+
+struct Uplink {
+    config: UplinkConfig,
+    /// `None` when the link cannot be probed on its own on this platform.
+    probes: Option<Arc<Mutex<ProbeWindow>>>,
+    task: Option<JoinHandle<()>>,
+}
+
+/// The uplinks of a bonded setup, each probed over its own path once a second:
+/// from `ip` when set, else through `interface` (Linux only).
+pub struct Uplinks {
+    links: Vec<Uplink>,
+}
+
+impl Uplinks {
+    pub fn spawn(configs: &[UplinkConfig], default_target: &str) -> Self {
+        let links = configs
+            .iter()
+            .map(|config| {
+                let target = config.probe_target.as_deref().unwrap_or(default_target);
+                let mut task = None;
+                let probes = match (target.parse::<SocketAddr>(), probe_source(config)) {
+                    (Ok(target), Some(source)) => {
+                        let window = Arc::new(Mutex::new(ProbeWindow::default()));
+                        task = Some(tokio::spawn(probe_loop(target, source, window.clone())));
+                        Some(window)
+                    }
+                    (Err(_), _) => {
+                        tracing::warn!(uplink = %config.name, target, "uplink probe target is not ip:port");
+                        None
+                    }
+                    (_, None) => {
+                        tracing::warn!(
+                            uplink = %config.name,
+                            "set uplink ip to measure latency and loss on this platform"
+                        );
+                        None
+                    }
+                };
+                Uplink {
+                    config: config.clone(),
+                    probes,
+                    task,
+                }
+            })
+            .collect();
+        Self { links }
+    }
+
+    /// Each uplink's probe results, with throughput taken from its interface.
+    pub fn frames(&self, interfaces: &[InterfaceFrame]) -> Vec<UplinkFrame> {
+        self.links
+            .iter()
+            .map(|link| {
+                let iface = link.config.interface.as_deref().and_then(|name| {
+                    interfaces
+                        .iter()
+                        .find(|i| i.name.eq_ignore_ascii_case(name))
+                });
+                let window = link.probes.as_ref().map(|p| p.lock().unwrap());
+                UplinkFrame {
+                    name: link.config.name.clone(),
+                    interface: link.config.interface.clone(),
+                    upload_mbps: iface.map(|i| i.upload_mbps),
+                    download_mbps: iface.map(|i| i.download_mbps),
+                    latency_ms: window.as_ref().and_then(|w| w.latency_ms()),
+                    loss_pct: window.as_ref().and_then(|w| w.loss_pct()),
+                    up: match &window {
+                        Some(window) => window.up(),
+                        None => iface.is_some(),
+                    },
+                }
+            })
+            .collect()
+    }
+}
+
+impl Drop for Uplinks {
+    fn drop(&mut self) {
+        for task in self.links.iter().filter_map(|link| link.task.as_ref()) {
+            task.abort();
+        }
+    }
+}
+
+/// The combined view: summed throughput, the best latency among links that
+/// are up and the mean loss.
+pub fn bond(uplinks: &[UplinkFrame]) -> Option<BondFrame> {
+    if uplinks.is_empty() {
+        return None;
+    }
+    let losses: Vec<f32> = uplinks.iter().filter_map(|u| u.loss_pct).collect();
+    Some(BondFrame {
+        links: uplinks.len(),
+        links_up: uplinks.iter().filter(|u| u.up).count(),
+        upload_mbps: uplinks.iter().filter_map(|u| u.upload_mbps).sum(),
+        download_mbps: uplinks.iter().filter_map(|u| u.download_mbps).sum(),
+        latency_ms: uplinks
+            .iter()
+            .filter(|u| u.up)
+            .filter_map(|u| u.latency_ms)
+            .reduce(f32::min),
+        loss_pct: (!losses.is_empty()).then(|| losses.iter().sum::<f32>() / losses.len() as f32),
+    })
+}
+
+#[derive(Debug, Clone)]
+enum ProbeSource {
+    Ip(IpAddr),
+    #[cfg_attr(
+        not(any(target_os = "android", target_os = "fuchsia", target_os = "linux")),
+        allow(dead_code)
+    )]
+    Device(String),
+}
+
+fn probe_source(config: &UplinkConfig) -> Option<ProbeSource> {
+    if let Some(ip) = config.ip.as_deref().and_then(|ip| ip.parse().ok()) {
+        return Some(ProbeSource::Ip(ip));
+    }
+    let interface = config.interface.clone()?;
+    cfg!(any(
+        target_os = "android",
+        target_os = "fuchsia",
+        target_os = "linux"
+    ))
+    .then_some(ProbeSource::Device(interface))
+}
+
+async fn probe_loop(target: SocketAddr, source: ProbeSource, window: Arc<Mutex<ProbeWindow>>) {
+    let mut ticker = tokio::time::interval(PROBE_INTERVAL);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    loop {
+        ticker.tick().await;
+        if crate::pause::is_paused() {
+            continue;
+        }
+        let result = probe(target, &source).await.ok();
+        window.lock().unwrap().push(result);
+    }
+}
+
+/// One TCP connect from `source`, in ms.
+async fn probe(target: SocketAddr, source: &ProbeSource) -> std::io::Result<f32> {
+    let socket = if target.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    match source {
+        ProbeSource::Ip(ip) => socket.bind(SocketAddr::new(*ip, 0))?,
+        #[cfg(any(target_os = "android", target_os = "fuchsia", target_os = "linux"))]
+        ProbeSource::Device(name) => socket.bind_device(Some(name.as_bytes()))?,
+        #[cfg(not(any(target_os = "android", target_os = "fuchsia", target_os = "linux")))]
+        ProbeSource::Device(_) => return Err(std::io::ErrorKind::Unsupported.into()),
+    }
+    let started = Instant::now();
+    let stream = tokio::time::timeout(PROBE_TIMEOUT, socket.connect(target))
+        .await
+        .map_err(|_| std::io::Error::from(std::io::ErrorKind::TimedOut))??;
+    let elapsed = started.elapsed().as_secs_f32() * 1000.0;
+    drop(stream);
+    Ok(elapsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_tracks_latency_and_loss() {
+        let mut window = ProbeWindow::default();
+        assert_eq!((window.latency_ms(), window.loss_pct()), (None, None));
+        for result in [Some(40.0), None, Some(60.0), None] {
+            window.push(result);
+        }
+        assert_eq!(window.latency_ms(), Some(50.0));
+        assert_eq!(window.loss_pct(), Some(50.0));
+        assert!(!window.up());
+        for _ in 0..WINDOW {
+            window.push(Some(30.0));
+        }
+        assert_eq!(window.loss_pct(), Some(0.0));
+        assert!(window.up());
+    }
+
+    #[test]
+    fn bond_combines_the_links() {
+        let link = |name: &str, up: bool, latency: f32, loss: f32| UplinkFrame {
+            name: name.to_string(),
+            interface: None,
+            upload_mbps: Some(5.0),
+            download_mbps: Some(1.0),
+            latency_ms: Some(latency),
+            loss_pct: Some(loss),
+            up,
+        };
+        let bond = bond(&[
+            link("lte1", true, 80.0, 0.0),
+            link("lte2", false, 40.0, 10.0),
+        ])
+        .unwrap();
+        assert_eq!((bond.links, bond.links_up), (2, 1));
+        assert_eq!(bond.upload_mbps, 10.0);
+        assert_eq!(bond.latency_ms, Some(80.0), "only links that are up");
+        assert_eq!(bond.loss_pct, Some(5.0));
+        assert!(super::bond(&[]).is_none());
+    }
+}
@@ -0,0 +1,65 @@
+use crate::config::ConfigHandle;
+use crate::model::TelemetryFrame;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+
+static STALE: AtomicBool = AtomicBool::new(false);
+
+/// Whether the latest frame is older than `metrics.stale_after_secs`, so
+/// consumers holding it are looking at frozen data. Kept process-wide like
+/// the pause flag, since the WebSocket hub and the IPC snapshots both read it.
+pub fn frames_stale() -> bool {
+    STALE.load(Ordering::Relaxed)
+}
+
+/// The empty frame from before the first collection is missing, not stale.
+fn is_stale(timestamp_unix: u64, now_unix: u64, stale_after_secs: u64) -> bool {
+    timestamp_unix > 0 && now_unix.saturating_sub(timestamp_unix) > stale_after_secs
+}
+
+/// Checks the latest frame's `timestamp_unix` every second and flips
+/// [`frames_stale`] when it stops advancing, logging both transitions.
+pub fn spawn_watchdog(config: ConfigHandle, rx: watch::Receiver<TelemetryFrame>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(1));
+        loop {
+            ticker.tick().await;
+            if rx.has_changed().is_err() {
+                return;
+            }
+            let timestamp = rx.borrow().timestamp_unix;
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let stale_after = config.read(|c| c.metrics.stale_after_secs);
+            let stale = is_stale(timestamp, now, stale_after);
+            if STALE.swap(stale, Ordering::Relaxed) == stale {
+                continue;
+            }
+            if stale {
+                tracing::warn!(
+                    age_secs = now.saturating_sub(timestamp),
+                    paused = crate::pause::is_paused(),
+                    "telemetry frames are stale"
+                );
+            } else {
+                tracing::info!("telemetry frames are fresh again");
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_go_stale_once_older_than_the_threshold() {
+        assert!(!is_stale(1_000, 1_015, 15));
+        assert!(is_stale(1_000, 1_016, 15));
+        assert!(!is_stale(0, 1_016, 15), "no frame collected yet");
+        assert!(!is_stale(1_020, 1_016, 15), "clock stepped back");
+    }
+}
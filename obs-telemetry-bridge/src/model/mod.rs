@@ -1,16 +1,74 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Default, Serialize)]
+/// Version of the frame layout in `TelemetryFrame::schema_version`. Adding
+/// fields keeps it; renaming, removing or retyping one bumps it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Fields missing from a serialized frame take their defaults and unknown ones
+/// are ignored, so frames written by other versions still parse.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TelemetryFrame {
+    /// [`SCHEMA_VERSION`] of the writer; 0 for frames recorded before
+    /// versioning.
+    #[serde(default)]
+    pub schema_version: u32,
     pub timestamp_unix: u64,
     pub health: f32,
+    pub health_trend: HealthTrend,
     pub obs: ObsFrame,
     pub system: SystemFrame,
     pub streams: Vec<StreamOutput>,
+    pub stream_stats: StreamStats,
     pub network: NetworkFrame,
+    pub relay: Option<RelayFrame>,
+    pub audio: AudioFrame,
+}
+
+impl Default for TelemetryFrame {
+    fn default() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            timestamp_unix: 0,
+            health: 0.0,
+            health_trend: HealthTrend::default(),
+            obs: ObsFrame::default(),
+            system: SystemFrame::default(),
+            streams: Vec::new(),
+            stream_stats: StreamStats::default(),
+            network: NetworkFrame::default(),
+            relay: None,
+            audio: AudioFrame::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrendState {
+    #[default]
+    Stable,
+    Improving,
+    /// Drops or RTT are climbing, though not necessarily past any threshold yet.
+    Degrading,
+}
+
+/// Where the stream is heading, from a fast and a slow moving average of the
+/// main output's drop rate and of the RTT. Stable until a few samples into a
+/// stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HealthTrend {
+    pub state: TrendState,
+    /// Fast minus slow average of the per-interval drop ratio (0.0-1.0, not percent).
+    pub drop_ratio_slope: f32,
+    /// Fast minus slow average of the RTT.
+    pub rtt_slope_ms: f32,
 }
 
-#[derive(Debug, Clone, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ObsFrame {
     pub connected: bool,
     pub streaming: bool,
@@ -24,24 +82,139 @@ pub struct ObsFrame {
     pub output_total_frames: u32,
     pub active_fps: f32,
     pub available_disk_space_mb: f64,
+    pub replay_buffer_active: bool,
+    /// Most recent replay saved this session, kept after the buffer stops.
+    pub last_replay_path: Option<String>,
+    pub virtualcam_active: bool,
+    /// Program scene, as last reported by OBS.
+    pub current_scene: Option<String>,
+    pub connection_state: ObsConnectionState,
+    /// Why OBS is unreachable, while it is.
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ObsConnectionState {
+    #[default]
+    Disconnected,
+    /// `obs.auto_detect_process` is on and the OBS process was not found.
+    NotRunning,
+    Connecting,
+    Connected,
+    AuthFailed,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioFrame {
+    pub inputs: Vec<AudioInputFrame>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioInputFrame {
+    pub name: String,
+    pub muted: bool,
+    /// Fader setting.
+    pub volume_db: f32,
+    /// Loudest channel's current magnitude, in dBFS.
+    pub level_db: f32,
+    /// Highest peak since the previous frame, in dBFS.
+    pub peak_db: f32,
+    pub clipping: bool,
 }
 
-#[derive(Debug, Clone, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct SystemFrame {
     pub cpu_percent: f32,
     pub mem_percent: f32,
     pub gpu_percent: Option<f32>,
     pub gpu_temp_c: Option<f32>,
+    /// Busiest video encoder engine (NVENC) across GPUs.
+    pub encoder_percent: Option<f32>,
+    pub gpus: Vec<GpuFrame>,
+    pub disks: Vec<DiskFrame>,
 }
 
-#[derive(Debug, Clone, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiskFrame {
+    pub mount_point: String,
+    pub name: String,
+    pub total_mb: f64,
+    pub available_mb: f64,
+    pub used_percent: f32,
+    /// What OBS writes here: `recording` and/or `replay`.
+    pub obs_uses: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GpuFrame {
+    pub index: u32,
+    pub vendor: String,
+    pub name: String,
+    pub utilization_percent: Option<f32>,
+    pub vram_used_mb: Option<u64>,
+    pub vram_total_mb: Option<u64>,
+    pub temp_c: Option<f32>,
+    pub encoder_percent: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct NetworkFrame {
     pub upload_mbps: f32,
     pub download_mbps: f32,
     pub latency_ms: f32,
+    /// Every adapter that has moved traffic; the totals above only count the
+    /// `measured` ones.
+    pub interfaces: Vec<InterfaceFrame>,
+    /// Configured `network.uplinks`, empty unless set up.
+    pub uplinks: Vec<UplinkFrame>,
+    /// The uplinks combined; absent without any.
+    pub bond: Option<BondFrame>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InterfaceFrame {
+    pub name: String,
+    pub upload_mbps: f32,
+    pub download_mbps: f32,
+    pub measured: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UplinkFrame {
+    pub name: String,
+    pub interface: Option<String>,
+    /// Absent when the link's interface is unknown or idle.
+    pub upload_mbps: Option<f32>,
+    pub download_mbps: Option<f32>,
+    /// Absent when the link cannot be probed separately.
+    pub latency_ms: Option<f32>,
+    pub loss_pct: Option<f32>,
+    pub up: bool,
 }
 
-#[derive(Debug, Clone, Default, Serialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BondFrame {
+    pub links: usize,
+    pub links_up: usize,
+    pub upload_mbps: f32,
+    pub download_mbps: f32,
+    /// The best latency among links that are up.
+    pub latency_ms: Option<f32>,
+    pub loss_pct: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct StreamOutput {
     pub name: String,
     pub bitrate_kbps: u32,
@@ -49,3 +222,109 @@ pub struct StreamOutput {
     pub fps: f32,
     pub encoding_lag_ms: f32,
 }
+
+/// Rolling statistics of the main stream output over the last 1 and 5
+/// minutes. Each is `None` until the stream has run long enough to measure.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamStats {
+    pub avg_bitrate_1m_kbps: Option<f32>,
+    pub avg_bitrate_5m_kbps: Option<f32>,
+    /// Variance of the sample-to-sample bitrate over the last minute, in kbps².
+    pub bitrate_variance: Option<f32>,
+    /// 1 minus the bitrate's coefficient of variation over the last minute,
+    /// clamped to 0..=1; 1 is perfectly steady.
+    pub bitrate_stability: Option<f32>,
+    pub drops_per_min: Option<f32>,
+    pub frame_time_p95_ms: Option<f32>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneInventory {
+    pub current_program_scene: Option<String>,
+    pub current_preview_scene: Option<String>,
+    pub scenes: Vec<SceneEntry>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneEntry {
+    pub name: String,
+    pub index: usize,
+    pub sources: Vec<SceneSource>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SceneSource {
+    pub item_id: i64,
+    pub name: String,
+    pub source_type: String,
+    pub input_kind: Option<String>,
+    pub is_group: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RelayFrame {
+    pub connected: bool,
+    pub ingest_active: bool,
+    pub bitrate_kbps: u32,
+    pub rtt_ms: f32,
+    pub packet_loss_pct: f32,
+    pub reconnects: u32,
+    pub links: Vec<RelayLinkFrame>,
+    pub updated_unix: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RelayLinkFrame {
+    pub link_id: String,
+    pub label: String,
+    pub status: String,
+    pub bitrate_kbps: u32,
+    pub rtt_ms: f32,
+    pub packet_loss_pct: f32,
+    pub jitter_ms: f32,
+    pub reconnects: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_round_trip() {
+        let mut frame = TelemetryFrame::default();
+        frame.obs.connection_state = ObsConnectionState::AuthFailed;
+        frame.streams.push(StreamOutput {
+            name: "Twitch".to_string(),
+            bitrate_kbps: 6000,
+            ..Default::default()
+        });
+        let json = serde_json::to_string(&frame).unwrap();
+        let parsed: TelemetryFrame = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.schema_version, SCHEMA_VERSION);
+        assert_eq!(parsed.obs.connection_state, ObsConnectionState::AuthFailed);
+        assert_eq!(parsed.streams[0].bitrate_kbps, 6000);
+    }
+
+    #[test]
+    fn older_and_newer_frames_parse() {
+        let old: TelemetryFrame =
+            serde_json::from_str(r#"{"timestamp_unix": 1700000000, "obs": {"streaming": true}}"#)
+                .unwrap();
+        assert_eq!(old.schema_version, 0, "recorded before versioning");
+        assert!(old.obs.streaming);
+        assert!(old.relay.is_none());
+
+        let newer: TelemetryFrame = serde_json::from_str(
+            r#"{"schema_version": 2, "health": 0.5, "network": {"jitter_ms": 3.0}, "future": {}}"#,
+        )
+        .unwrap();
+        assert_eq!(newer.schema_version, 2);
+        assert_eq!(newer.health, 0.5);
+    }
+}
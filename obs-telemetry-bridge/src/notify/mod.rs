@@ -0,0 +1,308 @@
+use crate::activity::ActionKind;
+use crate::config::{ConfigHandle, NotificationsConfig};
+use crate::events::EventKind;
+use crate::model::{TelemetryFrame, TrendState};
+use std::process::Command;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+const TITLE: &str = "Telemy";
+
+/// A built-in event worth interrupting the streamer for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoticeKind {
+    Disconnect,
+    HighDrops,
+    LowDisk,
+    Degrading,
+}
+
+impl NoticeKind {
+    pub const ALL: [NoticeKind; 4] = [
+        Self::Disconnect,
+        Self::HighDrops,
+        Self::LowDisk,
+        Self::Degrading,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Disconnect => "disconnect",
+            Self::HighDrops => "high drops",
+            Self::LowDisk => "low disk",
+            Self::Degrading => "degrading stream",
+        }
+    }
+
+    pub fn enabled(self, config: &NotificationsConfig) -> bool {
+        match self {
+            Self::Disconnect => config.on_disconnect,
+            Self::HighDrops => config.on_high_drops,
+            Self::LowDisk => config.on_low_disk,
+            Self::Degrading => config.on_degrading,
+        }
+    }
+
+    /// Flipped from the tray menu.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub fn toggle(self, config: &mut NotificationsConfig) {
+        let flag = match self {
+            Self::Disconnect => &mut config.on_disconnect,
+            Self::HighDrops => &mut config.on_high_drops,
+            Self::LowDisk => &mut config.on_low_disk,
+            Self::Degrading => &mut config.on_degrading,
+        };
+        *flag = !*flag;
+    }
+
+    fn holds(self, frame: &TelemetryFrame, config: &NotificationsConfig) -> bool {
+        match self {
+            // Only a lost connection counts; OBS not being open yet is not news.
+            Self::Disconnect => !frame.obs.connected,
+            Self::HighDrops => {
+                frame.obs.streaming
+                    && crate::rules::main_output(frame)
+                        .is_some_and(|o| o.drop_pct >= config.drop_pct_threshold)
+            }
+            Self::LowDisk => {
+                frame.obs.connected
+                    && frame.obs.available_disk_space_mb > 0.0
+                    && frame.obs.available_disk_space_mb < config.low_disk_mb
+            }
+            Self::Degrading => {
+                frame.obs.streaming && frame.health_trend.state == TrendState::Degrading
+            }
+        }
+    }
+
+    fn message(self, frame: &TelemetryFrame) -> String {
+        match self {
+            Self::Disconnect => "Lost connection to OBS".to_string(),
+            Self::HighDrops => {
+                let pct = crate::rules::main_output(frame).map_or(0.0, |o| o.drop_pct * 100.0);
+                format!("Stream is dropping frames ({pct:.1}%)")
+            }
+            Self::LowDisk => format!(
+                "Low disk space: {:.1} GB left",
+                frame.obs.available_disk_space_mb / 1024.0
+            ),
+            Self::Degrading => {
+                "Stream quality is degrading; consider lowering the bitrate".to_string()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct KindState {
+    active: bool,
+    last_shown: Option<Instant>,
+}
+
+/// Edge-triggered detection of [`NoticeKind`] events, one notification per
+/// episode and at most one per kind every `cooldown_secs`.
+#[derive(Debug, Default)]
+pub struct EventWatcher {
+    seen_connected: bool,
+    states: [KindState; 4],
+}
+
+impl EventWatcher {
+    pub fn observe(
+        &mut self,
+        frame: &TelemetryFrame,
+        config: &NotificationsConfig,
+        now: Instant,
+    ) -> Vec<(NoticeKind, String)> {
+        self.seen_connected |= frame.obs.connected;
+        let cooldown = Duration::from_secs(config.cooldown_secs);
+        let mut notices = Vec::new();
+        for (kind, state) in NoticeKind::ALL.into_iter().zip(self.states.iter_mut()) {
+            let holds = kind.holds(frame, config)
+                && (kind != NoticeKind::Disconnect || self.seen_connected);
+            let rising = holds && !state.active;
+            state.active = holds;
+            if !rising || !config.enable || !kind.enabled(config) {
+                continue;
+            }
+            if state
+                .last_shown
+                .is_some_and(|at| now.duration_since(at) < cooldown)
+            {
+                continue;
+            }
+            state.last_shown = Some(now);
+            notices.push((kind, kind.message(frame)));
+        }
+        notices
+    }
+}
+
+/// Logs an alert from a rule or script, records it in the activity log and,
+/// when notifications are on, shows it on the desktop.
+pub fn alert(config: &NotificationsConfig, message: &str) {
+    tracing::warn!(target: "telemy::alert", "{}", message);
+    crate::events::bus().emit(EventKind::AlertFired {
+        message: message.to_string(),
+    });
+    let mut outcome = Ok(message.to_string());
+    if config.enable {
+        if let Err(err) = show(TITLE, message) {
+            tracing::warn!(error = %err, "desktop notification failed");
+            outcome = Err(format!("{message} (desktop notification failed: {err})"));
+        }
+    }
+    crate::activity::log().record(ActionKind::Alert, "notify", None, outcome);
+}
+
+/// Shows a desktop notification without waiting for it to be dismissed.
+/// Windows toasts go through PowerShell's WinRT bindings; Linux uses
+/// `notify-send`. Text is passed in the environment, never in the script.
+pub fn show(title: &str, body: &str) -> Result<(), String> {
+    let mut command = notifier_command(title, body)?;
+    let mut child = command
+        .spawn()
+        .map_err(|err| format!("failed to start notifier: {err}"))?;
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+#[cfg(windows)]
+fn notifier_command(title: &str, body: &str) -> Result<Command, String> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    const SCRIPT: &str = r#"
+[Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] | Out-Null
+[Windows.Data.Xml.Dom.XmlDocument, Windows.Data.Xml.Dom.XmlDocument, ContentType = WindowsRuntime] | Out-Null
+$xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02)
+$text = $xml.GetElementsByTagName('text')
+$text.Item(0).AppendChild($xml.CreateTextNode($env:TELEMY_TOAST_TITLE)) | Out-Null
+$text.Item(1).AppendChild($xml.CreateTextNode($env:TELEMY_TOAST_BODY)) | Out-Null
+$toast = [Windows.UI.Notifications.ToastNotification]::new($xml)
+$app = '{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\WindowsPowerShell\v1.0\powershell.exe'
+[Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier($app).Show($toast)
+"#;
+    let mut command = Command::new("powershell");
+    command
+        .args(["-NoProfile", "-NonInteractive", "-Command", SCRIPT])
+        .env("TELEMY_TOAST_TITLE", title)
+        .env("TELEMY_TOAST_BODY", body)
+        .creation_flags(CREATE_NO_WINDOW);
+    Ok(command)
+}
+
+#[cfg(target_os = "linux")]
+fn notifier_command(title: &str, body: &str) -> Result<Command, String> {
+    let mut command = Command::new("notify-send");
+    command.args([
+        "--app-name",
+        TITLE,
+        "--urgency",
+        "critical",
+        "--",
+        title,
+        body,
+    ]);
+    Ok(command)
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn notifier_command(_title: &str, _body: &str) -> Result<Command, String> {
+    Err("desktop notifications are not supported on this platform".into())
+}
+
+/// Watches telemetry for the built-in events, reading the toggles from the
+/// live config so tray and file edits apply without a restart.
+pub fn spawn(config: ConfigHandle, mut rx: watch::Receiver<TelemetryFrame>) {
+    tokio::spawn(async move {
+        let mut watcher = EventWatcher::default();
+        while rx.changed().await.is_ok() {
+            let frame = rx.borrow_and_update().clone();
+            let settings = config.read(|c| c.notifications.clone());
+            for (kind, message) in watcher.observe(&frame, &settings, Instant::now()) {
+                tracing::info!(event = kind.label(), "{}", message);
+                crate::events::bus().emit(EventKind::AlertFired {
+                    message: message.clone(),
+                });
+                let outcome = match show(TITLE, &message) {
+                    Ok(()) => Ok(message),
+                    Err(err) => {
+                        tracing::warn!(error = %err, "desktop notification failed");
+                        Err(format!("{message} (desktop notification failed: {err})"))
+                    }
+                };
+                crate::activity::log().record(
+                    ActionKind::Alert,
+                    "notify",
+                    Some(kind.label().to_string()),
+                    outcome,
+                );
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::StreamOutput;
+
+    #[test]
+    fn events_fire_once_per_episode_and_respect_toggles() {
+        let mut config = NotificationsConfig::default();
+        let mut watcher = EventWatcher::default();
+        let now = Instant::now();
+        let mut frame = TelemetryFrame::default();
+        assert!(
+            watcher.observe(&frame, &config, now).is_empty(),
+            "OBS never connected"
+        );
+
+        frame.obs.connected = true;
+        frame.obs.streaming = true;
+        frame.obs.available_disk_space_mb = 100_000.0;
+        frame.streams = vec![StreamOutput {
+            name: "adv_stream".to_string(),
+            drop_pct: 0.05,
+            ..StreamOutput::default()
+        }];
+        let kinds: Vec<NoticeKind> = watcher
+            .observe(&frame, &config, now)
+            .into_iter()
+            .map(|(kind, _)| kind)
+            .collect();
+        assert_eq!(kinds, [NoticeKind::HighDrops]);
+        assert!(watcher.observe(&frame, &config, now).is_empty());
+
+        NoticeKind::Disconnect.toggle(&mut config);
+        frame.obs.connected = false;
+        assert!(watcher.observe(&frame, &config, now).is_empty());
+        NoticeKind::Disconnect.toggle(&mut config);
+        frame.obs.connected = true;
+        watcher.observe(&frame, &config, now);
+        frame.obs.connected = false;
+        let later = now + Duration::from_secs(1);
+        let notices = watcher.observe(&frame, &config, later);
+        assert_eq!(notices[0].0, NoticeKind::Disconnect);
+        assert_eq!(notices[0].1, "Lost connection to OBS");
+    }
+
+    #[test]
+    fn degrading_trend_warns_while_live() {
+        let config = NotificationsConfig::default();
+        let mut watcher = EventWatcher::default();
+        let now = Instant::now();
+        let mut frame = TelemetryFrame::default();
+        frame.obs.connected = true;
+        frame.obs.available_disk_space_mb = 100_000.0;
+        frame.health_trend.state = TrendState::Degrading;
+        assert!(watcher.observe(&frame, &config, now).is_empty(), "not live");
+
+        frame.obs.streaming = true;
+        let notices = watcher.observe(&frame, &config, now);
+        assert_eq!(notices.len(), 1);
+        assert_eq!(notices[0].0, NoticeKind::Degrading);
+    }
+}
@@ -0,0 +1,323 @@
+use crate::config::ObsConfig;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::broadcast;
+use utoipa::ToSchema;
+
+const CAPACITY: usize = 200;
+/// Raw lines buffered for each subscriber, e.g. the Loki shipper.
+const LINES_CAPACITY: usize = 1024;
+const POLL: Duration = Duration::from_secs(2);
+/// The most read from a log in one poll; OBS writes a few lines a second.
+const MAX_READ: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LogEventKind {
+    EncoderOverload,
+    RenderLag,
+    DroppedFrames,
+    Disconnect,
+    Reconnect,
+    /// Not a log line: OBS exited mid-stream, see `crate::crash`.
+    Crash,
+}
+
+/// A notable OBS log line, or the bridge's note on a crash; `at_unix_ms` is
+/// when the bridge read or wrote it.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct LogEvent {
+    pub kind: LogEventKind,
+    pub line: String,
+    pub at_unix_ms: u64,
+}
+
+/// Notable lines from the newest OBS log and detected crashes, newest last.
+/// Every line read is also passed on to subscribers.
+pub struct ObsLog {
+    file: Mutex<Option<PathBuf>>,
+    events: Mutex<VecDeque<LogEvent>>,
+    lines: broadcast::Sender<String>,
+}
+
+impl Default for ObsLog {
+    fn default() -> Self {
+        Self {
+            file: Mutex::default(),
+            events: Mutex::default(),
+            lines: broadcast::channel(LINES_CAPACITY).0,
+        }
+    }
+}
+
+impl ObsLog {
+    pub fn record(&self, event: LogEvent) {
+        let mut events = self.events.lock().unwrap();
+        if events.len() == CAPACITY {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    /// Every line read from here on, whether notable or not.
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.lines.subscribe()
+    }
+
+    /// Passes `lines` on and records the notable ones.
+    fn read(&self, lines: Vec<String>, at_unix_ms: u64) {
+        for line in lines {
+            if let Some(kind) = classify(&line) {
+                tracing::debug!(?kind, %line, "obs log event");
+                self.record(LogEvent {
+                    kind,
+                    line: line.clone(),
+                    at_unix_ms,
+                });
+            }
+            let _ = self.lines.send(line);
+        }
+    }
+
+    /// The log being tailed, once one was found.
+    pub fn file(&self) -> Option<PathBuf> {
+        self.file.lock().unwrap().clone()
+    }
+
+    /// Events read after `since_unix_ms`, oldest first.
+    pub fn since(&self, since_unix_ms: u64) -> Vec<LogEvent> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|event| event.at_unix_ms > since_unix_ms)
+            .cloned()
+            .collect()
+    }
+}
+
+/// `obs.log_dir`, else OBS's own `%APPDATA%/obs-studio/logs`.
+pub fn log_dir(config: &ObsConfig) -> PathBuf {
+    match &config.log_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let base = std::env::var("APPDATA").unwrap_or_else(|_| ".".to_string());
+            Path::new(&base).join("obs-studio").join("logs")
+        }
+    }
+}
+
+/// Starts following the newest log in `dir`, the one tailer behind the log
+/// events and the Loki shipper. OBS starts a new file per launch, so the
+/// bridge switches to it and reads it from the top; the file found at startup
+/// is only read from its end.
+pub fn spawn(dir: PathBuf) -> Arc<ObsLog> {
+    let log = Arc::new(ObsLog::default());
+    let shared = log.clone();
+    std::thread::Builder::new()
+        .name("telemy-obslog".to_string())
+        .spawn(move || {
+            let mut tailer = Tailer::new(dir);
+            loop {
+                match tailer.poll() {
+                    Ok(lines) => {
+                        *shared.file.lock().unwrap() = tailer.file.clone();
+                        shared.read(lines, now_unix_ms());
+                    }
+                    Err(err) => tracing::debug!(error = %err, "obs log not readable"),
+                }
+                std::thread::sleep(POLL);
+            }
+        })
+        .expect("spawn obs log thread");
+    log
+}
+
+struct Tailer {
+    dir: PathBuf,
+    file: Option<PathBuf>,
+    offset: u64,
+    partial: String,
+}
+
+impl Tailer {
+    fn new(dir: PathBuf) -> Self {
+        Self {
+            dir,
+            file: None,
+            offset: 0,
+            partial: String::new(),
+        }
+    }
+
+    /// Complete lines written since the last poll.
+    fn poll(&mut self) -> std::io::Result<Vec<String>> {
+        let Some(newest) = newest_log(&self.dir)? else {
+            return Ok(Vec::new());
+        };
+        let len = std::fs::metadata(&newest)?.len();
+        if self.file.as_ref() != Some(&newest) {
+            self.offset = if self.file.is_none() { len } else { 0 };
+            self.file = Some(newest.clone());
+            self.partial.clear();
+        }
+        if len < self.offset {
+            self.offset = 0;
+            self.partial.clear();
+        }
+        if len == self.offset {
+            return Ok(Vec::new());
+        }
+
+        let mut file = File::open(&newest)?;
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut bytes = Vec::new();
+        file.take(MAX_READ).read_to_end(&mut bytes)?;
+        self.offset += bytes.len() as u64;
+        self.partial.push_str(&String::from_utf8_lossy(&bytes));
+
+        let Some(end) = self.partial.rfind('\n') else {
+            return Ok(Vec::new());
+        };
+        let rest = self.partial.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.partial, rest);
+        Ok(complete
+            .lines()
+            .map(|line| line.trim_end_matches('\r').to_string())
+            .filter(|line| !line.is_empty())
+            .collect())
+    }
+}
+
+/// The most recently modified `*.txt` in `dir`.
+fn newest_log(dir: &Path) -> std::io::Result<Option<PathBuf>> {
+    let mut newest: Option<(SystemTime, PathBuf)> = None;
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("txt") {
+            continue;
+        }
+        let modified = std::fs::metadata(&path)?.modified()?;
+        if newest.as_ref().is_none_or(|(at, _)| modified > *at) {
+            newest = Some((modified, path));
+        }
+    }
+    Ok(newest.map(|(_, path)| path))
+}
+
+/// Sorts a log line into the kinds the dashboard annotates. The frame
+/// summaries OBS writes when an output stops only count when non-zero.
+fn classify(line: &str) -> Option<LogEventKind> {
+    let lower = line.to_ascii_lowercase();
+    let counted = |kind| {
+        let count = lower.rsplit(": ").next().unwrap_or_default();
+        (!count.starts_with("0 ") && !count.starts_with("0/")).then_some(kind)
+    };
+    if lower.contains("skipped frames due to encoding lag") {
+        counted(LogEventKind::EncoderOverload)
+    } else if lower.contains("encoder overloaded") || lower.contains("encoding overloaded") {
+        Some(LogEventKind::EncoderOverload)
+    } else if lower.contains("lagged frames due to rendering lag") {
+        counted(LogEventKind::RenderLag)
+    } else if lower.contains("dropped frames due to insufficient bandwidth") {
+        counted(LogEventKind::DroppedFrames)
+    } else if lower.contains("reconnected") || lower.contains("reconnect successful") {
+        Some(LogEventKind::Reconnect)
+    } else if lower.contains("disconnected from")
+        || lower.contains("reconnecting in")
+        || lower.contains("failed to connect")
+        || lower.contains("rtmp send error")
+    {
+        Some(LogEventKind::Disconnect)
+    } else {
+        None
+    }
+}
+
+fn now_unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn picks_out_notable_lines() {
+        let cases = [
+            ("12:00:01.100: [rtmp stream: 'adv_stream'] Disconnected from rtmp://live.twitch.tv/app", Some(LogEventKind::Disconnect)),
+            ("12:00:01.200: Output 'adv_stream': Reconnecting in 10.00 seconds..", Some(LogEventKind::Disconnect)),
+            ("12:00:11.300: Output 'adv_stream': Reconnected.", Some(LogEventKind::Reconnect)),
+            ("12:10:00.000: Output 'adv_stream': Number of dropped frames due to insufficient bandwidth/connection stalls: 120 (1.2%)", Some(LogEventKind::DroppedFrames)),
+            ("12:10:00.000: Output 'adv_stream': Number of dropped frames due to insufficient bandwidth/connection stalls: 0 (0.0%)", None),
+            ("12:10:00.000: Video stopped, number of skipped frames due to encoding lag: 312/54000 (0.6%)", Some(LogEventKind::EncoderOverload)),
+            ("12:10:00.000: Video stopped, number of skipped frames due to encoding lag: 0/54000 (0.0%)", None),
+            ("12:10:00.000: Number of lagged frames due to rendering lag/stalls: 42 (0.1%)", Some(LogEventKind::RenderLag)),
+            ("12:00:00.000: [x264 encoder: 'streaming_h264'] settings:", None),
+        ];
+        for (line, kind) in cases {
+            assert_eq!(classify(line), kind, "{line}");
+        }
+    }
+
+    #[test]
+    fn passes_every_line_on_and_records_the_notable_ones() {
+        let log = ObsLog::default();
+        let mut lines = log.subscribe();
+        log.read(
+            vec![
+                "12:00:00.000: ==== Streaming Start ====".to_string(),
+                "12:00:01.300: Output 'adv_stream': Reconnected.".to_string(),
+            ],
+            5,
+        );
+
+        assert_eq!(
+            lines.try_recv().unwrap(),
+            "12:00:00.000: ==== Streaming Start ===="
+        );
+        assert_eq!(
+            lines.try_recv().unwrap(),
+            "12:00:01.300: Output 'adv_stream': Reconnected."
+        );
+        let events = log.since(0);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, LogEventKind::Reconnect);
+    }
+
+    #[test]
+    fn follows_the_newest_log() {
+        let dir = std::env::temp_dir().join(format!("telemy-obslog-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let first = dir.join("2026-10-14 10-00-00.txt");
+        std::fs::write(&first, "10:00:00.000: old session\n").unwrap();
+
+        let mut tailer = Tailer::new(dir.clone());
+        assert!(tailer.poll().unwrap().is_empty(), "starts at the end");
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&first)
+            .unwrap();
+        file.write_all(b"10:00:01.000: one\n10:00:02.000: tw")
+            .unwrap();
+        assert_eq!(tailer.poll().unwrap(), ["10:00:01.000: one"]);
+        file.write_all(b"o\r\n").unwrap();
+        assert_eq!(tailer.poll().unwrap(), ["10:00:02.000: two"]);
+
+        std::thread::sleep(Duration::from_millis(20));
+        let second = dir.join("2026-10-14 11-00-00.txt");
+        std::fs::write(&second, "11:00:00.000: new session\n").unwrap();
+        assert_eq!(tailer.poll().unwrap(), ["11:00:00.000: new session"]);
+        assert_eq!(tailer.file.as_deref(), Some(second.as_path()));
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
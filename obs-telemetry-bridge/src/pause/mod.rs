@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+static PAUSED: AtomicBool = AtomicBool::new(false);
+static PAUSED_SINCE: AtomicU64 = AtomicU64::new(0);
+
+/// Body of `GET`/`PUT /api/v1/pause`; `since_unix` is only reported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PauseStatus {
+    pub paused: bool,
+    #[serde(default, skip_deserializing)]
+    pub since_unix: Option<u64>,
+}
+
+/// While paused the metrics loop stops polling OBS and probing the network and
+/// export sinks stop pushing; the server, IPC and Aegis keep running. Kept
+/// process-wide like the selfstats, since the tray, the API and the collection
+/// loops all read or flip it.
+pub fn is_paused() -> bool {
+    PAUSED.load(Ordering::Relaxed)
+}
+
+/// Pauses or resumes collection; returns the resulting status.
+pub fn set_paused(paused: bool, by: &str) -> PauseStatus {
+    if PAUSED.swap(paused, Ordering::Relaxed) != paused {
+        let since = if paused {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs()
+        } else {
+            0
+        };
+        PAUSED_SINCE.store(since, Ordering::Relaxed);
+        if paused {
+            tracing::info!(by, "telemetry collection paused");
+        } else {
+            tracing::info!(by, "telemetry collection resumed");
+        }
+    }
+    status()
+}
+
+pub fn status() -> PauseStatus {
+    let paused = is_paused();
+    PauseStatus {
+        paused,
+        since_unix: Some(PAUSED_SINCE.load(Ordering::Relaxed)).filter(|_| paused),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pause_records_when_it_started() {
+        let paused = set_paused(true, "test");
+        assert!(paused.paused && is_paused());
+        assert!(paused.since_unix.is_some_and(|t| t > 0));
+        assert_eq!(
+            set_paused(true, "test"),
+            paused,
+            "pausing twice keeps the start"
+        );
+
+        let resumed = set_paused(false, "test");
+        assert!(!resumed.paused && !is_paused());
+        assert_eq!(resumed.since_unix, None);
+    }
+}
@@ -0,0 +1,278 @@
+use crate::config::PeerConfig;
+use crate::model::{NetworkFrame, ObsFrame, RelayFrame, StreamOutput, StreamStats, SystemFrame};
+use crate::security::SecretString;
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, http::HeaderValue, Message};
+
+const MAX_BACKOFF_MS: u64 = 30_000;
+/// Peers only need to keep the hub's view current, not every tick.
+const SUBSCRIBE: &str = r#"{"type":"subscribe","channels":["frames"],"max_rate_hz":1}"#;
+
+/// The part of a peer's `"type": "frame"` WebSocket message the hub keeps.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PeerFrame {
+    pub schema_version: u32,
+    pub ts: u64,
+    pub health: f32,
+    pub obs: ObsFrame,
+    pub system: SystemFrame,
+    pub network: NetworkFrame,
+    pub outputs: Vec<StreamOutput>,
+    pub stream_stats: StreamStats,
+    pub relay: Option<RelayFrame>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum PeerMessage {
+    Frame(Box<PeerFrame>),
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PeerStatus {
+    pub name: String,
+    pub url: String,
+    pub connected: bool,
+    pub last_frame_unix_ms: Option<u64>,
+    /// Why the last connection attempt failed or ended.
+    pub error: Option<String>,
+    /// Latest frame, kept after the peer goes away.
+    pub frame: Option<PeerFrame>,
+}
+
+/// Totals across connected peers, for the combined view.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Combined {
+    pub peers: usize,
+    pub connected: usize,
+    pub streaming: usize,
+    /// Lowest health among connected peers.
+    pub worst_health: Option<f32>,
+    pub total_bitrate_kbps: u64,
+    pub total_dropped_frames: u64,
+}
+
+impl Combined {
+    pub fn of(peers: &[PeerStatus]) -> Self {
+        let mut combined = Self {
+            peers: peers.len(),
+            connected: peers.iter().filter(|p| p.connected).count(),
+            ..Default::default()
+        };
+        let live = peers
+            .iter()
+            .filter(|p| p.connected)
+            .filter_map(|p| p.frame.as_ref());
+        for frame in live {
+            combined.streaming += usize::from(frame.obs.streaming);
+            combined.worst_health = Some(
+                combined
+                    .worst_health
+                    .map_or(frame.health, |h| h.min(frame.health)),
+            );
+            combined.total_bitrate_kbps += frame
+                .outputs
+                .iter()
+                .map(|o| u64::from(o.bitrate_kbps))
+                .sum::<u64>();
+            combined.total_dropped_frames += frame.obs.total_dropped_frames;
+        }
+        combined
+    }
+}
+
+/// The instances followed in hub mode, each kept current by its own task.
+#[derive(Default)]
+pub struct Peers {
+    peers: Vec<Arc<Mutex<PeerStatus>>>,
+}
+
+impl Peers {
+    pub fn snapshot(&self) -> Vec<PeerStatus> {
+        self.peers
+            .iter()
+            .map(|peer| peer.lock().unwrap().clone())
+            .collect()
+    }
+}
+
+/// `http(s)://host[:port][/]` to the peer's authenticated WebSocket.
+fn ws_url(base: &str) -> String {
+    let base = base.trim_end_matches('/');
+    let base = match base.strip_prefix("https://") {
+        Some(rest) => format!("wss://{rest}"),
+        None => format!("ws://{}", base.trim_start_matches("http://")),
+    };
+    format!("{base}/api/v1/ws?client=hub")
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Follows every enabled `[[peers]]` entry; `tokens` holds the resolved
+/// `token_key` of each entry in `configs`.
+pub fn spawn(configs: &[PeerConfig], tokens: Vec<Option<SecretString>>) -> Arc<Peers> {
+    let mut peers = Peers::default();
+    for (config, token) in configs.iter().zip(tokens) {
+        if !config.enabled {
+            continue;
+        }
+        if config.token_key.is_some() && token.is_none() {
+            tracing::warn!(peer = %config.name, "peer token not found in vault");
+        }
+        let status = Arc::new(Mutex::new(PeerStatus {
+            name: config.name.clone(),
+            url: config.url.clone(),
+            connected: false,
+            last_frame_unix_ms: None,
+            error: None,
+            frame: None,
+        }));
+        peers.peers.push(status.clone());
+        let url = ws_url(&config.url);
+        tokio::spawn(async move {
+            follow(status, url, token).await;
+        });
+    }
+    Arc::new(peers)
+}
+
+async fn follow(status: Arc<Mutex<PeerStatus>>, url: String, token: Option<SecretString>) {
+    let mut backoff_ms = 1000u64;
+    loop {
+        let err = match stream_frames(&status, &url, token.as_ref()).await {
+            Ok(()) => "peer socket closed".to_string(),
+            Err(err) => err.to_string(),
+        };
+        let (name, was_connected) = {
+            let mut status = status.lock().unwrap();
+            let was_connected = std::mem::replace(&mut status.connected, false);
+            status.error = Some(err.clone());
+            (status.name.clone(), was_connected)
+        };
+        if was_connected {
+            backoff_ms = 1000;
+            tracing::warn!(peer = %name, error = %err, "peer disconnected");
+        } else {
+            tracing::debug!(peer = %name, error = %err, "peer connection failed");
+        }
+
+        tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
+        backoff_ms = (backoff_ms * 2).min(MAX_BACKOFF_MS);
+    }
+}
+
+/// Keeps the peer's latest frame until its socket closes.
+async fn stream_frames(
+    status: &Mutex<PeerStatus>,
+    url: &str,
+    token: Option<&SecretString>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut request = url.into_client_request()?;
+    if let Some(token) = token {
+        request.headers_mut().insert(
+            "Authorization",
+            HeaderValue::from_str(&format!("Bearer {}", token.expose()))?,
+        );
+    }
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await?;
+    socket.send(Message::Text(SUBSCRIBE.into())).await?;
+    {
+        let mut status = status.lock().unwrap();
+        status.connected = true;
+        status.error = None;
+        tracing::info!(peer = %status.name, "peer connected");
+    }
+
+    while let Some(msg) = socket.next().await {
+        match msg? {
+            Message::Text(text) => {
+                if let Ok(PeerMessage::Frame(frame)) = serde_json::from_str(&text) {
+                    let mut status = status.lock().unwrap();
+                    status.last_frame_unix_ms = Some(now_ms());
+                    status.frame = Some(*frame);
+                }
+            }
+            Message::Close(_) => return Ok(()),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peer(name: &str, connected: bool, frame: Option<PeerFrame>) -> PeerStatus {
+        PeerStatus {
+            name: name.to_string(),
+            url: String::new(),
+            connected,
+            last_frame_unix_ms: None,
+            error: None,
+            frame,
+        }
+    }
+
+    #[test]
+    fn builds_peer_ws_urls() {
+        assert_eq!(
+            ws_url("http://10.0.0.12:7070/"),
+            "ws://10.0.0.12:7070/api/v1/ws?client=hub"
+        );
+        assert_eq!(
+            ws_url("https://rig.example.com"),
+            "wss://rig.example.com/api/v1/ws?client=hub"
+        );
+    }
+
+    #[test]
+    fn parses_frames_and_combines_connected_peers() {
+        let text = r#"{"type":"frame","schema_version":1,"health":0.9,
+            "obs":{"streaming":true,"total_dropped_frames":12},
+            "outputs":[{"name":"adv_stream","bitrate_kbps":6000}],"future":1}"#;
+        let Ok(PeerMessage::Frame(frame)) = serde_json::from_str(text) else {
+            panic!("frame message");
+        };
+        let frame = *frame;
+        let subscribed = r#"{"type":"subscribed","channels":["frames"],"max_rate_hz":1}"#;
+        assert!(matches!(
+            serde_json::from_str(subscribed),
+            Ok(PeerMessage::Other)
+        ));
+
+        let idle = PeerFrame {
+            health: 1.0,
+            ..Default::default()
+        };
+        let peers = [
+            peer("a", true, Some(frame.clone())),
+            peer("b", true, Some(idle)),
+            peer("c", false, Some(frame)),
+            peer("d", false, None),
+        ];
+        assert_eq!(
+            Combined::of(&peers),
+            Combined {
+                peers: 4,
+                connected: 2,
+                streaming: 1,
+                worst_health: Some(0.9),
+                total_bitrate_kbps: 6000,
+                total_dropped_frames: 12,
+            }
+        );
+    }
+}
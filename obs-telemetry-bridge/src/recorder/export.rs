@@ -0,0 +1,270 @@
+use super::{recording_files, FILE_PREFIX};
+use crate::model::TelemetryFrame;
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// Columns when the request names none.
+pub const DEFAULT_FIELDS: &[&str] = &[
+    "timestamp_unix",
+    "health",
+    "obs.streaming",
+    "obs.current_scene",
+    "obs.total_dropped_frames",
+    "system.cpu_percent",
+    "system.mem_percent",
+    "system.gpu_percent",
+    "network.upload_mbps",
+    "network.latency_ms",
+    "streams.0.bitrate_kbps",
+    "streams.0.drop_pct",
+];
+
+const MAX_FIELDS: usize = 64;
+/// Rows are handed out in chunks of about this many bytes.
+const CHUNK_BYTES: usize = 64 * 1024;
+
+/// Recorded frames between `from` and `to` (unix seconds, inclusive) as CSV,
+/// one column per frame path.
+pub struct CsvExport {
+    pub fields: Vec<String>,
+    pub from: Option<u64>,
+    pub to: Option<u64>,
+    pub max_rows: usize,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ExportSummary {
+    pub rows: usize,
+    /// Stopped at `max_rows` with frames left in the range.
+    pub truncated: bool,
+}
+
+/// Parses a comma-separated list of dotted frame paths such as
+/// `system.cpu_percent,streams.0.fps`. Numeric segments index lists.
+pub fn parse_fields(spec: &str) -> Result<Vec<String>, String> {
+    let frame = serde_json::to_value(TelemetryFrame::default()).unwrap_or(Value::Null);
+    let fields: Vec<String> = spec
+        .split(',')
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+        .map(str::to_string)
+        .collect();
+    if fields.is_empty() {
+        return Err("fields must name at least one column".to_string());
+    }
+    if fields.len() > MAX_FIELDS {
+        return Err(format!("at most {MAX_FIELDS} fields per export"));
+    }
+    for field in &fields {
+        let valid = field.split('.').all(|segment| {
+            !segment.is_empty()
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+        let top = field.split('.').next().unwrap_or_default();
+        if !valid || frame.get(top).is_none() {
+            return Err(format!("unknown field \"{field}\""));
+        }
+    }
+    Ok(fields)
+}
+
+impl CsvExport {
+    /// Writes the header and the matching frames, oldest first, to `out` in
+    /// chunks. Stops early when `out` returns false, e.g. once the client has
+    /// gone away.
+    pub fn write(
+        &self,
+        dir: &Path,
+        mut out: impl FnMut(String) -> bool,
+    ) -> std::io::Result<ExportSummary> {
+        let mut summary = ExportSummary::default();
+        let mut chunk = self.fields.join(",");
+        chunk.push('\n');
+
+        let files = self.files_in_range(dir)?;
+        'files: for path in files {
+            for line in BufReader::new(File::open(&path)?).lines() {
+                // The newest file may end in a partly written line.
+                let Ok(frame) = serde_json::from_str::<Value>(&line?) else {
+                    continue;
+                };
+                let ts = frame["timestamp_unix"].as_u64().unwrap_or_default();
+                if self.from.is_some_and(|from| ts < from) {
+                    continue;
+                }
+                if self.to.is_some_and(|to| ts > to) {
+                    break 'files;
+                }
+                if summary.rows == self.max_rows {
+                    summary.truncated = true;
+                    break 'files;
+                }
+                let cells: Vec<String> = self
+                    .fields
+                    .iter()
+                    .map(|field| cell(lookup(&frame, field)))
+                    .collect();
+                chunk.push_str(&cells.join(","));
+                chunk.push('\n');
+                summary.rows += 1;
+                if chunk.len() >= CHUNK_BYTES && !out(std::mem::take(&mut chunk)) {
+                    return Ok(summary);
+                }
+            }
+        }
+        if !chunk.is_empty() {
+            out(chunk);
+        }
+        Ok(summary)
+    }
+
+    /// Recordings oldest first, skipping files that end before `from`: each
+    /// file's frames predate the next file's creation, encoded in its name.
+    fn files_in_range(&self, dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+        let mut files: Vec<(u64, PathBuf)> = recording_files(dir, FILE_PREFIX)?
+            .into_iter()
+            .map(|(path, _)| (started_secs(&path), path))
+            .collect();
+        files.sort();
+        let starts: Vec<u64> = files.iter().map(|(start, _)| *start).collect();
+        Ok(files
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| match (self.from, starts.get(i + 1)) {
+                (Some(from), Some(&next)) => next >= from,
+                _ => true,
+            })
+            .map(|(_, (_, path))| path)
+            .collect())
+    }
+}
+
+/// `frames-<unix ms>[-n].jsonl` → unix seconds; 0 for anything else.
+fn started_secs(path: &Path) -> u64 {
+    let name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    let digits: String = name
+        .strip_prefix(FILE_PREFIX)
+        .unwrap_or_default()
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    digits.parse::<u64>().map_or(0, |ms| ms / 1000)
+}
+
+fn lookup<'a>(frame: &'a Value, field: &str) -> Option<&'a Value> {
+    field
+        .split('.')
+        .try_fold(frame, |value, segment| match value {
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => value.get(segment),
+        })
+}
+
+/// One CSV cell: empty for missing or null, JSON for lists and objects, and
+/// quoted when it holds a comma, quote or line break. Strings a spreadsheet
+/// would read as a formula (scene names come from OBS) get a leading `'`.
+fn cell(value: Option<&Value>) -> String {
+    let text = match value {
+        None | Some(Value::Null) => return String::new(),
+        Some(Value::String(s)) if s.starts_with(['=', '+', '-', '@', '\t', '\r']) => {
+            format!("'{s}")
+        }
+        Some(Value::String(s)) => s.clone(),
+        Some(value) => value.to_string(),
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::Recorder;
+    use super::*;
+    use crate::model::StreamOutput;
+
+    #[test]
+    fn exports_the_selected_columns_in_range() {
+        let dir = std::env::temp_dir().join(format!("telemy-export-{}", uuid::Uuid::new_v4()));
+        let mut recorder = Recorder::new(&dir, 1024 * 1024, 5, 0).unwrap();
+        for ts in 10..20 {
+            let mut frame = TelemetryFrame {
+                timestamp_unix: ts,
+                ..Default::default()
+            };
+            frame.obs.current_scene = Some("Cam, \"wide\"".to_string());
+            frame.streams.push(StreamOutput {
+                bitrate_kbps: ts as u32 * 100,
+                ..Default::default()
+            });
+            recorder.record(&frame).unwrap();
+        }
+
+        let fields =
+            parse_fields("timestamp_unix, obs.current_scene,streams.0.bitrate_kbps,relay.rtt_ms")
+                .unwrap();
+        let mut export = CsvExport {
+            fields,
+            from: Some(12),
+            to: Some(15),
+            max_rows: 100,
+        };
+        let mut csv = String::new();
+        let summary = export
+            .write(&dir, |chunk| {
+                csv.push_str(&chunk);
+                true
+            })
+            .unwrap();
+        assert_eq!(
+            summary,
+            ExportSummary {
+                rows: 4,
+                truncated: false
+            }
+        );
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "timestamp_unix,obs.current_scene,streams.0.bitrate_kbps,relay.rtt_ms"
+        );
+        assert_eq!(lines[1], "12,\"Cam, \"\"wide\"\"\",1200,");
+        assert_eq!(lines.last().unwrap(), &"15,\"Cam, \"\"wide\"\"\",1500,");
+
+        export.max_rows = 2;
+        let summary = export.write(&dir, |_| true).unwrap();
+        assert_eq!(
+            summary,
+            ExportSummary {
+                rows: 2,
+                truncated: true
+            }
+        );
+
+        assert!(parse_fields("system.cpu_percent").is_ok());
+        assert!(parse_fields("cpu").is_err());
+        assert!(parse_fields("system..cpu").is_err());
+        assert!(parse_fields(" , ").is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn neutralizes_formula_cells() {
+        let cell_of = |v: Value| cell(Some(&v));
+        assert_eq!(cell_of(Value::from("=1+2")), "'=1+2");
+        assert_eq!(cell_of(Value::from("@SUM(A1)")), "'@SUM(A1)");
+        assert_eq!(cell_of(Value::from("+1,2")), "\"'+1,2\"");
+        assert_eq!(cell_of(Value::from("-cam")), "'-cam");
+        assert_eq!(cell_of(Value::from(-12)), "-12");
+        assert_eq!(cell_of(Value::from("Main")), "Main");
+    }
+}
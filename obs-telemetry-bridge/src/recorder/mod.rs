@@ -0,0 +1,311 @@
+use crate::config::RecorderConfig;
+use crate::exporters::{SinkError, TelemetrySink};
+use crate::model::TelemetryFrame;
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub mod export;
+
+const FILE_PREFIX: &str = "frames-";
+/// Files of `crate::events`, rotated and pruned on their own.
+pub const EVENTS_PREFIX: &str = "events-";
+const FILE_EXT: &str = "jsonl";
+
+/// Appends telemetry frames as JSON lines to size-rotated files, pruning old files
+/// by count and age.
+pub struct Recorder {
+    dir: PathBuf,
+    prefix: &'static str,
+    max_file_bytes: u64,
+    max_files: usize,
+    retention: Option<Duration>,
+    interval: Option<Duration>,
+    current: Option<(BufWriter<File>, u64)>,
+}
+
+impl Recorder {
+    pub fn new(
+        dir: impl Into<PathBuf>,
+        max_file_bytes: u64,
+        max_files: usize,
+        retention_days: u64,
+    ) -> std::io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            prefix: FILE_PREFIX,
+            max_file_bytes: max_file_bytes.max(1),
+            max_files: max_files.max(1),
+            retention: (retention_days > 0)
+                .then(|| Duration::from_secs(retention_days * 24 * 60 * 60)),
+            interval: None,
+            current: None,
+        })
+    }
+
+    /// Writes files named `<prefix><timestamp>.jsonl` instead of frames.
+    pub fn with_prefix(mut self, prefix: &'static str) -> Self {
+        self.prefix = prefix;
+        self
+    }
+
+    pub fn from_config(config: &RecorderConfig) -> std::io::Result<Self> {
+        let mut recorder = Self::new(
+            recorder_dir(config),
+            config.max_file_mb.saturating_mul(1024 * 1024),
+            config.max_files,
+            config.retention_days,
+        )?;
+        recorder.interval =
+            (config.interval_ms > 0).then(|| Duration::from_millis(config.interval_ms));
+        tracing::info!(dir = %recorder.dir.display(), "frame recorder enabled");
+        Ok(recorder)
+    }
+
+    pub fn record(&mut self, frame: &TelemetryFrame) -> std::io::Result<()> {
+        self.append(frame)
+    }
+
+    pub fn append<T: Serialize>(&mut self, value: &T) -> std::io::Result<()> {
+        let mut line = serde_json::to_vec(value)?;
+        line.push(b'\n');
+
+        let needs_rotate = match &self.current {
+            Some((_, written)) => *written + line.len() as u64 > self.max_file_bytes,
+            None => true,
+        };
+        if needs_rotate {
+            self.rotate()?;
+        }
+
+        let (writer, written) = self.current.as_mut().expect("rotate opens a file");
+        writer.write_all(&line)?;
+        writer.flush()?;
+        *written += line.len() as u64;
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        if let Some((mut writer, _)) = self.current.take() {
+            writer.flush()?;
+        }
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let prefix = self.prefix;
+        let mut path = self.dir.join(format!("{prefix}{ts}.{FILE_EXT}"));
+        let mut suffix = 1;
+        while path.exists() {
+            path = self
+                .dir
+                .join(format!("{prefix}{ts}-{suffix}.{FILE_EXT}"));
+            suffix += 1;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        self.current = Some((BufWriter::new(file), 0));
+        self.prune()
+    }
+
+    /// Removes recordings beyond `max_files` (oldest first) and those older than the
+    /// retention window. The file being written is always kept.
+    fn prune(&self) -> std::io::Result<()> {
+        let mut files = recording_files(&self.dir, self.prefix)?;
+        files.sort_by_key(|(_, modified)| *modified);
+
+        let now = SystemTime::now();
+        let excess = files.len().saturating_sub(self.max_files);
+        for (idx, (path, modified)) in files.iter().enumerate() {
+            let expired = self.retention.is_some_and(|retention| {
+                now.duration_since(*modified).unwrap_or_default() > retention
+            });
+            let is_newest = idx + 1 == files.len();
+            if !is_newest && (idx < excess || expired) {
+                if let Err(err) = fs::remove_file(path) {
+                    tracing::warn!(path = %path.display(), error = %err, "recorder prune failed");
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn recording_files(dir: &Path, prefix: &str) -> std::io::Result<Vec<(PathBuf, SystemTime)>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if !name.starts_with(prefix)
+            || path.extension().and_then(|e| e.to_str()) != Some(FILE_EXT)
+        {
+            continue;
+        }
+        let modified = entry.metadata()?.modified().unwrap_or(UNIX_EPOCH);
+        files.push((path, modified));
+    }
+    Ok(files)
+}
+
+/// Where recordings for `config` live, whether or not recording is enabled.
+pub fn recorder_dir(config: &RecorderConfig) -> PathBuf {
+    config
+        .dir
+        .as_deref()
+        .map(PathBuf::from)
+        .unwrap_or_else(default_recorder_dir)
+}
+
+/// The last `count` recorded frames as JSON lines, oldest first.
+pub fn recent_frames(dir: &Path, count: usize) -> std::io::Result<Vec<String>> {
+    recent_lines(dir, FILE_PREFIX, count)
+}
+
+/// The last `count` lines of the `prefix` files, oldest first.
+pub fn recent_lines(dir: &Path, prefix: &str, count: usize) -> std::io::Result<Vec<String>> {
+    let mut files = recording_files(dir, prefix)?;
+    files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| b.0.cmp(&a.0)));
+    let mut frames = Vec::new();
+    for (path, _) in files {
+        if frames.len() >= count {
+            break;
+        }
+        let raw = fs::read_to_string(&path)?;
+        let lines: Vec<&str> = raw.lines().filter(|l| !l.is_empty()).collect();
+        let wanted = count - frames.len();
+        let newer = std::mem::take(&mut frames);
+        frames = lines[lines.len().saturating_sub(wanted)..]
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        frames.extend(newer);
+    }
+    Ok(frames)
+}
+
+/// Appends `line` to a single log file, first moving the file to `<path>.1`
+/// (replacing the previous one) once it has reached `max_bytes`, so however
+/// often it is written the log stays within about twice that.
+pub fn append_rotating(path: &Path, line: &str, max_bytes: u64) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::metadata(path).is_ok_and(|meta| meta.len() >= max_bytes) {
+        let mut rotated = path.as_os_str().to_owned();
+        rotated.push(".1");
+        fs::rename(path, PathBuf::from(rotated))?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{line}")
+}
+
+/// The complete lines within the last `max_bytes` of `path`, oldest first.
+pub fn tail_lines(path: &Path, max_bytes: u64) -> std::io::Result<Vec<String>> {
+    let mut file = File::open(path)?;
+    let start = file.metadata()?.len().saturating_sub(max_bytes);
+    file.seek(SeekFrom::Start(start))?;
+    let mut raw = Vec::new();
+    file.read_to_end(&mut raw)?;
+    let raw = String::from_utf8_lossy(&raw);
+    // Starting mid-file, the first line is most likely cut.
+    let skip = usize::from(start > 0);
+    Ok(raw
+        .lines()
+        .skip(skip)
+        .filter(|l| !l.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+fn default_recorder_dir() -> PathBuf {
+    let base = std::env::var("APPDATA").unwrap_or_else(|_| ".".to_string());
+    Path::new(&base).join("Telemy").join("recordings")
+}
+
+impl TelemetrySink for Recorder {
+    fn interval(&self) -> Option<Duration> {
+        self.interval
+    }
+
+    fn record(&mut self, frame: &TelemetryFrame) -> Result<(), SinkError> {
+        Recorder::record(self, frame)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("telemy-recorder-{}-{}", name, uuid::Uuid::new_v4()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn record_appends_json_lines() {
+        let dir = test_dir("append");
+        let mut recorder = Recorder::new(&dir, 1024 * 1024, 5, 0).unwrap();
+        for ts in 0..3 {
+            recorder
+                .record(&TelemetryFrame {
+                    timestamp_unix: ts,
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let files = recording_files(&dir, FILE_PREFIX).unwrap();
+        assert_eq!(files.len(), 1);
+        let raw = fs::read_to_string(&files[0].0).unwrap();
+        let lines: Vec<serde_json::Value> = raw
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[2]["timestamp_unix"], 2);
+
+        let recent = recent_frames(&dir, 2).unwrap();
+        assert_eq!(recent.len(), 2);
+        assert!(recent[0].contains("\"timestamp_unix\":1"));
+        assert_eq!(recent_frames(&dir, 10).unwrap().len(), 3);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn single_file_logs_rotate_and_reload_only_their_tail() {
+        let dir = test_dir("single");
+        let path = dir.join("audit.jsonl");
+        for i in 0..10 {
+            append_rotating(&path, &format!("line {i:02}"), 32).unwrap();
+        }
+        let rotated = dir.join("audit.jsonl.1");
+        assert!(fs::metadata(&path).unwrap().len() <= 32 + 8);
+        assert!(fs::metadata(&rotated).unwrap().len() <= 32 + 8);
+        assert_eq!(tail_lines(&path, 1024).unwrap().last().unwrap(), "line 09");
+
+        let tail = tail_lines(&path, 12).unwrap();
+        assert_eq!(tail, vec!["line 09".to_string()], "the cut line is dropped");
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn rotation_keeps_at_most_max_files() {
+        let dir = test_dir("rotate");
+        // Every frame exceeds the size limit, so each record opens a new file.
+        let mut recorder = Recorder::new(&dir, 1, 2, 0).unwrap();
+        for _ in 0..5 {
+            recorder.record(&TelemetryFrame::default()).unwrap();
+        }
+
+        assert_eq!(recording_files(&dir, FILE_PREFIX).unwrap().len(), 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
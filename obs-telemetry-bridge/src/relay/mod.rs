@@ -0,0 +1,282 @@
+use crate::aegis::{RelaySession, RelaySessionHandle};
+use crate::model::{RelayFrame, RelayLinkFrame};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, http::HeaderValue, Message};
+
+pub type RelayTelemetryHandle = Arc<Mutex<Option<RelayFrame>>>;
+
+const SESSION_POLL_INTERVAL: Duration = Duration::from_secs(2);
+const MAX_BACKOFF_MS: u64 = 30_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RelayTarget {
+    session_id: String,
+    ws_url: String,
+    ws_token: Option<String>,
+}
+
+/// Per-link ingest stats as pushed by the relay (same shape as the relay health payload).
+#[derive(Debug, Deserialize, Default)]
+struct RelayStatsMessage {
+    #[serde(default)]
+    ingest_active: bool,
+    #[serde(default)]
+    links: Vec<RelayLinkStats>,
+    #[serde(default)]
+    bonded: Option<RelayBondedStats>,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RelayLinkStats {
+    #[serde(default)]
+    link_id: String,
+    #[serde(default)]
+    label: Option<String>,
+    #[serde(default)]
+    status: Option<String>,
+    #[serde(default)]
+    bitrate_kbps: u32,
+    #[serde(default)]
+    rtt_ms: f32,
+    #[serde(default)]
+    packet_loss_pct: f32,
+    #[serde(default)]
+    jitter_ms: f32,
+    #[serde(default)]
+    reconnects: u32,
+}
+
+#[derive(Debug, Deserialize, Default)]
+struct RelayBondedStats {
+    #[serde(default)]
+    total_bitrate_kbps: Option<u32>,
+}
+
+pub fn spawn_relay_telemetry(aegis_session_snapshot: RelaySessionHandle) -> RelayTelemetryHandle {
+    let handle: RelayTelemetryHandle = Arc::new(Mutex::new(None));
+    let task_handle = handle.clone();
+    tokio::spawn(async move {
+        run_relay_telemetry(aegis_session_snapshot, task_handle).await;
+    });
+    handle
+}
+
+async fn run_relay_telemetry(
+    aegis_session_snapshot: RelaySessionHandle,
+    handle: RelayTelemetryHandle,
+) {
+    let mut backoff_ms = 1000u64;
+    loop {
+        let target = relay_target(aegis_session_snapshot.read().await.as_ref());
+        let Some(target) = target else {
+            *handle.lock().unwrap() = None;
+            backoff_ms = 1000;
+            tokio::time::sleep(SESSION_POLL_INTERVAL).await;
+            continue;
+        };
+
+        match ingest_relay_stats(&target, &aegis_session_snapshot, &handle).await {
+            Ok(()) => {
+                backoff_ms = 1000;
+                continue;
+            }
+            Err(err) => {
+                tracing::warn!(
+                    session_id = %target.session_id,
+                    error = %err,
+                    "relay telemetry connection failed"
+                );
+                if let Some(frame) = handle.lock().unwrap().as_mut() {
+                    frame.connected = false;
+                }
+            }
+        }
+
+        tokio::time::sleep(Duration::from_millis(backoff_ms)).await;
+        backoff_ms = (backoff_ms * 2).min(MAX_BACKOFF_MS);
+    }
+}
+
+/// Streams stats from the relay until the socket closes or the active session changes.
+async fn ingest_relay_stats(
+    target: &RelayTarget,
+    aegis_session_snapshot: &RelaySessionHandle,
+    handle: &RelayTelemetryHandle,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut request = target.ws_url.as_str().into_client_request()?;
+    if let Some(token) = &target.ws_token {
+        request.headers_mut().insert(
+            "Authorization",
+            HeaderValue::from_str(&format!("Bearer {token}"))?,
+        );
+    }
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await?;
+    tracing::info!(session_id = %target.session_id, "relay telemetry connected");
+
+    let mut session_check = tokio::time::interval(SESSION_POLL_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = session_check.tick() => {
+                let current = relay_target(aegis_session_snapshot.read().await.as_ref());
+                if current.as_ref() != Some(target) {
+                    tracing::info!(session_id = %target.session_id, "relay telemetry target changed");
+                    return Ok(());
+                }
+            }
+            msg = socket.next() => {
+                match msg {
+                    Some(Ok(Message::Text(text))) => match parse_relay_stats(&text) {
+                        Ok(frame) => *handle.lock().unwrap() = Some(frame),
+                        Err(err) => tracing::debug!(error = %err, "relay telemetry message ignored"),
+                    },
+                    Some(Ok(Message::Close(_))) | None => return Err("relay telemetry socket closed".into()),
+                    Some(Ok(_)) => {}
+                    Some(Err(err)) => return Err(err.into()),
+                }
+            }
+        }
+    }
+}
+
+fn relay_target(session: Option<&RelaySession>) -> Option<RelayTarget> {
+    let session = session?;
+    if !matches!(session.status.as_str(), "active" | "grace") {
+        return None;
+    }
+    let ws_url = session.relay.as_ref()?.ws_url.as_deref()?.trim();
+    if ws_url.is_empty() {
+        return None;
+    }
+    Some(RelayTarget {
+        session_id: session.session_id.clone(),
+        ws_url: ws_url.to_string(),
+        ws_token: session
+            .credentials
+            .as_ref()
+            .and_then(|c| c.relay_ws_token.clone())
+            .filter(|t| !t.trim().is_empty()),
+    })
+}
+
+fn parse_relay_stats(text: &str) -> Result<RelayFrame, serde_json::Error> {
+    let msg: RelayStatsMessage = serde_json::from_str(text)?;
+
+    let live: Vec<&RelayLinkStats> = msg
+        .links
+        .iter()
+        .filter(|l| l.status.as_deref() != Some("disconnected"))
+        .collect();
+    let link_bitrate_sum = live
+        .iter()
+        .fold(0u32, |acc, l| acc.saturating_add(l.bitrate_kbps));
+    let (rtt_ms, packet_loss_pct) = if live.is_empty() {
+        (0.0, 0.0)
+    } else {
+        let n = live.len() as f32;
+        (
+            live.iter().map(|l| l.rtt_ms).sum::<f32>() / n,
+            live.iter().map(|l| l.packet_loss_pct).sum::<f32>() / n,
+        )
+    };
+
+    Ok(RelayFrame {
+        connected: true,
+        ingest_active: msg.ingest_active,
+        bitrate_kbps: msg
+            .bonded
+            .and_then(|b| b.total_bitrate_kbps)
+            .unwrap_or(link_bitrate_sum),
+        rtt_ms,
+        packet_loss_pct,
+        reconnects: msg.links.iter().map(|l| l.reconnects).sum(),
+        links: msg
+            .links
+            .into_iter()
+            .map(|l| RelayLinkFrame {
+                label: l.label.unwrap_or_else(|| l.link_id.clone()),
+                link_id: l.link_id,
+                status: l.status.unwrap_or_else(|| "active".to_string()),
+                bitrate_kbps: l.bitrate_kbps,
+                rtt_ms: l.rtt_ms,
+                packet_loss_pct: l.packet_loss_pct,
+                jitter_ms: l.jitter_ms,
+                reconnects: l.reconnects,
+            })
+            .collect(),
+        updated_unix: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aegis::{RelayCredentials, RelayEndpoint};
+
+    #[test]
+    fn parse_aggregates_live_links_and_prefers_bonded_total() {
+        let frame = parse_relay_stats(
+            r#"{
+                "ingest_active": true,
+                "links": [
+                    { "link_id": "link_01", "label": "T-Mobile 5G", "status": "active",
+                      "bitrate_kbps": 8200, "rtt_ms": 40, "packet_loss_pct": 0.2, "reconnects": 1 },
+                    { "link_id": "link_02", "status": "degraded",
+                      "bitrate_kbps": 1300, "rtt_ms": 120, "packet_loss_pct": 2.0, "reconnects": 3 },
+                    { "link_id": "link_03", "status": "disconnected", "rtt_ms": 999 }
+                ],
+                "bonded": { "total_bitrate_kbps": 9600 }
+            }"#,
+        )
+        .unwrap();
+
+        assert!(frame.connected);
+        assert!(frame.ingest_active);
+        assert_eq!(frame.bitrate_kbps, 9600);
+        assert_eq!(frame.rtt_ms, 80.0);
+        assert_eq!(frame.packet_loss_pct, 1.1);
+        assert_eq!(frame.reconnects, 4);
+        assert_eq!(frame.links.len(), 3);
+        assert_eq!(frame.links[1].label, "link_02");
+    }
+
+    #[test]
+    fn parse_sums_link_bitrate_without_bonded_section() {
+        let frame = parse_relay_stats(
+            r#"{ "links": [ { "link_id": "a", "bitrate_kbps": 100 }, { "link_id": "b", "bitrate_kbps": 250 } ] }"#,
+        )
+        .unwrap();
+        assert_eq!(frame.bitrate_kbps, 350);
+    }
+
+    #[test]
+    fn relay_target_requires_active_session_with_ws_url() {
+        let mut session = RelaySession {
+            session_id: "ses_1".to_string(),
+            status: "provisioning".to_string(),
+            relay: Some(RelayEndpoint {
+                ws_url: Some("wss://203.0.113.10:7443/telemetry".to_string()),
+                ..Default::default()
+            }),
+            credentials: Some(RelayCredentials {
+                relay_ws_token: Some("ws-token".to_string()),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        assert!(relay_target(Some(&session)).is_none());
+
+        session.status = "active".to_string();
+        let target = relay_target(Some(&session)).unwrap();
+        assert_eq!(target.ws_url, "wss://203.0.113.10:7443/telemetry");
+        assert_eq!(target.ws_token.as_deref(), Some("ws-token"));
+
+        assert!(relay_target(None).is_none());
+    }
+}
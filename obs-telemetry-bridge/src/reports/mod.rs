@@ -0,0 +1,509 @@
+use crate::config::ReportsConfig;
+use crate::model::TelemetryFrame;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tokio::sync::watch;
+
+const TIMELINE_BUCKET_SECS: u64 = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionReport {
+    pub id: String,
+    pub started_unix: u64,
+    pub ended_unix: u64,
+    pub duration_secs: u64,
+    pub avg_bitrate_kbps: u32,
+    pub peak_bitrate_kbps: u32,
+    pub total_dropped_frames: u64,
+    pub avg_health: f32,
+    pub min_health: f32,
+    pub health_timeline: Vec<HealthPoint>,
+    pub disconnect_events: Vec<DisconnectEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthPoint {
+    pub ts_unix: u64,
+    pub health: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DisconnectEvent {
+    /// `obs` (WebSocket to OBS lost) or `relay` (relay telemetry lost).
+    pub kind: String,
+    pub started_unix: u64,
+    pub duration_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportSummary {
+    pub id: String,
+    pub started_unix: u64,
+    pub duration_secs: u64,
+    pub avg_bitrate_kbps: u32,
+    pub total_dropped_frames: u64,
+}
+
+impl From<&SessionReport> for ReportSummary {
+    fn from(report: &SessionReport) -> Self {
+        Self {
+            id: report.id.clone(),
+            started_unix: report.started_unix,
+            duration_secs: report.duration_secs,
+            avg_bitrate_kbps: report.avg_bitrate_kbps,
+            total_dropped_frames: report.total_dropped_frames,
+        }
+    }
+}
+
+struct ReportBuilder {
+    started_unix: u64,
+    last_unix: u64,
+    dropped_at_start: u64,
+    dropped_latest: u64,
+    samples: u64,
+    bitrate_sum: u64,
+    peak_bitrate_kbps: u32,
+    health_sum: f64,
+    min_health: f32,
+    bucket: Option<(u64, f64, u32)>,
+    health_timeline: Vec<HealthPoint>,
+    disconnect_events: Vec<DisconnectEvent>,
+    obs_down_since: Option<u64>,
+    relay_down_since: Option<u64>,
+}
+
+impl ReportBuilder {
+    fn new(frame: &TelemetryFrame) -> Self {
+        Self {
+            started_unix: frame.timestamp_unix,
+            last_unix: frame.timestamp_unix,
+            dropped_at_start: frame.obs.total_dropped_frames,
+            dropped_latest: frame.obs.total_dropped_frames,
+            samples: 0,
+            bitrate_sum: 0,
+            peak_bitrate_kbps: 0,
+            health_sum: 0.0,
+            min_health: 1.0,
+            bucket: None,
+            health_timeline: Vec::new(),
+            disconnect_events: Vec::new(),
+            obs_down_since: None,
+            relay_down_since: None,
+        }
+    }
+
+    fn observe(&mut self, frame: &TelemetryFrame) {
+        let ts = frame.timestamp_unix;
+        self.last_unix = ts;
+
+        track_outage(
+            &mut self.obs_down_since,
+            &mut self.disconnect_events,
+            "obs",
+            !frame.obs.connected,
+            ts,
+        );
+        if let Some(relay) = &frame.relay {
+            track_outage(
+                &mut self.relay_down_since,
+                &mut self.disconnect_events,
+                "relay",
+                !relay.connected,
+                ts,
+            );
+        }
+        if !frame.obs.connected {
+            return;
+        }
+
+        let bitrate: u32 = frame.streams.iter().map(|s| s.bitrate_kbps).sum();
+        self.samples += 1;
+        self.bitrate_sum += bitrate as u64;
+        self.peak_bitrate_kbps = self.peak_bitrate_kbps.max(bitrate);
+        self.health_sum += frame.health as f64;
+        self.min_health = self.min_health.min(frame.health);
+        // OBS resets its counters on reconnect; keep the largest total seen.
+        self.dropped_latest = self.dropped_latest.max(frame.obs.total_dropped_frames);
+
+        let bucket_start = ts - ts % TIMELINE_BUCKET_SECS;
+        match &mut self.bucket {
+            Some((start, sum, count)) if *start == bucket_start => {
+                *sum += frame.health as f64;
+                *count += 1;
+            }
+            _ => {
+                self.flush_bucket();
+                self.bucket = Some((bucket_start, frame.health as f64, 1));
+            }
+        }
+    }
+
+    fn flush_bucket(&mut self) {
+        if let Some((start, sum, count)) = self.bucket.take() {
+            self.health_timeline.push(HealthPoint {
+                ts_unix: start,
+                health: (sum / count as f64) as f32,
+            });
+        }
+    }
+
+    fn finish(mut self) -> SessionReport {
+        self.flush_bucket();
+        let end = self.last_unix;
+        track_outage(
+            &mut self.obs_down_since,
+            &mut self.disconnect_events,
+            "obs",
+            false,
+            end,
+        );
+        track_outage(
+            &mut self.relay_down_since,
+            &mut self.disconnect_events,
+            "relay",
+            false,
+            end,
+        );
+
+        let samples = self.samples.max(1);
+        SessionReport {
+            id: format!("session-{}", self.started_unix),
+            started_unix: self.started_unix,
+            ended_unix: end,
+            duration_secs: end.saturating_sub(self.started_unix),
+            avg_bitrate_kbps: (self.bitrate_sum / samples) as u32,
+            peak_bitrate_kbps: self.peak_bitrate_kbps,
+            total_dropped_frames: self.dropped_latest.saturating_sub(self.dropped_at_start),
+            avg_health: if self.samples == 0 {
+                0.0
+            } else {
+                (self.health_sum / samples as f64) as f32
+            },
+            min_health: if self.samples == 0 {
+                0.0
+            } else {
+                self.min_health
+            },
+            health_timeline: self.health_timeline,
+            disconnect_events: self.disconnect_events,
+        }
+    }
+}
+
+fn track_outage(
+    down_since: &mut Option<u64>,
+    events: &mut Vec<DisconnectEvent>,
+    kind: &str,
+    is_down: bool,
+    ts: u64,
+) {
+    match (*down_since, is_down) {
+        (None, true) => *down_since = Some(ts),
+        (Some(started), false) => {
+            events.push(DisconnectEvent {
+                kind: kind.to_string(),
+                started_unix: started,
+                duration_secs: ts.saturating_sub(started),
+            });
+            *down_since = None;
+        }
+        _ => {}
+    }
+}
+
+/// Watches frames for streaming start/stop edges and yields a report when a stream ends.
+#[derive(Default)]
+pub struct ReportTracker {
+    current: Option<ReportBuilder>,
+}
+
+impl ReportTracker {
+    pub fn observe(&mut self, frame: &TelemetryFrame) -> Option<SessionReport> {
+        match &mut self.current {
+            None if frame.obs.streaming => {
+                let mut builder = ReportBuilder::new(frame);
+                builder.observe(frame);
+                self.current = Some(builder);
+                None
+            }
+            None => None,
+            // Losing the OBS connection is recorded as an outage, not as the end of the stream.
+            Some(builder) if frame.obs.streaming || !frame.obs.connected => {
+                builder.observe(frame);
+                None
+            }
+            Some(builder) => {
+                builder.last_unix = frame.timestamp_unix;
+                self.current.take().map(ReportBuilder::finish)
+            }
+        }
+    }
+}
+
+pub struct ReportStore {
+    dir: PathBuf,
+    max_reports: usize,
+}
+
+impl ReportStore {
+    pub fn new(dir: impl Into<PathBuf>, max_reports: usize) -> Self {
+        Self {
+            dir: dir.into(),
+            max_reports: max_reports.max(1),
+        }
+    }
+
+    pub fn from_config(config: &ReportsConfig) -> Self {
+        let dir = config
+            .dir
+            .as_deref()
+            .map(PathBuf::from)
+            .unwrap_or_else(default_reports_dir);
+        Self::new(dir, config.max_reports)
+    }
+
+    pub fn save(&self, report: &SessionReport) -> std::io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(
+            self.dir.join(format!("{}.json", report.id)),
+            serde_json::to_vec_pretty(report)?,
+        )?;
+        fs::write(
+            self.dir.join(format!("{}.html", report.id)),
+            render_html(report),
+        )?;
+        self.prune()
+    }
+
+    /// Most recent first.
+    pub fn list(&self) -> Vec<ReportSummary> {
+        let mut reports: Vec<ReportSummary> = self
+            .report_ids()
+            .iter()
+            .filter_map(|id| self.load(id))
+            .map(|r| ReportSummary::from(&r))
+            .collect();
+        reports.sort_by_key(|r| std::cmp::Reverse(r.started_unix));
+        reports
+    }
+
+    pub fn load(&self, id: &str) -> Option<SessionReport> {
+        if !is_valid_report_id(id) {
+            return None;
+        }
+        let raw = fs::read_to_string(self.dir.join(format!("{id}.json"))).ok()?;
+        serde_json::from_str(&raw).ok()
+    }
+
+    pub fn load_html(&self, id: &str) -> Option<String> {
+        if !is_valid_report_id(id) {
+            return None;
+        }
+        fs::read_to_string(self.dir.join(format!("{id}.html"))).ok()
+    }
+
+    fn report_ids(&self) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let name = e.file_name().to_string_lossy().into_owned();
+                name.strip_suffix(".json").map(str::to_string)
+            })
+            .filter(|id| is_valid_report_id(id))
+            .collect()
+    }
+
+    fn prune(&self) -> std::io::Result<()> {
+        let reports = self.list();
+        for stale in reports.iter().skip(self.max_reports) {
+            for ext in ["json", "html"] {
+                let path = self.dir.join(format!("{}.{}", stale.id, ext));
+                if path.exists() {
+                    fs::remove_file(path)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_valid_report_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+fn default_reports_dir() -> PathBuf {
+    let base = std::env::var("APPDATA").unwrap_or_else(|_| ".".to_string());
+    Path::new(&base).join("Telemy").join("reports")
+}
+
+fn render_html(report: &SessionReport) -> String {
+    let timeline: String = report
+        .health_timeline
+        .iter()
+        .map(|p| {
+            let pct = (p.health.clamp(0.0, 1.0) * 100.0).round();
+            format!(
+                "<div class=\"bar\" style=\"height:{pct}%\" title=\"{} health {pct}%\"></div>",
+                p.ts_unix
+            )
+        })
+        .collect();
+    let disconnects: String = if report.disconnect_events.is_empty() {
+        "<li>None</li>".to_string()
+    } else {
+        report
+            .disconnect_events
+            .iter()
+            .map(|e| {
+                format!(
+                    "<li>{} lost at {} for {}s</li>",
+                    e.kind, e.started_unix, e.duration_secs
+                )
+            })
+            .collect()
+    };
+
+    format!(
+        r#"<!doctype html>
+<html>
+<head>
+  <meta charset="utf-8" />
+  <title>Telemy Stream Report {id}</title>
+  <style>
+    body {{ font-family: Arial, sans-serif; background: #0b0e12; color: #e6edf3; margin: 24px; }}
+    table {{ border-collapse: collapse; }}
+    td {{ padding: 4px 12px 4px 0; }}
+    td:first-child {{ color: #8da3c1; }}
+    .timeline {{ display: flex; align-items: flex-end; gap: 1px; height: 120px; border: 1px solid #1f2a3a; padding: 4px; }}
+    .bar {{ flex: 1; background: #33d17a; min-width: 2px; }}
+  </style>
+</head>
+<body>
+  <h1>Stream Report</h1>
+  <table>
+    <tr><td>Started (unix)</td><td>{started}</td></tr>
+    <tr><td>Duration</td><td>{duration}s</td></tr>
+    <tr><td>Avg bitrate</td><td>{avg_bitrate} kbps</td></tr>
+    <tr><td>Peak bitrate</td><td>{peak_bitrate} kbps</td></tr>
+    <tr><td>Dropped frames</td><td>{dropped}</td></tr>
+    <tr><td>Avg / min health</td><td>{avg_health:.0}% / {min_health:.0}%</td></tr>
+  </table>
+  <h2>Health timeline</h2>
+  <div class="timeline">{timeline}</div>
+  <h2>Disconnects</h2>
+  <ul>{disconnects}</ul>
+</body>
+</html>
+"#,
+        id = report.id,
+        started = report.started_unix,
+        duration = report.duration_secs,
+        avg_bitrate = report.avg_bitrate_kbps,
+        peak_bitrate = report.peak_bitrate_kbps,
+        dropped = report.total_dropped_frames,
+        avg_health = report.avg_health * 100.0,
+        min_health = report.min_health * 100.0,
+    )
+}
+
+pub fn spawn_report_writer(config: &ReportsConfig, mut rx: watch::Receiver<TelemetryFrame>) {
+    let store = ReportStore::from_config(config);
+    tokio::spawn(async move {
+        let mut tracker = ReportTracker::default();
+        while rx.changed().await.is_ok() {
+            let frame = rx.borrow_and_update().clone();
+            if let Some(report) = tracker.observe(&frame) {
+                match store.save(&report) {
+                    Ok(()) => tracing::info!(report_id = %report.id, "stream report saved"),
+                    Err(err) => tracing::warn!(error = %err, "stream report save failed"),
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ObsFrame, StreamOutput};
+
+    fn frame(
+        ts: u64,
+        streaming: bool,
+        connected: bool,
+        bitrate: u32,
+        dropped: u64,
+    ) -> TelemetryFrame {
+        TelemetryFrame {
+            timestamp_unix: ts,
+            health: 0.8,
+            obs: ObsFrame {
+                connected,
+                streaming,
+                total_dropped_frames: dropped,
+                ..Default::default()
+            },
+            streams: vec![StreamOutput {
+                bitrate_kbps: bitrate,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn tracker_emits_report_on_streaming_falling_edge() {
+        let mut tracker = ReportTracker::default();
+        assert!(tracker.observe(&frame(95, false, true, 0, 0)).is_none());
+        assert!(tracker.observe(&frame(100, true, true, 4000, 10)).is_none());
+        assert!(tracker.observe(&frame(110, true, true, 6000, 15)).is_none());
+        // OBS connection drop mid-stream must not end the session.
+        assert!(tracker.observe(&frame(115, false, false, 0, 0)).is_none());
+        assert!(tracker.observe(&frame(120, true, true, 5000, 25)).is_none());
+
+        let report = tracker.observe(&frame(130, false, true, 0, 25)).unwrap();
+        assert_eq!(report.id, "session-100");
+        assert_eq!(report.duration_secs, 30);
+        assert_eq!(report.avg_bitrate_kbps, 5000);
+        assert_eq!(report.peak_bitrate_kbps, 6000);
+        assert_eq!(report.total_dropped_frames, 15);
+        assert_eq!(report.disconnect_events.len(), 1);
+        assert_eq!(report.disconnect_events[0].kind, "obs");
+        assert_eq!(report.disconnect_events[0].duration_secs, 5);
+        assert_eq!(report.health_timeline.len(), 3);
+
+        assert!(tracker.observe(&frame(140, false, true, 0, 25)).is_none());
+    }
+
+    #[test]
+    fn store_round_trips_and_rejects_path_ids() {
+        let dir = std::env::temp_dir().join(format!("telemy-reports-{}", uuid::Uuid::new_v4()));
+        let store = ReportStore::new(&dir, 1);
+
+        let mut tracker = ReportTracker::default();
+        tracker.observe(&frame(100, true, true, 4000, 0));
+        let first = tracker.observe(&frame(160, false, true, 0, 0)).unwrap();
+        tracker.observe(&frame(200, true, true, 4000, 0));
+        let second = tracker.observe(&frame(260, false, true, 0, 0)).unwrap();
+        store.save(&first).unwrap();
+        store.save(&second).unwrap();
+
+        let listed = store.list();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, "session-200");
+        assert!(store
+            .load_html("session-200")
+            .unwrap()
+            .contains("Stream Report"));
+        assert!(store.load("../session-200").is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
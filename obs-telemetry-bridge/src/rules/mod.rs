@@ -0,0 +1,571 @@
+use crate::activity::ActionKind;
+use crate::aegis::RelaySessionHandle;
+use crate::config::{CompareOp, Config, RuleAction, RuleConfig};
+use crate::ipc::OverrideStoreHandle;
+use crate::metrics::ObsLink;
+use crate::model::{ObsConnectionState, StreamOutput, TelemetryFrame, TrendState};
+use crate::security::VaultHandle;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+
+const AUDIT_MEMORY_CAPACITY: usize = 500;
+
+type MetricReader = fn(&TelemetryFrame) -> Option<f64>;
+
+/// Metrics a rule condition can compare. Booleans read as 1/0; relay metrics are
+/// absent (and never match) while no relay is attached.
+pub const METRICS: &[(&str, MetricReader)] = &[
+    ("health", |f| Some(f.health as f64)),
+    ("health_trend.degrading", |f| {
+        Some(flag(f.health_trend.state == TrendState::Degrading))
+    }),
+    ("obs.connected", |f| Some(flag(f.obs.connected))),
+    ("obs.streaming", |f| Some(flag(f.obs.streaming))),
+    ("obs.recording", |f| Some(flag(f.obs.recording))),
+    ("obs.active_fps", |f| Some(f.obs.active_fps as f64)),
+    ("obs.total_dropped_frames", |f| {
+        Some(f.obs.total_dropped_frames as f64)
+    }),
+    ("obs.render_missed_frames", |f| {
+        Some(f.obs.render_missed_frames as f64)
+    }),
+    ("obs.available_disk_space_mb", |f| {
+        Some(f.obs.available_disk_space_mb)
+    }),
+    ("system.cpu_percent", |f| Some(f.system.cpu_percent as f64)),
+    ("system.mem_percent", |f| Some(f.system.mem_percent as f64)),
+    ("system.gpu_percent", |f| {
+        f.system.gpu_percent.map(f64::from)
+    }),
+    ("system.gpu_temp_c", |f| f.system.gpu_temp_c.map(f64::from)),
+    ("system.encoder_percent", |f| {
+        f.system.encoder_percent.map(f64::from)
+    }),
+    ("network.upload_mbps", |f| {
+        Some(f.network.upload_mbps as f64)
+    }),
+    ("network.latency_ms", |f| Some(f.network.latency_ms as f64)),
+    ("stream.bitrate_kbps", |f| {
+        main_output(f).map(|o| o.bitrate_kbps as f64)
+    }),
+    ("stream.drop_pct", |f| {
+        main_output(f).map(|o| o.drop_pct as f64)
+    }),
+    ("stream.encoding_lag_ms", |f| {
+        main_output(f).map(|o| o.encoding_lag_ms as f64)
+    }),
+    ("stream.avg_bitrate_1m_kbps", |f| {
+        f.stream_stats.avg_bitrate_1m_kbps.map(f64::from)
+    }),
+    ("stream.avg_bitrate_5m_kbps", |f| {
+        f.stream_stats.avg_bitrate_5m_kbps.map(f64::from)
+    }),
+    ("stream.bitrate_stability", |f| {
+        f.stream_stats.bitrate_stability.map(f64::from)
+    }),
+    ("stream.drops_per_min", |f| {
+        f.stream_stats.drops_per_min.map(f64::from)
+    }),
+    ("stream.frame_time_p95_ms", |f| {
+        f.stream_stats.frame_time_p95_ms.map(f64::from)
+    }),
+    ("relay.connected", |f| {
+        f.relay.as_ref().map(|r| flag(r.connected))
+    }),
+    ("relay.bitrate_kbps", |f| {
+        f.relay.as_ref().map(|r| r.bitrate_kbps as f64)
+    }),
+    ("relay.rtt_ms", |f| {
+        f.relay.as_ref().map(|r| r.rtt_ms as f64)
+    }),
+    ("relay.packet_loss_pct", |f| {
+        f.relay.as_ref().map(|r| r.packet_loss_pct as f64)
+    }),
+    ("audio.clipping_inputs", |f| {
+        Some(f.audio.inputs.iter().filter(|a| a.clipping).count() as f64)
+    }),
+    ("audio.muted_inputs", |f| {
+        Some(f.audio.inputs.iter().filter(|a| a.muted).count() as f64)
+    }),
+];
+
+pub fn is_known_metric(name: &str) -> bool {
+    METRICS.iter().any(|(known, _)| *known == name)
+}
+
+fn metric_value(frame: &TelemetryFrame, name: &str) -> Option<f64> {
+    METRICS
+        .iter()
+        .find(|(known, _)| *known == name)
+        .and_then(|(_, read)| read(frame))
+}
+
+fn flag(value: bool) -> f64 {
+    u8::from(value) as f64
+}
+
+/// The main stream output, or else the first one carrying data.
+pub fn main_output(frame: &TelemetryFrame) -> Option<&StreamOutput> {
+    frame
+        .streams
+        .iter()
+        .find(|o| o.name == "adv_stream" || o.name == "simple_stream")
+        .or_else(|| frame.streams.iter().find(|o| o.bitrate_kbps > 0))
+}
+
+fn compare(op: CompareOp, left: f64, right: f64) -> bool {
+    match op {
+        CompareOp::Lt => left < right,
+        CompareOp::Le => left <= right,
+        CompareOp::Gt => left > right,
+        CompareOp::Ge => left >= right,
+        CompareOp::Eq => left == right,
+        CompareOp::Ne => left != right,
+    }
+}
+
+struct RuleState {
+    config: RuleConfig,
+    /// When each condition started holding continuously.
+    held_since: Vec<Option<Instant>>,
+    /// Set while all conditions hold, so a rule fires once per episode.
+    triggered: bool,
+    last_fired: Option<Instant>,
+    last_fired_unix: Option<u64>,
+}
+
+/// Edge-triggered evaluation of the configured rules against each frame.
+pub struct RuleEngine {
+    rules: Vec<RuleState>,
+}
+
+impl RuleEngine {
+    pub fn new(rules: &[RuleConfig]) -> Self {
+        Self {
+            rules: rules
+                .iter()
+                .filter(|r| r.enabled)
+                .map(|r| RuleState {
+                    config: r.clone(),
+                    held_since: vec![None; r.when.len()],
+                    triggered: false,
+                    last_fired: None,
+                    last_fired_unix: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the rules that fire on this frame.
+    pub fn evaluate(&mut self, frame: &TelemetryFrame, now: Instant) -> Vec<RuleConfig> {
+        let mut fired = Vec::new();
+        for rule in &mut self.rules {
+            let mut all_held = true;
+            for (cond, since) in rule.config.when.iter().zip(rule.held_since.iter_mut()) {
+                let holds = metric_value(frame, &cond.metric)
+                    .is_some_and(|v| compare(cond.op, v, cond.value));
+                if !holds {
+                    *since = None;
+                    all_held = false;
+                    continue;
+                }
+                let since = *since.get_or_insert(now);
+                if now.duration_since(since) < Duration::from_secs(cond.for_secs) {
+                    all_held = false;
+                }
+            }
+
+            if !all_held {
+                rule.triggered = false;
+                continue;
+            }
+            let cooling = rule.last_fired.is_some_and(|at| {
+                now.duration_since(at) < Duration::from_secs(rule.config.cooldown_secs)
+            });
+            if rule.triggered || cooling {
+                continue;
+            }
+            rule.triggered = true;
+            rule.last_fired = Some(now);
+            rule.last_fired_unix = Some(now_unix());
+            fired.push(rule.config.clone());
+        }
+        fired
+    }
+
+    pub fn status(&self) -> Vec<RuleStatus> {
+        self.rules
+            .iter()
+            .map(|r| RuleStatus {
+                name: r.config.name.clone(),
+                triggered: r.triggered,
+                last_fired_unix: r.last_fired_unix,
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuleStatus {
+    pub name: String,
+    pub triggered: bool,
+    pub last_fired_unix: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleExecution {
+    pub rule: String,
+    pub action: String,
+    pub ok: bool,
+    pub detail: String,
+    pub timestamp_unix: u64,
+}
+
+/// Rule action executions, kept in memory for `/api/v1/rules` and appended as
+/// JSONL to `rules-audit.jsonl`.
+pub struct RuleAudit {
+    entries: Mutex<VecDeque<RuleExecution>>,
+    path: Option<PathBuf>,
+}
+
+impl RuleAudit {
+    pub fn open(path: PathBuf) -> Self {
+        let mut entries = VecDeque::new();
+        if let Ok(raw) = fs::read_to_string(&path) {
+            for line in raw.lines() {
+                if let Ok(entry) = serde_json::from_str::<RuleExecution>(line) {
+                    if entries.len() == AUDIT_MEMORY_CAPACITY {
+                        entries.pop_front();
+                    }
+                    entries.push_back(entry);
+                }
+            }
+        }
+        Self {
+            entries: Mutex::new(entries),
+            path: Some(path),
+        }
+    }
+
+    fn record(&self, entry: RuleExecution) {
+        if entry.ok {
+            tracing::info!(rule = %entry.rule, action = %entry.action, detail = %entry.detail, "rule action executed");
+        } else {
+            tracing::warn!(rule = %entry.rule, action = %entry.action, error = %entry.detail, "rule action failed");
+        }
+
+        if let Some(file) = &self.path {
+            if let Err(err) = append_line(file, &entry) {
+                tracing::warn!("rules audit log write failed: {}", err);
+            }
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() == AUDIT_MEMORY_CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Most recent executions first.
+    pub fn recent(&self, limit: usize) -> Vec<RuleExecution> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+fn append_line(path: &Path, entry: &RuleExecution) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", serde_json::to_string(entry)?)
+}
+
+pub fn default_audit_path() -> PathBuf {
+    let base = std::env::var("APPDATA").unwrap_or_else(|_| ".".to_string());
+    Path::new(&base).join("Telemy").join("rules-audit.jsonl")
+}
+
+/// What the server reads: per-rule state plus the execution audit trail.
+pub struct RulesState {
+    pub audit: RuleAudit,
+    status: Mutex<Vec<RuleStatus>>,
+}
+
+impl RulesState {
+    pub fn status(&self) -> Vec<RuleStatus> {
+        self.status.lock().unwrap().clone()
+    }
+}
+
+/// Everything a rule action may touch.
+pub struct RuleContext {
+    pub obs: ObsLink,
+    pub config: Config,
+    pub vault: VaultHandle,
+    pub aegis_session_snapshot: RelaySessionHandle,
+    pub overrides: OverrideStoreHandle,
+}
+
+pub fn spawn(
+    ctx: RuleContext,
+    mut rx: watch::Receiver<TelemetryFrame>,
+    audit_path: PathBuf,
+) -> Arc<RulesState> {
+    let mut engine = RuleEngine::new(&ctx.config.rules);
+    let state = Arc::new(RulesState {
+        audit: RuleAudit::open(audit_path),
+        status: Mutex::new(engine.status()),
+    });
+    if engine.rules.is_empty() {
+        return state;
+    }
+    tracing::info!(rules = engine.rules.len(), "rules engine started");
+
+    let ctx = Arc::new(ctx);
+    let task_state = state.clone();
+    tokio::spawn(async move {
+        while rx.changed().await.is_ok() {
+            let frame = rx.borrow_and_update().clone();
+            let fired = engine.evaluate(&frame, Instant::now());
+            if fired.is_empty() {
+                continue;
+            }
+            *task_state.status.lock().unwrap() = engine.status();
+            for rule in fired {
+                // Actions may wait on OBS or Aegis; keep evaluating meanwhile.
+                tokio::spawn(run_actions(ctx.clone(), task_state.clone(), rule));
+            }
+        }
+    });
+    state
+}
+
+async fn run_actions(ctx: Arc<RuleContext>, state: Arc<RulesState>, rule: RuleConfig) {
+    for action in &rule.actions {
+        // Alerts only inform, so they still go out under the manual override.
+        let held = !matches!(action, RuleAction::Alert { .. }) && ctx.overrides.manual_override();
+        let result = if held {
+            Err("skipped: manual override is on".to_string())
+        } else {
+            execute(&ctx, action).await
+        };
+        // Alerts and relay starts log themselves once they run.
+        let logs_itself = matches!(action, RuleAction::Alert { .. } | RuleAction::StartRelay);
+        if held || !logs_itself {
+            crate::activity::log().record(
+                activity_kind(action),
+                &format!("rule:{}", rule.name),
+                None,
+                result.clone(),
+            );
+        }
+        let ok = result.is_ok();
+        state.audit.record(RuleExecution {
+            rule: rule.name.clone(),
+            action: action_name(action).to_string(),
+            ok,
+            detail: result.unwrap_or_else(|err| err),
+            timestamp_unix: now_unix(),
+        });
+    }
+}
+
+fn action_name(action: &RuleAction) -> &'static str {
+    match action {
+        RuleAction::SwitchScene { .. } => "switch_scene",
+        RuleAction::StartRelay => "start_relay",
+        RuleAction::Alert { .. } => "alert",
+        RuleAction::RunCommand { .. } => "run_command",
+        RuleAction::ToggleSource { .. } => "toggle_source",
+    }
+}
+
+fn activity_kind(action: &RuleAction) -> ActionKind {
+    match action {
+        RuleAction::SwitchScene { .. } => ActionKind::SwitchScene,
+        RuleAction::StartRelay => ActionKind::RelayStart,
+        RuleAction::Alert { .. } => ActionKind::Alert,
+        RuleAction::RunCommand { .. } => ActionKind::RunCommand,
+        RuleAction::ToggleSource { .. } => ActionKind::ToggleSource,
+    }
+}
+
+async fn execute(ctx: &RuleContext, action: &RuleAction) -> Result<String, String> {
+    match action {
+        RuleAction::SwitchScene { scene } => ctx
+            .obs
+            .switch_scene(scene)
+            .await
+            .map(|()| format!("switched to '{scene}'")),
+        RuleAction::StartRelay => {
+            let live = ctx
+                .aegis_session_snapshot
+                .read()
+                .await
+                .as_ref()
+                .is_some_and(|s| matches!(s.status.as_str(), "provisioning" | "active" | "grace"));
+            if live {
+                return Ok("relay already live".to_string());
+            }
+            let session = crate::app::relay_start(
+                &ctx.config,
+                &ctx.vault,
+                "rule",
+                ctx.overrides.get().mode,
+                ctx.obs.status().0 == ObsConnectionState::Connected,
+            )
+            .await?;
+            let detail = format!("relay session {} {}", session.session_id, session.status);
+            *ctx.aegis_session_snapshot.write().await = Some(session);
+            Ok(detail)
+        }
+        RuleAction::Alert { message } => {
+            crate::notify::alert(&ctx.config.notifications, message);
+            Ok(message.clone())
+        }
+        RuleAction::RunCommand { command, args } => {
+            let mut child = std::process::Command::new(command)
+                .args(args)
+                .spawn()
+                .map_err(|err| format!("failed to run '{command}': {err}"))?;
+            let pid = child.id();
+            // Reap in the background so finished commands do not linger.
+            std::thread::spawn(move || {
+                let _ = child.wait();
+            });
+            Ok(format!("started '{command}' (pid {pid})"))
+        }
+        RuleAction::ToggleSource {
+            scene,
+            source,
+            enabled,
+        } => ctx
+            .obs
+            .set_source_enabled(scene, source, *enabled)
+            .await
+            .map(|shown| {
+                format!(
+                    "'{source}' in '{scene}' {}",
+                    if shown { "shown" } else { "hidden" }
+                )
+            }),
+    }
+}
+
+fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RuleCondition;
+
+    fn low_bitrate_rule() -> RuleConfig {
+        RuleConfig {
+            name: "low bitrate".to_string(),
+            when: vec![RuleCondition {
+                metric: "stream.bitrate_kbps".to_string(),
+                op: CompareOp::Lt,
+                value: 1500.0,
+                for_secs: 5,
+            }],
+            actions: vec![RuleAction::SwitchScene {
+                scene: "BRB".to_string(),
+            }],
+            cooldown_secs: 30,
+            ..RuleConfig::default()
+        }
+    }
+
+    fn frame_with_bitrate(kbps: u32) -> TelemetryFrame {
+        TelemetryFrame {
+            streams: vec![StreamOutput {
+                name: "adv_stream".to_string(),
+                bitrate_kbps: kbps,
+                ..StreamOutput::default()
+            }],
+            ..TelemetryFrame::default()
+        }
+    }
+
+    #[test]
+    fn fires_once_after_condition_holds_and_respects_cooldown() {
+        let mut engine = RuleEngine::new(&[low_bitrate_rule()]);
+        let start = Instant::now();
+        let low = frame_with_bitrate(800);
+        let at = |secs| start + Duration::from_secs(secs);
+
+        assert!(engine.evaluate(&low, at(0)).is_empty());
+        assert!(engine.evaluate(&low, at(4)).is_empty());
+        assert_eq!(engine.evaluate(&low, at(5)).len(), 1);
+        assert!(
+            engine.evaluate(&low, at(6)).is_empty(),
+            "one firing per episode"
+        );
+
+        // Recovers, then drops again: held long enough, but still cooling down.
+        assert!(engine.evaluate(&frame_with_bitrate(6000), at(7)).is_empty());
+        assert!(engine.evaluate(&low, at(8)).is_empty());
+        assert!(engine.evaluate(&low, at(34)).is_empty());
+        assert_eq!(engine.evaluate(&low, at(35)).len(), 1);
+        assert!(engine.status()[0].triggered);
+    }
+
+    #[test]
+    fn missing_metrics_never_match() {
+        let mut rule = low_bitrate_rule();
+        rule.when[0].metric = "relay.rtt_ms".to_string();
+        rule.when[0].op = CompareOp::Ge;
+        rule.when[0].value = 0.0;
+        rule.when[0].for_secs = 0;
+        let mut engine = RuleEngine::new(&[rule]);
+        assert!(engine
+            .evaluate(&TelemetryFrame::default(), Instant::now())
+            .is_empty());
+        assert!(is_known_metric("audio.clipping_inputs"));
+        assert!(!is_known_metric("obs.bogus"));
+    }
+
+    #[test]
+    fn parses_rules_from_toml() {
+        let config: Config = toml::from_str(
+            r#"
+            [[rules]]
+            name = "clipping"
+            when = [{ metric = "audio.clipping_inputs", op = ">", value = 0, for_secs = 2 }]
+            actions = [
+              { type = "alert", message = "Mic is clipping" },
+              { type = "toggle_source", scene = "Main", source = "Webcam" },
+              { type = "run_command", command = "notify-send", args = ["clip"] },
+            ]
+            "#,
+        )
+        .unwrap();
+        let rule = &config.rules[0];
+        assert!(rule.enabled);
+        assert_eq!(rule.when[0].op, CompareOp::Gt);
+        assert!(matches!(
+            &rule.actions[1],
+            RuleAction::ToggleSource { enabled: None, .. }
+        ));
+        assert!(config.validate().is_ok());
+    }
+}
@@ -0,0 +1,369 @@
+use crate::activity::ActionKind;
+use crate::aegis::RelaySessionHandle;
+use crate::config::Config;
+use crate::ipc::{CoreIpcCommand, CoreIpcCommandSender, OverrideStoreHandle};
+use crate::metrics::{ObsControlAction, ObsLink};
+use crate::model::{ObsConnectionState, TelemetryFrame};
+use crate::security::VaultHandle;
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use std::sync::{Arc, Mutex};
+use tokio::runtime::Handle;
+use tokio::sync::watch;
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Everything the script API can reach.
+pub struct ScriptContext {
+    pub obs: ObsLink,
+    pub config: Config,
+    pub vault: VaultHandle,
+    pub aegis_session_snapshot: RelaySessionHandle,
+    pub ipc_cmd_tx: CoreIpcCommandSender,
+    pub overrides: OverrideStoreHandle,
+}
+
+struct Script {
+    path: String,
+    ast: AST,
+    scope: Scope<'static>,
+    /// Bound as `this` in every callback. Rhai functions cannot see top-level
+    /// variables, so this map is where a script keeps state between calls.
+    state: Dynamic,
+}
+
+impl Script {
+    fn defines(&self, name: &str) -> bool {
+        self.ast.iter_functions().any(|f| f.name == name)
+    }
+
+    /// Calls a script function if the script defines it, logging (not
+    /// propagating) failures so one broken script does not stop the others.
+    fn call(&mut self, engine: &Engine, name: &str, args: impl rhai::FuncArgs) {
+        if !self.defines(name) {
+            return;
+        }
+        let options = CallFnOptions::new()
+            .eval_ast(false)
+            .bind_this_ptr(&mut self.state);
+        let result =
+            engine.call_fn_with_options::<Dynamic>(options, &mut self.scope, &self.ast, name, args);
+        if let Err(err) = result {
+            tracing::warn!(script = %self.path, function = name, "script error: {}", err);
+        }
+    }
+}
+
+/// Every event name [`frame_events`] produces.
+pub const FRAME_EVENTS: [&str; 9] = [
+    "obs_connected",
+    "obs_disconnected",
+    "stream_started",
+    "stream_stopped",
+    "recording_started",
+    "recording_stopped",
+    "relay_connected",
+    "relay_disconnected",
+    "scene_changed",
+];
+
+/// The frame-derived events passed to `on_event(name, data)`.
+pub fn frame_events(prev: &TelemetryFrame, next: &TelemetryFrame) -> Vec<(&'static str, Map)> {
+    let mut events = Vec::new();
+    let mut edge = |was: bool, is: bool, on: &'static str, off: &'static str| {
+        if was != is {
+            events.push((if is { on } else { off }, Map::new()));
+        }
+    };
+    edge(
+        prev.obs.connected,
+        next.obs.connected,
+        "obs_connected",
+        "obs_disconnected",
+    );
+    edge(
+        prev.obs.streaming,
+        next.obs.streaming,
+        "stream_started",
+        "stream_stopped",
+    );
+    edge(
+        prev.obs.recording,
+        next.obs.recording,
+        "recording_started",
+        "recording_stopped",
+    );
+    edge(
+        prev.relay.as_ref().is_some_and(|r| r.connected),
+        next.relay.as_ref().is_some_and(|r| r.connected),
+        "relay_connected",
+        "relay_disconnected",
+    );
+
+    if next.obs.current_scene.is_some() && prev.obs.current_scene != next.obs.current_scene {
+        let mut data = Map::new();
+        data.insert("from".into(), optional(prev.obs.current_scene.clone()));
+        data.insert("to".into(), optional(next.obs.current_scene.clone()));
+        events.push(("scene_changed", data));
+    }
+    events
+}
+
+fn optional(value: Option<String>) -> Dynamic {
+    value.map_or(Dynamic::UNIT, Dynamic::from)
+}
+
+fn to_dynamic<T: serde::Serialize>(value: &T) -> Dynamic {
+    rhai::serde::to_dynamic(value).unwrap_or(Dynamic::UNIT)
+}
+
+/// Builds the engine with the script API: `log`, `alert`, `frame`, OBS control
+/// (`switch_scene`, `obs_control`, `set_source_enabled`, `toggle_source`), IPC
+/// (`ipc_switch_scene`) and Aegis (`relay_start`, `relay_stop`, `relay_status`).
+/// Calls that reach OBS or Aegis block the script thread until they finish and
+/// raise a script error on failure, which scripts can handle with `try`/`catch`.
+fn build_engine(
+    ctx: Arc<ScriptContext>,
+    handle: Handle,
+    latest: Arc<Mutex<TelemetryFrame>>,
+    max_operations: u64,
+) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(max_operations);
+    engine.on_print(|msg| tracing::info!(target: "telemy::script", "{}", msg));
+    engine.on_debug(|msg, src, pos| {
+        tracing::debug!(target: "telemy::script", source = src.unwrap_or(""), %pos, "{}", msg)
+    });
+
+    engine.register_fn(
+        "log",
+        |msg: &str| tracing::info!(target: "telemy::script", "{}", msg),
+    );
+    let notifications = ctx.config.notifications.clone();
+    engine.register_fn("alert", move |msg: &str| {
+        crate::notify::alert(&notifications, msg)
+    });
+    engine.register_fn("frame", move || to_dynamic(&*latest.lock().unwrap()));
+
+    let (c, h) = (ctx.clone(), handle.clone());
+    engine.register_fn("switch_scene", move |scene: &str| -> ScriptResult<()> {
+        let result = h.block_on(c.obs.switch_scene(scene));
+        crate::activity::log().record(
+            ActionKind::SwitchScene,
+            "script",
+            None,
+            result.clone().map(|()| format!("switched to '{scene}'")),
+        );
+        Ok(result?)
+    });
+    let (c, h) = (ctx.clone(), handle.clone());
+    engine.register_fn("obs_control", move |action: &str| -> ScriptResult<()> {
+        let action: ObsControlAction = serde_json::from_value(action.into())
+            .map_err(|_| format!("unknown obs_control action '{action}'"))?;
+        Ok(h.block_on(c.obs.control(action))?)
+    });
+    let (c, h) = (ctx.clone(), handle.clone());
+    engine.register_fn(
+        "set_source_enabled",
+        move |scene: &str, source: &str, enabled: bool| -> ScriptResult<bool> {
+            Ok(h.block_on(c.obs.set_source_enabled(scene, source, Some(enabled)))?)
+        },
+    );
+    let (c, h) = (ctx.clone(), handle.clone());
+    engine.register_fn(
+        "toggle_source",
+        move |scene: &str, source: &str| -> ScriptResult<bool> {
+            Ok(h.block_on(c.obs.set_source_enabled(scene, source, None))?)
+        },
+    );
+
+    let c = ctx.clone();
+    engine.register_fn(
+        "ipc_switch_scene",
+        move |scene: &str, reason: &str| -> ScriptResult<()> {
+            if c.obs.scene_list().is_unknown(scene) {
+                return Err(format!("unknown_scene: OBS has no scene named '{scene}'").into());
+            }
+            c.ipc_cmd_tx
+                .send(CoreIpcCommand::SwitchScene {
+                    scene_name: scene.to_string(),
+                    reason: reason.to_string(),
+                    deadline_ms: 550,
+                })
+                .map(|_| ())
+                .map_err(|err| format!("ipc switch_scene unavailable: {err}").into())
+        },
+    );
+
+    let (c, h) = (ctx.clone(), handle.clone());
+    engine.register_fn("relay_start", move || -> ScriptResult<Dynamic> {
+        let session = h.block_on(crate::app::relay_start(
+            &c.config,
+            &c.vault,
+            "script",
+            c.overrides.get().mode,
+            c.obs.status().0 == ObsConnectionState::Connected,
+        ))?;
+        let value = to_dynamic(&session);
+        *c.aegis_session_snapshot.blocking_write() = Some(session);
+        Ok(value)
+    });
+    let (c, h) = (ctx.clone(), handle.clone());
+    engine.register_fn("relay_stop", move || -> ScriptResult<()> {
+        let session_id = c
+            .aegis_session_snapshot
+            .blocking_read()
+            .as_ref()
+            .map(|s| s.session_id.clone())
+            .ok_or("no relay session is active")?;
+        h.block_on(crate::app::relay_stop(
+            &c.config,
+            &c.vault,
+            session_id,
+            "script",
+            "user_requested",
+        ))?;
+        *c.aegis_session_snapshot.blocking_write() = None;
+        Ok(())
+    });
+    let (c, h) = (ctx, handle);
+    engine.register_fn("relay_status", move || -> ScriptResult<Dynamic> {
+        let client = {
+            let guard = c.vault.blocking_read();
+            crate::app::build_aegis_client(&c.config, &guard).map_err(|err| err.to_string())?
+        };
+        let session = h
+            .block_on(client.relay_active())
+            .map_err(|err| err.to_string())?;
+        *c.aegis_session_snapshot.blocking_write() = session.clone();
+        Ok(session.as_ref().map_or(Dynamic::UNIT, to_dynamic))
+    });
+
+    engine
+}
+
+fn load_scripts(engine: &Engine, paths: &[String]) -> Vec<Script> {
+    let mut scripts = Vec::new();
+    for path in paths {
+        let ast = match engine.compile_file(path.into()) {
+            Ok(ast) => ast,
+            Err(err) => {
+                tracing::warn!(script = %path, "failed to load script: {}", err);
+                continue;
+            }
+        };
+        let mut script = Script {
+            path: path.clone(),
+            ast,
+            scope: Scope::new(),
+            state: Map::new().into(),
+        };
+        // Top-level statements run once and may set up script-wide variables.
+        if let Err(err) = engine.run_ast_with_scope(&mut script.scope, &script.ast) {
+            tracing::warn!(script = %path, "script failed to start: {}", err);
+            continue;
+        }
+        tracing::info!(script = %path, "script loaded");
+        scripts.push(script);
+    }
+    scripts
+}
+
+/// Runs the configured scripts on a dedicated thread: `on_frame(frame)` for every
+/// new frame and `on_event(name, data)` for each transition between frames.
+pub fn spawn(ctx: ScriptContext, rx: watch::Receiver<TelemetryFrame>) {
+    if !ctx.config.scripting.enabled || ctx.config.scripting.scripts.is_empty() {
+        return;
+    }
+    let handle = Handle::current();
+    let spawned = std::thread::Builder::new()
+        .name("telemy-scripts".to_string())
+        .spawn(move || run(ctx, rx, handle));
+    if let Err(err) = spawned {
+        tracing::warn!("failed to start script thread: {}", err);
+    }
+}
+
+fn run(ctx: ScriptContext, mut rx: watch::Receiver<TelemetryFrame>, handle: Handle) {
+    let paths = ctx.config.scripting.scripts.clone();
+    let max_operations = ctx.config.scripting.max_operations;
+    let latest = Arc::new(Mutex::new(rx.borrow().clone()));
+    let engine = build_engine(
+        Arc::new(ctx),
+        handle.clone(),
+        latest.clone(),
+        max_operations,
+    );
+    let mut scripts = load_scripts(&engine, &paths);
+    if scripts.is_empty() {
+        return;
+    }
+
+    let mut prev = latest.lock().unwrap().clone();
+    while handle.block_on(rx.changed()).is_ok() {
+        let frame = rx.borrow_and_update().clone();
+        *latest.lock().unwrap() = frame.clone();
+        let events = frame_events(&prev, &frame);
+        let frame_value = to_dynamic(&frame);
+        for script in &mut scripts {
+            for (name, data) in &events {
+                script.call(&engine, "on_event", (name.to_string(), data.clone()));
+            }
+            script.call(&engine, "on_frame", (frame_value.clone(),));
+        }
+        prev = frame;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_transition_events_between_frames() {
+        let prev = TelemetryFrame::default();
+        let mut next = TelemetryFrame::default();
+        next.obs.connected = true;
+        next.obs.streaming = true;
+        next.obs.current_scene = Some("Game".to_string());
+
+        let events = frame_events(&prev, &next);
+        let names: Vec<_> = events.iter().map(|(name, _)| *name).collect();
+        assert_eq!(names, ["obs_connected", "stream_started", "scene_changed"]);
+        assert!(names.iter().all(|name| FRAME_EVENTS.contains(name)));
+        let scene = &events[2].1;
+        assert!(scene["from"].is_unit());
+        assert_eq!(scene["to"].clone().into_string().unwrap(), "Game");
+        assert!(frame_events(&next, &next).is_empty());
+    }
+
+    #[test]
+    fn scripts_keep_state_between_calls_and_see_the_frame() {
+        let engine = Engine::new();
+        let ast = engine
+            .compile(
+                r#"
+                fn on_frame(frame) {
+                    let cpu = frame.system.cpu_percent;
+                    if cpu > (this.peak ?? 0.0) { this.peak = cpu; }
+                }
+                "#,
+            )
+            .unwrap();
+        let mut script = Script {
+            path: "test.rhai".to_string(),
+            ast,
+            scope: Scope::new(),
+            state: Map::new().into(),
+        };
+        engine
+            .run_ast_with_scope(&mut script.scope, &script.ast)
+            .unwrap();
+
+        let mut frame = TelemetryFrame::default();
+        frame.system.cpu_percent = 42.0;
+        script.call(&engine, "on_frame", (to_dynamic(&frame),));
+        script.call(&engine, "on_event", ("ignored".to_string(), Map::new()));
+        let state = script.state.cast::<Map>();
+        assert_eq!(state["peak"].as_float().unwrap(), 42.0);
+    }
+}
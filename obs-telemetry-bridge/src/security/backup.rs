@@ -0,0 +1,146 @@
+use super::SecretString;
+use base64::{engine::general_purpose, Engine as _};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::num::NonZeroU32;
+use zeroize::Zeroizing;
+
+const FORMAT_VERSION: u32 = 1;
+const KDF: &str = "pbkdf2-hmac-sha256";
+/// OWASP's 2023 floor for PBKDF2-HMAC-SHA256.
+const ITERATIONS: u32 = 600_000;
+/// The count comes from the file, so a crafted backup could otherwise pin a
+/// CPU for hours before the password is even checked.
+const MAX_ITERATIONS: u32 = 10 * ITERATIONS;
+const SALT_LEN: usize = 16;
+pub const MIN_PASSWORD_LEN: usize = 8;
+
+/// A password-protected copy of vault entries. Unlike the vault itself it is
+/// not tied to one Windows user, so it restores on another machine.
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupFile {
+    version: u32,
+    kdf: String,
+    iterations: u32,
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn derive_key(password: &str, salt: &[u8], iterations: u32) -> Result<LessSafeKey, String> {
+    let iterations = NonZeroU32::new(iterations).ok_or("backup has zero KDF iterations")?;
+    let mut key = [0u8; 32];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        salt,
+        password.as_bytes(),
+        &mut key,
+    );
+    let key = UnboundKey::new(&AES_256_GCM, &key).map_err(|_| "invalid backup key")?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Encrypts `entries` (plaintext values by key) under `password`.
+pub fn seal(entries: &BTreeMap<String, SecretString>, password: &str) -> Result<String, String> {
+    seal_with(entries, password, ITERATIONS)
+}
+
+fn seal_with(
+    entries: &BTreeMap<String, SecretString>,
+    password: &str,
+    iterations: u32,
+) -> Result<String, String> {
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(format!(
+            "backup password must be at least {MIN_PASSWORD_LEN} characters"
+        ));
+    }
+    let rng = SystemRandom::new();
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rng.fill(&mut salt).map_err(|_| "no system randomness")?;
+    rng.fill(&mut nonce).map_err(|_| "no system randomness")?;
+
+    let mut data = serde_json::to_vec(entries).map_err(|err| err.to_string())?;
+    derive_key(password, &salt, iterations)?
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(KDF),
+            &mut data,
+        )
+        .map_err(|_| "backup encryption failed")?;
+
+    let file = BackupFile {
+        version: FORMAT_VERSION,
+        kdf: KDF.to_string(),
+        iterations,
+        salt: general_purpose::STANDARD.encode(salt),
+        nonce: general_purpose::STANDARD.encode(nonce),
+        ciphertext: general_purpose::STANDARD.encode(data),
+    };
+    serde_json::to_string_pretty(&file).map_err(|err| err.to_string())
+}
+
+/// Decrypts a backup written by [`seal`].
+pub fn open(raw: &str, password: &str) -> Result<BTreeMap<String, SecretString>, String> {
+    let file: BackupFile =
+        serde_json::from_str(raw).map_err(|err| format!("not a vault backup: {err}"))?;
+    if file.version != FORMAT_VERSION || file.kdf != KDF {
+        return Err(format!(
+            "unsupported vault backup (version {}, {})",
+            file.version, file.kdf
+        ));
+    }
+    if file.iterations > MAX_ITERATIONS {
+        return Err(format!(
+            "vault backup asks for {} KDF iterations; the limit is {MAX_ITERATIONS}",
+            file.iterations
+        ));
+    }
+    let decode = |field: &str| {
+        general_purpose::STANDARD
+            .decode(field)
+            .map_err(|err| format!("corrupt vault backup: {err}"))
+    };
+    let salt = decode(&file.salt)?;
+    let nonce = Nonce::try_assume_unique_for_key(&decode(&file.nonce)?)
+        .map_err(|_| "corrupt vault backup: bad nonce")?;
+    let mut data = Zeroizing::new(decode(&file.ciphertext)?);
+    let plain = derive_key(password, &salt, file.iterations)?
+        .open_in_place(nonce, Aad::from(KDF), &mut data)
+        .map_err(|_| "wrong password or corrupt vault backup")?;
+    serde_json::from_slice(plain).map_err(|err| format!("corrupt vault backup: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_and_rejects_wrong_password() {
+        let entries = BTreeMap::from([
+            ("server_token".to_string(), SecretString::from("abc123")),
+            ("obs_password".to_string(), SecretString::from("hunter22")),
+        ]);
+        // Few iterations keep the unoptimized test build fast; `open` reads the
+        // count from the file either way.
+        let sealed = seal_with(&entries, "correct horse", 1_000).unwrap();
+        assert!(!sealed.contains("abc123"));
+        let opened = open(&sealed, "correct horse").unwrap();
+        assert_eq!(opened.len(), 2);
+        assert_eq!(opened["server_token"].expose(), "abc123");
+
+        let err = open(&sealed, "wrong horse").unwrap_err();
+        assert!(err.contains("wrong password"), "{err}");
+        assert!(seal(&entries, "short").is_err());
+
+        let mut file: BackupFile = serde_json::from_str(&sealed).unwrap();
+        file.iterations = MAX_ITERATIONS + 1;
+        let err = open(&serde_json::to_string(&file).unwrap(), "correct horse").unwrap_err();
+        assert!(err.contains("KDF iterations"), "{err}");
+    }
+}
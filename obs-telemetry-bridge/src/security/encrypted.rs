@@ -0,0 +1,145 @@
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use std::fs;
+use std::num::NonZeroU32;
+use std::path::Path;
+
+const KEY_LEN: usize = 32;
+pub const SALT_LEN: usize = 16;
+const PASSPHRASE_ITERATIONS: u32 = 600_000;
+
+/// Key for the `encrypted` vault backend. Each entry is sealed with
+/// ChaCha20-Poly1305 under a fresh nonce and stored as `nonce || ciphertext`.
+pub struct EntryKey(LessSafeKey);
+
+impl std::fmt::Debug for EntryKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("EntryKey(..)")
+    }
+}
+
+fn random<const N: usize>() -> Result<[u8; N], String> {
+    let mut bytes = [0u8; N];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| "no system randomness")?;
+    Ok(bytes)
+}
+
+pub fn random_salt() -> Result<[u8; SALT_LEN], String> {
+    random()
+}
+
+impl EntryKey {
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let key = UnboundKey::new(&CHACHA20_POLY1305, bytes).map_err(|_| "invalid vault key")?;
+        Ok(Self(LessSafeKey::new(key)))
+    }
+
+    pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> Result<Self, String> {
+        Self::from_passphrase_with(passphrase, salt, PASSPHRASE_ITERATIONS)
+    }
+
+    fn from_passphrase_with(
+        passphrase: &str,
+        salt: &[u8],
+        iterations: u32,
+    ) -> Result<Self, String> {
+        if passphrase.is_empty() {
+            return Err("vault passphrase is empty".into());
+        }
+        let mut key = [0u8; KEY_LEN];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            NonZeroU32::new(iterations).expect("non-zero iterations"),
+            salt,
+            passphrase.as_bytes(),
+            &mut key,
+        );
+        Self::from_bytes(&key)
+    }
+
+    /// Reads the machine key at `path`, generating it on first use. The file is
+    /// created readable by its owner only.
+    pub fn load_or_create(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        match fs::read(path) {
+            Ok(bytes) if bytes.len() == KEY_LEN => return Ok(Self::from_bytes(&bytes)?),
+            Ok(_) => return Err(format!("{}: vault key file is corrupt", path.display()).into()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(format!("{}: {err}", path.display()).into()),
+        }
+        let key = random::<KEY_LEN>()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        std::io::Write::write_all(&mut options.open(path)?, &key)?;
+        tracing::info!(path = %path.display(), "generated vault key file");
+        Ok(Self::from_bytes(&key)?)
+    }
+
+    pub fn seal(&self, data: &[u8]) -> Result<Vec<u8>, String> {
+        let nonce = random::<NONCE_LEN>()?;
+        let mut out = data.to_vec();
+        self.0
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut out)
+            .map_err(|_| "vault encryption failed")?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend(out);
+        Ok(sealed)
+    }
+
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>, String> {
+        const UNREADABLE: &str =
+            "vault entry could not be decrypted (wrong passphrase or key file?)";
+        if sealed.len() < NONCE_LEN {
+            return Err(UNREADABLE.into());
+        }
+        let (nonce, data) = sealed.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| UNREADABLE)?;
+        let mut data = data.to_vec();
+        let plain = self
+            .0
+            .open_in_place(nonce, Aad::empty(), &mut data)
+            .map_err(|_| UNREADABLE)?;
+        Ok(plain.to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_open_only_with_the_same_key() {
+        let salt = random_salt().unwrap();
+        let key = EntryKey::from_passphrase_with("server passphrase", &salt, 1_000).unwrap();
+        let sealed = key.seal(b"grafana-token").unwrap();
+        assert!(!sealed.windows(13).any(|w| w == b"grafana-token"));
+        assert_eq!(key.open(&sealed).unwrap(), b"grafana-token");
+        assert_ne!(key.seal(b"grafana-token").unwrap(), sealed, "fresh nonce");
+
+        let other = EntryKey::from_passphrase_with("other passphrase", &salt, 1_000).unwrap();
+        assert!(other.open(&sealed).is_err());
+    }
+
+    #[test]
+    fn key_file_is_created_once_and_reused() {
+        let dir = std::env::temp_dir().join(format!("telemy-vault-key-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("vault.key");
+        let sealed = EntryKey::load_or_create(&path).unwrap().seal(b"x").unwrap();
+        let reopened = EntryKey::load_or_create(&path).unwrap();
+        assert_eq!(reopened.open(&sealed).unwrap(), b"x");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o077, 0, "key file is private");
+        }
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -1,10 +1,19 @@
+mod backup;
+mod encrypted;
+mod secret;
+
+use crate::config::{VaultBackend, VaultConfig};
 use base64::{engine::general_purpose, Engine as _};
+use encrypted::EntryKey;
+pub use secret::SecretString;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fs,
     path::{Path, PathBuf},
+    sync::Arc,
 };
+use tokio::sync::RwLock;
 #[cfg(windows)]
 use windows::Win32::Foundation::{LocalFree, HLOCAL};
 #[cfg(windows)]
@@ -12,17 +21,36 @@ use windows::Win32::Security::Cryptography::{
     CryptProtectData, CryptUnprotectData, CRYPTPROTECT_UI_FORBIDDEN, CRYPT_INTEGER_BLOB,
 };
 
+/// The vault shared by the server and background tasks. An async lock, so a
+/// handler waiting on a slow write does not block a runtime thread.
+pub type VaultHandle = Arc<RwLock<Vault>>;
+
 #[derive(Debug)]
 pub struct Vault {
     path: PathBuf,
     store: VaultStore,
+    /// Namespaces keys per config profile; callers only ever see unprefixed keys.
+    key_prefix: String,
+    /// Set for the `encrypted` backend; otherwise entries use [`protect`].
+    key: Option<EntryKey>,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 struct VaultStore {
+    /// Present once the file holds `encrypted`-backend entries, so a vault is
+    /// never read with the wrong backend.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encryption: Option<Encryption>,
     entries: HashMap<String, String>,
 }
 
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Encryption {
+    /// Base64 PBKDF2 salt when the key comes from a passphrase; absent for a key file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kdf_salt: Option<String>,
+}
+
 impl Vault {
     pub fn new(path: Option<&str>) -> Result<Self, Box<dyn std::error::Error>> {
         let path = match path {
@@ -41,29 +69,192 @@ impl Vault {
             VaultStore::default()
         };
 
-        Ok(Self { path, store })
+        Ok(Self {
+            path,
+            store,
+            key_prefix: String::new(),
+            key: None,
+        })
+    }
+
+    /// Opens the vault configured in `[vault]`, with its key prefix and backend applied.
+    pub fn open(config: &VaultConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut vault = Self::new(config.path.as_deref())?;
+        vault.key_prefix = config.effective_key_prefix().unwrap_or_default();
+        let has_entries = !vault.store.entries.is_empty();
+        match (config.backend, &vault.store.encryption) {
+            (VaultBackend::Os, None) => {}
+            (VaultBackend::Os, Some(_)) => {
+                return Err(format!(
+                "{} was written by the encrypted vault backend; set vault.backend = \"encrypted\"",
+                vault.path.display()
+            )
+                .into())
+            }
+            (VaultBackend::Encrypted, None) if has_entries => {
+                return Err(format!(
+                    "{} holds entries from the os vault backend; move them with `vault export` \
+                     and `vault import` after switching",
+                    vault.path.display()
+                )
+                .into())
+            }
+            (VaultBackend::Encrypted, _) => vault.unlock(config)?,
+        }
+        Ok(vault)
+    }
+
+    /// Derives or loads the entry key. The passphrase-or-key-file choice is
+    /// made when the vault is first encrypted and recorded in the file.
+    fn unlock(&mut self, config: &VaultConfig) -> Result<(), Box<dyn std::error::Error>> {
+        let passphrase = std::env::var("TELEMY_VAULT_PASSPHRASE").ok();
+        if self.store.encryption.is_none() {
+            let kdf_salt = match passphrase {
+                Some(_) => Some(general_purpose::STANDARD.encode(encrypted::random_salt()?)),
+                None => None,
+            };
+            self.store.encryption = Some(Encryption { kdf_salt });
+            self.persist()?;
+        }
+        let kdf_salt = self
+            .store
+            .encryption
+            .as_ref()
+            .and_then(|e| e.kdf_salt.as_ref());
+        self.key = Some(match (kdf_salt, passphrase) {
+            (Some(salt), Some(passphrase)) => {
+                EntryKey::from_passphrase(&passphrase, &general_purpose::STANDARD.decode(salt)?)?
+            }
+            (Some(_), None) => {
+                return Err("vault is passphrase-protected; set TELEMY_VAULT_PASSPHRASE".into())
+            }
+            (None, _) => {
+                let key_file = match &config.key_file {
+                    Some(path) => PathBuf::from(path),
+                    None => self.path.with_file_name("vault.key"),
+                };
+                EntryKey::load_or_create(&key_file)?
+            }
+        });
+        Ok(())
+    }
+
+    fn seal(&self, data: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        match &self.key {
+            Some(key) => Ok(key.seal(data)?),
+            None => protect(data),
+        }
+    }
+
+    fn unseal(&self, data: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        match &self.key {
+            Some(key) => Ok(key.open(data)?),
+            None => unprotect(data),
+        }
+    }
+
+    fn entry_key(&self, key: &str) -> String {
+        format!("{}{}", self.key_prefix, key)
     }
 
     pub fn store(&mut self, key: &str, value: &str) -> Result<(), Box<dyn std::error::Error>> {
-        let encrypted = protect(value.as_bytes())?;
+        let encrypted = self.seal(value.as_bytes())?;
         let encoded = general_purpose::STANDARD.encode(encrypted);
-        self.store.entries.insert(key.to_string(), encoded);
+        self.store.entries.insert(self.entry_key(key), encoded);
         self.persist()
     }
 
-    pub fn retrieve(&self, key: &str) -> Result<String, Box<dyn std::error::Error>> {
-        let encoded = self.store.entries.get(key).ok_or("missing vault key")?;
+    pub fn retrieve(&self, key: &str) -> Result<SecretString, Box<dyn std::error::Error>> {
+        let encoded = self
+            .store
+            .entries
+            .get(&self.entry_key(key))
+            .ok_or("missing vault key")?;
         let encrypted = general_purpose::STANDARD.decode(encoded)?;
-        let decrypted = unprotect(&encrypted)?;
-        Ok(String::from_utf8(decrypted)?)
+        let decrypted = self.unseal(&encrypted)?;
+        Ok(String::from_utf8(decrypted)?.into())
+    }
+
+    pub fn delete(&mut self, key: &str) -> Result<bool, Box<dyn std::error::Error>> {
+        if self.store.entries.remove(&self.entry_key(key)).is_none() {
+            return Ok(false);
+        }
+        self.persist()?;
+        Ok(true)
     }
 
     pub fn list_keys(&self) -> Vec<String> {
-        let mut keys: Vec<String> = self.store.entries.keys().cloned().collect();
+        let mut keys: Vec<String> = self
+            .store
+            .entries
+            .keys()
+            .filter_map(|key| key.strip_prefix(self.key_prefix.as_str()))
+            .map(str::to_string)
+            .collect();
         keys.sort();
         keys
     }
 
+    /// Decrypts and re-protects every entry, across all profiles, under the
+    /// current Windows user (or fresh nonces with the encrypted backend).
+    /// Returns the keys that could not be decrypted; those are left untouched
+    /// and must be set again.
+    pub fn rotate(&mut self) -> Result<(usize, Vec<String>), Box<dyn std::error::Error>> {
+        let (mut rotated, mut failed) = (0, Vec::new());
+        let keys: Vec<String> = self.store.entries.keys().cloned().collect();
+        for key in keys {
+            let plain = general_purpose::STANDARD
+                .decode(&self.store.entries[&key])
+                .map_err(|err| err.to_string())
+                .and_then(|raw| self.unseal(&raw).map_err(|err| err.to_string()));
+            match plain {
+                Ok(plain) => {
+                    let encoded = general_purpose::STANDARD.encode(self.seal(&plain)?);
+                    self.store.entries.insert(key, encoded);
+                    rotated += 1;
+                }
+                Err(_) => failed.push(key),
+            }
+        }
+        failed.sort();
+        self.persist()?;
+        Ok((rotated, failed))
+    }
+
+    /// This profile's secrets as a password-protected backup that can be
+    /// restored on another machine or user account.
+    pub fn export_backup(&self, password: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let mut entries = BTreeMap::new();
+        for key in self.list_keys() {
+            let value = self.retrieve(&key)?;
+            entries.insert(key, value);
+        }
+        Ok(backup::seal(&entries, password)?)
+    }
+
+    /// Restores a backup from [`Self::export_backup`] into this profile.
+    /// Existing keys are kept unless `overwrite`; returns the imported and
+    /// skipped keys.
+    pub fn import_backup(
+        &mut self,
+        raw: &str,
+        password: &str,
+        overwrite: bool,
+    ) -> Result<(Vec<String>, Vec<String>), Box<dyn std::error::Error>> {
+        let (mut imported, mut skipped) = (Vec::new(), Vec::new());
+        for (key, value) in backup::open(raw, password)? {
+            if !overwrite && self.store.entries.contains_key(&self.entry_key(&key)) {
+                skipped.push(key);
+                continue;
+            }
+            let encoded = general_purpose::STANDARD.encode(self.seal(value.expose().as_bytes())?);
+            self.store.entries.insert(self.entry_key(&key), encoded);
+            imported.push(key);
+        }
+        self.persist()?;
+        Ok((imported, skipped))
+    }
+
     fn persist(&self) -> Result<(), Box<dyn std::error::Error>> {
         let data = serde_json::to_string_pretty(&self.store)?;
         fs::write(&self.path, data)?;
@@ -136,3 +327,38 @@ fn protect(data: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
 fn unprotect(data: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
     Ok(data.to_vec())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_backend_keeps_values_out_of_the_file() {
+        let dir = std::env::temp_dir().join(format!("telemy-vault-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("vault.json");
+        let config = VaultConfig {
+            path: Some(path.to_string_lossy().into_owned()),
+            backend: VaultBackend::Encrypted,
+            ..VaultConfig::default()
+        };
+        let mut vault = Vault::open(&config).unwrap();
+        vault.store("grafana_auth", "Basic c2VjcmV0").unwrap();
+        assert!(!fs::read_to_string(&path).unwrap().contains("c2VjcmV0"));
+        assert!(dir.join("vault.key").exists());
+        assert_eq!(
+            Vault::open(&config)
+                .unwrap()
+                .retrieve("grafana_auth")
+                .unwrap()
+                .expose(),
+            "Basic c2VjcmV0"
+        );
+
+        let os = VaultConfig {
+            backend: VaultBackend::Os,
+            ..config
+        };
+        assert!(Vault::open(&os).is_err(), "wrong backend is refused");
+        let _ = fs::remove_dir_all(dir);
+    }
+}
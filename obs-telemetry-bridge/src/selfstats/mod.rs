@@ -0,0 +1,203 @@
+use crate::ipc::IpcLinkStats;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+static STATS: SelfStats = SelfStats::new();
+static STARTED: OnceLock<Instant> = OnceLock::new();
+
+/// Counters about the bridge itself, shared process-wide so the metrics loop, OBS
+/// client, WebSocket handler and exporters can update them without plumbing.
+pub struct SelfStats {
+    collect_count: AtomicU64,
+    collect_total_us: AtomicU64,
+    collect_last_us: AtomicU64,
+    collect_max_us: AtomicU64,
+    obs_request_failures: AtomicU64,
+    obs_connect_failures: AtomicU64,
+    ws_clients: AtomicU64,
+    otlp_buffered_batches: AtomicU64,
+    metrics_restarts: AtomicU64,
+    exports: Mutex<BTreeMap<&'static str, ExportCounts>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct ExportCounts {
+    pub successes: u64,
+    pub failures: u64,
+}
+
+pub fn stats() -> &'static SelfStats {
+    STARTED.get_or_init(Instant::now);
+    &STATS
+}
+
+impl SelfStats {
+    const fn new() -> Self {
+        Self {
+            collect_count: AtomicU64::new(0),
+            collect_total_us: AtomicU64::new(0),
+            collect_last_us: AtomicU64::new(0),
+            collect_max_us: AtomicU64::new(0),
+            obs_request_failures: AtomicU64::new(0),
+            obs_connect_failures: AtomicU64::new(0),
+            ws_clients: AtomicU64::new(0),
+            otlp_buffered_batches: AtomicU64::new(0),
+            metrics_restarts: AtomicU64::new(0),
+            exports: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn record_collect(&self, elapsed: Duration) {
+        let us = elapsed.as_micros() as u64;
+        self.collect_count.fetch_add(1, Ordering::Relaxed);
+        self.collect_total_us.fetch_add(us, Ordering::Relaxed);
+        self.collect_last_us.store(us, Ordering::Relaxed);
+        self.collect_max_us.fetch_max(us, Ordering::Relaxed);
+    }
+
+    pub fn obs_request_failed(&self) {
+        self.obs_request_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn obs_connect_failed(&self) {
+        self.obs_connect_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Counts a WebSocket client for as long as the returned guard is alive.
+    pub fn ws_client(&'static self) -> WsClientGuard {
+        self.ws_clients.fetch_add(1, Ordering::Relaxed);
+        WsClientGuard(self)
+    }
+
+    pub fn ws_clients(&self) -> u64 {
+        self.ws_clients.load(Ordering::Relaxed)
+    }
+
+    pub fn record_export(&self, exporter: &'static str, ok: bool) {
+        let mut exports = self.exports.lock().unwrap();
+        let counts = exports.entry(exporter).or_default();
+        if ok {
+            counts.successes += 1;
+        } else {
+            counts.failures += 1;
+        }
+    }
+
+    pub fn set_otlp_buffered(&self, batches: usize) {
+        self.otlp_buffered_batches
+            .store(batches as u64, Ordering::Relaxed);
+    }
+
+    pub fn metrics_task_restarted(&self) {
+        self.metrics_restarts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn metrics_restarts(&self) -> u64 {
+        self.metrics_restarts.load(Ordering::Relaxed)
+    }
+
+    pub fn snapshot(
+        &self,
+        ipc_session_connected: bool,
+        ipc_link: IpcLinkStats,
+    ) -> SelfStatsSnapshot {
+        let count = self.collect_count.load(Ordering::Relaxed);
+        let total_us = self.collect_total_us.load(Ordering::Relaxed);
+        SelfStatsSnapshot {
+            uptime_secs: STARTED.get().map_or(0, |s| s.elapsed().as_secs()),
+            collect: CollectStats {
+                count,
+                last_ms: us_to_ms(self.collect_last_us.load(Ordering::Relaxed)),
+                avg_ms: total_us.checked_div(count).map_or(0.0, us_to_ms),
+                max_ms: us_to_ms(self.collect_max_us.load(Ordering::Relaxed)),
+                total_secs: total_us as f64 / 1_000_000.0,
+            },
+            obs_request_failures: self.obs_request_failures.load(Ordering::Relaxed),
+            obs_connect_failures: self.obs_connect_failures.load(Ordering::Relaxed),
+            ws_clients: self.ws_clients.load(Ordering::Relaxed),
+            ipc_session_connected,
+            ipc_link,
+            exports: self.exports.lock().unwrap().clone(),
+            otlp_buffered_batches: self.otlp_buffered_batches.load(Ordering::Relaxed),
+            metrics_restarts: self.metrics_restarts(),
+            process_rss_bytes: process_rss_bytes(),
+        }
+    }
+}
+
+pub struct WsClientGuard(&'static SelfStats);
+
+impl Drop for WsClientGuard {
+    fn drop(&mut self) {
+        self.0.ws_clients.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SelfStatsSnapshot {
+    pub uptime_secs: u64,
+    pub collect: CollectStats,
+    pub obs_request_failures: u64,
+    pub obs_connect_failures: u64,
+    pub ws_clients: u64,
+    pub ipc_session_connected: bool,
+    pub ipc_link: IpcLinkStats,
+    pub exports: BTreeMap<&'static str, ExportCounts>,
+    pub otlp_buffered_batches: u64,
+    /// Times the metrics collection task died and was restarted.
+    pub metrics_restarts: u64,
+    pub process_rss_bytes: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CollectStats {
+    pub count: u64,
+    pub last_ms: f64,
+    pub avg_ms: f64,
+    pub max_ms: f64,
+    pub total_secs: f64,
+}
+
+fn us_to_ms(us: u64) -> f64 {
+    us as f64 / 1000.0
+}
+
+fn process_rss_bytes() -> Option<u64> {
+    let pid = sysinfo::get_current_pid().ok()?;
+    let mut sys = sysinfo::System::new();
+    sys.refresh_process(pid);
+    sys.process(pid).map(|p| p.memory())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn collect_timing_tracks_last_average_and_max() {
+        let stats = SelfStats::new();
+        stats.record_collect(Duration::from_millis(10));
+        stats.record_collect(Duration::from_millis(30));
+        stats.record_collect(Duration::from_millis(20));
+        let snapshot = stats.snapshot(false, IpcLinkStats::default());
+        assert_eq!(snapshot.collect.count, 3);
+        assert_eq!(snapshot.collect.last_ms, 20.0);
+        assert_eq!(snapshot.collect.avg_ms, 20.0);
+        assert_eq!(snapshot.collect.max_ms, 30.0);
+    }
+
+    #[test]
+    fn export_outcomes_are_counted_per_exporter() {
+        let stats = SelfStats::new();
+        stats.record_export("loki", true);
+        stats.record_export("loki", false);
+        stats.record_export("grafana_otlp", true);
+        let exports = stats.snapshot(false, IpcLinkStats::default()).exports;
+        assert_eq!(exports["loki"].successes, 1);
+        assert_eq!(exports["loki"].failures, 1);
+        assert_eq!(exports["grafana_otlp"].successes, 1);
+    }
+}
@@ -0,0 +1,171 @@
+use crate::config::AccessConfig;
+use axum::http::HeaderMap;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+const FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// An address or CIDR block such as `192.168.1.0/24` or `fd00::/8`. A bare
+/// address matches only itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IpNet {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl FromStr for IpNet {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let raw = raw.trim();
+        let (addr, prefix) = match raw.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (raw, None),
+        };
+        let addr: IpAddr = addr
+            .parse()
+            .map_err(|_| format!("invalid IP address: {raw}"))?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|p| *p <= max)
+                .ok_or_else(|| format!("invalid prefix length: {raw}"))?,
+            None => max,
+        };
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl IpNet {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, canonical(ip)) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                masked(u32::from(net).into(), 32, self.prefix)
+                    == masked(u32::from(ip).into(), 32, self.prefix)
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                masked(net.into(), 128, self.prefix) == masked(ip.into(), 128, self.prefix)
+            }
+            _ => false,
+        }
+    }
+}
+
+fn masked(bits: u128, width: u8, prefix: u8) -> u128 {
+    if prefix == 0 {
+        return 0;
+    }
+    bits >> (width - prefix)
+}
+
+/// IPv4 peers on a dual-stack socket show up as `::ffff:a.b.c.d`.
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+        v4 => v4,
+    }
+}
+
+/// Who may reach the dashboard at all, checked before auth and rate limiting.
+#[derive(Debug, Default)]
+pub struct AccessPolicy {
+    allow: Vec<IpNet>,
+    trusted_proxies: Vec<IpNet>,
+}
+
+impl AccessPolicy {
+    pub fn from_config(config: &AccessConfig) -> Result<Self, String> {
+        let parse =
+            |entries: &[String]| entries.iter().map(|e| e.parse()).collect::<Result<_, _>>();
+        Ok(Self {
+            allow: parse(&config.allow)?,
+            trusted_proxies: parse(&config.trusted_proxies)?,
+        })
+    }
+
+    fn is_trusted_proxy(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|net| net.contains(ip))
+    }
+
+    /// The address to apply policy to. Behind a trusted proxy that is the
+    /// right-most `X-Forwarded-For` hop not itself a trusted proxy; entries
+    /// further left are client-supplied and cannot be believed.
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.is_trusted_proxy(peer) {
+            return peer;
+        }
+        let hops = headers
+            .get_all(FORWARDED_FOR)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(str::trim)
+            .collect::<Vec<_>>();
+        for hop in hops.into_iter().rev() {
+            let Ok(ip) = hop.parse::<IpAddr>() else {
+                return peer;
+            };
+            if !self.is_trusted_proxy(ip) {
+                return canonical(ip);
+            }
+        }
+        peer
+    }
+
+    /// An empty allowlist admits everyone.
+    pub fn allows(&self, ip: IpAddr) -> bool {
+        self.allow.is_empty() || self.allow.iter().any(|net| net.contains(ip))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(allow: &[&str], trusted: &[&str]) -> AccessPolicy {
+        AccessPolicy::from_config(&AccessConfig {
+            allow: allow.iter().map(|s| s.to_string()).collect(),
+            trusted_proxies: trusted.iter().map(|s| s.to_string()).collect(),
+        })
+        .unwrap()
+    }
+
+    fn ip(raw: &str) -> IpAddr {
+        raw.parse().unwrap()
+    }
+
+    #[test]
+    fn cidr_matching() {
+        let net: IpNet = "192.168.1.0/24".parse().unwrap();
+        assert!(net.contains(ip("192.168.1.77")));
+        assert!(net.contains(ip("::ffff:192.168.1.77")));
+        assert!(!net.contains(ip("192.168.2.1")));
+        assert!("0.0.0.0/0"
+            .parse::<IpNet>()
+            .unwrap()
+            .contains(ip("8.8.8.8")));
+        assert!("fd00::/8".parse::<IpNet>().unwrap().contains(ip("fd12::1")));
+        assert!("10.0.0.0/33".parse::<IpNet>().is_err());
+        assert!("not-an-ip".parse::<IpNet>().is_err());
+    }
+
+    #[test]
+    fn forwarded_for_is_only_believed_from_trusted_proxies() {
+        let policy = policy(&["203.0.113.0/24"], &["127.0.0.1"]);
+        let mut headers = HeaderMap::new();
+        headers.insert(FORWARDED_FOR, "198.51.100.9, 203.0.113.5".parse().unwrap());
+
+        // Through the local tunnel: the hop the proxy saw is used, not the spoofable first entry.
+        let client = policy.client_ip(ip("127.0.0.1"), &headers);
+        assert_eq!(client, ip("203.0.113.5"));
+        assert!(policy.allows(client));
+
+        // Straight from the internet: the header is ignored.
+        let direct = policy.client_ip(ip("198.51.100.9"), &headers);
+        assert_eq!(direct, ip("198.51.100.9"));
+        assert!(!policy.allows(direct));
+        assert!(AccessPolicy::default().allows(direct));
+    }
+}
@@ -0,0 +1,401 @@
+use crate::security::{SecretString, VaultHandle};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+
+const TOKENS_VAULT_KEY: &str = "api_tokens";
+const AUDIT_MEMORY_CAPACITY: usize = 500;
+/// `auth-audit.jsonl` moves to `auth-audit.jsonl.1` at this size, so
+/// unauthenticated callers cannot grow it without bound.
+const AUDIT_FILE_MAX_BYTES: u64 = 1024 * 1024;
+/// Enough of the file's end to refill the in-memory entries on open.
+const AUDIT_TAIL_BYTES: u64 = 256 * 1024;
+
+pub const SESSION_COOKIE: &str = "telemy_session";
+pub const SESSION_TTL_SECS: u64 = 12 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Scope {
+    /// Dashboard, `/ws`, status and report reads.
+    TelemetryRead,
+    /// Settings, output names and Grafana configuration.
+    SettingsWrite,
+    /// Scene switching and other plugin commands over IPC.
+    IpcControl,
+    /// Aegis relay start/stop.
+    AegisControl,
+    /// OBS replay buffer and virtual camera start/stop/save.
+    ObsControl,
+    /// Everything, including token management.
+    Admin,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub token: String,
+    pub scopes: Vec<Scope>,
+    pub created_unix: u64,
+}
+
+/// Token metadata without the secret, for listings.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiTokenInfo {
+    pub id: String,
+    pub name: String,
+    pub scopes: Vec<Scope>,
+    pub created_unix: u64,
+}
+
+impl From<&ApiToken> for ApiTokenInfo {
+    fn from(token: &ApiToken) -> Self {
+        Self {
+            id: token.id.clone(),
+            name: token.name.clone(),
+            scopes: token.scopes.clone(),
+            created_unix: token.created_unix,
+        }
+    }
+}
+
+/// The server's master token (all scopes) plus named, scoped tokens persisted in
+/// the vault under `api_tokens`.
+pub struct TokenStore {
+    master: SecretString,
+    tokens: Mutex<Vec<ApiToken>>,
+    vault: Option<VaultHandle>,
+}
+
+impl TokenStore {
+    pub async fn load(master: SecretString, vault: VaultHandle) -> Self {
+        let tokens = vault
+            .read()
+            .await
+            .retrieve(TOKENS_VAULT_KEY)
+            .ok()
+            .and_then(|raw| serde_json::from_str(raw.expose()).ok())
+            .unwrap_or_default();
+        Self {
+            master,
+            tokens: Mutex::new(tokens),
+            vault: Some(vault),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn in_memory(master: &str) -> Self {
+        Self {
+            master: master.into(),
+            tokens: Mutex::new(Vec::new()),
+            vault: None,
+        }
+    }
+
+    pub fn authorize(&self, presented: &str, scope: Scope) -> bool {
+        if constant_time_eq(presented.as_bytes(), self.master.expose().as_bytes()) {
+            return true;
+        }
+        self.tokens.lock().unwrap().iter().any(|t| {
+            constant_time_eq(presented.as_bytes(), t.token.as_bytes())
+                && (t.scopes.contains(&scope) || t.scopes.contains(&Scope::Admin))
+        })
+    }
+
+    pub fn list(&self) -> Vec<ApiTokenInfo> {
+        self.tokens
+            .lock()
+            .unwrap()
+            .iter()
+            .map(ApiTokenInfo::from)
+            .collect()
+    }
+
+    pub async fn issue(&self, name: &str, scopes: Vec<Scope>) -> Result<ApiToken, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("token name is required".to_string());
+        }
+        if scopes.is_empty() {
+            return Err("at least one scope is required".to_string());
+        }
+
+        let token = ApiToken {
+            id: format!("tok_{}", random_alphanumeric(12)),
+            name: name.to_string(),
+            token: random_alphanumeric(40),
+            scopes,
+            created_unix: now_unix(),
+        };
+        let tokens = {
+            let mut tokens = self.tokens.lock().unwrap();
+            tokens.push(token.clone());
+            tokens.clone()
+        };
+        self.persist(&tokens).await?;
+        Ok(token)
+    }
+
+    pub async fn revoke(&self, id: &str) -> Result<bool, String> {
+        let tokens = {
+            let mut tokens = self.tokens.lock().unwrap();
+            let before = tokens.len();
+            tokens.retain(|t| t.id != id);
+            if tokens.len() == before {
+                return Ok(false);
+            }
+            tokens.clone()
+        };
+        self.persist(&tokens).await?;
+        Ok(true)
+    }
+
+    async fn persist(&self, tokens: &[ApiToken]) -> Result<(), String> {
+        let Some(vault) = &self.vault else {
+            return Ok(());
+        };
+        let raw = serde_json::to_string(tokens).map_err(|e| e.to_string())?;
+        vault
+            .write()
+            .await
+            .store(TOKENS_VAULT_KEY, &raw)
+            .map_err(|e| e.to_string())
+    }
+}
+
+struct Session {
+    token: String,
+    expires_unix: u64,
+}
+
+/// Browser sessions created by `/login`. Each session remembers the token it was
+/// exchanged for, so revoking that token also ends the session.
+pub struct SessionStore {
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl SessionStore {
+    pub fn new() -> Self {
+        Self {
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn create(&self, token: &str) -> String {
+        let now = now_unix();
+        let id = random_alphanumeric(48);
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, s| s.expires_unix > now);
+        sessions.insert(
+            id.clone(),
+            Session {
+                token: token.to_string(),
+                expires_unix: now + SESSION_TTL_SECS,
+            },
+        );
+        id
+    }
+
+    pub fn token_for(&self, id: &str) -> Option<String> {
+        let sessions = self.sessions.lock().unwrap();
+        sessions
+            .get(id)
+            .filter(|s| s.expires_unix > now_unix())
+            .map(|s| s.token.clone())
+    }
+
+    pub fn remove(&self, id: &str) {
+        self.sessions.lock().unwrap().remove(id);
+    }
+}
+
+fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Compares without short-circuiting on the first differing byte so response
+/// timing does not reveal how much of a guessed token was correct.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthFailure {
+    pub ip: String,
+    pub path: String,
+    pub timestamp_unix: u64,
+}
+
+/// Rejected requests, kept in memory for `/api/v1/security/audit` and appended
+/// as JSONL to `auth-audit.jsonl` so they survive restarts. IPC handshake
+/// failures are recorded here too, with `ip = "ipc"`. The file is rotated at
+/// [`AUDIT_FILE_MAX_BYTES`] and only its tail is read back.
+pub struct AuditLog {
+    entries: Mutex<VecDeque<AuthFailure>>,
+    path: Option<PathBuf>,
+}
+
+impl AuditLog {
+    pub fn open(path: PathBuf) -> Self {
+        let mut entries = VecDeque::new();
+        if let Ok(lines) = crate::recorder::tail_lines(&path, AUDIT_TAIL_BYTES) {
+            for line in lines {
+                if let Ok(entry) = serde_json::from_str::<AuthFailure>(&line) {
+                    if entries.len() == AUDIT_MEMORY_CAPACITY {
+                        entries.pop_front();
+                    }
+                    entries.push_back(entry);
+                }
+            }
+        }
+        Self {
+            entries: Mutex::new(entries),
+            path: Some(path),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn in_memory() -> Self {
+        Self {
+            entries: Mutex::new(VecDeque::new()),
+            path: None,
+        }
+    }
+
+    pub fn record(&self, ip: &str, path: &str) {
+        let entry = AuthFailure {
+            ip: ip.to_string(),
+            path: path.to_string(),
+            timestamp_unix: now_unix(),
+        };
+        tracing::warn!(ip = %entry.ip, path = %entry.path, "rejected unauthorized request");
+
+        // Writing under the lock keeps concurrent failures from racing the rotation.
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(file) = &self.path {
+            if let Err(err) = append_line(file, &entry) {
+                tracing::warn!("auth audit log write failed: {}", err);
+            }
+        }
+        if entries.len() == AUDIT_MEMORY_CAPACITY {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Most recent failures first.
+    pub fn recent(&self, limit: usize) -> Vec<AuthFailure> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+fn append_line(path: &Path, entry: &AuthFailure) -> std::io::Result<()> {
+    let line = serde_json::to_string(entry)?;
+    crate::recorder::append_rotating(path, &line, AUDIT_FILE_MAX_BYTES)
+}
+
+pub fn default_audit_path() -> PathBuf {
+    let base = std::env::var("APPDATA").unwrap_or_else(|_| ".".to_string());
+    Path::new(&base).join("Telemy").join("auth-audit.jsonl")
+}
+
+fn random_alphanumeric(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn master_token_has_every_scope() {
+        let store = TokenStore::in_memory("master");
+        assert!(store.authorize("master", Scope::Admin));
+        assert!(store.authorize("master", Scope::AegisControl));
+        assert!(!store.authorize("other", Scope::TelemetryRead));
+    }
+
+    #[tokio::test]
+    async fn scoped_token_is_limited_to_its_scopes_until_revoked() {
+        let store = TokenStore::in_memory("master");
+        let overlay = store
+            .issue("overlay", vec![Scope::TelemetryRead])
+            .await
+            .unwrap();
+        assert!(store.authorize(&overlay.token, Scope::TelemetryRead));
+        assert!(!store.authorize(&overlay.token, Scope::SettingsWrite));
+        assert!(!store.authorize(&overlay.token, Scope::Admin));
+
+        let admin = store.issue("ops", vec![Scope::Admin]).await.unwrap();
+        assert!(store.authorize(&admin.token, Scope::IpcControl));
+
+        assert!(store.revoke(&overlay.id).await.unwrap());
+        assert!(!store.authorize(&overlay.token, Scope::TelemetryRead));
+        assert!(!store.revoke(&overlay.id).await.unwrap());
+        assert_eq!(store.list().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn issue_requires_name_and_scopes() {
+        let store = TokenStore::in_memory("master");
+        assert!(store.issue(" ", vec![Scope::TelemetryRead]).await.is_err());
+        assert!(store.issue("overlay", vec![]).await.is_err());
+    }
+
+    #[test]
+    fn constant_time_eq_matches_exact_bytes_only() {
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret-longer"));
+    }
+
+    #[test]
+    fn audit_log_persists_and_reloads_failures() {
+        let path = std::env::temp_dir()
+            .join(format!("telemy-audit-{}", uuid::Uuid::new_v4()))
+            .join("auth-audit.jsonl");
+        let log = AuditLog::open(path.clone());
+        log.record("127.0.0.1", "/settings");
+        log.record("10.0.0.5", "/ws");
+        assert_eq!(log.recent(1)[0].path, "/ws");
+
+        let reopened = AuditLog::open(path.clone());
+        let recent = reopened.recent(10);
+        assert_eq!(recent.len(), 2);
+        assert_eq!(recent[1].ip, "127.0.0.1");
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn sessions_resolve_to_their_token_until_removed() {
+        let sessions = SessionStore::new();
+        let id = sessions.create("overlay-token");
+        assert_eq!(sessions.token_for(&id).as_deref(), Some("overlay-token"));
+        assert_eq!(sessions.token_for("unknown"), None);
+        sessions.remove(&id);
+        assert_eq!(sessions.token_for(&id), None);
+    }
+}
@@ -0,0 +1,82 @@
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+
+pub const HTML: &str = "text/html; charset=utf-8";
+
+/// Pages embed the caller's token, so only the browser may keep them, and it
+/// revalidates on every load; unchanged pages come back as a bodyless `304`.
+pub const PAGE: &str = "private, no-cache";
+/// Documents that only change with a new build or never (Grafana dashboard,
+/// OpenAPI, finished stream reports).
+pub const DOCUMENT: &str = "private, max-age=3600";
+/// Everything else is live telemetry or state; never stored.
+pub const DEFAULT: HeaderValue = HeaderValue::from_static("no-store");
+
+/// `body` with `cache_control` and an `ETag` over its bytes, or `304 Not
+/// Modified` when the request's `If-None-Match` already names it.
+pub fn with_etag(
+    headers: &HeaderMap,
+    cache_control: &'static str,
+    content_type: &'static str,
+    body: impl Into<Vec<u8>>,
+) -> Response {
+    let body = body.into();
+    let tag = etag(&body);
+    let cache = [
+        (header::CACHE_CONTROL, cache_control.to_string()),
+        (header::ETAG, tag.clone()),
+    ];
+    if matches(headers, &tag) {
+        return (StatusCode::NOT_MODIFIED, cache).into_response();
+    }
+    (
+        cache,
+        [(header::CONTENT_TYPE, content_type.to_string())],
+        body,
+    )
+        .into_response()
+}
+
+fn etag(body: &[u8]) -> String {
+    let digest = ring::digest::digest(&ring::digest::SHA256, body);
+    let hex: String = digest.as_ref()[..12]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    format!("\"{hex}\"")
+}
+
+fn matches(headers: &HeaderMap, tag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(|v| v.trim().trim_start_matches("W/"))
+        .any(|v| v == tag || v == "*")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matching_if_none_match_gets_not_modified() {
+        let fresh = with_etag(&HeaderMap::new(), PAGE, "text/html", "<p>hi</p>");
+        assert_eq!(fresh.status(), StatusCode::OK);
+        assert_eq!(fresh.headers()[header::CACHE_CONTROL], PAGE);
+        let tag = fresh.headers()[header::ETAG].clone();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::IF_NONE_MATCH,
+            HeaderValue::from_str(&format!("\"other\", W/{}", tag.to_str().unwrap())).unwrap(),
+        );
+        let cached = with_etag(&headers, PAGE, "text/html", "<p>hi</p>");
+        assert_eq!(cached.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(cached.headers()[header::ETAG], tag);
+
+        let changed = with_etag(&headers, PAGE, "text/html", "<p>bye</p>");
+        assert_eq!(changed.status(), StatusCode::OK);
+    }
+}
@@ -0,0 +1,117 @@
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// Body of every failed API call. `error` stays a plain string so existing
+/// clients that show it keep working; `code` is for clients that branch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ErrorBody {
+    /// Stable, snake_case kind of failure, e.g. `unauthorized` or `not_found`.
+    #[schema(example = "unauthorized")]
+    pub code: String,
+    /// Human-readable detail.
+    #[schema(example = "Unauthorized")]
+    pub error: String,
+    /// Set on `429` responses, mirroring the `Retry-After` header.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_secs: Option<u64>,
+}
+
+/// A failed request: the status plus an [`ErrorBody`].
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    status: StatusCode,
+    body: ErrorBody,
+}
+
+impl ApiError {
+    /// `code` is derived from the status, e.g. `502` becomes `bad_gateway`.
+    pub fn new(status: StatusCode, error: impl Into<String>) -> Self {
+        let code = status
+            .canonical_reason()
+            .unwrap_or("error")
+            .to_ascii_lowercase()
+            .replace([' ', '-'], "_");
+        Self {
+            status,
+            body: ErrorBody {
+                code,
+                error: error.into(),
+                retry_after_secs: None,
+            },
+        }
+    }
+
+    pub fn unauthorized() -> Self {
+        Self::new(StatusCode::UNAUTHORIZED, "Unauthorized")
+    }
+
+    pub fn forbidden() -> Self {
+        Self::new(StatusCode::FORBIDDEN, "Forbidden")
+    }
+
+    pub fn bad_request(error: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, error)
+    }
+
+    pub fn not_found(error: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, error)
+    }
+
+    pub fn internal(error: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, error)
+    }
+
+    pub fn bad_gateway(error: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_GATEWAY, error)
+    }
+
+    pub fn unavailable(error: impl Into<String>) -> Self {
+        Self::new(StatusCode::SERVICE_UNAVAILABLE, error)
+    }
+
+    /// Replaces the status-derived `code` with a more specific one.
+    pub fn with_code(mut self, code: &str) -> Self {
+        self.body.code = code.to_string();
+        self
+    }
+
+    pub fn too_many_requests(error: impl Into<String>, retry_after_secs: u64) -> Self {
+        let mut err = Self::new(StatusCode::TOO_MANY_REQUESTS, error);
+        err.body.retry_after_secs = Some(retry_after_secs);
+        err
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let retry_after = self.body.retry_after_secs;
+        let mut response = (self.status, axum::Json(self.body)).into_response();
+        if let Some(secs) = retry_after {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, secs.into());
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn code_follows_the_status() {
+        assert_eq!(ApiError::unauthorized().body.code, "unauthorized");
+        assert_eq!(ApiError::bad_gateway("x").body.code, "bad_gateway");
+        assert_eq!(ApiError::unavailable("x").body.code, "service_unavailable");
+        let unknown = ApiError::not_found("x").with_code("unknown_scene");
+        assert_eq!(unknown.body.code, "unknown_scene");
+        assert_eq!(unknown.status, StatusCode::NOT_FOUND);
+
+        let limited = ApiError::too_many_requests("slow down", 30).into_response();
+        assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(limited.headers()[header::RETRY_AFTER], "30");
+    }
+}
@@ -0,0 +1,426 @@
+use super::error::ApiError;
+use axum::http::StatusCode;
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+/// A bundled dashboard. Each is a `/api/dashboards/db` body whose panels read
+/// from the `${datasource}` variable, with a fixed uid so re-imports update it
+/// in place.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Dashboard {
+    /// Health, system, network and outputs at a glance.
+    #[default]
+    Overview,
+    /// Bitrate, FPS, drops and encoder lag per output.
+    Outputs,
+    /// CPU, memory, per-GPU load and VRAM, disk and network.
+    System,
+    /// Aegis relay session, overall and per bonded link.
+    Aegis,
+}
+
+impl Dashboard {
+    pub const ALL: [Dashboard; 4] = [
+        Dashboard::Overview,
+        Dashboard::Outputs,
+        Dashboard::System,
+        Dashboard::Aegis,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Dashboard::Overview => "overview",
+            Dashboard::Outputs => "outputs",
+            Dashboard::System => "system",
+            Dashboard::Aegis => "aegis",
+        }
+    }
+
+    pub fn json(self) -> &'static str {
+        match self {
+            Dashboard::Overview => include_str!("../../assets/grafana/overview.json"),
+            Dashboard::Outputs => include_str!("../../assets/grafana/outputs.json"),
+            Dashboard::System => include_str!("../../assets/grafana/system.json"),
+            Dashboard::Aegis => include_str!("../../assets/grafana/aegis.json"),
+        }
+    }
+
+    /// Download name; the overview keeps the name it had as the only dashboard.
+    pub fn file_name(self) -> String {
+        match self {
+            Dashboard::Overview => "telemy-dashboard.json".to_string(),
+            other => format!("telemy-{}-dashboard.json", other.as_str()),
+        }
+    }
+}
+
+impl std::str::FromStr for Dashboard {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|dashboard| dashboard.as_str() == s)
+            .ok_or_else(|| format!("unknown dashboard variant \"{s}\""))
+    }
+}
+
+/// Where and how to import a dashboard.
+pub struct ImportOptions<'a> {
+    /// Folder title or uid; created when no folder matches. `None` is General.
+    pub folder: Option<&'a str>,
+    /// Replace a dashboard with the same uid or title instead of failing.
+    pub overwrite: bool,
+    /// Prometheus data source the panels query instead of the default one.
+    pub datasource_uid: Option<&'a str>,
+    /// Import under a different uid, e.g. for a second copy.
+    pub dashboard_uid: Option<&'a str>,
+}
+
+/// What Grafana reports back after a successful import.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Imported {
+    /// Absolute link to the dashboard.
+    pub url: String,
+    pub uid: String,
+    pub version: Option<u64>,
+    /// Uid of the folder it landed in; absent for General.
+    pub folder_uid: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Folder {
+    uid: String,
+    title: String,
+}
+
+#[derive(Deserialize)]
+struct SaveResponse {
+    uid: String,
+    url: String,
+    version: Option<u64>,
+}
+
+/// Grafana's dashboard and folder uids: 1-40 of `[A-Za-z0-9_-]`.
+pub fn valid_uid(uid: &str) -> bool {
+    !uid.is_empty()
+        && uid.len() <= 40
+        && uid
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The `/api/dashboards/db` body for `template` with `options` applied.
+pub fn import_body(
+    template: &str,
+    options: &ImportOptions,
+    folder_uid: Option<&str>,
+) -> Result<Value, String> {
+    let mut body: Value =
+        serde_json::from_str(template).map_err(|e| format!("invalid dashboard template: {e}"))?;
+    let dashboard = body
+        .get_mut("dashboard")
+        .and_then(Value::as_object_mut)
+        .ok_or("dashboard template has no \"dashboard\" object")?;
+    dashboard.remove("id");
+    if let Some(uid) = options.dashboard_uid {
+        dashboard.insert("uid".to_string(), uid.into());
+    }
+    if let Some(uid) = options.datasource_uid {
+        for value in dashboard.values_mut() {
+            map_datasource(value, uid);
+        }
+    }
+    body["overwrite"] = options.overwrite.into();
+    if let Some(uid) = folder_uid {
+        body["folderUid"] = uid.into();
+    }
+    Ok(body)
+}
+
+/// Points every `${datasource}` reference, and the variable's current value,
+/// at `uid`.
+fn map_datasource(value: &mut Value, uid: &str) {
+    match value {
+        Value::Object(map) => {
+            if map.get("type").and_then(Value::as_str) == Some("datasource") {
+                map.insert(
+                    "current".to_string(),
+                    serde_json::json!({ "text": uid, "value": uid }),
+                );
+            }
+            if map.get("uid").and_then(Value::as_str) == Some("${datasource}") {
+                map.insert("uid".to_string(), uid.into());
+            }
+            map.values_mut().for_each(|v| map_datasource(v, uid));
+        }
+        Value::Array(items) => items.iter_mut().for_each(|v| map_datasource(v, uid)),
+        _ => {}
+    }
+}
+
+/// Imports `template` into the Grafana at `base_url` (no trailing slash).
+pub async fn import(
+    client: &reqwest::Client,
+    base_url: &str,
+    api_key: &str,
+    template: &str,
+    options: &ImportOptions<'_>,
+) -> Result<Imported, ApiError> {
+    let folder_uid = match options.folder {
+        Some(folder) => Some(ensure_folder(client, base_url, api_key, folder).await?),
+        None => None,
+    };
+    let body = import_body(template, options, folder_uid.as_deref()).map_err(ApiError::internal)?;
+
+    let (status, text) = send(
+        client
+            .post(format!("{base_url}/api/dashboards/db"))
+            .body(body.to_string()),
+        api_key,
+    )
+    .await?;
+    if status == reqwest::StatusCode::PRECONDITION_FAILED {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            format!("Dashboard already exists; import with overwrite to replace it: {text}"),
+        ));
+    }
+    if !status.is_success() {
+        return Err(ApiError::bad_gateway(format!(
+            "Grafana returned {status}: {text}"
+        )));
+    }
+    let saved: SaveResponse = serde_json::from_str(&text)
+        .map_err(|e| ApiError::bad_gateway(format!("Unexpected Grafana response: {e}")))?;
+    Ok(Imported {
+        url: format!("{base_url}{}", saved.url),
+        uid: saved.uid,
+        version: saved.version,
+        folder_uid,
+    })
+}
+
+/// The uid of the folder titled (or with uid) `folder`, created if missing.
+async fn ensure_folder(
+    client: &reqwest::Client,
+    base_url: &str,
+    api_key: &str,
+    folder: &str,
+) -> Result<String, ApiError> {
+    let (status, text) = send(client.get(format!("{base_url}/api/folders")), api_key).await?;
+    if !status.is_success() {
+        return Err(ApiError::bad_gateway(format!(
+            "Listing Grafana folders returned {status}: {text}"
+        )));
+    }
+    let folders: Vec<Folder> = serde_json::from_str(&text)
+        .map_err(|e| ApiError::bad_gateway(format!("Unexpected Grafana response: {e}")))?;
+    if let Some(found) = folders
+        .into_iter()
+        .find(|f| f.uid == folder || f.title == folder)
+    {
+        return Ok(found.uid);
+    }
+
+    let create = serde_json::json!({ "title": folder });
+    let (status, text) = send(
+        client
+            .post(format!("{base_url}/api/folders"))
+            .body(create.to_string()),
+        api_key,
+    )
+    .await?;
+    if !status.is_success() {
+        return Err(ApiError::bad_gateway(format!(
+            "Creating Grafana folder \"{folder}\" returned {status}: {text}"
+        )));
+    }
+    let created: Folder = serde_json::from_str(&text)
+        .map_err(|e| ApiError::bad_gateway(format!("Unexpected Grafana response: {e}")))?;
+    Ok(created.uid)
+}
+
+async fn send(
+    request: reqwest::RequestBuilder,
+    api_key: &str,
+) -> Result<(reqwest::StatusCode, String), ApiError> {
+    let resp = request
+        .header("Authorization", format!("Bearer {api_key}"))
+        .header("Content-Type", "application/json")
+        .send()
+        .await
+        .map_err(|e| ApiError::bad_gateway(format!("Failed to reach Grafana: {e}")))?;
+    let status = resp.status();
+    Ok((status, resp.text().await.unwrap_or_default()))
+}
+
+/// The OTLP auth value for a Grafana Cloud instance ID and access token, as
+/// settings stores it in the vault.
+pub fn basic_auth(instance_id: &str, api_token: &str) -> String {
+    let credentials = format!("{instance_id}:{api_token}");
+    format!("Basic {}", general_purpose::STANDARD.encode(credentials))
+}
+
+/// Where the metrics exporter pushes for an OTLP base `endpoint`.
+pub fn otlp_metrics_url(endpoint: &str) -> String {
+    format!("{endpoint}/v1/metrics")
+}
+
+/// A successful credentials check.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Verified {
+    /// The URL that accepted the push.
+    pub url: String,
+    pub status: u16,
+    pub latency_ms: u64,
+}
+
+/// Pushes an empty OTLP metrics export to `endpoint` with the given auth, so a
+/// wrong endpoint, instance ID or token shows up before the settings are saved
+/// rather than as silently dropped metrics. Nothing is written to the stack.
+pub async fn verify_otlp(
+    client: &reqwest::Client,
+    endpoint: &str,
+    auth_header: &str,
+    auth_value: &str,
+) -> Result<Verified, ApiError> {
+    let url = otlp_metrics_url(endpoint);
+    let started = std::time::Instant::now();
+    let resp = client
+        .post(&url)
+        .header(auth_header, auth_value)
+        .header("Content-Type", "application/x-protobuf")
+        .body(Vec::new())
+        .send()
+        .await
+        .map_err(|e| ApiError::bad_gateway(format!("Failed to reach {url}: {e}")))?;
+    let status = resp.status();
+    if !status.is_success() {
+        let text = resp.text().await.unwrap_or_default();
+        return Err(ApiError::bad_gateway(otlp_failure(status, &url, &text)));
+    }
+    Ok(Verified {
+        url,
+        status: status.as_u16(),
+        latency_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+/// What a rejected push most likely means.
+fn otlp_failure(status: reqwest::StatusCode, url: &str, body: &str) -> String {
+    let body = body.trim();
+    match status.as_u16() {
+        401 | 403 => format!(
+            "Grafana rejected the credentials ({status}); check the instance ID and token: {body}"
+        ),
+        404 | 405 => format!("No OTLP endpoint at {url} ({status}); check the endpoint URL"),
+        _ => format!("Grafana returned {status}: {body}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_body_applies_the_options() {
+        let options = ImportOptions {
+            folder: Some("Streaming"),
+            overwrite: false,
+            datasource_uid: Some("prom-1"),
+            dashboard_uid: Some("telemy-b"),
+        };
+        let body = import_body(Dashboard::Overview.json(), &options, Some("f1")).unwrap();
+        assert_eq!(body["overwrite"], false);
+        assert_eq!(body["folderUid"], "f1");
+        let dashboard = &body["dashboard"];
+        assert_eq!(dashboard["uid"], "telemy-b");
+        let panels = dashboard["panels"].as_array().unwrap();
+        assert!(panels.iter().all(|p| p["datasource"]["uid"] == "prom-1"));
+        assert_eq!(
+            dashboard["templating"]["list"][0]["current"]["value"],
+            "prom-1"
+        );
+    }
+
+    #[test]
+    fn defaults_keep_the_datasource_variable() {
+        let options = ImportOptions {
+            folder: None,
+            overwrite: true,
+            datasource_uid: None,
+            dashboard_uid: None,
+        };
+        let body = import_body(Dashboard::Overview.json(), &options, None).unwrap();
+        assert_eq!(body["dashboard"]["uid"], "telemy-obs");
+        assert!(body.get("folderUid").is_none());
+        let panels = body["dashboard"]["panels"].as_array().unwrap();
+        assert!(panels
+            .iter()
+            .all(|p| p["datasource"]["uid"] == "${datasource}"));
+        assert!(valid_uid("telemy-obs") && !valid_uid("a b") && !valid_uid(""));
+    }
+
+    #[test]
+    fn dashboards_only_query_exported_metrics() {
+        let exported: Vec<String> = crate::exporters::metric_names()
+            .map(|name| name.replace('.', "_"))
+            .collect();
+        let mut uids = std::collections::HashSet::new();
+        for dashboard in Dashboard::ALL {
+            let body: Value = serde_json::from_str(dashboard.json()).unwrap();
+            assert!(
+                uids.insert(body["dashboard"]["uid"].to_string()),
+                "{dashboard:?}"
+            );
+            let mut exprs = Vec::new();
+            collect_exprs(&body, &mut exprs);
+            assert!(!exprs.is_empty(), "{dashboard:?}");
+            for expr in exprs {
+                let metric: String = expr
+                    .trim_start_matches("label_values(")
+                    .chars()
+                    .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+                    .collect();
+                assert!(exported.contains(&metric), "{dashboard:?}: {expr}");
+            }
+        }
+    }
+
+    #[test]
+    fn otlp_checks_explain_the_failure() {
+        assert_eq!(
+            basic_auth("123456", "glc_x"),
+            format!("Basic {}", general_purpose::STANDARD.encode("123456:glc_x"))
+        );
+        let url = otlp_metrics_url("https://otlp.example.com/otlp");
+        assert_eq!(url, "https://otlp.example.com/otlp/v1/metrics");
+        let auth = otlp_failure(reqwest::StatusCode::UNAUTHORIZED, &url, "invalid token\n");
+        assert!(
+            auth.contains("instance ID and token: invalid token"),
+            "{auth}"
+        );
+        let missing = otlp_failure(reqwest::StatusCode::NOT_FOUND, &url, "");
+        assert!(missing.contains("check the endpoint URL"), "{missing}");
+    }
+
+    fn collect_exprs<'a>(value: &'a Value, out: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(map) => {
+                for (key, v) in map {
+                    match (key.as_str(), v.as_str()) {
+                        ("expr", Some(expr)) => out.push(expr),
+                        ("query", Some(q)) if q.starts_with("label_values(") => out.push(q),
+                        _ => collect_exprs(v, out),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter().for_each(|v| collect_exprs(v, out)),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+
+/// Languages with a bundle under `assets/i18n`, for `server.language`.
+pub const LANGUAGES: [&str; 4] = ["en", "de", "es", "pt"];
+
+/// UI strings for one language. Keys missing from a bundle fall back to
+/// English, and unknown keys to the key itself.
+pub struct Bundle(BTreeMap<String, String>);
+
+impl Bundle {
+    pub fn load(language: &str) -> Self {
+        let mut strings = parse("en");
+        if language != "en" {
+            strings.extend(parse(language));
+        }
+        Self(strings)
+    }
+
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.0.get(key).map(String::as_str).unwrap_or(key)
+    }
+
+    /// Fills `{{t:key}}` placeholders in a page template. Values are trusted
+    /// HTML from the embedded bundles.
+    pub fn localize(&self, template: &str) -> String {
+        let mut html = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find("{{t:") {
+            let Some(len) = rest[start..].find("}}") else {
+                break;
+            };
+            html.push_str(&rest[..start]);
+            html.push_str(self.get(&rest[start + 4..start + len]));
+            rest = &rest[start + len + 2..];
+        }
+        html.push_str(rest);
+        html
+    }
+
+    /// The whole bundle as JSON, for the strings pages build in script.
+    pub fn script_json(&self) -> String {
+        super::pages::script_json(&self.0)
+    }
+}
+
+fn source(language: &str) -> Option<&'static str> {
+    Some(match language {
+        "en" => include_str!("../../assets/i18n/en.json"),
+        "de" => include_str!("../../assets/i18n/de.json"),
+        "es" => include_str!("../../assets/i18n/es.json"),
+        "pt" => include_str!("../../assets/i18n/pt.json"),
+        _ => return None,
+    })
+}
+
+fn parse(language: &str) -> BTreeMap<String, String> {
+    source(language)
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::pages::Page;
+
+    #[test]
+    fn every_bundle_has_the_english_keys() {
+        let english = parse("en");
+        assert!(!english.is_empty());
+        for language in LANGUAGES {
+            let bundle = parse(language);
+            let missing: Vec<_> = english
+                .keys()
+                .filter(|k| !bundle.contains_key(*k))
+                .collect();
+            let extra: Vec<_> = bundle
+                .keys()
+                .filter(|k| !english.contains_key(*k))
+                .collect();
+            assert!(
+                missing.is_empty() && extra.is_empty(),
+                "{language}: missing {missing:?}, extra {extra:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn templates_only_use_known_keys() {
+        let english = parse("en");
+        for page in Page::ALL {
+            for (start, _) in page.embedded().match_indices("{{t:") {
+                let key = &page.embedded()[start + 4..];
+                let key = &key[..key.find("}}").unwrap()];
+                assert!(english.contains_key(key), "{page:?}: {key}");
+            }
+        }
+    }
+
+    #[test]
+    fn localize_falls_back_to_english_then_the_key() {
+        let bundle = Bundle::load("de");
+        assert_eq!(
+            bundle.localize("<h1>{{t:common.settings}}</h1>"),
+            "<h1>Einstellungen</h1>"
+        );
+        assert_eq!(
+            Bundle::load("xx").localize("{{t:common.settings}} {{t:nope}} {{t:open"),
+            "Settings nope {{t:open"
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use super::*;
+use axum::http::HeaderValue;
+
+/// Pre-`/api/v1` paths. Each moved to the same path under `/api/v1`.
+const MOVED: &[&str] = &[
+    "/ws",
+    "/settings",
+    "/output-names",
+    "/grafana-dashboard",
+    "/aegis",
+    "/ipc",
+    "/reports",
+];
+
+/// The `/api/v1` path a legacy API `path` moved to. The `GET /settings` page
+/// shares its path with the old form endpoint; callers check the method.
+pub fn successor(path: &str) -> Option<String> {
+    MOVED
+        .iter()
+        .any(|old| {
+            path.strip_prefix(old)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        })
+        .then(|| format!("/api/v1{path}"))
+}
+
+/// The old flat routes, kept so overlays and scripts written against them
+/// keep working. Responses are marked deprecated and point at the successor.
+pub fn aliases() -> Router<Arc<ServerState>> {
+    Router::new()
+        .route("/ws", get(ws_handler))
+        .route("/settings", post(settings_submit))
+        .route("/output-names", get(get_output_names))
+        .route("/output-names", post(save_output_names))
+        .route("/grafana-dashboard", get(grafana_dashboard_download))
+        .route("/grafana-dashboard/import", post(grafana_dashboard_import))
+        .route("/aegis/status", get(get_aegis_status))
+        .route("/aegis/start", post(post_aegis_start))
+        .route("/aegis/stop", post(post_aegis_stop))
+        .route("/aegis/login", get(get_aegis_login))
+        .route("/aegis/login", post(post_aegis_login))
+        .route("/ipc/status", get(get_ipc_status))
+        .route("/ipc/switch-scene", post(post_ipc_switch_scene))
+        .route("/reports", get(get_reports))
+        .route("/reports/:id", get(get_report))
+        .route_layer(middleware::from_fn(mark_deprecated))
+}
+
+async fn mark_deprecated(req: Request, next: Next) -> Response {
+    let successor = successor(req.uri().path());
+    let mut response = next.run(req).await;
+    if let Some(link) = successor.and_then(|path| {
+        HeaderValue::from_str(&format!("<{path}>; rel=\"successor-version\"")).ok()
+    }) {
+        let headers = response.headers_mut();
+        headers.insert("deprecation", HeaderValue::from_static("true"));
+        headers.insert(header::LINK, link);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn moved_paths_gain_the_version_prefix() {
+        assert_eq!(successor("/ws").as_deref(), Some("/api/v1/ws"));
+        assert_eq!(
+            successor("/reports/abc.html").as_deref(),
+            Some("/api/v1/reports/abc.html")
+        );
+        assert_eq!(
+            successor("/aegis/start").as_deref(),
+            Some("/api/v1/aegis/start")
+        );
+        assert_eq!(successor("/wsx"), None);
+        assert_eq!(successor("/health"), None);
+        assert_eq!(successor("/api/v1/status"), None);
+    }
+}
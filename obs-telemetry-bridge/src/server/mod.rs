@@ -1,94 +1,289 @@
+use crate::activity::ActionKind;
 use crate::aegis::{
-    ControlPlaneClient, RelaySession, RelayStartClientContext, RelayStartRequest, RelayStopRequest,
+    control_plane_health, resolve_region_preference, ControlPlaneClient, ControlPlaneHealth,
+    DeviceLogin, RelaySession, RelaySessionHandle, RelayStartClientContext, RelayStartRequest,
+    RelayStopRequest, SessionFreshness, SessionPoller,
 };
-use crate::config::{Config, ThemeConfig};
-use crate::ipc::{CoreIpcCommand, CoreIpcCommandSender, IpcDebugStatus, IpcDebugStatusHandle};
+use crate::config::{
+    Config, ConfigHandle, CorsConfig, DashboardPanel, RateLimitConfig, ThemeConfig,
+};
+use crate::ipc::{
+    CoreIpcCommand, CoreIpcCommandSender, IpcDebugStatus, IpcDebugStatusHandle,
+    OverrideStoreHandle, SessionOverrides,
+};
+use crate::ingest::IngestProbe;
+use crate::metrics::{ObsControlAction, ObsLink};
 use crate::model::TelemetryFrame;
-use crate::security::Vault;
+use crate::obslog::ObsLog;
+use crate::pause::PauseStatus;
+use crate::peers::Peers;
+use crate::rules::RulesState;
+use crate::security::{SecretString, Vault, VaultHandle};
+use access::AccessPolicy;
+use auth::{Scope, SessionStore, TokenStore, SESSION_COOKIE, SESSION_TTL_SECS};
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        Json, Query, State,
+        ConnectInfo, Extension, Json, Path, Query, Request, State,
     },
-    http::{HeaderMap, StatusCode},
-    response::{Html, IntoResponse},
-    routing::{get, post},
+    http::{header, HeaderMap, StatusCode},
+    middleware::{self, Next},
+    response::{Html, IntoResponse, Response},
+    routing::{delete, get, post, put},
     Form, Router,
 };
-use base64::{engine::general_purpose, Engine as _};
+use error::{ApiError, ErrorBody};
+use i18n::Bundle;
+use pages::Page;
 use rand::{distributions::Alphanumeric, Rng};
+use rate_limit::{Decision, RateLimiter};
 use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
-    net::SocketAddr,
-    sync::{Arc, Mutex},
-    time::{Duration, SystemTime, UNIX_EPOCH},
+    net::{IpAddr, SocketAddr},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use tokio::net::TcpListener;
 use tokio::sync::watch;
+use tower_http::{
+    compression::CompressionLayer,
+    cors::{AllowOrigin, CorsLayer},
+    set_header::SetResponseHeaderLayer,
+};
+use utoipa::ToSchema;
+use ws_hub::{ClientKind, WsClient, WsFormat, WsHub};
+
+mod access;
+mod auth;
+mod caching;
+mod error;
+mod grafana;
+mod i18n;
+mod legacy;
+mod openapi;
+mod pages;
+mod rate_limit;
+mod ws_hub;
+
+pub use access::IpNet;
+pub use auth::{default_audit_path, AuditLog};
+pub use i18n::LANGUAGES;
+pub use pages::{export_templates, templates_dir};
 
 #[derive(Clone)]
-#[allow(dead_code)]
 struct ServerState {
-    token: String,
+    tokens: Arc<TokenStore>,
+    audit: Arc<AuditLog>,
+    sessions: Arc<SessionStore>,
+    limiter: Arc<RateLimiter>,
+    access: Arc<AccessPolicy>,
+    ws_hub: Arc<WsHub>,
     rx: watch::Receiver<TelemetryFrame>,
-    theme: ThemeConfig,
-    vault: Arc<Mutex<Vault>>,
-    grafana_configured: Arc<Mutex<bool>>,
-    aegis_session_snapshot: Arc<Mutex<Option<RelaySession>>>,
+    vault: VaultHandle,
+    grafana_configured: Arc<AtomicBool>,
+    aegis_session_snapshot: RelaySessionHandle,
     ipc_cmd_tx: CoreIpcCommandSender,
     ipc_debug_status: IpcDebugStatusHandle,
+    ipc_overrides: OverrideStoreHandle,
+    obs: ObsLink,
+    obs_log: Arc<ObsLog>,
+    ingest: Arc<IngestProbe>,
+    peers: Arc<Peers>,
+    rules: Arc<RulesState>,
+    aegis_login: Arc<Mutex<AegisLoginStatus>>,
+    config: ConfigHandle,
+    shutdown: watch::Sender<bool>,
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn start(
     addr: SocketAddr,
-    token: String,
+    token: SecretString,
     rx: watch::Receiver<TelemetryFrame>,
-    mut shutdown_rx: watch::Receiver<bool>,
-    theme: ThemeConfig,
-    vault: Arc<Mutex<Vault>>,
+    shutdown: watch::Sender<bool>,
+    vault: VaultHandle,
     grafana_configured: bool,
-    aegis_session_snapshot: Arc<Mutex<Option<RelaySession>>>,
+    aegis_session_snapshot: RelaySessionHandle,
     ipc_cmd_tx: CoreIpcCommandSender,
     ipc_debug_status: IpcDebugStatusHandle,
+    ipc_overrides: OverrideStoreHandle,
+    obs: ObsLink,
+    obs_log: Arc<ObsLog>,
+    ingest: Arc<IngestProbe>,
+    peers: Arc<Peers>,
+    rules: Arc<RulesState>,
+    audit: Arc<AuditLog>,
+    rate_limit: RateLimitConfig,
+    config: ConfigHandle,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let cors = cors_layer(&config.read(|c| c.server.cors.clone()));
     let state = Arc::new(ServerState {
-        token,
+        tokens: Arc::new(TokenStore::load(token, vault.clone()).await),
+        audit,
+        sessions: Arc::new(SessionStore::new()),
+        limiter: Arc::new(RateLimiter::new(rate_limit)),
+        access: Arc::new(AccessPolicy::from_config(
+            &config.read(|c| c.server.access.clone()),
+        )?),
+        ws_hub: WsHub::spawn(
+            rx.clone(),
+            ipc_debug_status.clone(),
+            aegis_session_snapshot.clone(),
+            Duration::from_millis(500),
+        ),
         rx,
-        theme,
         vault,
-        grafana_configured: Arc::new(Mutex::new(grafana_configured)),
+        grafana_configured: Arc::new(AtomicBool::new(grafana_configured)),
         aegis_session_snapshot,
         ipc_cmd_tx,
         ipc_debug_status,
+        ipc_overrides,
+        obs,
+        obs_log,
+        ingest,
+        peers,
+        rules,
+        aegis_login: Arc::new(Mutex::new(AegisLoginStatus::default())),
+        config,
+        shutdown: shutdown.clone(),
     });
 
     let app = Router::new()
         .route("/health", get(health_check))
+        .route("/api/openapi.json", get(openapi::openapi_json))
+        .route("/api/docs", get(openapi::docs_page))
+        .route("/login", get(login_page))
+        .route("/login", post(login_submit))
+        .route("/logout", get(logout))
         .route("/obs", get(obs_page))
-        .route("/ws", get(ws_handler))
+        .route("/dock", get(dock_page))
         .route("/setup", get(setup_page))
         .route("/settings", get(settings_page))
+        .route("/metrics", get(get_prometheus_metrics))
+        .nest("/api/v1", api_v1())
+        .merge(legacy::aliases())
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            audit_auth_failures,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.limiter.clone(),
+            enforce_rate_limit,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            enforce_access,
+        ))
+        .with_state(state)
+        .layer(SetResponseHeaderLayer::if_not_present(
+            header::CACHE_CONTROL,
+            caching::DEFAULT,
+        ))
+        .layer(CompressionLayer::new().gzip(true).deflate(true));
+    // Outermost, so preflights are answered before auth and 401s still carry
+    // the headers a cross-origin caller needs to read them.
+    let app = match cors {
+        Some(cors) => app.layer(cors),
+        None => app,
+    };
+
+    let listener = TcpListener::bind(addr).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(async move {
+        let mut shutdown_rx = shutdown.subscribe();
+        let _ = shutdown_rx.changed().await;
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// The versioned API. Breaking payload changes ship under a new version
+/// instead of changing these routes; the pages, `/health` and `/metrics` stay
+/// unversioned.
+fn api_v1() -> Router<Arc<ServerState>> {
+    Router::new()
+        .route("/ws", get(ws_handler))
         .route("/settings", post(settings_submit))
         .route("/output-names", get(get_output_names))
         .route("/output-names", post(save_output_names))
         .route("/grafana-dashboard", get(grafana_dashboard_download))
         .route("/grafana-dashboard/import", post(grafana_dashboard_import))
+        .route("/settings/grafana/test", post(post_grafana_test))
         .route("/aegis/status", get(get_aegis_status))
         .route("/aegis/start", post(post_aegis_start))
         .route("/aegis/stop", post(post_aegis_stop))
+        .route("/aegis/login", get(get_aegis_login))
+        .route("/aegis/login", post(post_aegis_login))
         .route("/ipc/status", get(get_ipc_status))
         .route("/ipc/switch-scene", post(post_ipc_switch_scene))
-        .with_state(state);
-
-    let listener = TcpListener::bind(addr).await?;
-    axum::serve(listener, app)
-        .with_graceful_shutdown(async move {
-            let _ = shutdown_rx.changed().await;
-        })
-        .await?;
+        .route("/obs/scenes", get(get_obs_scenes))
+        .route("/obs/control", post(post_obs_control))
+        .route("/obs/scene-history", get(get_scene_history))
+        .route("/obs/log-events", get(get_obs_log_events))
+        .route("/network/ingest-check", get(get_ingest_check))
+        .route("/events", get(get_events))
+        .route("/audit", get(get_audit))
+        .route("/peers", get(get_peers))
+        .route("/rules", get(get_rules))
+        .route("/settings/runtime", get(get_runtime_settings))
+        .route("/settings/runtime", put(put_runtime_settings))
+        .route("/reports", get(get_reports))
+        .route("/history/export.csv", get(get_history_csv))
+        .route("/reports/:id", get(get_report))
+        .route("/tokens", get(get_tokens))
+        .route("/tokens", post(post_token))
+        .route("/tokens/:id", delete(delete_token))
+        .route("/security/audit", get(get_security_audit))
+        .route("/logs", get(get_logs))
+        .route("/selfstats", get(get_selfstats))
+        .route("/clients", get(get_ws_clients))
+        .route("/pause", get(get_pause))
+        .route("/pause", put(put_pause))
+        .route("/status", get(get_status))
+        .route("/shutdown", post(post_shutdown))
+}
 
-    Ok(())
+/// `None` when no origins are configured, leaving the API same-origin only.
+fn cors_layer(config: &CorsConfig) -> Option<CorsLayer> {
+    if config.allowed_origins.is_empty() {
+        return None;
+    }
+    let origins = if config.allowed_origins.iter().any(|o| o == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(
+            config
+                .allowed_origins
+                .iter()
+                .filter_map(|o| o.parse::<header::HeaderValue>().ok()),
+        )
+    };
+    let headers: Vec<header::HeaderName> = config
+        .allowed_headers
+        .iter()
+        .filter_map(|h| h.parse().ok())
+        .collect();
+    Some(
+        CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods([
+                axum::http::Method::GET,
+                axum::http::Method::POST,
+                axum::http::Method::PUT,
+                axum::http::Method::DELETE,
+            ])
+            .allow_headers(headers)
+            .expose_headers([header::RETRY_AFTER, header::LINK])
+            .max_age(Duration::from_secs(config.max_age_secs)),
+    )
 }
 
 async fn obs_page(
@@ -97,678 +292,82 @@ async fn obs_page(
     query: Query<HashMap<String, String>>,
 ) -> impl IntoResponse {
     // Support both Authorization header (for API access) and query param (for browser/Dock access)
-    if !is_token_valid(&headers, &query.0, &state.token, QueryTokenPolicy::Allow) {
-        return StatusCode::UNAUTHORIZED.into_response();
+    if !has_credentials(&headers, &query.0) {
+        return axum::response::Redirect::to("/login?next=/obs").into_response();
+    }
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Allow,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
     }
 
-    let css = theme_css(&state.theme);
+    let css = state.config.read(|c| theme_css(&c.theme));
+    let (layout, dir, lang) = state.config.read(|c| {
+        (
+            pages::script_json(&c.dashboard),
+            pages::templates_dir(&c.dashboard),
+            c.server.language.clone(),
+        )
+    });
+    let bundle = Bundle::load(&lang);
+
+    // Echo the caller's own token so a scoped token never reveals the master.
+    let token = presented_token(&headers, &query.0, QueryTokenPolicy::Allow).unwrap_or_default();
+    let html = pages::render(
+        &pages::localized(&dir, Page::Dashboard, &bundle),
+        &[
+            ("THEME_VARS", &css),
+            ("TOKEN", &html_escape(token)),
+            ("LAYOUT", &layout),
+            ("LANG", &lang),
+            ("I18N", &bundle.script_json()),
+        ],
+    );
+    caching::with_etag(&headers, caching::PAGE, caching::HTML, html)
+}
 
-    let html = r##"<!doctype html>
-<html>
-<head>
-  <meta charset="utf-8" />
-  <title>OBS Telemetry</title>
-  <style>
-    :root {
-      {{THEME_VARS}}
-    }
-    body {
-      margin: 0;
-      font-family: var(--font);
-      background:
-        radial-gradient(circle at 10% 0%, rgba(51,209,122,0.09), transparent 42%),
-        radial-gradient(circle at 100% 0%, rgba(246,211,45,0.07), transparent 34%),
-        linear-gradient(180deg, #07090d 0%, var(--bg) 38%, #090d14 100%);
-      color: #e6f0ff;
-    }
-    .wrap { max-width: 1180px; margin: 0 auto; padding: 18px 16px 24px; }
-    .row { display: flex; gap: 10px; align-items: center; flex-wrap: wrap; }
-    .badge {
-      padding: 7px 10px;
-      background: linear-gradient(180deg, rgba(255,255,255,0.02), rgba(255,255,255,0));
-      border-radius: 999px;
-      font-size: 12px;
-      border: 1px solid var(--line);
-      box-shadow: inset 0 0 0 1px rgba(255,255,255,0.01);
-    }
-    .shell { display: grid; gap: 12px; }
-    .hero {
-      background: linear-gradient(180deg, rgba(255,255,255,0.025), rgba(255,255,255,0.01));
-      border: 1px solid var(--line);
-      border-radius: 14px;
-      padding: 14px;
-      box-shadow: 0 14px 32px rgba(0,0,0,0.24);
-    }
-    .hero-header { display:flex; gap:12px; justify-content:space-between; align-items:flex-start; flex-wrap:wrap; }
-    .hero-title { font-size: 18px; font-weight: 700; letter-spacing: 0.02em; }
-    .hero-sub { color: var(--muted); font-size: 12px; margin-top: 4px; }
-    .hero-right { display:flex; gap:8px; flex-wrap:wrap; align-items:center; }
-    .link-badge { text-decoration:none; color:inherit; cursor:pointer; }
-    .grid { display: grid; grid-template-columns: 1fr; gap: 8px; }
-    .panel-card {
-      background: linear-gradient(180deg, rgba(255,255,255,0.02), rgba(255,255,255,0.005));
-      border: 1px solid var(--line);
-      border-radius: 12px;
-      padding: 12px;
-    }
-    .section-head { display:flex; justify-content:space-between; align-items:center; gap:8px; margin-bottom:8px; }
-    .section-title { font-size: 12px; color: var(--muted); text-transform: uppercase; letter-spacing: 0.08em; }
-    .output { background: rgba(255,255,255,0.015); border: 1px solid var(--line); border-radius: 8px; padding: 8px 10px; }
-    .output-inactive { background: rgba(255,255,255,0.01); border: 1px solid var(--line); border-radius: 8px; padding: 8px 10px; opacity: 0.5; }
-    .name { font-size: 13px; margin-bottom: 6px; }
-    .bar { height: 8px; background: #0f141c; border: 1px solid var(--line); border-radius: 4px; overflow: hidden; }
-    .fill { height: 100%; background: var(--good); width: 0%; }
-    canvas { width: 100%; height: 140px; background: #0d121a; border: 1px solid var(--line); border-radius: 8px; }
-    .muted { color: var(--muted); }
-    .edit-btn { cursor: pointer; color: var(--muted); font-size: 11px; text-decoration: underline; margin-left: 10px; }
-    .edit-btn:hover { color: var(--good); }
-    .modal { display: none; position: fixed; top: 0; left: 0; width: 100%; height: 100%; background: rgba(0,0,0,0.8); z-index: 1000; }
-    .modal-content { background: var(--panel); margin: 50px auto; padding: 20px; width: 90%; max-width: 600px; border: 1px solid var(--line); border-radius: 8px; max-height: 80vh; overflow-y: auto; }
-    .modal-header { display: flex; justify-content: space-between; align-items: center; margin-bottom: 20px; }
-    .modal-title { font-size: 16px; font-weight: bold; }
-    .close-btn { cursor: pointer; font-size: 20px; color: var(--muted); }
-    .close-btn:hover { color: var(--bad); }
-    .name-row { display: flex; gap: 10px; margin-bottom: 10px; align-items: center; }
-    .name-row input { flex: 1; background: var(--bg); border: 1px solid var(--line); color: #e6f0ff; padding: 6px; border-radius: 4px; }
-    .name-row .id-label { width: 150px; font-size: 11px; color: var(--muted); word-break: break-all; }
-    .save-btn { background: var(--good); color: #0b0e12; border: none; padding: 10px 20px; border-radius: 4px; cursor: pointer; font-weight: bold; margin-top: 10px; }
-    .save-btn:hover { opacity: 0.9; }
-    .add-btn { background: rgba(255,255,255,0.015); color: var(--good); border: 1px solid var(--good); padding: 7px 12px; border-radius: 999px; cursor: pointer; font-size: 12px; margin-bottom: 10px; }
-    .add-btn:hover { background: rgba(51,209,122,0.08); }
-    .test-mode { border: 1px solid var(--warn); color: var(--warn); font-weight: bold; }
-    .rec-badge { border: 1px solid var(--bad); color: var(--bad); font-weight: bold; }
-    .toggle-row { display: flex; align-items: center; gap: 6px; margin-top: 10px; font-size: 11px; color: var(--muted); }
-    .toggle-row input { accent-color: var(--good); }
-    .stats-row { display: flex; gap: 10px; flex-wrap: wrap; margin-top: 8px; }
-    .stat { padding: 6px 8px; background: rgba(255,255,255,0.015); border-radius: 8px; font-size: 11px; border: 1px solid var(--line); color: var(--muted); }
-    .dashboard-grid { display:grid; grid-template-columns: 1.15fr 0.85fr; gap:12px; align-items:start; }
-    .summary-grid { display:grid; grid-template-columns: repeat(3, minmax(0,1fr)); gap:10px; }
-    .summary-box { border:1px solid var(--line); border-radius:10px; padding:10px; background: rgba(255,255,255,0.015); }
-    .summary-label { color: var(--muted); font-size: 10px; text-transform: uppercase; letter-spacing: 0.08em; margin-bottom: 6px; }
-    .summary-value { font-size: 12px; line-height: 1.45; }
-    .details-shell { margin-top: 10px; border: 1px solid var(--line); border-radius: 10px; background: rgba(255,255,255,0.01); overflow: hidden; }
-    .details-shell > summary { cursor: pointer; list-style: none; padding: 10px 12px; color: var(--muted); font-size: 12px; user-select: none; }
-    .details-shell > summary::-webkit-details-marker { display: none; }
-    .details-shell > summary::before { content: "▸ "; color: var(--good); }
-    .details-shell[open] > summary::before { content: "▾ "; }
-    .details-content { padding: 0 12px 12px; }
-    .aegis-controls { display:flex; gap:8px; flex-wrap:wrap; align-items:center; }
-    .aegis-actions { margin-top: 8px; }
-    .toolbar-row { display:flex; justify-content:space-between; gap:8px; align-items:center; flex-wrap:wrap; margin-top:8px; }
-    .toolbar-links { display:flex; align-items:center; gap:2px; flex-wrap:wrap; }
-    @media (max-width: 860px) {
-      .dashboard-grid { grid-template-columns: 1fr; }
-      .summary-grid { grid-template-columns: 1fr; }
-      .hero-header { align-items: stretch; }
-      .hero-right { width: 100%; }
-      .hero-right .badge, .hero-right .link-badge { width: fit-content; }
+// Narrow layout for OBS custom browser docks: status at a glance plus the handful
+// of actions worth a click mid-stream. The full dashboard stays at /obs.
+async fn dock_page(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !has_credentials(&headers, &query.0) {
+        return axum::response::Redirect::to("/login?next=/dock").into_response();
+    }
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Allow,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
     }
-  </style>
-</head>
-<body>
-  <div class="wrap">
-    <div class="shell">
-      <div class="hero">
-        <div class="hero-header">
-          <div>
-            <div class="hero-title">Telemy Control Surface</div>
-            <div class="hero-sub">Legacy dashboard shell with v0.0.3 Aegis controls and live status plumbing</div>
-          </div>
-          <div class="hero-right">
-            <div class="badge" id="status">DISCONNECTED</div>
-            <div class="badge" id="time">--</div>
-            <a href="/settings?token={{TOKEN}}" class="badge link-badge">Settings</a>
-          </div>
-        </div>
-        <div class="row" style="margin-top:10px;">
-          <div class="badge" id="health">Health: --</div>
-          <div class="badge" id="obs">OBS: --</div>
-          <div class="badge" id="testmode" style="display:none;" class="test-mode">STUDIO MODE</div>
-          <div class="badge rec-badge" id="recbadge" style="display:none;">REC</div>
-          <div class="badge" id="sys">SYS: --</div>
-          <div class="badge" id="net">NET: --</div>
-          <div class="badge" id="aegis">AEGIS: --</div>
-        </div>
-      </div>
-
-      <div class="dashboard-grid">
-        <div class="panel-card">
-          <div class="section-head">
-            <div class="section-title">Live Summary</div>
-            <div class="muted" style="font-size:11px;">Connection, system, and main stream info</div>
-          </div>
-          <div class="summary-grid">
-            <div class="summary-box">
-              <div class="summary-label">Connection</div>
-              <div class="summary-value" id="summaryConn">OBS: --<br>Latency: --<br>Aegis: --</div>
-            </div>
-            <div class="summary-box">
-              <div class="summary-label">System</div>
-              <div class="summary-value" id="summarySystem">CPU: --<br>RAM: --<br>GPU/VRAM: --</div>
-            </div>
-            <div class="summary-box">
-              <div class="summary-label">Main Stream / Encoder</div>
-              <div class="summary-value" id="summaryMain">Bitrate: --<br>Drops: --<br>Lag/FPS: --</div>
-            </div>
-          </div>
-          <details class="details-shell" id="diagDetails">
-            <summary>Expanded Diagnostics</summary>
-            <div class="details-content">
-              <div class="section-head" style="margin-top:8px;">
-                <div class="section-title">OBS Health Trend</div>
-                <div class="muted" style="font-size:11px;">Graph shows overall health (1.0 = best)</div>
-              </div>
-              <canvas id="graph" width="600" height="140"></canvas>
-              <div class="stats-row" id="statsRow">
-                <div class="stat" id="statDisk">Disk: --</div>
-                <div class="stat" id="statRender">Render missed: --</div>
-                <div class="stat" id="statOutput">Encoder skipped: --</div>
-                <div class="stat" id="statFps">FPS: --</div>
-              </div>
-            </div>
-          </details>
-        </div>
-
-        <div class="panel-card">
-          <div class="section-head">
-            <div class="section-title">Aegis Relay Controls</div>
-          </div>
-          <div class="aegis-controls">
-            <button class="add-btn" id="aegisStartBtn" style="margin-bottom:0;">Aegis Start</button>
-            <button class="add-btn" id="aegisStopBtn" style="margin-bottom:0;">Aegis Stop</button>
-            <span class="edit-btn" id="refreshAegisBtn" style="margin-left:0;">Refresh Aegis</span>
-          </div>
-          <div class="row aegis-actions" style="margin-top:8px;">
-            <input id="ipcSceneName" type="text" value="BRB" placeholder="Scene name"
-              style="background:var(--bg); border:1px solid var(--line); color:#e6f0ff; padding:7px 9px; border-radius:8px; min-width:110px;">
-            <input id="ipcSceneReason" type="text" value="manual_debug" placeholder="Reason"
-              style="background:var(--bg); border:1px solid var(--line); color:#e6f0ff; padding:7px 9px; border-radius:8px; min-width:130px;">
-            <label style="display:flex; align-items:center; gap:6px; color:#9cb0d0; font-size:12px;">
-              <input id="ipcAllowEmptyScene" type="checkbox">
-              empty (debug)
-            </label>
-            <button class="add-btn" id="ipcSwitchSceneBtn" style="margin-bottom:0;">IPC Switch Scene</button>
-          </div>
-          <div class="stats-row aegis-actions">
-            <div class="stat" id="aegisActionMsg" style="min-width:220px;">Aegis action: idle</div>
-            <div class="stat" id="ipcStatusMsg" style="min-width:280px;">IPC: --</div>
-          </div>
-          <div class="toolbar-row">
-            <div class="toggle-row" style="margin-top:0;">
-              <input type="checkbox" id="hideInactive" /> <label for="hideInactive">Hide inactive outputs</label>
-            </div>
-            <div class="toolbar-links">
-              <span class="edit-btn" id="editNamesBtn" style="margin-left:0;">Edit Output Names</span>
-            </div>
-          </div>
-        </div>
-      </div>
-
-      <details class="panel-card details-shell" id="outputsDetails" open>
-        <summary>Outputs</summary>
-        <div class="details-content">
-          <div class="section-head">
-            <div class="section-title">Outputs</div>
-          </div>
-          <div class="grid" id="outputs"></div>
-        </div>
-      </details>
-    </div>
-  </div>
-  
-  <!-- Modal for editing output names -->
-  <div class="modal" id="nameModal">
-    <div class="modal-content">
-      <div class="modal-header">
-        <span class="modal-title">Edit Output Names</span>
-        <span class="close-btn" id="closeModal">&times;</span>
-      </div>
-      <div id="nameEditor"></div>
-      <button class="save-btn" id="saveNames">Save Changes</button>
-      <div id="saveMsg" style="margin-top:10px; font-size:13px;"></div>
-    </div>
-  </div>
-  
-  <script>
-    // Default pretty names for known outputs
-    const defaultNames = {
-      'adv_stream': 'Main Stream',
-      'adv_file_output': 'Recording',
-      'virtualcam_output': 'Virtual Camera'
-    };
-    
-    // Output name mappings - will be loaded dynamically
-    let outputNameMap = {};
-    
-    const params = new URLSearchParams(window.location.search);
-    const token = params.get('token');
-    const ws = new WebSocket(`ws://${window.location.host}/ws?token=${token}`);
-    
-    // Load output names from server
-    async function loadOutputNames() {
-      try {
-        const res = await fetch(`/output-names`, {
-          headers: {
-            "Authorization": "Bearer " + token
-          }
-        });
-        if (res.ok) {
-          outputNameMap = await res.json();
-        }
-      } catch (e) {
-        console.error('Failed to load output names:', e);
-      }
-    }
-    
-    // Load names on startup
-    loadOutputNames();
-
-    async function loadAegisStatus(refresh = false) {
-      try {
-        const url = refresh ? "/aegis/status?refresh=1" : "/aegis/status";
-        const res = await fetch(url, {
-          headers: {
-            "Authorization": "Bearer " + token
-          }
-        });
-        if (!res.ok) return;
-        const data = await res.json();
-        const session = data.session;
-        if (!data.enabled) {
-          aegisEl.textContent = "AEGIS: disabled";
-          aegisEl.style.borderColor = "var(--line)";
-          return;
-        }
-        if (!session) {
-          aegisEl.textContent = "AEGIS: none";
-          aegisEl.style.borderColor = "var(--line)";
-          return;
-        }
-        const region = session.region ? ` @ ${session.region}` : "";
-        aegisEl.textContent = `AEGIS: ${session.status}${region}`;
-        aegisEl.style.borderColor = session.status === "active" ? "var(--good)" : "var(--warn)";
-      } catch (e) {
-        aegisEl.textContent = "AEGIS: error";
-        aegisEl.style.borderColor = "var(--bad)";
-      }
-    }
-
-    async function aegisAction(path) {
-      try {
-        aegisActionMsg.textContent = `Aegis action: ${path === "/aegis/start" ? "starting..." : "stopping..."}`;
-        const res = await fetch(path, {
-          method: "POST",
-          headers: {
-            "Authorization": "Bearer " + token
-          }
-        });
-        const data = await res.json().catch(() => ({}));
-        if (!res.ok) {
-          aegisActionMsg.textContent = `Aegis action error: ${data.error || res.status}`;
-          return;
-        }
-        aegisActionMsg.textContent = `Aegis action: ${data.message || "ok"}`;
-        await loadAegisStatus(true);
-      } catch (e) {
-        aegisActionMsg.textContent = `Aegis action error: ${e.message}`;
-      }
-    }
-
-    async function loadIpcStatus() {
-      try {
-        const res = await fetch("/ipc/status", {
-          headers: {
-            "Authorization": "Bearer " + token
-          }
-        });
-        if (!res.ok) {
-          ipcStatusMsg.textContent = `IPC: status error (${res.status})`;
-          return;
-        }
-        const data = await res.json();
-        const conn = data.session_connected ? "connected" : "disconnected";
-        const pending = Number(data.pending_switch_count || 0);
-        let tail = "";
-        if (data.last_switch_result) {
-          const r = data.last_switch_result;
-          tail = ` | last=${r.status}${r.error ? ` (${r.error})` : ""}`;
-        } else if (data.last_switch_request) {
-          const r = data.last_switch_request;
-          tail = ` | queued=${r.scene_name}`;
-        }
-        ipcStatusMsg.textContent = `IPC: ${conn} | pending=${pending}${tail}`;
-      } catch (e) {
-        ipcStatusMsg.textContent = `IPC: status error (${e.message})`;
-      }
-    }
-
-    async function ipcSwitchScene() {
-      try {
-        const sceneName = (ipcSceneNameEl.value || "").trim();
-        const reason = (ipcSceneReasonEl.value || "").trim();
-        const allowEmpty = !!(ipcAllowEmptySceneEl && ipcAllowEmptySceneEl.checked);
-        if (!sceneName && !allowEmpty) {
-          aegisActionMsg.textContent = "Aegis action error: scene name required";
-          return;
-        }
-        const displayScene = sceneName || "<empty>";
-        aegisActionMsg.textContent = `Aegis action: queueing IPC switch '${displayScene}'...`;
-        const res = await fetch("/ipc/switch-scene", {
-          method: "POST",
-          headers: {
-            "Authorization": "Bearer " + token,
-            "Content-Type": "application/json"
-          },
-          body: JSON.stringify({
-            scene_name: sceneName,
-            reason: reason || "manual_debug",
-            deadline_ms: 550,
-            allow_empty: allowEmpty
-          })
-        });
-        const data = await res.json().catch(() => ({}));
-        if (!res.ok) {
-          aegisActionMsg.textContent = `Aegis action error: ${data.message || res.status}`;
-          return;
-        }
-        aegisActionMsg.textContent = `Aegis action: ${data.message || "IPC switch queued"}`;
-      } catch (e) {
-        aegisActionMsg.textContent = `Aegis action error: ${e.message}`;
-      }
-    }
-
-    const statusEl = document.getElementById("status");
-    const timeEl = document.getElementById("time");
-    const healthEl = document.getElementById("health");
-    const obsEl = document.getElementById("obs");
-    const testModeEl = document.getElementById("testmode");
-    const recBadgeEl = document.getElementById("recbadge");
-    const sysEl = document.getElementById("sys");
-    const netEl = document.getElementById("net");
-    const aegisEl = document.getElementById("aegis");
-    const statDisk = document.getElementById("statDisk");
-    const statRender = document.getElementById("statRender");
-    const statOutput = document.getElementById("statOutput");
-    const statFps = document.getElementById("statFps");
-    const hideInactiveEl = document.getElementById("hideInactive");
-    const summaryConnEl = document.getElementById("summaryConn");
-    const summarySystemEl = document.getElementById("summarySystem");
-    const summaryMainEl = document.getElementById("summaryMain");
-    const outputsEl = document.getElementById("outputs");
-    const canvas = document.getElementById("graph");
-    const ctx = canvas.getContext("2d");
-    const values = [];
-    const maxPoints = 120;
-
-    function healthColor(v) {
-      if (v >= 0.95) return "var(--good)";
-      if (v >= 0.90) return "var(--warn)";
-      return "var(--bad)";
-    }
-
-    function draw() {
-      ctx.clearRect(0, 0, canvas.width, canvas.height);
-      
-      // Draw grid lines
-      ctx.strokeStyle = "#1f2a3a";
-      ctx.lineWidth = 1;
-      ctx.beginPath();
-      // 0.5 line (50%)
-      ctx.moveTo(30, canvas.height / 2);
-      ctx.lineTo(canvas.width, canvas.height / 2);
-      // 0.0 line (0%)
-      ctx.moveTo(30, canvas.height - 1);
-      ctx.lineTo(canvas.width, canvas.height - 1);
-      // 1.0 line (100%)
-      ctx.moveTo(30, 1);
-      ctx.lineTo(canvas.width, 1);
-      ctx.stroke();
-      
-      // Draw labels
-      ctx.fillStyle = "#8da3c1";
-      ctx.font = "10px Arial";
-      ctx.textAlign = "right";
-      ctx.textBaseline = "middle";
-      ctx.fillText("100%", 25, 6);
-      ctx.fillText("50%", 25, canvas.height / 2);
-      ctx.fillText("0%", 25, canvas.height - 6);
-      
-      // Draw graph
-      ctx.strokeStyle = "#33d17a";
-      ctx.lineWidth = 2;
-      ctx.beginPath();
-      
-      const graphWidth = canvas.width - 30;
-      values.forEach((v, i) => {
-        const x = 30 + (i / Math.max(1, maxPoints - 1)) * graphWidth;
-        const y = canvas.height - (v * canvas.height);
-        // Clamp y to canvas bounds
-        const clampedY = Math.max(0, Math.min(canvas.height, y));
-        
-        if (i === 0) ctx.moveTo(x, clampedY); else ctx.lineTo(x, clampedY);
-      });
-      ctx.stroke();
-    }
-
-    function renderOutputs(outputs) {
-      outputsEl.innerHTML = "";
-      const hideInactive = hideInactiveEl.checked;
-      outputs.forEach(o => {
-        const isActive = o.bitrate_kbps > 0 || o.fps > 0;
-
-        if (hideInactive && !isActive) return;
-
-        let displayName = outputNameMap[o.name] || defaultNames[o.name] || o.name;
-        if (!isActive) displayName += " (Inactive)";
-
-        const box = document.createElement("div");
-        box.className = isActive ? "output" : "output-inactive";
-        box.dataset.outputId = o.name;
-
-        const name = document.createElement("div");
-        name.className = "name";
-        name.textContent = `${displayName} | ${o.bitrate_kbps} kbps | ${o.fps.toFixed(0)} fps | ${(o.drop_pct*100).toFixed(2)}% drop | ${o.encoding_lag_ms.toFixed(1)} ms lag`;
-
-        const bar = document.createElement("div");
-        bar.className = "bar";
-        const fill = document.createElement("div");
-        fill.className = "fill";
-        const health = 1 - o.drop_pct;
-        fill.style.width = `${Math.max(0, Math.min(100, health*100))}%`;
-        fill.style.background = healthColor(health);
-        bar.appendChild(fill);
-        box.appendChild(name);
-        box.appendChild(bar);
-        outputsEl.appendChild(box);
-      });
-    }
-
-    function pickMainOutput(outputs) {
-      if (!outputs || outputs.length === 0) return null;
-      return outputs.find(o => o.name === "adv_stream")
-        || outputs.find(o => o.bitrate_kbps > 0 || o.fps > 0)
-        || outputs[0];
-    }
-
-    function updateSummaryPanels(data) {
-      const aegisText = (aegisEl.textContent || "AEGIS: --").replace(/^AEGIS:\s*/, "");
-      const obsConn = data.obs.connected ? "Connected" : "Disconnected";
-      const obsMode = data.obs.streaming ? "Streaming" : "Idle";
-      summaryConnEl.innerHTML = `OBS: ${obsConn} (${obsMode})<br>Latency: ${data.network.latency_ms.toFixed(0)} ms<br>Aegis: ${aegisText}`;
-
-      const gpuPctText = data.system.gpu_percent != null ? `${data.system.gpu_percent.toFixed(0)}%` : "n/a";
-      const gpuTempText = data.system.gpu_temp_c != null ? ` ${data.system.gpu_temp_c.toFixed(0)}C` : "";
-      summarySystemEl.innerHTML = `CPU: ${data.system.cpu_percent.toFixed(0)}%<br>RAM: ${data.system.mem_percent.toFixed(0)}%<br>GPU/VRAM: ${gpuPctText}${gpuTempText} / n/a`;
-
-      const main = pickMainOutput(data.outputs);
-      if (!main) {
-        summaryMainEl.innerHTML = "Bitrate: --<br>Drops: --<br>Lag/FPS: --";
-        return;
-      }
-      summaryMainEl.innerHTML =
-        `Bitrate: ${main.bitrate_kbps} kbps (${main.name})<br>` +
-        `Drops: ${(main.drop_pct * 100).toFixed(2)}%<br>` +
-        `Lag/FPS: ${main.encoding_lag_ms.toFixed(1)} ms / ${main.fps.toFixed(1)} fps`;
-    }
-
-    ws.onopen = () => { statusEl.textContent = "CONNECTED"; };
-    ws.onclose = () => { statusEl.textContent = "DISCONNECTED"; };
-    ws.onmessage = (event) => {
-      const data = JSON.parse(event.data);
-      timeEl.textContent = new Date(data.ts * 1000).toLocaleTimeString();
-      healthEl.textContent = `Health: ${(data.health*100).toFixed(1)}%`;
-      healthEl.style.borderColor = healthColor(data.health);
-      obsEl.textContent = `OBS: ${data.obs.streaming ? "LIVE" : "IDLE"} | dropped ${data.obs.total_dropped_frames}`;
-
-      // Studio mode badge
-      testModeEl.style.display = data.obs.studio_mode ? "block" : "none";
-
-      // Recording badge
-      recBadgeEl.style.display = data.obs.recording ? "block" : "none";
-
-      // System: include GPU temp if available
-      const gpuPct = data.system.gpu_percent ?? 0;
-      const gpuTemp = data.system.gpu_temp_c != null ? ` ${data.system.gpu_temp_c.toFixed(0)}C` : "";
-      sysEl.textContent = `SYS: CPU ${data.system.cpu_percent.toFixed(0)}% | MEM ${data.system.mem_percent.toFixed(0)}% | GPU ${gpuPct}%${gpuTemp}`;
-
-      // Network: show both upload and download
-      netEl.textContent = `NET: UP ${data.network.upload_mbps.toFixed(1)} | DN ${data.network.download_mbps.toFixed(1)} Mb/s | LAT ${data.network.latency_ms.toFixed(0)} ms`;
-
-      // OBS Stats row
-      const diskGb = (data.obs.available_disk_space_mb / 1024).toFixed(1);
-      statDisk.textContent = `Disk: ${diskGb} GB`;
-      statRender.textContent = `Render missed: ${data.obs.render_missed_frames} / ${data.obs.render_total_frames}`;
-      statOutput.textContent = `Encoder skipped: ${data.obs.output_skipped_frames} / ${data.obs.output_total_frames}`;
-      statFps.textContent = `FPS: ${data.obs.active_fps.toFixed(1)}`;
-      updateSummaryPanels(data);
-
-      values.push(data.health);
-      if (values.length > maxPoints) values.shift();
-      draw();
-      renderOutputs(data.outputs);
-    };
-    
-    // Modal functionality for editing output names
-    const modal = document.getElementById("nameModal");
-    const editBtn = document.getElementById("editNamesBtn");
-    const closeBtn = document.getElementById("closeModal");
-    const nameEditor = document.getElementById("nameEditor");
-    const saveBtn = document.getElementById("saveNames");
-    const saveMsg = document.getElementById("saveMsg");
-    const refreshAegisBtn = document.getElementById("refreshAegisBtn");
-    const aegisStartBtn = document.getElementById("aegisStartBtn");
-    const aegisStopBtn = document.getElementById("aegisStopBtn");
-    const ipcSceneNameEl = document.getElementById("ipcSceneName");
-    const ipcSceneReasonEl = document.getElementById("ipcSceneReason");
-    const ipcAllowEmptySceneEl = document.getElementById("ipcAllowEmptyScene");
-    const ipcSwitchSceneBtn = document.getElementById("ipcSwitchSceneBtn");
-    const aegisActionMsg = document.getElementById("aegisActionMsg");
-    const ipcStatusMsg = document.getElementById("ipcStatusMsg");
-
-    loadAegisStatus();
-    loadIpcStatus();
-    setInterval(() => loadAegisStatus(false), 10000);
-    setInterval(() => loadIpcStatus(), 2000);
-    refreshAegisBtn.onclick = () => loadAegisStatus(true);
-    aegisStartBtn.onclick = () => aegisAction("/aegis/start");
-    aegisStopBtn.onclick = () => aegisAction("/aegis/stop");
-    ipcSwitchSceneBtn.onclick = () => ipcSwitchScene();
-    
-    editBtn.onclick = () => {
-      modal.style.display = "block";
-      populateNameEditor();
-    };
-    
-    closeBtn.onclick = () => {
-      modal.style.display = "none";
-    };
-    
-    window.onclick = (e) => {
-      if (e.target === modal) modal.style.display = "none";
-    };
-    
-    function populateNameEditor() {
-      nameEditor.innerHTML = "";
-      
-      // Add currently visible outputs
-      const currentOutputs = Array.from(document.querySelectorAll(".output, .output-inactive"));
-      const seenIds = new Set();
-      
-      currentOutputs.forEach(box => {
-        // Use the real ID stored in dataset
-        const id = box.dataset.outputId;
-        
-        if (id && !seenIds.has(id) && !defaultNames[id]) {
-          seenIds.add(id);
-          const currentName = outputNameMap[id] || id;
-          addNameRow(id, currentName);
-        }
-      });
-      
-      if (seenIds.size === 0) {
-        nameEditor.innerHTML = "<div class=\"muted\">No custom outputs detected yet. Start streaming to see outputs.</div>";
-      }
-    }
-    
-    function addNameRow(id, name) {
-      const row = document.createElement("div");
-      row.className = "name-row";
-      row.innerHTML = `
-        <span class="id-label">${id}</span>
-        <input type="text" data-id="${id}" value="${name}" placeholder="Display name">
-      `;
-      nameEditor.appendChild(row);
-    }
-    
-    saveBtn.onclick = async () => {
-      const inputs = nameEditor.querySelectorAll("input");
-      const mappings = {};
-      
-      inputs.forEach(input => {
-        const id = input.getAttribute("data-id");
-        const name = input.value.trim();
-        if (name && name !== id) {
-          mappings[id] = name;
-        }
-      });
-      
-      try {
-        const res = await fetch("/output-names", {
-          method: "POST",
-          headers: {
-            "Content-Type": "application/json",
-            "Authorization": "Bearer " + token
-          },
-          body: JSON.stringify(mappings)
-        });
-        
-        if (res.ok) {
-          saveMsg.textContent = "Saved! Refresh the page to see changes.";
-          saveMsg.style.color = "var(--good)";
-          setTimeout(() => {
-            modal.style.display = "none";
-            location.reload();
-          }, 1500);
-        } else {
-          saveMsg.textContent = "Failed to save.";
-          saveMsg.style.color = "var(--bad)";
-        }
-      } catch (err) {
-        saveMsg.textContent = "Error: " + err.message;
-        saveMsg.style.color = "var(--bad)";
-      }
-    };
-  </script>
-</body>
-</html>"##;
 
-    let html = html
-        .replace("{{THEME_VARS}}", &css)
-        .replace("{{TOKEN}}", &html_escape(&state.token));
-    Html(html).into_response()
+    let css = state.config.read(|c| theme_css(&c.theme));
+    let (dir, lang) = state.config.read(|c| {
+        (
+            pages::templates_dir(&c.dashboard),
+            c.server.language.clone(),
+        )
+    });
+    let bundle = Bundle::load(&lang);
+    let html = pages::render(
+        &pages::localized(&dir, Page::Dock, &bundle),
+        &[
+            ("THEME_VARS", &css),
+            ("LANG", &lang),
+            ("I18N", &bundle.script_json()),
+        ],
+    );
+    caching::with_etag(&headers, caching::PAGE, caching::HTML, html)
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct SettingsForm {
     obs_host: String,
     obs_port: u16,
@@ -777,6 +376,23 @@ struct SettingsForm {
     grafana_endpoint: Option<String>,
     grafana_instance_id: Option<String>,
     grafana_api_token: Option<String>,
+    /// Visible dashboard panels in order, comma-separated. The dashboard fields
+    /// are only applied when this is sent, so callers that omit them keep the layout.
+    dashboard_panels: Option<String>,
+    dashboard_diagnostics: Option<String>,
+    dashboard_hide_inactive: Option<String>,
+    /// Page language; see `server.language`.
+    language: Option<String>,
+    /// Theme fields, applied only when `theme_bg` is sent.
+    theme_font_family: Option<String>,
+    theme_bg: Option<String>,
+    theme_panel: Option<String>,
+    theme_text: Option<String>,
+    theme_muted: Option<String>,
+    theme_good: Option<String>,
+    theme_warn: Option<String>,
+    theme_bad: Option<String>,
+    theme_line: Option<String>,
 }
 
 async fn settings_page(
@@ -784,224 +400,137 @@ async fn settings_page(
     headers: HeaderMap,
     query: Query<HashMap<String, String>>,
 ) -> impl IntoResponse {
-    if !is_token_valid(&headers, &query.0, &state.token, QueryTokenPolicy::Allow) {
-        return StatusCode::UNAUTHORIZED.into_response();
+    if !has_credentials(&headers, &query.0) {
+        return axum::response::Redirect::to("/login?next=/settings").into_response();
+    }
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Allow,
+        Scope::SettingsWrite,
+    ) {
+        return ApiError::unauthorized().into_response();
     }
 
-    let config = match Config::load() {
-        Ok(c) => c,
-        Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to load config: {}", e),
-            )
-                .into_response()
-        }
+    let config = state.config.get();
+    let css = state.config.read(|c| theme_css(&c.theme));
+    let bundle = Bundle::load(&config.server.language);
+    let status = |ok: bool, key: &str| {
+        let class = if ok { "status-ok" } else { "status-off" };
+        format!(r#"<div class="status {class}">{}</div>"#, bundle.get(key))
     };
-    let css = theme_css(&state.theme);
 
-    let grafana_configured = *state.grafana_configured.lock().unwrap();
+    let grafana_configured = state.grafana_configured.load(Ordering::Relaxed);
     let grafana_status = if grafana_configured {
-        r#"<div class="status status-ok">Grafana Cloud: Connected</div>"#
+        status(true, "settings.grafana_connected")
     } else {
-        r#"<div class="status status-off">Grafana Cloud: Not Configured</div>"#
+        status(false, "settings.grafana_not_configured")
     };
 
     let grafana_endpoint = config.grafana.endpoint.as_deref().unwrap_or("");
 
-    let html = format!(
-        r#"<!doctype html>
-<html>
-<head>
-  <meta charset="utf-8" />
-  <title>Telemy - Settings</title>
-  <style>
-    :root {{ {css} }}
-    body {{ margin:0; font-family:var(--font); background:var(--bg); color:#e6f0ff; }}
-    .wrap {{ max-width:480px; margin:40px auto; padding:0 16px; }}
-    h1 {{ font-size:20px; margin-bottom:20px; }}
-    h2 {{ font-size:16px; margin-top:28px; margin-bottom:8px; border-top:1px solid var(--line); padding-top:18px; }}
-    label {{ display:block; font-size:13px; color:var(--muted); margin-bottom:4px; margin-top:14px; }}
-    input {{ width:100%; box-sizing:border-box; padding:8px 10px; background:var(--panel);
-             border:1px solid var(--line); border-radius:4px; color:#e6f0ff; font-size:14px;
-             font-family:var(--font); }}
-    input:focus {{ outline:none; border-color:var(--good); }}
-    button {{ margin-top:20px; padding:10px 20px; background:var(--good); color:#0b0e12;
-              border:none; border-radius:4px; font-size:14px; font-weight:bold; cursor:pointer; }}
-    button:hover {{ opacity:0.9; }}
-    .msg {{ margin-top:14px; padding:8px 12px; border-radius:6px; font-size:13px; display:none; }}
-    .msg-ok {{ background:#1a2e1a; border:1px solid var(--good); color:var(--good); display:block; }}
-    .msg-err {{ background:#2e1a1a; border:1px solid var(--bad); color:var(--bad); display:block; }}
-    .back {{ font-size:12px; color:var(--muted); text-decoration:none; margin-bottom:20px; display:inline-block; }}
-    .back:hover {{ color:#e6f0ff; }}
-    .help {{ color:var(--muted); font-size:11px; margin-top:2px; }}
-    .status {{ padding:8px 12px; border-radius:6px; margin-bottom:12px; font-size:13px; }}
-    .status-ok {{ background:#1a2e1a; border:1px solid var(--good); color:var(--good); }}
-    .status-off {{ background:#2e1a1a; border:1px solid var(--bad); color:var(--bad); }}
-    .note {{ color:var(--muted); font-size:12px; margin-top:8px; }}
-  </style>
-</head>
-<body>
-  <div class="wrap">
-    <a href="/obs?token={token}" class="back">&larr; Back to Dashboard</a>
-    <h1>Settings</h1>
-    <div id="msg" class="msg"></div>
-    <form id="settingsForm">
-
-      <h2>OBS Connection</h2>
-      <label for="obs_host">OBS Host</label>
-      <input id="obs_host" name="obs_host" type="text" value="{obs_host}" required />
-
-      <label for="obs_port">OBS WebSocket Port</label>
-      <input id="obs_port" name="obs_port" type="number" value="{obs_port}" required />
-
-      <label for="obs_password">OBS WebSocket Password</label>
-      <input id="obs_password" name="obs_password" type="password" placeholder="Leave blank to keep current" />
-      <div class="help">Only fill in to change the stored password</div>
-
-      <h2>Grafana Cloud</h2>
-      {grafana_status}
-
-      <label for="grafana_endpoint">OTLP Endpoint</label>
-      <input id="grafana_endpoint" name="grafana_endpoint" type="url" value="{grafana_endpoint}"
-             placeholder="https://otlp-gateway-prod-us-east-0.grafana.net/otlp" />
-      <div class="help">Found in Grafana Cloud &rarr; OpenTelemetry &rarr; Configure</div>
-
-      <label for="grafana_instance_id">Instance ID</label>
-      <input id="grafana_instance_id" name="grafana_instance_id" type="text"
-             placeholder="123456" />
-      <div class="help">Your Grafana Cloud stack instance number</div>
-
-      <label for="grafana_api_token">API Token</label>
-      <input id="grafana_api_token" name="grafana_api_token" type="password"
-             placeholder="glc_eyJ..." />
-      <div class="help">Generate under Security &rarr; API Keys with MetricsPublisher role</div>
-
-      <label for="grafana_interval">Push Interval (ms)</label>
-      <input id="grafana_interval" name="grafana_interval" type="number" value="{grafana_interval}" required />
-
-      <div class="note">Restart Telemy after saving for connection changes to take effect.</div>
-
-      <button type="submit">Save Changes</button>
-    </form>
+    let aegis_signed_in = {
+        let vault = state.vault.read().await;
+        config
+            .aegis
+            .access_jwt_key
+            .as_deref()
+            .is_some_and(|key| vault.list_keys().iter().any(|k| k == key))
+    };
+    let aegis_status = if aegis_signed_in {
+        status(true, "settings.aegis_status_signed_in")
+    } else {
+        status(false, "settings.aegis_status_signed_out")
+    };
+    let aegis_base_url = config.aegis.base_url.as_deref().unwrap_or("");
+
+    // Shown panels in their configured order, then the hidden ones.
+    let dashboard = &config.dashboard;
+    let hidden = DashboardPanel::ALL
+        .into_iter()
+        .filter(|p| !dashboard.panels.contains(p));
+    let dashboard_panels =
+        serde_json::Value::from_iter(dashboard.panels.iter().copied().chain(hidden).map(|p| {
+            serde_json::json!({
+                "name": p.as_str(),
+                "label": bundle.get(&format!("panel.{}", p.as_str())),
+                "shown": dashboard.panels.contains(&p),
+            })
+        }));
+    let checked = |on: bool| if on { "checked" } else { "" };
+    let language_options: String = LANGUAGES
+        .into_iter()
+        .map(|lang| {
+            let selected = if lang == config.server.language {
+                " selected"
+            } else {
+                ""
+            };
+            let name = Bundle::load(lang).get("language.name").to_string();
+            format!(r#"<option value="{lang}"{selected}>{name}</option>"#)
+        })
+        .collect();
+    let theme_presets: HashMap<_, _> = ThemeConfig::presets().into_iter().collect();
 
-    <h2>Grafana Dashboard</h2>
-    <div class="note" style="margin-bottom:12px;">Import a pre-built Telemy dashboard into Grafana to visualize your metrics.</div>
-    <a href="/grafana-dashboard?token={token}" download="telemy-dashboard.json"
-       style="display:inline-block; padding:8px 16px; background:var(--panel); border:1px solid var(--line);
-              border-radius:4px; color:#e6f0ff; text-decoration:none; font-size:13px; cursor:pointer;">
-      Download Dashboard JSON
-    </a>
-    <div class="help" style="margin-top:6px;">Import this file in Grafana &rarr; Dashboards &rarr; Import</div>
-
-    <details style="margin-top:16px;">
-      <summary style="cursor:pointer; color:var(--muted); font-size:13px;">Auto-import via Grafana API (optional)</summary>
-      <div style="margin-top:10px;">
-        <label for="grafana_url">Grafana URL</label>
-        <input id="grafana_url" type="url" placeholder="https://yourstack.grafana.net" />
-        <div class="help">Your Grafana instance URL (not the OTLP endpoint)</div>
-
-        <label for="grafana_org_key">Service Account Token</label>
-        <input id="grafana_org_key" type="password" placeholder="glsa_..." />
-        <div class="help">Needs Dashboard Editor permissions. Create under Administration &rarr; Service Accounts.</div>
-
-        <button type="button" id="importBtn"
-                style="margin-top:12px; padding:8px 16px; background:var(--panel); border:1px solid var(--good);
-                       color:var(--good); border-radius:4px; font-size:13px; cursor:pointer;">
-          Import Dashboard
-        </button>
-        <div id="importMsg" class="msg" style="margin-top:8px;"></div>
-      </div>
-    </details>
-  </div>
-  <script>
-    const params = new URLSearchParams(window.location.search);
-    const token = params.get("token");
-
-    document.getElementById("settingsForm").addEventListener("submit", async (e) => {{
-      e.preventDefault();
-      const msg = document.getElementById("msg");
-      const data = new URLSearchParams(new FormData(e.target));
-      try {{
-        const res = await fetch("/settings", {{
-          method: "POST",
-          headers: {{
-            "Content-Type": "application/x-www-form-urlencoded",
-            "Authorization": "Bearer " + token
-          }},
-          body: data,
-        }});
-        const text = await res.text();
-        msg.textContent = text;
-        msg.className = res.ok ? "msg msg-ok" : "msg msg-err";
-      }} catch (err) {{
-        msg.textContent = "Request failed: " + err.message;
-        msg.className = "msg msg-err";
-      }}
-    }});
-
-    document.getElementById("importBtn").addEventListener("click", async () => {{
-      const importMsg = document.getElementById("importMsg");
-      const grafanaUrl = document.getElementById("grafana_url").value.trim();
-      const grafanaKey = document.getElementById("grafana_org_key").value.trim();
-      if (!grafanaUrl || !grafanaKey) {{
-        importMsg.textContent = "Both Grafana URL and API key are required.";
-        importMsg.className = "msg msg-err";
-        return;
-      }}
-      const data = new URLSearchParams({{ grafana_url: grafanaUrl, grafana_api_key: grafanaKey }});
-      try {{
-        const res = await fetch("/grafana-dashboard/import?token=" + token, {{
-          method: "POST",
-          headers: {{
-            "Content-Type": "application/x-www-form-urlencoded",
-            "Authorization": "Bearer " + token
-          }},
-          body: data,
-        }});
-        const text = await res.text();
-        importMsg.textContent = text;
-        importMsg.className = res.ok ? "msg msg-ok" : "msg msg-err";
-      }} catch (err) {{
-        importMsg.textContent = "Request failed: " + err.message;
-        importMsg.className = "msg msg-err";
-      }}
-    }});
-  </script>
-</body>
-</html>"#,
-        css = css,
-        token = html_escape(&state.token),
-        obs_host = html_escape(&config.obs.host),
-        obs_port = config.obs.port,
-        grafana_status = grafana_status,
-        grafana_endpoint = html_escape(grafana_endpoint),
-        grafana_interval = config.grafana.push_interval_ms
+    let token = html_escape(
+        presented_token(&headers, &query.0, QueryTokenPolicy::Allow).unwrap_or_default(),
+    );
+    let html = pages::render(
+        &pages::localized(&pages::templates_dir(dashboard), Page::Settings, &bundle),
+        &[
+            ("THEME_VARS", &css),
+            ("TOKEN", &token),
+            ("LANG", &config.server.language),
+            ("I18N", &bundle.script_json()),
+            ("LANGUAGE_OPTIONS", &language_options),
+            ("OBS_HOST", &html_escape(&config.obs.host)),
+            ("OBS_PORT", &config.obs.port.to_string()),
+            ("GRAFANA_STATUS", &grafana_status),
+            ("GRAFANA_ENDPOINT", &html_escape(grafana_endpoint)),
+            (
+                "GRAFANA_INTERVAL",
+                &config.grafana.push_interval_ms.to_string(),
+            ),
+            ("AEGIS_STATUS", &aegis_status),
+            ("AEGIS_BASE_URL", &html_escape(aegis_base_url)),
+            ("DASHBOARD_PANELS", &pages::script_json(&dashboard_panels)),
+            ("THEME", &pages::script_json(&config.theme)),
+            ("THEME_PRESETS", &pages::script_json(&theme_presets)),
+            ("DIAGNOSTICS_CHECKED", checked(dashboard.diagnostics)),
+            (
+                "HIDE_INACTIVE_CHECKED",
+                checked(dashboard.hide_inactive_outputs),
+            ),
+        ],
     );
 
-    Html(html).into_response()
+    caching::with_etag(&headers, caching::PAGE, caching::HTML, html)
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/settings",
+    tag = "settings",
+    request_body(content = SettingsForm, content_type = "application/x-www-form-urlencoded"),
+    responses((status = 200, description = "Saved", body = String, content_type = "text/plain")),
+    security(("bearer" = ["settings_write"]))
+)]
 async fn settings_submit(
     State(state): State<Arc<ServerState>>,
     headers: HeaderMap,
     query: Query<HashMap<String, String>>,
     Form(form): Form<SettingsForm>,
 ) -> impl IntoResponse {
-    if !is_token_valid(&headers, &query.0, &state.token, QueryTokenPolicy::Deny) {
-        return (StatusCode::UNAUTHORIZED, "Unauthorized".to_string()).into_response();
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::SettingsWrite,
+    ) {
+        return ApiError::unauthorized().into_response();
     }
 
-    let mut config = match Config::load() {
-        Ok(c) => c,
-        Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to load config: {}", e),
-            )
-                .into_response()
-        }
-    };
+    let mut config = state.config.get();
 
     // OBS settings
     config.obs.host = form.obs_host;
@@ -1010,12 +539,9 @@ async fn settings_submit(
     // OBS password — only update if user provided a new one
     if let Some(ref pw) = form.obs_password {
         if !pw.is_empty() {
-            let mut vault = state.vault.lock().unwrap();
+            let mut vault = state.vault.write().await;
             if let Err(e) = vault.store("obs_password", pw) {
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Failed to store OBS password: {}", e),
-                )
+                return ApiError::internal(format!("Failed to store OBS password: {}", e))
                     .into_response();
             }
             config.obs.password_key = Some("obs_password".to_string());
@@ -1046,17 +572,12 @@ async fn settings_submit(
         .to_string();
 
     if !endpoint.is_empty() && !instance_id.is_empty() && !api_token.is_empty() {
-        let credentials = format!("{}:{}", instance_id, api_token);
-        let encoded = general_purpose::STANDARD.encode(credentials.as_bytes());
-        let auth_value = format!("Basic {}", encoded);
+        let auth_value = grafana::basic_auth(&instance_id, &api_token);
 
         {
-            let mut vault = state.vault.lock().unwrap();
+            let mut vault = state.vault.write().await;
             if let Err(e) = vault.store("grafana_auth", &auth_value) {
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Failed to store Grafana credentials: {}", e),
-                )
+                return ApiError::internal(format!("Failed to store Grafana credentials: {}", e))
                     .into_response();
             }
         }
@@ -1064,26 +585,193 @@ async fn settings_submit(
         config.grafana.enabled = true;
         config.grafana.endpoint = Some(endpoint);
         config.grafana.auth_value_key = Some("grafana_auth".to_string());
-        *state.grafana_configured.lock().unwrap() = true;
+        state.grafana_configured.store(true, Ordering::Relaxed);
     } else if !endpoint.is_empty() {
         // Allow updating just the endpoint without re-entering credentials
         config.grafana.endpoint = Some(endpoint);
     }
 
-    match config.save() {
+    // Dashboard layout, applied on the next page load
+    if let Some(panels) = &form.dashboard_panels {
+        let panels: Result<Vec<DashboardPanel>, String> = panels
+            .split(',')
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(str::parse)
+            .collect();
+        match panels {
+            Ok(panels) => config.dashboard.panels = panels,
+            Err(err) => return ApiError::bad_request(err).into_response(),
+        }
+        config.dashboard.diagnostics = form.dashboard_diagnostics.is_some();
+        config.dashboard.hide_inactive_outputs = form.dashboard_hide_inactive.is_some();
+    }
+
+    if let Some(language) = form.language {
+        config.server.language = language;
+    }
+
+    // Theme, applied on the next page load
+    if form.theme_bg.is_some() {
+        let theme = &mut config.theme;
+        for (field, value) in [
+            (&mut theme.font_family, form.theme_font_family),
+            (&mut theme.bg, form.theme_bg),
+            (&mut theme.panel, form.theme_panel),
+            (&mut theme.text, form.theme_text),
+            (&mut theme.muted, form.theme_muted),
+            (&mut theme.good, form.theme_good),
+            (&mut theme.warn, form.theme_warn),
+            (&mut theme.bad, form.theme_bad),
+            (&mut theme.line, form.theme_line),
+        ] {
+            if let Some(value) = value {
+                *field = value.trim().to_string();
+            }
+        }
+    }
+    if let Err(err) = config.validate() {
+        return ApiError::bad_request(err.to_string()).into_response();
+    }
+
+    match state.config.save(config) {
         Ok(_) => (
             StatusCode::OK,
             "Settings saved. Restart required for connection changes to take effect.".to_string(),
         )
             .into_response(),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to save config: {}", e),
+        Err(e) => ApiError::internal(format!("Failed to save config: {}", e)).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct LoginForm {
+    token: String,
+    next: Option<String>,
+}
+
+async fn login_page(
+    State(state): State<Arc<ServerState>>,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    let next = query.0.get("next").map(String::as_str).unwrap_or("/obs");
+    Html(
+        state
+            .config
+            .read(|c| login_html(&c.theme, safe_next(next), None)),
+    )
+}
+
+// Exchanges a token for an HttpOnly session cookie so browser pages don't need
+// `?token=` in the URL, where it ends up in history and OBS browser-source logs.
+async fn login_submit(
+    State(state): State<Arc<ServerState>>,
+    Form(form): Form<LoginForm>,
+) -> Response {
+    let next = safe_next(form.next.as_deref().unwrap_or("/obs"));
+    let token = form.token.trim();
+    if !state.tokens.authorize(token, Scope::TelemetryRead) {
+        return (
+            StatusCode::UNAUTHORIZED,
+            Html(
+                state
+                    .config
+                    .read(|c| login_html(&c.theme, next, Some("Invalid token."))),
+            ),
         )
-            .into_response(),
+            .into_response();
+    }
+
+    let session = state.sessions.create(token);
+    (
+        StatusCode::SEE_OTHER,
+        [
+            (header::LOCATION, next.to_string()),
+            (
+                header::SET_COOKIE,
+                format!(
+                    "{SESSION_COOKIE}={session}; HttpOnly; SameSite=Strict; Path=/; Max-Age={SESSION_TTL_SECS}"
+                ),
+            ),
+        ],
+    )
+        .into_response()
+}
+
+async fn logout(State(state): State<Arc<ServerState>>, headers: HeaderMap) -> Response {
+    if let Some(id) = session_id(&headers) {
+        state.sessions.remove(id);
+    }
+    (
+        StatusCode::SEE_OTHER,
+        [
+            (header::LOCATION, "/login".to_string()),
+            (
+                header::SET_COOKIE,
+                format!("{SESSION_COOKIE}=; HttpOnly; SameSite=Strict; Path=/; Max-Age=0"),
+            ),
+        ],
+    )
+        .into_response()
+}
+
+/// Only same-origin paths are accepted as a post-login redirect target.
+fn safe_next(next: &str) -> &str {
+    if next.starts_with('/') && !next.starts_with("//") && !next.contains('\\') {
+        next
+    } else {
+        "/obs"
     }
 }
 
+fn login_html(theme: &ThemeConfig, next: &str, error: Option<&str>) -> String {
+    let css = theme_css(theme);
+    let error = error
+        .map(|e| format!(r#"<div class="msg">{}</div>"#, html_escape(e)))
+        .unwrap_or_default();
+    format!(
+        r#"<!doctype html>
+<html>
+<head>
+  <meta charset="utf-8" />
+  <title>Telemy - Login</title>
+  <style>
+    :root {{ {css} }}
+    body {{ margin:0; font-family:var(--font); background:var(--bg); color:var(--text); }}
+    .wrap {{ max-width:360px; margin:80px auto; padding:0 16px; }}
+    h1 {{ font-size:20px; margin-bottom:20px; }}
+    label {{ display:block; font-size:13px; color:var(--muted); margin-bottom:4px; }}
+    input {{ width:100%; box-sizing:border-box; padding:8px 10px; background:var(--panel);
+             border:1px solid var(--line); border-radius:4px; color:var(--text); font-size:14px;
+             font-family:var(--font); }}
+    input:focus {{ outline:none; border-color:var(--good); }}
+    button {{ margin-top:20px; padding:10px 20px; background:var(--good); color:#0b0e12;
+              border:none; border-radius:4px; font-size:14px; font-weight:bold; cursor:pointer; }}
+    .msg {{ margin-bottom:14px; padding:8px 12px; border-radius:6px; font-size:13px;
+            background:#2e1a1a; border:1px solid var(--bad); color:var(--bad); }}
+    .help {{ color:var(--muted); font-size:11px; margin-top:6px; }}
+  </style>
+</head>
+<body>
+  <div class="wrap">
+    <h1>Telemy</h1>
+    {error}
+    <form method="post" action="/login">
+      <input type="hidden" name="next" value="{next}" />
+      <label for="token">Access Token</label>
+      <input id="token" name="token" type="password" autocomplete="current-password" required autofocus />
+      <div class="help">The server token (vault key <code>server_token</code>) or a token issued via <code>/api/v1/tokens</code>.</div>
+      <button type="submit">Log in</button>
+    </form>
+  </div>
+</body>
+</html>"#,
+        css = css,
+        error = error,
+        next = html_escape(next),
+    )
+}
+
 async fn setup_page(query: Query<HashMap<String, String>>) -> impl IntoResponse {
     // Redirect /setup to /settings (Grafana config is now in settings)
     let token = query.0.get("token").cloned().unwrap_or_default();
@@ -1091,45 +779,104 @@ async fn setup_page(query: Query<HashMap<String, String>>) -> impl IntoResponse
         .into_response()
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/ws",
+    tag = "telemetry",
+    params(("client" = Option<String>, Query, description = "`dashboard`, `overlay`, ... for `/api/v1/clients`"), ("token" = Option<String>, Query, description = "Token for browsers that cannot set headers"), ("format" = Option<String>, Query, description = "`msgpack` for binary MessagePack messages; the `telemy.msgpack` subprotocol does the same")),
+    responses((status = 101, description = "WebSocket upgrade; frames and events until the client sends `{\"type\": \"subscribe\", \"channels\": [...], \"max_rate_hz\": n}` choosing among frames, events, ipc_status and aegis")),
+    security(("bearer" = ["telemetry_read"]))
+)]
 async fn ws_handler(
     State(state): State<Arc<ServerState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
     ws: WebSocketUpgrade,
     headers: HeaderMap,
     query: Query<HashMap<String, String>>,
 ) -> impl IntoResponse {
     // Native browser WebSocket clients cannot set Authorization headers directly.
     // Keep query-token fallback here for local dashboard compatibility.
-    if !is_token_valid(&headers, &query.0, &state.token, QueryTokenPolicy::Allow) {
-        return StatusCode::UNAUTHORIZED.into_response();
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Allow,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
     }
 
-    let rx = state.rx.clone();
-    ws.on_upgrade(move |socket| handle_socket(socket, rx))
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    let kind = ClientKind::detect(
+        query.get("client").map(String::as_str),
+        user_agent.as_deref(),
+    );
+    let format = WsFormat::detect(
+        query.get("format").map(String::as_str),
+        headers
+            .get(header::SEC_WEBSOCKET_PROTOCOL)
+            .and_then(|v| v.to_str().ok()),
+    );
+    let client = state
+        .ws_hub
+        .join(SocketAddr::new(ip, addr.port()), kind, user_agent, format);
+    ws.protocols([ws_hub::MSGPACK_PROTOCOL])
+        .on_upgrade(move |socket| handle_socket(socket, client))
 }
 
-async fn handle_socket(mut socket: WebSocket, rx: watch::Receiver<TelemetryFrame>) {
-    let mut ticker = tokio::time::interval(Duration::from_millis(500));
+async fn handle_socket(mut socket: WebSocket, mut client: WsClient) {
+    let _client = crate::selfstats::stats().ws_client();
 
     loop {
         tokio::select! {
-            _ = ticker.tick() => {
-                let frame = rx.borrow().clone();
-                let payload = serde_json::json!({
-                    "ts": frame.timestamp_unix,
-                    "health": frame.health,
-                    "obs": frame.obs,
-                    "system": frame.system,
-                    "network": frame.network,
-                    "outputs": frame.streams,
-                });
-                if socket.send(Message::Text(payload.to_string())).await.is_err() {
+            message = client.frames.recv() => {
+                let message = match message {
+                    Ok(message) => message,
+                    // A slow client skips the frames it missed and gets the next one.
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+                if !client.filter.admit(message.channel, Instant::now()) {
+                    continue;
+                }
+                let message = match client.format {
+                    WsFormat::Json => Message::Text(message.payload.as_ref().clone()),
+                    WsFormat::Msgpack => Message::Binary(message.packed()),
+                };
+                if socket.send(message).await.is_err() {
                     break;
                 }
             }
             msg = socket.recv() => {
-                match msg {
+                let reply = match msg {
                     Some(Ok(Message::Close(_))) | None => break,
-                    _ => {}
+                    Some(Ok(Message::Text(text))) => ws_hub::Subscription::parse(&text),
+                    Some(Ok(Message::Binary(bytes))) => ws_hub::Subscription::parse_msgpack(&bytes),
+                    _ => continue,
+                };
+                let reply = match reply {
+                    Ok(subscription) => {
+                        let reply = serde_json::json!({
+                            "type": "subscribed",
+                            "channels": subscription.channels,
+                            "max_rate_hz": subscription.max_rate_hz,
+                        });
+                        client.subscribe(subscription);
+                        reply
+                    }
+                    Err(error) => serde_json::json!({ "type": "error", "error": error }),
+                };
+                let reply = match client.format {
+                    WsFormat::Json => Message::Text(reply.to_string()),
+                    WsFormat::Msgpack => {
+                        Message::Binary(rmp_serde::to_vec_named(&reply).unwrap_or_default())
+                    }
+                };
+                if socket.send(reply).await.is_err() {
+                    break;
                 }
             }
         }
@@ -1142,30 +889,160 @@ enum QueryTokenPolicy {
     Deny,
 }
 
-fn is_token_valid(
-    headers: &HeaderMap,
-    query: &HashMap<String, String>,
-    token: &str,
+impl ServerState {
+    fn self_stats(&self) -> crate::selfstats::SelfStatsSnapshot {
+        let (ipc_connected, ipc_link) = {
+            let ipc = self.ipc_debug_status.lock().unwrap();
+            (ipc.session_connected, ipc.link)
+        };
+        crate::selfstats::stats().snapshot(ipc_connected, ipc_link)
+    }
+
+    fn authorize(
+        &self,
+        headers: &HeaderMap,
+        query: &HashMap<String, String>,
+        query_policy: QueryTokenPolicy,
+        scope: Scope,
+    ) -> bool {
+        if bearer_token(headers).is_none() {
+            let session_token = session_id(headers).and_then(|id| self.sessions.token_for(id));
+            if let Some(token) = session_token {
+                if self.tokens.authorize(&token, scope) {
+                    return true;
+                }
+            }
+        }
+        is_token_valid(headers, query, &self.tokens, query_policy, scope)
+    }
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+fn session_id(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(name, _)| *name == SESSION_COOKIE)
+        .map(|(_, value)| value)
+}
+
+fn has_credentials(headers: &HeaderMap, query: &HashMap<String, String>) -> bool {
+    bearer_token(headers).is_some()
+        || session_id(headers).is_some()
+        || query.get("token").is_some_and(|t| !t.is_empty())
+}
+
+/// The client address after `[server.access]` proxy handling, set by
+/// [`enforce_access`] for the layers and handlers inside it.
+#[derive(Debug, Clone, Copy)]
+struct ClientIp(IpAddr);
+
+async fn enforce_access(
+    State(state): State<Arc<ServerState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let ip = state.access.client_ip(addr.ip(), req.headers());
+    if !state.access.allows(ip) {
+        tracing::debug!(%ip, path = req.uri().path(), "rejected by server.access allowlist");
+        return ApiError::forbidden().into_response();
+    }
+    req.extensions_mut().insert(ClientIp(ip));
+    next.run(req).await
+}
+
+// Every handler rejects with 401, so failures are recorded in one place here.
+async fn audit_auth_failures(
+    State(state): State<Arc<ServerState>>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let path = req.uri().path().to_string();
+    let response = next.run(req).await;
+    if response.status() == StatusCode::UNAUTHORIZED {
+        state.audit.record(&ip.to_string(), &path);
+    }
+    response
+}
+
+async fn enforce_rate_limit(
+    State(limiter): State<Arc<RateLimiter>>,
+    Extension(ClientIp(ip)): Extension<ClientIp>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let path = req.uri().path();
+    let successor = legacy::successor(path);
+    let sensitive = rate_limit::is_sensitive(req.method(), successor.as_deref().unwrap_or(path));
+    let (retry_after_secs, error) = match limiter.check(ip, sensitive, Instant::now()) {
+        Decision::Allow => {
+            let response = next.run(req).await;
+            if response.status() == StatusCode::UNAUTHORIZED {
+                limiter.record_failure(ip, Instant::now());
+            }
+            return response;
+        }
+        Decision::Limited { retry_after_secs } => (retry_after_secs, "rate limit exceeded"),
+        Decision::LockedOut { retry_after_secs } => {
+            (retry_after_secs, "too many failed auth attempts")
+        }
+    };
+    ApiError::too_many_requests(error, retry_after_secs).into_response()
+}
+
+fn presented_token<'a>(
+    headers: &'a HeaderMap,
+    query: &'a HashMap<String, String>,
     query_policy: QueryTokenPolicy,
-) -> bool {
+) -> Option<&'a str> {
     // First check Authorization header (preferred for API access)
     // Format: "Bearer <token>"
-    if let Some(auth_header) = headers.get("authorization") {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if let Some(provided_token) = auth_str.strip_prefix("Bearer ") {
-                return provided_token == token;
-            }
-        }
+    if let Some(provided_token) = bearer_token(headers) {
+        return Some(provided_token);
     }
 
     if query_policy == QueryTokenPolicy::Allow {
         // Fall back to query parameter for browser/Dock GET routes.
-        return query.get("token").map(|t| t == token).unwrap_or(false);
+        return query
+            .get("token")
+            .map(String::as_str)
+            .filter(|t| !t.is_empty());
     }
 
-    false
+    None
+}
+
+fn is_token_valid(
+    headers: &HeaderMap,
+    query: &HashMap<String, String>,
+    tokens: &TokenStore,
+    query_policy: QueryTokenPolicy,
+    scope: Scope,
+) -> bool {
+    presented_token(headers, query, query_policy)
+        .map(|token| tokens.authorize(token, scope))
+        .unwrap_or(false)
 }
 
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "system",
+    responses((status = 200, description = "Liveness probe", body = Object)),
+    security(())
+)]
 async fn health_check() -> impl IntoResponse {
     (
         StatusCode::OK,
@@ -1189,10 +1066,11 @@ fn html_escape(s: &str) -> String {
 
 fn theme_css(theme: &ThemeConfig) -> String {
     format!(
-        "--font: {}; --bg: {}; --panel: {}; --muted: {}; --good: {}; --warn: {}; --bad: {}; --line: {};",
+        "--font: {}; --bg: {}; --panel: {}; --text: {}; --muted: {}; --good: {}; --warn: {}; --bad: {}; --line: {};",
         theme.font_family,
         theme.bg,
         theme.panel,
+        theme.text,
         theme.muted,
         theme.good,
         theme.warn,
@@ -1207,47 +1085,55 @@ struct OutputNamesPayload {
     names: HashMap<String, String>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/output-names",
+    tag = "settings",
+    responses((status = 200, description = "Output id to display name", body = HashMap<String, String>)),
+    security(("bearer" = ["telemetry_read"]))
+)]
 async fn get_output_names(
     State(state): State<Arc<ServerState>>,
     headers: HeaderMap,
     query: Query<HashMap<String, String>>,
 ) -> impl IntoResponse {
-    if !is_token_valid(&headers, &query.0, &state.token, QueryTokenPolicy::Deny) {
-        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
     }
 
-    // Load current config to get latest names
-    match Config::load() {
-        Ok(config) => (StatusCode::OK, axum::Json(config.output_names)).into_response(),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to load config: {}", e),
-        )
-            .into_response(),
-    }
+    let output_names = state.config.read(|config| config.output_names.clone());
+    (StatusCode::OK, axum::Json(output_names)).into_response()
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/output-names",
+    tag = "settings",
+    request_body = HashMap<String, String>,
+    responses((status = 200, description = "Saved", body = String, content_type = "text/plain")),
+    security(("bearer" = ["settings_write"]))
+)]
 async fn save_output_names(
     State(state): State<Arc<ServerState>>,
     headers: HeaderMap,
     query: Query<HashMap<String, String>>,
     axum::Json(payload): axum::Json<OutputNamesPayload>,
 ) -> impl IntoResponse {
-    if !is_token_valid(&headers, &query.0, &state.token, QueryTokenPolicy::Deny) {
-        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::SettingsWrite,
+    ) {
+        return ApiError::unauthorized().into_response();
     }
 
-    // Load current config
-    let mut config = match Config::load() {
-        Ok(cfg) => cfg,
-        Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to load config: {}", e),
-            )
-                .into_response();
-        }
-    };
+    let mut config = state.config.get();
 
     // Merge new names with existing
     for (id, name) in payload.names {
@@ -1259,118 +1145,237 @@ async fn save_output_names(
     }
 
     // Save config
-    match config.save() {
+    match state.config.save(config) {
         Ok(()) => (StatusCode::OK, "Output names saved").into_response(),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            format!("Failed to save config: {}", e),
-        )
-            .into_response(),
+        Err(e) => ApiError::internal(format!("Failed to save config: {}", e)).into_response(),
     }
 }
 
-const GRAFANA_DASHBOARD_JSON: &str = include_str!("../../assets/grafana-dashboard.json");
-
+#[utoipa::path(
+    get,
+    path = "/api/v1/grafana-dashboard",
+    tag = "settings",
+    params(
+        ("token" = Option<String>, Query, description = "Token for download links"),
+        ("variant" = Option<String>, Query, description = "overview (default), outputs, system or aegis")
+    ),
+    responses(
+        (status = 200, description = "Grafana dashboard JSON", body = Object),
+        (status = 400, description = "Unknown variant", body = ErrorBody)
+    ),
+    security(("bearer" = ["telemetry_read"]))
+)]
 async fn grafana_dashboard_download(
     State(state): State<Arc<ServerState>>,
     headers: HeaderMap,
     query: Query<HashMap<String, String>>,
 ) -> impl IntoResponse {
-    if !is_token_valid(&headers, &query.0, &state.token, QueryTokenPolicy::Allow) {
-        return StatusCode::UNAUTHORIZED.into_response();
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Allow,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
     }
 
-    (
-        StatusCode::OK,
-        [
-            ("content-type", "application/json"),
-            (
-                "content-disposition",
-                "attachment; filename=\"telemy-dashboard.json\"",
-            ),
-        ],
-        GRAFANA_DASHBOARD_JSON,
-    )
-        .into_response()
+    let dashboard = match query
+        .0
+        .get("variant")
+        .map(|v| v.parse::<grafana::Dashboard>())
+    {
+        None => grafana::Dashboard::default(),
+        Some(Ok(dashboard)) => dashboard,
+        Some(Err(err)) => return ApiError::bad_request(err).into_response(),
+    };
+    let mut response = caching::with_etag(
+        &headers,
+        caching::DOCUMENT,
+        "application/json",
+        dashboard.json(),
+    );
+    let disposition = format!("attachment; filename=\"{}\"", dashboard.file_name());
+    if let Ok(value) = header::HeaderValue::from_str(&disposition) {
+        response
+            .headers_mut()
+            .insert(header::CONTENT_DISPOSITION, value);
+    }
+    response
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 struct GrafanaImportForm {
     grafana_url: String,
     grafana_api_key: String,
+    /// Which bundled dashboard to import; defaults to the overview.
+    variant: Option<grafana::Dashboard>,
+    /// Folder title or uid, created when missing; empty for General.
+    folder: Option<String>,
+    /// Replace an existing dashboard with the same uid. Defaults to true.
+    overwrite: Option<bool>,
+    /// Prometheus data source uid the panels should query.
+    datasource_uid: Option<String>,
+    /// Import under this uid instead of the bundled one.
+    dashboard_uid: Option<String>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/grafana-dashboard/import",
+    tag = "settings",
+    request_body(content = GrafanaImportForm, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "Imported", body = grafana::Imported),
+        (status = 409, description = "Exists and overwrite is off", body = ErrorBody),
+        (status = 502, description = "Grafana rejected the import", body = ErrorBody)
+    ),
+    security(("bearer" = ["settings_write"]))
+)]
 async fn grafana_dashboard_import(
     State(state): State<Arc<ServerState>>,
     headers: HeaderMap,
     query: Query<HashMap<String, String>>,
     Form(form): Form<GrafanaImportForm>,
 ) -> impl IntoResponse {
-    if !is_token_valid(&headers, &query.0, &state.token, QueryTokenPolicy::Deny) {
-        return (StatusCode::UNAUTHORIZED, "Unauthorized".to_string()).into_response();
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::SettingsWrite,
+    ) {
+        return ApiError::unauthorized().into_response();
     }
 
     let url = form.grafana_url.trim().trim_end_matches('/');
     let api_key = form.grafana_api_key.trim();
 
     if url.is_empty() || api_key.is_empty() {
-        return (
-            StatusCode::BAD_REQUEST,
-            "Grafana URL and API key are required".to_string(),
-        )
-            .into_response();
+        return ApiError::bad_request("Grafana URL and API key are required").into_response();
     }
 
-    let import_url = format!("{}/api/dashboards/db", url);
+    fn non_empty(v: &Option<String>) -> Option<&str> {
+        v.as_deref().map(str::trim).filter(|v| !v.is_empty())
+    }
+    let options = grafana::ImportOptions {
+        folder: non_empty(&form.folder),
+        overwrite: form.overwrite.unwrap_or(true),
+        datasource_uid: non_empty(&form.datasource_uid),
+        dashboard_uid: non_empty(&form.dashboard_uid),
+    };
+    for (name, uid) in [
+        ("datasource_uid", options.datasource_uid),
+        ("dashboard_uid", options.dashboard_uid),
+    ] {
+        if uid.is_some_and(|uid| !grafana::valid_uid(uid)) {
+            return ApiError::bad_request(format!(
+                "{name} may only contain letters, digits, - and _ (up to 40)"
+            ))
+            .into_response();
+        }
+    }
 
     let client = match reqwest::Client::builder()
         .timeout(Duration::from_secs(10))
         .build()
     {
         Ok(c) => c,
-        Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("HTTP client error: {}", e),
-            )
-                .into_response()
-        }
+        Err(e) => return ApiError::internal(format!("HTTP client error: {}", e)).into_response(),
     };
 
-    let res = client
-        .post(&import_url)
-        .header("Authorization", format!("Bearer {}", api_key))
-        .header("Content-Type", "application/json")
-        .body(GRAFANA_DASHBOARD_JSON)
-        .send()
-        .await;
-
-    match res {
-        Ok(resp) => {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            if status.is_success() {
-                (
-                    StatusCode::OK,
-                    "Dashboard imported successfully into Grafana.".to_string(),
-                )
-                    .into_response()
-            } else {
-                (
-                    StatusCode::BAD_GATEWAY,
-                    format!("Grafana returned {}: {}", status, body),
-                )
-                    .into_response()
+    let dashboard = form.variant.unwrap_or_default();
+    match grafana::import(&client, url, api_key, dashboard.json(), &options).await {
+        Ok(imported) => axum::Json(imported).into_response(),
+        Err(err) => err.into_response(),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+struct GrafanaTestForm {
+    /// OTLP base URL; blank uses the saved endpoint.
+    grafana_endpoint: Option<String>,
+    /// Leave both blank to check the credentials already in the vault.
+    grafana_instance_id: Option<String>,
+    grafana_api_token: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/settings/grafana/test",
+    tag = "settings",
+    request_body(content = GrafanaTestForm, content_type = "application/x-www-form-urlencoded"),
+    responses(
+        (status = 200, description = "Grafana accepted the push", body = grafana::Verified),
+        (status = 400, description = "Endpoint or credentials missing", body = ErrorBody),
+        (status = 502, description = "Grafana rejected the push or was unreachable", body = ErrorBody)
+    ),
+    security(("bearer" = ["settings_write"]))
+)]
+async fn post_grafana_test(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+    Form(form): Form<GrafanaTestForm>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::SettingsWrite,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    fn non_empty(v: &Option<String>) -> Option<&str> {
+        v.as_deref().map(str::trim).filter(|v| !v.is_empty())
+    }
+    let grafana = state.config.read(|c| c.grafana.clone());
+    let Some(endpoint) = non_empty(&form.grafana_endpoint)
+        .map(str::to_string)
+        .or(grafana.endpoint)
+    else {
+        return ApiError::bad_request("Enter the OTLP endpoint").into_response();
+    };
+    let auth_value = match (
+        non_empty(&form.grafana_instance_id),
+        non_empty(&form.grafana_api_token),
+    ) {
+        (Some(instance_id), Some(api_token)) => grafana::basic_auth(instance_id, api_token),
+        (None, None) => {
+            let vault = state.vault.read().await;
+            let stored = grafana
+                .auth_value_key
+                .as_deref()
+                .and_then(|key| vault.retrieve(key).ok());
+            match stored {
+                Some(value) => value.expose().to_string(),
+                None => {
+                    return ApiError::bad_request("Enter the instance ID and API token")
+                        .into_response()
+                }
             }
         }
-        Err(e) => (
-            StatusCode::BAD_GATEWAY,
-            format!("Failed to reach Grafana: {}", e),
-        )
-            .into_response(),
+        _ => {
+            return ApiError::bad_request("Enter both the instance ID and the API token")
+                .into_response()
+        }
+    };
+
+    let client = match reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()
+    {
+        Ok(c) => c,
+        Err(e) => return ApiError::internal(format!("HTTP client error: {}", e)).into_response(),
+    };
+    match grafana::verify_otlp(&client, &endpoint, &grafana.auth_header, &auth_value).await {
+        Ok(verified) => axum::Json(verified).into_response(),
+        Err(err) => err.into_response(),
     }
 }
 
+/// How long `/api/v1/aegis/status?refresh=1` waits for the background poll.
+const AEGIS_REFRESH_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Serialize)]
 struct AegisStatusResponse {
     enabled: bool,
@@ -1378,19 +1383,46 @@ struct AegisStatusResponse {
     refreshed: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
+    control_plane: ControlPlaneHealth,
+    #[serde(flatten)]
+    freshness: SessionFreshness,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct AegisActionResponse {
     ok: bool,
     message: String,
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
     session: Option<RelaySession>,
     #[serde(skip_serializing_if = "Option::is_none")]
     error: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Progress of the settings-page device login; one runs at a time.
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+struct AegisLoginStatus {
+    /// `idle`, `pending`, `complete` or `failed`.
+    state: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    verification_uri: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    verification_uri_complete: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_in: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct AegisLoginForm {
+    #[serde(default)]
+    base_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
 struct IpcSwitchSceneRequest {
     scene_name: String,
     #[serde(default)]
@@ -1401,32 +1433,298 @@ struct IpcSwitchSceneRequest {
     allow_empty: Option<bool>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct IpcSwitchSceneResponse {
     ok: bool,
     message: String,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/ipc/status",
+    tag = "ipc",
+    responses((status = 200, description = "Plugin IPC session and link stats", body = Object)),
+    security(("bearer" = ["telemetry_read"]))
+)]
 async fn get_ipc_status(
     State(state): State<Arc<ServerState>>,
     headers: HeaderMap,
     query: Query<HashMap<String, String>>,
 ) -> impl IntoResponse {
-    if !is_token_valid(&headers, &query.0, &state.token, QueryTokenPolicy::Deny) {
-        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
     }
 
     let snapshot: IpcDebugStatus = state.ipc_debug_status.lock().unwrap().clone();
     (StatusCode::OK, axum::Json(snapshot)).into_response()
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/clients",
+    tag = "system",
+    responses((status = 200, description = "Connected WebSocket clients by kind", body = Object)),
+    security(("bearer" = ["telemetry_read"]))
+)]
+async fn get_ws_clients(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(&headers, &query.0, QueryTokenPolicy::Deny, Scope::Admin) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    (StatusCode::OK, axum::Json(state.ws_hub.summary())).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/selfstats",
+    tag = "system",
+    responses((status = 200, description = "Bridge process stats", body = Object)),
+    security(("bearer" = ["telemetry_read"]))
+)]
+async fn get_selfstats(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    (StatusCode::OK, axum::Json(state.self_stats())).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/pause",
+    tag = "system",
+    responses((status = 200, body = PauseStatus)),
+    security(("bearer" = ["telemetry_read"]))
+)]
+async fn get_pause(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    axum::Json(crate::pause::status()).into_response()
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/pause",
+    tag = "system",
+    request_body = PauseStatus,
+    responses((status = 200, body = PauseStatus)),
+    security(("bearer" = ["settings_write"]))
+)]
+async fn put_pause(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+    Json(body): Json<PauseStatus>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::SettingsWrite,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    axum::Json(crate::pause::set_paused(body.paused, "api")).into_response()
+}
+
+/// `GET /api/v1/status`: a one-glance summary for `telemy status`; relay
+/// credentials stay behind `/api/v1/aegis/status`.
+#[derive(Debug, Serialize, ToSchema)]
+struct StatusResponse {
+    version: &'static str,
+    pid: u32,
+    uptime_secs: u64,
+    paused: bool,
+    health: f32,
+    obs: ObsStatus,
+    aegis: Option<AegisBrief>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct ObsStatus {
+    state: crate::model::ObsConnectionState,
+    streaming: bool,
+    recording: bool,
+    bitrate_kbps: Option<u32>,
+    drop_pct: Option<f32>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+struct AegisBrief {
+    session_id: String,
+    status: String,
+    region: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/status",
+    tag = "system",
+    responses((status = 200, body = StatusResponse)),
+    security(("bearer" = ["telemetry_read"]))
+)]
+async fn get_status(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    let frame = state.rx.borrow().clone();
+    let main = crate::rules::main_output(&frame);
+    let aegis = state
+        .aegis_session_snapshot
+        .read()
+        .await
+        .as_ref()
+        .map(|session| AegisBrief {
+            session_id: session.session_id.clone(),
+            status: session.status.clone(),
+            region: session.region.clone(),
+        });
+    axum::Json(StatusResponse {
+        version: env!("CARGO_PKG_VERSION"),
+        pid: std::process::id(),
+        uptime_secs: state.self_stats().uptime_secs,
+        paused: crate::pause::is_paused(),
+        health: frame.health,
+        obs: ObsStatus {
+            state: frame.obs.connection_state,
+            streaming: frame.obs.streaming,
+            recording: frame.obs.recording,
+            bitrate_kbps: main.map(|o| o.bitrate_kbps),
+            drop_pct: main.map(|o| o.drop_pct),
+        },
+        aegis,
+    })
+    .into_response()
+}
+
+/// Delay before `POST /api/v1/shutdown` takes effect, so the reply gets out first.
+const SHUTDOWN_DELAY: Duration = Duration::from_millis(250);
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/shutdown",
+    tag = "system",
+    responses((status = 202, description = "`{ stopping, pid }`; the process exits shortly after", body = Object)),
+    security(("bearer" = ["admin"]))
+)]
+async fn post_shutdown(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(&headers, &query.0, QueryTokenPolicy::Deny, Scope::Admin) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    tracing::info!("shutdown requested over the API");
+    let shutdown = state.shutdown.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(SHUTDOWN_DELAY).await;
+        let _ = shutdown.send(true);
+    });
+    (
+        StatusCode::ACCEPTED,
+        axum::Json(serde_json::json!({ "stopping": true, "pid": std::process::id() })),
+    )
+        .into_response()
+}
+
+/// Prometheus scrape target; scrapers authenticate with `authorization: credentials`.
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "telemetry",
+    responses((status = 200, description = "Prometheus text exposition", body = String, content_type = "text/plain")),
+    security(("bearer" = ["telemetry_read"]))
+)]
+async fn get_prometheus_metrics(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    let frame = state.rx.borrow().clone();
+    let output_names = state.config.read(|config| config.output_names.clone());
+    let body = crate::exporters::prometheus::render(&frame, &output_names, &state.self_stats());
+    (
+        StatusCode::OK,
+        [(
+            header::CONTENT_TYPE,
+            crate::exporters::prometheus::CONTENT_TYPE,
+        )],
+        body,
+    )
+        .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/aegis/status",
+    tag = "aegis",
+    params(("refresh" = Option<bool>, Query, description = "Poll the control plane first")),
+    responses((status = 200, description = "Relay session and control-plane health", body = Object), (status = 502, description = "Refresh failed", body = Object)),
+    security(("bearer" = ["telemetry_read"]))
+)]
 async fn get_aegis_status(
     State(state): State<Arc<ServerState>>,
     headers: HeaderMap,
     query: Query<HashMap<String, String>>,
 ) -> impl IntoResponse {
-    if !is_token_valid(&headers, &query.0, &state.token, QueryTokenPolicy::Allow) {
-        return StatusCode::UNAUTHORIZED.into_response();
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Allow,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
     }
 
     let refresh_requested = query
@@ -1435,21 +1733,7 @@ async fn get_aegis_status(
         .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
         .unwrap_or(false);
 
-    let config = match Config::load() {
-        Ok(cfg) => cfg,
-        Err(err) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                axum::Json(AegisStatusResponse {
-                    enabled: false,
-                    session: state.aegis_session_snapshot.lock().unwrap().clone(),
-                    refreshed: false,
-                    error: Some(format!("config load failed: {err}")),
-                }),
-            )
-                .into_response();
-        }
-    };
+    let config = state.config.get();
 
     if !config.aegis.enabled {
         return (
@@ -1459,35 +1743,30 @@ async fn get_aegis_status(
                 session: None,
                 refreshed: false,
                 error: None,
+                control_plane: control_plane_health(),
+                freshness: SessionPoller::shared().freshness(Instant::now()),
             }),
         )
             .into_response();
     }
 
     if refresh_requested {
-        let client = {
-            let vault = state.vault.lock().unwrap();
-            build_aegis_client_from_config(&config, &vault).map_err(|err| err.to_string())
-        };
-        let refreshed = match client {
-            Ok(client) => match client.relay_active().await {
-                Ok(session) => {
-                    *state.aegis_session_snapshot.lock().unwrap() = session.clone();
-                    Ok(session)
-                }
-                Err(err) => Err(format!("{err}")),
-            },
-            Err(err) => Err(format!("{err}")),
-        };
+        // The background poller does the round trip; concurrent refreshes share it.
+        let refreshed = SessionPoller::shared()
+            .refresh_now(AEGIS_REFRESH_TIMEOUT)
+            .await
+            .unwrap_or_else(|| Err("aegis refresh timed out".to_string()));
 
         return match refreshed {
-            Ok(session) => (
+            Ok(()) => (
                 StatusCode::OK,
                 axum::Json(AegisStatusResponse {
                     enabled: true,
-                    session,
+                    session: state.aegis_session_snapshot.read().await.clone(),
                     refreshed: true,
                     error: None,
+                    control_plane: control_plane_health(),
+                    freshness: SessionPoller::shared().freshness(Instant::now()),
                 }),
             )
                 .into_response(),
@@ -1495,9 +1774,11 @@ async fn get_aegis_status(
                 StatusCode::BAD_GATEWAY,
                 axum::Json(AegisStatusResponse {
                     enabled: true,
-                    session: state.aegis_session_snapshot.lock().unwrap().clone(),
+                    session: state.aegis_session_snapshot.read().await.clone(),
                     refreshed: false,
                     error: Some(err),
+                    control_plane: control_plane_health(),
+                    freshness: SessionPoller::shared().freshness(Instant::now()),
                 }),
             )
                 .into_response(),
@@ -1508,41 +1789,57 @@ async fn get_aegis_status(
         StatusCode::OK,
         axum::Json(AegisStatusResponse {
             enabled: true,
-            session: state.aegis_session_snapshot.lock().unwrap().clone(),
+            session: state.aegis_session_snapshot.read().await.clone(),
             refreshed: false,
             error: None,
+            control_plane: control_plane_health(),
+            freshness: SessionPoller::shared().freshness(Instant::now()),
         }),
     )
         .into_response()
 }
 
+/// Optional body of `POST /aegis/start`; the dashboard sends none, the CLI
+/// forwards its `--region` when handing off to a running instance.
+#[derive(Debug, Deserialize, ToSchema)]
+struct AegisStartBody {
+    region: Option<String>,
+    requested_by: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+struct AegisStopBody {
+    session_id: Option<String>,
+    reason: Option<String>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/aegis/start",
+    tag = "aegis",
+    request_body(content = Option<AegisStartBody>, description = "Optional; region defaults to `auto`"),
+    responses((status = 200, body = AegisActionResponse), (status = 400, body = AegisActionResponse), (status = 502, body = AegisActionResponse)),
+    security(("bearer" = ["aegis_control"]))
+)]
 async fn post_aegis_start(
     State(state): State<Arc<ServerState>>,
     headers: HeaderMap,
     query: Query<HashMap<String, String>>,
+    body: Option<Json<AegisStartBody>>,
 ) -> impl IntoResponse {
-    if !is_token_valid(&headers, &query.0, &state.token, QueryTokenPolicy::Deny) {
-        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::AegisControl,
+    ) {
+        return ApiError::unauthorized().into_response();
     }
 
-    let config = match Config::load() {
-        Ok(cfg) => cfg,
-        Err(err) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                axum::Json(AegisActionResponse {
-                    ok: false,
-                    message: "config load failed".to_string(),
-                    session: state.aegis_session_snapshot.lock().unwrap().clone(),
-                    error: Some(err.to_string()),
-                }),
-            )
-                .into_response()
-        }
-    };
+    let config = state.config.get();
 
     let client = {
-        let vault = state.vault.lock().unwrap();
+        let vault = state.vault.read().await;
         build_aegis_client_from_config(&config, &vault).map_err(|err| err.to_string())
     };
 
@@ -1554,7 +1851,7 @@ async fn post_aegis_start(
                 axum::Json(AegisActionResponse {
                     ok: false,
                     message: "aegis client config invalid".to_string(),
-                    session: state.aegis_session_snapshot.lock().unwrap().clone(),
+                    session: state.aegis_session_snapshot.read().await.clone(),
                     error: Some(err),
                 }),
             )
@@ -1562,19 +1859,39 @@ async fn post_aegis_start(
         }
     };
 
+    let (region, requested_by) = match body {
+        Some(Json(body)) => (body.region, body.requested_by),
+        None => (Some("auto".to_string()), None),
+    };
     let request = RelayStartRequest {
-        region_preference: Some("auto".to_string()),
+        region_preference: resolve_region_preference(&client, region).await,
         client_context: Some(RelayStartClientContext {
             obs_connected: None,
             mode: Some("studio".to_string()),
-            requested_by: Some("dashboard".to_string()),
+            requested_by: Some(requested_by.unwrap_or_else(|| "dashboard".to_string())),
         }),
     };
     let idem = generate_idempotency_key();
 
-    match client.relay_start(&idem, &request).await {
+    let result = client.relay_start(&idem, &request).await;
+    crate::activity::log().record(
+        ActionKind::RelayStart,
+        "api",
+        request
+            .client_context
+            .as_ref()
+            .and_then(|c| c.requested_by.clone()),
+        match &result {
+            Ok(session) => Ok(format!(
+                "relay session {} {}",
+                session.session_id, session.status
+            )),
+            Err(err) => Err(err.to_string()),
+        },
+    );
+    match result {
         Ok(session) => {
-            *state.aegis_session_snapshot.lock().unwrap() = Some(session.clone());
+            *state.aegis_session_snapshot.write().await = Some(session.clone());
             (
                 StatusCode::OK,
                 axum::Json(AegisActionResponse {
@@ -1591,7 +1908,7 @@ async fn post_aegis_start(
             axum::Json(AegisActionResponse {
                 ok: false,
                 message: "relay start failed".to_string(),
-                session: state.aegis_session_snapshot.lock().unwrap().clone(),
+                session: state.aegis_session_snapshot.read().await.clone(),
                 error: Some(err.to_string()),
             }),
         )
@@ -1599,33 +1916,165 @@ async fn post_aegis_start(
     }
 }
 
-async fn post_aegis_stop(
+#[utoipa::path(
+    get,
+    path = "/api/v1/aegis/login",
+    tag = "aegis",
+    responses((status = 200, body = AegisLoginStatus)),
+    security(("bearer" = ["settings_write"]))
+)]
+async fn get_aegis_login(
     State(state): State<Arc<ServerState>>,
     headers: HeaderMap,
     query: Query<HashMap<String, String>>,
 ) -> impl IntoResponse {
-    if !is_token_valid(&headers, &query.0, &state.token, QueryTokenPolicy::Deny) {
-        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::SettingsWrite,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+    let mut status = state.aegis_login.lock().unwrap().clone();
+    if status.state.is_empty() {
+        status.state = "idle";
     }
+    axum::Json(status).into_response()
+}
 
-    let config = match Config::load() {
-        Ok(cfg) => cfg,
-        Err(err) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                axum::Json(AegisActionResponse {
-                    ok: false,
-                    message: "config load failed".to_string(),
-                    session: state.aegis_session_snapshot.lock().unwrap().clone(),
-                    error: Some(err.to_string()),
-                }),
-            )
-                .into_response()
+/// Starts a device login and returns the code to show the user. Approval is
+/// awaited in the background; poll `GET /aegis/login` for the outcome.
+#[utoipa::path(
+    post,
+    path = "/api/v1/aegis/login",
+    tag = "aegis",
+    request_body(content = AegisLoginForm, content_type = "application/x-www-form-urlencoded"),
+    responses((status = 200, body = AegisLoginStatus), (status = 409, description = "A login is already pending", body = AegisLoginStatus), (status = 502, body = AegisLoginStatus)),
+    security(("bearer" = ["settings_write"]))
+)]
+async fn post_aegis_login(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+    Form(form): Form<AegisLoginForm>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::SettingsWrite,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+    {
+        let current = state.aegis_login.lock().unwrap();
+        if current.state == "pending" {
+            return (StatusCode::CONFLICT, axum::Json(current.clone())).into_response();
         }
+    }
+
+    let failed = |status: StatusCode, error: String| {
+        let body = AegisLoginStatus {
+            state: "failed",
+            error: Some(error),
+            ..AegisLoginStatus::default()
+        };
+        (status, axum::Json(body)).into_response()
+    };
+    let config = state.config.get();
+    let Some(base_url) = form
+        .base_url
+        .or(config.aegis.base_url)
+        .filter(|url| !url.trim().is_empty())
+    else {
+        return failed(
+            StatusCode::BAD_REQUEST,
+            "missing aegis.base_url in config".to_string(),
+        );
+    };
+    let login = match DeviceLogin::new(&base_url) {
+        Ok(login) => login,
+        Err(err) => return failed(StatusCode::BAD_REQUEST, err.to_string()),
+    };
+    let auth = match login.start().await {
+        Ok(auth) => auth,
+        Err(err) => return failed(StatusCode::BAD_GATEWAY, err.to_string()),
     };
 
+    let pending = AegisLoginStatus {
+        state: "pending",
+        user_code: Some(auth.user_code.clone()),
+        verification_uri: Some(auth.verification_uri.clone()),
+        verification_uri_complete: auth.verification_uri_complete.clone(),
+        expires_in: Some(auth.expires_in),
+        error: None,
+    };
+    *state.aegis_login.lock().unwrap() = pending.clone();
+
+    let login_state = state.aegis_login.clone();
+    let vault = state.vault.clone();
+    let config_handle = state.config.clone();
+    tokio::spawn(async move {
+        let outcome = match login.wait_for_tokens(&auth).await {
+            Ok(tokens) => {
+                let mut config = config_handle.get();
+                let mut vault = vault.write().await;
+                crate::app::store_aegis_tokens(&mut config, &mut vault, &base_url, &tokens)
+                    .and_then(|key| config_handle.reload_if_changed().map(|_| key))
+            }
+            Err(err) => Err(err.into()),
+        };
+        let mut status = login_state.lock().unwrap();
+        match outcome {
+            Ok(key) => {
+                tracing::info!(vault_key = %key, "aegis device login complete");
+                *status = AegisLoginStatus {
+                    state: "complete",
+                    ..AegisLoginStatus::default()
+                };
+            }
+            Err(err) => {
+                tracing::warn!(error = %err, "aegis device login failed");
+                *status = AegisLoginStatus {
+                    state: "failed",
+                    error: Some(err.to_string()),
+                    ..AegisLoginStatus::default()
+                };
+            }
+        }
+    });
+
+    axum::Json(pending).into_response()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/aegis/stop",
+    tag = "aegis",
+    request_body(content = Option<AegisStopBody>, description = "Optional; stops the active session"),
+    responses((status = 200, body = AegisActionResponse), (status = 400, body = AegisActionResponse), (status = 502, body = AegisActionResponse)),
+    security(("bearer" = ["aegis_control"]))
+)]
+async fn post_aegis_stop(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+    body: Option<Json<AegisStopBody>>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::AegisControl,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    let config = state.config.get();
+
     let client = {
-        let vault = state.vault.lock().unwrap();
+        let vault = state.vault.read().await;
         build_aegis_client_from_config(&config, &vault).map_err(|err| err.to_string())
     };
     let client = match client {
@@ -1636,7 +2085,7 @@ async fn post_aegis_stop(
                 axum::Json(AegisActionResponse {
                     ok: false,
                     message: "aegis client config invalid".to_string(),
-                    session: state.aegis_session_snapshot.lock().unwrap().clone(),
+                    session: state.aegis_session_snapshot.read().await.clone(),
                     error: Some(err),
                 }),
             )
@@ -1644,15 +2093,23 @@ async fn post_aegis_stop(
         }
     };
 
-    let current = match client.relay_active().await {
-        Ok(session) => session,
+    let Json(body) = body.unwrap_or_default();
+    let current = match body.session_id {
+        Some(session_id) => Ok(Some(session_id)),
+        None => client
+            .relay_active()
+            .await
+            .map(|session| session.map(|s| s.session_id)),
+    };
+    let current = match current {
+        Ok(session_id) => session_id,
         Err(err) => {
             return (
                 StatusCode::BAD_GATEWAY,
                 axum::Json(AegisActionResponse {
                     ok: false,
                     message: "relay active lookup failed".to_string(),
-                    session: state.aegis_session_snapshot.lock().unwrap().clone(),
+                    session: state.aegis_session_snapshot.read().await.clone(),
                     error: Some(err.to_string()),
                 }),
             )
@@ -1660,8 +2117,8 @@ async fn post_aegis_stop(
         }
     };
 
-    let Some(session) = current else {
-        *state.aegis_session_snapshot.lock().unwrap() = None;
+    let Some(session_id) = current else {
+        *state.aegis_session_snapshot.write().await = None;
         return (
             StatusCode::OK,
             axum::Json(AegisActionResponse {
@@ -1675,12 +2132,22 @@ async fn post_aegis_stop(
     };
 
     let stop_req = RelayStopRequest {
-        session_id: session.session_id.clone(),
-        reason: "user_requested".to_string(),
+        session_id,
+        reason: body.reason.unwrap_or_else(|| "user_requested".to_string()),
     };
-    match client.relay_stop(&stop_req).await {
+    let result = client.relay_stop(&stop_req).await;
+    crate::activity::log().record(
+        ActionKind::RelayStop,
+        "api",
+        Some(stop_req.reason.clone()),
+        match &result {
+            Ok(_) => Ok(format!("relay session {} stopped", stop_req.session_id)),
+            Err(err) => Err(err.to_string()),
+        },
+    );
+    match result {
         Ok(_) => {
-            *state.aegis_session_snapshot.lock().unwrap() = None;
+            *state.aegis_session_snapshot.write().await = None;
             (
                 StatusCode::OK,
                 axum::Json(AegisActionResponse {
@@ -1697,7 +2164,7 @@ async fn post_aegis_stop(
             axum::Json(AegisActionResponse {
                 ok: false,
                 message: "relay stop failed".to_string(),
-                session: state.aegis_session_snapshot.lock().unwrap().clone(),
+                session: state.aegis_session_snapshot.read().await.clone(),
                 error: Some(err.to_string()),
             }),
         )
@@ -1705,14 +2172,27 @@ async fn post_aegis_stop(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/ipc/switch-scene",
+    tag = "ipc",
+    request_body = IpcSwitchSceneRequest,
+    responses((status = 200, body = IpcSwitchSceneResponse), (status = 400, body = IpcSwitchSceneResponse), (status = 404, description = "`unknown_scene`: OBS has no such scene", body = ErrorBody), (status = 503, body = IpcSwitchSceneResponse)),
+    security(("bearer" = ["ipc_control"]))
+)]
 async fn post_ipc_switch_scene(
     State(state): State<Arc<ServerState>>,
     headers: HeaderMap,
     query: Query<HashMap<String, String>>,
     Json(body): Json<IpcSwitchSceneRequest>,
 ) -> impl IntoResponse {
-    if !is_token_valid(&headers, &query.0, &state.token, QueryTokenPolicy::Deny) {
-        return (StatusCode::UNAUTHORIZED, "Unauthorized").into_response();
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::IpcControl,
+    ) {
+        return ApiError::unauthorized().into_response();
     }
 
     let scene_name = body.scene_name.trim();
@@ -1736,15 +2216,39 @@ async fn post_ipc_switch_scene(
         .to_string();
     let deadline_ms = body.deadline_ms.unwrap_or(550).clamp(50, 5000);
 
-    match state.ipc_cmd_tx.send(CoreIpcCommand::SwitchScene {
+    let reason = if reason.is_empty() {
+        "manual_debug".to_string()
+    } else {
+        reason
+    };
+    // The empty debug case is meant to reach the plugin.
+    if !scene_name.is_empty() && state.obs.scene_list().is_unknown(scene_name) {
+        let message = format!("OBS has no scene named '{scene_name}'");
+        crate::activity::log().record(
+            ActionKind::SwitchScene,
+            "api",
+            Some(reason),
+            Err(message.clone()),
+        );
+        return ApiError::not_found(message)
+            .with_code("unknown_scene")
+            .into_response();
+    }
+    let sent = state.ipc_cmd_tx.send(CoreIpcCommand::SwitchScene {
         scene_name: scene_name.to_string(),
-        reason: if reason.is_empty() {
-            "manual_debug".to_string()
-        } else {
-            reason
-        },
+        reason: reason.clone(),
         deadline_ms,
-    }) {
+    });
+    crate::activity::log().record(
+        ActionKind::SwitchScene,
+        "api",
+        Some(reason),
+        match &sent {
+            Ok(_) => Ok(format!("queued '{scene_name}' for the OBS plugin")),
+            Err(err) => Err(format!("ipc switch_scene unavailable: {err}")),
+        },
+    );
+    match sent {
         Ok(_receiver_count) => (
             StatusCode::OK,
             axum::Json(IpcSwitchSceneResponse {
@@ -1753,7 +2257,11 @@ async fn post_ipc_switch_scene(
                     "queued ipc switch_scene '{}' (deadline={}ms{})",
                     scene_name,
                     deadline_ms,
-                    if scene_name.is_empty() { ", empty scene debug case" } else { "" }
+                    if scene_name.is_empty() {
+                        ", empty scene debug case"
+                    } else {
+                        ""
+                    }
                 ),
             }),
         )
@@ -1769,6 +2277,747 @@ async fn post_ipc_switch_scene(
     }
 }
 
+#[derive(Debug, Deserialize, ToSchema)]
+struct ObsControlRequest {
+    action: ObsControlAction,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/obs/control",
+    tag = "obs",
+    request_body = ObsControlRequest,
+    responses((status = 200, description = "`{ ok, action }`", body = Object), (status = 502, description = "`{ ok: false, action, error }`", body = Object)),
+    security(("bearer" = ["obs_control"]))
+)]
+async fn post_obs_control(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+    Json(body): Json<ObsControlRequest>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::ObsControl,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    let result = state.obs.control(body.action).await;
+    crate::activity::log().record(
+        ActionKind::ObsControl,
+        "api",
+        None,
+        result.clone().map(|()| body.action.as_str().to_string()),
+    );
+    match result {
+        Ok(()) => (
+            StatusCode::OK,
+            axum::Json(serde_json::json!({ "ok": true, "action": body.action })),
+        )
+            .into_response(),
+        Err(err) => (
+            StatusCode::BAD_GATEWAY,
+            axum::Json(serde_json::json!({ "ok": false, "action": body.action, "error": err })),
+        )
+            .into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/obs/scene-history",
+    tag = "obs",
+    responses((status = 200, description = "Recent program scene changes", body = Object)),
+    security(("bearer" = ["telemetry_read"]))
+)]
+async fn get_scene_history(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    let history = state.obs.scene_history();
+    axum::Json(serde_json::json!({
+        "current": history.current(),
+        "changes": history.snapshot(),
+    }))
+    .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/obs/log-events",
+    tag = "obs",
+    params(("since" = Option<u64>, Query, description = "Only events read after this unix time in ms")),
+    responses((status = 200, description = "Notable lines from the newest OBS log", body = Object)),
+    security(("bearer" = ["telemetry_read"]))
+)]
+async fn get_obs_log_events(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    let since = match query.get("since").map(|s| s.parse::<u64>()) {
+        None => 0,
+        Some(Ok(since)) => since,
+        Some(Err(_)) => {
+            return ApiError::bad_request("since must be unix milliseconds").into_response()
+        }
+    };
+    axum::Json(serde_json::json!({
+        "file": state.obs_log.file(),
+        "events": state.obs_log.since(since),
+    }))
+    .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/events",
+    tag = "telemetry",
+    params(
+        ("since" = Option<u64>, Query, description = "Only events after this unix time in ms"),
+        ("kind" = Option<String>, Query, description = "Comma-separated event kinds, e.g. stream_started,alert_fired"),
+    ),
+    responses((status = 200, description = "Recent state transitions, oldest first", body = Object)),
+    security(("bearer" = ["telemetry_read"]))
+)]
+async fn get_events(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    let since = match query.get("since").map(|s| s.parse::<u64>()) {
+        None => 0,
+        Some(Ok(since)) => since,
+        Some(Err(_)) => {
+            return ApiError::bad_request("since must be unix milliseconds").into_response()
+        }
+    };
+    let kinds: Vec<String> = query
+        .get("kind")
+        .map(|kinds| {
+            kinds
+                .split(',')
+                .map(str::trim)
+                .filter(|k| !k.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    axum::Json(serde_json::json!({
+        "events": crate::events::bus().since(since, &kinds),
+    }))
+    .into_response()
+}
+
+/// Default and cap for `?limit=` on `/api/v1/audit`.
+const AUDIT_LIMIT_DEFAULT: usize = 100;
+const AUDIT_LIMIT_MAX: usize = 500;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/audit",
+    tag = "telemetry",
+    params(
+        ("since" = Option<u64>, Query, description = "Only actions after this unix time in ms"),
+        ("action" = Option<String>, Query, description = "Comma-separated actions, e.g. switch_scene,relay_start"),
+        ("source" = Option<String>, Query, description = "Only this source, e.g. failover or rule:<name>"),
+        ("limit" = Option<usize>, Query, description = "Default 100, at most 500"),
+    ),
+    responses((status = 200, description = "`{ entries }`: recent actions and their outcomes, newest first", body = Object)),
+    security(("bearer" = ["telemetry_read"]))
+)]
+async fn get_audit(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    let since = match query.get("since").map(|s| s.parse::<u64>()) {
+        None => 0,
+        Some(Ok(since)) => since,
+        Some(Err(_)) => {
+            return ApiError::bad_request("since must be unix milliseconds").into_response()
+        }
+    };
+    let actions: Vec<String> = query
+        .get("action")
+        .map(|actions| {
+            actions
+                .split(',')
+                .map(str::trim)
+                .filter(|a| !a.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    let limit = query
+        .get("limit")
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(AUDIT_LIMIT_DEFAULT)
+        .min(AUDIT_LIMIT_MAX);
+    axum::Json(serde_json::json!({
+        "entries": crate::activity::log().query(
+            since,
+            &actions,
+            query.get("source").map(String::as_str),
+            limit,
+        ),
+    }))
+    .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/peers",
+    tag = "telemetry",
+    responses((status = 200, description = "Each `[[peers]]` instance's latest frame and their combined totals", body = Object)),
+    security(("bearer" = ["telemetry_read"]))
+)]
+async fn get_peers(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    let peers = state.peers.snapshot();
+    axum::Json(serde_json::json!({
+        "combined": crate::peers::Combined::of(&peers),
+        "peers": peers,
+    }))
+    .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/network/ingest-check",
+    tag = "network",
+    params(("target" = Option<String>, Query, description = "Ingest URL to probe instead of the configured one")),
+    responses(
+        (status = 200, body = crate::ingest::IngestCheck),
+        (status = 400, description = "Unparseable target", body = ErrorBody),
+    ),
+    security(("bearer" = ["telemetry_read"]))
+)]
+async fn get_ingest_check(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    match state.ingest.check(query.get("target").map(String::as_str)).await {
+        Ok(check) => axum::Json(check).into_response(),
+        Err(err) => ApiError::bad_request(err).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/settings/runtime",
+    tag = "settings",
+    responses((status = 200, body = SessionOverrides)),
+    security(("bearer" = ["telemetry_read"]))
+)]
+async fn get_runtime_settings(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    axum::Json(state.ipc_overrides.get()).into_response()
+}
+
+/// Sets dock overrides from outside the dock; fields left out keep their value.
+/// Connected plugins pick the change up with the next status snapshot.
+#[utoipa::path(
+    put,
+    path = "/api/v1/settings/runtime",
+    tag = "settings",
+    request_body = SessionOverrides,
+    responses((status = 200, body = SessionOverrides)),
+    security(("bearer" = ["settings_write"]))
+)]
+async fn put_runtime_settings(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+    Json(patch): Json<SessionOverrides>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::SettingsWrite,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    let updated = state.ipc_overrides.update(|o| {
+        o.merge(patch);
+        o.clone()
+    });
+    if let Some(mode) = &updated.mode {
+        state.ipc_debug_status.lock().unwrap().mode_override = Some(mode.as_str().to_string());
+    }
+    axum::Json(updated).into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/rules",
+    tag = "settings",
+    responses((status = 200, description = "Configured rules and their last evaluation", body = Object)),
+    security(("bearer" = ["telemetry_read"]))
+)]
+async fn get_rules(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    let limit = query
+        .0
+        .get("limit")
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(100);
+    axum::Json(serde_json::json!({
+        "rules": state.rules.status(),
+        "executions": state.rules.audit.recent(limit),
+    }))
+    .into_response()
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/obs/scenes",
+    tag = "obs",
+    responses((status = 200, description = "Scenes and their sources", body = Object), (status = 502, body = ErrorBody), (status = 503, body = ErrorBody)),
+    security(("bearer" = ["telemetry_read"]))
+)]
+async fn get_obs_scenes(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    let Some(client) = state.obs.client() else {
+        return ApiError::unavailable("obs is not connected").into_response();
+    };
+
+    match crate::metrics::collect_scene_inventory(&client).await {
+        Ok(inventory) => (StatusCode::OK, axum::Json(inventory)).into_response(),
+        Err(err) => ApiError::bad_gateway(format!("obs scene query failed: {err}")).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/reports",
+    tag = "telemetry",
+    responses((status = 200, description = "Stream reports, newest first", body = Object)),
+    security(("bearer" = ["telemetry_read"]))
+)]
+async fn get_reports(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Deny,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    let config = state.config.get();
+
+    let reports = crate::reports::ReportStore::from_config(&config.reports).list();
+    (
+        StatusCode::OK,
+        axum::Json(serde_json::json!({ "reports": reports })),
+    )
+        .into_response()
+}
+
+// Allows the query token so the HTML view can be opened directly in a browser.
+#[utoipa::path(
+    get,
+    path = "/api/v1/reports/{id}",
+    tag = "telemetry",
+    params(("id" = String, Path, description = "Report id; append `.html` for the rendered page")),
+    responses((status = 200, description = "Report JSON or HTML", body = Object), (status = 404, body = ErrorBody)),
+    security(("bearer" = ["telemetry_read"]))
+)]
+async fn get_report(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Allow,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    let config = state.config.get();
+
+    let store = crate::reports::ReportStore::from_config(&config.reports);
+    if query.0.get("format").map(String::as_str) == Some("html") {
+        return match store.load_html(&id) {
+            Some(html) => caching::with_etag(&headers, caching::DOCUMENT, caching::HTML, html),
+            None => ApiError::not_found("report not found").into_response(),
+        };
+    }
+    match store.load(&id) {
+        Some(report) => (StatusCode::OK, axum::Json(report)).into_response(),
+        None => ApiError::not_found("report not found").into_response(),
+    }
+}
+
+// Allows the query token so the export can be a plain download link.
+#[utoipa::path(
+    get,
+    path = "/api/v1/history/export.csv",
+    tag = "telemetry",
+    params(
+        ("from" = Option<u64>, Query, description = "First frame, unix seconds"),
+        ("to" = Option<u64>, Query, description = "Last frame, unix seconds"),
+        ("fields" = Option<String>, Query, description = "Comma-separated frame paths, e.g. system.cpu_percent,streams.0.fps")
+    ),
+    responses(
+        (status = 200, description = "Recorded frames as CSV, capped at recorder.export_max_rows", body = String, content_type = "text/csv"),
+        (status = 400, description = "Bad range or unknown field", body = ErrorBody),
+        (status = 404, description = "Nothing recorded yet", body = ErrorBody)
+    ),
+    security(("bearer" = ["telemetry_read"]))
+)]
+async fn get_history_csv(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    use crate::recorder::export::{parse_fields, CsvExport, DEFAULT_FIELDS};
+
+    if !state.authorize(
+        &headers,
+        &query.0,
+        QueryTokenPolicy::Allow,
+        Scope::TelemetryRead,
+    ) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    let mut range = [None, None];
+    for (bound, name) in range.iter_mut().zip(["from", "to"]) {
+        if let Some(value) = query.0.get(name).filter(|v| !v.is_empty()) {
+            match value.parse::<u64>() {
+                Ok(secs) => *bound = Some(secs),
+                Err(_) => {
+                    return ApiError::bad_request(format!("{name} must be unix seconds"))
+                        .into_response()
+                }
+            }
+        }
+    }
+    let [from, to] = range;
+    if let (Some(from), Some(to)) = (from, to) {
+        if from > to {
+            return ApiError::bad_request("from must not be after to").into_response();
+        }
+    }
+    let fields = match query.0.get("fields") {
+        Some(spec) => parse_fields(spec),
+        None => Ok(DEFAULT_FIELDS.iter().map(|f| f.to_string()).collect()),
+    };
+    let fields = match fields {
+        Ok(fields) => fields,
+        Err(err) => return ApiError::bad_request(err).into_response(),
+    };
+
+    let recorder = state.config.read(|c| c.recorder.clone());
+    let dir = crate::recorder::recorder_dir(&recorder);
+    if !dir.is_dir() {
+        return ApiError::not_found("no recordings; enable [recorder] first").into_response();
+    }
+    let export = CsvExport {
+        fields,
+        from,
+        to,
+        max_rows: recorder.export_max_rows,
+    };
+
+    let (tx, rx) = tokio::sync::mpsc::channel::<std::io::Result<String>>(4);
+    tokio::task::spawn_blocking(move || {
+        let sent = export.write(&dir, |chunk| tx.blocking_send(Ok(chunk)).is_ok());
+        match sent {
+            Ok(summary) if summary.truncated => tracing::info!(
+                rows = summary.rows,
+                "history export stopped at recorder.export_max_rows"
+            ),
+            Ok(_) => {}
+            Err(err) => {
+                let _ = tx.blocking_send(Err(err));
+            }
+        }
+    });
+    let body = futures_util::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (chunk, rx))
+    });
+
+    let file_name = match (from, to) {
+        (Some(from), Some(to)) => format!("telemy-history-{from}-{to}.csv"),
+        _ => "telemy-history.csv".to_string(),
+    };
+    let mut response = (
+        [(header::CONTENT_TYPE, "text/csv; charset=utf-8")],
+        axum::body::Body::from_stream(body),
+    )
+        .into_response();
+    if let Ok(value) =
+        header::HeaderValue::from_str(&format!("attachment; filename=\"{file_name}\""))
+    {
+        response
+            .headers_mut()
+            .insert(header::CONTENT_DISPOSITION, value);
+    }
+    response
+}
+
+#[derive(Deserialize, ToSchema)]
+struct IssueTokenRequest {
+    name: String,
+    scopes: Vec<Scope>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/tokens",
+    tag = "tokens",
+    responses((status = 200, description = "`{ tokens }` without the secrets", body = Object)),
+    security(("bearer" = ["admin"]))
+)]
+async fn get_tokens(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(&headers, &query.0, QueryTokenPolicy::Deny, Scope::Admin) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    (
+        StatusCode::OK,
+        axum::Json(serde_json::json!({ "tokens": state.tokens.list() })),
+    )
+        .into_response()
+}
+
+// The token value is only returned here; listings never include it.
+#[utoipa::path(
+    post,
+    path = "/api/v1/tokens",
+    tag = "tokens",
+    request_body = IssueTokenRequest,
+    responses((status = 201, description = "The new token; its secret is only shown here", body = ApiToken), (status = 400, body = ErrorBody)),
+    security(("bearer" = ["admin"]))
+)]
+async fn post_token(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+    Json(body): Json<IssueTokenRequest>,
+) -> impl IntoResponse {
+    if !state.authorize(&headers, &query.0, QueryTokenPolicy::Deny, Scope::Admin) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    match state.tokens.issue(&body.name, body.scopes).await {
+        Ok(token) => (StatusCode::CREATED, axum::Json(token)).into_response(),
+        Err(err) => ApiError::bad_request(err).into_response(),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/tokens/{id}",
+    tag = "tokens",
+    params(("id" = String, Path, description = "Token id")),
+    responses((status = 204, description = "Revoked"), (status = 404, body = ErrorBody)),
+    security(("bearer" = ["admin"]))
+)]
+async fn delete_token(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(&headers, &query.0, QueryTokenPolicy::Deny, Scope::Admin) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    match state.tokens.revoke(&id).await {
+        Ok(true) => StatusCode::NO_CONTENT.into_response(),
+        Ok(false) => ApiError::not_found("token not found").into_response(),
+        Err(err) => ApiError::internal(format!("token revoke failed: {err}")).into_response(),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/security/audit",
+    tag = "tokens",
+    params(("limit" = Option<usize>, Query, description = "Default 100")),
+    responses((status = 200, description = "`{ failures }`: recent 401s by client", body = Object)),
+    security(("bearer" = ["admin"]))
+)]
+async fn get_security_audit(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(&headers, &query.0, QueryTokenPolicy::Deny, Scope::Admin) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    let limit = query
+        .0
+        .get("limit")
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(100);
+    (
+        StatusCode::OK,
+        axum::Json(serde_json::json!({ "failures": state.audit.recent(limit) })),
+    )
+        .into_response()
+}
+
+/// Default and cap for `?tail=` on `/api/v1/logs`.
+const LOG_TAIL_DEFAULT: usize = 500;
+const LOG_TAIL_MAX: usize = 5000;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/logs",
+    tag = "system",
+    params(("tail" = Option<usize>, Query, description = "Lines to return, default 500, at most 5000")),
+    responses((status = 200, description = "`{ dir, file, lines }` from the newest log file", body = Object), (status = 500, body = ErrorBody)),
+    security(("bearer" = ["admin"]))
+)]
+async fn get_logs(
+    State(state): State<Arc<ServerState>>,
+    headers: HeaderMap,
+    query: Query<HashMap<String, String>>,
+) -> impl IntoResponse {
+    if !state.authorize(&headers, &query.0, QueryTokenPolicy::Deny, Scope::Admin) {
+        return ApiError::unauthorized().into_response();
+    }
+
+    let tail = query
+        .0
+        .get("tail")
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(LOG_TAIL_DEFAULT)
+        .clamp(1, LOG_TAIL_MAX);
+    let dir = crate::logging::log_dir(&state.config.read(|c| c.logging.clone()));
+    let (file, lines) = match crate::logging::tail(&dir, tail) {
+        Ok(Some((file, lines))) => (Some(file), lines),
+        Ok(None) => (None, Vec::new()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => (None, Vec::new()),
+        Err(err) => {
+            return ApiError::internal(err.to_string()).into_response();
+        }
+    };
+    (
+        StatusCode::OK,
+        axum::Json(serde_json::json!({ "dir": dir, "file": file, "lines": lines })),
+    )
+        .into_response()
+}
+
 fn build_aegis_client_from_config(
     config: &Config,
     vault: &Vault,
@@ -1792,7 +3041,10 @@ fn build_aegis_client_from_config(
         return Err("missing aegis.access_jwt_key in config".into());
     }
     let access_jwt = vault.retrieve(jwt_key)?;
-    Ok(ControlPlaneClient::new(base_url, access_jwt.trim())?)
+    Ok(ControlPlaneClient::new(
+        base_url,
+        access_jwt.expose().trim(),
+    )?)
 }
 
 fn generate_idempotency_key() -> String {
@@ -1810,9 +3062,16 @@ fn generate_idempotency_key() -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::{is_token_valid, QueryTokenPolicy};
-    use axum::http::{HeaderMap, HeaderValue};
+    use super::{
+        enforce_rate_limit, is_token_valid, session_id, ClientIp, QueryTokenPolicy,
+        RateLimitConfig, RateLimiter, Scope, TokenStore,
+    };
+    use axum::http::{HeaderMap, HeaderValue, StatusCode};
+    use axum::routing::{get, post};
+    use axum::{middleware, Extension, Router};
     use std::collections::HashMap;
+    use std::net::IpAddr;
+    use std::sync::Arc;
 
     #[test]
     fn token_valid_accepts_bearer_header_when_query_denied() {
@@ -1823,7 +3082,14 @@ mod tests {
         );
         let query = HashMap::from([("token".to_string(), "wrong-token".to_string())]);
 
-        let ok = is_token_valid(&headers, &query, "test-token", QueryTokenPolicy::Deny);
+        let tokens = TokenStore::in_memory("test-token");
+        let ok = is_token_valid(
+            &headers,
+            &query,
+            &tokens,
+            QueryTokenPolicy::Deny,
+            Scope::TelemetryRead,
+        );
         assert!(ok);
     }
 
@@ -1832,7 +3098,14 @@ mod tests {
         let headers = HeaderMap::new();
         let query = HashMap::from([("token".to_string(), "test-token".to_string())]);
 
-        let ok = is_token_valid(&headers, &query, "test-token", QueryTokenPolicy::Deny);
+        let tokens = TokenStore::in_memory("test-token");
+        let ok = is_token_valid(
+            &headers,
+            &query,
+            &tokens,
+            QueryTokenPolicy::Deny,
+            Scope::TelemetryRead,
+        );
         assert!(!ok);
     }
 
@@ -1841,7 +3114,109 @@ mod tests {
         let headers = HeaderMap::new();
         let query = HashMap::from([("token".to_string(), "test-token".to_string())]);
 
-        let ok = is_token_valid(&headers, &query, "test-token", QueryTokenPolicy::Allow);
+        let tokens = TokenStore::in_memory("test-token");
+        let ok = is_token_valid(
+            &headers,
+            &query,
+            &tokens,
+            QueryTokenPolicy::Allow,
+            Scope::TelemetryRead,
+        );
         assert!(ok);
     }
+
+    #[tokio::test]
+    async fn token_valid_enforces_scope_for_issued_tokens() {
+        let tokens = TokenStore::in_memory("test-token");
+        let overlay = tokens
+            .issue("overlay", vec![Scope::TelemetryRead])
+            .await
+            .unwrap();
+        let headers = HeaderMap::new();
+        let query = HashMap::from([("token".to_string(), overlay.token)]);
+
+        let read = is_token_valid(
+            &headers,
+            &query,
+            &tokens,
+            QueryTokenPolicy::Allow,
+            Scope::TelemetryRead,
+        );
+        let write = is_token_valid(
+            &headers,
+            &query,
+            &tokens,
+            QueryTokenPolicy::Allow,
+            Scope::SettingsWrite,
+        );
+        assert!(read);
+        assert!(!write);
+    }
+
+    #[tokio::test]
+    async fn a_locked_out_client_still_reads_frames_with_a_valid_token() {
+        let limiter = Arc::new(RateLimiter::new(RateLimitConfig {
+            enabled: true,
+            requests_per_minute: 30,
+            max_failures: 2,
+            failure_window_secs: 60,
+            lockout_secs: 300,
+        }));
+        let tokens = Arc::new(TokenStore::in_memory("test-token"));
+        let authed = move |headers: HeaderMap| {
+            let tokens = tokens.clone();
+            async move {
+                let query = HashMap::new();
+                if is_token_valid(
+                    &headers,
+                    &query,
+                    &tokens,
+                    QueryTokenPolicy::Deny,
+                    Scope::TelemetryRead,
+                ) {
+                    StatusCode::OK
+                } else {
+                    StatusCode::UNAUTHORIZED
+                }
+            }
+        };
+        // Every local client shares the loopback address.
+        let app = Router::new()
+            .route("/api/v1/frame", get(authed.clone()))
+            .route("/login", post(authed))
+            .layer(middleware::from_fn_with_state(limiter, enforce_rate_limit))
+            .layer(Extension(ClientIp(IpAddr::from([127, 0, 0, 1]))));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let client = reqwest::Client::new();
+        let frame = |token: &str| {
+            client
+                .get(format!("http://{addr}/api/v1/frame"))
+                .bearer_auth(token)
+                .send()
+        };
+        for _ in 0..2 {
+            assert_eq!(frame("revoked-token").await.unwrap().status().as_u16(), 401);
+        }
+        let login = client
+            .post(format!("http://{addr}/login"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(login.status().as_u16(), 429, "sensitive routes are locked");
+        assert_eq!(frame("test-token").await.unwrap().status().as_u16(), 200);
+    }
+
+    #[test]
+    fn session_id_reads_telemy_cookie_among_others() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "cookie",
+            HeaderValue::from_static("theme=dark; telemy_session=abc123; other=1"),
+        );
+        assert_eq!(session_id(&headers), Some("abc123"));
+        assert_eq!(session_id(&HeaderMap::new()), None);
+    }
 }
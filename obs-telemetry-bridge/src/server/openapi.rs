@@ -0,0 +1,187 @@
+use super::*;
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
+use utoipa::openapi::{ContentBuilder, Ref, ResponseBuilder};
+use utoipa::{Modify, OpenApi};
+
+/// The HTTP API as served at `/api/openapi.json`. Security requirements list
+/// the Telemy token scope each route needs; HTML pages are left out.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Telemy", description = "Local API of the OBS telemetry bridge."),
+    paths(
+        health_check,
+        ws_handler,
+        settings_submit,
+        get_output_names,
+        save_output_names,
+        grafana_dashboard_download,
+        grafana_dashboard_import,
+        post_grafana_test,
+        get_aegis_status,
+        post_aegis_start,
+        post_aegis_stop,
+        get_aegis_login,
+        post_aegis_login,
+        get_ipc_status,
+        post_ipc_switch_scene,
+        get_obs_scenes,
+        post_obs_control,
+        get_scene_history,
+        get_obs_log_events,
+        get_ingest_check,
+        get_events,
+        get_audit,
+        get_peers,
+        get_rules,
+        get_runtime_settings,
+        put_runtime_settings,
+        get_reports,
+        get_report,
+        get_history_csv,
+        get_tokens,
+        post_token,
+        delete_token,
+        get_security_audit,
+        get_logs,
+        get_selfstats,
+        get_ws_clients,
+        get_pause,
+        put_pause,
+        get_status,
+        post_shutdown,
+        get_prometheus_metrics,
+    ),
+    components(schemas(
+        ErrorBody,
+        SettingsForm,
+        GrafanaImportForm,
+        grafana::Imported,
+        grafana::Dashboard,
+        GrafanaTestForm,
+        grafana::Verified,
+        AegisStartBody,
+        AegisStopBody,
+        AegisActionResponse,
+        AegisLoginForm,
+        AegisLoginStatus,
+        IpcSwitchSceneRequest,
+        IpcSwitchSceneResponse,
+        ObsControlRequest,
+        ObsControlAction,
+        IssueTokenRequest,
+        auth::ApiToken,
+        Scope,
+        SessionOverrides,
+        PauseStatus,
+        StatusResponse,
+        ObsStatus,
+        AegisBrief,
+        crate::model::ObsConnectionState,
+        crate::obslog::LogEvent,
+        crate::obslog::LogEventKind,
+        crate::activity::Activity,
+        crate::activity::ActionKind,
+        crate::ingest::IngestCheck,
+        crate::ingest::Protocol,
+    )),
+    modifiers(&Auth),
+    tags(
+        (name = "system", description = "Process status, pause, logs and shutdown"),
+        (name = "telemetry", description = "Live frames, reports and metrics"),
+        (name = "settings", description = "Configuration and rules"),
+        (name = "obs", description = "OBS scenes and output controls"),
+        (name = "network", description = "Ingest reachability"),
+        (name = "ipc", description = "OBS plugin link"),
+        (name = "aegis", description = "Aegis relay sessions and sign-in"),
+        (name = "tokens", description = "Scoped API tokens and auth audit"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Registers the bearer scheme and adds the shared `401` to every route that
+/// needs a token.
+struct Auth;
+
+impl Modify for Auth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        openapi
+            .components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+            );
+        let unauthorized = ResponseBuilder::new()
+            .description("Missing token, or a token without the required scope")
+            .content(
+                "application/json",
+                ContentBuilder::new()
+                    .schema(Ref::from_schema_name("ErrorBody"))
+                    .build(),
+            )
+            .build();
+        for item in openapi.paths.paths.values_mut() {
+            for operation in item.operations.values_mut() {
+                let public = operation
+                    .security
+                    .as_ref()
+                    .is_some_and(|reqs| reqs.iter().all(|req| *req == Default::default()));
+                if !public {
+                    operation
+                        .responses
+                        .responses
+                        .entry("401".to_string())
+                        .or_insert_with(|| unauthorized.clone().into());
+                }
+            }
+        }
+    }
+}
+
+/// `GET /api/openapi.json`
+pub async fn openapi_json(headers: HeaderMap) -> impl IntoResponse {
+    let doc = serde_json::to_vec(&ApiDoc::openapi()).unwrap_or_default();
+    caching::with_etag(&headers, caching::DOCUMENT, "application/json", doc)
+}
+
+/// `GET /api/docs`: Swagger UI from a CDN, pointed at the local document.
+/// Use "Authorize" with a token to try routes out.
+pub async fn docs_page(headers: HeaderMap) -> impl IntoResponse {
+    caching::with_etag(
+        &headers,
+        caching::DOCUMENT,
+        caching::HTML,
+        r##"<!doctype html>
+<html>
+<head>
+  <meta charset="utf-8" />
+  <title>Telemy API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css" />
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    SwaggerUIBundle({ url: "/api/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>"##,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn document_covers_the_routes_and_marks_public_ones() {
+        let doc = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let paths = doc["paths"].as_object().unwrap();
+        assert!(paths.contains_key("/api/v1/status"));
+        assert!(paths.contains_key("/api/v1/reports/{id}"));
+        assert!(paths["/api/v1/pause"]["put"]["requestBody"].is_object());
+        assert!(paths["/api/v1/status"]["get"]["responses"]["401"].is_object());
+        assert!(paths["/health"]["get"]["responses"]["401"].is_null());
+        assert!(doc["components"]["schemas"]["ErrorBody"].is_object());
+    }
+}
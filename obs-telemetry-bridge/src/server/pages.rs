@@ -0,0 +1,146 @@
+use super::i18n::Bundle;
+use crate::config::DashboardConfig;
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+/// An HTML page served from a template. Each is embedded in the binary and can
+/// be replaced by a file of the same name in the templates directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Page {
+    /// `/obs`. Placeholders: `THEME_VARS`, `TOKEN`, `LAYOUT`.
+    Dashboard,
+    /// `/dock`. Placeholders: `THEME_VARS`.
+    Dock,
+    /// `/settings`. Placeholders: `THEME_VARS`, `TOKEN`, the current values of
+    /// the form fields, and `THEME`/`THEME_PRESETS` as JSON for the theme editor.
+    Settings,
+}
+
+impl Page {
+    pub const ALL: [Page; 3] = [Page::Dashboard, Page::Dock, Page::Settings];
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Page::Dashboard => "dashboard.html",
+            Page::Dock => "dock.html",
+            Page::Settings => "settings.html",
+        }
+    }
+
+    pub fn embedded(self) -> &'static str {
+        match self {
+            Page::Dashboard => include_str!("../../assets/pages/dashboard.html"),
+            Page::Dock => include_str!("../../assets/pages/dock.html"),
+            Page::Settings => include_str!("../../assets/pages/settings.html"),
+        }
+    }
+}
+
+/// `dashboard.templates_dir`, else `templates` under the data dir.
+pub fn templates_dir(config: &DashboardConfig) -> PathBuf {
+    if let Some(dir) = &config.templates_dir {
+        return PathBuf::from(dir);
+    }
+    let base = std::env::var("APPDATA").unwrap_or_else(|_| ".".to_string());
+    Path::new(&base).join("Telemy").join("templates")
+}
+
+/// The override in `dir` when there is one, else the embedded template. Read
+/// on every request so edits show up on reload.
+pub fn template(dir: &Path, page: Page) -> Cow<'static, str> {
+    let path = dir.join(page.file_name());
+    match std::fs::read_to_string(&path) {
+        Ok(html) => Cow::Owned(html),
+        Err(err) => {
+            if err.kind() != std::io::ErrorKind::NotFound {
+                tracing::warn!(path = %path.display(), error = %err, "ignoring page template");
+            }
+            Cow::Borrowed(page.embedded())
+        }
+    }
+}
+
+/// [`template`] with its `{{t:key}}` strings in the configured language. Every
+/// page also gets `LANG` and `I18N`, the bundle as JSON for its script.
+pub fn localized(dir: &Path, page: Page, bundle: &Bundle) -> String {
+    bundle.localize(&template(dir, page))
+}
+
+/// Fills `{{NAME}}` placeholders in `template`. Values are inserted verbatim, so
+/// callers escape anything user-controlled.
+pub fn render(template: &str, vars: &[(&str, &str)]) -> String {
+    vars.iter()
+        .fold(template.to_string(), |html, (name, value)| {
+            html.replace(&format!("{{{{{name}}}}}"), value)
+        })
+}
+
+/// `value` as JSON for a `<script>` block; `<` is escaped so config values can
+/// never close the tag.
+pub fn script_json(value: &impl serde::Serialize) -> String {
+    serde_json::to_string(value)
+        .unwrap_or_else(|_| "null".to_string())
+        .replace('<', "\\u003c")
+}
+
+/// Writes the embedded templates into `dir` as a starting point for overrides.
+/// Existing files are kept unless `force`. Returns the files written.
+pub fn export_templates(dir: &Path, force: bool) -> std::io::Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    let mut written = Vec::new();
+    for page in Page::ALL {
+        let path = dir.join(page.file_name());
+        if path.exists() && !force {
+            continue;
+        }
+        std::fs::write(&path, page.embedded())?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_fills_every_placeholder() {
+        let html = render(
+            "<a href=\"/settings?token={{TOKEN}}\">{{TOKEN}}</a>{{OTHER}}",
+            &[("TOKEN", "abc")],
+        );
+        assert_eq!(html, "<a href=\"/settings?token=abc\">abc</a>{{OTHER}}");
+    }
+
+    #[test]
+    fn script_json_cannot_close_the_script_tag() {
+        assert_eq!(
+            script_json(&serde_json::json!({ "a": "</script>" })),
+            r#"{"a":"\u003c/script>"}"#
+        );
+    }
+
+    #[test]
+    fn templates_carry_the_theme_placeholder() {
+        for page in Page::ALL {
+            assert!(page.embedded().contains("{{THEME_VARS}}"), "{page:?}");
+        }
+        assert!(Page::Dashboard.embedded().contains("{{LAYOUT}}"));
+    }
+
+    #[test]
+    fn overrides_replace_the_embedded_page() {
+        let dir = std::env::temp_dir().join(format!("telemy-templates-{}", uuid::Uuid::new_v4()));
+        assert_eq!(template(&dir, Page::Dock), Page::Dock.embedded());
+
+        assert_eq!(
+            export_templates(&dir, false).unwrap().len(),
+            Page::ALL.len()
+        );
+        std::fs::write(dir.join("dock.html"), "<p>{{THEME_VARS}}</p>").unwrap();
+        assert!(export_templates(&dir, false).unwrap().is_empty());
+        assert_eq!(template(&dir, Page::Dock), "<p>{{THEME_VARS}}</p>");
+        assert_eq!(template(&dir, Page::Settings), Page::Settings.embedded());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,193 @@
+use crate::config::RateLimitConfig;
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+const PRUNE_THRESHOLD: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Allow,
+    /// Over `requests_per_minute` on a sensitive route.
+    Limited {
+        retry_after_secs: u64,
+    },
+    /// Too many recent 401s; sensitive routes are refused until the lockout
+    /// expires. Everything else stays reachable, since every local client
+    /// (dashboard, dock, overlays) shares the loopback address and one stale
+    /// token must not lock the others out mid-stream.
+    LockedOut {
+        retry_after_secs: u64,
+    },
+}
+
+#[derive(Default)]
+struct ClientState {
+    window_start: Option<Instant>,
+    requests: u32,
+    failures: VecDeque<Instant>,
+    locked_until: Option<Instant>,
+}
+
+impl ClientState {
+    fn is_idle(&self, now: Instant, failure_window: Duration) -> bool {
+        self.locked_until.is_none_or(|until| until <= now)
+            && self
+                .window_start
+                .is_none_or(|start| now.duration_since(start) >= RATE_WINDOW)
+            && self
+                .failures
+                .back()
+                .is_none_or(|last| now.duration_since(*last) >= failure_window)
+    }
+}
+
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    clients: Mutex<HashMap<IpAddr, ClientState>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn check(&self, ip: IpAddr, sensitive: bool, now: Instant) -> Decision {
+        if !self.config.enabled || !sensitive {
+            return Decision::Allow;
+        }
+        let failure_window = Duration::from_secs(self.config.failure_window_secs);
+        let mut clients = self.clients.lock().unwrap();
+        if clients.len() > PRUNE_THRESHOLD {
+            clients.retain(|_, c| !c.is_idle(now, failure_window));
+        }
+        let client = clients.entry(ip).or_default();
+
+        if let Some(until) = client.locked_until {
+            if until > now {
+                return Decision::LockedOut {
+                    retry_after_secs: secs_until(until, now),
+                };
+            }
+            client.locked_until = None;
+        }
+
+        let window_start = match client.window_start {
+            Some(start) if now.duration_since(start) < RATE_WINDOW => start,
+            _ => {
+                client.window_start = Some(now);
+                client.requests = 0;
+                now
+            }
+        };
+        if client.requests >= self.config.requests_per_minute {
+            return Decision::Limited {
+                retry_after_secs: secs_until(window_start + RATE_WINDOW, now),
+            };
+        }
+        client.requests += 1;
+        Decision::Allow
+    }
+
+    pub fn record_failure(&self, ip: IpAddr, now: Instant) {
+        if !self.config.enabled {
+            return;
+        }
+        let failure_window = Duration::from_secs(self.config.failure_window_secs);
+        let mut clients = self.clients.lock().unwrap();
+        let client = clients.entry(ip).or_default();
+        while client
+            .failures
+            .front()
+            .is_some_and(|first| now.duration_since(*first) >= failure_window)
+        {
+            client.failures.pop_front();
+        }
+        client.failures.push_back(now);
+        if client.failures.len() as u32 >= self.config.max_failures {
+            client.failures.clear();
+            client.locked_until = Some(now + Duration::from_secs(self.config.lockout_secs));
+            tracing::warn!(
+                %ip,
+                lockout_secs = self.config.lockout_secs,
+                "locking out client after repeated auth failures"
+            );
+        }
+    }
+}
+
+/// Routes where a leaked or guessed token does real damage, or where the token is
+/// being tried directly (login). Polled dashboard reads stay unthrottled. Legacy
+/// aliases are passed as their `/api/v1` successor.
+pub fn is_sensitive(method: &axum::http::Method, path: &str) -> bool {
+    let mutating = method != axum::http::Method::GET;
+    ((path == "/login" || path == "/api/v1/settings") && mutating)
+        || path.starts_with("/api/v1/tokens")
+        || path == "/api/v1/security/audit"
+        || path == "/api/v1/aegis/start"
+        || path == "/api/v1/aegis/stop"
+        || path == "/api/v1/grafana-dashboard/import"
+        || path == "/api/v1/settings/grafana/test"
+}
+
+fn secs_until(deadline: Instant, now: Instant) -> u64 {
+    deadline.saturating_duration_since(now).as_secs().max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            enabled: true,
+            requests_per_minute: 2,
+            max_failures: 3,
+            failure_window_secs: 60,
+            lockout_secs: 120,
+        })
+    }
+
+    #[test]
+    fn limits_sensitive_routes_per_minute() {
+        let limiter = limiter();
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let now = Instant::now();
+        assert_eq!(limiter.check(ip, true, now), Decision::Allow);
+        assert_eq!(limiter.check(ip, true, now), Decision::Allow);
+        assert!(matches!(
+            limiter.check(ip, true, now),
+            Decision::Limited { .. }
+        ));
+        assert_eq!(limiter.check(ip, false, now), Decision::Allow);
+        assert_eq!(limiter.check(ip, true, now + RATE_WINDOW), Decision::Allow);
+    }
+
+    #[test]
+    fn locks_out_after_repeated_failures() {
+        let limiter = limiter();
+        let ip: IpAddr = "10.0.0.2".parse().unwrap();
+        let other: IpAddr = "10.0.0.3".parse().unwrap();
+        let now = Instant::now();
+        for _ in 0..3 {
+            limiter.record_failure(ip, now);
+        }
+        assert_eq!(
+            limiter.check(ip, true, now),
+            Decision::LockedOut {
+                retry_after_secs: 120
+            }
+        );
+        assert_eq!(limiter.check(ip, false, now), Decision::Allow);
+        assert_eq!(limiter.check(other, true, now), Decision::Allow);
+        assert_eq!(
+            limiter.check(ip, true, now + Duration::from_secs(121)),
+            Decision::Allow
+        );
+    }
+}
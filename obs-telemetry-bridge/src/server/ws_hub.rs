@@ -0,0 +1,548 @@
+use crate::aegis::RelaySessionHandle;
+use crate::ipc::IpcDebugStatusHandle;
+use crate::model::{
+    AudioFrame, HealthTrend, NetworkFrame, ObsFrame, RelayFrame, StreamOutput, StreamStats,
+    SystemFrame, TelemetryFrame,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{broadcast, watch};
+
+const BROADCAST_CAPACITY: usize = 8;
+/// Fastest `max_rate_hz` a client may ask for.
+const MAX_RATE_HZ: f64 = 20.0;
+
+/// What a `/ws` client can subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Channel {
+    Frames,
+    Events,
+    /// The plugin link, as at `/api/v1/ipc/status`, when it changes.
+    IpcStatus,
+    /// The Aegis relay session, when it changes.
+    Aegis,
+}
+
+/// Subprotocol that selects MessagePack, as an alternative to `?format=msgpack`.
+pub const MSGPACK_PROTOCOL: &str = "telemy.msgpack";
+
+/// How messages are encoded for one client: JSON text or MessagePack binary
+/// messages with the same field names.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WsFormat {
+    #[default]
+    Json,
+    Msgpack,
+}
+
+impl WsFormat {
+    pub fn detect(format: Option<&str>, protocols: Option<&str>) -> Self {
+        let requested =
+            protocols.is_some_and(|p| p.split(',').any(|p| p.trim() == MSGPACK_PROTOCOL));
+        if format == Some("msgpack") || requested {
+            Self::Msgpack
+        } else {
+            Self::Json
+        }
+    }
+}
+
+/// A payload serialized once and shared by every client. The MessagePack
+/// copy is only made while a MessagePack client is connected.
+#[derive(Debug, Clone)]
+pub struct WsMessage {
+    pub channel: Channel,
+    pub payload: Arc<String>,
+    pub msgpack: Option<Arc<Vec<u8>>>,
+}
+
+impl WsMessage {
+    /// The MessagePack bytes, re-encoded from the JSON when the message was
+    /// made before the first MessagePack client joined.
+    pub fn packed(&self) -> Vec<u8> {
+        match &self.msgpack {
+            Some(packed) => packed.as_ref().clone(),
+            None => serde_json::from_str::<serde_json::Value>(&self.payload)
+                .ok()
+                .and_then(|value| rmp_serde::to_vec_named(&value).ok())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn encode<T: Serialize>(channel: Channel, value: &T, msgpack: bool) -> Self {
+        Self {
+            channel,
+            payload: Arc::new(serde_json::to_string(value).unwrap_or_default()),
+            msgpack: msgpack
+                .then(|| rmp_serde::to_vec_named(value).ok())
+                .flatten()
+                .map(Arc::new),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SubscribeRequest {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(flatten)]
+    subscription: Subscription,
+}
+
+/// A client's `{"type": "subscribe", "channels": [...], "max_rate_hz": 2}`.
+/// Until one arrives clients get frames and events.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Subscription {
+    pub channels: Vec<Channel>,
+    /// Caps frame, IPC and Aegis updates; events are never held back.
+    #[serde(default)]
+    pub max_rate_hz: Option<f64>,
+}
+
+impl Default for Subscription {
+    fn default() -> Self {
+        Self {
+            channels: vec![Channel::Frames, Channel::Events],
+            max_rate_hz: None,
+        }
+    }
+}
+
+impl Subscription {
+    /// Reads a client message; anything but a valid `subscribe` is an error.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        Self::validate(serde_json::from_str(text).map_err(|err| format!("invalid message: {err}"))?)
+    }
+
+    /// [`Subscription::parse`] for a MessagePack client's binary message.
+    pub fn parse_msgpack(bytes: &[u8]) -> Result<Self, String> {
+        Self::validate(
+            rmp_serde::from_slice(bytes).map_err(|err| format!("invalid message: {err}"))?,
+        )
+    }
+
+    fn validate(request: SubscribeRequest) -> Result<Self, String> {
+        if request.kind != "subscribe" {
+            return Err(format!("unknown message type {}", request.kind));
+        }
+        let mut subscription = request.subscription;
+        if let Some(rate) = subscription.max_rate_hz {
+            if !rate.is_finite() || rate <= 0.0 {
+                return Err("max_rate_hz must be positive".to_string());
+            }
+            subscription.max_rate_hz = Some(rate.min(MAX_RATE_HZ));
+        }
+        subscription.channels.sort();
+        subscription.channels.dedup();
+        Ok(subscription)
+    }
+}
+
+/// Decides which broadcast messages go to one client.
+#[derive(Debug, Default)]
+pub struct ClientFilter {
+    subscription: Subscription,
+    last_sent: BTreeMap<Channel, Instant>,
+}
+
+impl ClientFilter {
+    pub fn subscribe(&mut self, subscription: Subscription) {
+        self.subscription = subscription;
+        self.last_sent.clear();
+    }
+
+    pub fn admit(&mut self, channel: Channel, now: Instant) -> bool {
+        if !self.subscription.channels.contains(&channel) {
+            return false;
+        }
+        if channel != Channel::Events {
+            if let Some(rate) = self.subscription.max_rate_hz {
+                let gap = Duration::from_secs_f64(1.0 / rate);
+                if self
+                    .last_sent
+                    .get(&channel)
+                    .is_some_and(|at| now.duration_since(*at) < gap)
+                {
+                    return false;
+                }
+            }
+        }
+        self.last_sent.insert(channel, now);
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientKind {
+    Dashboard,
+    Dock,
+    /// OBS browser sources (and anything else identifying as OBS's CEF).
+    Overlay,
+    /// Another Telemy instance following this one (`[[peers]]`).
+    Hub,
+    Other,
+}
+
+impl ClientKind {
+    /// Our own pages announce themselves with `?client=`; otherwise OBS browser
+    /// sources are recognised by the `OBS/<version>` token in their user agent.
+    pub fn detect(client: Option<&str>, user_agent: Option<&str>) -> Self {
+        match client {
+            Some("dashboard") => Self::Dashboard,
+            Some("dock") => Self::Dock,
+            Some("overlay") => Self::Overlay,
+            Some("hub") => Self::Hub,
+            _ if user_agent.is_some_and(|ua| ua.contains("OBS/")) => Self::Overlay,
+            _ => Self::Other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ClientInfo {
+    pub id: u64,
+    pub remote_addr: String,
+    pub kind: ClientKind,
+    pub user_agent: Option<String>,
+    pub connected_unix: u64,
+    pub channels: Vec<Channel>,
+    pub format: WsFormat,
+}
+
+/// Serializes the latest frame once per tick and fans it out, along with
+/// every event and IPC/Aegis status changes, to each `/ws` client over a
+/// broadcast channel, keeping a registry of who is connected. Clients drop
+/// what they did not subscribe to.
+pub struct WsHub {
+    tx: broadcast::Sender<WsMessage>,
+    clients: Mutex<HashMap<u64, ClientInfo>>,
+    next_id: AtomicU64,
+    msgpack_clients: Arc<AtomicUsize>,
+}
+
+impl WsHub {
+    pub fn spawn(
+        mut rx: watch::Receiver<TelemetryFrame>,
+        ipc_status: IpcDebugStatusHandle,
+        aegis_session: RelaySessionHandle,
+        interval: Duration,
+    ) -> Arc<Self> {
+        let (tx, _) = broadcast::channel(BROADCAST_CAPACITY);
+        let hub = Arc::new(Self {
+            tx,
+            clients: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(1),
+            msgpack_clients: Arc::new(AtomicUsize::new(0)),
+        });
+
+        let tx = hub.tx.clone();
+        let msgpack_clients = hub.msgpack_clients.clone();
+        let mut events = crate::events::bus().subscribe();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => {
+                        let msgpack = msgpack_clients.load(Ordering::Relaxed) > 0;
+                        let payload = serde_json::json!({ "type": "event", "event": event });
+                        let _ = tx.send(WsMessage::encode(Channel::Events, &payload, msgpack));
+                    }
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return,
+                }
+            }
+        });
+
+        let tx = hub.tx.clone();
+        let msgpack_clients = hub.msgpack_clients.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let mut last_ipc = Arc::default();
+            let mut last_aegis = Arc::default();
+            loop {
+                ticker.tick().await;
+                if rx.has_changed().is_err() {
+                    return;
+                }
+                if tx.receiver_count() == 0 {
+                    continue;
+                }
+                let msgpack = msgpack_clients.load(Ordering::Relaxed) > 0;
+                let frame = WsMessage::encode(
+                    Channel::Frames,
+                    &FramePayload::new(&rx.borrow_and_update()),
+                    msgpack,
+                );
+                let _ = tx.send(frame);
+
+                let ipc = serde_json::json!({
+                    "type": "ipc_status",
+                    "ipc_status": *ipc_status.lock().unwrap(),
+                });
+                let ipc = WsMessage::encode(Channel::IpcStatus, &ipc, msgpack);
+                if ipc.payload != last_ipc {
+                    last_ipc = ipc.payload.clone();
+                    let _ = tx.send(ipc);
+                }
+                let aegis = serde_json::json!({
+                    "type": "aegis",
+                    "session": *aegis_session.read().await,
+                });
+                let aegis = WsMessage::encode(Channel::Aegis, &aegis, msgpack);
+                if aegis.payload != last_aegis {
+                    last_aegis = aegis.payload.clone();
+                    let _ = tx.send(aegis);
+                }
+            }
+        });
+        hub
+    }
+
+    /// Registers a client; it stays listed until the returned handle is dropped.
+    pub fn join(
+        self: &Arc<Self>,
+        remote_addr: SocketAddr,
+        kind: ClientKind,
+        user_agent: Option<String>,
+        format: WsFormat,
+    ) -> WsClient {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        if format == WsFormat::Msgpack {
+            self.msgpack_clients.fetch_add(1, Ordering::Relaxed);
+        }
+        let info = ClientInfo {
+            id,
+            remote_addr: remote_addr.to_string(),
+            kind,
+            user_agent,
+            connected_unix: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            channels: Subscription::default().channels,
+            format,
+        };
+        self.clients.lock().unwrap().insert(id, info);
+        WsClient {
+            hub: self.clone(),
+            id,
+            frames: self.tx.subscribe(),
+            filter: ClientFilter::default(),
+            format,
+        }
+    }
+
+    pub fn clients(&self) -> Vec<ClientInfo> {
+        let mut clients: Vec<_> = self.clients.lock().unwrap().values().cloned().collect();
+        clients.sort_by_key(|c| c.id);
+        clients
+    }
+
+    pub fn summary(&self) -> serde_json::Value {
+        let clients = self.clients();
+        let mut by_kind: BTreeMap<ClientKind, usize> = BTreeMap::new();
+        for client in &clients {
+            *by_kind.entry(client.kind).or_default() += 1;
+        }
+        serde_json::json!({
+            "count": clients.len(),
+            "by_kind": by_kind,
+            "clients": clients,
+        })
+    }
+}
+
+pub struct WsClient {
+    hub: Arc<WsHub>,
+    id: u64,
+    pub frames: broadcast::Receiver<WsMessage>,
+    pub filter: ClientFilter,
+    pub format: WsFormat,
+}
+
+impl WsClient {
+    /// Applies a `subscribe` message and lists the channels in `/clients`.
+    pub fn subscribe(&mut self, subscription: Subscription) {
+        if let Some(info) = self.hub.clients.lock().unwrap().get_mut(&self.id) {
+            info.channels = subscription.channels.clone();
+        }
+        self.filter.subscribe(subscription);
+    }
+}
+
+impl Drop for WsClient {
+    fn drop(&mut self) {
+        self.hub.clients.lock().unwrap().remove(&self.id);
+        if self.format == WsFormat::Msgpack {
+            self.hub.msgpack_clients.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// The `frames` message, serialized straight from the frame.
+#[derive(Serialize)]
+struct FramePayload<'a> {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    schema_version: u32,
+    ts: u64,
+    /// The frame stopped advancing; see `metrics.stale_after_secs`.
+    stale: bool,
+    health: f32,
+    health_trend: HealthTrend,
+    obs: &'a ObsFrame,
+    system: &'a SystemFrame,
+    network: &'a NetworkFrame,
+    outputs: &'a [StreamOutput],
+    stream_stats: &'a StreamStats,
+    relay: &'a Option<RelayFrame>,
+    audio: &'a AudioFrame,
+}
+
+impl<'a> FramePayload<'a> {
+    fn new(frame: &'a TelemetryFrame) -> Self {
+        Self {
+            kind: "frame",
+            schema_version: frame.schema_version,
+            ts: frame.timestamp_unix,
+            stale: crate::metrics::frames_stale(),
+            health: frame.health,
+            health_trend: frame.health_trend,
+            obs: &frame.obs,
+            system: &frame.system,
+            network: &frame.network,
+            outputs: &frame.streams,
+            stream_stats: &frame.stream_stats,
+            relay: &frame.relay,
+            audio: &frame.audio,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_client_kind_from_query_then_user_agent() {
+        let obs_ua = "Mozilla/5.0 (Windows NT 10.0) Chrome/103.0 Safari/537.36 OBS/30.1.2";
+        assert_eq!(
+            ClientKind::detect(Some("dock"), Some(obs_ua)),
+            ClientKind::Dock
+        );
+        assert_eq!(ClientKind::detect(None, Some(obs_ua)), ClientKind::Overlay);
+        assert_eq!(
+            ClientKind::detect(None, Some("Mozilla/5.0 Firefox/128.0")),
+            ClientKind::Other
+        );
+    }
+
+    #[tokio::test]
+    async fn fans_out_one_payload_and_tracks_clients() {
+        let (tx, rx) = watch::channel(TelemetryFrame::default());
+        let hub = WsHub::spawn(
+            rx,
+            crate::ipc::new_debug_status(),
+            Default::default(),
+            Duration::from_millis(10),
+        );
+        let addr: SocketAddr = "127.0.0.1:50000".parse().unwrap();
+        let mut dashboard = hub.join(addr, ClientKind::Dashboard, None, WsFormat::Json);
+        let mut overlay = hub.join(addr, ClientKind::Overlay, None, WsFormat::Msgpack);
+        assert_eq!(hub.summary()["by_kind"]["overlay"], 1);
+
+        tx.send(TelemetryFrame {
+            timestamp_unix: 42,
+            ..Default::default()
+        })
+        .unwrap();
+        let a = loop {
+            let message = dashboard.frames.recv().await.unwrap();
+            if message.payload.contains("\"ts\":42") {
+                break message.payload;
+            }
+        };
+        let (b, packed) = loop {
+            let message = overlay.frames.recv().await.unwrap();
+            if message.payload.contains("\"ts\":42") {
+                break (message.payload, message.msgpack);
+            }
+        };
+        assert!(Arc::ptr_eq(&a, &b));
+        let packed: serde_json::Value = rmp_serde::from_slice(&packed.unwrap()).unwrap();
+        assert_eq!(packed["ts"], 42);
+        assert_eq!(packed["type"], "frame");
+
+        dashboard.subscribe(Subscription {
+            channels: vec![Channel::Aegis],
+            max_rate_hz: None,
+        });
+        assert_eq!(hub.clients()[0].channels, vec![Channel::Aegis]);
+        drop(overlay);
+        assert_eq!(hub.clients().len(), 1);
+        assert_eq!(hub.msgpack_clients.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn msgpack_is_opt_in() {
+        assert_eq!(WsFormat::detect(None, None), WsFormat::Json);
+        assert_eq!(WsFormat::detect(Some("msgpack"), None), WsFormat::Msgpack);
+        assert_eq!(
+            WsFormat::detect(None, Some("telemy.json, telemy.msgpack")),
+            WsFormat::Msgpack
+        );
+
+        let request = serde_json::json!({ "type": "subscribe", "channels": ["events"] });
+        let packed = rmp_serde::to_vec_named(&request).unwrap();
+        assert_eq!(
+            Subscription::parse_msgpack(&packed).unwrap().channels,
+            vec![Channel::Events]
+        );
+    }
+
+    #[test]
+    fn parses_subscribe_messages() {
+        let sub = Subscription::parse(
+            r#"{"type":"subscribe","channels":["events","frames","events"],"max_rate_hz":100}"#,
+        )
+        .unwrap();
+        assert_eq!(sub.channels, vec![Channel::Frames, Channel::Events]);
+        assert_eq!(sub.max_rate_hz, Some(MAX_RATE_HZ));
+
+        assert!(Subscription::parse(r#"{"type":"subscribe","channels":["chat"]}"#).is_err());
+        assert!(Subscription::parse(r#"{"type":"ping"}"#).is_err());
+        assert!(
+            Subscription::parse(r#"{"type":"subscribe","channels":[],"max_rate_hz":0}"#).is_err()
+        );
+    }
+
+    #[test]
+    fn filter_drops_unsubscribed_and_rate_limits_updates() {
+        let mut filter = ClientFilter::default();
+        let now = Instant::now();
+        assert!(filter.admit(Channel::Frames, now));
+        assert!(
+            !filter.admit(Channel::Aegis, now),
+            "not subscribed by default"
+        );
+
+        filter.subscribe(Subscription {
+            channels: vec![Channel::Frames, Channel::Events],
+            max_rate_hz: Some(2.0),
+        });
+        assert!(filter.admit(Channel::Frames, now));
+        assert!(!filter.admit(Channel::Frames, now + Duration::from_millis(200)));
+        assert!(filter.admit(Channel::Frames, now + Duration::from_millis(500)));
+        assert!(filter.admit(Channel::Events, now));
+        assert!(
+            filter.admit(Channel::Events, now),
+            "events are never held back"
+        );
+    }
+}
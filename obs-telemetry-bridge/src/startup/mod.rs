@@ -1,4 +1,7 @@
 #[cfg(windows)]
+use crate::config::AutostartMethod;
+use crate::config::StartupConfig;
+#[cfg(windows)]
 use std::os::windows::ffi::OsStrExt;
 #[cfg(windows)]
 use windows::core::PCWSTR;
@@ -7,11 +10,53 @@ use windows::Win32::System::Registry::{
     RegDeleteValueW, RegOpenKeyExW, RegSetValueExW, HKEY_CURRENT_USER, KEY_SET_VALUE, REG_SZ,
 };
 
+/// Registers or removes the login entry for `config.method`. Enabling one
+/// method removes the other's entry so Telemy never starts twice.
 #[cfg(windows)]
-pub fn set_autostart(app_name: &str, enable: bool) -> Result<(), Box<dyn std::error::Error>> {
+pub fn set_autostart(
+    config: &StartupConfig,
+    enable: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
     let exe = std::env::current_exe()?;
     let exe = exe.to_string_lossy().to_string();
 
+    if !enable {
+        set_run_key(&config.app_name, &exe, false)?;
+        return delete_logon_task(&config.app_name);
+    }
+    match config.method {
+        AutostartMethod::Registry => {
+            if is_elevated() {
+                tracing::warn!(
+                    "Telemy is running elevated but the Run key starts it unelevated; set startup.method = \"task_scheduler\" with highest_privileges = true"
+                );
+            }
+            delete_logon_task(&config.app_name)?;
+            set_run_key(&config.app_name, &exe, true)
+        }
+        AutostartMethod::TaskScheduler => {
+            if config.highest_privileges && !is_elevated() {
+                return Err(
+                    "a highest-privileges logon task can only be created from an elevated prompt"
+                        .into(),
+                );
+            }
+            create_logon_task(&config.app_name, &exe, config.highest_privileges)?;
+            set_run_key(&config.app_name, &exe, false)
+        }
+    }
+}
+
+#[cfg(not(windows))]
+pub fn set_autostart(
+    _config: &StartupConfig,
+    _enable: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Err("autostart is only supported on Windows".into())
+}
+
+#[cfg(windows)]
+fn set_run_key(app_name: &str, exe: &str, enable: bool) -> Result<(), Box<dyn std::error::Error>> {
     let key_path = to_wide("Software\\Microsoft\\Windows\\CurrentVersion\\Run");
     let mut hkey = HKEY_CURRENT_USER;
 
@@ -25,7 +70,7 @@ pub fn set_autostart(app_name: &str, enable: bool) -> Result<(), Box<dyn std::er
         )?;
 
         if enable {
-            let value = to_wide(&exe);
+            let value = to_wide(exe);
             let bytes = std::slice::from_raw_parts(value.as_ptr() as *const u8, value.len() * 2);
             RegSetValueExW(
                 hkey,
@@ -42,9 +87,98 @@ pub fn set_autostart(app_name: &str, enable: bool) -> Result<(), Box<dyn std::er
     Ok(())
 }
 
-#[cfg(not(windows))]
-pub fn set_autostart(_app_name: &str, _enable: bool) -> Result<(), Box<dyn std::error::Error>> {
-    Err("autostart is only supported on Windows".into())
+/// `schtasks` arguments for a logon task that starts `exe` for the current user.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn create_task_args(app_name: &str, exe: &str, highest_privileges: bool) -> Vec<String> {
+    let level = if highest_privileges {
+        "HIGHEST"
+    } else {
+        "LIMITED"
+    };
+    [
+        "/Create",
+        "/TN",
+        app_name,
+        "/TR",
+        &format!("\"{exe}\""),
+        "/SC",
+        "ONLOGON",
+        "/RL",
+        level,
+        "/F",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .collect()
+}
+
+#[cfg(windows)]
+fn schtasks(args: &[String]) -> Result<std::process::Output, Box<dyn std::error::Error>> {
+    Ok(std::process::Command::new("schtasks").args(args).output()?)
+}
+
+#[cfg(windows)]
+fn create_logon_task(
+    app_name: &str,
+    exe: &str,
+    highest_privileges: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let output = schtasks(&create_task_args(app_name, exe, highest_privileges))?;
+    if !output.status.success() {
+        return Err(format!(
+            "schtasks /Create failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Removing a task that does not exist is not an error.
+#[cfg(windows)]
+fn delete_logon_task(app_name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let query = schtasks(&["/Query".into(), "/TN".into(), app_name.into()])?;
+    if !query.status.success() {
+        return Ok(());
+    }
+    let output = schtasks(&["/Delete".into(), "/TN".into(), app_name.into(), "/F".into()])?;
+    if !output.status.success() {
+        return Err(format!(
+            "schtasks /Delete failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Whether this process runs with an elevated (administrator) token.
+#[cfg(windows)]
+pub fn is_elevated() -> bool {
+    use windows::Win32::Foundation::{CloseHandle, HANDLE};
+    use windows::Win32::Security::{
+        GetTokenInformation, TokenElevation, TOKEN_ELEVATION, TOKEN_QUERY,
+    };
+    use windows::Win32::System::Threading::{GetCurrentProcess, OpenProcessToken};
+
+    unsafe {
+        let mut token = HANDLE::default();
+        if OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token).is_err() {
+            return false;
+        }
+        let mut elevation = TOKEN_ELEVATION::default();
+        let mut len = 0u32;
+        let ok = GetTokenInformation(
+            token,
+            TokenElevation,
+            Some(&mut elevation as *mut TOKEN_ELEVATION as *mut std::ffi::c_void),
+            std::mem::size_of::<TOKEN_ELEVATION>() as u32,
+            &mut len,
+        )
+        .is_ok();
+        let _ = CloseHandle(token);
+        ok && elevation.TokenIsElevated != 0
+    }
 }
 
 #[cfg(windows)]
@@ -57,6 +191,7 @@ fn to_wide(s: &str) -> Vec<u16> {
 
 #[cfg(test)]
 mod tests {
+    use super::create_task_args;
     #[cfg(windows)]
     use super::to_wide;
 
@@ -75,4 +210,14 @@ mod tests {
         assert_eq!(v[0], 'A' as u16);
         assert_eq!(v[1], 0);
     }
+
+    #[test]
+    fn logon_task_quotes_the_exe_and_sets_the_run_level() {
+        let args = create_task_args("Telemy", r"C:\Program Files\Telemy\telemy.exe", true);
+        assert_eq!(
+            args.join(" "),
+            r#"/Create /TN Telemy /TR "C:\Program Files\Telemy\telemy.exe" /SC ONLOGON /RL HIGHEST /F"#
+        );
+        assert!(create_task_args("Telemy", "t.exe", false).contains(&"LIMITED".to_string()));
+    }
 }
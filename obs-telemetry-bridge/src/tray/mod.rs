@@ -1,4 +1,13 @@
 #[cfg(windows)]
+use crate::activity::ActionKind;
+use crate::aegis::RelaySessionHandle;
+use crate::config::ConfigHandle;
+use crate::ipc::OverrideStoreHandle;
+use crate::metrics::ObsLink;
+use crate::model::TelemetryFrame;
+use crate::notify::NoticeKind;
+use crate::security::VaultHandle;
+#[cfg(windows)]
 use std::io::Write;
 #[cfg(windows)]
 use std::process::{Command, Stdio};
@@ -6,33 +15,211 @@ use std::sync::atomic::AtomicBool;
 #[cfg(windows)]
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use tokio::runtime::Handle;
+use tokio::sync::watch;
 #[cfg(windows)]
 use tray_item::{IconSource, TrayItem};
 
+/// Everything the tray shows or acts on. Menu callbacks run on the tray's
+/// own thread, so async actions are spawned onto `runtime`.
+#[cfg_attr(not(windows), allow(dead_code))]
+pub struct TrayContext {
+    pub dashboard_url: String,
+    pub settings_url: String,
+    pub shutdown_flag: Arc<AtomicBool>,
+    pub shutdown_tx: watch::Sender<bool>,
+    pub telemetry: watch::Receiver<TelemetryFrame>,
+    pub config: ConfigHandle,
+    pub obs: ObsLink,
+    pub vault: VaultHandle,
+    pub aegis_session_snapshot: RelaySessionHandle,
+    pub ipc_overrides: OverrideStoreHandle,
+    pub runtime: Handle,
+}
+
+/// What the tray icon shows. Thresholds match the dashboard health badge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(windows), allow(dead_code))]
+enum TrayState {
+    Healthy,
+    Degraded,
+    Critical,
+    Disconnected,
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+impl TrayState {
+    fn from_frame(frame: &TelemetryFrame) -> Self {
+        if !frame.obs.connected {
+            Self::Disconnected
+        } else if frame.health >= 0.95 {
+            Self::Healthy
+        } else if frame.health >= 0.90 {
+            Self::Degraded
+        } else {
+            Self::Critical
+        }
+    }
+
+    /// Icon resource embedded by `build.rs`.
+    fn resource(self) -> &'static str {
+        match self {
+            Self::Healthy => "tray_healthy",
+            Self::Degraded => "tray_degraded",
+            Self::Critical => "tray_critical",
+            Self::Disconnected => "tray_disconnected",
+        }
+    }
+
+    fn tooltip(self, frame: &TelemetryFrame) -> String {
+        match self {
+            Self::Disconnected => "Telemy - OBS disconnected".to_string(),
+            _ => format!("Telemy - health {:.1}%", frame.health * 100.0),
+        }
+    }
+}
+
+/// The disabled first menu line, refreshed with every frame.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn status_line(frame: &TelemetryFrame, paused: bool) -> String {
+    if paused {
+        return "Telemetry paused".to_string();
+    }
+    if !frame.obs.connected {
+        return "OBS disconnected".to_string();
+    }
+    let (bitrate, drop_pct) =
+        crate::rules::main_output(frame).map_or((0, 0.0), |o| (o.bitrate_kbps, o.drop_pct));
+    format!(
+        "{bitrate} kbps | health {:.1}% | {:.2}% dropped",
+        frame.health * 100.0,
+        drop_pct * 100.0
+    )
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+fn pause_label(paused: bool) -> &'static str {
+    if paused {
+        "Resume telemetry"
+    } else {
+        "Pause telemetry"
+    }
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+fn relay_label(live: bool) -> &'static str {
+    if live {
+        "Stop Aegis relay"
+    } else {
+        "Start Aegis relay"
+    }
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+fn relay_live(snapshot: &RelaySessionHandle) -> bool {
+    snapshot
+        .blocking_read()
+        .as_ref()
+        .is_some_and(|s| matches!(s.status.as_str(), "provisioning" | "active" | "grace"))
+}
+
+/// Starts a relay, or stops the live one, like the dashboard's Aegis buttons.
+#[cfg_attr(not(windows), allow(dead_code))]
+async fn toggle_relay(ctx: &TrayContext) -> Result<String, String> {
+    let config = ctx.config.get();
+    let live = ctx
+        .aegis_session_snapshot
+        .read()
+        .await
+        .as_ref()
+        .filter(|s| matches!(s.status.as_str(), "provisioning" | "active" | "grace"))
+        .map(|s| s.session_id.clone());
+    match live {
+        Some(session_id) => {
+            crate::app::relay_stop(&config, &ctx.vault, session_id, "tray", "user_requested")
+                .await?;
+            *ctx.aegis_session_snapshot.write().await = None;
+            Ok("Relay stopped".to_string())
+        }
+        None => {
+            let obs_connected = ctx.telemetry.borrow().obs.connected;
+            let session = crate::app::relay_start(
+                &config,
+                &ctx.vault,
+                "tray",
+                ctx.ipc_overrides.get().mode,
+                obs_connected,
+            )
+            .await?;
+            let detail = format!("Relay {}", session.status);
+            *ctx.aegis_session_snapshot.write().await = Some(session);
+            Ok(detail)
+        }
+    }
+}
+
+/// Runs a menu action in the background and reports the outcome as a
+/// notification, since the menu has closed by the time it finishes.
 #[cfg(windows)]
-pub fn start_tray(
-    dashboard_url: String,
-    settings_url: String,
-    shutdown_flag: Arc<AtomicBool>,
-    shutdown_tx: tokio::sync::watch::Sender<bool>,
-) -> Result<(), Box<dyn std::error::Error>> {
+fn spawn_action<F>(ctx: &Arc<TrayContext>, action: fn(Arc<TrayContext>) -> F)
+where
+    F: std::future::Future<Output = Result<String, String>> + Send + 'static,
+{
+    let task = action(ctx.clone());
+    ctx.runtime.spawn(async move {
+        let message = match task.await {
+            Ok(message) => message,
+            Err(err) => {
+                tracing::warn!(error = %err, "tray action failed");
+                format!("Failed: {err}")
+            }
+        };
+        if let Err(err) = crate::notify::show("Telemy", &message) {
+            tracing::debug!(error = %err, "tray action result not shown");
+        }
+    });
+}
+
+/// Menu label for one "Notify on" toggle; the tray has no checkbox items.
+#[cfg_attr(not(windows), allow(dead_code))]
+fn notify_label(kind: NoticeKind, enabled: bool) -> String {
+    let mark = if enabled { "[x]" } else { "[ ]" };
+    format!("{mark} Notify on {}", kind.label())
+}
+
+#[cfg_attr(not(windows), allow(dead_code))]
+fn override_label(enabled: bool) -> String {
+    let mark = if enabled { "[x]" } else { "[ ]" };
+    format!("{mark} Manual override")
+}
+
+#[cfg(windows)]
+pub fn start_tray(ctx: TrayContext) -> Result<(), Box<dyn std::error::Error>> {
+    let ctx = Arc::new(ctx);
+    let config = &ctx.config;
     let mut tray = TrayItem::new("Telemy", IconSource::Resource("tray_default"))?;
 
-    let open_url = dashboard_url.clone();
+    let status_id = tray.inner_mut().add_label_with_id(&status_line(
+        &ctx.telemetry.borrow(),
+        crate::pause::is_paused(),
+    ))?;
+    tray.inner_mut().add_separator()?;
+
+    let open_url = ctx.dashboard_url.clone();
     tray.add_menu_item("Open Dashboard", move || {
         let _ = Command::new("cmd")
             .args(["/C", "start", "", &open_url])
             .spawn();
     })?;
 
-    let settings = settings_url.clone();
+    let settings = ctx.settings_url.clone();
     tray.add_menu_item("Settings", move || {
         let _ = Command::new("cmd")
             .args(["/C", "start", "", &settings])
             .spawn();
     })?;
 
-    let copy_url = dashboard_url.clone();
+    let copy_url = ctx.dashboard_url.clone();
     tray.add_menu_item("Copy Dashboard URL", move || {
         if let Ok(mut child) = Command::new("clip").stdin(Stdio::piped()).spawn() {
             if let Some(mut stdin) = child.stdin.take() {
@@ -41,17 +228,133 @@ pub fn start_tray(
         }
     })?;
 
-    let quit_flag = shutdown_flag.clone();
-    let quit_tx = shutdown_tx.clone();
+    tray.inner_mut().add_separator()?;
+    let relay = if config.read(|c| c.aegis.enabled) {
+        let live = relay_live(&ctx.aegis_session_snapshot);
+        let c = ctx.clone();
+        let id = tray
+            .inner_mut()
+            .add_menu_item_with_id(relay_label(live), move || {
+                spawn_action(&c, |ctx| async move { toggle_relay(&ctx).await })
+            })?;
+        Some((id, live))
+    } else {
+        None
+    };
+    let brb_scene = config.read(|c| c.tray.brb_scene.clone());
+    let c = ctx.clone();
+    tray.add_menu_item(&format!("Switch to {brb_scene}"), move || {
+        spawn_action(&c, |ctx| async move {
+            let scene = ctx.config.read(|c| c.tray.brb_scene.clone());
+            let result = ctx.obs.switch_scene(&scene).await;
+            crate::activity::log().record(
+                ActionKind::SwitchScene,
+                "tray",
+                None,
+                result.clone().map(|()| format!("switched to '{scene}'")),
+            );
+            result?;
+            Ok(format!("Switched to '{scene}'"))
+        })
+    })?;
+    let mut manual = ctx.ipc_overrides.manual_override();
+    let overrides = ctx.ipc_overrides.clone();
+    let manual_id = tray
+        .inner_mut()
+        .add_menu_item_with_id(&override_label(manual), move || {
+            let enabled = !overrides.manual_override();
+            let _ = overrides.update(|o| o.apply_setting_if_changed("manual_override", enabled));
+            tracing::info!(enabled, "manual override set from the tray");
+        })?;
+    let mut paused = crate::pause::is_paused();
+    let pause_id = tray
+        .inner_mut()
+        .add_menu_item_with_id(pause_label(paused), || {
+            crate::pause::set_paused(!crate::pause::is_paused(), "tray");
+        })?;
+
+    tray.inner_mut().add_separator()?;
+    let mut toggles = Vec::new();
+    for kind in NoticeKind::ALL {
+        let enabled = config.read(|c| kind.enabled(&c.notifications));
+        let handle = config.clone();
+        let id =
+            tray.inner_mut()
+                .add_menu_item_with_id(&notify_label(kind, enabled), move || {
+                    let mut updated = handle.get();
+                    kind.toggle(&mut updated.notifications);
+                    if let Err(err) = handle.save(updated) {
+                        tracing::warn!(error = %err, "failed to save notification setting");
+                    }
+                })?;
+        toggles.push((kind, id, enabled));
+    }
+    tray.inner_mut().add_separator()?;
+
+    let quit_flag = ctx.shutdown_flag.clone();
+    let quit_tx = ctx.shutdown_tx.clone();
     tray.add_menu_item("Quit", move || {
         quit_flag.store(true, Ordering::SeqCst);
         let _ = quit_tx.send(true);
     })?;
 
+    let mut shown = None;
+    let mut tooltip = String::new();
+    let mut status = String::new();
+    let mut relay = relay;
     loop {
-        if shutdown_flag.load(Ordering::SeqCst) {
+        if ctx.shutdown_flag.load(Ordering::SeqCst) {
             break;
         }
+        let frame = ctx.telemetry.borrow().clone();
+        let state = TrayState::from_frame(&frame);
+        if shown != Some(state) {
+            match tray.set_icon(IconSource::Resource(state.resource())) {
+                Ok(()) => shown = Some(state),
+                Err(err) => tracing::warn!(error = %err, "tray icon update failed"),
+            }
+        }
+        let text = state.tooltip(&frame);
+        if text != tooltip {
+            let _ = tray.inner_mut().set_tooltip(&text);
+            tooltip = text;
+        }
+        let now_paused = crate::pause::is_paused();
+        if now_paused != paused {
+            let _ = tray
+                .inner_mut()
+                .set_menu_item_label(pause_label(now_paused), pause_id);
+            paused = now_paused;
+        }
+        let now_manual = ctx.ipc_overrides.manual_override();
+        if now_manual != manual {
+            let _ = tray
+                .inner_mut()
+                .set_menu_item_label(&override_label(now_manual), manual_id);
+            manual = now_manual;
+        }
+        let line = status_line(&frame, paused);
+        if line != status {
+            let _ = tray.inner_mut().set_label(&line, status_id);
+            status = line;
+        }
+        if let Some((id, shown_live)) = &mut relay {
+            let live = relay_live(&ctx.aegis_session_snapshot);
+            if live != *shown_live {
+                let _ = tray.inner_mut().set_menu_item_label(relay_label(live), *id);
+                *shown_live = live;
+            }
+        }
+        // Labels follow the config, so edits from the file or CLI show up too.
+        for (kind, id, shown) in &mut toggles {
+            let enabled = config.read(|c| kind.enabled(&c.notifications));
+            if enabled != *shown {
+                let _ = tray
+                    .inner_mut()
+                    .set_menu_item_label(&notify_label(*kind, enabled), *id);
+                *shown = enabled;
+            }
+        }
         std::thread::sleep(std::time::Duration::from_millis(500));
     }
 
@@ -59,11 +362,61 @@ pub fn start_tray(
 }
 
 #[cfg(not(windows))]
-pub fn start_tray(
-    _dashboard_url: String,
-    _settings_url: String,
-    _shutdown_flag: Arc<AtomicBool>,
-    _shutdown_tx: tokio::sync::watch::Sender<bool>,
-) -> Result<(), Box<dyn std::error::Error>> {
+pub fn start_tray(_ctx: TrayContext) -> Result<(), Box<dyn std::error::Error>> {
     Err("tray is only supported on Windows".into())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icon_follows_health_and_obs_connection() {
+        let mut frame = TelemetryFrame {
+            health: 1.0,
+            ..TelemetryFrame::default()
+        };
+        assert_eq!(TrayState::from_frame(&frame), TrayState::Disconnected);
+        frame.obs.connected = true;
+        assert_eq!(TrayState::from_frame(&frame), TrayState::Healthy);
+        frame.health = 0.92;
+        assert_eq!(TrayState::from_frame(&frame), TrayState::Degraded);
+        frame.health = 0.5;
+        assert_eq!(TrayState::from_frame(&frame), TrayState::Critical);
+        assert_eq!(TrayState::Critical.tooltip(&frame), "Telemy - health 50.0%");
+    }
+
+    #[test]
+    fn status_line_shows_main_stream_numbers() {
+        let mut frame = TelemetryFrame {
+            health: 0.975,
+            ..TelemetryFrame::default()
+        };
+        assert_eq!(status_line(&frame, false), "OBS disconnected");
+        assert_eq!(status_line(&frame, true), "Telemetry paused");
+        frame.obs.connected = true;
+        frame.streams = vec![crate::model::StreamOutput {
+            name: "adv_stream".to_string(),
+            bitrate_kbps: 6000,
+            drop_pct: 0.0125,
+            ..Default::default()
+        }];
+        assert_eq!(
+            status_line(&frame, false),
+            "6000 kbps | health 97.5% | 1.25% dropped"
+        );
+    }
+
+    #[test]
+    fn notify_toggles_show_their_state() {
+        assert_eq!(
+            notify_label(NoticeKind::LowDisk, true),
+            "[x] Notify on low disk"
+        );
+        assert_eq!(
+            notify_label(NoticeKind::Disconnect, false),
+            "[ ] Notify on disconnect"
+        );
+        assert_eq!(override_label(true), "[x] Manual override");
+    }
+}
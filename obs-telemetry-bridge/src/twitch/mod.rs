@@ -0,0 +1,344 @@
+use crate::config::TwitchConfig;
+use crate::model::TelemetryFrame;
+use crate::security::SecretString;
+use futures_util::{SinkExt, StreamExt};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio_tungstenite::tungstenite::Message;
+
+type AnyError = Box<dyn std::error::Error + Send + Sync>;
+
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+/// Twitch rejects a bad token with this notice instead of closing the socket.
+const LOGIN_FAILED: &str = "Login authentication failed";
+
+/// A chat command the bot answers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Command {
+    Bitrate,
+    Health,
+}
+
+impl Command {
+    fn parse(text: &str) -> Option<Self> {
+        match text
+            .split_whitespace()
+            .next()?
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "!bitrate" => Some(Self::Bitrate),
+            "!health" => Some(Self::Health),
+            _ => None,
+        }
+    }
+
+    fn reply(self, frame: &TelemetryFrame) -> String {
+        if !frame.obs.streaming {
+            return "Not live right now.".to_string();
+        }
+        let main = crate::rules::main_output(frame);
+        let drop_pct = main.map_or(0.0, |o| o.drop_pct * 100.0);
+        match self {
+            Self::Bitrate => {
+                let mut reply = format!(
+                    "Bitrate: {} kbps ({drop_pct:.1}% dropped)",
+                    main.map_or(0, |o| o.bitrate_kbps)
+                );
+                if let Some(relay) = frame.relay.as_ref().filter(|r| r.connected) {
+                    reply.push_str(&format!(
+                        " | relay {} kbps over {} links",
+                        relay.bitrate_kbps,
+                        relay.links.len()
+                    ));
+                }
+                reply
+            }
+            Self::Health => format!(
+                "Health {:.0}% | CPU {:.0}% | drops {drop_pct:.1}% | upload {:.1} Mbps | latency {:.0} ms",
+                frame.health * 100.0,
+                frame.system.cpu_percent,
+                frame.network.upload_mbps,
+                frame.network.latency_ms,
+            ),
+        }
+    }
+}
+
+/// The IRC lines the bot acts on.
+#[derive(Debug, PartialEq, Eq)]
+enum Line<'a> {
+    Ping(&'a str),
+    Privmsg { user: &'a str, text: &'a str },
+    LoginFailed,
+    Other,
+}
+
+fn parse_line(line: &str) -> Line<'_> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    if let Some(payload) = line.strip_prefix("PING ") {
+        return Line::Ping(payload);
+    }
+    let Some((prefix, rest)) = line.strip_prefix(':').and_then(|l| l.split_once(' ')) else {
+        return Line::Other;
+    };
+    if let Some(params) = rest.strip_prefix("PRIVMSG ") {
+        let Some((_channel, text)) = params.split_once(" :") else {
+            return Line::Other;
+        };
+        let user = prefix.split('!').next().unwrap_or(prefix);
+        return Line::Privmsg { user, text };
+    }
+    if rest.starts_with("NOTICE ") && rest.ends_with(LOGIN_FAILED) {
+        return Line::LoginFailed;
+    }
+    Line::Other
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Announcement {
+    Brb,
+    Recovered,
+}
+
+/// Follows the program scene while live and reports switches into and out of
+/// the BRB scene. Going live on, or ending the stream from, BRB is not news.
+#[derive(Debug, Default)]
+struct BrbWatcher {
+    in_brb: Option<bool>,
+}
+
+impl BrbWatcher {
+    fn observe(&mut self, frame: &TelemetryFrame, brb_scene: &str) -> Option<Announcement> {
+        if !frame.obs.streaming {
+            self.in_brb = None;
+            return None;
+        }
+        let now = frame.obs.current_scene.as_deref() == Some(brb_scene);
+        match (self.in_brb.replace(now), now) {
+            (Some(false), true) => Some(Announcement::Brb),
+            (Some(true), false) => Some(Announcement::Recovered),
+            _ => None,
+        }
+    }
+}
+
+struct Bot {
+    config: TwitchConfig,
+    brb_scene: String,
+    oauth_token: SecretString,
+    channel: String,
+    last_reply: HashMap<Command, Instant>,
+    brb: BrbWatcher,
+}
+
+/// Connects the chat bot to `config.channel` and keeps it connected, answering
+/// `!bitrate`/`!health` from the latest frame and announcing switches to and
+/// from `brb_scene` (the `[tray]` BRB scene the failover switches to).
+pub fn spawn(
+    config: TwitchConfig,
+    brb_scene: String,
+    oauth_token: SecretString,
+    rx: watch::Receiver<TelemetryFrame>,
+) {
+    let channel = config.channel.trim_start_matches('#').to_ascii_lowercase();
+    let mut bot = Bot {
+        config,
+        brb_scene,
+        oauth_token,
+        channel,
+        last_reply: HashMap::new(),
+        brb: BrbWatcher::default(),
+    };
+    tokio::spawn(async move {
+        let mut rx = rx;
+        let mut backoff = Duration::from_secs(1);
+        loop {
+            let started = Instant::now();
+            match bot.run(&mut rx).await {
+                Ok(()) => return,
+                Err(err) => {
+                    tracing::warn!(channel = %bot.channel, error = %err, "twitch chat disconnected")
+                }
+            }
+            if started.elapsed() > MAX_BACKOFF {
+                backoff = Duration::from_secs(1);
+            }
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+        }
+    });
+}
+
+impl Bot {
+    /// One chat session; returns `Ok` only once the telemetry channel closes.
+    async fn run(&mut self, rx: &mut watch::Receiver<TelemetryFrame>) -> Result<(), AnyError> {
+        let (mut socket, _) = tokio_tungstenite::connect_async(self.config.server.as_str()).await?;
+        let token = self.oauth_token.expose().trim();
+        let pass = if token.starts_with("oauth:") {
+            token.to_string()
+        } else {
+            format!("oauth:{token}")
+        };
+        for line in [
+            format!("PASS {pass}"),
+            format!("NICK {}", self.config.username.to_ascii_lowercase()),
+            format!("JOIN #{}", self.channel),
+        ] {
+            socket.send(Message::text(line)).await?;
+        }
+        tracing::info!(channel = %self.channel, "twitch chat connected");
+
+        loop {
+            let mut outgoing = Vec::new();
+            tokio::select! {
+                changed = rx.changed() => {
+                    if changed.is_err() {
+                        return Ok(());
+                    }
+                    let frame = rx.borrow_and_update().clone();
+                    if let Some(text) = self.announcement(&frame) {
+                        outgoing.push(self.privmsg(&text));
+                    }
+                }
+                msg = socket.next() => match msg {
+                    Some(Ok(Message::Text(text))) => {
+                        for line in text.lines() {
+                            match parse_line(line) {
+                                Line::Ping(payload) => outgoing.push(format!("PONG {payload}")),
+                                Line::Privmsg { user, text } => {
+                                    if let Some(reply) = self.answer(text, &rx.borrow()) {
+                                        tracing::info!(user, command = text, "twitch chat command");
+                                        outgoing.push(self.privmsg(&reply));
+                                    }
+                                }
+                                Line::LoginFailed => {
+                                    return Err("login failed; check twitch.username and the OAuth token".into())
+                                }
+                                Line::Other => {}
+                            }
+                        }
+                    }
+                    Some(Ok(Message::Close(_))) | None => return Err("chat socket closed".into()),
+                    Some(Ok(_)) => {}
+                    Some(Err(err)) => return Err(err.into()),
+                },
+            }
+            for line in outgoing {
+                socket.send(Message::text(line)).await?;
+            }
+        }
+    }
+
+    fn privmsg(&self, text: &str) -> String {
+        format!(
+            "PRIVMSG #{} :{}",
+            self.channel,
+            text.replace(['\r', '\n'], " ")
+        )
+    }
+
+    /// The reply to a chat line, at most once per command every
+    /// `command_cooldown_secs` so chat cannot make the bot flood.
+    fn answer(&mut self, text: &str, frame: &TelemetryFrame) -> Option<String> {
+        if !self.config.commands {
+            return None;
+        }
+        let command = Command::parse(text)?;
+        let now = Instant::now();
+        let cooldown = Duration::from_secs(self.config.command_cooldown_secs);
+        if self
+            .last_reply
+            .get(&command)
+            .is_some_and(|at| now.duration_since(*at) < cooldown)
+        {
+            return None;
+        }
+        self.last_reply.insert(command, now);
+        Some(command.reply(frame))
+    }
+
+    fn announcement(&mut self, frame: &TelemetryFrame) -> Option<String> {
+        let announcement = self.brb.observe(frame, &self.brb_scene)?;
+        if !self.config.announce_brb {
+            return None;
+        }
+        Some(match announcement {
+            Announcement::Brb => self.config.brb_message.clone(),
+            Announcement::Recovered => self.config.recovered_message.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::StreamOutput;
+
+    #[test]
+    fn parses_the_lines_the_bot_needs() {
+        assert_eq!(
+            parse_line("PING :tmi.twitch.tv\r\n"),
+            Line::Ping(":tmi.twitch.tv")
+        );
+        assert_eq!(
+            parse_line(":viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #streamer :!bitrate please"),
+            Line::Privmsg {
+                user: "viewer",
+                text: "!bitrate please"
+            }
+        );
+        assert_eq!(
+            parse_line(":tmi.twitch.tv NOTICE * :Login authentication failed"),
+            Line::LoginFailed
+        );
+        assert_eq!(
+            parse_line(":tmi.twitch.tv 001 bot :Welcome, GLHF!"),
+            Line::Other
+        );
+        assert_eq!(Command::parse("!HEALTH"), Some(Command::Health));
+        assert_eq!(Command::parse("bitrate"), None);
+    }
+
+    #[test]
+    fn replies_use_the_main_output() {
+        let mut frame = TelemetryFrame::default();
+        assert_eq!(Command::Bitrate.reply(&frame), "Not live right now.");
+        frame.obs.streaming = true;
+        frame.health = 0.97;
+        frame.streams.push(StreamOutput {
+            name: "adv_stream".to_string(),
+            bitrate_kbps: 6000,
+            drop_pct: 0.012,
+            ..Default::default()
+        });
+        assert_eq!(
+            Command::Bitrate.reply(&frame),
+            "Bitrate: 6000 kbps (1.2% dropped)"
+        );
+        assert!(Command::Health
+            .reply(&frame)
+            .starts_with("Health 97% | CPU 0%"));
+    }
+
+    #[test]
+    fn announces_brb_switches_while_live() {
+        let mut watcher = BrbWatcher::default();
+        let mut frame = TelemetryFrame::default();
+        frame.obs.current_scene = Some("BRB".to_string());
+        assert_eq!(watcher.observe(&frame, "BRB"), None, "not live");
+        frame.obs.streaming = true;
+        assert_eq!(watcher.observe(&frame, "BRB"), None, "went live on BRB");
+        frame.obs.current_scene = Some("Main".to_string());
+        assert_eq!(
+            watcher.observe(&frame, "BRB"),
+            Some(Announcement::Recovered)
+        );
+        frame.obs.current_scene = Some("BRB".to_string());
+        assert_eq!(watcher.observe(&frame, "BRB"), Some(Announcement::Brb));
+        assert_eq!(watcher.observe(&frame, "BRB"), None);
+        frame.obs.streaming = false;
+        assert_eq!(watcher.observe(&frame, "BRB"), None);
+    }
+}